@@ -151,7 +151,7 @@ pub enum Commands {
     /// Mark an issue as superseded by another.
     Supersede(SupersedeArgs),
 
-    /// Show where an issue lives (stub).
+    /// Show which beads database is used and why.
     #[command(name = "where")]
     WhereCmd(WhereCmdArgs),
 
@@ -201,8 +201,8 @@ pub enum Commands {
     /// Manage configuration.
     Config(ConfigArgs),
 
-    /// Sync (deprecated: use 'bd dolt push' and 'bd dolt pull' instead).
-    Sync,
+    /// Sync issues with the git remote via the JSONL export.
+    Sync(SyncArgs),
 
     /// Print version information.
     Version,
@@ -230,6 +230,10 @@ pub enum Commands {
     /// Export issues to external formats.
     Export(ExportArgs),
 
+    /// Git merge driver for .beads/issues.jsonl (invoked by git).
+    #[command(name = "merge-driver")]
+    MergeDriver(MergeDriverArgs),
+
     /// Jira integration.
     Jira(JiraArgs),
 
@@ -255,7 +259,7 @@ pub enum Commands {
     Dolt(DoltArgs),
 
     /// Clean up temporary data and orphaned records.
-    Cleanup,
+    Cleanup(CleanupArgs),
 
     /// Compact the database (vacuum and optimize).
     Compact,
@@ -264,7 +268,7 @@ pub enum Commands {
     Reset,
 
     /// Run database migrations.
-    Migrate,
+    Migrate(MigrateArgs),
 
     /// Administrative operations.
     Admin(AdminArgs),
@@ -414,6 +418,17 @@ pub struct CreateArgs {
     #[arg(short = 'l', long = "label", num_args = 1..)]
     pub labels: Vec<String>,
 
+    /// Due time: a date or timestamp, a duration from now, or a phrase, with
+    /// an optional timezone (e.g. 2026-06-01, 2h, +3d, tomorrow 5pm,
+    /// end of month, friday 9am Europe/Berlin).
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub due: Option<String>,
+
+    /// Hide from ready work until this time, in the forms --due accepts
+    /// (e.g. 1w, next monday, 2026-06-01 9am PST).
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub defer: Option<String>,
+
     /// Explicit issue ID (e.g., 'bd-42' for partitioning).
     #[arg(long)]
     pub id: Option<String>,
@@ -505,6 +520,34 @@ pub struct ListArgs {
     /// Display issues in a tree format with status/priority symbols.
     #[arg(long)]
     pub tree: bool,
+
+    /// Only issues created at or after this time (e.g. 2026-01-01, -7d, last monday).
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub created_after: Option<String>,
+
+    /// Only issues created at or before this time.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub created_before: Option<String>,
+
+    /// Only issues updated at or after this time (e.g. "3 days ago").
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub updated_after: Option<String>,
+
+    /// Only issues updated at or before this time.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub updated_before: Option<String>,
+
+    /// Only issues closed at or after this time.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub closed_after: Option<String>,
+
+    /// Only issues closed at or before this time.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub closed_before: Option<String>,
+
+    /// Filter expression, e.g. `status:open AND (label:backend OR priority<=1)`.
+    #[arg(long, value_name = "EXPR")]
+    pub query: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -533,7 +576,8 @@ pub struct CloseArgs {
 /// Arguments for `bd ready`.
 #[derive(Args, Debug)]
 pub struct ReadyArgs {
-    /// Sort policy: priority (default), hybrid, oldest.
+    /// Sort policy: priority (default), hybrid (priority with aging, most
+    /// unblocking first), oldest.
     #[arg(short = 's', long, default_value = "priority")]
     pub sort: String,
 
@@ -560,6 +604,19 @@ pub struct ReadyArgs {
     /// Show only unassigned issues.
     #[arg(short = 'u', long)]
     pub unassigned: bool,
+
+    /// Show only descendants of this epic (at any depth).
+    #[arg(long, value_name = "ID")]
+    pub epic: Option<String>,
+
+    /// Filter expression, e.g. `status:open AND (label:backend OR priority<=1)`.
+    #[arg(long, value_name = "EXPR")]
+    pub query: Option<String>,
+
+    /// Close issues skipped because their conditional-blocks blocker
+    /// succeeded.
+    #[arg(long)]
+    pub close_skipped: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -569,7 +626,8 @@ pub struct ReadyArgs {
 /// Arguments for `bd search`.
 #[derive(Args, Debug)]
 pub struct SearchArgs {
-    /// Search query.
+    /// Search query: words, `prefix*`, `"phrases"`, `field:term` (id, title,
+    /// description, design, acceptance, notes, comments) and AND/OR/NOT.
     pub query: String,
 
     /// Filter by status.
@@ -591,6 +649,18 @@ pub struct SearchArgs {
     /// Limit results.
     #[arg(short = 'n', long, default_value = "50")]
     pub limit: i32,
+
+    /// Order by relevance and show each match's score.
+    #[arg(long)]
+    pub rank: bool,
+
+    /// Show an excerpt of each match with the matching terms highlighted.
+    #[arg(long)]
+    pub snippet: bool,
+
+    /// Filter expression the matches must also satisfy, e.g. `updated>7d`.
+    #[arg(long = "query", value_name = "EXPR")]
+    pub filter: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -627,6 +697,10 @@ pub struct InitArgs {
     /// Force re-initialization even if data already exists.
     #[arg(long)]
     pub force: bool,
+
+    /// Do not install the git merge driver for issues.jsonl.
+    #[arg(long)]
+    pub no_merge_driver: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -651,6 +725,8 @@ pub enum ConfigCommands {
     List,
     /// Unset a configuration value.
     Unset(ConfigUnsetArgs),
+    /// Show the effective config.yaml settings.
+    Show(ConfigShowArgs),
 }
 
 /// Arguments for `bd config set`.
@@ -676,6 +752,14 @@ pub struct ConfigUnsetArgs {
     pub key: String,
 }
 
+/// Arguments for `bd config show`.
+#[derive(Args, Debug)]
+pub struct ConfigShowArgs {
+    /// Show where each value came from.
+    #[arg(long)]
+    pub origin: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Dep
 // ---------------------------------------------------------------------------
@@ -807,6 +891,16 @@ pub struct UpdateArgs {
     /// Remove labels.
     #[arg(long = "remove-label", num_args = 1..)]
     pub remove_labels: Vec<String>,
+
+    /// Due time (e.g. 2026-06-01, 2h, +3d, tomorrow 5pm Europe/Berlin);
+    /// "none" clears it.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub due: Option<String>,
+
+    /// Hide from ready work until this time (e.g. 1w, next monday 9am PST);
+    /// "none" clears it.
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
+    pub defer: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -864,6 +958,10 @@ pub struct CountArgs {
     /// Group by status.
     #[arg(long)]
     pub by_status: bool,
+
+    /// Filter expression, e.g. `status:open AND (label:backend OR priority<=1)`.
+    #[arg(long, value_name = "EXPR")]
+    pub query: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -883,9 +981,9 @@ pub struct StatsArgs {
 /// Arguments for `bd stale`.
 #[derive(Args, Debug)]
 pub struct StaleArgs {
-    /// Number of days without updates to consider stale (default 30).
+    /// How long without updates counts as stale: days, or a duration like 2w.
     #[arg(short = 'd', long, default_value = "30")]
-    pub days: i32,
+    pub days: String,
 }
 
 // ---------------------------------------------------------------------------
@@ -1065,8 +1163,9 @@ pub struct DeferArgs {
     /// Issue ID to defer.
     pub id: String,
 
-    /// Defer until date (ISO 8601 date string, e.g. 2025-06-01).
-    #[arg(long)]
+    /// Defer until this time (e.g. 2026-06-01, 3d, tomorrow 9am, next monday,
+    /// friday 5pm Europe/Berlin).
+    #[arg(long, value_name = "TIME", allow_hyphen_values = true)]
     pub until: Option<String>,
 }
 
@@ -1114,8 +1213,8 @@ pub struct SupersedeArgs {
 /// Arguments for `bd where`.
 #[derive(Args, Debug)]
 pub struct WhereCmdArgs {
-    /// Issue ID to locate.
-    pub id: String,
+    /// Issue ID to locate in that database.
+    pub id: Option<String>,
 }
 
 // ---------------------------------------------------------------------------
@@ -1162,21 +1261,21 @@ pub enum MolCommands {
     Pour(MolPourArgs),
     /// Wisp operations within a molecule.
     Wisp(MolWispArgs),
-    /// Bond issues together in a molecule.
+    /// Bond a molecule onto another at a bond point.
     Bond(MolBondArgs),
-    /// Squash molecule contents.
+    /// Collapse a finished molecule into its root as a summary.
     Squash(MolSquashArgs),
-    /// Burn (remove) a molecule.
+    /// Delete a molecule and all of its steps.
     Burn(MolBurnArgs),
-    /// Distill a molecule.
+    /// Write a formula reproducing an epic or molecule.
     Distill(MolDistillArgs),
-    /// Seed a molecule.
+    /// Create a molecule root with variables, to pour later.
     Seed(MolSeedArgs),
-    /// Show stale molecules.
+    /// Show molecules with no step activity for a while.
     Stale(MolStaleArgs),
-    /// Show ready-gated molecules.
+    /// Show molecules waiting only on gates, and which gates.
     ReadyGated(MolReadyGatedArgs),
-    /// Show current molecule context.
+    /// Show the molecule being worked on and its next ready step.
     Current(MolCurrentArgs),
     /// Show molecule progress.
     Progress(MolProgressArgs),
@@ -1192,7 +1291,7 @@ pub struct MolShowArgs {
 /// Arguments for `bd mol pour`.
 #[derive(Args, Debug)]
 pub struct MolPourArgs {
-    /// Formula name or file path.
+    /// Formula name or file path, or the ID of a seeded molecule.
     pub id: Option<String>,
 
     /// Variable substitution (key=value), repeatable.
@@ -1222,56 +1321,105 @@ pub struct MolWispArgs {
 /// Arguments for `bd mol bond`.
 #[derive(Args, Debug)]
 pub struct MolBondArgs {
-    /// Molecule identifier.
-    pub id: Option<String>,
+    /// Molecule that receives the bond.
+    pub id: String,
+
+    /// Molecule bonded onto it.
+    pub other: String,
+
+    /// Bond type: sequential, parallel or conditional.
+    #[arg(short = 't', long = "type", default_value = "sequential")]
+    pub bond_type: String,
+
+    /// Step of the first molecule to attach to (default: its last steps).
+    #[arg(long = "at")]
+    pub at: Option<String>,
 }
 
 /// Arguments for `bd mol squash`.
 #[derive(Args, Debug)]
 pub struct MolSquashArgs {
-    /// Molecule identifier.
-    pub id: Option<String>,
+    /// Molecule root ID.
+    pub id: String,
+
+    /// Squash even if some steps are still open.
+    #[arg(long)]
+    pub force: bool,
 }
 
 /// Arguments for `bd mol burn`.
 #[derive(Args, Debug)]
 pub struct MolBurnArgs {
-    /// Molecule identifier.
-    pub id: Option<String>,
+    /// Molecule root ID.
+    pub id: String,
+
+    /// Confirm deletion.
+    #[arg(long)]
+    pub force: bool,
+
+    /// Show what would be deleted.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 /// Arguments for `bd mol distill`.
 #[derive(Args, Debug)]
 pub struct MolDistillArgs {
-    /// Molecule identifier.
-    pub id: Option<String>,
+    /// Epic or molecule root ID.
+    pub id: String,
+
+    /// Formula name (default: derived from the title).
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Replace a value with a variable (name=value), repeatable.
+    #[arg(long = "var", num_args = 1..)]
+    pub vars: Vec<String>,
+
+    /// Write the formula to this file instead of stdout.
+    #[arg(short = 'o', long)]
+    pub output: Option<String>,
 }
 
 /// Arguments for `bd mol seed`.
 #[derive(Args, Debug)]
 pub struct MolSeedArgs {
-    /// Molecule identifier.
-    pub id: Option<String>,
+    /// Formula name or file path.
+    pub formula: String,
+
+    /// Variable values (key=value), repeatable; more can be given on pour.
+    #[arg(long = "var", num_args = 1..)]
+    pub vars: Vec<String>,
+
+    /// Root title (default: the formula name).
+    #[arg(long)]
+    pub title: Option<String>,
 }
 
 /// Arguments for `bd mol stale`.
 #[derive(Args, Debug)]
 pub struct MolStaleArgs {
-    /// Number of days to consider stale.
+    /// How long without step activity counts as stale: days, or a duration
+    /// like 2w.
     #[arg(short = 'd', long, default_value = "30")]
-    pub days: i32,
+    pub days: String,
 }
 
 /// Arguments for `bd mol ready-gated`.
 #[derive(Args, Debug)]
 pub struct MolReadyGatedArgs {
-    /// Molecule identifier.
+    /// Molecule identifier (default: every open molecule).
     pub id: Option<String>,
 }
 
 /// Arguments for `bd mol current`.
 #[derive(Args, Debug)]
-pub struct MolCurrentArgs {}
+pub struct MolCurrentArgs {
+    /// Agent bead whose hooked issue names the molecule (default: the
+    /// molecule of the actor's in-progress steps).
+    #[arg(long)]
+    pub agent: Option<String>,
+}
 
 /// Arguments for `bd mol progress`.
 #[derive(Args, Debug)]
@@ -1316,6 +1464,22 @@ pub struct FormulaShowArgs {
 pub struct FormulaCreateArgs {
     /// Formula name.
     pub name: String,
+
+    /// Build the steps from an existing epic's children.
+    #[arg(long = "from-epic", value_name = "ID")]
+    pub from_epic: Option<String>,
+
+    /// Formula description.
+    #[arg(long, short = 'd')]
+    pub description: Option<String>,
+
+    /// Write to ~/.beads/formulas/ instead of .beads/formulas/.
+    #[arg(long)]
+    pub global: bool,
+
+    /// Overwrite an existing formula file.
+    #[arg(long)]
+    pub force: bool,
 }
 
 /// Arguments for `bd formula delete`.
@@ -1323,6 +1487,10 @@ pub struct FormulaCreateArgs {
 pub struct FormulaDeleteArgs {
     /// Formula name or identifier.
     pub name: String,
+
+    /// Confirm deletion.
+    #[arg(long)]
+    pub force: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -1345,14 +1513,43 @@ pub struct CookArgs {
 }
 
 // ---------------------------------------------------------------------------
-// Wisp (Phase 4 stub)
+// Wisp
 // ---------------------------------------------------------------------------
 
 /// Arguments for `bd wisp`.
 #[derive(Args, Debug)]
 pub struct WispArgs {
-    /// Wisp expression or identifier.
-    pub expr: Option<String>,
+    #[command(subcommand)]
+    pub command: WispCommands,
+}
+
+/// Wisp subcommands.
+#[derive(Subcommand, Debug)]
+pub enum WispCommands {
+    /// Pour a formula as ephemeral steps that expire after their TTL.
+    Run(WispRunArgs),
+    /// List wisps with their type and expiry.
+    List,
+}
+
+/// Arguments for `bd wisp run`.
+#[derive(Args, Debug)]
+pub struct WispRunArgs {
+    /// Formula name or file path.
+    pub formula: String,
+
+    /// Variable substitution (key=value), repeatable.
+    #[arg(long = "var", num_args = 1..)]
+    pub vars: Vec<String>,
+
+    /// Wisp type, which selects the TTL (heartbeat, ping, patrol, gc_report,
+    /// recovery, error, escalation).
+    #[arg(short = 't', long = "type")]
+    pub wisp_type: Option<String>,
+
+    /// Preview cooked steps without creating issues.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 // ---------------------------------------------------------------------------
@@ -1430,29 +1627,117 @@ pub struct TemplateInstantiateArgs {
 }
 
 // ---------------------------------------------------------------------------
-// Import (Phase 5 stub)
+// Sync (Phase 5 -- git-backed JSONL sync)
+// ---------------------------------------------------------------------------
+
+/// Arguments for `bd sync`.
+#[derive(Args, Debug)]
+pub struct SyncArgs {
+    /// Only export the database to JSONL (no git operations).
+    #[arg(long, conflicts_with_all = ["import_only", "status"])]
+    pub flush_only: bool,
+
+    /// Only import the JSONL into the database (no git operations).
+    #[arg(long, conflicts_with = "status")]
+    pub import_only: bool,
+
+    /// Show sync state without changing anything.
+    #[arg(long)]
+    pub status: bool,
+
+    /// Commit message for the JSONL commit.
+    #[arg(short = 'm', long)]
+    pub message: Option<String>,
+
+    /// Do not pull and merge remote changes.
+    #[arg(long)]
+    pub no_pull: bool,
+
+    /// Do not push after syncing.
+    #[arg(long)]
+    pub no_push: bool,
+}
+
+// ---------------------------------------------------------------------------
+// Merge driver (Phase 5)
+// ---------------------------------------------------------------------------
+
+/// Arguments for `bd migrate`.
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    /// Show the schema version and applied/pending migrations.
+    #[arg(long, conflicts_with_all = ["dry_run", "to"])]
+    pub status: bool,
+
+    /// List the migrations that would run without applying them.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Migrate up to this schema version instead of the latest.
+    #[arg(long, value_name = "VERSION")]
+    pub to: Option<i32>,
+}
+
+/// Arguments for `bd merge-driver` (git passes `%O %A %B`).
+#[derive(Args, Debug)]
+pub struct MergeDriverArgs {
+    /// Common ancestor version (%O).
+    pub base: String,
+
+    /// Current branch version (%A); receives the merge result.
+    pub ours: String,
+
+    /// Other branch version (%B).
+    pub theirs: String,
+}
+
+// ---------------------------------------------------------------------------
+// Import (Phase 5 -- real implementation)
 // ---------------------------------------------------------------------------
 
 /// Arguments for `bd import`.
 #[derive(Args, Debug)]
 pub struct ImportArgs {
-    /// Source file or URL to import from.
+    /// JSONL file to import (defaults to .beads/issues.jsonl).
     pub source: Option<String>,
 
-    /// Import format (json, csv, markdown).
+    /// Import format (only json/jsonl is supported).
     #[arg(short = 'f', long, default_value = "json")]
     pub format: String,
+
+    /// Report what would change without writing to the database.
+    #[arg(long)]
+    pub dry_run: bool,
 }
 
 // ---------------------------------------------------------------------------
-// Export (Phase 5 stub)
+// Export (Phase 5 -- JSONL implemented, Obsidian stub)
 // ---------------------------------------------------------------------------
 
 /// Arguments for `bd export`.
+///
+/// Without a subcommand, writes JSONL (default: `.beads/issues.jsonl`).
 #[derive(Args, Debug)]
 pub struct ExportArgs {
     #[command(subcommand)]
     pub command: Option<ExportCommands>,
+
+    /// Output file ("-" for stdout). Defaults to .beads/issues.jsonl, or
+    /// stdout when a filter is given.
+    #[arg(short = 'o', long)]
+    pub output: Option<String>,
+
+    /// Only export issues with this status.
+    #[arg(short = 's', long)]
+    pub status: Option<String>,
+
+    /// Only export issues with this label (repeatable, AND semantics).
+    #[arg(short = 'l', long = "label")]
+    pub labels: Vec<String>,
+
+    /// Include ephemeral issues (wisps), which are excluded by default.
+    #[arg(long)]
+    pub include_ephemeral: bool,
 }
 
 /// Export subcommands.
@@ -1595,6 +1880,26 @@ pub enum DoctorCommands {
     Artifacts,
 }
 
+// ---------------------------------------------------------------------------
+// Cleanup
+// ---------------------------------------------------------------------------
+
+/// Arguments for `bd cleanup`.
+#[derive(Args, Debug)]
+pub struct CleanupArgs {
+    /// Delete wisps idle for longer than their TTL.
+    #[arg(long)]
+    pub wisps: bool,
+
+    /// List what would be deleted without deleting it.
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Record what was deleted in a gc_report wisp.
+    #[arg(long)]
+    pub report: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Dolt (Phase 6 stub)
 // ---------------------------------------------------------------------------
@@ -1708,8 +2013,12 @@ pub enum AgentCommands {
     State(AgentStateArgs),
     /// Set agent to "running" and assign a hook bead.
     Run(AgentRunArgs),
-    /// Route work to an agent.
+    /// Route ready work to idle agents by attested skills.
     Route(AgentRouteArgs),
+    /// Record that an agent is alive.
+    Heartbeat(AgentHeartbeatArgs),
+    /// Mark silent agents dead and reclaim their hooked work.
+    Reap(AgentReapArgs),
 }
 
 /// Arguments for `bd agent show`.
@@ -1740,10 +2049,28 @@ pub struct AgentRunArgs {
 /// Arguments for `bd agent route`.
 #[derive(Args, Debug)]
 pub struct AgentRouteArgs {
+    /// Route work to this agent only (default: every idle agent).
+    pub name: Option<String>,
+    /// Show the assignment plan without assigning anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// Arguments for `bd agent heartbeat`.
+#[derive(Args, Debug)]
+pub struct AgentHeartbeatArgs {
     /// Agent name or identifier.
     pub name: String,
 }
 
+/// Arguments for `bd agent reap`.
+#[derive(Args, Debug)]
+pub struct AgentReapArgs {
+    /// Show which agents would be reaped without changing anything.
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
 // ---------------------------------------------------------------------------
 // Swarm (Phase 7)
 // ---------------------------------------------------------------------------
@@ -1933,8 +2260,12 @@ pub enum GateCommands {
     Create(GateCreateArgs),
     /// Close (satisfy) a gate manually.
     Close(GateCloseArgs),
-    /// Check all open gates and auto-close resolved ones.
-    Check,
+    /// Check all open gates once: close resolved ones, notify their waiters
+    /// and escalate overdue ones.
+    Check(GateCheckArgs),
+    /// Keep checking open gates, backing off per await type while none
+    /// resolve.
+    Watch(GateWatchArgs),
 }
 
 /// Arguments for `bd gate show`.
@@ -1950,15 +2281,18 @@ pub struct GateCreateArgs {
     /// Gate title.
     pub title: String,
 
-    /// Await type (human|timer|gh:run|gh:pr).
+    /// Await type: human, timer, file, cmd, http, issue, gh:run, gh:pr,
+    /// all, any or a configured evaluator; may carry its argument, as in
+    /// `file:dist/app`.
     #[arg(long)]
     pub await_type: Option<String>,
 
-    /// Await identifier (run ID, PR number, or duration like "30m").
+    /// Await argument (run ID, PR number, path, command, URL, issue ID or
+    /// JSON list of specs).
     #[arg(long)]
     pub await_id: Option<String>,
 
-    /// Timeout duration string (e.g. "30m", "2h", "1d"), stored as nanoseconds.
+    /// Timeout duration (e.g. "30m", "1h30m", "2w"); a bare number is seconds.
     #[arg(long)]
     pub timeout: Option<String>,
 
@@ -1967,6 +2301,35 @@ pub struct GateCreateArgs {
     pub waiters: Vec<String>,
 }
 
+/// Arguments for `bd gate check`.
+#[derive(Args, Debug)]
+pub struct GateCheckArgs {
+    /// Also mail waiters through the mail delegate.
+    #[arg(long)]
+    pub mail: bool,
+}
+
+/// Arguments for `bd gate watch`.
+#[derive(Args, Debug)]
+pub struct GateWatchArgs {
+    /// Poll interval per await type, doubled after each poll that resolves
+    /// nothing (e.g. "30s", "5m").
+    #[arg(long, default_value = "30s")]
+    pub interval: String,
+
+    /// Longest poll interval backoff reaches.
+    #[arg(long, default_value = "10m")]
+    pub max_interval: String,
+
+    /// Also mail waiters through the mail delegate.
+    #[arg(long)]
+    pub mail: bool,
+
+    /// Exit once no open gates remain.
+    #[arg(long)]
+    pub exit_when_idle: bool,
+}
+
 /// Arguments for `bd gate close`.
 #[derive(Args, Debug)]
 pub struct GateCloseArgs {
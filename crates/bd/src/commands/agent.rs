@@ -1,15 +1,43 @@
 //! `bd agent` -- AI/automation agent state tracking.
 //!
 //! Agents are issues labeled `gt:agent` that self-report their state.
-//! This module implements list, show, state, and run subcommands.
+//! This module implements list, show, state, run, route, heartbeat, and
+//! reap subcommands.
+//!
+//! `route` hands ready issues to idle agents. An issue requires the skills
+//! named by its `skill:<name>[:<level>]` labels and by the `skills` key of
+//! its metadata (a list of names, or an object of name to minimum level).
+//! An agent has the skills attested for it: `attests` dependencies from an
+//! attester onto the agent, carrying [`AttestsMeta`].
+//!
+//! `reap` marks agents dead once they have been silent (no heartbeat, state
+//! change or run) for longer than the liveness timeout of their role type
+//! (`agent.liveness` in the config), returns their hooked issue to open and
+//! files a `recovery` wisp about each.
+
+use std::cell::Cell;
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration, Utc};
 
-use anyhow::{Context, Result, bail};
-use chrono::Utc;
+use beads_config::config::AgentConfig;
+use beads_core::dependency::{AttestsMeta, Dependency};
+use beads_core::enums::{AgentState, DependencyType, IssueType, Status, WispType, WorkType};
+use beads_core::filter::WorkFilter;
+use beads_core::issue::Issue;
+use beads_storage::{IssueUpdates, Storage, StorageError};
+use beads_timeparsing::{Unit, format_duration, format_duration_approx, parse_duration_in};
 
 use crate::cli::{AgentArgs, AgentCommands};
+use crate::commands::create::{generate_issue_id, issue_prefix};
+use crate::commands::mol::MOLECULE_LABEL;
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
+/// Label that marks an issue as an agent bead.
+const AGENT_LABEL: &str = "gt:agent";
+
 /// Valid agent states.
 const VALID_STATES: &[&str] = &[
     "idle", "spawning", "running", "working", "stuck", "done", "stopped", "dead",
@@ -22,10 +50,9 @@ pub fn run(ctx: &RuntimeContext, args: &AgentArgs) -> Result<()> {
         AgentCommands::Show(a) => cmd_show(ctx, &a.name),
         AgentCommands::State(a) => cmd_state(ctx, &a.name, &a.new_state),
         AgentCommands::Run(a) => cmd_run(ctx, &a.name, &a.hook_bead),
-        AgentCommands::Route(a) => {
-            println!("bd agent route {}: not yet implemented", a.name);
-            Ok(())
-        }
+        AgentCommands::Route(a) => cmd_route(ctx, a.name.as_deref(), a.dry_run),
+        AgentCommands::Heartbeat(a) => cmd_heartbeat(ctx, &a.name),
+        AgentCommands::Reap(a) => cmd_reap(ctx, a.dry_run),
     }
 }
 
@@ -34,30 +61,11 @@ pub fn run(ctx: &RuntimeContext, args: &AgentArgs) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
-    let conn = open_db(ctx, false)?;
-
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.agent_state, i.role_type, i.rig, i.last_activity, i.hook_bead \
-         FROM issues i \
-         JOIN labels l ON i.id = l.issue_id \
-         WHERE l.label = 'gt:agent' \
-         ORDER BY i.updated_at DESC",
-    )?;
-
-    let agents: Vec<AgentRow> = stmt
-        .query_map([], |row| {
-            Ok(AgentRow {
-                id: row.get(0)?,
-                title: row.get::<_, String>(1).unwrap_or_default(),
-                agent_state: row.get::<_, String>(2).unwrap_or_default(),
-                role_type: row.get::<_, String>(3).unwrap_or_default(),
-                rig: row.get::<_, String>(4).unwrap_or_default(),
-                last_activity: row.get::<_, String>(5).unwrap_or_default(),
-                hook_bead: row.get::<_, String>(6).unwrap_or_default(),
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let store = ctx.store()?;
+
+    let mut issues = store.get_issues_by_label(AGENT_LABEL)?;
+    issues.sort_by_key(|i| std::cmp::Reverse(i.updated_at));
+    let agents: Vec<AgentRow> = issues.iter().map(AgentRow::from).collect();
 
     if ctx.json {
         let json_agents: Vec<serde_json::Value> = agents
@@ -114,13 +122,10 @@ fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_show(ctx: &RuntimeContext, agent_id: &str) -> Result<()> {
-    let conn = open_db(ctx, false)?;
+    let store = ctx.store()?;
 
-    // Load agent
-    let agent = load_agent(&conn, agent_id)?;
-
-    // Verify it has gt:agent label
-    verify_agent_label(&conn, &agent.id)?;
+    // Load agent and verify it has the gt:agent label
+    let agent = AgentFull::from(&load_agent(store, agent_id)?);
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -206,62 +211,49 @@ fn cmd_state(ctx: &RuntimeContext, agent_id: &str, new_state: &str) -> Result<()
         );
     }
 
-    let conn = open_db(ctx, true)?;
-    let now_str = Utc::now().to_rfc3339();
+    let store = ctx.store()?;
+    let now = Utc::now();
 
-    // Check if agent exists
-    let exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-            rusqlite::params![agent_id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
+    let exists = match store.get_issue(agent_id) {
+        Ok(_) => true,
+        Err(e) if e.is_not_found() => false,
+        Err(e) => return Err(e.into()),
+    };
 
     if !exists {
         // Auto-create the agent issue
-        conn.execute(
-            "INSERT INTO issues (id, title, status, priority, issue_type, agent_state, last_activity, created_at, created_by, updated_at) \
-             VALUES (?1, ?2, 'open', 2, 'task', ?3, ?4, ?5, ?6, ?7)",
-            rusqlite::params![
-                agent_id,
-                &format!("Agent: {}", agent_id),
-                &state,
-                &now_str,
-                &now_str,
-                &ctx.actor,
-                &now_str,
-            ],
-        )
-        .with_context(|| format!("failed to create agent {}", agent_id))?;
-
-        // Add gt:agent label
-        conn.execute(
-            "INSERT OR IGNORE INTO labels (issue_id, label, created_at, created_by) VALUES (?1, 'gt:agent', ?2, ?3)",
-            rusqlite::params![agent_id, &now_str, &ctx.actor],
-        )?;
-
-        // Record event
-        conn.execute(
-            "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) VALUES (?1, 'created', ?2, ?3, ?4)",
-            rusqlite::params![agent_id, &ctx.actor, &format!("Agent: {}", agent_id), &now_str],
-        )?;
+        let issue = Issue {
+            id: agent_id.to_string(),
+            title: format!("Agent: {}", agent_id),
+            status: Status::Open,
+            priority: 2,
+            issue_type: IssueType::Task,
+            agent_state: AgentState::from(state.as_str()),
+            last_activity: Some(now),
+            created_at: now,
+            created_by: ctx.actor.clone(),
+            updated_at: now,
+            ..Issue::default()
+        };
+        store
+            .run_in_transaction(&|tx| {
+                tx.create_issue(&issue, &ctx.actor)?;
+                tx.add_label(agent_id, AGENT_LABEL, &ctx.actor)
+            })
+            .with_context(|| format!("failed to create agent {}", agent_id))?;
     } else {
         // Verify it's an agent
-        verify_agent_label(&conn, agent_id)?;
+        verify_agent_label(store, agent_id)?;
 
-        // Update state
-        conn.execute(
-            "UPDATE issues SET agent_state = ?1, last_activity = ?2, updated_at = ?3 WHERE id = ?4",
-            rusqlite::params![&state, &now_str, &now_str, agent_id],
-        )?;
+        let updates = IssueUpdates {
+            agent_state: Some(state.clone()),
+            last_activity: Some(Some(now)),
+            ..Default::default()
+        };
+        store.update_issue(agent_id, &updates, &ctx.actor)?;
     }
 
-    // Record state change event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) VALUES (?1, 'agent_state', ?2, ?3, ?4)",
-        rusqlite::params![agent_id, &ctx.actor, &state, &now_str],
-    )?;
+    let now_str = now.to_rfc3339();
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -285,41 +277,22 @@ fn cmd_run(ctx: &RuntimeContext, agent_id: &str, hook_bead: &str) -> Result<()>
         bail!("cannot update agent in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
-    let now_str = Utc::now().to_rfc3339();
+    let store = ctx.store()?;
+    let now = Utc::now();
 
     // Verify agent exists and has gt:agent label
-    let exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-            rusqlite::params![agent_id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if !exists {
-        bail!("agent '{}' not found", agent_id);
-    }
-
-    verify_agent_label(&conn, agent_id)?;
+    load_agent(store, agent_id)?;
 
     // Update state to running and set hook_bead
-    conn.execute(
-        "UPDATE issues SET agent_state = 'running', hook_bead = ?1, last_activity = ?2, updated_at = ?3 WHERE id = ?4",
-        rusqlite::params![hook_bead, &now_str, &now_str, agent_id],
-    )?;
-
-    // Record event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, new_value, comment, created_at) \
-         VALUES (?1, 'agent_state', ?2, 'running', ?3, ?4)",
-        rusqlite::params![
-            agent_id,
-            &ctx.actor,
-            &format!("hook={}", hook_bead),
-            &now_str
-        ],
-    )?;
+    let updates = IssueUpdates {
+        agent_state: Some(AgentState::Running.as_str().to_string()),
+        hook_bead: Some(hook_bead.to_string()),
+        last_activity: Some(Some(now)),
+        ..Default::default()
+    };
+    store.update_issue(agent_id, &updates, &ctx.actor)?;
+
+    let now_str = now.to_rfc3339();
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -335,6 +308,628 @@ fn cmd_run(ctx: &RuntimeContext, agent_id: &str, hook_bead: &str) -> Result<()>
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Route
+// ---------------------------------------------------------------------------
+
+/// Label prefix naming a skill an issue requires: `skill:<name>[:<level>]`.
+const SKILL_LABEL_PREFIX: &str = "skill:";
+
+/// Proficiency levels, weakest first.
+const SKILL_LEVELS: &[&str] = &["beginner", "intermediate", "advanced", "expert"];
+
+fn cmd_route(ctx: &RuntimeContext, only: Option<&str>, dry_run: bool) -> Result<()> {
+    if ctx.readonly && !dry_run {
+        bail!("cannot route work in read-only mode");
+    }
+
+    let store = ctx.store()?;
+
+    // Idle agents, and why the others cannot take work
+    let mut agents = Vec::new();
+    let mut skipped = Vec::new();
+    let candidates = match only {
+        Some(id) => vec![load_agent(store, id)?],
+        None => store.get_issues_by_label(AGENT_LABEL)?,
+    };
+    for agent in candidates {
+        match route_blocker(store, &agent)? {
+            Some(reason) if only.is_some() => {
+                bail!("agent {} cannot take work: {}", agent.id, reason)
+            }
+            Some(reason) => skipped.push((agent.id, reason)),
+            None => agents.push(RouteAgent {
+                skills: attested_skills(store, &agent.id)?,
+                issue: agent,
+            }),
+        }
+    }
+    agents.sort_by(|a, b| a.issue.id.cmp(&b.issue.id));
+    skipped.sort();
+
+    // Ready issues, in ready order, minus the agent beads themselves and
+    // the containers of real work: epics and molecule roots. Poured steps
+    // are routable like any other issue.
+    let filter = WorkFilter {
+        include_mol_steps: true,
+        ..WorkFilter::default()
+    };
+    let ready = store.get_ready_work(&filter)?;
+    let ids: Vec<String> = ready.iter().map(|i| i.id.clone()).collect();
+    let labels = store.get_labels_for_issues(&ids)?;
+    let work: Vec<RouteWork> = ready
+        .into_iter()
+        .filter_map(|issue| {
+            let labels = labels.get(&issue.id).map(Vec::as_slice).unwrap_or(&[]);
+            if issue.issue_type == IssueType::Epic
+                || labels
+                    .iter()
+                    .any(|l| l == AGENT_LABEL || l == MOLECULE_LABEL)
+            {
+                return None;
+            }
+            Some(RouteWork {
+                skills: required_skills(&issue, labels),
+                issue,
+            })
+        })
+        .collect();
+
+    let plan = plan_routes(&agents, &work);
+    if !dry_run && !plan.assignments.is_empty() {
+        apply_routes(store, &plan, &ctx.actor)?;
+    }
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "dry_run": dry_run,
+            "assignments": plan.assignments.iter().map(RouteAssignment::to_json).collect::<Vec<_>>(),
+            "idle_agents": plan.idle.iter().map(|(agent, reason)| serde_json::json!({
+                "agent": agent,
+                "reason": reason,
+            })).collect::<Vec<_>>(),
+            "skipped_agents": skipped.iter().map(|(agent, reason)| serde_json::json!({
+                "agent": agent,
+                "reason": reason,
+            })).collect::<Vec<_>>(),
+            "unrouted": plan.unrouted.iter().map(|(issue, reason)| serde_json::json!({
+                "issue": issue,
+                "reason": reason,
+            })).collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+
+    if plan.assignments.is_empty() {
+        println!("No work routed.");
+    } else {
+        println!(
+            "{} {} issue(s):",
+            if dry_run { "Would route" } else { "Routed" },
+            plan.assignments.len()
+        );
+        for a in &plan.assignments {
+            println!("  {} -> {}  {}  ({})", a.agent, a.issue, a.title, a.reason);
+        }
+    }
+    for (agent, reason) in &plan.idle {
+        println!("Idle: {} ({})", agent, reason);
+    }
+    for (agent, reason) in &skipped {
+        println!("Skipped: {} ({})", agent, reason);
+    }
+    for (issue, reason) in &plan.unrouted {
+        println!("Unrouted: {} ({})", issue, reason);
+    }
+
+    Ok(())
+}
+
+/// Why `agent` cannot take work now: it is closed, not idle, or still
+/// hooked to an unfinished issue.
+fn route_blocker(store: &dyn Storage, agent: &Issue) -> Result<Option<String>> {
+    if agent.status == Status::Closed {
+        return Ok(Some("closed".to_string()));
+    }
+    if agent.agent_state != AgentState::Idle {
+        return Ok(Some(if agent.agent_state.is_default() {
+            "no state reported".to_string()
+        } else {
+            format!("state is {}", agent.agent_state.as_str())
+        }));
+    }
+    if agent.hook_bead.is_empty() {
+        return Ok(None);
+    }
+    match store.get_issue(&agent.hook_bead) {
+        Ok(hook) if hook.status != Status::Closed => {
+            Ok(Some(format!("still hooked to {}", hook.id)))
+        }
+        Ok(_) => Ok(None),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// The skills attested for `agent_id`, each at its best attested level.
+fn attested_skills(store: &dyn Storage, agent_id: &str) -> Result<BTreeMap<String, String>> {
+    let mut skills: BTreeMap<String, String> = BTreeMap::new();
+    for dependent in store.get_dependents_with_metadata(agent_id)? {
+        let dep = &dependent.dependency;
+        if dep.dep_type != DependencyType::Attests {
+            continue;
+        }
+        let Ok(meta) = serde_json::from_str::<AttestsMeta>(&dep.metadata) else {
+            continue;
+        };
+        let skill = meta.skill.trim().to_lowercase();
+        let level = meta.level.trim().to_lowercase();
+        if skill.is_empty() {
+            continue;
+        }
+        let best = skills.entry(skill).or_default();
+        if best.is_empty() || level_rank(&level) > level_rank(best) {
+            *best = level;
+        }
+    }
+    Ok(skills)
+}
+
+/// The skills `issue` requires, each with its minimum level (empty for any).
+fn required_skills(issue: &Issue, labels: &[String]) -> BTreeMap<String, String> {
+    let mut skills = BTreeMap::new();
+    for label in labels {
+        if let Some(spec) = label.strip_prefix(SKILL_LABEL_PREFIX) {
+            let (skill, level) = spec.split_once(':').unwrap_or((spec, ""));
+            require_skill(&mut skills, skill, level);
+        }
+    }
+    let metadata = issue
+        .metadata
+        .as_ref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m.get()).ok());
+    match metadata.as_ref().and_then(|m| m.get("skills")) {
+        Some(serde_json::Value::Array(names)) => {
+            for name in names.iter().filter_map(|n| n.as_str()) {
+                require_skill(&mut skills, name, "");
+            }
+        }
+        Some(serde_json::Value::Object(levels)) => {
+            for (name, level) in levels {
+                require_skill(&mut skills, name, level.as_str().unwrap_or(""));
+            }
+        }
+        _ => {}
+    }
+    skills
+}
+
+/// Records that `skill` is needed at `level` or better, keeping the
+/// stricter of two levels.
+fn require_skill(skills: &mut BTreeMap<String, String>, skill: &str, level: &str) {
+    let skill = skill.trim().to_lowercase();
+    let level = level.trim().to_lowercase();
+    if skill.is_empty() {
+        return;
+    }
+    let min = skills.entry(skill).or_default();
+    if !level.is_empty() && (min.is_empty() || level_rank(&level) > level_rank(min)) {
+        *min = level;
+    }
+}
+
+/// Rank of a proficiency level, from 1 (beginner, or unknown) to 4 (expert).
+fn level_rank(level: &str) -> usize {
+    SKILL_LEVELS
+        .iter()
+        .position(|l| *l == level)
+        .map_or(1, |i| i + 1)
+}
+
+/// How well `agent` fits `work`: the summed rank of its levels on the
+/// required skills, or `None` if it lacks one or is below its minimum.
+fn skill_fit(agent: &RouteAgent, work: &RouteWork) -> Option<usize> {
+    let mut score = 0;
+    for (skill, min) in &work.skills {
+        let level = agent.skills.get(skill)?;
+        if !min.is_empty() && level_rank(level) < level_rank(min) {
+            return None;
+        }
+        score += level_rank(level);
+    }
+    Some(score)
+}
+
+/// Plans which idle agent takes which ready issue.
+///
+/// Issues are taken in ready order. Each goes to the eligible free agent
+/// with the highest proficiency, preferring specialists (fewer attested
+/// skills) and then the agent idle the longest. Mutex issues that already
+/// have an assignee are left alone. Agents left over afterwards join the
+/// first open-competition issue they are eligible for, so that several
+/// agents may compete on one issue.
+fn plan_routes(agents: &[RouteAgent], work: &[RouteWork]) -> RoutePlan {
+    let mut plan = RoutePlan::default();
+    let mut free: Vec<&RouteAgent> = agents.iter().collect();
+    let mut competitions = Vec::new();
+
+    for w in work {
+        let competing = w.issue.work_type == WorkType::OpenCompetition;
+        if !competing && !w.issue.assignee.is_empty() {
+            plan.unrouted.push((
+                w.issue.id.clone(),
+                format!("already assigned to {}", w.issue.assignee),
+            ));
+            continue;
+        }
+        if competing {
+            competitions.push(w);
+        }
+        let eligible: Vec<(usize, usize)> = free
+            .iter()
+            .enumerate()
+            .filter_map(|(i, a)| skill_fit(a, w).map(|score| (i, score)))
+            .collect();
+        let best = eligible.iter().copied().min_by(|&(i, s), &(j, t)| {
+            t.cmp(&s)
+                .then_with(|| free[i].skills.len().cmp(&free[j].skills.len()))
+                .then_with(|| {
+                    free[i]
+                        .issue
+                        .last_activity
+                        .cmp(&free[j].issue.last_activity)
+                })
+                .then_with(|| free[i].issue.id.cmp(&free[j].issue.id))
+        });
+        match best {
+            Some((i, score)) => {
+                let agent = free.remove(i);
+                plan.assignments
+                    .push(RouteAssignment::new(agent, w, score, eligible.len()));
+            }
+            None => plan.unrouted.push((
+                w.issue.id.clone(),
+                if free.is_empty() {
+                    "no idle agent left".to_string()
+                } else {
+                    "no idle agent has the required skills".to_string()
+                },
+            )),
+        }
+    }
+
+    for agent in free {
+        let joined = competitions
+            .iter()
+            .find_map(|w| skill_fit(agent, w).map(|score| (w, score)));
+        match joined {
+            Some((w, score)) => plan
+                .assignments
+                .push(RouteAssignment::new(agent, w, score, 1)),
+            None => plan.idle.push((
+                agent.issue.id.clone(),
+                if work.is_empty() {
+                    "no ready work".to_string()
+                } else {
+                    "no ready issue matches its skills".to_string()
+                },
+            )),
+        }
+    }
+
+    plan
+}
+
+/// Carries out `plan` in one transaction: each mutex issue gets its agent
+/// as assignee and goes in progress, and each agent is hooked to its issue
+/// and set running. Fails without changing anything if an agent is no
+/// longer idle, an issue is no longer open, or a mutex issue was claimed
+/// in the meantime.
+fn apply_routes(store: &dyn Storage, plan: &RoutePlan, actor: &str) -> Result<()> {
+    let now = Utc::now();
+    store
+        .run_in_transaction(&|tx| {
+            for a in &plan.assignments {
+                let agent = tx.get_issue(&a.agent)?;
+                if agent.agent_state != AgentState::Idle {
+                    return Err(StorageError::validation(format!(
+                        "agent {} is no longer idle",
+                        a.agent
+                    )));
+                }
+                let issue = tx.get_issue(&a.issue)?;
+                if issue.status != Status::Open {
+                    return Err(StorageError::validation(format!(
+                        "issue {} is no longer open",
+                        a.issue
+                    )));
+                }
+                if !a.competing {
+                    if !issue.assignee.is_empty() {
+                        return Err(StorageError::AlreadyClaimed {
+                            assignee: issue.assignee,
+                        });
+                    }
+                    let claim = IssueUpdates {
+                        assignee: Some(a.agent.clone()),
+                        status: Some(Status::InProgress),
+                        ..Default::default()
+                    };
+                    tx.update_issue(&a.issue, &claim, actor)?;
+                }
+                let hook = IssueUpdates {
+                    agent_state: Some(AgentState::Running.as_str().to_string()),
+                    hook_bead: Some(a.issue.clone()),
+                    last_activity: Some(Some(now)),
+                    ..Default::default()
+                };
+                tx.update_issue(&a.agent, &hook, actor)?;
+            }
+            Ok(())
+        })
+        .context("failed to route work")
+}
+
+// ---------------------------------------------------------------------------
+// Heartbeat
+// ---------------------------------------------------------------------------
+
+fn cmd_heartbeat(ctx: &RuntimeContext, agent_id: &str) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot record a heartbeat in read-only mode");
+    }
+
+    let store = ctx.store()?;
+    let agent = load_agent(store, agent_id)?;
+    let now = Utc::now();
+    store.record_heartbeat(agent_id, now)?;
+
+    if agent.agent_state == AgentState::Dead && !ctx.quiet {
+        eprintln!(
+            "Warning: {} was reaped and its work reclaimed; set its state to resume",
+            agent_id
+        );
+    }
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "agent": agent_id,
+            "agent_state": non_empty(agent.agent_state.as_str()),
+            "last_activity": now.to_rfc3339(),
+        }));
+    } else if !ctx.quiet {
+        println!("{} heartbeat", agent_id);
+    }
+
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Reap
+// ---------------------------------------------------------------------------
+
+fn cmd_reap(ctx: &RuntimeContext, dry_run: bool) -> Result<()> {
+    if ctx.readonly && !dry_run {
+        bail!("cannot reap agents in read-only mode");
+    }
+
+    let store = ctx.store()?;
+    let config = ctx.config()?.agent.clone();
+    let now = Utc::now();
+
+    let mut silent = Vec::new();
+    for agent in store.get_issues_by_label(AGENT_LABEL)? {
+        if agent.status == Status::Closed
+            || matches!(
+                agent.agent_state,
+                AgentState::None | AgentState::Dead | AgentState::Stopped | AgentState::Done
+            )
+        {
+            continue;
+        }
+        let timeout = liveness_timeout(&config, &agent.role_type)?;
+        let last_seen = agent.last_activity.unwrap_or(agent.updated_at);
+        if now.signed_duration_since(last_seen) > timeout {
+            silent.push(SilentAgent {
+                agent,
+                last_seen,
+                timeout,
+            });
+        }
+    }
+    silent.sort_by(|a, b| a.agent.id.cmp(&b.agent.id));
+
+    let mut reaped = Vec::new();
+    for s in &silent {
+        let hooked = hooked_issue(store, &s.agent)?;
+        let wisp = if dry_run {
+            None
+        } else {
+            match reap(ctx, store, s, hooked.as_ref(), now)? {
+                Some(wisp) => Some(wisp),
+                // It showed signs of life while we looked
+                None => continue,
+            }
+        };
+        reaped.push((s, hooked.map(|i| i.id), wisp));
+    }
+
+    if ctx.json {
+        let rows: Vec<serde_json::Value> = reaped
+            .iter()
+            .map(|(s, reclaimed, wisp)| {
+                serde_json::json!({
+                    "agent": s.agent.id,
+                    "role_type": non_empty(&s.agent.role_type),
+                    "agent_state": s.agent.agent_state.as_str(),
+                    "last_activity": s.last_seen.to_rfc3339(),
+                    "silent_for": format_duration_approx(now.signed_duration_since(s.last_seen), 2),
+                    "timeout": format_duration(s.timeout),
+                    "reclaimed": reclaimed,
+                    "wisp": wisp,
+                })
+            })
+            .collect();
+        output_json(&serde_json::json!({
+            "dry_run": dry_run,
+            "reaped": rows,
+        }));
+    } else if reaped.is_empty() {
+        println!("No silent agents.");
+    } else {
+        println!(
+            "{} {} silent agent(s):",
+            if dry_run { "Would reap" } else { "Reaped" },
+            reaped.len()
+        );
+        for (s, reclaimed, wisp) in &reaped {
+            let mut line = format!(
+                "  {}: silent for {} (timeout {})",
+                s.agent.id,
+                format_duration_approx(now.signed_duration_since(s.last_seen), 2),
+                format_duration(s.timeout)
+            );
+            if let Some(issue) = reclaimed {
+                line.push_str(&format!("; reclaimed {}", issue));
+            }
+            if let Some(wisp) = wisp {
+                line.push_str(&format!("; recovery wisp {}", wisp));
+            }
+            println!("{}", line);
+        }
+    }
+
+    Ok(())
+}
+
+/// The liveness timeout of agents with `role_type`; a bare number is
+/// seconds.
+fn liveness_timeout(config: &AgentConfig, role_type: &str) -> Result<Duration> {
+    let timeout = config.timeout_for(role_type);
+    parse_duration_in(timeout, Unit::Second)
+        .map_err(|e| anyhow!("invalid liveness timeout for role '{}': {}", role_type, e))
+}
+
+/// The unfinished issue `agent` is hooked to, if any.
+fn hooked_issue(store: &dyn Storage, agent: &Issue) -> Result<Option<Issue>> {
+    if agent.hook_bead.is_empty() {
+        return Ok(None);
+    }
+    match store.get_issue(&agent.hook_bead) {
+        Ok(issue) if issue.status != Status::Closed => Ok(Some(issue)),
+        Ok(_) => Ok(None),
+        Err(e) if e.is_not_found() => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reaps one silent agent in a single transaction: marks it dead and clears
+/// its hook, returns the hooked issue to open (releasing the agent's claim)
+/// with a comment saying why, and files a `recovery` wisp related to both.
+/// Returns the wisp's ID, or `None` if the agent reported activity since it
+/// was found silent.
+fn reap(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    silent: &SilentAgent,
+    hooked: Option<&Issue>,
+    now: DateTime<Utc>,
+) -> Result<Option<String>> {
+    let agent = &silent.agent;
+    let role = if agent.role_type.is_empty() {
+        "no role"
+    } else {
+        agent.role_type.as_str()
+    };
+    let silent_for = format_duration_approx(now.signed_duration_since(silent.last_seen), 2);
+    let timeout = format_duration(silent.timeout);
+
+    let title = format!("Agent {} reaped after {} of silence", agent.id, silent_for);
+    let mut description = format!(
+        "Agent {} ({}) last reported activity at {}, longer ago than its {} \
+         liveness timeout, and was marked dead.",
+        agent.id,
+        role,
+        silent.last_seen.to_rfc3339(),
+        timeout
+    );
+    if let Some(issue) = hooked {
+        description.push_str(&format!(
+            "\n\nIts hooked issue {} ({}) was returned to open.",
+            issue.id, issue.title
+        ));
+    }
+    let prefix = issue_prefix(ctx, store);
+    let id = generate_issue_id(store, &prefix, &title, &description, &ctx.actor, now)?;
+
+    let wisp = Issue {
+        id: id.clone(),
+        title,
+        description,
+        status: Status::Open,
+        priority: 1,
+        issue_type: IssueType::Task,
+        ephemeral: true,
+        wisp_type: WispType::Recovery,
+        created_by: ctx.actor.clone(),
+        created_at: now,
+        updated_at: now,
+        ..Issue::default()
+    };
+    let related = |target: &str| Dependency {
+        issue_id: id.clone(),
+        depends_on_id: target.to_string(),
+        dep_type: DependencyType::Related,
+        created_at: now,
+        created_by: ctx.actor.clone(),
+        metadata: String::new(),
+        thread_id: String::new(),
+    };
+    let comment = format!(
+        "Reclaimed from agent {}, which was silent for {} (liveness timeout {}) \
+         and was marked dead. See {}.",
+        agent.id, silent_for, timeout, id
+    );
+    let dead = IssueUpdates {
+        agent_state: Some(AgentState::Dead.as_str().to_string()),
+        hook_bead: Some(String::new()),
+        ..Default::default()
+    };
+
+    let alive = Cell::new(false);
+    store
+        .run_in_transaction(&|tx| {
+            let current = tx.get_issue(&agent.id)?;
+            if current.last_activity != agent.last_activity
+                || current.agent_state != agent.agent_state
+            {
+                alive.set(true);
+                return Ok(());
+            }
+            if let Some(issue) = hooked {
+                let release = IssueUpdates {
+                    status: matches!(issue.status, Status::InProgress | Status::Hooked)
+                        .then_some(Status::Open),
+                    assignee: (issue.assignee == agent.id).then(String::new),
+                    ..Default::default()
+                };
+                tx.update_issue(&issue.id, &release, &ctx.actor)?;
+                tx.add_comment(&issue.id, &ctx.actor, &comment)?;
+            }
+            tx.update_issue(&agent.id, &dead, &ctx.actor)?;
+            tx.create_issue(&wisp, &ctx.actor)?;
+            tx.add_label(&id, "ephemeral", &ctx.actor)?;
+            tx.add_dependency(&related(&agent.id), &ctx.actor)?;
+            if let Some(issue) = hooked {
+                tx.add_dependency(&related(&issue.id), &ctx.actor)?;
+            }
+            Ok(())
+        })
+        .with_context(|| format!("failed to reap agent {}", agent.id))?;
+
+    Ok((!alive.get()).then_some(id))
+}
+
 // ---------------------------------------------------------------------------
 // Data types
 // ---------------------------------------------------------------------------
@@ -350,6 +945,20 @@ struct AgentRow {
     hook_bead: String,
 }
 
+impl From<&Issue> for AgentRow {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            agent_state: issue.agent_state.as_str().to_string(),
+            role_type: issue.role_type.clone(),
+            rig: issue.rig.clone(),
+            last_activity: activity_string(issue),
+            hook_bead: issue.hook_bead.clone(),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct AgentFull {
     id: String,
@@ -362,75 +971,161 @@ struct AgentFull {
     role_bead: String,
 }
 
-// ---------------------------------------------------------------------------
-// Helpers
-// ---------------------------------------------------------------------------
+impl From<&Issue> for AgentFull {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            agent_state: issue.agent_state.as_str().to_string(),
+            role_type: issue.role_type.clone(),
+            rig: issue.rig.clone(),
+            last_activity: activity_string(issue),
+            hook_bead: issue.hook_bead.clone(),
+            role_bead: issue.role_bead.clone(),
+        }
+    }
+}
 
-/// Open the beads database.
-fn open_db(ctx: &RuntimeContext, writable: bool) -> Result<rusqlite::Connection> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+/// An agent silent for longer than its liveness timeout.
+struct SilentAgent {
+    agent: Issue,
+    /// Its last activity, or its last update if it never reported any.
+    last_seen: DateTime<Utc>,
+    timeout: Duration,
+}
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
+/// An idle agent and its attested skills.
+struct RouteAgent {
+    issue: Issue,
+    skills: BTreeMap<String, String>,
+}
+
+/// A ready issue and the skills it requires.
+struct RouteWork {
+    issue: Issue,
+    skills: BTreeMap<String, String>,
+}
+
+/// One routing decision.
+#[derive(Debug)]
+struct RouteAssignment {
+    agent: String,
+    issue: String,
+    title: String,
+    priority: i32,
+    /// Whether the issue is open competition (no exclusive assignee).
+    competing: bool,
+    /// Required skills and their minimum levels.
+    required: BTreeMap<String, String>,
+    /// The agent's levels on the required skills.
+    matched: BTreeMap<String, String>,
+    score: usize,
+    /// How many free agents were eligible.
+    candidates: usize,
+    reason: String,
+}
+
+impl RouteAssignment {
+    fn new(agent: &RouteAgent, work: &RouteWork, score: usize, candidates: usize) -> Self {
+        let competing = work.issue.work_type == WorkType::OpenCompetition;
+        let matched: BTreeMap<String, String> = work
+            .skills
+            .keys()
+            .filter_map(|s| Some((s.clone(), agent.skills.get(s)?.clone())))
+            .collect();
+        let mut reason = if matched.is_empty() {
+            "no skills required".to_string()
+        } else {
+            let skills: Vec<String> = matched
+                .iter()
+                .map(|(skill, level)| match work.skills[skill].as_str() {
+                    "" => format!("{} {}", skill, level),
+                    min => format!("{} {} (needs {})", skill, level, min),
+                })
+                .collect();
+            format!("attests {}", skills.join(", "))
+        };
+        if competing {
+            reason.push_str("; open competition");
+        } else {
+            reason.push_str(&format!("; best of {} eligible", candidates));
+        }
+        Self {
+            agent: agent.issue.id.clone(),
+            issue: work.issue.id.clone(),
+            title: work.issue.title.clone(),
+            priority: work.issue.priority,
+            competing,
+            required: work.skills.clone(),
+            matched,
+            score,
+            candidates,
+            reason,
+        }
     }
 
-    if writable {
-        rusqlite::Connection::open(&db_path)
-            .with_context(|| format!("failed to open database: {}", db_path.display()))
-    } else {
-        rusqlite::Connection::open_with_flags(
-            &db_path,
-            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-        )
-        .with_context(|| format!("failed to open database: {}", db_path.display()))
-    }
-}
-
-/// Load full agent details by ID.
-fn load_agent(conn: &rusqlite::Connection, id: &str) -> Result<AgentFull> {
-    conn.query_row(
-        "SELECT id, title, agent_state, role_type, rig, last_activity, hook_bead, role_bead \
-         FROM issues WHERE id = ?1",
-        rusqlite::params![id],
-        |row| {
-            Ok(AgentFull {
-                id: row.get(0)?,
-                title: row.get::<_, String>(1).unwrap_or_default(),
-                agent_state: row.get::<_, String>(2).unwrap_or_default(),
-                role_type: row.get::<_, String>(3).unwrap_or_default(),
-                rig: row.get::<_, String>(4).unwrap_or_default(),
-                last_activity: row.get::<_, String>(5).unwrap_or_default(),
-                hook_bead: row.get::<_, String>(6).unwrap_or_default(),
-                role_bead: row.get::<_, String>(7).unwrap_or_default(),
-            })
-        },
-    )
-    .with_context(|| format!("agent '{}' not found", id))
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "agent": self.agent,
+            "issue": self.issue,
+            "title": self.title,
+            "priority": self.priority,
+            "work_type": if self.competing {
+                WorkType::OpenCompetition.as_str()
+            } else {
+                WorkType::Mutex.as_str()
+            },
+            "required_skills": self.required,
+            "agent_skills": self.matched,
+            "score": self.score,
+            "candidates": self.candidates,
+            "reason": self.reason,
+        })
+    }
+}
+
+/// The outcome of planning a route.
+#[derive(Debug, Default)]
+struct RoutePlan {
+    assignments: Vec<RouteAssignment>,
+    /// Idle agents left without work, with the reason.
+    idle: Vec<(String, String)>,
+    /// Ready issues left without an agent, with the reason.
+    unrouted: Vec<(String, String)>,
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// Load an agent issue by ID, verifying it carries the gt:agent label.
+pub(crate) fn load_agent(store: &dyn Storage, id: &str) -> Result<Issue> {
+    let issue = match store.get_issue(id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("agent '{}' not found", id),
+        Err(e) => return Err(e.into()),
+    };
+    verify_agent_label(store, id)?;
+    Ok(issue)
 }
 
 /// Verify that an issue has the gt:agent label.
-fn verify_agent_label(conn: &rusqlite::Connection, id: &str) -> Result<()> {
-    let has_label: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM labels WHERE issue_id = ?1 AND label = 'gt:agent')",
-            rusqlite::params![id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if !has_label {
+fn verify_agent_label(store: &dyn Storage, id: &str) -> Result<()> {
+    if !store.get_labels(id)?.iter().any(|l| l == AGENT_LABEL) {
         bail!("{} is not an agent bead (missing gt:agent label)", id);
     }
 
     Ok(())
 }
 
+/// Render an issue's last_activity as RFC 3339 (empty when unset).
+fn activity_string(issue: &Issue) -> String {
+    issue
+        .last_activity
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default()
+}
+
 /// Format a last_activity timestamp for display.
 fn format_activity(activity: &str) -> String {
     if activity.is_empty() {
@@ -463,3 +1158,90 @@ fn non_empty(s: &str) -> serde_json::Value {
         serde_json::Value::String(s.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent(id: &str, skills: &[(&str, &str)]) -> RouteAgent {
+        RouteAgent {
+            issue: Issue {
+                id: id.to_string(),
+                ..Issue::default()
+            },
+            skills: skills
+                .iter()
+                .map(|(s, l)| (s.to_string(), l.to_string()))
+                .collect(),
+        }
+    }
+
+    fn work(id: &str, labels: &[&str], work_type: WorkType) -> RouteWork {
+        let issue = Issue {
+            id: id.to_string(),
+            work_type,
+            ..Issue::default()
+        };
+        let labels: Vec<String> = labels.iter().map(|l| l.to_string()).collect();
+        RouteWork {
+            skills: required_skills(&issue, &labels),
+            issue,
+        }
+    }
+
+    #[test]
+    fn required_skills_merge_labels_and_metadata() {
+        let issue = Issue {
+            metadata: Some(
+                serde_json::value::RawValue::from_string(
+                    r#"{"skills":{"Rust":"advanced","sql":""}}"#.to_string(),
+                )
+                .unwrap(),
+            ),
+            ..Issue::default()
+        };
+        let labels = vec!["skill:rust:expert".to_string(), "backend".to_string()];
+        let skills = required_skills(&issue, &labels);
+        assert_eq!(skills.len(), 2);
+        assert_eq!(skills["rust"], "expert");
+        assert_eq!(skills["sql"], "");
+    }
+
+    #[test]
+    fn plan_matches_skills_and_work_type() {
+        let agents = vec![
+            agent("a-generalist", &[("rust", "expert"), ("go", "expert")]),
+            agent("a-junior", &[("rust", "beginner")]),
+            agent("a-rust", &[("rust", "expert")]),
+        ];
+        let work = vec![
+            work("t-1", &["skill:rust:advanced"], WorkType::Mutex),
+            work("t-2", &["skill:python"], WorkType::Mutex),
+            work("t-3", &["skill:rust"], WorkType::OpenCompetition),
+        ];
+        let plan = plan_routes(&agents, &work);
+        let routes: Vec<(&str, &str)> = plan
+            .assignments
+            .iter()
+            .map(|a| (a.agent.as_str(), a.issue.as_str()))
+            .collect();
+        // The specialist takes the advanced issue; the generalist and the
+        // junior both compete on the open issue
+        assert_eq!(
+            routes,
+            vec![
+                ("a-rust", "t-1"),
+                ("a-generalist", "t-3"),
+                ("a-junior", "t-3"),
+            ]
+        );
+        assert_eq!(
+            plan.unrouted,
+            vec![(
+                "t-2".to_string(),
+                "no idle agent has the required skills".to_string()
+            )]
+        );
+        assert!(plan.idle.is_empty());
+    }
+}
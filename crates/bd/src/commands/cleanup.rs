@@ -1,11 +1,152 @@
-//! `bd cleanup` -- clean up temporary data (stub).
+//! `bd cleanup` -- clean up temporary data.
+//!
+//! `--wisps` garbage collects wisps idle for longer than their TTL. A wisp
+//! molecule goes as a whole, once its root and every step have expired.
+//! Deleting an issue also deletes its labels, comments, events and
+//! dependencies.
 
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
 
+use anyhow::{Result, bail};
+use chrono::Utc;
+
+use beads_core::enums::{IssueType, Status, WispType};
+use beads_core::issue::Issue;
+use beads_storage::Storage;
+
+use crate::cli::CleanupArgs;
+use crate::commands::create::{generate_issue_id, issue_prefix};
+use crate::commands::{mol, wisp};
 use crate::context::RuntimeContext;
+use crate::output::output_json;
+
+/// Execute the `bd cleanup` command.
+pub fn run(ctx: &RuntimeContext, args: &CleanupArgs) -> Result<()> {
+    if !args.wisps {
+        bail!("nothing to clean up: pass --wisps to delete expired wisps");
+    }
+    if ctx.readonly && !args.dry_run {
+        bail!("cannot delete issues in read-only mode");
+    }
+
+    let store = ctx.store()?;
+    let expired = expired_wisps(ctx, store)?;
+    let ids: Vec<String> = expired.iter().map(|w| w.id.clone()).collect();
 
-/// Execute the `bd cleanup` command (stub).
-pub fn run(_ctx: &RuntimeContext) -> Result<()> {
-    println!("bd cleanup: not yet implemented");
+    if args.dry_run {
+        if ctx.json {
+            output_json(&serde_json::json!({
+                "dry_run": true,
+                "would_delete": ids,
+            }));
+        } else if expired.is_empty() {
+            println!("No expired wisps.");
+        } else {
+            println!("Would delete {} expired wisps:", expired.len());
+            for wisp in &expired {
+                println!("  {}", describe(wisp));
+            }
+        }
+        return Ok(());
+    }
+
+    if !ids.is_empty() {
+        store.run_in_transaction(&|tx| {
+            for id in &ids {
+                tx.delete_issue(id)?;
+            }
+            Ok(())
+        })?;
+    }
+    let report = if args.report && !expired.is_empty() {
+        Some(create_report(ctx, store, &expired)?)
+    } else {
+        None
+    };
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "deleted": ids,
+            "report": report,
+        }));
+    } else if !ctx.quiet {
+        println!("Deleted {} expired wisps", ids.len());
+        if let Some(report) = report {
+            println!("  Report: {}", report);
+        }
+    }
     Ok(())
 }
+
+/// Wisps past their TTL, leaving out molecules with members still alive.
+fn expired_wisps(ctx: &RuntimeContext, store: &dyn Storage) -> Result<Vec<Issue>> {
+    let config = wisp::wisp_config(ctx)?;
+    let now = Utc::now();
+
+    let wisps = wisp::load_wisps(store)?;
+    let mut expired = HashSet::new();
+    let mut molecules: HashMap<String, Vec<&str>> = HashMap::new();
+    for w in &wisps {
+        if wisp::expires_at(&config, w)? <= now {
+            expired.insert(w.id.as_str());
+        }
+        if let Some(root) = mol::molecule_of(store, &w.id)? {
+            molecules.entry(root).or_default().push(&w.id);
+        }
+    }
+    for members in molecules.values() {
+        if !members.iter().all(|id| expired.contains(id)) {
+            for id in members {
+                expired.remove(id);
+            }
+        }
+    }
+
+    let mut expired: Vec<Issue> = wisps
+        .iter()
+        .filter(|w| expired.contains(w.id.as_str()))
+        .cloned()
+        .collect();
+    expired.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    Ok(expired)
+}
+
+/// Creates a `gc_report` wisp listing `deleted` and returns its ID.
+fn create_report(ctx: &RuntimeContext, store: &dyn Storage, deleted: &[Issue]) -> Result<String> {
+    let now = Utc::now();
+    let title = format!("GC report: deleted {} expired wisps", deleted.len());
+    let description: String = deleted
+        .iter()
+        .map(|w| format!("- {}\n", describe(w)))
+        .collect();
+    let prefix = issue_prefix(ctx, store);
+    let id = generate_issue_id(store, &prefix, &title, &description, &ctx.actor, now)?;
+
+    let report = Issue {
+        id: id.clone(),
+        title,
+        description,
+        status: Status::Closed,
+        issue_type: IssueType::Task,
+        ephemeral: true,
+        wisp_type: WispType::GcReport,
+        close_reason: "Garbage collection finished".to_string(),
+        closed_at: Some(now),
+        created_by: ctx.actor.clone(),
+        created_at: now,
+        updated_at: now,
+        ..Issue::default()
+    };
+    store.create_issue(&report, &ctx.actor)?;
+    store.add_label(&id, "ephemeral", &ctx.actor)?;
+    Ok(id)
+}
+
+/// One-line summary of a wisp: ID, type and title.
+fn describe(wisp: &Issue) -> String {
+    let wisp_type = match wisp.wisp_type.as_str() {
+        "" => "wisp",
+        other => other,
+    };
+    format!("{} [{}] {}", wisp.id, wisp_type, wisp.title)
+}
@@ -1,4 +1,6 @@
-//! `bd config` -- manage configuration (set/get/list/unset).
+//! `bd config` -- manage configuration (set/get/list/unset/show).
+
+use std::collections::BTreeMap;
 
 use anyhow::{Context, Result, bail};
 
@@ -8,32 +10,22 @@ use crate::output::output_json;
 
 /// Execute the `bd config` command.
 pub fn run(ctx: &RuntimeContext, args: &ConfigArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
+    // The layered config.yaml settings do not need the database
+    if let ConfigCommands::Show(show_args) = &args.command {
+        return show(ctx, show_args.origin);
     }
 
+    let store = ctx.store()?;
+
     match &args.command {
         ConfigCommands::Set(set_args) => {
             if ctx.readonly {
                 bail!("cannot set config in read-only mode");
             }
 
-            let conn = rusqlite::Connection::open(&db_path)
-                .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            conn.execute(
-                "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
-                rusqlite::params![&set_args.key, &set_args.value],
-            )
-            .with_context(|| format!("failed to set config key '{}'", set_args.key))?;
+            store
+                .set_config(&set_args.key, &set_args.value)
+                .with_context(|| format!("failed to set config key '{}'", set_args.key))?;
 
             if ctx.json {
                 output_json(&serde_json::json!({
@@ -46,20 +38,11 @@ pub fn run(ctx: &RuntimeContext, args: &ConfigArgs) -> Result<()> {
         }
 
         ConfigCommands::Get(get_args) => {
-            let conn = rusqlite::Connection::open_with_flags(
-                &db_path,
-                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
-                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-            )
-            .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            let value: Option<String> = conn
-                .query_row(
-                    "SELECT value FROM config WHERE key = ?1",
-                    rusqlite::params![&get_args.key],
-                    |row| row.get(0),
-                )
-                .ok();
+            let value = match store.get_config(&get_args.key) {
+                Ok(v) => Some(v),
+                Err(e) if e.is_not_found() => None,
+                Err(e) => return Err(e.into()),
+            };
 
             if ctx.json {
                 output_json(&serde_json::json!({
@@ -78,25 +61,10 @@ pub fn run(ctx: &RuntimeContext, args: &ConfigArgs) -> Result<()> {
         }
 
         ConfigCommands::List => {
-            let conn = rusqlite::Connection::open_with_flags(
-                &db_path,
-                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
-                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-            )
-            .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            let mut stmt = conn.prepare("SELECT key, value FROM config ORDER BY key")?;
-            let entries: Vec<(String, String)> = stmt
-                .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-                .filter_map(|r| r.ok())
-                .collect();
+            let entries: BTreeMap<String, String> = store.get_all_config()?.into_iter().collect();
 
             if ctx.json {
-                let map: serde_json::Map<String, serde_json::Value> = entries
-                    .iter()
-                    .map(|(k, v)| (k.clone(), serde_json::Value::String(v.clone())))
-                    .collect();
-                output_json(&map);
+                output_json(&entries);
             } else if entries.is_empty() {
                 println!("No configuration values set");
             } else {
@@ -106,25 +74,25 @@ pub fn run(ctx: &RuntimeContext, args: &ConfigArgs) -> Result<()> {
             }
         }
 
+        ConfigCommands::Show(_) => unreachable!("handled above"),
+
         ConfigCommands::Unset(unset_args) => {
             if ctx.readonly {
                 bail!("cannot unset config in read-only mode");
             }
 
-            let conn = rusqlite::Connection::open(&db_path)
-                .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            let changes = conn.execute(
-                "DELETE FROM config WHERE key = ?1",
-                rusqlite::params![&unset_args.key],
-            )?;
+            let deleted = match store.delete_config(&unset_args.key) {
+                Ok(()) => true,
+                Err(e) if e.is_not_found() => false,
+                Err(e) => return Err(e.into()),
+            };
 
             if ctx.json {
                 output_json(&serde_json::json!({
                     "key": unset_args.key,
-                    "deleted": changes > 0,
+                    "deleted": deleted,
                 }));
-            } else if changes > 0 {
+            } else if deleted {
                 if !ctx.quiet {
                     println!("Unset {}", unset_args.key);
                 }
@@ -136,3 +104,50 @@ pub fn run(ctx: &RuntimeContext, args: &ConfigArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// `bd config show`: every setting of the layered config (defaults, user
+/// and project config.yaml, `BD_*` variables and flags), optionally with the
+/// layer it came from.
+fn show(ctx: &RuntimeContext, origin: bool) -> Result<()> {
+    let layered = ctx.layered_config()?;
+    let entries: Vec<(&str, serde_json::Value, _)> = layered
+        .entries()
+        .map(|(key, value, source)| {
+            let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+            (key, value, source)
+        })
+        .collect();
+
+    if ctx.json {
+        let map: serde_json::Map<String, serde_json::Value> = entries
+            .into_iter()
+            .map(|(key, value, source)| {
+                let value = if origin {
+                    serde_json::json!({
+                        "value": value,
+                        "origin": source.to_string(),
+                        "layer": source.layer(),
+                    })
+                } else {
+                    value
+                };
+                (key.to_string(), value)
+            })
+            .collect();
+        output_json(&map);
+        return Ok(());
+    }
+
+    for (key, value, source) in entries {
+        let value = match value {
+            serde_json::Value::String(s) => s,
+            other => other.to_string(),
+        };
+        if origin {
+            println!("{} = {}  ({})", key, value, source);
+        } else {
+            println!("{} = {}", key, value);
+        }
+    }
+    Ok(())
+}
@@ -4,18 +4,22 @@
 //! and either previews the cooked steps (--dry-run) or creates issues
 //! in the database.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::{Context, Result, bail};
 use chrono::Utc;
 
-use beads_core::enums::IssueType;
-use beads_core::idgen;
+use beads_core::dependency::{Dependency, WaitsForMeta, waits_for_gate};
+use beads_core::enums::{DependencyType, IssueType, Status, WispType};
+use beads_core::issue::Issue;
 use beads_formula::engine;
 use beads_formula::parser;
 use beads_formula::types::CookedStep;
+use beads_storage::{Storage, Transaction};
 
 use crate::cli::CookArgs;
+use crate::commands::create::{generate_issue_id_avoiding, issue_prefix};
+use crate::commands::mol;
 use crate::context::RuntimeContext;
 use crate::output::output_json;
 
@@ -35,7 +39,8 @@ pub fn run(ctx: &RuntimeContext, args: &CookArgs) -> Result<()> {
     let vars = parse_var_flags(&args.vars)?;
 
     // 3. Cook the formula
-    let cooked = engine::cook(&formula, &vars).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let cooked = engine::cook_with(&formula, &vars, &|name| parser::load_named(name, &cwd))
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
 
     if cooked.is_empty() {
         println!("No steps to create (all filtered by conditions).");
@@ -48,7 +53,7 @@ pub fn run(ctx: &RuntimeContext, args: &CookArgs) -> Result<()> {
     }
 
     // 5. Create issues in the database
-    create_issues(ctx, &formula.formula, &cooked, false)
+    create_issues(ctx, &formula.formula, &cooked)
 }
 
 /// Parse `--var key=value` flags into a HashMap.
@@ -100,192 +105,199 @@ fn print_cooked(ctx: &RuntimeContext, formula_name: &str, steps: &[CookedStep])
 }
 
 /// Create issues in the database for each cooked step.
-pub(crate) fn create_issues(
-    ctx: &RuntimeContext,
-    formula_name: &str,
-    steps: &[CookedStep],
-    ephemeral: bool,
-) -> Result<()> {
+fn create_issues(ctx: &RuntimeContext, formula_name: &str, steps: &[CookedStep]) -> Result<()> {
     if ctx.readonly {
         bail!("cannot create issues in read-only mode");
     }
+    let store = ctx.store()?;
+    let planned = StepIssues::plan(ctx, store, formula_name, steps, None, None, &HashSet::new())?;
+    store.run_in_transaction(&|tx| planned.write(tx, &ctx.actor))?;
+    planned.print(ctx);
+    Ok(())
+}
 
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open(&db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let prefix: String = conn
-        .query_row(
-            "SELECT value FROM config WHERE key = 'issue_prefix'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "bd".to_string());
-
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM issues", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    let hash_length = idgen::compute_adaptive_length(
-        count as usize,
-        idgen::adaptive_defaults::MIN_LENGTH,
-        idgen::adaptive_defaults::MAX_LENGTH,
-        idgen::adaptive_defaults::MAX_COLLISION_PROB,
-    );
-
-    let now = Utc::now();
-    let now_str = now.to_rfc3339();
-
-    // Map step IDs to issue IDs
-    let mut id_map: HashMap<String, String> = HashMap::new();
-    let mut created: Vec<serde_json::Value> = Vec::new();
+/// The issues, labels and dependencies for a set of cooked steps, planned
+/// up front so that they can be created in one transaction.
+pub(crate) struct StepIssues {
+    formula: String,
+    ephemeral: bool,
+    molecule: Option<String>,
+    issues: Vec<Issue>,
+    labels: Vec<(String, String)>,
+    deps: Vec<Dependency>,
+    created: Vec<serde_json::Value>,
+}
 
-    for step in steps {
-        let issue_type = IssueType::from(step.issue_type.as_str()).normalize();
+impl StepIssues {
+    /// Plans an issue for each cooked step, as steps of the molecule rooted
+    /// at `molecule` if given. With `wisp` the issues are ephemeral wisps of
+    /// that type. IDs in `taken` are planned for other issues and avoided.
+    pub(crate) fn plan(
+        ctx: &RuntimeContext,
+        store: &dyn Storage,
+        formula_name: &str,
+        steps: &[CookedStep],
+        wisp: Option<&WispType>,
+        molecule: Option<&str>,
+        taken: &HashSet<String>,
+    ) -> Result<Self> {
+        let ephemeral = wisp.is_some();
+        let prefix = issue_prefix(ctx, store);
+        let now = Utc::now();
+        let mut taken = taken.clone();
+
+        // Map step IDs to issue IDs
+        let mut id_map: HashMap<String, String> = HashMap::new();
+        let mut planned = Self {
+            formula: formula_name.to_string(),
+            ephemeral,
+            molecule: molecule.map(str::to_string),
+            issues: Vec::new(),
+            labels: Vec::new(),
+            deps: Vec::new(),
+            created: Vec::new(),
+        };
 
-        // Generate unique issue ID
-        let mut issue_id = String::new();
-        for nonce in 0..10 {
-            let candidate = idgen::generate_hash_id(
+        for step in steps {
+            let issue_id = generate_issue_id_avoiding(
+                store,
+                &taken,
                 &prefix,
                 &step.title,
                 &step.description,
                 &ctx.actor,
                 now,
-                hash_length,
-                nonce,
-            );
-            let exists: bool = conn
-                .query_row(
-                    "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                    rusqlite::params![&candidate],
-                    |row| row.get(0),
-                )
-                .unwrap_or(false);
-            if !exists && !id_map.values().any(|v| v == &candidate) {
-                issue_id = candidate;
-                break;
+            )
+            .with_context(|| format!("failed to generate unique ID for step '{}'", step.id))?;
+            taken.insert(issue_id.clone());
+
+            planned.issues.push(Issue {
+                id: issue_id.clone(),
+                title: step.title.clone(),
+                description: step.description.clone(),
+                status: Status::Open,
+                priority: step.priority,
+                issue_type: IssueType::from(step.issue_type.as_str()).normalize(),
+                assignee: step.assignee.clone().unwrap_or_default(),
+                ephemeral,
+                wisp_type: wisp.cloned().unwrap_or_default(),
+                created_by: ctx.actor.clone(),
+                created_at: now,
+                updated_at: now,
+                ..Issue::default()
+            });
+
+            // Step labels, the formula source label, the molecule and the
+            // ephemeral marker
+            let formula_label = format!("formula:{}", formula_name);
+            let molecule_label = molecule.map(mol::step_label);
+            let extra = ephemeral.then_some("ephemeral");
+            for label in step
+                .labels
+                .iter()
+                .map(String::as_str)
+                .chain(std::iter::once(formula_label.as_str()))
+                .chain(molecule_label.as_deref())
+                .chain(extra)
+            {
+                planned.labels.push((issue_id.clone(), label.to_string()));
             }
-        }
-        if issue_id.is_empty() {
-            bail!("failed to generate unique ID for step '{}'", step.id);
-        }
 
-        let ephemeral_flag: i32 = if ephemeral { 1 } else { 0 };
-
-        conn.execute(
-            "INSERT INTO issues (id, title, description, status, priority, issue_type, assignee, \
-             is_template, ephemeral, created_at, created_by, updated_at) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10, ?11)",
-            rusqlite::params![
-                &issue_id,
-                &step.title,
-                &step.description,
-                "open",
-                step.priority,
-                issue_type.as_str(),
-                step.assignee.as_deref().unwrap_or(""),
-                ephemeral_flag,
-                &now_str,
-                &ctx.actor,
-                &now_str,
-            ],
-        )
-        .with_context(|| format!("failed to create issue for step '{}'", step.id))?;
-
-        // Record event
-        conn.execute(
-            "INSERT INTO events (issue_id, event_type, actor, new_value, comment, created_at) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![
-                &issue_id,
-                "created",
-                &ctx.actor,
-                &step.title,
-                &format!("Cooked from formula {} step {}", formula_name, step.id),
-                &now_str,
-            ],
-        )?;
-
-        // Add labels
-        for label in &step.labels {
-            conn.execute(
-                "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-                rusqlite::params![&issue_id, label],
-            )?;
+            id_map.insert(step.id.clone(), issue_id.clone());
+            planned.created.push(serde_json::json!({
+                "id": issue_id,
+                "step": step.id,
+                "title": step.title,
+            }));
         }
 
-        // Add formula source label
-        conn.execute(
-            "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-            rusqlite::params![&issue_id, &format!("formula:{}", formula_name)],
-        )?;
-
-        if ephemeral {
-            conn.execute(
-                "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-                rusqlite::params![&issue_id, "ephemeral"],
-            )?;
+        // Create dependencies: needs -> blocks, or waits-for (all-children)
+        // on the copies of a for_each step. The spawner is recorded only
+        // when it is itself an issue; a fanned-out step has no issue of its
+        // own.
+        let spawners: HashMap<&str, &str> = steps
+            .iter()
+            .filter_map(|s| Some((s.id.as_str(), s.spawned_by.as_deref()?)))
+            .collect();
+        for step in steps {
+            if let Some(issue_id) = id_map.get(&step.id) {
+                for need in &step.needs {
+                    if let Some(dep_id) = id_map.get(need) {
+                        let (dep_type, metadata) = match spawners.get(need.as_str()) {
+                            Some(spawner) => (
+                                DependencyType::WaitsFor,
+                                serde_json::to_string(&WaitsForMeta {
+                                    gate: waits_for_gate::ALL_CHILDREN.to_string(),
+                                    spawner_id: id_map.get(*spawner).cloned().unwrap_or_default(),
+                                })?,
+                            ),
+                            None => (DependencyType::Blocks, String::new()),
+                        };
+                        planned.deps.push(Dependency {
+                            issue_id: issue_id.clone(),
+                            depends_on_id: dep_id.clone(),
+                            dep_type,
+                            created_at: now,
+                            created_by: ctx.actor.clone(),
+                            metadata,
+                            thread_id: String::new(),
+                        });
+                    }
+                }
+            }
         }
-
-        id_map.insert(step.id.clone(), issue_id.clone());
-        created.push(serde_json::json!({
-            "id": issue_id,
-            "step": step.id,
-            "title": step.title,
-        }));
+        Ok(planned)
     }
 
-    // Create dependencies (needs -> blocks)
-    for step in steps {
-        if let Some(issue_id) = id_map.get(&step.id) {
-            for need in &step.needs {
-                if let Some(dep_id) = id_map.get(need) {
-                    conn.execute(
-                        "INSERT OR IGNORE INTO dependencies (issue_id, depends_on_id, type, created_at, created_by) \
-                         VALUES (?1, ?2, ?3, ?4, ?5)",
-                        rusqlite::params![issue_id, dep_id, "blocks", &now_str, &ctx.actor],
-                    )?;
-                }
-            }
+    /// Creates the planned issues, labels and dependencies in `tx`.
+    pub(crate) fn write(
+        &self,
+        tx: &dyn Transaction,
+        actor: &str,
+    ) -> beads_storage::error::Result<()> {
+        for issue in &self.issues {
+            tx.create_issue(issue, actor)?;
+        }
+        for (issue_id, label) in &self.labels {
+            tx.add_label(issue_id, label, actor)?;
+        }
+        for dep in &self.deps {
+            tx.add_dependency(dep, actor)?;
         }
+        Ok(())
     }
 
-    // Output
-    if ctx.json {
-        output_json(&serde_json::json!({
-            "formula": formula_name,
-            "ephemeral": ephemeral,
-            "created": created,
-        }));
-    } else {
-        let mode = if ephemeral { "wisp" } else { "pour" };
-        println!(
-            "Cooked formula '{}' ({} mode) -> {} issues:",
-            formula_name,
-            mode,
-            created.len()
-        );
-        for entry in &created {
+    /// Reports the created issues.
+    pub(crate) fn print(&self, ctx: &RuntimeContext) {
+        if ctx.json {
+            let mut out = serde_json::json!({
+                "formula": self.formula,
+                "ephemeral": self.ephemeral,
+                "created": self.created,
+            });
+            if let Some(root) = &self.molecule {
+                out["molecule"] = serde_json::json!(root);
+            }
+            output_json(&out);
+        } else {
+            let mode = if self.ephemeral { "wisp" } else { "pour" };
             println!(
-                "  {} (step {}): {}",
-                entry["id"].as_str().unwrap_or(""),
-                entry["step"].as_str().unwrap_or(""),
-                entry["title"].as_str().unwrap_or(""),
+                "Cooked formula '{}' ({} mode) -> {} issues:",
+                self.formula,
+                mode,
+                self.created.len()
             );
+            if let Some(root) = &self.molecule {
+                println!("  Molecule: {}", root);
+            }
+            for entry in &self.created {
+                println!(
+                    "  {} (step {}): {}",
+                    entry["id"].as_str().unwrap_or(""),
+                    entry["step"].as_str().unwrap_or(""),
+                    entry["title"].as_str().unwrap_or(""),
+                );
+            }
         }
     }
-
-    Ok(())
 }
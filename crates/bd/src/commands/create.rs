@@ -1,15 +1,20 @@
 //! `bd create` -- create a new issue.
 
-use anyhow::{Context, Result, bail};
-use chrono::Utc;
+use std::collections::HashSet;
 
-use beads_core::enums::{IssueType, Status};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local, Utc};
+
+use beads_config::config::BeadsConfig;
+use beads_core::dependency::Dependency;
+use beads_core::enums::{DependencyType, IssueType, Status};
 use beads_core::idgen;
 use beads_core::issue::Issue;
+use beads_storage::Storage;
 
 use crate::cli::CreateArgs;
 use crate::context::RuntimeContext;
-use crate::output::output_json;
+use crate::output::{format_time, output_json};
 
 /// Execute the `bd create` command.
 pub fn run(ctx: &RuntimeContext, args: &CreateArgs) -> Result<()> {
@@ -38,105 +43,71 @@ pub fn run(ctx: &RuntimeContext, args: &CreateArgs) -> Result<()> {
     // Normalize issue type
     let issue_type = IssueType::from(args.issue_type.as_str()).normalize();
 
-    // Resolve the database path
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open(&db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    // Get issue prefix from config
-    let prefix: String = conn
-        .query_row(
-            "SELECT value FROM config WHERE key = 'issue_prefix'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "bd".to_string());
-
-    // Generate ID
-    let issue_id = if let Some(ref explicit_id) = args.id {
-        explicit_id.clone()
-    } else {
-        // Get issue count for adaptive length
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM issues", [], |row| row.get(0))
-            .unwrap_or(0);
-
-        let hash_length = idgen::compute_adaptive_length(
-            count as usize,
-            idgen::adaptive_defaults::MIN_LENGTH,
-            idgen::adaptive_defaults::MAX_LENGTH,
-            idgen::adaptive_defaults::MAX_COLLISION_PROB,
-        );
-
-        let description = args.description.as_deref().unwrap_or("");
-        let now = Utc::now();
-
-        // Try up to 10 nonces to avoid collisions
-        let mut id = String::new();
-        for nonce in 0..10 {
-            let candidate = idgen::generate_hash_id(
-                &prefix,
-                &title,
-                description,
-                &ctx.actor,
-                now,
-                hash_length,
-                nonce,
-            );
-
-            // Check if ID already exists
-            let exists: bool = conn
-                .query_row(
-                    "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                    rusqlite::params![&candidate],
-                    |row| row.get(0),
-                )
-                .unwrap_or(false);
-
-            if !exists {
-                id = candidate;
-                break;
+    let store = ctx.store()?;
+    let config = ctx.config()?;
+    let prefix = issue_prefix(ctx, store);
+    let description = args.description.as_deref().unwrap_or("");
+    check_issue_type(config, &issue_type)?;
+    check_description(config, description)?;
+
+    // Check the parent and the nesting depth
+    if let Some(ref parent) = args.parent {
+        store.get_issue(parent).map_err(|e| {
+            if e.is_not_found() {
+                anyhow!("parent issue not found: {}", parent)
+            } else {
+                e.into()
             }
-        }
+        })?;
+        check_depth(config, store, parent, None)?;
+    }
 
-        if id.is_empty() {
-            bail!("failed to generate unique ID after 10 attempts");
+    // Generate ID: children get the next hierarchical ID under their parent
+    let issue_id = match (&args.id, &args.parent) {
+        (Some(explicit_id), _) => explicit_id.clone(),
+        (None, Some(parent)) => next_child_id(store, parent)?,
+        (None, None) => {
+            generate_issue_id(store, &prefix, &title, description, &ctx.actor, Utc::now())?
         }
-
-        id
     };
 
     let now = Utc::now();
-    let now_str = now.to_rfc3339();
-    let description = args.description.as_deref().unwrap_or("");
+    let labels: Vec<String> = args
+        .labels
+        .iter()
+        .flat_map(|l| l.split(','))
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect();
+    let due_at = args
+        .due
+        .as_deref()
+        .map(|t| parse_future_time_arg("--due", t))
+        .transpose()?;
+    let defer_until = args
+        .defer
+        .as_deref()
+        .map(|t| parse_future_time_arg("--defer", t))
+        .transpose()?;
+    let issue = Issue {
+        id: issue_id.clone(),
+        title: title.clone(),
+        description: description.to_string(),
+        status: Status::Open,
+        priority,
+        issue_type,
+        assignee: args.assignee.clone().unwrap_or_default(),
+        due_at,
+        defer_until,
+        created_by: ctx.actor.clone(),
+        created_at: now,
+        updated_at: now,
+        ..Issue::default()
+    };
 
     // Handle --dry-run
     if args.dry_run {
-        let issue = Issue {
-            id: issue_id.clone(),
-            title: title.clone(),
-            description: description.to_string(),
-            status: Status::Open,
-            priority,
-            issue_type: issue_type.clone(),
-            assignee: args.assignee.clone().unwrap_or_default(),
-            created_by: ctx.actor.clone(),
-            created_at: now,
-            updated_at: now,
-            ..Issue::default()
-        };
-
         if ctx.json {
             output_json(&issue);
         } else {
@@ -146,6 +117,9 @@ pub fn run(ctx: &RuntimeContext, args: &CreateArgs) -> Result<()> {
             println!("  Type: {}", issue.issue_type);
             println!("  Priority: P{}", issue.priority);
             println!("  Status: {}", issue.status);
+            if let Some(ref parent) = args.parent {
+                println!("  Parent: {}", parent);
+            }
             if !issue.assignee.is_empty() {
                 println!("  Assignee: {}", issue.assignee);
             }
@@ -155,67 +129,42 @@ pub fn run(ctx: &RuntimeContext, args: &CreateArgs) -> Result<()> {
             if !args.labels.is_empty() {
                 println!("  Labels: {}", args.labels.join(", "));
             }
+            if let Some(due_at) = issue.due_at {
+                println!("  Due: {}", format_time(due_at));
+            }
+            if let Some(defer_until) = issue.defer_until {
+                println!("  Deferred until: {}", format_time(defer_until));
+            }
         }
         return Ok(());
     }
 
-    // Insert the issue
-    conn.execute(
-        "INSERT INTO issues (id, title, description, status, priority, issue_type, assignee, created_at, created_by, updated_at) \
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-        rusqlite::params![
-            &issue_id,
-            &title,
-            description,
-            "open",
-            priority,
-            issue_type.as_str(),
-            args.assignee.as_deref().unwrap_or(""),
-            &now_str,
-            &ctx.actor,
-            &now_str,
-        ],
-    )
-    .with_context(|| format!("failed to create issue {}", issue_id))?;
-
-    // Add labels
-    for label in &args.labels {
-        // Handle comma-separated labels within a single argument
-        for l in label.split(',') {
-            let l = l.trim();
-            if l.is_empty() {
-                continue;
-            }
-            conn.execute(
-                "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-                rusqlite::params![&issue_id, l],
-            )
+    store
+        .create_issue(&issue, &ctx.actor)
+        .with_context(|| format!("failed to create issue {}", issue_id))?;
+    for l in &labels {
+        store
+            .add_label(&issue_id, l, &ctx.actor)
             .with_context(|| format!("failed to add label '{}' to {}", l, issue_id))?;
-        }
     }
-
-    // Record create event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&issue_id, "created", &ctx.actor, &title, &now_str],
-    )?;
+    if let Some(ref parent) = args.parent {
+        let dep = Dependency {
+            issue_id: issue_id.clone(),
+            depends_on_id: parent.clone(),
+            dep_type: DependencyType::ParentChild,
+            created_at: now,
+            created_by: ctx.actor.clone(),
+            metadata: String::new(),
+            thread_id: String::new(),
+        };
+        store
+            .add_dependency(&dep, &ctx.actor)
+            .with_context(|| format!("failed to add {} to parent {}", issue_id, parent))?;
+    }
 
     // Output
     if ctx.json {
-        let issue = Issue {
-            id: issue_id.clone(),
-            title,
-            description: description.to_string(),
-            status: Status::Open,
-            priority,
-            issue_type,
-            assignee: args.assignee.clone().unwrap_or_default(),
-            created_by: ctx.actor.clone(),
-            created_at: now,
-            updated_at: now,
-            labels: args.labels.clone(),
-            ..Issue::default()
-        };
+        let issue = Issue { labels, ..issue };
         output_json(&issue);
     } else if args.silent {
         println!("{}", issue_id);
@@ -229,8 +178,203 @@ pub fn run(ctx: &RuntimeContext, args: &CreateArgs) -> Result<()> {
     Ok(())
 }
 
+/// Returns the issue ID prefix: `issue-prefix` from the layered config,
+/// else the one `bd init` stored in the database (`bd` if unset).
+pub(crate) fn issue_prefix(ctx: &RuntimeContext, store: &dyn Storage) -> String {
+    if let Some(prefix) = ctx
+        .config()
+        .ok()
+        .and_then(|c| c.prefix.as_deref())
+        .map(|p| p.trim().trim_end_matches('-'))
+        .filter(|p| !p.is_empty())
+    {
+        return prefix.to_string();
+    }
+    store
+        .get_config("issue_prefix")
+        .unwrap_or_else(|_| "bd".to_string())
+}
+
+/// Generates a hash-based issue ID that is not taken yet.
+///
+/// The hash length adapts to the number of issues in the database; up to 10
+/// nonces are tried before giving up.
+pub(crate) fn generate_issue_id(
+    store: &dyn Storage,
+    prefix: &str,
+    title: &str,
+    description: &str,
+    actor: &str,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    generate_issue_id_avoiding(
+        store,
+        &HashSet::new(),
+        prefix,
+        title,
+        description,
+        actor,
+        now,
+    )
+}
+
+/// Like [`generate_issue_id`], but also avoids the IDs in `taken`: those
+/// planned for issues that are created together in one transaction.
+pub(crate) fn generate_issue_id_avoiding(
+    store: &dyn Storage,
+    taken: &HashSet<String>,
+    prefix: &str,
+    title: &str,
+    description: &str,
+    actor: &str,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let count = store.get_statistics()?.total_issues as usize + taken.len();
+    let hash_length = idgen::compute_adaptive_length(
+        count,
+        idgen::adaptive_defaults::MIN_LENGTH,
+        idgen::adaptive_defaults::MAX_LENGTH,
+        idgen::adaptive_defaults::MAX_COLLISION_PROB,
+    );
+
+    for nonce in 0..10 {
+        let candidate =
+            idgen::generate_hash_id(prefix, title, description, actor, now, hash_length, nonce);
+        if taken.contains(&candidate) {
+            continue;
+        }
+        match store.get_issue(&candidate) {
+            Err(e) if e.is_not_found() => return Ok(candidate),
+            Err(e) => return Err(e.into()),
+            Ok(_) => {}
+        }
+    }
+    bail!("failed to generate unique ID after 10 attempts")
+}
+
+// ---------------------------------------------------------------------------
+// Config policies
+// ---------------------------------------------------------------------------
+
+/// Rejects issue types that are neither built in nor listed in
+/// `types.custom`.
+pub(crate) fn check_issue_type(config: &BeadsConfig, issue_type: &IssueType) -> Result<()> {
+    let custom = config.custom_types();
+    let mut allowed: Vec<&str> = custom.iter().map(String::as_str).collect();
+    allowed.push("gate");
+    if !issue_type.is_valid_with_custom(&allowed) {
+        bail!(
+            "unknown issue type '{}' (custom types are set with types.custom in .beads/config.yaml)",
+            issue_type
+        );
+    }
+    Ok(())
+}
+
+/// Rejects statuses that are neither built in nor listed in
+/// `status.custom`.
+pub(crate) fn check_status(config: &BeadsConfig, status: &Status) -> Result<()> {
+    let custom = config.custom_statuses();
+    let allowed: Vec<&str> = custom.iter().map(String::as_str).collect();
+    if !status.is_valid_with_custom(&allowed) {
+        bail!(
+            "unknown status '{}' (custom statuses are set with status.custom in .beads/config.yaml)",
+            status
+        );
+    }
+    Ok(())
+}
+
+/// Rejects an empty description when `create.require-description` is set.
+pub(crate) fn check_description(config: &BeadsConfig, description: &str) -> Result<()> {
+    if config.create.require_description && description.trim().is_empty() {
+        bail!("a description is required (create.require-description is set)");
+    }
+    Ok(())
+}
+
+/// Rejects making `child` (with everything below it) a child of `parent`
+/// when that nests deeper than `hierarchy.max-depth`. A new issue has
+/// nothing below it.
+pub(crate) fn check_depth(
+    config: &BeadsConfig,
+    store: &dyn Storage,
+    parent: &str,
+    child: Option<&str>,
+) -> Result<()> {
+    let below = match child {
+        Some(child) => hierarchy_height(store, child, &mut HashSet::new())?,
+        None => 0,
+    };
+    let depth = hierarchy_depth(store, parent)? + 1 + below;
+    let max_depth = config.hierarchy.max_depth as usize;
+    if depth > max_depth {
+        bail!(
+            "nesting under {} would reach depth {}, beyond hierarchy.max-depth {}",
+            parent,
+            depth,
+            max_depth
+        );
+    }
+    Ok(())
+}
+
+/// How many parent-child levels sit above `id` (0 for a top-level issue).
+fn hierarchy_depth(store: &dyn Storage, id: &str) -> Result<usize> {
+    let mut depth = 0;
+    let mut seen = HashSet::from([id.to_string()]);
+    let mut current = id.to_string();
+    while let Some(parent) = store
+        .get_dependency_records(&current)?
+        .into_iter()
+        .find(|d| d.dep_type == DependencyType::ParentChild)
+        .map(|d| d.depends_on_id)
+    {
+        if !seen.insert(parent.clone()) {
+            break;
+        }
+        depth += 1;
+        current = parent;
+    }
+    Ok(depth)
+}
+
+/// How many parent-child levels sit below `id` (0 without children).
+fn hierarchy_height(store: &dyn Storage, id: &str, seen: &mut HashSet<String>) -> Result<usize> {
+    if !seen.insert(id.to_string()) {
+        return Ok(0);
+    }
+    let mut height = 0;
+    for child in store.get_dependents_with_metadata(id)? {
+        if child.dependency.dep_type == DependencyType::ParentChild {
+            height = height.max(1 + hierarchy_height(store, &child.issue.id, seen)?);
+        }
+    }
+    Ok(height)
+}
+
+/// The next hierarchical child ID of `parent`: `<parent>.<n>`.
+fn next_child_id(store: &dyn Storage, parent: &str) -> Result<String> {
+    let prefix = format!("{}.", parent);
+    let mut n = store
+        .get_dependents_with_metadata(parent)?
+        .iter()
+        .filter_map(|c| c.issue.id.strip_prefix(&prefix)?.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0);
+    loop {
+        n += 1;
+        let candidate = format!("{}{}", prefix, n);
+        match store.get_issue(&candidate) {
+            Err(e) if e.is_not_found() => return Ok(candidate),
+            Err(e) => return Err(e.into()),
+            Ok(_) => {}
+        }
+    }
+}
+
 /// Parse a priority string that can be either a bare number ("2") or prefixed ("P2"/"p2").
-fn parse_priority(s: &str) -> Result<i32> {
+pub(crate) fn parse_priority(s: &str) -> Result<i32> {
     let s = s.trim();
     let num_str = if s.starts_with('P') || s.starts_with('p') {
         &s[1..]
@@ -249,6 +393,18 @@ fn parse_priority(s: &str) -> Result<i32> {
     Ok(p)
 }
 
+/// Parses a time flag (`2026-06-01`, `+3d`, `tomorrow 9am`, ...) in the
+/// local timezone.
+pub(crate) fn parse_time_arg(flag: &str, value: &str) -> Result<DateTime<Utc>> {
+    beads_timeparsing::parse_time(value, &Local::now()).map_err(|e| anyhow!("{flag}: {e}"))
+}
+
+/// Parses a deadline or deferral flag: as [`parse_time_arg`], but a bare
+/// duration (`2h`, `3d`) is that long from now.
+pub(crate) fn parse_future_time_arg(flag: &str, value: &str) -> Result<DateTime<Utc>> {
+    beads_timeparsing::parse_future_time(value, &Local::now()).map_err(|e| anyhow!("{flag}: {e}"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -5,25 +5,18 @@ use std::collections::{HashMap, HashSet};
 use anyhow::{Context, Result, bail};
 use chrono::Utc;
 
+use beads_core::dependency::Dependency;
 use beads_core::enums::DependencyType;
+use beads_storage::{IssueWithDependencyMetadata, Storage};
 
 use crate::cli::{DepArgs, DepCommands};
+use crate::commands::create::check_depth;
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
 /// Execute the `bd dep` command.
 pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
+    let store = ctx.store()?;
 
     match &args.command {
         DepCommands::Add(add_args) => {
@@ -31,9 +24,6 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
                 bail!("cannot add dependencies in read-only mode");
             }
 
-            let conn = rusqlite::Connection::open(&db_path)
-                .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
             // Validate dependency type
             let dep_type = DependencyType::from(add_args.dep_type.as_str());
             if !dep_type.is_valid() {
@@ -45,51 +35,33 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
 
             // Validate both issues exist
             for id in [&add_args.from, &add_args.to] {
-                let exists: bool = conn
-                    .query_row(
-                        "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                        rusqlite::params![id],
-                        |row| row.get(0),
-                    )
-                    .unwrap_or(false);
-                if !exists {
-                    bail!("issue '{}' not found", id);
+                if let Err(e) = store.get_issue(id) {
+                    if e.is_not_found() {
+                        bail!("issue '{}' not found", id);
+                    }
+                    return Err(e.into());
                 }
             }
+            if dep_type == DependencyType::ParentChild {
+                check_depth(ctx.config()?, store, &add_args.to, Some(&add_args.from))?;
+            }
 
-            let now_str = Utc::now().to_rfc3339();
-
-            conn.execute(
-                "INSERT OR IGNORE INTO dependencies (issue_id, depends_on_id, type, created_at, created_by) \
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![
-                    &add_args.from,
-                    &add_args.to,
-                    dep_type.as_str(),
-                    &now_str,
-                    &ctx.actor,
-                ],
-            )
-            .with_context(|| {
+            let dep = Dependency {
+                issue_id: add_args.from.clone(),
+                depends_on_id: add_args.to.clone(),
+                dep_type: dep_type.clone(),
+                created_at: Utc::now(),
+                created_by: ctx.actor.clone(),
+                metadata: String::new(),
+                thread_id: String::new(),
+            };
+            store.add_dependency(&dep, &ctx.actor).with_context(|| {
                 format!(
                     "failed to add dependency {} -> {}",
                     add_args.from, add_args.to
                 )
             })?;
 
-            // Record event
-            conn.execute(
-                "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) \
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![
-                    &add_args.from,
-                    "dependency_added",
-                    &ctx.actor,
-                    format!("{}:{}", dep_type.as_str(), add_args.to),
-                    &now_str,
-                ],
-            )?;
-
             if ctx.json {
                 output_json(&serde_json::json!({
                     "from": add_args.from,
@@ -109,38 +81,20 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
                 bail!("cannot remove dependencies in read-only mode");
             }
 
-            let conn = rusqlite::Connection::open(&db_path)
-                .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            let now_str = Utc::now().to_rfc3339();
-
-            let changes = conn.execute(
-                "DELETE FROM dependencies WHERE issue_id = ?1 AND depends_on_id = ?2",
-                rusqlite::params![&remove_args.from, &remove_args.to],
-            )?;
-
-            if changes > 0 {
-                // Record event
-                conn.execute(
-                    "INSERT INTO events (issue_id, event_type, actor, old_value, created_at) \
-                     VALUES (?1, ?2, ?3, ?4, ?5)",
-                    rusqlite::params![
-                        &remove_args.from,
-                        "dependency_removed",
-                        &ctx.actor,
-                        &remove_args.to,
-                        &now_str,
-                    ],
-                )?;
-            }
+            let removed =
+                match store.remove_dependency(&remove_args.from, &remove_args.to, &ctx.actor) {
+                    Ok(()) => true,
+                    Err(e) if e.is_not_found() => false,
+                    Err(e) => return Err(e.into()),
+                };
 
             if ctx.json {
                 output_json(&serde_json::json!({
                     "from": remove_args.from,
                     "to": remove_args.to,
-                    "removed": changes > 0,
+                    "removed": removed,
                 }));
-            } else if changes > 0 {
+            } else if removed {
                 if !ctx.quiet {
                     println!(
                         "Removed dependency: {} -> {}",
@@ -156,52 +110,11 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
         }
 
         DepCommands::List(list_args) => {
-            let conn = rusqlite::Connection::open_with_flags(
-                &db_path,
-                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
-                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-            )
-            .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
             // Dependencies (this issue depends on)
-            let mut dep_stmt = conn.prepare(
-                "SELECT d.depends_on_id, d.type, i.title, i.status \
-                 FROM dependencies d \
-                 LEFT JOIN issues i ON d.depends_on_id = i.id \
-                 WHERE d.issue_id = ?1 \
-                 ORDER BY d.type, d.depends_on_id",
-            )?;
-            let deps: Vec<(String, String, String, String)> = dep_stmt
-                .query_map(rusqlite::params![&list_args.id], |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get::<_, String>(2).unwrap_or_default(),
-                        row.get::<_, String>(3).unwrap_or_default(),
-                    ))
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
+            let deps = dependency_rows(store.get_dependencies_with_metadata(&list_args.id)?);
 
             // Dependents (issues that depend on this one)
-            let mut dependent_stmt = conn.prepare(
-                "SELECT d.issue_id, d.type, i.title, i.status \
-                 FROM dependencies d \
-                 LEFT JOIN issues i ON d.issue_id = i.id \
-                 WHERE d.depends_on_id = ?1 \
-                 ORDER BY d.type, d.issue_id",
-            )?;
-            let dependents: Vec<(String, String, String, String)> = dependent_stmt
-                .query_map(rusqlite::params![&list_args.id], |row| {
-                    Ok((
-                        row.get(0)?,
-                        row.get(1)?,
-                        row.get::<_, String>(2).unwrap_or_default(),
-                        row.get::<_, String>(3).unwrap_or_default(),
-                    ))
-                })?
-                .filter_map(|r| r.ok())
-                .collect();
+            let dependents = dependency_rows(store.get_dependents_with_metadata(&list_args.id)?);
 
             if ctx.json {
                 let deps_json: Vec<serde_json::Value> = deps
@@ -259,14 +172,7 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
         }
 
         DepCommands::Cycles => {
-            let conn = rusqlite::Connection::open_with_flags(
-                &db_path,
-                rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY
-                    | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-            )
-            .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-            let cycles = detect_cycles(&conn)?;
+            let cycles = detect_cycles(store)?;
 
             if ctx.json {
                 output_json(&cycles);
@@ -292,11 +198,11 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
         }
 
         DepCommands::Parents(parent_args) => {
-            run_parents(ctx, &db_path, &parent_args.id)?;
+            run_parents(ctx, &parent_args.id)?;
         }
 
         DepCommands::Children(children_args) => {
-            run_children(ctx, &db_path, &children_args.id)?;
+            run_children(ctx, &children_args.id)?;
         }
     }
 
@@ -304,28 +210,11 @@ pub fn run(ctx: &RuntimeContext, args: &DepArgs) -> Result<()> {
 }
 
 /// Run `dep parents <id>` -- show parent issues.
-pub fn run_parents(ctx: &RuntimeContext, db_path: &std::path::Path, issue_id: &str) -> Result<()> {
-    let conn = rusqlite::Connection::open_with_flags(
-        db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+pub fn run_parents(ctx: &RuntimeContext, issue_id: &str) -> Result<()> {
+    let store = ctx.store()?;
 
     // In parent-child deps: issue_id=child depends on depends_on_id=parent.
-    // Parents of X: SELECT depends_on_id WHERE issue_id = X AND type = 'parent-child'
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.status, i.priority \
-         FROM dependencies d \
-         JOIN issues i ON d.depends_on_id = i.id \
-         WHERE d.issue_id = ?1 AND d.type = 'parent-child' \
-         ORDER BY i.id",
-    )?;
-    let parents: Vec<(String, String, String, i32)> = stmt
-        .query_map(rusqlite::params![issue_id], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let parents = family_rows(store.get_dependencies_with_metadata(issue_id)?);
 
     if ctx.json {
         let json_parents: Vec<serde_json::Value> = parents
@@ -366,28 +255,11 @@ pub fn run_parents(ctx: &RuntimeContext, db_path: &std::path::Path, issue_id: &s
 }
 
 /// Run `dep children <id>` -- show child issues.
-pub fn run_children(ctx: &RuntimeContext, db_path: &std::path::Path, issue_id: &str) -> Result<()> {
-    let conn = rusqlite::Connection::open_with_flags(
-        db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    // Children: issues where issue_id=child depends on depends_on_id=parent
-    // Children of X: SELECT issue_id FROM dependencies WHERE depends_on_id = X AND type = 'parent-child'
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.status, i.priority \
-         FROM dependencies d \
-         JOIN issues i ON d.issue_id = i.id \
-         WHERE d.depends_on_id = ?1 AND d.type = 'parent-child' \
-         ORDER BY i.id",
-    )?;
-    let children: Vec<(String, String, String, i32)> = stmt
-        .query_map(rusqlite::params![issue_id], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+pub fn run_children(ctx: &RuntimeContext, issue_id: &str) -> Result<()> {
+    let store = ctx.store()?;
+
+    // Children of X are the issues whose parent-child edge points at X.
+    let children = family_rows(store.get_dependents_with_metadata(issue_id)?);
 
     if ctx.json {
         let json_children: Vec<serde_json::Value> = children
@@ -427,28 +299,63 @@ pub fn run_children(ctx: &RuntimeContext, db_path: &std::path::Path, issue_id: &
     Ok(())
 }
 
+/// Flatten dependency edges into `(id, type, title, status)` rows, ordered by
+/// type then ID.
+fn dependency_rows(
+    entries: Vec<IssueWithDependencyMetadata>,
+) -> Vec<(String, String, String, String)> {
+    let mut rows: Vec<_> = entries
+        .into_iter()
+        .map(|e| {
+            (
+                e.issue.id,
+                e.dependency.dep_type.as_str().to_string(),
+                e.issue.title,
+                e.issue.status.as_str().to_string(),
+            )
+        })
+        .collect();
+    rows.sort_by(|a, b| (&a.1, &a.0).cmp(&(&b.1, &b.0)));
+    rows
+}
+
+/// Keep only parent-child edges as `(id, title, status, priority)` rows,
+/// ordered by ID.
+fn family_rows(entries: Vec<IssueWithDependencyMetadata>) -> Vec<(String, String, String, i32)> {
+    let mut rows: Vec<_> = entries
+        .into_iter()
+        .filter(|e| e.dependency.dep_type == DependencyType::ParentChild)
+        .map(|e| {
+            (
+                e.issue.id,
+                e.issue.title,
+                e.issue.status.as_str().to_string(),
+                e.issue.priority,
+            )
+        })
+        .collect();
+    rows.sort_by(|a, b| a.0.cmp(&b.0));
+    rows
+}
+
 /// Detect dependency cycles using DFS on blocking dependency types.
 ///
 /// Returns a list of cycles, where each cycle is a list of issue IDs.
-fn detect_cycles(conn: &rusqlite::Connection) -> Result<Vec<Vec<String>>> {
-    // Load all blocking dependencies (blocks, parent-child, conditional-blocks, waits-for)
-    let mut stmt = conn.prepare(
-        "SELECT issue_id, depends_on_id FROM dependencies \
-         WHERE type IN ('blocks', 'parent-child', 'conditional-blocks', 'waits-for')",
-    )?;
-
+fn detect_cycles(store: &dyn Storage) -> Result<Vec<Vec<String>>> {
+    // Blocking dependencies (blocks, parent-child, conditional-blocks, waits-for)
     let mut graph: HashMap<String, Vec<String>> = HashMap::new();
     let mut all_nodes: HashSet<String> = HashSet::new();
 
-    let edges: Vec<(String, String)> = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    for (from, to) in &edges {
-        graph.entry(from.clone()).or_default().push(to.clone());
-        all_nodes.insert(from.clone());
-        all_nodes.insert(to.clone());
+    for dep in store.get_all_dependency_records()?.into_values().flatten() {
+        if !dep.dep_type.affects_ready_work() {
+            continue;
+        }
+        all_nodes.insert(dep.issue_id.clone());
+        all_nodes.insert(dep.depends_on_id.clone());
+        graph
+            .entry(dep.issue_id)
+            .or_default()
+            .push(dep.depends_on_id);
     }
 
     let mut cycles: Vec<Vec<String>> = Vec::new();
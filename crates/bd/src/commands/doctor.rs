@@ -8,10 +8,16 @@
 //! - Schema tables are present and valid
 //! - Counts of issues, dependencies, labels, comments, events
 //! - Data quality issues (empty titles, orphaned records)
+//! - Other processes holding `.beads` locks
 //!
 //! Other subcommands (fix, validate, pollution, artifacts) are stubs.
 
-use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+
+use beads_core::filter::IssueFilter;
 
 use crate::cli::{DoctorArgs, DoctorCommands};
 use crate::context::RuntimeContext;
@@ -84,42 +90,40 @@ fn run_health(ctx: &RuntimeContext) -> Result<()> {
     println!("[OK] Database file exists: {}", db_path.display());
 
     // 3. Try to open the database
-    let conn = match rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    ) {
-        Ok(conn) => {
+    let store = match ctx.store() {
+        Ok(store) => {
             println!("[OK] Database opens successfully");
-            conn
+            store
         }
         Err(e) => {
-            println!("[FAIL] Cannot open database: {}", e);
+            println!("[FAIL] Cannot open database: {:#}", e);
             issues_found += 1;
             print_summary(issues_found);
             return Ok(());
         }
     };
 
-    // 4. Check integrity
-    match conn.query_row("PRAGMA integrity_check", [], |row| row.get::<_, String>(0)) {
-        Ok(result) if result == "ok" => {
-            println!("[OK] SQLite integrity check passed");
-        }
-        Ok(result) => {
-            println!("[WARN] SQLite integrity check: {}", result);
-            issues_found += 1;
-        }
+    let report = match store.check_health() {
+        Ok(report) => report,
         Err(e) => {
-            println!("[FAIL] SQLite integrity check failed: {}", e);
+            println!("[FAIL] Health check failed: {}", e);
             issues_found += 1;
+            print_summary(issues_found);
+            return Ok(());
         }
+    };
+
+    // 4. Check integrity
+    if report.integrity == "ok" {
+        println!("[OK] SQLite integrity check passed");
+    } else {
+        println!("[WARN] SQLite integrity check: {}", report.integrity);
+        issues_found += 1;
     }
 
     // 5. Check schema: verify expected tables exist
-    let existing_tables = get_table_names(&conn).context("failed to query table list")?;
-
     for table in EXPECTED_TABLES {
-        if existing_tables.contains(&table.to_string()) {
+        if report.tables.iter().any(|t| t == table) {
             println!("[OK] Table '{}' exists", table);
         } else {
             println!("[FAIL] Table '{}' is missing", table);
@@ -132,31 +136,23 @@ fn run_health(ctx: &RuntimeContext) -> Result<()> {
     println!("Record counts:");
 
     let count = |table: &str| -> i64 {
-        conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| {
-            row.get(0)
-        })
-        .unwrap_or(-1)
+        report
+            .record_counts
+            .iter()
+            .find(|(name, _)| name == table)
+            .map_or(-1, |(_, n)| *n)
     };
 
-    let issue_count = count("issues");
-    let dep_count = count("dependencies");
-    let label_count = count("labels");
-    let comment_count = count("comments");
-    let event_count = count("events");
-
-    println!("  Issues:       {}", issue_count);
-    println!("  Dependencies: {}", dep_count);
-    println!("  Labels:       {}", label_count);
-    println!("  Comments:     {}", comment_count);
-    println!("  Events:       {}", event_count);
+    println!("  Issues:       {}", count("issues"));
+    println!("  Dependencies: {}", count("dependencies"));
+    println!("  Labels:       {}", count("labels"));
+    println!("  Comments:     {}", count("comments"));
+    println!("  Events:       {}", count("events"));
 
     // 7. Check for empty-title issues
-    let empty_titles: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM issues WHERE title IS NULL OR title = ''",
-            [],
-            |row| row.get(0),
-        )
+    let empty_titles = store
+        .search_issues("", &IssueFilter::default())
+        .map(|issues| issues.iter().filter(|i| i.title.is_empty()).count())
         .unwrap_or(0);
 
     if empty_titles > 0 {
@@ -169,45 +165,52 @@ fn run_health(ctx: &RuntimeContext) -> Result<()> {
     }
 
     // 8. Check for orphaned dependencies (referencing non-existent issues)
-    let orphaned_deps: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM dependencies \
-             WHERE issue_id NOT IN (SELECT id FROM issues) \
-                OR depends_on_id NOT IN (SELECT id FROM issues)",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-
-    if orphaned_deps > 0 {
+    if report.orphaned_dependencies > 0 {
         println!(
             "[WARN] {} orphaned dependency record(s) detected",
-            orphaned_deps
+            report.orphaned_dependencies
         );
         issues_found += 1;
     }
 
     // 9. Check for orphaned labels
-    let orphaned_labels: i64 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM labels WHERE issue_id NOT IN (SELECT id FROM issues)",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or(0);
-
-    if orphaned_labels > 0 {
+    if report.orphaned_labels > 0 {
         println!(
             "[WARN] {} orphaned label record(s) detected",
-            orphaned_labels
+            report.orphaned_labels
         );
         issues_found += 1;
     }
 
+    // 10. Report other processes holding locks; informational only.
+    print_lock_holders(&beads_dir);
+
     print_summary(issues_found);
     Ok(())
 }
 
+/// Lists live holders of the `.beads/*.lock` files other than this process.
+fn print_lock_holders(beads_dir: &Path) {
+    let Ok(entries) = fs::read_dir(beads_dir) else {
+        return;
+    };
+    let mut lock_files: Vec<_> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "lock"))
+        .collect();
+    lock_files.sort();
+    let me = std::process::id();
+    for path in lock_files {
+        for holder in beads_lockfile::holders(&path) {
+            if holder.pid == me {
+                continue;
+            }
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            println!("[INFO] {} held by {}", name, holder);
+        }
+    }
+}
+
 /// Print the final summary line.
 fn print_summary(issues_found: u32) {
     println!();
@@ -217,15 +220,3 @@ fn print_summary(issues_found: u32) {
         println!("Health check completed: {} issue(s) found", issues_found);
     }
 }
-
-/// Get all user table names from the database.
-fn get_table_names(conn: &rusqlite::Connection) -> Result<Vec<String>> {
-    let mut stmt = conn.prepare(
-        "SELECT name FROM sqlite_master WHERE type='table' AND name NOT LIKE 'sqlite_%'",
-    )?;
-    let names: Vec<String> = stmt
-        .query_map([], |row| row.get(0))?
-        .filter_map(|r| r.ok())
-        .collect();
-    Ok(names)
-}
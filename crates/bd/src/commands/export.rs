@@ -1,19 +1,217 @@
-//! `bd export` -- export issues to external formats (stub).
+//! `bd export` -- export issues to JSONL (Obsidian export is a stub).
+//!
+//! The JSONL export is the git-tracked representation of the database. It is
+//! fully deterministic: issues are sorted by ID and every record is passed
+//! through [`canonicalize`], so exporting unchanged data rewrites the file
+//! byte-for-byte and git diffs only show real changes.
 
-use anyhow::Result;
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use beads_core::enums::Status;
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
+use beads_core::jsonl::{canonicalize, write_jsonl};
+use beads_lockfile::LockMode;
+use beads_storage::Storage;
 
 use crate::cli::{ExportArgs, ExportCommands};
+use crate::commands::sync_cmd::record_jsonl_state;
 use crate::context::RuntimeContext;
+use crate::locks;
+use crate::output::output_json;
 
-/// Execute the `bd export` command (stub).
-pub fn run(_ctx: &RuntimeContext, args: &ExportArgs) -> Result<()> {
+/// Execute the `bd export` command.
+pub fn run(ctx: &RuntimeContext, args: &ExportArgs) -> Result<()> {
     match &args.command {
         Some(ExportCommands::Obsidian(_)) => {
             println!("bd export obsidian: not yet implemented");
+            Ok(())
         }
-        None => {
-            println!("bd export: not yet implemented");
-        }
+        None => run_jsonl(ctx, args),
+    }
+}
+
+/// Default JSONL export.
+fn run_jsonl(ctx: &RuntimeContext, args: &ExportArgs) -> Result<()> {
+    let store = ctx.store()?;
+
+    let filter = IssueFilter {
+        status: args.status.as_deref().map(Status::from),
+        labels: args.labels.clone(),
+        ephemeral: if args.include_ephemeral {
+            None
+        } else {
+            Some(false)
+        },
+        ..Default::default()
+    };
+    let filtered = args.status.is_some() || !args.labels.is_empty();
+
+    // A filtered export must never clobber the canonical issues.jsonl
+    // unless explicitly asked to.
+    let target = match args.output.as_deref() {
+        Some("-") => None,
+        Some(path) => Some(PathBuf::from(path)),
+        None if filtered => None,
+        None => Some(
+            ctx.resolve_db_path()
+                .context("no beads database found. Run 'bd init' to create one.")?
+                .join("issues.jsonl"),
+        ),
+    };
+
+    // Hold the file from snapshot to rename so a concurrent import or
+    // export sees either the old file or this one.
+    let _lock = target
+        .as_deref()
+        .map(|path| locks::lock_file(path, LockMode::Exclusive))
+        .transpose()?;
+    let issues = collect_issues(store, &filter)?;
+
+    let Some(path) = target else {
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        write_jsonl(&mut handle, &issues).context("failed to write JSONL")?;
+        return Ok(());
+    };
+
+    write_jsonl_file(&path, &issues)?;
+    if args.output.is_none() && !args.include_ephemeral {
+        // The canonical export now matches the database.
+        record_jsonl_state(store, &path)?;
+    }
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "path": path.display().to_string(),
+            "exported": issues.len(),
+        }));
+    } else if !ctx.quiet {
+        println!("Exported {} issues to {}", issues.len(), path.display());
+    }
+    Ok(())
+}
+
+/// Loads every issue matching `filter` with its labels, dependencies and
+/// comments attached, canonicalized and sorted by ID.
+///
+/// Reads happen inside one transaction so the export is a consistent
+/// snapshot even if another process writes concurrently.
+pub fn collect_issues(store: &dyn Storage, filter: &IssueFilter) -> Result<Vec<Issue>> {
+    let collected = RefCell::new(Vec::new());
+    store
+        .run_in_transaction(&|tx| {
+            let mut issues = tx.search_issues("", filter)?;
+            for issue in &mut issues {
+                issue.labels = tx.get_labels(&issue.id)?;
+                issue.dependencies = tx.get_dependency_records(&issue.id)?;
+                issue.comments = tx.get_comments(&issue.id)?;
+                canonicalize(issue);
+            }
+            *collected.borrow_mut() = issues;
+            Ok(())
+        })
+        .context("failed to read issues for export")?;
+
+    let mut issues = collected.into_inner();
+    issues.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(issues)
+}
+
+/// Writes `issues` as JSONL to `path`, replacing it atomically via a
+/// temporary file in the same directory.
+pub fn write_jsonl_file(path: &Path, issues: &[Issue]) -> Result<()> {
+    let tmp_path = path.with_extension("jsonl.tmp");
+    {
+        let file = fs::File::create(&tmp_path)
+            .with_context(|| format!("failed to create {}", tmp_path.display()))?;
+        let mut writer = BufWriter::new(file);
+        write_jsonl(&mut writer, issues)
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        writer
+            .flush()
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
     }
+    fs::rename(&tmp_path, path).with_context(|| format!("failed to replace {}", path.display()))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beads_core::dependency::Dependency;
+    use beads_core::enums::DependencyType;
+    use beads_core::issue::IssueBuilder;
+    use beads_storage::SqliteStore;
+
+    fn seeded_store() -> SqliteStore {
+        let store = SqliteStore::open_in_memory().unwrap();
+        for id in ["bd-c", "bd-a", "bd-b"] {
+            let issue = IssueBuilder::new(format!("Issue {id}")).id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        store.add_label_impl("bd-a", "zeta", "alice").unwrap();
+        store.add_label_impl("bd-a", "alpha", "alice").unwrap();
+        store.add_comment_impl("bd-b", "bob", "note").unwrap();
+        store
+            .add_dependency_impl(
+                &Dependency {
+                    issue_id: "bd-c".into(),
+                    depends_on_id: "bd-a".into(),
+                    dep_type: DependencyType::Blocks,
+                    created_at: chrono::Utc::now(),
+                    created_by: "alice".into(),
+                    metadata: String::new(),
+                    thread_id: String::new(),
+                },
+                "alice",
+            )
+            .unwrap();
+        store
+    }
+
+    #[test]
+    fn export_is_sorted_and_embeds_relations() {
+        let store = seeded_store();
+        let issues = collect_issues(&store, &IssueFilter::default()).unwrap();
+
+        let ids: Vec<_> = issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["bd-a", "bd-b", "bd-c"]);
+        assert_eq!(issues[0].labels, vec!["alpha", "zeta"]);
+        assert_eq!(issues[1].comments.len(), 1);
+        assert_eq!(issues[1].comments[0].id, 0);
+        assert_eq!(issues[2].dependencies[0].depends_on_id, "bd-a");
+    }
+
+    #[test]
+    fn export_is_byte_stable() {
+        let store = seeded_store();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("issues.jsonl");
+
+        let issues = collect_issues(&store, &IssueFilter::default()).unwrap();
+        write_jsonl_file(&path, &issues).unwrap();
+        let first = fs::read(&path).unwrap();
+
+        let issues = collect_issues(&store, &IssueFilter::default()).unwrap();
+        write_jsonl_file(&path, &issues).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), first);
+    }
+
+    #[test]
+    fn export_filters_by_label() {
+        let store = seeded_store();
+        let filter = IssueFilter {
+            labels: vec!["alpha".into()],
+            ..Default::default()
+        };
+        let issues = collect_issues(&store, &filter).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].id, "bd-a");
+    }
+}
@@ -1,18 +1,612 @@
-//! `bd formula` -- formula operations (Phase 4 stub).
+//! `bd formula` -- list, show, create and delete formula files.
+//!
+//! Formulas live in the search roots of [`parser::find_formula`]: the
+//! current directory (`*.formula.toml` / `*.formula.json`),
+//! `.beads/formulas/` and `~/.beads/formulas/`. A name found in several
+//! roots resolves to the first, and only that one is listed.
 
-use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
 
-use crate::cli::{FormulaArgs, FormulaCommands};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Serialize;
+
+use beads_core::enums::DependencyType;
+use beads_core::issue::Issue;
+use beads_formula::engine;
+use beads_formula::parser::{self, FORMULA_SUFFIXES, FormulaScope};
+use beads_formula::types::{Formula, Selector, Step};
+use beads_storage::Storage;
+
+use crate::cli::{FormulaArgs, FormulaCommands, FormulaCreateArgs, FormulaDeleteArgs};
+use crate::commands::mol;
 use crate::context::RuntimeContext;
+use crate::output::{output_json, output_table};
+
+/// Execute the `bd formula` command.
+pub fn run(ctx: &RuntimeContext, args: &FormulaArgs) -> Result<()> {
+    match &args.command {
+        FormulaCommands::List => cmd_list(ctx),
+        FormulaCommands::Show(a) => cmd_show(ctx, &a.name),
+        FormulaCommands::Create(a) => cmd_create(ctx, a),
+        FormulaCommands::Delete(a) => cmd_delete(ctx, a),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// List
+// ---------------------------------------------------------------------------
+
+/// One row of `bd formula list`.
+#[derive(Serialize)]
+struct FormulaRow {
+    name: String,
+    scope: FormulaScope,
+    path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    r#type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<i32>,
+    vars: Vec<String>,
+    steps: usize,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    description: String,
+    /// Why the file could not be loaded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let rows: Vec<FormulaRow> = parser::discover_formulas(&cwd)
+        .into_iter()
+        .map(|file| {
+            let mut row = FormulaRow {
+                name: file.name,
+                scope: file.scope,
+                path: file.path.display().to_string(),
+                r#type: None,
+                version: None,
+                vars: Vec::new(),
+                steps: 0,
+                description: String::new(),
+                error: None,
+            };
+            match parser::load_formula(&file.path) {
+                Ok(formula) => {
+                    row.r#type = Some(formula.r#type);
+                    row.version = Some(formula.version);
+                    row.vars = formula.vars.into_keys().collect();
+                    row.steps = formula.steps.len();
+                    row.description = formula.description;
+                }
+                Err(e) => row.error = Some(e.to_string()),
+            }
+            row
+        })
+        .collect();
+
+    if ctx.json {
+        output_json(&rows);
+        return Ok(());
+    }
+    if rows.is_empty() {
+        println!("No formulas found (searched cwd, .beads/formulas/, ~/.beads/formulas/).");
+        println!("Create one with: bd formula create <name>");
+        return Ok(());
+    }
+
+    let table: Vec<Vec<String>> = rows
+        .iter()
+        .map(|r| {
+            vec![
+                r.name.clone(),
+                r.r#type.clone().unwrap_or_else(|| "invalid".into()),
+                r.version.map_or_else(|| "-".into(), |v| v.to_string()),
+                if r.vars.is_empty() {
+                    "-".into()
+                } else {
+                    r.vars.join(",")
+                },
+                r.steps.to_string(),
+                r.scope.as_str().to_string(),
+                r.path.clone(),
+            ]
+        })
+        .collect();
+    output_table(
+        &["NAME", "TYPE", "VER", "VARS", "STEPS", "SCOPE", "PATH"],
+        &table,
+    );
+    for r in &rows {
+        if let Some(ref e) = r.error {
+            eprintln!("Warning: {}: {}", r.path, e);
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Show
+// ---------------------------------------------------------------------------
+
+/// JSON shape of `bd formula show`.
+#[derive(Serialize)]
+struct FormulaDetail<'a> {
+    path: String,
+    #[serde(flatten)]
+    formula: &'a Formula,
+    /// Step IDs grouped by dependency layer.
+    layers: Vec<Vec<&'a str>>,
+}
+
+fn cmd_show(ctx: &RuntimeContext, name: &str) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let path = parser::find_formula(name, &cwd).map_err(|e| anyhow!("{}", e))?;
+    let formula = parser::load_formula(&path).map_err(|e| anyhow!("{}: {}", path.display(), e))?;
+    let layers = engine::step_layers(&formula)
+        .map_err(|e| anyhow!("formula '{}' has an invalid step graph: {}", name, e))?;
+
+    if ctx.json {
+        output_json(&FormulaDetail {
+            path: path.display().to_string(),
+            formula: &formula,
+            layers: layers
+                .iter()
+                .map(|layer| layer.iter().map(|s| s.id.as_str()).collect())
+                .collect(),
+        });
+        return Ok(());
+    }
 
-/// Execute the `bd formula` command (stub).
-pub fn run(_ctx: &RuntimeContext, args: &FormulaArgs) -> Result<()> {
-    let sub = match &args.command {
-        FormulaCommands::List => "list",
-        FormulaCommands::Show(_) => "show",
-        FormulaCommands::Create(_) => "create",
-        FormulaCommands::Delete(_) => "delete",
+    println!(
+        "Formula: {} ({}, v{})",
+        formula.formula, formula.r#type, formula.version
+    );
+    println!("Source: {}", path.display());
+    if !formula.description.is_empty() {
+        println!();
+        println!("{}", formula.description);
+    }
+
+    if !formula.vars.is_empty() {
+        println!();
+        println!("Variables ({}):", formula.vars.len());
+        let width = formula.vars.keys().map(String::len).max().unwrap_or(0);
+        for (var, def) in &formula.vars {
+            let spec = match (&def.default, def.required) {
+                (Some(default), _) => format!("default: {default:?}"),
+                (None, true) => "required".to_string(),
+                (None, false) => "optional".to_string(),
+            };
+            let mut line = format!("  {:<width$}  {}", var, def.r#type.as_str());
+            if !def.values.is_empty() {
+                line.push_str(&format!(" [{}]", def.values.join("|")));
+            }
+            line.push_str(&format!(", {spec}"));
+            if !def.description.is_empty() {
+                line.push_str(&format!("  -- {}", def.description));
+            }
+            println!("{line}");
+        }
+    }
+
+    if let Some(ref pointcut) = formula.pointcut {
+        for (position, selector) in [("before", &pointcut.before), ("after", &pointcut.after)] {
+            if let Some(selector) = selector {
+                println!();
+                println!("Woven {position}: {}", describe_selector(selector));
+            }
+        }
+    }
+    if !formula.aspects.is_empty() {
+        println!();
+        println!("Aspects: {}", formula.aspects.join(", "));
+    }
+
+    println!();
+    println!("Steps ({}):", formula.steps.len());
+    for (depth, layer) in layers.iter().enumerate() {
+        for step in layer {
+            print_step(depth + 1, step);
+        }
+    }
+    Ok(())
+}
+
+/// Describes an aspect selector, e.g. `steps with type feature`.
+fn describe_selector(selector: &Selector) -> String {
+    let mut parts = Vec::new();
+    if let Some(ref t) = selector.r#type {
+        parts.push(format!("type {t}"));
+    }
+    if let Some(ref l) = selector.label {
+        parts.push(format!("label {l}"));
+    }
+    if let Some(ref id) = selector.id {
+        parts.push(format!("id {id}"));
+    }
+    if parts.is_empty() {
+        "every step".to_string()
+    } else {
+        format!("steps with {}", parts.join(" and "))
+    }
+}
+
+/// Prints one step of the DAG, prefixed by its layer number.
+fn print_step(layer: usize, step: &Step) {
+    let mut line = format!("  [{}] {}: {}", layer, step.id, step.title);
+    if step.r#type != "task" || step.priority != 2 {
+        line.push_str(&format!(" ({}, P{})", step.r#type, step.priority));
+    }
+    println!("{line}");
+    let indent = "      ";
+    if !step.needs.is_empty() {
+        println!("{indent}needs: {}", step.needs.join(", "));
+    }
+    if let Some(ref name) = step.expand {
+        let bindings: Vec<String> = step.with.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        if bindings.is_empty() {
+            println!("{indent}expands: {name}");
+        } else {
+            println!("{indent}expands: {name} ({})", bindings.join(", "));
+        }
+    }
+    if let Some(ref list) = step.for_each {
+        println!("{indent}for each: {list}");
+    }
+    if let Some(ref condition) = step.condition {
+        println!("{indent}when: {condition}");
+    }
+    if let Some(ref gate) = step.gate {
+        let mut desc = gate.r#type.clone();
+        if !gate.id.is_empty() {
+            desc.push(' ');
+            desc.push_str(&gate.id);
+        }
+        if !gate.timeout.is_empty() {
+            desc.push_str(&format!(" (timeout {})", gate.timeout));
+        }
+        println!("{indent}gate: {desc}");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Create
+// ---------------------------------------------------------------------------
+
+fn cmd_create(ctx: &RuntimeContext, args: &FormulaCreateArgs) -> Result<()> {
+    validate_name(&args.name)?;
+    let cwd = std::env::current_dir()?;
+    let dir = if args.global {
+        parser::user_formula_dir().context("cannot determine the home directory")?
+    } else {
+        if !cwd.join(".beads").is_dir() {
+            bail!(
+                "no .beads directory in {}; run from the project root or use --global",
+                cwd.display()
+            );
+        }
+        parser::project_formula_dir(&cwd)
+    };
+    let path = dir.join(format!("{}.formula.toml", args.name));
+    if path.exists() && !args.force {
+        bail!(
+            "formula file {} already exists (use --force to overwrite)",
+            path.display()
+        );
+    }
+
+    let (content, steps) = match &args.from_epic {
+        Some(epic_id) => {
+            let formula = formula_from_epic(ctx.store()?, &args.name, epic_id, args)?;
+            let header = format!("# Generated from epic {epic_id}.\n");
+            let body = parser::to_toml(&formula).map_err(|e| anyhow!("{}", e))?;
+            (header + &body, formula.steps.len())
+        }
+        None => {
+            let description = args
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("{} workflow", args.name));
+            let content = skeleton(&args.name, &description);
+            let steps = parser::parse_toml(&content)
+                .map_err(|e| anyhow!("{}", e))?
+                .steps
+                .len();
+            (content, steps)
+        }
     };
-    println!("bd formula {}: formula support not yet implemented", sub);
+
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    fs::write(&path, content).with_context(|| format!("failed to write {}", path.display()))?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "name": args.name,
+            "path": path.display().to_string(),
+            "steps": steps,
+        }));
+    } else if !ctx.quiet {
+        println!(
+            "Created formula {} with {} steps: {}",
+            args.name,
+            steps,
+            path.display()
+        );
+    }
     Ok(())
 }
+
+/// Formula names become file names, so keep them to a safe alphabet.
+pub(crate) fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with(['.', '-'])
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!(
+            "invalid formula name '{}': use letters, digits, '-', '_' and '.'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// A starter formula showing variables, dependencies, conditions and gates.
+fn skeleton(name: &str, description: &str) -> String {
+    let quote = |s: &str| serde_json::to_string(s).unwrap_or_else(|_| "\"\"".into());
+    format!(
+        r#"# Cook with: bd cook {name} --var component=<name>
+formula = {formula}
+description = {description}
+type = "workflow"
+version = 1
+
+[vars.component]
+description = "What the workflow is about"
+required = true
+
+[vars.reviewer]
+description = "Who reviews the change (leave empty to skip review)"
+default = ""
+
+[[steps]]
+id = "design"
+title = "Design {{{{component}}}}"
+
+[[steps]]
+id = "implement"
+title = "Implement {{{{component}}}}"
+needs = ["design"]
+
+[[steps]]
+id = "review"
+title = "Review {{{{component}}}}"
+assignee = "{{{{reviewer}}}}"
+needs = ["implement"]
+condition = "{{{{reviewer}}}}"
+gate = {{ type = "human", timeout = "2d" }}
+"#,
+        formula = quote(name),
+        description = quote(description),
+    )
+}
+
+/// Builds a formula whose steps mirror an epic's descendants, with `blocks`
+/// dependencies between them as `needs`.
+fn formula_from_epic(
+    store: &dyn Storage,
+    name: &str,
+    epic_id: &str,
+    args: &FormulaCreateArgs,
+) -> Result<Formula> {
+    let epic = match store.get_issue(epic_id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", epic_id),
+        Err(e) => return Err(e.into()),
+    };
+    let issues = descendants(store, &epic.id)?;
+    if issues.is_empty() {
+        bail!("epic {} has no children to turn into steps", epic.id);
+    }
+    let description = args
+        .description
+        .clone()
+        .unwrap_or_else(|| epic.title.clone());
+    formula_from_issues(store, name, &description, &issues)
+}
+
+/// Builds a formula with one step per issue. `blocks` and `waits-for`
+/// dependencies between the issues become `needs`; labels added by pouring
+/// are left out.
+pub(crate) fn formula_from_issues(
+    store: &dyn Storage,
+    name: &str,
+    description: &str,
+    issues: &[Issue],
+) -> Result<Formula> {
+    let mut used = HashSet::new();
+    let step_ids: HashMap<String, String> = issues
+        .iter()
+        .map(|issue| (issue.id.clone(), unique_step_id(&issue.title, &mut used)))
+        .collect();
+
+    let mut steps = Vec::with_capacity(issues.len());
+    for issue in issues {
+        let needs = store
+            .get_dependency_records(&issue.id)?
+            .into_iter()
+            .filter(|d| {
+                matches!(
+                    d.dep_type,
+                    DependencyType::Blocks | DependencyType::WaitsFor
+                )
+            })
+            .filter_map(|d| step_ids.get(&d.depends_on_id).cloned())
+            .collect();
+        let labels = store
+            .get_labels(&issue.id)?
+            .into_iter()
+            .filter(|l| !mol::is_pour_label(l))
+            .collect();
+        steps.push(Step {
+            id: step_ids[&issue.id].clone(),
+            title: issue.title.clone(),
+            description: issue.description.clone(),
+            r#type: issue.issue_type.as_str().to_string(),
+            priority: issue.priority,
+            needs,
+            condition: None,
+            gate: None,
+            assignee: (!issue.assignee.is_empty()).then(|| issue.assignee.clone()),
+            labels,
+            expand: None,
+            with: Default::default(),
+            for_each: None,
+        });
+    }
+
+    Ok(Formula {
+        formula: name.to_string(),
+        description: description.to_string(),
+        r#type: "workflow".to_string(),
+        version: 1,
+        vars: Default::default(),
+        steps,
+        aspects: Vec::new(),
+        pointcut: None,
+        source: String::new(),
+    })
+}
+
+/// All issues below `parent_id` via parent-child edges, depth-first in ID
+/// order.
+pub(crate) fn descendants(store: &dyn Storage, parent_id: &str) -> Result<Vec<Issue>> {
+    let mut result = Vec::new();
+    let mut visited = HashSet::from([parent_id.to_string()]);
+    let mut stack = vec![parent_id.to_string()];
+    while let Some(id) = stack.pop() {
+        let mut children: Vec<Issue> = store
+            .get_dependents_with_metadata(&id)?
+            .into_iter()
+            .filter(|e| e.dependency.dep_type == DependencyType::ParentChild)
+            .map(|e| e.issue)
+            .filter(|issue| visited.insert(issue.id.clone()))
+            .collect();
+        children.sort_by(|a, b| b.id.cmp(&a.id));
+        for child in children {
+            stack.push(child.id.clone());
+            result.push(child);
+        }
+    }
+    result.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(result)
+}
+
+/// Slugifies a title into a step ID not yet in `used`.
+pub(crate) fn unique_step_id(title: &str, used: &mut HashSet<String>) -> String {
+    let mut slug = String::new();
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= 32 {
+            break;
+        }
+    }
+    let base = match slug.trim_end_matches('-') {
+        "" => "step".to_string(),
+        s => s.to_string(),
+    };
+    let mut id = base.clone();
+    let mut n = 2;
+    while !used.insert(id.clone()) {
+        id = format!("{base}-{n}");
+        n += 1;
+    }
+    id
+}
+
+// ---------------------------------------------------------------------------
+// Delete
+// ---------------------------------------------------------------------------
+
+fn cmd_delete(ctx: &RuntimeContext, args: &FormulaDeleteArgs) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let path = parser::find_formula(&args.name, &cwd).map_err(|e| anyhow!("{}", e))?;
+    if !is_formula_file(&path) {
+        bail!("{} is not a formula file", path.display());
+    }
+    if !args.force {
+        bail!(
+            "deletion cannot be undone.\nUse --force to confirm deletion of formula '{}': {}",
+            args.name,
+            path.display()
+        );
+    }
+
+    fs::remove_file(&path).with_context(|| format!("failed to delete {}", path.display()))?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "deleted": args.name,
+            "path": path.display().to_string(),
+        }));
+    } else if !ctx.quiet {
+        println!("Deleted formula {}: {}", args.name, path.display());
+    }
+    Ok(())
+}
+
+/// Only files named like formulas or that parse as one may be deleted, so a
+/// stray name cannot remove an unrelated file.
+fn is_formula_file(path: &Path) -> bool {
+    let named = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| FORMULA_SUFFIXES[..2].iter().any(|s| n.ends_with(s)));
+    named || parser::load_formula(path).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skeleton_parses_and_lays_out() {
+        let formula = parser::parse_toml(&skeleton("feature", "A \"quoted\" workflow")).unwrap();
+        assert_eq!(formula.formula, "feature");
+        assert_eq!(formula.description, "A \"quoted\" workflow");
+        assert!(formula.vars["component"].required);
+        let layers = engine::step_layers(&formula).unwrap();
+        assert_eq!(layers.len(), 3);
+        assert_eq!(formula.steps[2].condition.as_deref(), Some("{{reviewer}}"));
+    }
+
+    #[test]
+    fn step_ids_are_unique_slugs() {
+        let mut used = HashSet::new();
+        assert_eq!(
+            unique_step_id("Write the API docs!", &mut used),
+            "write-the-api-docs"
+        );
+        assert_eq!(
+            unique_step_id("Write the API docs", &mut used),
+            "write-the-api-docs-2"
+        );
+        assert_eq!(unique_step_id("???", &mut used), "step");
+    }
+
+    #[test]
+    fn names_are_file_safe() {
+        assert!(validate_name("mol-release_2.x").is_ok());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name("a/b").is_err());
+        assert!(validate_name("").is_err());
+    }
+}
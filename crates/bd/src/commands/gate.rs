@@ -1,21 +1,34 @@
 //! `bd gate` -- quality gate management (list, show, create, close, check).
 //!
 //! Gates are issues with `issue_type='gate'` that block workflow until a
-//! condition is met. They support several `await_type` values:
-//! - `human`: must be manually closed
-//! - `timer`: auto-close when `created_at + timeout < now`
-//! - `gh:run`: auto-close when a GitHub Actions run succeeds
-//! - `gh:pr`: auto-close when a GitHub PR is merged
+//! condition is met. Their `await_type` selects the evaluator that
+//! `bd gate check` asks, e.g. `human` (must be manually closed), `timer`,
+//! `file:<path>`, `cmd:<shell>`, `http:<url>`, `issue:<id>`, `gh:run`,
+//! `gh:pr` or `all:`/`any:` composites; see [`crate::gates`].
 
 use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
 use chrono::{DateTime, Utc};
 
-use beads_core::idgen;
+use beads_core::dependency::Dependency;
+use beads_core::enums::{DependencyType, IssueType, Status, WispType};
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
+use beads_storage::{IssueUpdates, Storage};
+use beads_timeparsing::{Unit, format_duration, parse_duration_in};
 
-use crate::cli::{GateArgs, GateCommands};
+use crate::cli::{GateArgs, GateCheckArgs, GateCommands, GateWatchArgs};
+use crate::commands::create::{generate_issue_id, issue_prefix};
+use crate::commands::mail;
 use crate::context::RuntimeContext;
+use crate::gates::{GateContext, GateRegistry, GateResult};
 use crate::output::{output_json, output_table};
 
+/// Label marking a gate that was escalated for passing its timeout.
+const ESCALATED_LABEL: &str = "escalated";
+
 /// Execute the `bd gate` command.
 pub fn run(ctx: &RuntimeContext, args: &GateArgs) -> Result<()> {
     match &args.command {
@@ -23,7 +36,8 @@ pub fn run(ctx: &RuntimeContext, args: &GateArgs) -> Result<()> {
         GateCommands::Show(a) => cmd_show(ctx, &a.id),
         GateCommands::Create(a) => cmd_create(ctx, a),
         GateCommands::Close(a) => cmd_close(ctx, &a.id, a.reason.as_deref()),
-        GateCommands::Check => cmd_check(ctx),
+        GateCommands::Check(a) => cmd_check(ctx, a),
+        GateCommands::Watch(a) => cmd_watch(ctx, a),
     }
 }
 
@@ -32,29 +46,10 @@ pub fn run(ctx: &RuntimeContext, args: &GateArgs) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
-    let conn = open_db(ctx, false)?;
-
-    let mut stmt = conn.prepare(
-        "SELECT id, title, status, await_type, await_id, timeout_ns, waiters, created_at \
-         FROM issues WHERE issue_type = 'gate' AND status != 'closed' \
-         ORDER BY created_at DESC",
-    )?;
+    let store = ctx.store()?;
 
-    let gates: Vec<GateRow> = stmt
-        .query_map([], |row| {
-            Ok(GateRow {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                status: row.get::<_, String>(2).unwrap_or_default(),
-                await_type: row.get::<_, String>(3).unwrap_or_default(),
-                await_id: row.get::<_, String>(4).unwrap_or_default(),
-                timeout_ns: row.get::<_, i64>(5).unwrap_or(0),
-                waiters_json: row.get::<_, String>(6).unwrap_or_else(|_| "[]".to_string()),
-                created_at: row.get::<_, String>(7).unwrap_or_default(),
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let mut gates = load_open_gates(store)?;
+    gates.reverse();
 
     if ctx.json {
         let json_gates: Vec<serde_json::Value> = gates.iter().map(gate_to_json).collect();
@@ -86,8 +81,8 @@ fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_show(ctx: &RuntimeContext, id: &str) -> Result<()> {
-    let conn = open_db(ctx, false)?;
-    let gate = load_gate(&conn, id)?;
+    let store = ctx.store()?;
+    let gate = load_gate(store, id)?;
 
     let waiters: Vec<String> = serde_json::from_str(&gate.waiters_json).unwrap_or_default();
     let timeout_display = format_duration_ns(gate.timeout_ns);
@@ -129,10 +124,18 @@ fn cmd_create(ctx: &RuntimeContext, args: &crate::cli::GateCreateArgs) -> Result
         bail!("cannot create gates in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
+    let store = ctx.store()?;
 
     let await_type = args.await_type.as_deref().unwrap_or("human");
     let await_id = args.await_id.as_deref().unwrap_or("");
+    let registry = gate_registry(ctx)?;
+    if registry.resolve(await_type, await_id).is_none() {
+        bail!(
+            "unknown await type '{}' (known: {})",
+            await_type,
+            registry.names().join(", ")
+        );
+    }
 
     // Parse timeout
     let timeout_ns: i64 = if let Some(ref t) = args.timeout {
@@ -141,82 +144,28 @@ fn cmd_create(ctx: &RuntimeContext, args: &crate::cli::GateCreateArgs) -> Result
         0
     };
 
-    let waiters_json = serde_json::to_string(&args.waiters)?;
-
-    // Get prefix
-    let prefix: String = conn
-        .query_row(
-            "SELECT value FROM config WHERE key = 'issue_prefix'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "bd".to_string());
-
-    // Generate ID
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM issues", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    let hash_length = idgen::compute_adaptive_length(
-        count as usize,
-        idgen::adaptive_defaults::MIN_LENGTH,
-        idgen::adaptive_defaults::MAX_LENGTH,
-        idgen::adaptive_defaults::MAX_COLLISION_PROB,
-    );
-
     let now = Utc::now();
-    let now_str = now.to_rfc3339();
-
-    let mut issue_id = String::new();
-    for nonce in 0..10 {
-        let candidate = idgen::generate_hash_id(
-            &prefix,
-            &args.title,
-            "",
-            &ctx.actor,
-            now,
-            hash_length,
-            nonce,
-        );
-        let exists: bool = conn
-            .query_row(
-                "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                rusqlite::params![&candidate],
-                |row| row.get(0),
-            )
-            .unwrap_or(false);
-        if !exists {
-            issue_id = candidate;
-            break;
-        }
-    }
-
-    if issue_id.is_empty() {
-        bail!("failed to generate unique ID after 10 attempts");
-    }
-
-    conn.execute(
-        "INSERT INTO issues (id, title, status, priority, issue_type, await_type, await_id, timeout_ns, waiters, created_at, created_by, updated_at) \
-         VALUES (?1, ?2, 'open', 2, 'gate', ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        rusqlite::params![
-            &issue_id,
-            &args.title,
-            await_type,
-            await_id,
-            timeout_ns,
-            &waiters_json,
-            &now_str,
-            &ctx.actor,
-            &now_str,
-        ],
-    )
-    .with_context(|| format!("failed to create gate {}", issue_id))?;
-
-    // Record event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&issue_id, "created", &ctx.actor, &args.title, &now_str],
-    )?;
+    let prefix = issue_prefix(ctx, store);
+    let issue_id = generate_issue_id(store, &prefix, &args.title, "", &ctx.actor, now)?;
+
+    let issue = Issue {
+        id: issue_id.clone(),
+        title: args.title.clone(),
+        status: Status::Open,
+        priority: 2,
+        issue_type: IssueType::from("gate"),
+        await_type: await_type.to_string(),
+        await_id: await_id.to_string(),
+        timeout: (timeout_ns > 0).then(|| Duration::from_nanos(timeout_ns as u64)),
+        waiters: args.waiters.clone(),
+        created_at: now,
+        created_by: ctx.actor.clone(),
+        updated_at: now,
+        ..Issue::default()
+    };
+    store
+        .create_issue(&issue, &ctx.actor)
+        .with_context(|| format!("failed to create gate {}", issue_id))?;
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -251,36 +200,15 @@ fn cmd_close(ctx: &RuntimeContext, id: &str, reason: Option<&str>) -> Result<()>
         bail!("cannot close gates in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
-    let gate = load_gate(&conn, id)?;
+    let store = ctx.store()?;
+    let gate = load_gate(store, id)?;
 
     if gate.status == "closed" {
         bail!("gate '{}' is already closed", id);
     }
 
     let reason = reason.unwrap_or("Manually closed");
-    let now_str = Utc::now().to_rfc3339();
-
-    conn.execute(
-        "UPDATE issues SET status = 'closed', close_reason = ?1, closed_at = ?2, updated_at = ?3 \
-         WHERE id = ?4",
-        rusqlite::params![reason, &now_str, &now_str, id],
-    )?;
-
-    // Record event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, old_value, new_value, comment, created_at) \
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        rusqlite::params![
-            id,
-            "closed",
-            &ctx.actor,
-            &gate.status,
-            "closed",
-            reason,
-            &now_str,
-        ],
-    )?;
+    store.close_issue(id, reason, &ctx.actor, "")?;
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -299,103 +227,73 @@ fn cmd_close(ctx: &RuntimeContext, id: &str, reason: Option<&str>) -> Result<()>
 // Check
 // ---------------------------------------------------------------------------
 
-fn cmd_check(ctx: &RuntimeContext) -> Result<()> {
-    let conn = open_db(ctx, true)?;
+fn cmd_check(ctx: &RuntimeContext, args: &GateCheckArgs) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot check gates in read-only mode");
+    }
 
-    let mut stmt = conn.prepare(
-        "SELECT id, title, status, await_type, await_id, timeout_ns, waiters, created_at \
-         FROM issues WHERE issue_type = 'gate' AND status != 'closed' \
-         ORDER BY created_at ASC",
-    )?;
+    let store = ctx.store()?;
 
-    let gates: Vec<GateRow> = stmt
-        .query_map([], |row| {
-            Ok(GateRow {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                status: row.get::<_, String>(2).unwrap_or_default(),
-                await_type: row.get::<_, String>(3).unwrap_or_default(),
-                await_id: row.get::<_, String>(4).unwrap_or_default(),
-                timeout_ns: row.get::<_, i64>(5).unwrap_or(0),
-                waiters_json: row.get::<_, String>(6).unwrap_or_else(|_| "[]".to_string()),
-                created_at: row.get::<_, String>(7).unwrap_or_default(),
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let gates = load_open_gates(store)?;
 
     if gates.is_empty() {
         if ctx.json {
-            output_json(&serde_json::json!({ "checked": 0, "closed": [] }));
+            output_json(&serde_json::json!({ "checked": 0, "closed": [], "escalated": [] }));
         } else {
             println!("No open gates to check.");
         }
         return Ok(());
     }
 
-    let now = Utc::now();
-    let now_str = now.to_rfc3339();
-    let mut closed_gates: Vec<serde_json::Value> = Vec::new();
+    let registry = gate_registry(ctx)?;
+    let mail = mail_delegate(ctx, args.mail)?;
+    let gate_refs: Vec<&GateRow> = gates.iter().collect();
+    let outcomes = check_gates(
+        ctx,
+        store,
+        &registry,
+        &gate_refs,
+        mail.as_deref(),
+        Utc::now(),
+    )?;
 
-    for gate in &gates {
-        let result = check_gate(gate, now);
-        match result {
-            GateResult::Resolved(reason) => {
-                // Auto-close the gate
-                conn.execute(
-                    "UPDATE issues SET status = 'closed', close_reason = ?1, closed_at = ?2, updated_at = ?3 \
-                     WHERE id = ?4",
-                    rusqlite::params![&reason, &now_str, &now_str, &gate.id],
-                )?;
-
-                conn.execute(
-                    "INSERT INTO events (issue_id, event_type, actor, old_value, new_value, comment, created_at) \
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-                    rusqlite::params![
-                        &gate.id,
-                        "closed",
-                        &ctx.actor,
-                        &gate.status,
-                        "closed",
-                        &reason,
-                        &now_str,
-                    ],
-                )?;
-
-                closed_gates.push(serde_json::json!({
-                    "id": gate.id,
-                    "reason": reason,
-                }));
-
-                if !ctx.json {
-                    println!("Closed gate {}: {}", gate.id, reason);
-                }
-            }
-            GateResult::Pending => {
-                if !ctx.json && !ctx.quiet {
-                    println!("Gate {} ({}): pending", gate.id, gate.await_type);
-                }
-            }
-            GateResult::Error(msg) => {
-                if !ctx.json {
-                    eprintln!("Gate {} ({}): error -- {}", gate.id, gate.await_type, msg);
-                }
-            }
+    for outcome in &outcomes {
+        if !ctx.json {
+            print_outcome(ctx, outcome);
         }
     }
+    let closed: Vec<serde_json::Value> = outcomes
+        .iter()
+        .filter_map(|o| match &o.result {
+            GateResult::Resolved(reason) => Some(serde_json::json!({
+                "id": o.gate.id,
+                "reason": reason,
+                "notified": o.notified,
+            })),
+            _ => None,
+        })
+        .collect();
+    let escalated: Vec<serde_json::Value> = outcomes
+        .iter()
+        .filter_map(|o| {
+            let escalation = o.escalation.as_ref()?;
+            Some(serde_json::json!({ "id": o.gate.id, "escalation": escalation }))
+        })
+        .collect();
 
     if ctx.json {
         output_json(&serde_json::json!({
             "checked": gates.len(),
-            "closed": closed_gates,
+            "closed": closed,
+            "escalated": escalated,
         }));
-    } else if closed_gates.is_empty() {
+    } else if closed.is_empty() {
         println!("\nChecked {} gates, none resolved.", gates.len());
     } else {
         println!(
             "\nChecked {} gates, {} auto-closed.",
             gates.len(),
-            closed_gates.len()
+            closed.len()
         );
     }
 
@@ -403,121 +301,438 @@ fn cmd_check(ctx: &RuntimeContext) -> Result<()> {
 }
 
 // ---------------------------------------------------------------------------
-// Gate check logic
+// Watch
 // ---------------------------------------------------------------------------
 
-enum GateResult {
-    Resolved(String),
-    Pending,
-    Error(String),
+/// When the gates of one await type are polled next.
+struct Backoff {
+    delay: Duration,
+    next: Instant,
 }
 
-fn check_gate(gate: &GateRow, now: DateTime<Utc>) -> GateResult {
-    match gate.await_type.as_str() {
-        "timer" => check_timer_gate(gate, now),
-        "human" => GateResult::Pending, // Must be manually closed
-        "gh:run" => check_gh_run_gate(gate),
-        "gh:pr" => check_gh_pr_gate(gate),
-        _ => GateResult::Pending,
+fn cmd_watch(ctx: &RuntimeContext, args: &GateWatchArgs) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot watch gates in read-only mode");
     }
-}
 
-fn check_timer_gate(gate: &GateRow, now: DateTime<Utc>) -> GateResult {
-    if gate.timeout_ns <= 0 {
-        return GateResult::Pending;
+    let interval = parse_interval(&args.interval)?;
+    let max_interval = parse_interval(&args.max_interval)?.max(interval);
+    let registry = gate_registry(ctx)?;
+    let mail = mail_delegate(ctx, args.mail)?;
+
+    if !ctx.json && !ctx.quiet {
+        println!(
+            "Watching gates every {} (backing off to {}); Ctrl+C to stop.",
+            args.interval, args.max_interval
+        );
     }
 
-    let created_at = DateTime::parse_from_rfc3339(&gate.created_at)
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or(now);
+    let mut backoff: HashMap<String, Backoff> = HashMap::new();
+    loop {
+        // A failed pass (the database busy or migrating, a gate that could
+        // not be closed) is retried on the next one rather than ending the
+        // watch.
+        let started = Instant::now();
+        let pass = WatchPass {
+            registry: &registry,
+            mail: mail.as_deref(),
+            interval,
+            max_interval,
+            started,
+        };
+        match pass.run(ctx, args, &mut backoff) {
+            Ok(true) => {}
+            Ok(false) => {
+                if !ctx.json && !ctx.quiet {
+                    println!("No open gates left.");
+                }
+                return Ok(());
+            }
+            Err(e) => eprintln!("Warning: gate watch pass failed: {e:#}"),
+        }
 
-    let elapsed = now.signed_duration_since(created_at);
-    let timeout = chrono::Duration::nanoseconds(gate.timeout_ns);
+        // Sleep until the next type is due, waking at least every interval
+        // to pick up new gates.
+        let wake = backoff
+            .values()
+            .map(|b| b.next)
+            .min()
+            .unwrap_or(started + interval)
+            .min(started + interval);
+        std::thread::sleep(wake.saturating_duration_since(Instant::now()));
+    }
+}
 
-    if elapsed >= timeout {
-        GateResult::Resolved("timer expired".to_string())
-    } else {
-        GateResult::Pending
+/// One polling pass of `bd gate watch`.
+struct WatchPass<'a> {
+    registry: &'a GateRegistry,
+    mail: Option<&'a str>,
+    interval: Duration,
+    max_interval: Duration,
+    started: Instant,
+}
+
+impl WatchPass<'_> {
+    /// Checks the gates that are due and updates `backoff`. Returns `false`
+    /// when the watch should stop because no gates are left.
+    fn run(
+        &self,
+        ctx: &RuntimeContext,
+        args: &GateWatchArgs,
+        backoff: &mut HashMap<String, Backoff>,
+    ) -> Result<bool> {
+        // The database is only open, and its lock held, during a pass, so
+        // `bd migrate` can run while the watcher sleeps. Each pass picks up
+        // JSONL changes first, as other commands do before they start.
+        let locked = ctx.open_store()?;
+        let store: &dyn Storage = &*locked;
+        crate::commands::sync_cmd::auto_import_with(ctx, store);
+        let gates = load_open_gates(store)?;
+        if gates.is_empty() && args.exit_when_idle {
+            return Ok(false);
+        }
+
+        // Poll the await types that are due.
+        let due: Vec<&GateRow> = gates
+            .iter()
+            .filter(|g| {
+                backoff
+                    .get(&await_kind(self.registry, g))
+                    .is_none_or(|b| b.next <= self.started)
+            })
+            .collect();
+        let outcomes = check_gates(ctx, store, self.registry, &due, self.mail, Utc::now())?;
+
+        for outcome in &outcomes {
+            if ctx.json {
+                for event in watch_events(outcome) {
+                    println!("{}", event);
+                }
+            } else if !matches!(outcome.result, GateResult::Pending) || outcome.escalation.is_some()
+            {
+                print_outcome(ctx, outcome);
+            }
+        }
+
+        // Types that resolved something start over; the others back off.
+        let mut polled: HashMap<String, bool> = HashMap::new();
+        for outcome in &outcomes {
+            *polled.entry(outcome.kind.clone()).or_default() |=
+                matches!(outcome.result, GateResult::Resolved(_));
+        }
+        for (kind, resolved) in polled {
+            let delay = match backoff.get(&kind) {
+                Some(b) if !resolved => (b.delay * 2).min(self.max_interval),
+                _ => self.interval,
+            };
+            backoff.insert(
+                kind,
+                Backoff {
+                    delay,
+                    next: self.started + delay,
+                },
+            );
+        }
+
+        if outcomes
+            .iter()
+            .any(|o| matches!(o.result, GateResult::Resolved(_)) || o.escalation.is_some())
+        {
+            crate::commands::sync_cmd::auto_export_with(ctx, store);
+        }
+        Ok(true)
+    }
+}
+
+/// Parses a `--interval` value.
+fn parse_interval(s: &str) -> Result<Duration> {
+    let ns = parse_duration_to_ns(s)?;
+    if ns <= 0 {
+        bail!("interval must be positive: '{}'", s);
     }
+    Ok(Duration::from_nanos(ns as u64))
 }
 
-fn check_gh_run_gate(gate: &GateRow) -> GateResult {
-    if gate.await_id.is_empty() {
-        return GateResult::Error("no await_id set for gh:run gate".to_string());
+/// NDJSON events `bd gate watch --json` prints for one outcome.
+fn watch_events(outcome: &GateOutcome) -> Vec<serde_json::Value> {
+    let mut events = Vec::new();
+    match &outcome.result {
+        GateResult::Resolved(reason) => events.push(serde_json::json!({
+            "event": "closed",
+            "id": outcome.gate.id,
+            "reason": reason,
+            "notified": outcome.notified,
+        })),
+        GateResult::Error(error) => events.push(serde_json::json!({
+            "event": "error",
+            "id": outcome.gate.id,
+            "error": error,
+        })),
+        GateResult::Pending => {}
+    }
+    if let Some(escalation) = &outcome.escalation {
+        events.push(serde_json::json!({
+            "event": "escalated",
+            "id": outcome.gate.id,
+            "escalation": escalation,
+        }));
     }
+    events
+}
+
+// ---------------------------------------------------------------------------
+// Gate check logic
+// ---------------------------------------------------------------------------
+
+/// The evaluators for this project: built-ins plus `gate.evaluators`.
+///
+/// `gate.allow-cmd`, `gate.allow-http` and `gate.allow-external` only count
+/// when set on this machine, not by the project config that everyone with
+/// push access shares.
+fn gate_registry(ctx: &RuntimeContext) -> Result<GateRegistry> {
+    let layered = ctx.layered_config()?;
+    let mut config = layered.config.gate.clone();
+    config.allow_cmd &= layered.is_local("gate.allow-cmd");
+    config.allow_http &= layered.is_local("gate.allow-http");
+    config.allow_external &= layered.is_local("gate.allow-external");
+    Ok(GateRegistry::with_config(&config))
+}
 
-    // Shell out to: gh run view <await_id> --json status,conclusion
-    match std::process::Command::new("gh")
-        .args(["run", "view", &gate.await_id, "--json", "status,conclusion"])
-        .output()
-    {
-        Ok(output) => {
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return GateResult::Error(format!("gh run view failed: {}", stderr.trim()));
+fn check_gate(
+    registry: &GateRegistry,
+    store: &dyn Storage,
+    gate: &GateRow,
+    now: DateTime<Utc>,
+) -> GateResult {
+    let created_at = DateTime::parse_from_rfc3339(&gate.created_at)
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or(now);
+    let gate_ctx = GateContext {
+        registry,
+        store: Some(store),
+        gate_id: &gate.id,
+        created_at,
+        timeout: (gate.timeout_ns > 0).then(|| chrono::Duration::nanoseconds(gate.timeout_ns)),
+        now,
+    };
+    registry.evaluate(&gate.await_type, &gate.await_id, &gate_ctx)
+}
+
+/// What one check did to a gate.
+struct GateOutcome<'a> {
+    gate: &'a GateRow,
+    /// The evaluator name, which backoff is tracked by.
+    kind: String,
+    result: GateResult,
+    /// Waiters told that the gate resolved.
+    notified: Vec<String>,
+    /// The escalation wisp created for an overdue gate.
+    escalation: Option<String>,
+}
+
+/// Checks `gates`: closes the resolved ones and notifies their waiters, and
+/// escalates the ones past their timeout.
+fn check_gates<'a>(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    registry: &GateRegistry,
+    gates: &[&'a GateRow],
+    mail: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<Vec<GateOutcome<'a>>> {
+    let mut outcomes = Vec::new();
+    for &gate in gates {
+        let result = check_gate(registry, store, gate, now);
+        let mut notified = Vec::new();
+        let mut escalation = None;
+        match &result {
+            GateResult::Resolved(reason) => {
+                notified = close_gate(ctx, store, gate, reason, mail)?;
             }
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            match serde_json::from_str::<serde_json::Value>(&stdout) {
-                Ok(val) => {
-                    let status = val["status"].as_str().unwrap_or("");
-                    let conclusion = val["conclusion"].as_str().unwrap_or("");
-                    if status == "completed" && conclusion == "success" {
-                        GateResult::Resolved(format!(
-                            "GitHub Actions run {} completed successfully",
-                            gate.await_id
-                        ))
-                    } else if status == "completed" {
-                        GateResult::Error(format!(
-                            "run {} completed with conclusion: {}",
-                            gate.await_id, conclusion
-                        ))
-                    } else {
-                        GateResult::Pending
-                    }
+            GateResult::Pending | GateResult::Error(_) => {
+                if is_overdue(registry, gate, now)
+                    && !store
+                        .get_labels(&gate.id)?
+                        .iter()
+                        .any(|l| l == ESCALATED_LABEL)
+                {
+                    escalation = Some(escalate(ctx, store, gate, now)?);
                 }
-                Err(e) => GateResult::Error(format!("failed to parse gh output: {}", e)),
             }
         }
-        Err(e) => GateResult::Error(format!("failed to run gh: {}", e)),
+        outcomes.push(GateOutcome {
+            gate,
+            kind: await_kind(registry, gate),
+            result,
+            notified,
+            escalation,
+        });
+    }
+    Ok(outcomes)
+}
+
+/// Prints what happened to a gate.
+fn print_outcome(ctx: &RuntimeContext, outcome: &GateOutcome) {
+    let gate = outcome.gate;
+    match &outcome.result {
+        GateResult::Resolved(reason) => {
+            println!("Closed gate {}: {}", gate.id, reason);
+            if !outcome.notified.is_empty() {
+                println!("  Notified: {}", outcome.notified.join(", "));
+            }
+        }
+        GateResult::Pending => {
+            if !ctx.quiet {
+                println!("Gate {} ({}): pending", gate.id, gate.await_type);
+            }
+        }
+        GateResult::Error(msg) => {
+            eprintln!("Gate {} ({}): error -- {}", gate.id, gate.await_type, msg);
+        }
+    }
+    if let Some(escalation) = &outcome.escalation {
+        println!(
+            "Escalated gate {}: timed out after {} ({})",
+            gate.id,
+            format_duration_ns(gate.timeout_ns),
+            escalation
+        );
     }
 }
 
-fn check_gh_pr_gate(gate: &GateRow) -> GateResult {
-    if gate.await_id.is_empty() {
-        return GateResult::Error("no await_id set for gh:pr gate".to_string());
+/// The evaluator name for a gate's await type, or the await type itself.
+fn await_kind(registry: &GateRegistry, gate: &GateRow) -> String {
+    registry
+        .resolve(&gate.await_type, &gate.await_id)
+        .map_or_else(|| gate.await_type.clone(), |(e, _)| e.name().to_string())
+}
+
+/// Whether a gate has waited past its timeout. Timer gates resolve on their
+/// timeout instead.
+fn is_overdue(registry: &GateRegistry, gate: &GateRow, now: DateTime<Utc>) -> bool {
+    gate.timeout_ns > 0
+        && await_kind(registry, gate) != "timer"
+        && DateTime::parse_from_rfc3339(&gate.created_at).is_ok_and(|created| {
+            now.signed_duration_since(created) >= chrono::Duration::nanoseconds(gate.timeout_ns)
+        })
+}
+
+/// The mail delegate when `--mail` is given.
+fn mail_delegate(ctx: &RuntimeContext, mail: bool) -> Result<Option<String>> {
+    if !mail {
+        return Ok(None);
+    }
+    match mail::delegate(ctx) {
+        Some(delegate) => Ok(Some(delegate)),
+        None => bail!("--mail needs a mail delegate (bd config set mail.delegate '<command>')"),
     }
+}
 
-    // Shell out to: gh pr view <await_id> --json state,merged
-    match std::process::Command::new("gh")
-        .args(["pr", "view", &gate.await_id, "--json", "state,merged"])
-        .output()
-    {
-        Ok(output) => {
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                return GateResult::Error(format!("gh pr view failed: {}", stderr.trim()));
-            }
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            match serde_json::from_str::<serde_json::Value>(&stdout) {
-                Ok(val) => {
-                    let state = val["state"].as_str().unwrap_or("");
-                    let merged = val["merged"].as_bool().unwrap_or(false);
-                    if state == "MERGED" || merged {
-                        GateResult::Resolved(format!("GitHub PR {} merged", gate.await_id))
-                    } else if state == "CLOSED" {
-                        GateResult::Error(format!(
-                            "PR {} was closed without merging",
-                            gate.await_id
-                        ))
-                    } else {
-                        GateResult::Pending
-                    }
-                }
-                Err(e) => GateResult::Error(format!("failed to parse gh output: {}", e)),
+/// Closes a resolved gate and tells its waiters: a comment on each waiter
+/// that is an issue and, with a mail delegate, a message to every waiter.
+/// Returns the waiters reached.
+///
+/// The comments are written in the same transaction as the close, so a
+/// gate whose waiters could not be told stays open and is retried.
+fn close_gate(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    gate: &GateRow,
+    reason: &str,
+    mail: Option<&str>,
+) -> Result<Vec<String>> {
+    let waiters: Vec<String> = serde_json::from_str(&gate.waiters_json).unwrap_or_default();
+    let message = format!("Gate {} ({}) resolved: {}", gate.id, gate.title, reason);
+    let mut issues = Vec::new();
+    for waiter in &waiters {
+        match store.get_issue(waiter) {
+            Ok(_) => issues.push(waiter.as_str()),
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    store.run_in_transaction(&|tx| {
+        tx.close_issue(&gate.id, reason, &ctx.actor, "")?;
+        for waiter in &issues {
+            tx.add_comment(waiter, &ctx.actor, &message)?;
+        }
+        Ok(())
+    })?;
+
+    let mut notified = Vec::new();
+    for waiter in &waiters {
+        let mut reached = issues.contains(&waiter.as_str());
+        if let Some(delegate) = mail {
+            let subject = format!("Gate {} resolved", gate.id);
+            match mail::send(delegate, waiter, &subject, &message) {
+                Ok(()) => reached = true,
+                Err(e) => eprintln!("Warning: failed to mail {}: {:#}", waiter, e),
             }
         }
-        Err(e) => GateResult::Error(format!("failed to run gh: {}", e)),
+        if reached {
+            notified.push(waiter.clone());
+        }
     }
+    Ok(notified)
+}
+
+/// Escalates an overdue gate: raises it to P0 and files an `escalation`
+/// wisp related to it. Returns the wisp's ID.
+fn escalate(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    gate: &GateRow,
+    now: DateTime<Utc>,
+) -> Result<String> {
+    let waiters: Vec<String> = serde_json::from_str(&gate.waiters_json).unwrap_or_default();
+    let title = format!("Gate {} timed out: {}", gate.id, gate.title);
+    let mut description = format!(
+        "Gate {} has waited on {} for longer than its {} timeout.",
+        gate.id,
+        gate.await_type,
+        format_duration_ns(gate.timeout_ns)
+    );
+    if !waiters.is_empty() {
+        description.push_str(&format!("\n\nWaiters: {}", waiters.join(", ")));
+    }
+    let prefix = issue_prefix(ctx, store);
+    let id = generate_issue_id(store, &prefix, &title, &description, &ctx.actor, now)?;
+
+    let wisp = Issue {
+        id: id.clone(),
+        title,
+        description,
+        status: Status::Open,
+        priority: 0,
+        issue_type: IssueType::Task,
+        ephemeral: true,
+        wisp_type: WispType::Escalation,
+        created_by: ctx.actor.clone(),
+        created_at: now,
+        updated_at: now,
+        ..Issue::default()
+    };
+    let link = Dependency {
+        issue_id: id.clone(),
+        depends_on_id: gate.id.clone(),
+        dep_type: DependencyType::Related,
+        created_at: now,
+        created_by: ctx.actor.clone(),
+        metadata: String::new(),
+        thread_id: String::new(),
+    };
+    let raise = IssueUpdates {
+        priority: Some(0),
+        ..Default::default()
+    };
+    store.run_in_transaction(&|tx| {
+        tx.create_issue(&wisp, &ctx.actor)?;
+        tx.add_label(&id, "ephemeral", &ctx.actor)?;
+        tx.add_dependency(&link, &ctx.actor)?;
+        tx.update_issue(&gate.id, &raise, &ctx.actor)?;
+        tx.add_label(&gate.id, ESCALATED_LABEL, &ctx.actor)
+    })?;
+    Ok(id)
 }
 
 // ---------------------------------------------------------------------------
@@ -536,6 +751,21 @@ struct GateRow {
     created_at: String,
 }
 
+impl From<&Issue> for GateRow {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            status: issue.status.as_str().to_string(),
+            await_type: issue.await_type.clone(),
+            await_id: issue.await_id.clone(),
+            timeout_ns: issue.timeout.map_or(0, |d| d.as_nanos() as i64),
+            waiters_json: serde_json::to_string(&issue.waiters).unwrap_or_else(|_| "[]".into()),
+            created_at: issue.created_at.to_rfc3339(),
+        }
+    }
+}
+
 fn gate_to_json(gate: &GateRow) -> serde_json::Value {
     let waiters: Vec<String> = serde_json::from_str(&gate.waiters_json).unwrap_or_default();
     serde_json::json!({
@@ -556,80 +786,42 @@ fn gate_to_json(gate: &GateRow) -> serde_json::Value {
 // ---------------------------------------------------------------------------
 
 /// Load a gate issue by ID.
-fn load_gate(conn: &rusqlite::Connection, id: &str) -> Result<GateRow> {
-    conn.query_row(
-        "SELECT id, title, status, await_type, await_id, timeout_ns, waiters, created_at \
-         FROM issues WHERE id = ?1",
-        rusqlite::params![id],
-        |row| {
-            Ok(GateRow {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                status: row.get::<_, String>(2).unwrap_or_default(),
-                await_type: row.get::<_, String>(3).unwrap_or_default(),
-                await_id: row.get::<_, String>(4).unwrap_or_default(),
-                timeout_ns: row.get::<_, i64>(5).unwrap_or(0),
-                waiters_json: row.get::<_, String>(6).unwrap_or_else(|_| "[]".to_string()),
-                created_at: row.get::<_, String>(7).unwrap_or_default(),
-            })
-        },
-    )
-    .with_context(|| format!("gate '{}' not found", id))
+fn load_gate(store: &dyn Storage, id: &str) -> Result<GateRow> {
+    match store.get_issue(id) {
+        Ok(issue) => Ok(GateRow::from(&issue)),
+        Err(e) if e.is_not_found() => bail!("gate '{}' not found", id),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Load all gates that are not closed yet, oldest first.
+fn load_open_gates(store: &dyn Storage) -> Result<Vec<GateRow>> {
+    let filter = IssueFilter {
+        issue_type: Some(IssueType::from("gate")),
+        exclude_status: vec![Status::Closed],
+        ..Default::default()
+    };
+    let mut issues = store.search_issues("", &filter)?;
+    issues.sort_by_key(|i| i.created_at);
+    Ok(issues.iter().map(GateRow::from).collect())
 }
 
 /// Parse a human-readable duration string into nanoseconds.
 ///
-/// Supports: `30s`, `5m`, `2h`, `1d`, `30m`, `1h30m`, etc.
+/// Supports: `30s`, `5m`, `2h`, `1d`, `2w`, `1h30m`, etc.; a bare number is
+/// seconds.
 fn parse_duration_to_ns(s: &str) -> Result<i64> {
-    let s = s.trim();
-    if s.is_empty() {
+    if s.trim().is_empty() {
         return Ok(0);
     }
 
-    let mut total_ns: i64 = 0;
-    let mut current_num = String::new();
-
-    for ch in s.chars() {
-        if ch.is_ascii_digit() {
-            current_num.push(ch);
-        } else {
-            if current_num.is_empty() {
-                bail!("invalid duration '{}': expected number before unit", s);
-            }
-            let num: i64 = current_num
-                .parse()
-                .with_context(|| format!("invalid number in duration '{}'", s))?;
-            current_num.clear();
-
-            let multiplier: i64 = match ch {
-                's' => 1_000_000_000,                // seconds
-                'm' => 60 * 1_000_000_000,           // minutes
-                'h' => 60 * 60 * 1_000_000_000,      // hours
-                'd' => 24 * 60 * 60 * 1_000_000_000, // days
-                _ => bail!(
-                    "invalid duration unit '{}' in '{}' (valid: s, m, h, d)",
-                    ch,
-                    s
-                ),
-            };
-
-            total_ns += num * multiplier;
-        }
-    }
-
-    // If there's a trailing number without unit, treat as seconds
-    if !current_num.is_empty() {
-        let num: i64 = current_num
-            .parse()
-            .with_context(|| format!("invalid number in duration '{}'", s))?;
-        total_ns += num * 1_000_000_000;
-    }
-
-    if total_ns == 0 {
+    let duration = parse_duration_in(s, Unit::Second)?;
+    if duration <= chrono::Duration::zero() {
         bail!("invalid duration '{}': parsed to zero", s);
     }
-
-    Ok(total_ns)
+    duration
+        .num_nanoseconds()
+        .with_context(|| format!("duration '{}' is too long", s))
 }
 
 /// Format nanoseconds as human-readable duration string.
@@ -637,32 +829,7 @@ fn format_duration_ns(ns: i64) -> String {
     if ns <= 0 {
         return "none".to_string();
     }
-
-    let total_secs = ns / 1_000_000_000;
-    let days = total_secs / 86400;
-    let hours = (total_secs % 86400) / 3600;
-    let minutes = (total_secs % 3600) / 60;
-    let secs = total_secs % 60;
-
-    let mut parts = Vec::new();
-    if days > 0 {
-        parts.push(format!("{}d", days));
-    }
-    if hours > 0 {
-        parts.push(format!("{}h", hours));
-    }
-    if minutes > 0 {
-        parts.push(format!("{}m", minutes));
-    }
-    if secs > 0 {
-        parts.push(format!("{}s", secs));
-    }
-
-    if parts.is_empty() {
-        "0s".to_string()
-    } else {
-        parts.join("")
-    }
+    format_duration(chrono::Duration::nanoseconds(ns))
 }
 
 /// Truncate a string to max length, appending "..." if truncated.
@@ -673,29 +840,3 @@ fn truncate(s: &str, max: usize) -> String {
         format!("{}...", &s[..max.saturating_sub(3)])
     }
 }
-
-/// Open the beads database (read-only or read-write).
-fn open_db(ctx: &RuntimeContext, writable: bool) -> Result<rusqlite::Connection> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    if writable {
-        rusqlite::Connection::open(&db_path)
-            .with_context(|| format!("failed to open database: {}", db_path.display()))
-    } else {
-        rusqlite::Connection::open_with_flags(
-            &db_path,
-            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-        )
-        .with_context(|| format!("failed to open database: {}", db_path.display()))
-    }
-}
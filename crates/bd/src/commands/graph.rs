@@ -4,13 +4,22 @@
 //! - Default compact tree (layers with status symbols)
 //! - Graphviz DOT (`--dot`)
 //! - JSON (`--json` global flag)
+//!
+//! Issues skipped by a conditional-blocks blocker that succeeded are marked
+//! as such.
 
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::io::{self, Write};
 
 use anyhow::{Context, Result, bail};
 
+use beads_core::dependency::is_condition_skipped;
+use beads_core::enums::{DependencyType, Status};
+use beads_core::issue::Issue;
+use beads_storage::Storage;
+
 use crate::cli::GraphArgs;
+use crate::commands::list::listing_filter;
 use crate::context::RuntimeContext;
 use crate::output::{output_json, status_symbol};
 
@@ -23,8 +32,25 @@ struct Node {
     id: String,
     title: String,
     status: String,
+    close_reason: String,
     priority: i32,
     layer: Option<usize>,
+    /// Open, but its conditional-blocks blocker succeeded.
+    skipped: bool,
+}
+
+impl From<&Issue> for Node {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            status: issue.status.as_str().to_string(),
+            close_reason: issue.close_reason.clone(),
+            priority: issue.priority,
+            layer: None,
+            skipped: false,
+        }
+    }
 }
 
 /// An edge in the dependency graph.
@@ -45,9 +71,6 @@ struct DepGraph {
     reverse: HashMap<String, Vec<String>>,
 }
 
-// Blocking dependency types that form the DAG structure.
-const BLOCKING_TYPES: &[&str] = &["blocks", "parent-child", "conditional-blocks", "waits-for"];
-
 // ---------------------------------------------------------------------------
 // Entry point
 // ---------------------------------------------------------------------------
@@ -58,29 +81,13 @@ pub fn run(ctx: &RuntimeContext, args: &GraphArgs) -> Result<()> {
         bail!("specify an issue ID or use --all to graph all open issues");
     }
 
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    let store = ctx.store()?;
 
     if args.all {
-        run_all(ctx, args, &conn)
+        run_all(ctx, args, store)
     } else {
         let id = args.id.as_ref().unwrap();
-        let graph = build_subgraph(&conn, id)?;
+        let graph = build_subgraph(store, id)?;
         render(ctx, args, &graph)
     }
 }
@@ -89,17 +96,12 @@ pub fn run(ctx: &RuntimeContext, args: &GraphArgs) -> Result<()> {
 // --all: discover connected components and render each
 // ---------------------------------------------------------------------------
 
-fn run_all(ctx: &RuntimeContext, args: &GraphArgs, conn: &rusqlite::Connection) -> Result<()> {
+fn run_all(ctx: &RuntimeContext, args: &GraphArgs, store: &dyn Storage) -> Result<()> {
     // Load all non-closed, non-template, non-gate issues.
-    let mut stmt = conn.prepare(
-        "SELECT id, title, status, priority FROM issues \
-         WHERE status != 'closed' AND is_template = 0 AND issue_type != 'gate'",
-    )?;
-    let issues: Vec<(String, String, String, i32)> = stmt
-        .query_map([], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        })?
-        .filter_map(|r| r.ok())
+    let issues: Vec<Issue> = store
+        .search_issues("", &listing_filter())?
+        .into_iter()
+        .filter(|issue| issue.status != Status::Closed)
         .collect();
 
     if issues.is_empty() {
@@ -112,36 +114,22 @@ fn run_all(ctx: &RuntimeContext, args: &GraphArgs, conn: &rusqlite::Connection)
     }
 
     // Load all blocking edges.
-    let blocking_filter = BLOCKING_TYPES
-        .iter()
-        .map(|t| format!("'{}'", t))
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    let query = format!(
-        "SELECT issue_id, depends_on_id, type FROM dependencies WHERE type IN ({})",
-        blocking_filter
-    );
-    let mut edge_stmt = conn.prepare(&query)?;
-    let all_edges: Vec<(String, String, String)> = edge_stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
-        .filter_map(|r| r.ok())
+    let all_edges: Vec<(String, String, String)> = store
+        .get_all_dependency_records()?
+        .into_values()
+        .flatten()
+        .filter(|dep| dep.dep_type.affects_ready_work())
+        .map(|dep| {
+            let dep_type = dep.dep_type.as_str().to_string();
+            (dep.issue_id, dep.depends_on_id, dep_type)
+        })
         .collect();
 
     // Build full node map (include nodes mentioned in edges too).
-    let mut node_map: HashMap<String, Node> = HashMap::new();
-    for (id, title, status, priority) in &issues {
-        node_map.insert(
-            id.clone(),
-            Node {
-                id: id.clone(),
-                title: title.clone(),
-                status: status.clone(),
-                priority: *priority,
-                layer: None,
-            },
-        );
-    }
+    let mut node_map: HashMap<String, Node> = issues
+        .iter()
+        .map(|issue| (issue.id.clone(), Node::from(issue)))
+        .collect();
 
     // For nodes referenced by edges but not in our issue set, load them.
     let mut missing: HashSet<String> = HashSet::new();
@@ -154,27 +142,8 @@ fn run_all(ctx: &RuntimeContext, args: &GraphArgs, conn: &rusqlite::Connection)
         }
     }
     for mid in &missing {
-        if let Ok((title, status, priority)) = conn.query_row(
-            "SELECT title, status, priority FROM issues WHERE id = ?1",
-            rusqlite::params![mid],
-            |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, i32>(2)?,
-                ))
-            },
-        ) {
-            node_map.insert(
-                mid.clone(),
-                Node {
-                    id: mid.clone(),
-                    title,
-                    status,
-                    priority,
-                    layer: None,
-                },
-            );
+        if let Ok(issue) = store.get_issue(mid) {
+            node_map.insert(mid.clone(), Node::from(&issue));
         }
     }
 
@@ -267,38 +236,21 @@ fn run_all(ctx: &RuntimeContext, args: &GraphArgs, conn: &rusqlite::Connection)
 // Build subgraph for a single issue (BFS both directions)
 // ---------------------------------------------------------------------------
 
-fn build_subgraph(conn: &rusqlite::Connection, root_id: &str) -> Result<DepGraph> {
+fn build_subgraph(store: &dyn Storage, root_id: &str) -> Result<DepGraph> {
     // Verify the root issue exists.
-    let (title, status, priority): (String, String, i32) = conn
-        .query_row(
-            "SELECT title, status, priority FROM issues WHERE id = ?1",
-            rusqlite::params![root_id],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
-        )
-        .with_context(|| format!("issue '{}' not found", root_id))?;
+    let root = match store.get_issue(root_id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", root_id),
+        Err(e) => return Err(e.into()),
+    };
 
     let mut nodes: HashMap<String, Node> = HashMap::new();
-    nodes.insert(
-        root_id.to_string(),
-        Node {
-            id: root_id.to_string(),
-            title,
-            status,
-            priority,
-            layer: None,
-        },
-    );
+    nodes.insert(root_id.to_string(), Node::from(&root));
 
     let mut all_edges: Vec<Edge> = Vec::new();
     let mut forward: HashMap<String, Vec<String>> = HashMap::new();
     let mut reverse: HashMap<String, Vec<String>> = HashMap::new();
 
-    let blocking_filter = BLOCKING_TYPES
-        .iter()
-        .map(|t| format!("'{}'", t))
-        .collect::<Vec<_>>()
-        .join(", ");
-
     // BFS: explore in both directions to find full connected component.
     let mut queue: VecDeque<String> = VecDeque::new();
     let mut visited: HashSet<String> = HashSet::new();
@@ -307,19 +259,14 @@ fn build_subgraph(conn: &rusqlite::Connection, root_id: &str) -> Result<DepGraph
 
     while let Some(current) = queue.pop_front() {
         // Forward: current depends on X.
-        let fwd_query = format!(
-            "SELECT depends_on_id, type FROM dependencies WHERE issue_id = ?1 AND type IN ({})",
-            blocking_filter
-        );
-        let mut fwd_stmt = conn.prepare(&fwd_query)?;
-        let fwd_deps: Vec<(String, String)> = fwd_stmt
-            .query_map(rusqlite::params![&current], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        let fwd_deps = store.get_dependencies_with_metadata(&current)?;
 
-        for (dep_id, dep_type) in fwd_deps {
+        for entry in fwd_deps {
+            if !entry.dependency.dep_type.affects_ready_work() {
+                continue;
+            }
+            let dep_id = entry.issue.id.clone();
+            let dep_type = entry.dependency.dep_type.as_str().to_string();
             forward
                 .entry(current.clone())
                 .or_default()
@@ -337,47 +284,19 @@ fn build_subgraph(conn: &rusqlite::Connection, root_id: &str) -> Result<DepGraph
             if !visited.contains(&dep_id) {
                 visited.insert(dep_id.clone());
                 queue.push_back(dep_id.clone());
-                // Load node info.
-                if let Ok((t, s, p)) = conn.query_row(
-                    "SELECT title, status, priority FROM issues WHERE id = ?1",
-                    rusqlite::params![&dep_id],
-                    |row| {
-                        Ok((
-                            row.get::<_, String>(0)?,
-                            row.get::<_, String>(1)?,
-                            row.get::<_, i32>(2)?,
-                        ))
-                    },
-                ) {
-                    let node_id = dep_id.clone();
-                    nodes.insert(
-                        dep_id,
-                        Node {
-                            id: node_id,
-                            title: t,
-                            status: s,
-                            priority: p,
-                            layer: None,
-                        },
-                    );
-                }
+                nodes.insert(dep_id, Node::from(&entry.issue));
             }
         }
 
         // Reverse: X depends on current.
-        let rev_query = format!(
-            "SELECT issue_id, type FROM dependencies WHERE depends_on_id = ?1 AND type IN ({})",
-            blocking_filter
-        );
-        let mut rev_stmt = conn.prepare(&rev_query)?;
-        let rev_deps: Vec<(String, String)> = rev_stmt
-            .query_map(rusqlite::params![&current], |row| {
-                Ok((row.get(0)?, row.get(1)?))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
+        let rev_deps = store.get_dependents_with_metadata(&current)?;
 
-        for (dep_id, dep_type) in rev_deps {
+        for entry in rev_deps {
+            if !entry.dependency.dep_type.affects_ready_work() {
+                continue;
+            }
+            let dep_id = entry.issue.id.clone();
+            let dep_type = entry.dependency.dep_type.as_str().to_string();
             // dep_id depends on current -- add edge dep_id -> current.
             forward
                 .entry(dep_id.clone())
@@ -396,29 +315,7 @@ fn build_subgraph(conn: &rusqlite::Connection, root_id: &str) -> Result<DepGraph
             if !visited.contains(&dep_id) {
                 visited.insert(dep_id.clone());
                 queue.push_back(dep_id.clone());
-                if let Ok((t, s, p)) = conn.query_row(
-                    "SELECT title, status, priority FROM issues WHERE id = ?1",
-                    rusqlite::params![&dep_id],
-                    |row| {
-                        Ok((
-                            row.get::<_, String>(0)?,
-                            row.get::<_, String>(1)?,
-                            row.get::<_, i32>(2)?,
-                        ))
-                    },
-                ) {
-                    let node_id = dep_id.clone();
-                    nodes.insert(
-                        dep_id,
-                        Node {
-                            id: node_id,
-                            title: t,
-                            status: s,
-                            priority: p,
-                            layer: None,
-                        },
-                    );
-                }
+                nodes.insert(dep_id, Node::from(&entry.issue));
             }
         }
     }
@@ -450,6 +347,7 @@ fn build_subgraph(conn: &rusqlite::Connection, root_id: &str) -> Result<DepGraph
         reverse,
     };
 
+    mark_skipped(&mut graph);
     assign_layers(&mut graph);
     Ok(graph)
 }
@@ -472,8 +370,10 @@ fn build_graph_from_sets(
                     id: n.id.clone(),
                     title: n.title.clone(),
                     status: n.status.clone(),
+                    close_reason: n.close_reason.clone(),
                     priority: n.priority,
                     layer: None,
+                    skipped: false,
                 },
             );
         }
@@ -501,10 +401,51 @@ fn build_graph_from_sets(
         forward,
         reverse,
     };
+    mark_skipped(&mut graph);
     assign_layers(&mut graph);
     graph
 }
 
+// ---------------------------------------------------------------------------
+// Conditional-blocks outcomes
+// ---------------------------------------------------------------------------
+
+/// Marks open nodes whose conditional-blocks blocker succeeded, or is itself
+/// skipped.
+fn mark_skipped(graph: &mut DepGraph) {
+    let conditional = DependencyType::ConditionalBlocks.as_str();
+    loop {
+        let newly: Vec<String> = graph
+            .edges
+            .iter()
+            .filter(|e| e.dep_type == conditional)
+            .filter(|e| {
+                let (Some(node), Some(blocker)) =
+                    (graph.nodes.get(&e.from), graph.nodes.get(&e.to))
+                else {
+                    return false;
+                };
+                node.status != Status::Closed.as_str()
+                    && !node.skipped
+                    && (blocker.skipped
+                        || is_condition_skipped(
+                            &Status::from(blocker.status.as_str()),
+                            &blocker.close_reason,
+                        ))
+            })
+            .map(|e| e.from.clone())
+            .collect();
+        if newly.is_empty() {
+            break;
+        }
+        for id in newly {
+            if let Some(node) = graph.nodes.get_mut(&id) {
+                node.skipped = true;
+            }
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Layer assignment: longest path from sources
 // ---------------------------------------------------------------------------
@@ -661,11 +602,12 @@ fn render_compact<W: Write>(graph: &DepGraph, w: &mut W) {
             } else {
                 format!(" (needs: {})", needs.join(", "))
             };
+            let skipped_str = if node.skipped { " [skipped]" } else { "" };
 
             let _ = writeln!(
                 w,
-                "  {}  {}  P{}  {}{}",
-                sym, node.id, node.priority, node.title, needs_str
+                "  {}  {}  P{}  {}{}{}",
+                sym, node.id, node.priority, node.title, needs_str, skipped_str
             );
         }
     }
@@ -687,17 +629,28 @@ fn render_dot<W: Write>(graph: &DepGraph, w: &mut W) {
         let node = &graph.nodes[*id];
         // Escape quotes in title.
         let escaped_title = node.title.replace('\\', "\\\\").replace('"', "\\\"");
+        let (status, style) = if node.skipped {
+            ("skipped", " style=dashed")
+        } else {
+            (node.status.as_str(), "")
+        };
         let _ = writeln!(
             w,
-            "  \"{}\" [label=\"{}\\n{}\\nP{} {}\"];",
-            id, id, escaped_title, node.priority, node.status
+            "  \"{}\" [label=\"{}\\n{}\\nP{} {}\"{}];",
+            id, id, escaped_title, node.priority, status, style
         );
     }
 
     // Emit edges: from depends on to, so arrow goes to -> from
-    // (showing "blocks" direction: to blocks from).
+    // (showing "blocks" direction: to blocks from). Conditional edges are
+    // dashed.
     for edge in &graph.edges {
-        let _ = writeln!(w, "  \"{}\" -> \"{}\";", edge.to, edge.from);
+        let style = if edge.dep_type == DependencyType::ConditionalBlocks.as_str() {
+            " [style=dashed]"
+        } else {
+            ""
+        };
+        let _ = writeln!(w, "  \"{}\" -> \"{}\"{};", edge.to, edge.from, style);
     }
 
     let _ = writeln!(w, "}}");
@@ -725,6 +678,7 @@ fn graph_to_json(graph: &DepGraph) -> serde_json::Value {
                 "layer": n.layer.unwrap_or(0),
                 "status": n.status,
                 "priority": n.priority,
+                "skipped": n.skipped,
             })
         })
         .collect();
@@ -1,6 +1,6 @@
 //! `bd history` -- show event history for an issue.
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Result, bail};
 
 use crate::cli::HistoryArgs;
 use crate::context::RuntimeContext;
@@ -8,54 +8,28 @@ use crate::output::output_json;
 
 /// Execute the `bd history` command.
 pub fn run(ctx: &RuntimeContext, args: &HistoryArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    let store = ctx.store()?;
 
     // Verify issue exists
-    let exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-            rusqlite::params![&args.id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-    if !exists {
-        bail!("issue '{}' not found", args.id);
-    }
-
-    let mut stmt = conn.prepare(
-        "SELECT event_type, actor, old_value, new_value, created_at \
-         FROM events \
-         WHERE issue_id = ?1 \
-         ORDER BY created_at ASC",
-    )?;
+    match store.get_issue(&args.id) {
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", args.id),
+        other => other?,
+    };
 
-    let events: Vec<(String, String, String, String, String)> = stmt
-        .query_map(rusqlite::params![&args.id], |row| {
-            Ok((
-                row.get::<_, String>(0).unwrap_or_default(),
-                row.get::<_, String>(1).unwrap_or_default(),
-                row.get::<_, String>(2).unwrap_or_default(),
-                row.get::<_, String>(3).unwrap_or_default(),
-                row.get::<_, String>(4).unwrap_or_default(),
-            ))
-        })?
-        .filter_map(|r| r.ok())
+    // Oldest first
+    let events: Vec<(String, String, String, String, String)> = store
+        .get_events(&args.id, i32::MAX)?
+        .into_iter()
+        .rev()
+        .map(|e| {
+            (
+                e.event_type.as_str().to_string(),
+                e.actor,
+                e.old_value.unwrap_or_default(),
+                e.new_value.unwrap_or_default(),
+                e.created_at.to_rfc3339(),
+            )
+        })
         .collect();
 
     if ctx.json {
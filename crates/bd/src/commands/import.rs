@@ -1,12 +1,445 @@
-//! `bd import` -- import issues from external sources (stub).
+//! `bd import` -- import issues from a JSONL file.
+//!
+//! Issues are upserted by ID inside a single transaction. A record whose
+//! content hash, labels, dependencies and comments already match the database
+//! is left alone; changed records are overwritten (keeping their original
+//! timestamps) and unknown IDs are created. Relations are applied in a second
+//! pass so that dependencies may point at issues later in the file.
 
-use anyhow::Result;
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+
+use beads_core::comment::Comment;
+use beads_core::content_hash::compute_content_hash;
+use beads_core::dependency::Dependency;
+use beads_core::issue::Issue;
+use beads_core::jsonl::read_jsonl;
+use beads_core::validation::validate_for_import;
+use beads_lockfile::LockMode;
+use beads_storage::{Storage, Transaction};
 
 use crate::cli::ImportArgs;
+use crate::commands::sync_cmd::record_jsonl_state;
 use crate::context::RuntimeContext;
+use crate::locks;
+use crate::output::output_json;
+
+/// Outcome of importing a single record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+/// A record that could not be imported.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportFailure {
+    /// Issue ID, if the record got far enough to have one.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub id: String,
+    /// Human-readable reason.
+    pub error: String,
+}
+
+/// Summary of an import run.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub failed: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ImportFailure>,
+}
+
+impl ImportSummary {
+    fn record(&mut self, outcome: ImportOutcome) {
+        match outcome {
+            ImportOutcome::Created => self.created += 1,
+            ImportOutcome::Updated => self.updated += 1,
+            ImportOutcome::Unchanged => self.unchanged += 1,
+        }
+    }
+
+    fn fail(&mut self, id: &str, error: impl ToString) {
+        self.failed += 1;
+        self.errors.push(ImportFailure {
+            id: id.to_string(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Execute the `bd import` command.
+pub fn run(ctx: &RuntimeContext, args: &ImportArgs) -> Result<()> {
+    if ctx.readonly && !args.dry_run {
+        bail!("cannot import issues in read-only mode");
+    }
+    if !matches!(args.format.as_str(), "json" | "jsonl") {
+        bail!(
+            "unsupported import format '{}' (only json/jsonl is supported)",
+            args.format
+        );
+    }
+
+    let store = ctx.store()?;
+    let source = match &args.source {
+        Some(s) => PathBuf::from(s),
+        None => ctx
+            .resolve_db_path()
+            .context("no beads database found. Run 'bd init' to create one.")?
+            .join("issues.jsonl"),
+    };
+
+    // Keeps exports from replacing the file until its state is recorded.
+    let _lock = locks::lock_file(&source, LockMode::Shared)?;
+    let file =
+        File::open(&source).with_context(|| format!("failed to open {}", source.display()))?;
 
-/// Execute the `bd import` command (stub).
-pub fn run(_ctx: &RuntimeContext, _args: &ImportArgs) -> Result<()> {
-    println!("bd import: not yet implemented");
+    // Parse everything up front; malformed lines are reported, not fatal.
+    let mut summary = ImportSummary::default();
+    let mut issues = Vec::new();
+    for record in read_jsonl(BufReader::new(file)) {
+        match record {
+            Ok(issue) => issues.push(issue),
+            Err(e) => summary.fail("", e),
+        }
+    }
+
+    let result = import_issues(store, &issues, &ctx.actor, args.dry_run)?;
+    summary.created = result.created;
+    summary.updated = result.updated;
+    summary.unchanged = result.unchanged;
+    summary.failed += result.failed;
+    summary.errors.extend(result.errors);
+    if args.source.is_none() && !args.dry_run {
+        record_jsonl_state(store, &source)?;
+    }
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "source": source.display().to_string(),
+            "dry_run": args.dry_run,
+            "created": summary.created,
+            "updated": summary.updated,
+            "unchanged": summary.unchanged,
+            "failed": summary.failed,
+            "errors": summary.errors,
+        }));
+    } else {
+        for failure in &summary.errors {
+            if failure.id.is_empty() {
+                eprintln!("  failed: {}", failure.error);
+            } else {
+                eprintln!("  failed: {}: {}", failure.id, failure.error);
+            }
+        }
+        if !ctx.quiet || summary.failed > 0 {
+            let verb = if args.dry_run {
+                "Would import"
+            } else {
+                "Imported"
+            };
+            println!(
+                "{} {}: {} created, {} updated, {} unchanged, {} failed",
+                verb,
+                source.display(),
+                summary.created,
+                summary.updated,
+                summary.unchanged,
+                summary.failed
+            );
+        }
+    }
+
+    if summary.failed > 0 {
+        std::process::exit(1);
+    }
     Ok(())
 }
+
+/// Imports `issues` into `store` in a single transaction.
+///
+/// Per-record problems (validation errors, dangling dependencies, cycles) are
+/// collected in the summary; only storage-level failures abort the import.
+/// With `dry_run`, the database is read but never written.
+pub fn import_issues(
+    store: &dyn Storage,
+    issues: &[Issue],
+    actor: &str,
+    dry_run: bool,
+) -> Result<ImportSummary> {
+    let summary = RefCell::new(ImportSummary::default());
+    store
+        .run_in_transaction(&|tx| {
+            *summary.borrow_mut() = import_in_tx(tx, issues, actor, dry_run)?;
+            Ok(())
+        })
+        .context("import failed")?;
+    Ok(summary.into_inner())
+}
+
+/// Two-pass import on an open transaction: issue rows first, then labels,
+/// dependencies and comments.
+fn import_in_tx(
+    tx: &dyn Transaction,
+    issues: &[Issue],
+    actor: &str,
+    dry_run: bool,
+) -> beads_storage::error::Result<ImportSummary> {
+    let custom_statuses = match tx.get_config("status.custom") {
+        Ok(v) => v,
+        Err(e) if e.is_not_found() => String::new(),
+        Err(e) => return Err(e),
+    };
+    let custom_statuses: Vec<&str> = custom_statuses
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let mut summary = ImportSummary::default();
+    let mut pending: Vec<(Issue, ImportOutcome)> = Vec::new();
+
+    for raw in issues {
+        let mut issue = raw.clone();
+        issue.set_defaults();
+
+        if issue.id.is_empty() {
+            summary.fail("", format!("record '{}' has no id", issue.title));
+            continue;
+        }
+        if let Err(e) = validate_for_import(&issue, &custom_statuses) {
+            summary.fail(&issue.id, e);
+            continue;
+        }
+
+        let existing = match tx.get_issue(&issue.id) {
+            Ok(existing) => Some(existing),
+            Err(e) if e.is_not_found() => None,
+            Err(e) => return Err(e),
+        };
+
+        let (outcome, row_changed) = match existing {
+            None => (ImportOutcome::Created, true),
+            Some(ref current) => {
+                let row_changed = compute_content_hash(current) != compute_content_hash(&issue);
+                if row_changed || !relations_match(tx, &issue)? {
+                    (ImportOutcome::Updated, row_changed)
+                } else {
+                    (ImportOutcome::Unchanged, false)
+                }
+            }
+        };
+
+        if !dry_run && row_changed {
+            if let Err(e) = tx.import_issue(&issue, actor) {
+                summary.fail(&issue.id, e);
+                continue;
+            }
+        }
+        pending.push((issue, outcome));
+    }
+
+    // Second pass: every issue row now exists, so dependency targets resolve.
+    for (issue, outcome) in pending {
+        if !dry_run && outcome != ImportOutcome::Unchanged {
+            if let Err(e) = apply_relations(tx, &issue, actor) {
+                summary.fail(&issue.id, e);
+                continue;
+            }
+        }
+        summary.record(outcome);
+    }
+
+    Ok(summary)
+}
+
+/// Key identifying a dependency edge for comparison.
+fn dep_key(dep: &Dependency) -> (String, String, String) {
+    (
+        dep.depends_on_id.clone(),
+        dep.dep_type.as_str().to_string(),
+        dep.metadata.clone(),
+    )
+}
+
+/// Key identifying a comment for comparison (millisecond precision, as stored).
+fn comment_key(c: &Comment) -> (String, String, i64) {
+    (
+        c.author.clone(),
+        c.text.clone(),
+        c.created_at.timestamp_millis(),
+    )
+}
+
+/// Returns `true` if the stored labels, dependencies and comments already
+/// reflect the incoming record.
+fn relations_match(tx: &dyn Transaction, issue: &Issue) -> beads_storage::error::Result<bool> {
+    let current_labels: BTreeSet<String> = tx.get_labels(&issue.id)?.into_iter().collect();
+    let wanted_labels: BTreeSet<String> = issue.labels.iter().cloned().collect();
+    if current_labels != wanted_labels {
+        return Ok(false);
+    }
+
+    let current_deps: BTreeSet<_> = tx
+        .get_dependency_records(&issue.id)?
+        .iter()
+        .map(dep_key)
+        .collect();
+    let wanted_deps: BTreeSet<_> = issue.dependencies.iter().map(dep_key).collect();
+    if current_deps != wanted_deps {
+        return Ok(false);
+    }
+
+    let current_comments: BTreeSet<_> = tx
+        .get_comments(&issue.id)?
+        .iter()
+        .map(comment_key)
+        .collect();
+    Ok(issue
+        .comments
+        .iter()
+        .all(|c| current_comments.contains(&comment_key(c))))
+}
+
+/// Reconciles labels and dependencies with the incoming record and appends
+/// any comments not yet present. Existing comments are never removed.
+fn apply_relations(
+    tx: &dyn Transaction,
+    issue: &Issue,
+    actor: &str,
+) -> beads_storage::error::Result<()> {
+    let current_labels: BTreeSet<String> = tx.get_labels(&issue.id)?.into_iter().collect();
+    let wanted_labels: BTreeSet<String> = issue.labels.iter().cloned().collect();
+    for label in current_labels.difference(&wanted_labels) {
+        tx.remove_label(&issue.id, label, actor)?;
+    }
+    for label in wanted_labels.difference(&current_labels) {
+        tx.add_label(&issue.id, label, actor)?;
+    }
+
+    let current_deps = tx.get_dependency_records(&issue.id)?;
+    let wanted_keys: BTreeSet<_> = issue.dependencies.iter().map(dep_key).collect();
+    let current_keys: BTreeSet<_> = current_deps.iter().map(dep_key).collect();
+    for dep in &current_deps {
+        if !wanted_keys.contains(&dep_key(dep)) {
+            tx.remove_dependency(&issue.id, &dep.depends_on_id, actor)?;
+        }
+    }
+    for dep in &issue.dependencies {
+        if !current_keys.contains(&dep_key(dep)) {
+            let mut dep = dep.clone();
+            dep.issue_id = issue.id.clone();
+            tx.add_dependency(&dep, actor)?;
+        }
+    }
+
+    let current_comments: BTreeSet<_> = tx
+        .get_comments(&issue.id)?
+        .iter()
+        .map(comment_key)
+        .collect();
+    for comment in &issue.comments {
+        if !current_comments.contains(&comment_key(comment)) {
+            tx.import_comment(
+                &issue.id,
+                &comment.author,
+                &comment.text,
+                comment.created_at,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beads_core::enums::DependencyType;
+    use beads_core::issue::IssueBuilder;
+    use beads_storage::SqliteStore;
+
+    fn sample() -> Vec<Issue> {
+        let mut a = IssueBuilder::new("First").id("bd-a").build();
+        a.labels = vec!["backend".into()];
+        a.comments = vec![Comment {
+            id: 0,
+            issue_id: "bd-a".into(),
+            author: "alice".into(),
+            text: "hello".into(),
+            created_at: chrono::Utc::now(),
+        }];
+        let mut b = IssueBuilder::new("Second").id("bd-b").build();
+        // Points at an issue that appears earlier; order must not matter.
+        b.dependencies = vec![Dependency {
+            issue_id: "bd-b".into(),
+            depends_on_id: "bd-a".into(),
+            dep_type: DependencyType::Blocks,
+            created_at: chrono::Utc::now(),
+            created_by: "alice".into(),
+            metadata: String::new(),
+            thread_id: String::new(),
+        }];
+        vec![b, a]
+    }
+
+    #[test]
+    fn import_creates_then_is_idempotent() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let issues = sample();
+
+        let first = import_issues(&store, &issues, "tester", false).unwrap();
+        assert_eq!((first.created, first.failed), (2, 0), "{:?}", first.errors);
+        assert_eq!(store.get_labels_impl("bd-a").unwrap(), vec!["backend"]);
+        assert_eq!(store.get_comments_impl("bd-a").unwrap().len(), 1);
+        assert_eq!(store.get_dependencies_impl("bd-b").unwrap().len(), 1);
+
+        let second = import_issues(&store, &issues, "tester", false).unwrap();
+        assert_eq!(second.unchanged, 2);
+        assert_eq!(store.get_comments_impl("bd-a").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn import_updates_changed_records() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut issues = sample();
+        import_issues(&store, &issues, "tester", false).unwrap();
+
+        issues[0].title = "Second (renamed)".into();
+        issues[1].labels.clear();
+        let summary = import_issues(&store, &issues, "tester", false).unwrap();
+        assert_eq!((summary.updated, summary.unchanged), (2, 0));
+        assert_eq!(
+            store.get_issue_impl("bd-b").unwrap().title,
+            "Second (renamed)"
+        );
+        assert!(store.get_labels_impl("bd-a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn dry_run_writes_nothing() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let summary = import_issues(&store, &sample(), "tester", true).unwrap();
+        assert_eq!(summary.created, 2);
+        assert!(store.get_issue_impl("bd-a").unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn invalid_records_are_reported() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let bad = IssueBuilder::new("").id("bd-bad").build();
+        let no_id = IssueBuilder::new("No id").build();
+        let summary = import_issues(&store, &[bad, no_id], "tester", false).unwrap();
+        assert_eq!(summary.failed, 2);
+        assert_eq!(summary.errors[0].id, "bd-bad");
+    }
+}
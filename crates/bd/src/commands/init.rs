@@ -2,12 +2,17 @@
 
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
 use anyhow::{Context, Result, bail};
 
+use beads_lockfile::LockMode;
+use beads_storage::{SqliteStore, Storage};
+
 use crate::cli::InitArgs;
+use crate::commands::merge_driver;
 use crate::context::RuntimeContext;
+use crate::locks;
 
 /// Default gitignore content for the `.beads` directory.
 const GITIGNORE_CONTENT: &str = r#"# Beads database files
@@ -15,8 +20,14 @@ const GITIGNORE_CONTENT: &str = r#"# Beads database files
 *.db-journal
 *.db-wal
 *.db-shm
+*.lock
+*.lock.d/
 dolt/
 
+# Migration backups and interrupted writes
+*.bak
+*.tmp
+
 # Local state
 .local_version
 interactions.jsonl
@@ -65,6 +76,11 @@ pub fn run(ctx: &RuntimeContext, args: &InitArgs) -> Result<()> {
     fs::create_dir_all(&beads_dir)
         .with_context(|| format!("failed to create directory: {}", beads_dir.display()))?;
 
+    // A concurrent `bd init` (or any command opening the database) waits
+    // until the workspace is fully set up.
+    let db_path = beads_dir.join("beads.db");
+    let db_lock = locks::lock_file(&db_path, LockMode::Exclusive)?;
+
     // Create .gitignore
     let gitignore_path = beads_dir.join(".gitignore");
     if !gitignore_path.exists() {
@@ -92,7 +108,6 @@ pub fn run(ctx: &RuntimeContext, args: &InitArgs) -> Result<()> {
     }
 
     // Create the SQLite database
-    let db_path = beads_dir.join("beads.db");
     create_database(&db_path, &prefix, &ctx.actor)?;
 
     // Create empty issues.jsonl
@@ -101,12 +116,26 @@ pub fn run(ctx: &RuntimeContext, args: &InitArgs) -> Result<()> {
         fs::write(&jsonl_path, "")
             .with_context(|| format!("failed to create issues.jsonl: {}", jsonl_path.display()))?;
     }
+    drop(db_lock);
+
+    // Register the JSONL merge driver when inside a git repository.
+    let merge_driver = !args.no_merge_driver
+        && match merge_driver::install(&jsonl_path) {
+            Ok(installed) => installed,
+            Err(e) => {
+                eprintln!("Warning: failed to install git merge driver: {e:#}");
+                false
+            }
+        };
 
     if !args.quiet {
         println!();
         println!("bd initialized successfully!");
         println!();
         println!("  Database: {}", db_path.display());
+        if merge_driver {
+            println!("  Git merge driver: installed for issues.jsonl");
+        }
         println!("  Issue prefix: {}", prefix);
         println!(
             "  Issues will be named: {}-<hash> (e.g., {}-a3f2dd)",
@@ -121,172 +150,21 @@ pub fn run(ctx: &RuntimeContext, args: &InitArgs) -> Result<()> {
 }
 
 /// Create and initialize the SQLite database with schema and config.
-fn create_database(db_path: &PathBuf, prefix: &str, actor: &str) -> Result<()> {
-    let conn = rusqlite::Connection::open(db_path)
+///
+/// The schema itself is owned by the storage layer; opening the store
+/// creates any missing tables.
+fn create_database(db_path: &Path, prefix: &str, actor: &str) -> Result<()> {
+    let store = SqliteStore::open(db_path)
         .with_context(|| format!("failed to create database: {}", db_path.display()))?;
 
-    // Enable WAL mode for better concurrent read performance
-    conn.execute_batch("PRAGMA journal_mode=WAL;")?;
-
-    // Create schema
-    conn.execute_batch(SCHEMA_SQL)?;
-
-    // Set issue prefix
-    conn.execute(
-        "INSERT OR REPLACE INTO config (key, value) VALUES (?1, ?2)",
-        rusqlite::params!["issue_prefix", prefix],
-    )?;
-
-    // Set initial bd version
-    conn.execute(
-        "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
-        rusqlite::params!["bd_version", env!("CARGO_PKG_VERSION")],
-    )?;
-
-    // Set initialization timestamp
-    let now = chrono::Utc::now().to_rfc3339();
-    conn.execute(
-        "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
-        rusqlite::params!["last_import_time", &now],
-    )?;
+    store.set_config("issue_prefix", prefix)?;
+    store.set_metadata("bd_version", env!("CARGO_PKG_VERSION"))?;
+    store.set_metadata("last_import_time", &chrono::Utc::now().to_rfc3339())?;
 
     // Record the actor who initialized
     if !actor.is_empty() {
-        conn.execute(
-            "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
-            rusqlite::params!["init_actor", actor],
-        )?;
+        store.set_metadata("init_actor", actor)?;
     }
 
     Ok(())
 }
-
-/// SQL schema for the beads database.
-///
-/// Matches the Go version's schema for compatibility.
-const SCHEMA_SQL: &str = r#"
-CREATE TABLE IF NOT EXISTS issues (
-    id TEXT PRIMARY KEY,
-    title TEXT NOT NULL,
-    description TEXT DEFAULT '',
-    design TEXT DEFAULT '',
-    acceptance_criteria TEXT DEFAULT '',
-    notes TEXT DEFAULT '',
-    spec_id TEXT DEFAULT '',
-    status TEXT DEFAULT 'open',
-    priority INTEGER DEFAULT 2,
-    issue_type TEXT DEFAULT 'task',
-    assignee TEXT DEFAULT '',
-    owner TEXT DEFAULT '',
-    estimated_minutes INTEGER,
-    created_at TEXT NOT NULL,
-    created_by TEXT DEFAULT '',
-    updated_at TEXT NOT NULL,
-    closed_at TEXT,
-    close_reason TEXT DEFAULT '',
-    closed_by_session TEXT DEFAULT '',
-    due_at TEXT,
-    defer_until TEXT,
-    external_ref TEXT,
-    source_system TEXT DEFAULT '',
-    metadata TEXT,
-    compaction_level INTEGER DEFAULT 0,
-    compacted_at TEXT,
-    compacted_at_commit TEXT,
-    original_size INTEGER DEFAULT 0,
-    source_repo TEXT DEFAULT '',
-    sender TEXT DEFAULT '',
-    ephemeral INTEGER DEFAULT 0,
-    wisp_type TEXT DEFAULT '',
-    pinned INTEGER DEFAULT 0,
-    is_template INTEGER DEFAULT 0,
-    bonded_from TEXT DEFAULT '[]',
-    creator TEXT,
-    validations TEXT DEFAULT '[]',
-    quality_score REAL,
-    crystallizes INTEGER DEFAULT 0,
-    await_type TEXT DEFAULT '',
-    await_id TEXT DEFAULT '',
-    timeout_ns INTEGER DEFAULT 0,
-    waiters TEXT DEFAULT '[]',
-    holder TEXT DEFAULT '',
-    source_formula TEXT DEFAULT '',
-    source_location TEXT DEFAULT '',
-    hook_bead TEXT DEFAULT '',
-    role_bead TEXT DEFAULT '',
-    agent_state TEXT DEFAULT '',
-    last_activity TEXT,
-    role_type TEXT DEFAULT '',
-    rig TEXT DEFAULT '',
-    mol_type TEXT DEFAULT '',
-    work_type TEXT DEFAULT 'mutex',
-    event_kind TEXT DEFAULT '',
-    actor TEXT DEFAULT '',
-    target TEXT DEFAULT '',
-    payload TEXT DEFAULT '',
-    content_hash TEXT DEFAULT ''
-);
-
-CREATE TABLE IF NOT EXISTS dependencies (
-    issue_id TEXT NOT NULL,
-    depends_on_id TEXT NOT NULL,
-    type TEXT DEFAULT 'blocks',
-    created_at TEXT NOT NULL,
-    created_by TEXT DEFAULT '',
-    metadata TEXT DEFAULT '',
-    thread_id TEXT DEFAULT '',
-    PRIMARY KEY (issue_id, depends_on_id, type),
-    FOREIGN KEY (issue_id) REFERENCES issues(id),
-    FOREIGN KEY (depends_on_id) REFERENCES issues(id)
-);
-
-CREATE TABLE IF NOT EXISTS labels (
-    issue_id TEXT NOT NULL,
-    label TEXT NOT NULL,
-    PRIMARY KEY (issue_id, label),
-    FOREIGN KEY (issue_id) REFERENCES issues(id)
-);
-
-CREATE TABLE IF NOT EXISTS comments (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    issue_id TEXT NOT NULL,
-    author TEXT NOT NULL,
-    text TEXT NOT NULL,
-    created_at TEXT NOT NULL,
-    FOREIGN KEY (issue_id) REFERENCES issues(id)
-);
-
-CREATE TABLE IF NOT EXISTS events (
-    id INTEGER PRIMARY KEY AUTOINCREMENT,
-    issue_id TEXT NOT NULL,
-    event_type TEXT NOT NULL,
-    actor TEXT NOT NULL,
-    old_value TEXT,
-    new_value TEXT,
-    comment TEXT,
-    created_at TEXT NOT NULL,
-    FOREIGN KEY (issue_id) REFERENCES issues(id)
-);
-
-CREATE TABLE IF NOT EXISTS config (
-    key TEXT PRIMARY KEY,
-    value TEXT NOT NULL
-);
-
-CREATE TABLE IF NOT EXISTS metadata (
-    key TEXT PRIMARY KEY,
-    value TEXT NOT NULL
-);
-
--- Indices for common queries
-CREATE INDEX IF NOT EXISTS idx_issues_status ON issues(status);
-CREATE INDEX IF NOT EXISTS idx_issues_assignee ON issues(assignee);
-CREATE INDEX IF NOT EXISTS idx_issues_priority ON issues(priority);
-CREATE INDEX IF NOT EXISTS idx_issues_type ON issues(issue_type);
-CREATE INDEX IF NOT EXISTS idx_issues_created ON issues(created_at);
-CREATE INDEX IF NOT EXISTS idx_issues_updated ON issues(updated_at);
-CREATE INDEX IF NOT EXISTS idx_dependencies_depends_on ON dependencies(depends_on_id);
-CREATE INDEX IF NOT EXISTS idx_labels_label ON labels(label);
-CREATE INDEX IF NOT EXISTS idx_comments_issue ON comments(issue_id);
-CREATE INDEX IF NOT EXISTS idx_events_issue ON events(issue_id);
-"#;
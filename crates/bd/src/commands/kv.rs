@@ -3,48 +3,34 @@
 //! Provides CRUD operations on the `metadata` table in the beads database.
 //! Internal keys (prefixed with `migration:`) are excluded from `list` output.
 
+use std::collections::BTreeMap;
+
 use anyhow::{Context, Result, bail};
 
+use beads_storage::Storage;
+
 use crate::cli::{KvArgs, KvCommands};
 use crate::context::RuntimeContext;
 use crate::output::output_json;
 
 /// Execute the `bd kv` command.
 pub fn run(ctx: &RuntimeContext, args: &KvArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
+    let store = ctx.store()?;
 
     match &args.command {
-        KvCommands::Get(get_args) => run_get(ctx, &db_path, &get_args.key),
-        KvCommands::Set(set_args) => run_set(ctx, &db_path, &set_args.key, &set_args.value),
-        KvCommands::List => run_list(ctx, &db_path),
-        KvCommands::Delete(del_args) => run_delete(ctx, &db_path, &del_args.key),
+        KvCommands::Get(get_args) => run_get(ctx, store, &get_args.key),
+        KvCommands::Set(set_args) => run_set(ctx, store, &set_args.key, &set_args.value),
+        KvCommands::List => run_list(ctx, store),
+        KvCommands::Delete(del_args) => run_delete(ctx, store, &del_args.key),
     }
 }
 
-fn run_get(ctx: &RuntimeContext, db_path: &std::path::Path, key: &str) -> Result<()> {
-    let conn = rusqlite::Connection::open_with_flags(
-        db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let value: Option<String> = conn
-        .query_row(
-            "SELECT value FROM metadata WHERE key = ?1",
-            rusqlite::params![key],
-            |row| row.get(0),
-        )
-        .ok();
+fn run_get(ctx: &RuntimeContext, store: &dyn Storage, key: &str) -> Result<()> {
+    let value = match store.get_metadata(key) {
+        Ok(v) => Some(v),
+        Err(e) if e.is_not_found() => None,
+        Err(e) => return Err(e.into()),
+    };
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -64,19 +50,14 @@ fn run_get(ctx: &RuntimeContext, db_path: &std::path::Path, key: &str) -> Result
     Ok(())
 }
 
-fn run_set(ctx: &RuntimeContext, db_path: &std::path::Path, key: &str, value: &str) -> Result<()> {
+fn run_set(ctx: &RuntimeContext, store: &dyn Storage, key: &str, value: &str) -> Result<()> {
     if ctx.readonly {
         bail!("cannot set metadata in read-only mode");
     }
 
-    let conn = rusqlite::Connection::open(db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    conn.execute(
-        "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
-        rusqlite::params![key, value],
-    )
-    .with_context(|| format!("failed to set metadata key '{}'", key))?;
+    store
+        .set_metadata(key, value)
+        .with_context(|| format!("failed to set metadata key '{}'", key))?;
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -90,18 +71,11 @@ fn run_set(ctx: &RuntimeContext, db_path: &std::path::Path, key: &str, value: &s
     Ok(())
 }
 
-fn run_list(ctx: &RuntimeContext, db_path: &std::path::Path) -> Result<()> {
-    let conn = rusqlite::Connection::open_with_flags(
-        db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let mut stmt = conn
-        .prepare("SELECT key, value FROM metadata WHERE key NOT LIKE 'migration:%' ORDER BY key")?;
-    let entries: Vec<(String, String)> = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
+fn run_list(ctx: &RuntimeContext, store: &dyn Storage) -> Result<()> {
+    let entries: BTreeMap<String, String> = store
+        .get_all_metadata()?
+        .into_iter()
+        .filter(|(key, _)| !key.starts_with("migration:"))
         .collect();
 
     if ctx.json {
@@ -121,25 +95,23 @@ fn run_list(ctx: &RuntimeContext, db_path: &std::path::Path) -> Result<()> {
     Ok(())
 }
 
-fn run_delete(ctx: &RuntimeContext, db_path: &std::path::Path, key: &str) -> Result<()> {
+fn run_delete(ctx: &RuntimeContext, store: &dyn Storage, key: &str) -> Result<()> {
     if ctx.readonly {
         bail!("cannot delete metadata in read-only mode");
     }
 
-    let conn = rusqlite::Connection::open(db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let changes = conn.execute(
-        "DELETE FROM metadata WHERE key = ?1",
-        rusqlite::params![key],
-    )?;
+    let deleted = match store.delete_metadata(key) {
+        Ok(()) => true,
+        Err(e) if e.is_not_found() => false,
+        Err(e) => return Err(e.into()),
+    };
 
     if ctx.json {
         output_json(&serde_json::json!({
             "key": key,
-            "deleted": changes > 0,
+            "deleted": deleted,
         }));
-    } else if changes > 0 {
+    } else if deleted {
         if !ctx.quiet {
             println!("Deleted {}", key);
         }
@@ -8,32 +8,13 @@ use crate::output::{load_labels, output_json};
 
 /// Execute the `bd label` command.
 pub fn run(ctx: &RuntimeContext, args: &LabelArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+    let store = ctx.store()?;
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    // Check if issue exists
-    let conn = rusqlite::Connection::open(&db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let exists: bool = conn
-        .query_row(
-            "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-            rusqlite::params![&args.id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if !exists {
-        bail!("issue '{}' not found", args.id);
+    if let Err(e) = store.get_issue(&args.id) {
+        if e.is_not_found() {
+            bail!("issue '{}' not found", args.id);
+        }
+        return Err(e.into());
     }
 
     match &args.command {
@@ -42,11 +23,11 @@ pub fn run(ctx: &RuntimeContext, args: &LabelArgs) -> Result<()> {
                 bail!("cannot add labels in read-only mode");
             }
 
-            conn.execute(
-                "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-                rusqlite::params![&args.id, &add_args.label],
-            )
-            .with_context(|| format!("failed to add label '{}' to {}", add_args.label, args.id))?;
+            store
+                .add_label(&args.id, &add_args.label, &ctx.actor)
+                .with_context(|| {
+                    format!("failed to add label '{}' to {}", add_args.label, args.id)
+                })?;
 
             if ctx.json {
                 output_json(&serde_json::json!({
@@ -64,19 +45,20 @@ pub fn run(ctx: &RuntimeContext, args: &LabelArgs) -> Result<()> {
                 bail!("cannot remove labels in read-only mode");
             }
 
-            let changes = conn.execute(
-                "DELETE FROM labels WHERE issue_id = ?1 AND label = ?2",
-                rusqlite::params![&args.id, &remove_args.label],
-            )?;
+            let removed = match store.remove_label(&args.id, &remove_args.label, &ctx.actor) {
+                Ok(()) => true,
+                Err(e) if e.is_not_found() => false,
+                Err(e) => return Err(e.into()),
+            };
 
             if ctx.json {
                 output_json(&serde_json::json!({
                     "status": "removed",
                     "issue_id": args.id,
                     "label": remove_args.label,
-                    "removed": changes > 0,
+                    "removed": removed,
                 }));
-            } else if changes > 0 {
+            } else if removed {
                 if !ctx.quiet {
                     println!("Removed label '{}' from {}", remove_args.label, args.id);
                 }
@@ -86,7 +68,7 @@ pub fn run(ctx: &RuntimeContext, args: &LabelArgs) -> Result<()> {
         }
 
         LabelCommands::List => {
-            let labels = load_labels(&conn, &args.id);
+            let labels = load_labels(store, &args.id);
 
             if ctx.json {
                 output_json(&labels);
@@ -7,7 +7,9 @@
 //! - Orphaned dependencies (dependencies referencing non-existent issues)
 //! - Issues with status values not in the known set
 
-use anyhow::{Context, Result, bail};
+use anyhow::Result;
+
+use beads_core::filter::IssueFilter;
 
 use crate::cli::LintArgs;
 use crate::context::RuntimeContext;
@@ -30,23 +32,9 @@ const VALID_TYPES: &[&str] = &[
 
 /// Execute the `bd lint` command.
 pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    let store = ctx.store()?;
+    let issues = store.search_issues("", &IssueFilter::default())?;
+    let health = store.check_health()?;
 
     let mut warnings = 0u32;
     let mut errors = 0u32;
@@ -56,10 +44,10 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 1. Empty titles
     {
-        let mut stmt = conn.prepare("SELECT id FROM issues WHERE title IS NULL OR title = ''")?;
-        let ids: Vec<String> = stmt
-            .query_map([], |row| row.get(0))?
-            .filter_map(|r| r.ok())
+        let ids: Vec<&str> = issues
+            .iter()
+            .filter(|i| i.title.is_empty())
+            .map(|i| i.id.as_str())
             .collect();
         if !ids.is_empty() {
             errors += ids.len() as u32;
@@ -73,11 +61,10 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 2. Invalid priorities (outside 0-4)
     {
-        let mut stmt =
-            conn.prepare("SELECT id, priority FROM issues WHERE priority < 0 OR priority > 4")?;
-        let bad: Vec<(String, i32)> = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .filter_map(|r| r.ok())
+        let bad: Vec<(&str, i32)> = issues
+            .iter()
+            .filter(|i| !(0..=4).contains(&i.priority))
+            .map(|i| (i.id.as_str(), i.priority))
             .collect();
         if !bad.is_empty() {
             warnings += bad.len() as u32;
@@ -94,19 +81,10 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 3. Invalid status values
     {
-        let placeholders: String = VALID_STATUSES
+        let bad: Vec<(&str, &str)> = issues
             .iter()
-            .map(|s| format!("'{}'", s))
-            .collect::<Vec<_>>()
-            .join(", ");
-        let query = format!(
-            "SELECT id, status FROM issues WHERE status NOT IN ({})",
-            placeholders
-        );
-        let mut stmt = conn.prepare(&query)?;
-        let bad: Vec<(String, String)> = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .filter_map(|r| r.ok())
+            .filter(|i| !VALID_STATUSES.contains(&i.status.as_str()))
+            .map(|i| (i.id.as_str(), i.status.as_str()))
             .collect();
         if !bad.is_empty() {
             warnings += bad.len() as u32;
@@ -120,19 +98,10 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 4. Invalid issue types
     {
-        let placeholders: String = VALID_TYPES
+        let bad: Vec<(&str, &str)> = issues
             .iter()
-            .map(|s| format!("'{}'", s))
-            .collect::<Vec<_>>()
-            .join(", ");
-        let query = format!(
-            "SELECT id, issue_type FROM issues WHERE issue_type NOT IN ({}) AND issue_type != ''",
-            placeholders
-        );
-        let mut stmt = conn.prepare(&query)?;
-        let bad: Vec<(String, String)> = stmt
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-            .filter_map(|r| r.ok())
+            .map(|i| (i.id.as_str(), i.issue_type.as_str()))
+            .filter(|(_, t)| !t.is_empty() && !VALID_TYPES.contains(t))
             .collect();
         if !bad.is_empty() {
             warnings += bad.len() as u32;
@@ -146,13 +115,7 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 5. Orphaned labels (labels for non-existent issues)
     {
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM labels WHERE issue_id NOT IN (SELECT id FROM issues)",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
+        let count = health.orphaned_labels;
         if count > 0 {
             warnings += 1;
             println!(
@@ -165,15 +128,7 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 6. Orphaned dependencies
     {
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM dependencies \
-                 WHERE issue_id NOT IN (SELECT id FROM issues) \
-                    OR depends_on_id NOT IN (SELECT id FROM issues)",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
+        let count = health.orphaned_dependencies;
         if count > 0 {
             warnings += 1;
             println!(
@@ -186,13 +141,7 @@ pub fn run(ctx: &RuntimeContext, _args: &LintArgs) -> Result<()> {
 
     // 7. Orphaned comments
     {
-        let count: i64 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM comments WHERE issue_id NOT IN (SELECT id FROM issues)",
-                [],
-                |row| row.get(0),
-            )
-            .unwrap_or(0);
+        let count = health.orphaned_comments;
         if count > 0 {
             warnings += 1;
             println!(
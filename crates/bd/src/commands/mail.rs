@@ -11,25 +11,18 @@
 
 use anyhow::{Context, Result, bail};
 
+use std::time::Duration;
+
 use crate::cli::MailArgs;
 use crate::context::RuntimeContext;
 
+/// How long sending one message may take.
+const SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Execute the `bd mail` command.
 pub fn run(ctx: &RuntimeContext, args: &MailArgs) -> Result<()> {
-    // 1. Try environment variables
-    let delegate = std::env::var("BEADS_MAIL_DELEGATE")
-        .or_else(|_| std::env::var("BD_MAIL_DELEGATE"))
-        .unwrap_or_default();
-
-    // 2. If no env delegate, try loading from config database
-    let delegate = if delegate.is_empty() {
-        load_delegate_from_config(ctx).unwrap_or_default()
-    } else {
-        delegate
-    };
-
-    // 3. If still no delegate, print helpful error
-    if delegate.is_empty() {
+    // 1-3. Resolve the delegate, or print a helpful error
+    let Some(delegate) = delegate(ctx) else {
         bail!(
             "No mail delegate configured.\n\n\
              Set one of the following:\n\
@@ -41,7 +34,7 @@ pub fn run(ctx: &RuntimeContext, args: &MailArgs) -> Result<()> {
              - 'msmtp -t'    (msmtp relay)\n\
              - 'mailx'       (BSD mail)"
         );
-    }
+    };
 
     // 4. Split delegate command into program and arguments
     let parts: Vec<&str> = delegate.split_whitespace().collect();
@@ -65,25 +58,45 @@ pub fn run(ctx: &RuntimeContext, args: &MailArgs) -> Result<()> {
     std::process::exit(status.code().unwrap_or(1));
 }
 
-/// Try to load the mail delegate from the beads config table.
-fn load_delegate_from_config(ctx: &RuntimeContext) -> Option<String> {
-    let beads_dir = ctx.resolve_db_path()?;
-    let db_path = beads_dir.join("beads.db");
+/// The configured mail delegate, from the environment or the config table.
+pub(crate) fn delegate(ctx: &RuntimeContext) -> Option<String> {
+    // 1. Try environment variables
+    let delegate = std::env::var("BEADS_MAIL_DELEGATE")
+        .or_else(|_| std::env::var("BD_MAIL_DELEGATE"))
+        .unwrap_or_default();
 
-    if !db_path.exists() {
-        return None;
-    }
+    // 2. If no env delegate, try loading from config database
+    let delegate = if delegate.trim().is_empty() {
+        load_delegate_from_config(ctx).unwrap_or_default()
+    } else {
+        delegate
+    };
+    (!delegate.trim().is_empty()).then_some(delegate)
+}
 
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .ok()?;
+/// Sends a message through `delegate` as `<delegate> send <to> -s <subject>
+/// -m <body>`, without a terminal.
+pub(crate) fn send(delegate: &str, to: &str, subject: &str, body: &str) -> Result<()> {
+    let parts: Vec<&str> = delegate.split_whitespace().collect();
+    let Some((program, base_args)) = parts.split_first() else {
+        bail!("mail delegate is empty after parsing: '{}'", delegate);
+    };
+    let mut command = std::process::Command::new(program);
+    command
+        .args(base_args)
+        .args(["send", to, "-s", subject, "-m", body]);
+    let output =
+        crate::gates::run_command(command, None, SEND_TIMEOUT).map_err(anyhow::Error::msg)?;
+    if !output.status.success() {
+        bail!(
+            "mail delegate failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
 
-    conn.query_row(
-        "SELECT value FROM config WHERE key = 'mail.delegate'",
-        [],
-        |row| row.get(0),
-    )
-    .ok()
+/// Try to load the mail delegate from the beads config table.
+fn load_delegate_from_config(ctx: &RuntimeContext) -> Option<String> {
+    ctx.store().ok()?.get_config("mail.delegate").ok()
 }
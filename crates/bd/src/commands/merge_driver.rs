@@ -0,0 +1,121 @@
+//! `bd merge-driver` -- git merge driver for `.beads/issues.jsonl`.
+//!
+//! Git invokes `bd merge-driver %O %A %B` when both branches touched the
+//! JSONL. The three versions are merged record by record (see
+//! [`crate::merge`]) and the result is written over `%A`. If the configured
+//! conflict strategy leaves conflicts, a JSON report is printed to stderr and
+//! the driver exits non-zero so git marks the file as conflicted.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use beads_git::commands::git_command;
+
+use crate::cli::MergeDriverArgs;
+use crate::commands::export::write_jsonl_file;
+use crate::commands::sync_cmd::repo_path;
+use crate::context::RuntimeContext;
+use crate::merge::{merge_issues, parse_jsonl};
+
+/// Name of the driver in `.gitattributes` and `merge.<name>.*` git config.
+const DRIVER_NAME: &str = "beads";
+
+/// Command line git runs for the driver.
+const DRIVER_COMMAND: &str = "bd merge-driver %O %A %B";
+
+/// Execute the `bd merge-driver` command.
+pub fn run(ctx: &RuntimeContext, args: &MergeDriverArgs) -> Result<()> {
+    let read = |path: &str| -> Result<_> {
+        let bytes = fs::read(path).with_context(|| format!("failed to read {path}"))?;
+        parse_jsonl(&bytes).with_context(|| format!("failed to parse {path}"))
+    };
+    let base = read(&args.base)?;
+    let ours = read(&args.ours)?;
+    let theirs = read(&args.theirs)?;
+
+    // Git runs drivers from the repository root, so the usual discovery
+    // finds the project's conflict settings.
+    let config = ctx.config()?;
+
+    let merged = merge_issues(&base, &ours, &theirs, &config.conflict)?;
+    write_jsonl_file(Path::new(&args.ours), &merged.issues)?;
+
+    if !merged.conflicts.is_empty() {
+        let report = serde_json::json!({ "conflicts": merged.conflicts });
+        eprintln!(
+            "{}",
+            serde_json::to_string_pretty(&report).context("failed to serialize conflicts")?
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Registers the driver for `jsonl` in its git repository: appends a
+/// `merge=beads` rule to `.gitattributes` at the repository root and sets
+/// `merge.beads.driver` in the local git config.
+///
+/// Returns `false` (and changes nothing) if `jsonl` is not inside a git
+/// repository.
+pub fn install(jsonl: &Path) -> Result<bool> {
+    let Some((root, rel)) = repo_path(jsonl) else {
+        return Ok(false);
+    };
+
+    let rule = format!("{rel} merge={DRIVER_NAME}");
+    let attributes = root.join(".gitattributes");
+    let existing = fs::read_to_string(&attributes).unwrap_or_default();
+    if !existing.lines().any(|line| line.trim() == rule) {
+        let mut content = existing;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&rule);
+        content.push('\n');
+        fs::write(&attributes, content)
+            .with_context(|| format!("failed to write {}", attributes.display()))?;
+    }
+
+    let name_key = format!("merge.{DRIVER_NAME}.name");
+    let driver_key = format!("merge.{DRIVER_NAME}.driver");
+    git_command(&["config", &name_key, "bd JSONL merge driver"], &root)
+        .context("failed to configure merge driver")?;
+    git_command(&["config", &driver_key, DRIVER_COMMAND], &root)
+        .context("failed to configure merge driver")?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn install_writes_attributes_once() {
+        let dir = tempfile::tempdir().unwrap();
+        git_command(&["init", "-q"], dir.path()).unwrap();
+        let beads = dir.path().join(".beads");
+        fs::create_dir(&beads).unwrap();
+        let jsonl = beads.join("issues.jsonl");
+
+        assert!(install(&jsonl).unwrap());
+        assert!(install(&jsonl).unwrap());
+
+        let attributes = fs::read_to_string(dir.path().join(".gitattributes")).unwrap();
+        assert_eq!(attributes, ".beads/issues.jsonl merge=beads\n");
+        let driver = git_command(&["config", "merge.beads.driver"], dir.path()).unwrap();
+        assert_eq!(driver, DRIVER_COMMAND);
+    }
+
+    #[test]
+    fn install_skips_non_repositories() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join(".beads").join("issues.jsonl");
+        // tempdir may itself live inside a repository on some machines; only
+        // assert when git agrees there is none.
+        if git_command(&["rev-parse", "--git-dir"], dir.path()).is_err() {
+            assert!(!install(&jsonl).unwrap());
+        }
+    }
+}
@@ -1,11 +1,135 @@
-//! `bd migrate` -- run database migrations (stub).
+//! `bd migrate` -- inspect and apply schema migrations.
+//!
+//! Every other command migrates the database automatically when it opens
+//! it; `bd migrate` opens it without doing so, so the pending migrations can
+//! be listed (`--status`, `--dry-run`) or applied up to a chosen version
+//! (`--to`). A backup of `beads.db` is written next to it before anything is
+//! applied.
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
 
+use beads_lockfile::LockMode;
+use beads_storage::SqliteStore;
+use beads_storage::sqlite::migrations::MigrationInfo;
+
+use crate::cli::MigrateArgs;
 use crate::context::RuntimeContext;
+use crate::locks;
+use crate::output::output_json;
+
+/// Execute the `bd migrate` command.
+pub fn run(ctx: &RuntimeContext, args: &MigrateArgs) -> Result<()> {
+    let db_path = ctx.db_file()?;
+    // Reports only read the schema; applying migrations has to wait for
+    // every other process using the database.
+    let mode = if args.status || args.dry_run {
+        LockMode::Shared
+    } else {
+        LockMode::Exclusive
+    };
+    let _lock = locks::lock_file(&db_path, mode)?;
+    let store = SqliteStore::open_without_migrations(&db_path)
+        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    let status = store.migration_status()?;
+
+    if args.status {
+        if ctx.json {
+            output_json(&status);
+            return Ok(());
+        }
+        println!(
+            "Schema version: {} (latest: {})",
+            status.current_version, status.latest_version
+        );
+        if !status.applied.is_empty() {
+            println!("\nApplied:");
+            for m in &status.applied {
+                let flag = if m.modified { "  [modified]" } else { "" };
+                println!("  v{} {}  {}{}", m.version, m.name, m.applied_at, flag);
+            }
+        }
+        print_pending(&status.pending);
+        return Ok(());
+    }
+
+    let target = args.to.unwrap_or(status.latest_version);
+    if target < status.current_version {
+        bail!(
+            "cannot migrate down from version {} to {}",
+            status.current_version,
+            target
+        );
+    }
+    if target > status.latest_version {
+        bail!(
+            "unknown schema version {} (latest is {})",
+            target,
+            status.latest_version
+        );
+    }
+
+    if args.dry_run {
+        let pending: Vec<MigrationInfo> = status
+            .pending
+            .into_iter()
+            .filter(|m| m.version <= target)
+            .collect();
+        if ctx.json {
+            output_json(&serde_json::json!({
+                "current_version": status.current_version,
+                "target_version": target,
+                "pending": pending,
+            }));
+            return Ok(());
+        }
+        println!(
+            "Schema version: {} (target: {})",
+            status.current_version, target
+        );
+        print_pending(&pending);
+        return Ok(());
+    }
 
-/// Execute the `bd migrate` command (stub).
-pub fn run(_ctx: &RuntimeContext) -> Result<()> {
-    println!("bd migrate: not yet implemented");
+    if ctx.readonly {
+        bail!("cannot migrate the database in read-only mode");
+    }
+
+    let report = store
+        .migrate_to(target)
+        .context("migration failed; the database is left at the last successful version")?;
+
+    if ctx.json {
+        output_json(&report);
+        return Ok(());
+    }
+    if report.applied.is_empty() {
+        println!(
+            "Database is up to date (schema version {})",
+            report.to_version
+        );
+        return Ok(());
+    }
+    if let Some(ref backup) = report.backup {
+        println!("Backed up database to {}", backup);
+    }
+    for m in &report.applied {
+        println!("Applied v{} {}", m.version, m.name);
+    }
+    println!(
+        "Migrated schema from version {} to {}",
+        report.from_version, report.to_version
+    );
     Ok(())
 }
+
+/// Prints the pending list, or a note that there is nothing to do.
+fn print_pending(pending: &[MigrationInfo]) {
+    if pending.is_empty() {
+        println!("\nNo pending migrations.");
+        return;
+    }
+    println!("\nPending:");
+    for m in pending {
+        println!("  v{} {}", m.version, m.name);
+    }
+}
@@ -47,6 +47,7 @@ pub mod linear;
 pub mod lint;
 pub mod list;
 pub mod mail;
+pub mod merge_driver;
 pub mod migrate;
 pub mod misc;
 pub mod mol;
@@ -1,102 +1,339 @@
 //! `bd mol` -- molecule operations.
 //!
+//! A molecule is a poured formula: a root epic labelled `molecule` that
+//! records its formula and variables in `metadata`, plus one issue per
+//! cooked step labelled `mol:<root id>`. Steps are not children of the
+//! root, so each becomes ready as soon as its own needs are closed.
+//!
 //! Implements:
-//! - `pour`: create persistent issues from a formula
+//! - `pour`: create persistent issues from a formula (or a seeded molecule)
 //! - `wisp`: create ephemeral issues from a formula
+//! - `seed`: create a molecule root with variables, to pour later
 //! - `show`: display a molecule (formula-created issue set) and its children
 //! - `progress`: show completion progress for a molecule
-//!
-//! Other subcommands remain stubs.
+//! - `bond`: attach one molecule to another, recording a `BondRef`
+//! - `squash`: collapse a finished molecule into its root
+//! - `burn`: delete a molecule with all of its steps
+//! - `distill`: write a formula reproducing an epic or molecule
+//! - `current`: show the molecule being worked on and its next ready step
+//! - `stale`: list molecules without step activity for N days
+//! - `ready-gated`: list molecules waiting only on gates
+
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Utc;
+use serde_json::value::RawValue;
 
+use beads_core::dependency::Dependency;
+use beads_core::entity::{BondRef, bond_type};
+use beads_core::enums::{DependencyType, IssueType, MolType, SortPolicy, Status, WispType};
+use beads_core::filter::{IssueFilter, WorkFilter};
+use beads_core::issue::Issue;
 use beads_formula::engine;
 use beads_formula::parser;
+use beads_formula::types::{Formula, VarDef, VarType};
+use beads_storage::{IssueUpdates, Storage, Transaction};
+use beads_timeparsing::{Unit, parse_duration_in};
 
-use crate::cli::{MolArgs, MolCommands};
-use crate::commands::cook::{create_issues, parse_var_flags};
+use crate::cli::{
+    MolArgs, MolBondArgs, MolBurnArgs, MolCommands, MolCurrentArgs, MolDistillArgs,
+    MolReadyGatedArgs, MolSeedArgs, MolSquashArgs, MolStaleArgs,
+};
+use crate::commands::agent::load_agent;
+use crate::commands::cook::{StepIssues, parse_var_flags};
+use crate::commands::create::{generate_issue_id, issue_prefix};
+use crate::commands::formula;
+use crate::commands::stale::describe_age;
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
+/// Label on every molecule root.
+pub(crate) const MOLECULE_LABEL: &str = "molecule";
+
 /// Execute the `bd mol` command.
 pub fn run(ctx: &RuntimeContext, args: &MolArgs) -> Result<()> {
     match &args.command {
-        MolCommands::Pour(a) => cmd_pour(ctx, a),
-        MolCommands::Wisp(a) => cmd_wisp(ctx, a),
+        MolCommands::Pour(a) => cmd_pour(ctx, a.id.as_deref(), &a.vars, a.dry_run, None),
+        MolCommands::Wisp(a) => cmd_pour(
+            ctx,
+            a.id.as_deref(),
+            &a.vars,
+            a.dry_run,
+            Some(&WispType::default()),
+        ),
         MolCommands::Show(a) => cmd_show(ctx, a),
         MolCommands::Progress(a) => cmd_progress(ctx, a),
-        MolCommands::Bond(_) => stub("bond"),
-        MolCommands::Squash(_) => stub("squash"),
-        MolCommands::Burn(_) => stub("burn"),
-        MolCommands::Distill(_) => stub("distill"),
-        MolCommands::Seed(_) => stub("seed"),
-        MolCommands::Stale(_) => stub("stale"),
-        MolCommands::ReadyGated(_) => stub("ready-gated"),
-        MolCommands::Current(_) => stub("current"),
+        MolCommands::Bond(a) => cmd_bond(ctx, a),
+        MolCommands::Squash(a) => cmd_squash(ctx, a),
+        MolCommands::Burn(a) => cmd_burn(ctx, a),
+        MolCommands::Distill(a) => cmd_distill(ctx, a),
+        MolCommands::Seed(a) => cmd_seed(ctx, a),
+        MolCommands::Stale(a) => cmd_stale(ctx, a),
+        MolCommands::ReadyGated(a) => cmd_ready_gated(ctx, a),
+        MolCommands::Current(a) => cmd_current(ctx, a),
     }
 }
 
-fn stub(name: &str) -> Result<()> {
-    println!("bd mol {}: not yet implemented", name);
-    Ok(())
-}
-
 // ---------------------------------------------------------------------------
-// Pour
+// Molecule model
 // ---------------------------------------------------------------------------
 
-fn cmd_pour(ctx: &RuntimeContext, args: &crate::cli::MolPourArgs) -> Result<()> {
-    let formula_name = args
-        .id
-        .as_deref()
-        .context("formula name or path is required")?;
+/// Label tying a step to the molecule rooted at `root_id`.
+pub(crate) fn step_label(root_id: &str) -> String {
+    format!("mol:{root_id}")
+}
 
-    let cwd = std::env::current_dir()?;
-    let path = parser::find_formula(formula_name, &cwd).map_err(|e| anyhow::anyhow!("{}", e))?;
-    let formula = parser::load_formula(&path).map_err(|e| anyhow::anyhow!("{}", e))?;
+/// Whether `label` is added by pouring rather than taken from the formula.
+pub(crate) fn is_pour_label(label: &str) -> bool {
+    label == MOLECULE_LABEL
+        || label == "ephemeral"
+        || label.starts_with("mol:")
+        || label.starts_with("formula:")
+}
 
-    let vars = parse_var_flags(&args.vars)?;
-    let cooked = engine::cook(&formula, &vars).map_err(|e| anyhow::anyhow!("{}", e))?;
+/// A molecule root and its steps.
+struct Molecule {
+    root: Issue,
+    steps: Vec<Issue>,
+}
 
-    if cooked.is_empty() {
-        println!("No steps to create (all filtered by conditions).");
-        return Ok(());
+/// Whether `id` is a molecule root.
+fn is_root(store: &dyn Storage, id: &str) -> Result<bool> {
+    Ok(store.get_labels(id)?.iter().any(|l| l == MOLECULE_LABEL))
+}
+
+/// Loads the molecule rooted at `id`, steps oldest first.
+fn load_molecule(store: &dyn Storage, id: &str) -> Result<Molecule> {
+    let root = match store.get_issue(id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", id),
+        Err(e) => return Err(e.into()),
+    };
+    if !is_root(store, &root.id)? {
+        bail!("{} is not a molecule root", root.id);
     }
+    let mut steps = store.get_issues_by_label(&step_label(&root.id))?;
+    steps.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    Ok(Molecule { root, steps })
+}
 
-    if args.dry_run {
-        return print_pour_preview(&formula.formula, &cooked, false);
+/// The `{"formula": ..., "vars": {...}}` metadata of a molecule root.
+fn root_metadata(root: &Issue) -> serde_json::Value {
+    root.metadata
+        .as_ref()
+        .and_then(|raw| serde_json::from_str(raw.get()).ok())
+        .unwrap_or_default()
+}
+
+/// Name of the formula a molecule root was poured or seeded from.
+fn root_formula(root: &Issue) -> String {
+    root_metadata(root)["formula"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Variables recorded on a molecule root.
+fn root_vars(root: &Issue) -> HashMap<String, String> {
+    serde_json::from_value(root_metadata(root)["vars"].clone()).unwrap_or_default()
+}
+
+/// Root metadata recording `formula` and `vars`.
+fn root_metadata_for(formula: &str, vars: &HashMap<String, String>) -> Result<Box<RawValue>> {
+    let vars: BTreeMap<&String, &String> = vars.iter().collect();
+    Ok(serde_json::value::to_raw_value(
+        &serde_json::json!({ "formula": formula, "vars": vars }),
+    )?)
+}
+
+/// Creates the root of a new molecule for `formula` and returns its ID.
+fn create_root(
+    ctx: &RuntimeContext,
+    formula: &Formula,
+    vars: &HashMap<String, String>,
+    title: Option<&str>,
+    wisp: Option<&WispType>,
+) -> Result<String> {
+    let store = ctx.store()?;
+    let root = new_root(ctx, store, formula, vars, title, wisp)?;
+    store.run_in_transaction(&|tx| write_root(tx, &root, &ctx.actor))?;
+    Ok(root.id)
+}
+
+/// The root of a new molecule for `formula`, not created yet.
+fn new_root(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    formula: &Formula,
+    vars: &HashMap<String, String>,
+    title: Option<&str>,
+    wisp: Option<&WispType>,
+) -> Result<Issue> {
+    let prefix = issue_prefix(ctx, store);
+    let now = Utc::now();
+    let title = title.unwrap_or(&formula.formula).to_string();
+    let id = generate_issue_id(
+        store,
+        &prefix,
+        &title,
+        &formula.description,
+        &ctx.actor,
+        now,
+    )?;
+
+    Ok(Issue {
+        id,
+        title,
+        description: formula.description.clone(),
+        status: Status::Open,
+        issue_type: IssueType::Epic,
+        mol_type: MolType::Work,
+        ephemeral: wisp.is_some(),
+        wisp_type: wisp.cloned().unwrap_or_default(),
+        metadata: Some(root_metadata_for(&formula.formula, vars)?),
+        created_by: ctx.actor.clone(),
+        created_at: now,
+        updated_at: now,
+        ..Issue::default()
+    })
+}
+
+/// Creates molecule root `root` in `tx`, with its labels.
+fn write_root(tx: &dyn Transaction, root: &Issue, actor: &str) -> beads_storage::error::Result<()> {
+    tx.create_issue(root, actor)?;
+    tx.add_label(&root.id, MOLECULE_LABEL, actor)?;
+    if root.ephemeral {
+        tx.add_label(&root.id, "ephemeral", actor)?;
     }
+    Ok(())
+}
 
-    create_issues(ctx, &formula.formula, &cooked, false)
+/// IDs of `steps` that depend on no other step of the set (`first`), or
+/// that no other step of the set depends on.
+fn boundary_steps(store: &dyn Storage, steps: &[Issue], first: bool) -> Result<Vec<String>> {
+    let ids: HashSet<&str> = steps.iter().map(|s| s.id.as_str()).collect();
+    let mut inner = HashSet::new();
+    for step in steps {
+        for dep in store.get_dependency_records(&step.id)? {
+            if dep.dep_type.affects_ready_work() && ids.contains(dep.depends_on_id.as_str()) {
+                inner.insert(if first {
+                    dep.issue_id
+                } else {
+                    dep.depends_on_id
+                });
+            }
+        }
+    }
+    Ok(steps
+        .iter()
+        .filter(|s| !inner.contains(&s.id))
+        .map(|s| s.id.clone())
+        .collect())
 }
 
 // ---------------------------------------------------------------------------
-// Wisp
+// Pour / Wisp
 // ---------------------------------------------------------------------------
 
-fn cmd_wisp(ctx: &RuntimeContext, args: &crate::cli::MolWispArgs) -> Result<()> {
-    let formula_name = args
-        .id
-        .as_deref()
-        .context("formula name or path is required")?;
+/// Pours `name` -- a formula, or a seeded molecule -- into a molecule, of
+/// wisps of the given type if `wisp` is set.
+pub(crate) fn cmd_pour(
+    ctx: &RuntimeContext,
+    name: Option<&str>,
+    var_flags: &[String],
+    dry_run: bool,
+    wisp: Option<&WispType>,
+) -> Result<()> {
+    let name = name.context("formula name or path is required")?;
 
     let cwd = std::env::current_dir()?;
-    let path = parser::find_formula(formula_name, &cwd).map_err(|e| anyhow::anyhow!("{}", e))?;
-    let formula = parser::load_formula(&path).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let (formula, vars, seeded) = match parser::find_formula(name, &cwd) {
+        Ok(path) => {
+            let formula = parser::load_formula(&path).map_err(|e| anyhow!("{}", e))?;
+            (formula, parse_var_flags(var_flags)?, None)
+        }
+        Err(err) => {
+            let Some(root) = seeded_root(ctx, name)? else {
+                return Err(anyhow!("{}", err));
+            };
+            let formula =
+                parser::load_named(&root_formula(&root), &cwd).map_err(|e| anyhow!("{}", e))?;
+            let mut vars = root_vars(&root);
+            vars.extend(parse_var_flags(var_flags)?);
+            (formula, vars, Some(root))
+        }
+    };
 
-    let vars = parse_var_flags(&args.vars)?;
-    let cooked = engine::cook(&formula, &vars).map_err(|e| anyhow::anyhow!("{}", e))?;
+    let cooked = engine::cook_with(&formula, &vars, &|name| parser::load_named(name, &cwd))
+        .map_err(|e| anyhow!("{}", e))?;
 
     if cooked.is_empty() {
         println!("No steps to create (all filtered by conditions).");
         return Ok(());
     }
 
-    if args.dry_run {
-        return print_pour_preview(&formula.formula, &cooked, true);
+    if dry_run {
+        return print_pour_preview(&formula.formula, &cooked, wisp.is_some());
+    }
+    if ctx.readonly {
+        bail!("cannot create issues in read-only mode");
     }
 
-    create_issues(ctx, &formula.formula, &cooked, true)
+    // The root (or the seeded root's metadata), the steps, their labels and
+    // their dependencies are written together, so a failure leaves no
+    // half-poured molecule behind.
+    let store = ctx.store()?;
+    let (root, seed_updates) = match seeded {
+        Some(root) => {
+            let updates = IssueUpdates {
+                metadata: Some(Some(
+                    root_metadata_for(&formula.formula, &vars)?
+                        .get()
+                        .to_string(),
+                )),
+                ..Default::default()
+            };
+            (root, Some(updates))
+        }
+        None => (new_root(ctx, store, &formula, &vars, None, wisp)?, None),
+    };
+    let steps = StepIssues::plan(
+        ctx,
+        store,
+        &formula.formula,
+        &cooked,
+        wisp,
+        Some(&root.id),
+        &HashSet::from([root.id.clone()]),
+    )?;
+    store.run_in_transaction(&|tx| {
+        match &seed_updates {
+            Some(updates) => tx.update_issue(&root.id, updates, &ctx.actor)?,
+            None => write_root(tx, &root, &ctx.actor)?,
+        }
+        steps.write(tx, &ctx.actor)
+    })?;
+    steps.print(ctx);
+    Ok(())
+}
+
+/// The seeded molecule `id`, if it names one. Fails if it has already been
+/// poured.
+fn seeded_root(ctx: &RuntimeContext, id: &str) -> Result<Option<Issue>> {
+    let Ok(store) = ctx.store() else {
+        return Ok(None);
+    };
+    let Ok(root) = store.get_issue(id) else {
+        return Ok(None);
+    };
+    if root_formula(&root).is_empty() || !is_root(store, &root.id)? {
+        return Ok(None);
+    }
+    if !store.get_issues_by_label(&step_label(&root.id))?.is_empty() {
+        bail!("molecule {} has already been poured", root.id);
+    }
+    Ok(Some(root))
 }
 
 fn print_pour_preview(
@@ -121,72 +358,128 @@ fn print_pour_preview(
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// Seed
+// ---------------------------------------------------------------------------
+
+fn cmd_seed(ctx: &RuntimeContext, args: &MolSeedArgs) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot create issues in read-only mode");
+    }
+
+    let cwd = std::env::current_dir()?;
+    let path = parser::find_formula(&args.formula, &cwd).map_err(|e| anyhow!("{}", e))?;
+    let formula = parser::load_formula(&path).map_err(|e| anyhow!("{}", e))?;
+
+    // Values given now must fit; the rest can come when pouring
+    let vars = parse_var_flags(&args.vars)?;
+    for (name, value) in &vars {
+        if let Some(def) = formula.vars.get(name) {
+            def.check(value)
+                .map_err(|reason| anyhow!("invalid value for variable {}: {}", name, reason))?;
+        }
+    }
+
+    let id = create_root(ctx, &formula, &vars, args.title.as_deref(), None)?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "id": id,
+            "formula": formula.formula,
+            "vars": vars.iter().collect::<BTreeMap<_, _>>(),
+        }));
+    } else if !ctx.quiet {
+        println!("Seeded molecule {} from formula '{}'", id, formula.formula);
+        println!("  Pour with: bd mol pour {}", id);
+    }
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // Show
 // ---------------------------------------------------------------------------
 
+/// The molecule root `id` and its steps, or -- when `id` is not a root --
+/// the issues poured from formula `id`.
+fn members(store: &dyn Storage, id: &str) -> Result<(Option<Issue>, Vec<Issue>)> {
+    if let Ok(root) = store.get_issue(id) {
+        if is_root(store, &root.id)? {
+            let molecule = load_molecule(store, &root.id)?;
+            return Ok((Some(molecule.root), molecule.steps));
+        }
+    }
+    let mut issues = store.get_issues_by_label(&format!("formula:{}", id))?;
+    issues.sort_by_key(|i| i.created_at);
+    Ok((None, issues))
+}
+
 fn cmd_show(ctx: &RuntimeContext, args: &crate::cli::MolShowArgs) -> Result<()> {
     let id = args.id.as_deref().context("molecule ID is required")?;
 
-    let conn = open_db(ctx)?;
+    let store = ctx.store()?;
 
-    // Find all issues with the formula:<id> label
-    let label = format!("formula:{}", id);
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.status, i.priority, i.issue_type \
-         FROM issues i \
-         JOIN labels l ON i.id = l.issue_id \
-         WHERE l.label = ?1 \
-         ORDER BY i.created_at ASC",
-    )?;
+    let (root, issues) = members(store, id)?;
 
-    let issues: Vec<(String, String, String, i32, String)> = stmt
-        .query_map(rusqlite::params![&label], |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get::<_, String>(2).unwrap_or_default(),
-                row.get(3)?,
-                row.get::<_, String>(4).unwrap_or_default(),
-            ))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    if issues.is_empty() {
+    if issues.is_empty() && root.is_none() {
         bail!("no issues found for molecule '{}'", id);
     }
 
     if ctx.json {
         let items: Vec<serde_json::Value> = issues
             .iter()
-            .map(|(id, title, status, pri, itype)| {
+            .map(|issue| {
                 serde_json::json!({
-                    "id": id,
-                    "title": title,
-                    "status": status,
-                    "priority": pri,
-                    "type": itype,
+                    "id": issue.id,
+                    "title": issue.title,
+                    "status": issue.status,
+                    "priority": issue.priority,
+                    "type": issue.issue_type,
                 })
             })
             .collect();
-        output_json(&serde_json::json!({
+        let mut out = serde_json::json!({
             "molecule": id,
             "issues": items,
-        }));
+        });
+        if let Some(ref root) = root {
+            out["root"] = serde_json::json!({
+                "id": root.id,
+                "title": root.title,
+                "status": root.status,
+                "formula": root_formula(root),
+                "vars": root_vars(root).into_iter().collect::<BTreeMap<_, _>>(),
+                "bonded_from": root.bonded_from,
+            });
+        }
+        output_json(&out);
     } else {
         println!("Molecule: {}", id);
+        if let Some(ref root) = root {
+            println!("Root: {} [{}]", root.title, root.status);
+            let formula = root_formula(root);
+            if !formula.is_empty() {
+                println!("Formula: {}", formula);
+            }
+            for bond in &root.bonded_from {
+                let point = if bond.bond_point.is_empty() {
+                    String::new()
+                } else {
+                    format!(" at {}", bond.bond_point)
+                };
+                println!("Bonded: {} ({}{})", bond.source_id, bond.bond_type, point);
+            }
+        }
         println!("Issues ({}):", issues.len());
         let headers = &["ID", "PRI", "STATUS", "TYPE", "TITLE"];
         let rows: Vec<Vec<String>> = issues
             .iter()
-            .map(|(id, title, status, pri, itype)| {
+            .map(|issue| {
                 vec![
-                    id.clone(),
-                    format!("P{}", pri),
-                    status.clone(),
-                    itype.clone(),
-                    title.clone(),
+                    issue.id.clone(),
+                    format!("P{}", issue.priority),
+                    issue.status.to_string(),
+                    issue.issue_type.to_string(),
+                    issue.title.clone(),
                 ]
             })
             .collect();
@@ -203,27 +496,19 @@ fn cmd_show(ctx: &RuntimeContext, args: &crate::cli::MolShowArgs) -> Result<()>
 fn cmd_progress(ctx: &RuntimeContext, args: &crate::cli::MolProgressArgs) -> Result<()> {
     let id = args.id.as_deref().context("molecule ID is required")?;
 
-    let conn = open_db(ctx)?;
+    let store = ctx.store()?;
 
-    let label = format!("formula:{}", id);
-
-    let total: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM labels WHERE label = ?1",
-        rusqlite::params![&label],
-        |row| row.get(0),
-    )?;
+    let (_, issues) = members(store, id)?;
 
+    let total = issues.len() as i64;
     if total == 0 {
         bail!("no issues found for molecule '{}'", id);
     }
 
-    let closed: i64 = conn.query_row(
-        "SELECT COUNT(*) FROM issues i \
-         JOIN labels l ON i.id = l.issue_id \
-         WHERE l.label = ?1 AND i.status = 'closed'",
-        rusqlite::params![&label],
-        |row| row.get(0),
-    )?;
+    let closed = issues
+        .iter()
+        .filter(|issue| issue.status == Status::Closed)
+        .count() as i64;
 
     let pct = if total > 0 {
         (closed as f64 / total as f64 * 100.0) as i32
@@ -255,25 +540,800 @@ fn cmd_progress(ctx: &RuntimeContext, args: &crate::cli::MolProgressArgs) -> Res
 }
 
 // ---------------------------------------------------------------------------
-// Helpers
+// Tracking: current / stale / ready-gated
 // ---------------------------------------------------------------------------
 
-fn open_db(ctx: &RuntimeContext) -> Result<rusqlite::Connection> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+/// Whether an issue in `status` still holds up issues depending on it.
+fn holds_work(status: &Status) -> bool {
+    matches!(
+        status,
+        Status::Open | Status::InProgress | Status::Blocked | Status::Deferred | Status::Hooked
+    )
+}
+
+fn is_gate(issue: &Issue) -> bool {
+    issue.issue_type.as_str() == "gate"
+}
+
+/// Issues that `issue_id` waits on and that are not closed yet.
+fn open_blockers(store: &dyn Storage, issue_id: &str) -> Result<Vec<Issue>> {
+    let mut blockers = Vec::new();
+    for dep in store.get_dependency_records(issue_id)? {
+        if !dep.dep_type.affects_ready_work() {
+            continue;
+        }
+        match store.get_issue(&dep.depends_on_id) {
+            Ok(blocker) if holds_work(&blocker.status) => blockers.push(blocker),
+            Ok(_) => {}
+            Err(e) if e.is_not_found() => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(blockers)
+}
+
+/// The molecule root of `issue_id`: the issue itself if it is a root, or
+/// the root named by its `mol:` label.
+pub(crate) fn molecule_of(store: &dyn Storage, issue_id: &str) -> Result<Option<String>> {
+    let labels = store.get_labels(issue_id)?;
+    if labels.iter().any(|l| l == MOLECULE_LABEL) {
+        return Ok(Some(issue_id.to_string()));
+    }
+    Ok(labels
+        .iter()
+        .find_map(|l| l.strip_prefix("mol:"))
+        .map(str::to_string))
+}
+
+/// Molecule roots that are not closed, oldest first.
+fn open_molecules(store: &dyn Storage) -> Result<Vec<Issue>> {
+    let mut roots: Vec<Issue> = store
+        .get_issues_by_label(MOLECULE_LABEL)?
+        .into_iter()
+        .filter(|r| r.status != Status::Closed)
+        .collect();
+    roots.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    Ok(roots)
+}
+
+/// Steps of `root_id` ready to work on, in `bd ready` order.
+fn ready_steps(store: &dyn Storage, root_id: &str) -> Result<Vec<Issue>> {
+    let filter = WorkFilter {
+        labels: vec![step_label(root_id)],
+        include_ephemeral: true,
+        include_mol_steps: true,
+        sort_policy: SortPolicy::Priority,
+        ..Default::default()
+    };
+    Ok(store.get_ready_work(&filter)?)
+}
+
+/// The gates holding up `molecule`, if nothing else does: every unfinished
+/// step that no other unfinished step precedes is either a gate itself or
+/// waits only on gates. `None` when some step is workable or waits on
+/// other work.
+fn holding_gates(store: &dyn Storage, molecule: &Molecule) -> Result<Option<Vec<Issue>>> {
+    let open: Vec<&Issue> = molecule
+        .steps
+        .iter()
+        .filter(|s| holds_work(&s.status))
+        .collect();
+    let open_ids: HashSet<&str> = open.iter().map(|s| s.id.as_str()).collect();
+
+    let mut gates: Vec<Issue> = Vec::new();
+    for step in open {
+        let blockers = open_blockers(store, &step.id)?;
+        if blockers.iter().any(|b| open_ids.contains(b.id.as_str())) {
+            continue;
+        }
+        if is_gate(step) {
+            gates.push(step.clone());
+        } else if blockers.is_empty() || !blockers.iter().all(is_gate) {
+            return Ok(None);
+        } else {
+            gates.extend(blockers);
+        }
+    }
+    gates.sort_by(|a, b| a.id.cmp(&b.id));
+    gates.dedup_by(|a, b| a.id == b.id);
+    Ok((!gates.is_empty()).then_some(gates))
+}
+
+/// Resolves the molecule being worked on: through the agent's hooked issue,
+/// or else the actor's most recently touched in-progress step.
+fn current_molecule(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    agent: Option<&str>,
+) -> Result<Molecule> {
+    if let Some(agent) = agent {
+        let agent = load_agent(store, agent)?;
+        if agent.hook_bead.is_empty() {
+            bail!("agent {} has no hooked issue", agent.id);
+        }
+        return match molecule_of(store, &agent.hook_bead)? {
+            Some(root) => load_molecule(store, &root),
+            None => bail!(
+                "issue {} hooked by agent {} is not part of a molecule",
+                agent.hook_bead,
+                agent.id
+            ),
+        };
+    }
+
+    let mut working: Vec<Issue> = Vec::new();
+    for status in [Status::InProgress, Status::Hooked] {
+        let filter = IssueFilter {
+            status: Some(status),
+            assignee: Some(ctx.actor.clone()),
+            ..Default::default()
+        };
+        working.extend(store.search_issues("", &filter)?);
+    }
+    working.sort_by_key(|i| std::cmp::Reverse(i.updated_at));
+    for issue in &working {
+        if let Some(root) = molecule_of(store, &issue.id)? {
+            return load_molecule(store, &root);
+        }
+    }
+    bail!(
+        "no current molecule for {}: no in-progress steps assigned to them (use --agent to go by an agent's hooked issue)",
+        ctx.actor
+    )
+}
+
+fn cmd_current(ctx: &RuntimeContext, args: &MolCurrentArgs) -> Result<()> {
+    let store = ctx.store()?;
+    let molecule = current_molecule(ctx, store, args.agent.as_deref())?;
+    let root = &molecule.root;
+
+    let closed = molecule
+        .steps
+        .iter()
+        .filter(|s| s.status == Status::Closed)
+        .count();
+    let working: Vec<&Issue> = molecule
+        .steps
+        .iter()
+        .filter(|s| matches!(s.status, Status::InProgress | Status::Hooked))
+        .collect();
+    let ready = ready_steps(store, &root.id)?;
+    let gates = if ready.is_empty() {
+        holding_gates(store, &molecule)?.unwrap_or_default()
+    } else {
+        Vec::new()
+    };
+
+    if ctx.json {
+        let brief = |i: &Issue| {
+            serde_json::json!({
+                "id": i.id,
+                "title": i.title,
+                "status": i.status,
+                "priority": i.priority,
+            })
+        };
+        output_json(&serde_json::json!({
+            "molecule": root.id,
+            "title": root.title,
+            "formula": root_formula(root),
+            "total": molecule.steps.len(),
+            "closed": closed,
+            "working": working.iter().map(|i| brief(i)).collect::<Vec<_>>(),
+            "next": ready.first().map(brief),
+            "ready": ready.iter().map(brief).collect::<Vec<_>>(),
+            "gates": gates.iter().map(brief).collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+
+    println!("Molecule: {} {}", root.id, root.title);
+    let formula = root_formula(root);
+    if !formula.is_empty() {
+        println!("Formula: {}", formula);
+    }
+    println!(
+        "Progress: {}/{} steps complete",
+        closed,
+        molecule.steps.len()
+    );
+    for step in &working {
+        println!("Working on: {} {} [{}]", step.id, step.title, step.status);
+    }
+    match ready.first() {
+        Some(next) => {
+            println!("Next: {} [P{}] {}", next.id, next.priority, next.title);
+            if ready.len() > 1 {
+                println!("  ({} more ready)", ready.len() - 1);
+            }
+        }
+        None if !gates.is_empty() => {
+            println!("Next: none ready, waiting on gates:");
+            for gate in &gates {
+                println!("  {} [{}] {}", gate.id, gate.await_type, gate.title);
+            }
+        }
+        None if closed == molecule.steps.len() => {
+            println!(
+                "Next: all steps complete (squash with: bd mol squash {})",
+                root.id
+            )
+        }
+        None => println!("Next: none ready"),
+    }
+    Ok(())
+}
+
+fn cmd_stale(ctx: &RuntimeContext, args: &MolStaleArgs) -> Result<()> {
+    let store = ctx.store()?;
+
+    let age = parse_duration_in(&args.days, Unit::Day)?;
+    if age < chrono::Duration::zero() {
+        bail!("--days must not be negative (got '{}')", args.days);
+    }
+    let cutoff = Utc::now() - age;
 
-    if !db_path.exists() {
+    // Last activity is the latest update of the root or any step
+    let mut stale = Vec::new();
+    for root in open_molecules(store)? {
+        let molecule = load_molecule(store, &root.id)?;
+        let last = molecule
+            .steps
+            .iter()
+            .map(|s| s.updated_at)
+            .chain(std::iter::once(root.updated_at))
+            .max()
+            .unwrap_or(root.updated_at);
+        if last < cutoff {
+            stale.push((molecule, last));
+        }
+    }
+    stale.sort_by_key(|(_, last)| *last);
+
+    if ctx.json {
+        let molecules: Vec<serde_json::Value> = stale
+            .iter()
+            .map(|(m, last)| {
+                serde_json::json!({
+                    "id": m.root.id,
+                    "title": m.root.title,
+                    "formula": root_formula(&m.root),
+                    "total": m.steps.len(),
+                    "closed": m.steps.iter().filter(|s| s.status == Status::Closed).count(),
+                    "last_activity": last.to_rfc3339(),
+                })
+            })
+            .collect();
+        output_json(&serde_json::json!({
+            "days": age.num_days(),
+            "count": stale.len(),
+            "molecules": molecules,
+        }));
+        return Ok(());
+    }
+
+    if stale.is_empty() {
+        println!(
+            "No stale molecules (step activity within last {})",
+            describe_age(age)
+        );
+        return Ok(());
+    }
+    println!(
+        "Stale molecules (no step activity in {}): {}\n",
+        describe_age(age),
+        stale.len()
+    );
+    let headers = &["ID", "TITLE", "FORMULA", "PROGRESS", "LAST ACTIVITY"];
+    let rows: Vec<Vec<String>> = stale
+        .iter()
+        .map(|(m, last)| {
+            let closed = m
+                .steps
+                .iter()
+                .filter(|s| s.status == Status::Closed)
+                .count();
+            vec![
+                m.root.id.clone(),
+                m.root.title.clone(),
+                root_formula(&m.root),
+                format!("{}/{}", closed, m.steps.len()),
+                last.format("%Y-%m-%d").to_string(),
+            ]
+        })
+        .collect();
+    output_table(headers, &rows);
+    Ok(())
+}
+
+fn cmd_ready_gated(ctx: &RuntimeContext, args: &MolReadyGatedArgs) -> Result<()> {
+    let store = ctx.store()?;
+
+    let roots = match args.id {
+        Some(ref id) => vec![load_molecule(store, id)?.root],
+        None => open_molecules(store)?,
+    };
+    let mut gated = Vec::new();
+    for root in roots {
+        let molecule = load_molecule(store, &root.id)?;
+        if let Some(gates) = holding_gates(store, &molecule)? {
+            gated.push((molecule.root, gates));
+        }
+    }
+
+    if ctx.json {
+        let molecules: Vec<serde_json::Value> = gated
+            .iter()
+            .map(|(root, gates)| {
+                serde_json::json!({
+                    "id": root.id,
+                    "title": root.title,
+                    "gates": gates
+                        .iter()
+                        .map(|g| serde_json::json!({
+                            "id": g.id,
+                            "title": g.title,
+                            "await_type": g.await_type,
+                            "await_id": g.await_id,
+                        }))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        output_json(&molecules);
+        return Ok(());
+    }
+
+    if gated.is_empty() {
+        println!("No molecules waiting only on gates.");
+        return Ok(());
+    }
+    for (root, gates) in &gated {
+        println!("{} {}", root.id, root.title);
+        for gate in gates {
+            let await_info = match (gate.await_type.is_empty(), gate.await_id.is_empty()) {
+                (true, _) => String::new(),
+                (false, true) => format!(" [{}]", gate.await_type),
+                (false, false) => format!(" [{} {}]", gate.await_type, gate.await_id),
+            };
+            println!("  gate {}{} {}", gate.id, await_info, gate.title);
+        }
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Bond
+// ---------------------------------------------------------------------------
+
+/// Attaches `args.other` to `args.id`: sequential bonds make the other
+/// molecule's first steps wait on the bond point (default: the last steps
+/// of the first molecule), conditional bonds do the same with
+/// `conditional-blocks`, and parallel bonds only record the lineage.
+fn cmd_bond(ctx: &RuntimeContext, args: &MolBondArgs) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot bond molecules in read-only mode");
+    }
+    let dep_type = match args.bond_type.as_str() {
+        bond_type::SEQUENTIAL => Some(DependencyType::Blocks),
+        bond_type::CONDITIONAL => Some(DependencyType::ConditionalBlocks),
+        bond_type::PARALLEL => None,
+        other => bail!(
+            "invalid bond type '{}': use sequential, parallel or conditional",
+            other
+        ),
+    };
+
+    let store = ctx.store()?;
+    let target = load_molecule(store, &args.id)?;
+    let other = load_molecule(store, &args.other)?;
+    if target.root.id == other.root.id {
+        bail!("cannot bond molecule {} to itself", target.root.id);
+    }
+    if target
+        .root
+        .bonded_from
+        .iter()
+        .any(|b| b.source_id == other.root.id)
+    {
         bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
+            "molecule {} is already bonded to {}",
+            other.root.id,
+            target.root.id
         );
     }
 
-    rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))
+    let points = match args.at.as_deref() {
+        Some(step) if target.steps.iter().any(|s| s.id == step) => vec![step.to_string()],
+        Some(step) => bail!("{} is not a step of molecule {}", step, target.root.id),
+        None => boundary_steps(store, &target.steps, false)?,
+    };
+    let entries = boundary_steps(store, &other.steps, true)?;
+
+    let now = Utc::now();
+    let mut edges = Vec::new();
+    if let Some(ref dep_type) = dep_type {
+        for entry in &entries {
+            for point in &points {
+                edges.push(Dependency {
+                    issue_id: entry.clone(),
+                    depends_on_id: point.clone(),
+                    dep_type: dep_type.clone(),
+                    created_at: now,
+                    created_by: ctx.actor.clone(),
+                    metadata: String::new(),
+                    thread_id: String::new(),
+                });
+            }
+        }
+    }
+
+    let bond = BondRef {
+        source_id: other.root.id.clone(),
+        bond_type: args.bond_type.clone(),
+        bond_point: args.at.clone().unwrap_or_default(),
+    };
+    let mut bonded_from = target.root.bonded_from.clone();
+    bonded_from.push(bond.clone());
+    let updates = IssueUpdates {
+        bonded_from: Some(bonded_from),
+        ..Default::default()
+    };
+
+    store.run_in_transaction(&|tx| {
+        for dep in &edges {
+            tx.add_dependency(dep, &ctx.actor)?;
+        }
+        tx.update_issue(&target.root.id, &updates, &ctx.actor)
+    })?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "molecule": target.root.id,
+            "bond": bond,
+            "edges": edges
+                .iter()
+                .map(|d| serde_json::json!({"issue_id": d.issue_id, "depends_on_id": d.depends_on_id, "type": d.dep_type}))
+                .collect::<Vec<_>>(),
+        }));
+    } else if !ctx.quiet {
+        println!(
+            "Bonded {} onto {} ({}, {} edges)",
+            other.root.id,
+            target.root.id,
+            args.bond_type,
+            edges.len()
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Squash
+// ---------------------------------------------------------------------------
+
+/// Replaces a molecule's steps by a digest on its root. Edges from other
+/// issues onto the steps move to the root, a finished molecule's root is
+/// closed, and a wisp's root is kept as a persistent issue.
+fn cmd_squash(ctx: &RuntimeContext, args: &MolSquashArgs) -> Result<()> {
+    if ctx.readonly {
+        bail!("cannot squash molecules in read-only mode");
+    }
+    let store = ctx.store()?;
+    let molecule = load_molecule(store, &args.id)?;
+    let root = &molecule.root;
+    if molecule.steps.is_empty() {
+        bail!("molecule {} has no steps to squash", root.id);
+    }
+    let open = molecule
+        .steps
+        .iter()
+        .filter(|s| s.status != Status::Closed)
+        .count();
+    if open > 0 && !args.force {
+        bail!(
+            "{} of {} steps of molecule {} are not closed.\n\
+            Use --force to squash it anyway",
+            open,
+            molecule.steps.len(),
+            root.id
+        );
+    }
+
+    let step_ids: HashSet<&str> = molecule.steps.iter().map(|s| s.id.as_str()).collect();
+    let now = Utc::now();
+    let mut redirected: Vec<Dependency> = Vec::new();
+    let mut seen = HashSet::new();
+    for step in &molecule.steps {
+        for edge in store.get_dependents_with_metadata(&step.id)? {
+            let from = edge.issue.id;
+            if from == root.id || step_ids.contains(from.as_str()) {
+                continue;
+            }
+            let already = store
+                .get_dependency_records(&from)?
+                .iter()
+                .any(|d| d.depends_on_id == root.id);
+            if !already && seen.insert(from.clone()) {
+                redirected.push(Dependency {
+                    issue_id: from,
+                    depends_on_id: root.id.clone(),
+                    created_at: now,
+                    ..edge.dependency
+                });
+            }
+        }
+    }
+
+    let mut digest = root.description.trim_end().to_string();
+    if !digest.is_empty() {
+        digest.push_str("\n\n");
+    }
+    digest.push_str(&format!("Squashed {} steps:\n", molecule.steps.len()));
+    for step in &molecule.steps {
+        let mark = if step.status == Status::Closed {
+            "x"
+        } else {
+            " "
+        };
+        digest.push_str(&format!("- [{}] {}: {}", mark, step.id, step.title));
+        if !step.close_reason.is_empty() {
+            digest.push_str(&format!(" ({})", step.close_reason));
+        }
+        digest.push('\n');
+    }
+
+    let mut updates = IssueUpdates {
+        description: Some(digest),
+        ..Default::default()
+    };
+    if open == 0 {
+        updates.status = Some(Status::Closed);
+        updates.close_reason = Some(format!("Squashed {} steps", molecule.steps.len()));
+    }
+    let was_wisp = root.ephemeral;
+    if was_wisp {
+        updates.ephemeral = Some(false);
+        updates.wisp_type = Some(String::new());
+    }
+    let drop_label = store.get_labels(&root.id)?.iter().any(|l| l == "ephemeral");
+
+    store.run_in_transaction(&|tx| {
+        for step in &molecule.steps {
+            tx.delete_issue(&step.id)?;
+        }
+        for dep in &redirected {
+            tx.add_dependency(dep, &ctx.actor)?;
+        }
+        tx.update_issue(&root.id, &updates, &ctx.actor)?;
+        if drop_label {
+            tx.remove_label(&root.id, "ephemeral", &ctx.actor)?;
+        }
+        Ok(())
+    })?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "molecule": root.id,
+            "squashed": molecule.steps.iter().map(|s| &s.id).collect::<Vec<_>>(),
+            "closed": open == 0,
+            "redirected": redirected.len(),
+        }));
+    } else if !ctx.quiet {
+        println!(
+            "Squashed {} steps of molecule {} into its root{}",
+            molecule.steps.len(),
+            root.id,
+            if open == 0 { " (closed)" } else { "" }
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Burn
+// ---------------------------------------------------------------------------
+
+fn cmd_burn(ctx: &RuntimeContext, args: &MolBurnArgs) -> Result<()> {
+    if ctx.readonly && !args.dry_run {
+        bail!("cannot delete issues in read-only mode");
+    }
+    let store = ctx.store()?;
+    let molecule = load_molecule(store, &args.id)?;
+    let ids: Vec<String> = molecule
+        .steps
+        .iter()
+        .map(|s| s.id.clone())
+        .chain(std::iter::once(molecule.root.id.clone()))
+        .collect();
+
+    if args.dry_run {
+        if ctx.json {
+            output_json(&serde_json::json!({
+                "molecule": molecule.root.id,
+                "would_delete": ids,
+            }));
+        } else {
+            println!(
+                "Would burn molecule {} ({} issues): {}",
+                molecule.root.id,
+                ids.len(),
+                ids.join(", ")
+            );
+        }
+        return Ok(());
+    }
+    if !args.force {
+        bail!(
+            "burning a molecule deletes its root and every step and cannot be undone.\n\
+            Use --force to confirm deletion of {} issue(s): {}",
+            ids.len(),
+            ids.join(", ")
+        );
+    }
+
+    // Labels, comments, events and edges go with each issue
+    store.run_in_transaction(&|tx| {
+        for id in &ids {
+            tx.delete_issue(id)?;
+        }
+        Ok(())
+    })?;
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "molecule": molecule.root.id,
+            "deleted": ids,
+        }));
+    } else if !ctx.quiet {
+        println!(
+            "Burned molecule {}: deleted {} issues",
+            molecule.root.id,
+            ids.len()
+        );
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Distill
+// ---------------------------------------------------------------------------
+
+fn cmd_distill(ctx: &RuntimeContext, args: &MolDistillArgs) -> Result<()> {
+    let store = ctx.store()?;
+    let issue = match store.get_issue(&args.id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", args.id),
+        Err(e) => return Err(e.into()),
+    };
+
+    let (issues, mut vars) = if is_root(store, &issue.id)? {
+        let molecule = load_molecule(store, &issue.id)?;
+        (molecule.steps, root_vars(&molecule.root))
+    } else {
+        (formula::descendants(store, &issue.id)?, HashMap::new())
+    };
+    if issues.is_empty() {
+        bail!("{} has no steps or children to distill", issue.id);
+    }
+    vars.extend(parse_var_flags(&args.vars)?);
+
+    let name = match args.name {
+        Some(ref name) => name.clone(),
+        None => formula::unique_step_id(&issue.title, &mut HashSet::new()),
+    };
+    formula::validate_name(&name)?;
+
+    let mut distilled = formula::formula_from_issues(store, &name, &issue.title, &issues)?;
+    parameterize(&mut distilled, &vars);
+    let content = format!(
+        "# Distilled from {}.\n{}",
+        issue.id,
+        parser::to_toml(&distilled).map_err(|e| anyhow!("{}", e))?
+    );
+
+    match args.output {
+        Some(ref path) => {
+            std::fs::write(path, &content).with_context(|| format!("failed to write {}", path))?;
+            if ctx.json {
+                output_json(&serde_json::json!({
+                    "name": name,
+                    "path": path,
+                    "steps": distilled.steps.len(),
+                    "vars": distilled.vars.keys().collect::<Vec<_>>(),
+                }));
+            } else if !ctx.quiet {
+                println!(
+                    "Distilled formula {} with {} steps: {}",
+                    name,
+                    distilled.steps.len(),
+                    path
+                );
+            }
+        }
+        None if ctx.json => output_json(&serde_json::json!({
+            "name": name,
+            "steps": distilled.steps.len(),
+            "vars": distilled.vars.keys().collect::<Vec<_>>(),
+            "formula": content,
+        })),
+        None => print!("{content}"),
+    }
+    Ok(())
+}
+
+/// Replaces each variable's value in step titles, descriptions and
+/// assignees by `{{name}}` and declares the variable with the value as its
+/// default. Longer values go first so a value containing another stays
+/// whole.
+fn parameterize(formula: &mut Formula, vars: &HashMap<String, String>) {
+    let mut vars: Vec<(&String, &String)> = vars.iter().filter(|(_, v)| !v.is_empty()).collect();
+    vars.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then(a.0.cmp(b.0)));
+    for (name, value) in vars {
+        let placeholder = format!("{{{{{name}}}}}");
+        for step in &mut formula.steps {
+            step.title = replace_word(&step.title, value, &placeholder);
+            step.description = replace_word(&step.description, value, &placeholder);
+            if let Some(ref mut assignee) = step.assignee {
+                *assignee = replace_word(assignee, value, &placeholder);
+            }
+        }
+        formula.vars.insert(
+            name.clone(),
+            VarDef {
+                description: String::new(),
+                required: false,
+                default: Some(value.clone()),
+                r#type: VarType::String,
+                values: Vec::new(),
+            },
+        );
+    }
+}
+
+/// Replaces the occurrences of `word` in `text` that are not part of a
+/// longer word.
+fn replace_word(text: &str, word: &str, with: &str) -> String {
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find(word) {
+        let before = rest[..pos].chars().last().or_else(|| out.chars().last());
+        let after = rest[pos + word.len()..].chars().next();
+        out.push_str(&rest[..pos]);
+        if is_word(before) || is_word(after) {
+            out.push_str(word);
+        } else {
+            out.push_str(with);
+        }
+        rest = &rest[pos + word.len()..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_word_respects_boundaries() {
+        assert_eq!(
+            replace_word("Review api (rapid api)", "api", "{{m}}"),
+            "Review {{m}} (rapid {{m}})"
+        );
+        assert_eq!(
+            replace_word("apis and api_v2", "api", "X"),
+            "apis and api_v2"
+        );
+        assert_eq!(replace_word("api", "api", "X"), "X");
+    }
+
+    #[test]
+    fn pour_labels_are_recognised() {
+        assert!(is_pour_label("molecule"));
+        assert!(is_pour_label(&step_label("t-abc")));
+        assert!(is_pour_label("formula:release"));
+        assert!(!is_pour_label("backend"));
+    }
 }
@@ -1,65 +1,50 @@
 //! `bd orphans` -- show issues with no dependencies.
 
-use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+
+use anyhow::Result;
+
+use beads_core::enums::Status;
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
 
 use crate::cli::OrphansArgs;
+use crate::commands::list::{listing_filter, sort_issues};
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
 /// Execute the `bd orphans` command.
 pub fn run(ctx: &RuntimeContext, _args: &OrphansArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+    let store = ctx.store()?;
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
+    // Issues that appear on either end of any dependency edge
+    let mut linked: HashSet<String> = HashSet::new();
+    for dep in store.get_all_dependency_records()?.into_values().flatten() {
+        linked.insert(dep.issue_id);
+        linked.insert(dep.depends_on_id);
     }
 
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.status, i.priority, i.assignee \
-         FROM issues i \
-         WHERE i.id NOT IN (SELECT issue_id FROM dependencies) \
-           AND i.id NOT IN (SELECT depends_on_id FROM dependencies) \
-           AND COALESCE(i.is_template, 0) = 0 \
-           AND i.issue_type != 'gate' \
-           AND i.status != 'closed' \
-         ORDER BY i.priority ASC, i.created_at DESC",
-    )?;
-
-    let orphans: Vec<(String, String, String, i32, String)> = stmt
-        .query_map([], |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get::<_, String>(4).unwrap_or_default(),
-            ))
-        })?
-        .filter_map(|r| r.ok())
+    let filter = IssueFilter {
+        exclude_status: vec![Status::Closed],
+        ..listing_filter()
+    };
+    let mut orphans: Vec<Issue> = store
+        .search_issues("", &filter)?
+        .into_iter()
+        .filter(|i| !linked.contains(&i.id))
         .collect();
+    sort_issues(&mut orphans, None, false);
 
     if ctx.json {
         let json_orphans: Vec<serde_json::Value> = orphans
             .iter()
-            .map(|(id, title, status, priority, assignee)| {
+            .map(|i| {
                 serde_json::json!({
-                    "id": id,
-                    "title": title,
-                    "status": status,
-                    "priority": priority,
-                    "assignee": assignee,
+                    "id": i.id,
+                    "title": i.title,
+                    "status": i.status.as_str(),
+                    "priority": i.priority,
+                    "assignee": i.assignee,
                 })
             })
             .collect();
@@ -77,13 +62,13 @@ pub fn run(ctx: &RuntimeContext, _args: &OrphansArgs) -> Result<()> {
         let headers = &["ID", "PRI", "STATUS", "TITLE", "ASSIGNEE"];
         let rows: Vec<Vec<String>> = orphans
             .iter()
-            .map(|(id, title, status, priority, assignee)| {
+            .map(|i| {
                 vec![
-                    id.clone(),
-                    format!("P{}", priority),
-                    status.clone(),
-                    title.clone(),
-                    assignee.clone(),
+                    i.id.clone(),
+                    format!("P{}", i.priority),
+                    i.status.as_str().to_string(),
+                    i.title.clone(),
+                    i.assignee.clone(),
                 ]
             })
             .collect();
@@ -2,6 +2,10 @@
 //!
 //! Runs an arbitrary SQL query in read-only mode and prints results
 //! as an aligned table (or JSON with `--json`).
+//!
+//! This is the one command that deliberately bypasses the `Storage` trait:
+//! it is an escape hatch for inspecting the SQLite schema directly, so it
+//! opens its own read-only connection and never writes.
 
 use anyhow::{Context, Result, bail};
 use rusqlite::types::Value;
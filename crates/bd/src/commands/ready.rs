@@ -1,173 +1,86 @@
 //! `bd ready` -- show ready work items (open, no active blockers).
 
 use anyhow::{Context, Result, bail};
-use chrono::{DateTime, Utc};
 
-use beads_core::enums::{IssueType, Status};
-use beads_core::issue::Issue;
+use beads_core::enums::{IssueType, SortPolicy};
+use beads_core::filter::WorkFilter;
+use beads_storage::{SkippedIssue, Storage};
 
 use crate::cli::ReadyArgs;
+use crate::commands::list::{matching_ids, parse_query, split_labels};
 use crate::context::RuntimeContext;
 use crate::output::output_json;
 
 /// Execute the `bd ready` command.
 pub fn run(ctx: &RuntimeContext, args: &ReadyArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    // Ready work = open issues with no open blocking dependencies.
-    // This query:
-    // 1. Selects open issues
-    // 2. Excludes issues that have open blockers (via LEFT JOIN + IS NULL)
-    // 3. Excludes templates, gates, and deferred issues
-    let mut conditions: Vec<String> = vec![
-        "i.status = 'open'".to_string(),
-        "COALESCE(i.is_template, 0) = 0".to_string(),
-        "i.issue_type != 'gate'".to_string(),
-        "COALESCE(i.ephemeral, 0) = 0".to_string(),
-        "(i.defer_until IS NULL OR i.defer_until <= datetime('now'))".to_string(),
-    ];
-    let mut params: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
-
-    // Assignee filter
-    if args.unassigned {
-        conditions.push("(i.assignee = '' OR i.assignee IS NULL)".to_string());
-    } else if let Some(ref assignee) = args.assignee {
-        conditions.push(format!("i.assignee = ?{}", params.len() + 1));
-        params.push(Box::new(assignee.clone()));
-    }
-
-    // Priority filter
-    if let Some(priority) = args.priority {
-        conditions.push(format!("i.priority = ?{}", params.len() + 1));
-        params.push(Box::new(priority));
-    }
-
-    // Type filter
-    if let Some(ref t) = args.issue_type {
-        let normalized = IssueType::from(t.as_str()).normalize();
-        conditions.push(format!("i.issue_type = ?{}", params.len() + 1));
-        params.push(Box::new(normalized.as_str().to_string()));
+    let store = ctx.store()?;
+    let query = parse_query(args.query.as_deref())?;
+    if let Some(ref epic) = args.epic {
+        match store.get_issue(epic) {
+            Err(e) if e.is_not_found() => bail!("issue '{}' not found", epic),
+            other => other?,
+        };
     }
 
-    let where_clause = format!("WHERE {}", conditions.join(" AND "));
-
-    // Sort policy
-    let order_clause = match args.sort.as_str() {
-        "priority" => "ORDER BY i.priority ASC, i.created_at ASC",
-        "oldest" => "ORDER BY i.created_at ASC",
-        _ => "ORDER BY i.priority ASC, i.created_at ASC",
+    // Ready work = open issues with no open blocking dependencies,
+    // excluding templates, gates, wisps and deferred issues. Molecule steps
+    // are work too.
+    let filter = WorkFilter {
+        issue_type: args
+            .issue_type
+            .as_deref()
+            .map(|t| IssueType::from(t).normalize().as_str().to_string()),
+        priority: args.priority,
+        assignee: if args.unassigned {
+            None
+        } else {
+            args.assignee.clone()
+        },
+        unassigned: args.unassigned,
+        labels: split_labels(&args.labels),
+        limit: (args.limit > 0).then_some(args.limit),
+        sort_policy: SortPolicy::from(args.sort.as_str()),
+        parent_id: args.epic.clone(),
+        include_mol_steps: true,
+        ..WorkFilter::default()
     };
-
-    let limit_clause = if args.limit > 0 {
-        format!("LIMIT {}", args.limit)
-    } else {
-        String::new()
+    let issues = match query {
+        None => store.get_ready_work(&filter)?,
+        Some(ref query) => match query.to_work_filter(&filter) {
+            Some(filter) => store.get_ready_work(&filter)?,
+            None => {
+                // Not expressible as a WorkFilter: take all ready work, keep
+                // what matches the query, then apply the limit.
+                let all = store.get_ready_work(&WorkFilter {
+                    limit: None,
+                    ..filter.clone()
+                })?;
+                let ids = all.iter().map(|i| i.id.clone()).collect();
+                let matching = matching_ids(store, query, ids)?;
+                let mut issues: Vec<_> = all
+                    .into_iter()
+                    .filter(|i| matching.contains(&i.id))
+                    .collect();
+                if let Some(limit) = filter.limit {
+                    issues.truncate(limit as usize);
+                }
+                issues
+            }
+        },
     };
 
-    // Query: open issues with no open blocking dependencies
-    let sql = format!(
-        "SELECT i.id, i.title, i.description, i.design, i.acceptance_criteria, i.notes, i.spec_id, \
-         i.status, i.priority, i.issue_type, i.assignee, i.owner, i.estimated_minutes, \
-         i.created_at, i.created_by, i.updated_at, i.closed_at, i.close_reason, \
-         i.due_at, i.defer_until, i.external_ref \
-         FROM issues i \
-         LEFT JOIN (\
-             SELECT d.issue_id \
-             FROM dependencies d \
-             JOIN issues blocker ON d.depends_on_id = blocker.id \
-             WHERE d.type IN ('blocks', 'parent-child') AND blocker.status != 'closed'\
-         ) blocked ON i.id = blocked.issue_id \
-         {} AND blocked.issue_id IS NULL \
-         {} {}",
-        where_clause, order_clause, limit_clause
-    );
-
-    let mut stmt = conn.prepare(&sql)?;
-    let param_refs: Vec<&dyn rusqlite::types::ToSql> = params.iter().map(|p| p.as_ref()).collect();
-
-    let issues: Vec<Issue> = stmt
-        .query_map(param_refs.as_slice(), |row| {
-            let status_str: String = row.get(7)?;
-            let type_str: String = row.get(9)?;
-            let created_at_str: String = row.get(13)?;
-            let updated_at_str: String = row.get(15)?;
-            let closed_at_str: Option<String> = row.get(16)?;
-            let due_at_str: Option<String> = row.get(18)?;
-            let defer_until_str: Option<String> = row.get(19)?;
-
-            Ok(Issue {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get::<_, String>(2).unwrap_or_default(),
-                design: row.get::<_, String>(3).unwrap_or_default(),
-                acceptance_criteria: row.get::<_, String>(4).unwrap_or_default(),
-                notes: row.get::<_, String>(5).unwrap_or_default(),
-                spec_id: row.get::<_, String>(6).unwrap_or_default(),
-                status: Status::from(status_str.as_str()),
-                priority: row.get(8)?,
-                issue_type: IssueType::from(type_str.as_str()),
-                assignee: row.get::<_, String>(10).unwrap_or_default(),
-                owner: row.get::<_, String>(11).unwrap_or_default(),
-                estimated_minutes: row.get(12)?,
-                created_at: parse_datetime(&created_at_str),
-                created_by: row.get::<_, String>(14).unwrap_or_default(),
-                updated_at: parse_datetime(&updated_at_str),
-                closed_at: closed_at_str.as_deref().map(parse_datetime),
-                close_reason: row.get::<_, String>(17).unwrap_or_default(),
-                due_at: due_at_str.as_deref().map(parse_datetime),
-                defer_until: defer_until_str.as_deref().map(parse_datetime),
-                external_ref: row.get(20)?,
-                ..Issue::default()
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    // Apply label filter in-memory
-    let issues = if args.labels.is_empty() {
-        issues
-    } else {
-        let filter_labels: Vec<String> = args
-            .labels
-            .iter()
-            .flat_map(|l| l.split(','))
-            .map(|l| l.trim().to_string())
-            .filter(|l| !l.is_empty())
-            .collect();
-
-        if filter_labels.is_empty() {
-            issues
-        } else {
-            issues
-                .into_iter()
-                .filter(|issue| {
-                    let labels: Vec<String> = conn
-                        .prepare("SELECT label FROM labels WHERE issue_id = ?1")
-                        .and_then(|mut stmt| {
-                            stmt.query_map(rusqlite::params![&issue.id], |row| row.get(0))
-                                .map(|rows| rows.filter_map(|r| r.ok()).collect())
-                        })
-                        .unwrap_or_default();
-                    filter_labels.iter().all(|fl| labels.contains(fl))
-                })
-                .collect()
+    // Conditional dependents whose blocker succeeded will never run.
+    let skip_filter = WorkFilter {
+        limit: None,
+        ..filter.clone()
+    };
+    let skipped = if args.close_skipped {
+        if ctx.readonly {
+            bail!("cannot close skipped issues in read-only mode");
         }
+        close_skipped(ctx, store, &skip_filter)?
+    } else {
+        store.get_skipped_issues(&skip_filter)?
     };
 
     // Output
@@ -179,6 +92,7 @@ pub fn run(ctx: &RuntimeContext, args: &ReadyArgs) -> Result<()> {
 
     if issues.is_empty() {
         println!("\nNo ready work found\n");
+        print_skipped(&skipped, args.close_skipped);
         return Ok(());
     }
 
@@ -204,13 +118,53 @@ pub fn run(ctx: &RuntimeContext, args: &ReadyArgs) -> Result<()> {
         }
     }
     println!();
+    print_skipped(&skipped, args.close_skipped);
 
     Ok(())
 }
 
-/// Parse a datetime string (RFC3339) into a `DateTime<Utc>`.
-fn parse_datetime(s: &str) -> DateTime<Utc> {
-    DateTime::parse_from_rfc3339(s)
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or_else(|_| Utc::now())
+/// Closes skipped issues, naming the blocker that skipped each. Skipping
+/// already cascades down conditional chains, so one pass closes them all.
+fn close_skipped(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    filter: &WorkFilter,
+) -> Result<Vec<SkippedIssue>> {
+    let skipped = store.get_skipped_issues(filter)?;
+    for s in &skipped {
+        let reason = format!("skipped: {} closed without failing", s.blocker_id);
+        store
+            .close_issue(&s.issue.id, &reason, &ctx.actor, "")
+            .with_context(|| format!("failed to close skipped issue {}", s.issue.id))?;
+    }
+    Ok(skipped)
+}
+
+/// Lists skipped issues after the ready work.
+fn print_skipped(skipped: &[SkippedIssue], closed: bool) {
+    if skipped.is_empty() {
+        return;
+    }
+    let s = if skipped.len() == 1 { "" } else { "s" };
+    if closed {
+        println!("Closed {} skipped issue{}:\n", skipped.len(), s);
+    } else {
+        println!(
+            "Skipped ({} issue{} whose conditional blocker succeeded; close with --close-skipped):\n",
+            skipped.len(),
+            s
+        );
+    }
+    for s in skipped {
+        let reason = if s.blocker_close_reason.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", s.blocker_close_reason)
+        };
+        println!(
+            "  {}: {} ({} closed{})",
+            s.issue.id, s.issue.title, s.blocker_id, reason
+        );
+    }
+    println!();
 }
@@ -1,14 +1,13 @@
 //! `bd reopen` -- reopen a closed issue.
 
 use anyhow::{Context, Result, bail};
-use chrono::Utc;
 
-use beads_core::enums::{IssueType, Status};
-use beads_core::issue::Issue;
+use beads_core::enums::Status;
+use beads_storage::IssueUpdates;
 
 use crate::cli::ReopenArgs;
 use crate::context::RuntimeContext;
-use crate::output::{load_labels, output_json};
+use crate::output::{load_issue, output_json};
 
 /// Execute the `bd reopen` command.
 pub fn run(ctx: &RuntimeContext, args: &ReopenArgs) -> Result<()> {
@@ -16,62 +15,33 @@ pub fn run(ctx: &RuntimeContext, args: &ReopenArgs) -> Result<()> {
         bail!("cannot reopen issues in read-only mode");
     }
 
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+    let store = ctx.store()?;
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open(&db_path)
-        .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    let issue = match store.get_issue(&args.id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", args.id),
+        Err(e) => return Err(e.into()),
+    };
 
-    // Check issue exists and get current status
-    let current_status: String = conn
-        .query_row(
-            "SELECT status FROM issues WHERE id = ?1",
-            rusqlite::params![&args.id],
-            |row| row.get(0),
-        )
-        .with_context(|| format!("issue '{}' not found", args.id))?;
-
-    if current_status != "closed" {
+    if issue.status != Status::Closed {
         bail!(
             "issue '{}' is not closed (current status: {})",
             args.id,
-            current_status
+            issue.status
         );
     }
 
-    let now_str = Utc::now().to_rfc3339();
-
-    // Reopen the issue
-    conn.execute(
-        "UPDATE issues SET status = 'open', closed_at = NULL, close_reason = '', updated_at = ?1 WHERE id = ?2",
-        rusqlite::params![&now_str, &args.id],
-    )
-    .with_context(|| format!("failed to reopen issue {}", args.id))?;
-
-    // Record a "reopened" event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, old_value, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![
-            &args.id,
-            "reopened",
-            &ctx.actor,
-            "closed",
-            "open",
-            &now_str,
-        ],
-    )?;
+    // Moving off closed clears closed_at/close_reason and records "reopened"
+    let updates = IssueUpdates {
+        status: Some(Status::Open),
+        ..Default::default()
+    };
+    store
+        .update_issue(&args.id, &updates, &ctx.actor)
+        .with_context(|| format!("failed to reopen issue {}", args.id))?;
 
     if ctx.json {
-        let issue = load_issue_by_id(&conn, &args.id)?;
+        let issue = load_issue(store, &args.id)?;
         output_json(&vec![issue]);
     } else if !ctx.quiet {
         println!("Reopened {}", args.id);
@@ -79,59 +49,3 @@ pub fn run(ctx: &RuntimeContext, args: &ReopenArgs) -> Result<()> {
 
     Ok(())
 }
-
-/// Load an issue by ID, including labels.
-fn load_issue_by_id(conn: &rusqlite::Connection, id: &str) -> Result<Issue> {
-    let mut stmt = conn.prepare(
-        "SELECT id, title, description, design, acceptance_criteria, notes, spec_id, \
-         status, priority, issue_type, assignee, owner, estimated_minutes, \
-         created_at, created_by, updated_at, closed_at, close_reason, \
-         due_at, defer_until, external_ref \
-         FROM issues WHERE id = ?1",
-    )?;
-
-    let mut issue: Issue = stmt.query_row(rusqlite::params![id], |row| {
-        let status_str: String = row.get(7)?;
-        let type_str: String = row.get(9)?;
-        let created_at_str: String = row.get(13)?;
-        let updated_at_str: String = row.get(15)?;
-        let closed_at_str: Option<String> = row.get(16)?;
-        let due_at_str: Option<String> = row.get(18)?;
-        let defer_until_str: Option<String> = row.get(19)?;
-
-        Ok(Issue {
-            id: row.get(0)?,
-            title: row.get(1)?,
-            description: row.get::<_, String>(2).unwrap_or_default(),
-            design: row.get::<_, String>(3).unwrap_or_default(),
-            acceptance_criteria: row.get::<_, String>(4).unwrap_or_default(),
-            notes: row.get::<_, String>(5).unwrap_or_default(),
-            spec_id: row.get::<_, String>(6).unwrap_or_default(),
-            status: Status::from(status_str.as_str()),
-            priority: row.get(8)?,
-            issue_type: IssueType::from(type_str.as_str()),
-            assignee: row.get::<_, String>(10).unwrap_or_default(),
-            owner: row.get::<_, String>(11).unwrap_or_default(),
-            estimated_minutes: row.get(12)?,
-            created_at: parse_datetime(&created_at_str),
-            created_by: row.get::<_, String>(14).unwrap_or_default(),
-            updated_at: parse_datetime(&updated_at_str),
-            closed_at: closed_at_str.as_deref().map(parse_datetime),
-            close_reason: row.get::<_, String>(17).unwrap_or_default(),
-            due_at: due_at_str.as_deref().map(parse_datetime),
-            defer_until: defer_until_str.as_deref().map(parse_datetime),
-            external_ref: row.get(20)?,
-            ..Issue::default()
-        })
-    })?;
-
-    issue.labels = load_labels(conn, id);
-    Ok(issue)
-}
-
-/// Parse a datetime string (RFC3339) into a `DateTime<Utc>`.
-fn parse_datetime(s: &str) -> chrono::DateTime<chrono::Utc> {
-    chrono::DateTime::parse_from_rfc3339(s)
-        .map(|dt| dt.with_timezone(&chrono::Utc))
-        .unwrap_or_else(|_| chrono::Utc::now())
-}
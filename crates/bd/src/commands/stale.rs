@@ -1,6 +1,10 @@
 //! `bd stale` -- show issues not updated in N days.
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Result, bail};
+use chrono::{Duration, Utc};
+
+use beads_core::filter::StaleFilter;
+use beads_timeparsing::{Unit, format_duration, parse_duration_in};
 
 use crate::cli::StaleArgs;
 use crate::context::RuntimeContext;
@@ -8,62 +12,31 @@ use crate::output::{output_json, output_table};
 
 /// Execute the `bd stale` command.
 pub fn run(ctx: &RuntimeContext, args: &StaleArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
+    let store = ctx.store()?;
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
+    let age = parse_duration_in(&args.days, Unit::Day)?;
+    if age < Duration::zero() {
+        bail!("--days must not be negative (got '{}')", args.days);
     }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let days = args.days;
-    let threshold = format!("-{} days", days);
-
-    let mut stmt = conn.prepare(
-        "SELECT id, title, status, priority, assignee, updated_at \
-         FROM issues \
-         WHERE status NOT IN ('closed') \
-           AND COALESCE(is_template, 0) = 0 \
-           AND issue_type != 'gate' \
-           AND updated_at < datetime('now', ?1) \
-         ORDER BY updated_at ASC",
-    )?;
-
-    let issues: Vec<(String, String, String, i32, String, String)> = stmt
-        .query_map(rusqlite::params![&threshold], |row| {
-            Ok((
-                row.get(0)?,
-                row.get(1)?,
-                row.get(2)?,
-                row.get(3)?,
-                row.get::<_, String>(4).unwrap_or_default(),
-                row.get::<_, String>(5).unwrap_or_default(),
-            ))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let days = age.num_days();
+    let filter = StaleFilter {
+        days: i32::try_from(days)?,
+        updated_before: Some(Utc::now() - age),
+        ..Default::default()
+    };
+    let issues = store.get_stale_issues(&filter)?;
 
     if ctx.json {
         let json_issues: Vec<serde_json::Value> = issues
             .iter()
-            .map(|(id, title, status, priority, assignee, updated_at)| {
+            .map(|issue| {
                 serde_json::json!({
-                    "id": id,
-                    "title": title,
-                    "status": status,
-                    "priority": priority,
-                    "assignee": assignee,
-                    "updated_at": updated_at,
+                    "id": issue.id,
+                    "title": issue.title,
+                    "status": issue.status,
+                    "priority": issue.priority,
+                    "assignee": issue.assignee,
+                    "updated_at": issue.updated_at.to_rfc3339(),
                 })
             })
             .collect();
@@ -74,32 +47,29 @@ pub fn run(ctx: &RuntimeContext, args: &StaleArgs) -> Result<()> {
         }));
     } else {
         if issues.is_empty() {
-            println!("No stale issues (updated within last {} days)", days);
+            println!(
+                "No stale issues (updated within last {})",
+                describe_age(age)
+            );
             return Ok(());
         }
 
         println!(
-            "Stale issues (not updated in {} days): {}\n",
-            days,
+            "Stale issues (not updated in {}): {}\n",
+            describe_age(age),
             issues.len()
         );
         let headers = &["ID", "PRI", "STATUS", "TITLE", "ASSIGNEE", "UPDATED"];
         let rows: Vec<Vec<String>> = issues
             .iter()
-            .map(|(id, title, status, priority, assignee, updated_at)| {
-                // Truncate updated_at to date only
-                let date = if updated_at.len() >= 10 {
-                    &updated_at[..10]
-                } else {
-                    updated_at
-                };
+            .map(|issue| {
                 vec![
-                    id.clone(),
-                    format!("P{}", priority),
-                    status.clone(),
-                    title.clone(),
-                    assignee.clone(),
-                    date.to_string(),
+                    issue.id.clone(),
+                    format!("P{}", issue.priority),
+                    issue.status.to_string(),
+                    issue.title.clone(),
+                    issue.assignee.clone(),
+                    issue.updated_at.format("%Y-%m-%d").to_string(),
                 ]
             })
             .collect();
@@ -108,3 +78,13 @@ pub fn run(ctx: &RuntimeContext, args: &StaleArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// A staleness threshold for messages: `N days`, or the duration itself
+/// (`12h`) when it is not a whole number of days.
+pub(crate) fn describe_age(age: Duration) -> String {
+    if age == Duration::days(age.num_days()) {
+        format!("{} days", age.num_days())
+    } else {
+        format_duration(age)
+    }
+}
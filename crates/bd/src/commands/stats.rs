@@ -1,85 +1,45 @@
 //! `bd stats` -- show project statistics.
 
-use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use anyhow::Result;
+
+use beads_core::enums::Status;
 
 use crate::cli::StatsArgs;
+use crate::commands::list::listing_filter;
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
 /// Execute the `bd stats` command.
 pub fn run(ctx: &RuntimeContext, _args: &StatsArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
+    let issues = ctx.store()?.search_issues("", &listing_filter())?;
 
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
+    // Total counts by status
+    let total = issues.len();
+    let count_status = |status: Status| issues.iter().filter(|i| i.status == status).count();
 
-    let base_where = "WHERE COALESCE(is_template, 0) = 0 AND issue_type != 'gate'";
+    let open = count_status(Status::Open);
+    let closed = count_status(Status::Closed);
+    let in_progress = count_status(Status::InProgress);
+    let blocked = count_status(Status::Blocked);
+    let deferred = count_status(Status::Deferred);
 
-    // Total counts by status
-    let total: i64 = conn.query_row(
-        &format!("SELECT COUNT(*) FROM issues {}", base_where),
-        [],
-        |row| row.get(0),
-    )?;
-
-    let count_status = |status: &str| -> i64 {
-        conn.query_row(
-            &format!("SELECT COUNT(*) FROM issues {} AND status = ?1", base_where),
-            rusqlite::params![status],
-            |row| row.get(0),
-        )
-        .unwrap_or(0)
-    };
-
-    let open = count_status("open");
-    let closed = count_status("closed");
-    let in_progress = count_status("in_progress");
-    let blocked = count_status("blocked");
-    let deferred = count_status("deferred");
-
-    // Issues by type
-    let mut type_stmt = conn.prepare(&format!(
-        "SELECT issue_type, COUNT(*) FROM issues {} GROUP BY issue_type ORDER BY COUNT(*) DESC",
-        base_where
-    ))?;
-    let by_type: Vec<(String, i64)> = type_stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    // Issues by priority
-    let mut pri_stmt = conn.prepare(&format!(
-        "SELECT priority, COUNT(*) FROM issues {} GROUP BY priority ORDER BY priority ASC",
-        base_where
-    ))?;
-    let by_priority: Vec<(i32, i64)> = pri_stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
-        .collect();
+    let by_type = tally(issues.iter().map(|i| i.issue_type.as_str().to_string()));
+
+    let mut by_priority = tally(issues.iter().map(|i| i.priority));
+    by_priority.sort();
 
     // Top 10 assignees
-    let mut assignee_stmt = conn.prepare(&format!(
-        "SELECT CASE WHEN assignee = '' THEN '(unassigned)' ELSE assignee END, COUNT(*) \
-         FROM issues {} GROUP BY assignee ORDER BY COUNT(*) DESC LIMIT 10",
-        base_where
-    ))?;
-    let by_assignee: Vec<(String, i64)> = assignee_stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
-        .filter_map(|r| r.ok())
-        .collect();
+    let mut by_assignee = tally(issues.iter().map(|i| {
+        if i.assignee.is_empty() {
+            "(unassigned)".to_string()
+        } else {
+            i.assignee.clone()
+        }
+    }));
+    by_assignee.truncate(10);
 
     if ctx.json {
         let type_map: serde_json::Map<String, serde_json::Value> = by_type
@@ -153,3 +113,14 @@ pub fn run(ctx: &RuntimeContext, _args: &StatsArgs) -> Result<()> {
 
     Ok(())
 }
+
+/// Counts occurrences of each key, most frequent first (ties by key).
+pub(crate) fn tally<K: Hash + Ord>(keys: impl IntoIterator<Item = K>) -> Vec<(K, usize)> {
+    let mut counts: HashMap<K, usize> = HashMap::new();
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+    let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
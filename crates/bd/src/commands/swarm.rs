@@ -2,12 +2,18 @@
 //!
 //! Analyzes an epic's dependency structure using topological sort (Kahn's
 //! algorithm) to compute parallelism waves, detect cycles, and show progress.
+//! Children skipped by a conditional-blocks blocker that succeeded are left
+//! out of the waves and listed separately.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Result, bail};
 use serde::Serialize;
 
+use beads_core::dependency::is_condition_skipped;
+use beads_core::enums::DependencyType;
+use beads_storage::Storage;
+
 use crate::cli::{SwarmArgs, SwarmCommands};
 use crate::context::RuntimeContext;
 use crate::output::output_json;
@@ -38,6 +44,8 @@ struct SwarmAnalysis {
     warnings: Vec<String>,
     errors: Vec<String>,
     swarmable: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<String>,
 }
 
 /// A wave of issues that can be worked on in parallel.
@@ -67,6 +75,8 @@ struct SwarmStatus {
     completed: usize,
     progress_percent: f64,
     waves: Vec<StatusWave>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    skipped: Vec<StatusIssue>,
 }
 
 /// A wave in status output with completion info.
@@ -99,20 +109,22 @@ struct ChildIssue {
 // ---------------------------------------------------------------------------
 
 fn cmd_validate(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
-    let conn = open_db(ctx)?;
+    let store = ctx.store()?;
 
     // Load and verify epic
-    let (eid, etitle, etype) = load_issue_basic(&conn, epic_id)?;
+    let (eid, etitle, etype) = load_issue_basic(store, epic_id)?;
     if etype != "epic" && etype != "molecule" {
         bail!("'{}' is not an epic (type: {})", eid, etype);
     }
 
     // Load children and blocking deps
-    let children = load_epic_children(&conn, &eid)?;
-    let blocking_deps = load_blocking_deps(&conn, &eid, &children)?;
+    let (children, skipped) = split_skipped(store, load_epic_children(store, &eid)?)?;
+    let blocking_deps = load_blocking_deps(store, &eid, &children)?;
 
     // Analyze
-    let analysis = analyze_epic(&eid, &etitle, &children, &blocking_deps);
+    let mut analysis = analyze_epic(&eid, &etitle, &children, &blocking_deps);
+    analysis.total_issues += skipped.len();
+    analysis.skipped = skipped.into_iter().map(|c| c.id).collect();
 
     if ctx.json {
         output_json(&analysis);
@@ -168,6 +180,12 @@ fn cmd_validate(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
     );
     println!("  Estimated sessions: {}", analysis.estimated_sessions);
     println!("  Critical path length: {}", analysis.critical_path_length);
+    if !analysis.skipped.is_empty() {
+        println!(
+            "  Skipped (condition not met): {}",
+            analysis.skipped.join(", ")
+        );
+    }
 
     if !analysis.warnings.is_empty() {
         println!();
@@ -201,17 +219,17 @@ fn cmd_validate(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_status(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
-    let conn = open_db(ctx)?;
+    let store = ctx.store()?;
 
     // Load and verify epic
-    let (eid, etitle, etype) = load_issue_basic(&conn, epic_id)?;
+    let (eid, etitle, etype) = load_issue_basic(store, epic_id)?;
     if etype != "epic" && etype != "molecule" {
         bail!("'{}' is not an epic (type: {})", eid, etype);
     }
 
     // Load children and blocking deps
-    let children = load_epic_children(&conn, &eid)?;
-    let blocking_deps = load_blocking_deps(&conn, &eid, &children)?;
+    let (children, skipped) = split_skipped(store, load_epic_children(store, &eid)?)?;
+    let blocking_deps = load_blocking_deps(store, &eid, &children)?;
 
     // Compute waves
     let child_ids: Vec<String> = children.iter().map(|c| c.id.clone()).collect();
@@ -250,19 +268,29 @@ fn cmd_status(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
         });
     }
 
-    let progress = if children.is_empty() {
+    // Skipped children will never run, so they count towards progress.
+    let total_issues = children.len() + skipped.len();
+    let progress = if total_issues == 0 {
         0.0
     } else {
-        (completed_total as f64 / children.len() as f64) * 100.0
+        ((completed_total + skipped.len()) as f64 / total_issues as f64) * 100.0
     };
 
     let status = SwarmStatus {
         epic_id: eid.clone(),
         epic_title: etitle.clone(),
-        total_issues: children.len(),
+        total_issues,
         completed: completed_total,
         progress_percent: progress,
         waves: status_waves,
+        skipped: skipped
+            .into_iter()
+            .map(|c| StatusIssue {
+                id: c.id,
+                title: c.title,
+                status: c.status,
+            })
+            .collect(),
     };
 
     if ctx.json {
@@ -321,6 +349,14 @@ fn cmd_status(ctx: &RuntimeContext, epic_id: &str) -> Result<()> {
         println!();
     }
 
+    if !status.skipped.is_empty() {
+        println!("Skipped (condition not met):");
+        for si in &status.skipped {
+            println!("  -  {}  {}", si.id, si.title);
+        }
+        println!();
+    }
+
     println!(
         "Overall: {}/{} complete ({:.0}%)",
         status.completed, status.total_issues, status.progress_percent
@@ -353,6 +389,7 @@ fn analyze_epic(
             warnings: vec!["Epic has no children".to_string()],
             errors: Vec::new(),
             swarmable: true,
+            skipped: Vec::new(),
         };
     }
 
@@ -470,6 +507,7 @@ fn analyze_epic(
         warnings,
         errors: errors.clone(),
         swarmable: errors.is_empty(),
+        skipped: Vec::new(),
     }
 }
 
@@ -587,106 +625,103 @@ fn detect_cycle(child_ids: &[String], depends_on: &HashMap<&str, Vec<&str>>) ->
 // Database helpers
 // ---------------------------------------------------------------------------
 
-/// Open the beads database (read-only).
-fn open_db(ctx: &RuntimeContext) -> Result<rusqlite::Connection> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))
-}
-
 /// Load basic issue info (id, title, issue_type) by ID.
-fn load_issue_basic(conn: &rusqlite::Connection, id: &str) -> Result<(String, String, String)> {
-    conn.query_row(
-        "SELECT id, title, issue_type FROM issues WHERE id = ?1",
-        rusqlite::params![id],
-        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
-    )
-    .with_context(|| format!("issue '{}' not found", id))
+fn load_issue_basic(store: &dyn Storage, id: &str) -> Result<(String, String, String)> {
+    let issue = match store.get_issue(id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", id),
+        Err(e) => return Err(e.into()),
+    };
+    let issue_type = issue.issue_type.as_str().to_string();
+    Ok((issue.id, issue.title, issue_type))
 }
 
 /// Load all child issues of an epic (via parent-child dependencies).
-fn load_epic_children(conn: &rusqlite::Connection, epic_id: &str) -> Result<Vec<ChildIssue>> {
-    let mut stmt = conn.prepare(
-        "SELECT i.id, i.title, i.priority, i.status \
-         FROM issues i \
-         JOIN dependencies d ON i.id = d.issue_id \
-         WHERE d.depends_on_id = ?1 AND d.type = 'parent-child' \
-         ORDER BY i.priority ASC, i.id ASC",
-    )?;
-
-    let children = stmt
-        .query_map(rusqlite::params![epic_id], |row| {
-            Ok(ChildIssue {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                priority: row.get(2)?,
-                status: row.get::<_, String>(3).unwrap_or_default(),
-            })
-        })?
-        .filter_map(|r| r.ok())
+fn load_epic_children(store: &dyn Storage, epic_id: &str) -> Result<Vec<ChildIssue>> {
+    let mut children: Vec<ChildIssue> = store
+        .get_dependents_with_metadata(epic_id)?
+        .into_iter()
+        .filter(|e| e.dependency.dep_type == DependencyType::ParentChild)
+        .map(|e| ChildIssue {
+            status: e.issue.status.as_str().to_string(),
+            id: e.issue.id,
+            title: e.issue.title,
+            priority: e.issue.priority,
+        })
         .collect();
+    children.sort_by(|a, b| a.priority.cmp(&b.priority).then_with(|| a.id.cmp(&b.id)));
 
     Ok(children)
 }
 
+/// Splits open children that will never run off the rest: those with a
+/// conditional-blocks blocker that closed without failing, or that is itself
+/// skipped.
+fn split_skipped(
+    store: &dyn Storage,
+    children: Vec<ChildIssue>,
+) -> Result<(Vec<ChildIssue>, Vec<ChildIssue>)> {
+    let mut conditions: Vec<(&str, Vec<(String, bool)>)> = Vec::new();
+    for child in children.iter().filter(|c| c.status != "closed") {
+        let blockers: Vec<(String, bool)> = store
+            .get_dependencies_with_metadata(&child.id)?
+            .into_iter()
+            .filter(|e| e.dependency.dep_type == DependencyType::ConditionalBlocks)
+            .map(|e| {
+                let succeeded = is_condition_skipped(&e.issue.status, &e.issue.close_reason);
+                (e.issue.id, succeeded)
+            })
+            .collect();
+        if !blockers.is_empty() {
+            conditions.push((child.id.as_str(), blockers));
+        }
+    }
+
+    // Skipping cascades through conditional chains
+    let mut skipped: HashSet<String> = HashSet::new();
+    loop {
+        let before = skipped.len();
+        for (id, blockers) in &conditions {
+            if blockers
+                .iter()
+                .any(|(blocker, succeeded)| *succeeded || skipped.contains(blocker))
+            {
+                skipped.insert(id.to_string());
+            }
+        }
+        if skipped.len() == before {
+            break;
+        }
+    }
+
+    Ok(children.into_iter().partition(|c| !skipped.contains(&c.id)))
+}
+
 /// Load blocking dependencies between children of an epic.
 ///
 /// Returns pairs `(blocker_id, blocked_id)` where blocker blocks blocked.
 fn load_blocking_deps(
-    conn: &rusqlite::Connection,
+    store: &dyn Storage,
     epic_id: &str,
     children: &[ChildIssue],
 ) -> Result<Vec<(String, String)>> {
-    let child_set: std::collections::HashSet<&str> =
-        children.iter().map(|c| c.id.as_str()).collect();
+    let child_set: HashSet<&str> = children.iter().map(|c| c.id.as_str()).collect();
 
     let mut deps = Vec::new();
 
     for child in children {
-        let mut stmt =
-            conn.prepare("SELECT depends_on_id, type FROM dependencies WHERE issue_id = ?1")?;
-
-        let rows: Vec<(String, String)> = stmt
-            .query_map(rusqlite::params![&child.id], |row| {
-                Ok((row.get(0)?, row.get::<_, String>(1).unwrap_or_default()))
-            })?
-            .filter_map(|r| r.ok())
-            .collect();
-
-        for (dep_id, dep_type) in rows {
+        for dep in store.get_dependency_records(&child.id)? {
             // Skip parent-child to epic itself
-            if dep_id == epic_id && dep_type == "parent-child" {
+            if dep.depends_on_id == epic_id && dep.dep_type == DependencyType::ParentChild {
                 continue;
             }
             // Only track blocking deps within children
-            if affects_ready_work(&dep_type) && child_set.contains(dep_id.as_str()) {
-                // dep_id blocks child.id
-                deps.push((dep_id, child.id.clone()));
+            if dep.dep_type.affects_ready_work() && child_set.contains(dep.depends_on_id.as_str()) {
+                // depends_on_id blocks child.id
+                deps.push((dep.depends_on_id, child.id.clone()));
             }
         }
     }
 
     Ok(deps)
 }
-
-/// Check if a dependency type affects ready work (mirrors DependencyType::affects_ready_work).
-fn affects_ready_work(dep_type: &str) -> bool {
-    matches!(
-        dep_type,
-        "blocks" | "parent-child" | "conditional-blocks" | "waits-for"
-    )
-}
@@ -1,13 +1,643 @@
-//! `bd sync` -- deprecated command stub.
+//! `bd sync` -- synchronize the database with git through the JSONL export.
 //!
-//! Prints a deprecation message directing users to `bd dolt push` and `bd dolt pull`.
+//! A full sync:
+//!
+//! 1. exports the database to `.beads/issues.jsonl` and commits it,
+//! 2. fetches the upstream branch and three-way merges the base, local and
+//!    remote JSONL using the `conflict` config (see [`crate::merge`]),
+//! 3. merges the upstream commit, replacing any textual conflict in the JSONL
+//!    with the merged records,
+//! 4. re-imports the merged records into the database, and
+//! 5. pushes.
+//!
+//! The `sync.export_on` / `sync.import_on` triggers decide whether export and
+//! import also run automatically around every other command (`change`) or
+//! only as part of `bd sync` (`push` / `pull`); see [`auto_import`] and
+//! [`auto_export`].
+
+use std::cell::RefCell;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
+use beads_config::config::{BeadsConfig, SyncConfig};
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
+use beads_core::jsonl::write_jsonl;
+use beads_git::commands::{GitError, git_command};
+use beads_lockfile::LockMode;
+use beads_storage::Storage;
+
+use crate::cli::SyncArgs;
+use crate::commands::export::{collect_issues, write_jsonl_file};
+use crate::commands::import::{ImportSummary, import_issues};
 use crate::context::RuntimeContext;
+use crate::locks;
+use crate::merge::{MergeConflict, merge_issues, parse_jsonl};
+use crate::output::output_json;
+
+/// Metadata key holding the SHA-256 of the JSONL as last exported/imported.
+const JSONL_HASH_KEY: &str = "jsonl_hash";
+
+/// Default commit message for JSONL commits.
+const DEFAULT_COMMIT_MESSAGE: &str = "bd sync: update issues";
+
+/// What a sync run did, for `--json` output.
+#[derive(Debug, Default, Serialize)]
+struct SyncReport {
+    exported: usize,
+    committed: bool,
+    pulled: bool,
+    created: usize,
+    updated: usize,
+    deleted: usize,
+    pushed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    conflicts: Vec<MergeConflict>,
+}
+
+/// The repository the JSONL lives in.
+struct GitRepo {
+    root: PathBuf,
+    /// JSONL path relative to `root`, with `/` separators.
+    jsonl: String,
+}
+
+impl GitRepo {
+    fn git(&self, args: &[&str]) -> Result<String> {
+        git_command(args, &self.root).with_context(|| format!("git {} failed", args.join(" ")))
+    }
+
+    /// Contents of the JSONL at `rev`, or nothing if it did not exist there.
+    fn show_jsonl(&self, rev: &str) -> Vec<u8> {
+        git_command(&["show", &format!("{rev}:{}", self.jsonl)], &self.root)
+            .map(String::into_bytes)
+            .unwrap_or_default()
+    }
+}
+
+/// Execute the `bd sync` command.
+pub fn run(ctx: &RuntimeContext, args: &SyncArgs) -> Result<()> {
+    let store = ctx.store()?;
+    let beads_dir = ctx
+        .resolve_db_path()
+        .context("no beads database found. Run 'bd init' to create one.")?;
+    let config = ctx.config()?;
+    let jsonl = beads_dir.join("issues.jsonl");
+    let repo = if config.no_git_ops {
+        None
+    } else {
+        find_repo(&jsonl)
+    };
+
+    if args.status {
+        return run_status(ctx, store, &jsonl, repo.as_ref());
+    }
+    if ctx.readonly {
+        bail!("cannot sync in read-only mode");
+    }
+    let _lock = locks::lock_file(&jsonl, LockMode::Exclusive)?;
+
+    if args.import_only {
+        let bytes =
+            fs::read(&jsonl).with_context(|| format!("failed to read {}", jsonl.display()))?;
+        let issues = parse_jsonl(&bytes)?;
+        let summary = import_issues(store, &issues, &ctx.actor, false)?;
+        record_jsonl_state(store, &jsonl)?;
+        if ctx.json {
+            output_json(&summary);
+        } else if !ctx.quiet {
+            println!(
+                "Imported {}: {} created, {} updated, {} unchanged, {} failed",
+                jsonl.display(),
+                summary.created,
+                summary.updated,
+                summary.unchanged,
+                summary.failed
+            );
+        }
+        return Ok(());
+    }
+
+    let mut report = SyncReport::default();
+
+    // Someone changed the JSONL behind our back (e.g. a manual `git pull`):
+    // fold it in before the export overwrites it.
+    if jsonl_changed_externally(store, &jsonl)? {
+        let issues = parse_jsonl(&fs::read(&jsonl)?)?;
+        let summary = import_issues(store, &issues, &ctx.actor, false)?;
+        report.created += summary.created;
+        report.updated += summary.updated;
+    }
+
+    let local = export(store, &jsonl)?;
+    report.exported = local.len();
+    progress(
+        ctx,
+        &format!("Exported {} issues to {}", local.len(), jsonl.display()),
+    );
+
+    let Some(repo) = repo.filter(|_| !args.flush_only) else {
+        return finish(ctx, report);
+    };
+
+    report.committed = commit_jsonl(&repo, config, args.message.as_deref())?;
+    if report.committed {
+        progress(ctx, &format!("Committed {}", repo.jsonl));
+    }
+
+    let upstream = git_command(
+        &["rev-parse", "--abbrev-ref", "--symbolic-full-name", "@{u}"],
+        &repo.root,
+    )
+    .ok();
+    let Some(upstream) = upstream else {
+        progress(ctx, "No upstream branch configured; skipping pull and push");
+        return finish(ctx, report);
+    };
 
-/// Execute the `bd sync` command (deprecated).
-pub fn run(_ctx: &RuntimeContext) -> Result<()> {
-    eprintln!("bd sync is deprecated. Use 'bd dolt push' and 'bd dolt pull' instead.");
+    if !args.no_pull {
+        repo.git(&["fetch", "--quiet"])?;
+        let theirs_rev = repo.git(&["rev-parse", "@{u}"])?;
+        let base_rev = git_command(&["merge-base", "HEAD", "@{u}"], &repo.root).unwrap_or_default();
+
+        if base_rev != theirs_rev {
+            let base = parse_jsonl(&repo.show_jsonl(&base_rev))?;
+            let ours = parse_jsonl(&repo.show_jsonl("HEAD"))?;
+            let theirs = parse_jsonl(&repo.show_jsonl(&theirs_rev))?;
+            let merged = merge_issues(&base, &ours, &theirs, &config.conflict)?;
+
+            if !merged.conflicts.is_empty() {
+                report.conflicts = merged.conflicts;
+                report_conflicts(ctx, &report);
+                std::process::exit(1);
+            }
+
+            merge_upstream(&repo, config, &theirs_rev, &jsonl, &merged.issues)?;
+            report.pulled = true;
+            progress(ctx, &format!("Merged {upstream}"));
+
+            let (summary, deleted) = apply_snapshot(store, &local, &merged.issues, &ctx.actor)?;
+            report.created += summary.created;
+            report.updated += summary.updated;
+            report.deleted = deleted;
+            record_jsonl_state(store, &jsonl)?;
+            for failure in &summary.errors {
+                eprintln!("  import failed: {}: {}", failure.id, failure.error);
+            }
+        }
+    }
+
+    if !args.no_push && !config.no_push {
+        repo.git(&["push", "--quiet"])
+            .context("push failed; run 'bd sync' again after resolving the problem")?;
+        report.pushed = true;
+        progress(ctx, &format!("Pushed to {upstream}"));
+    }
+
+    finish(ctx, report)
+}
+
+/// Prints the final summary.
+fn finish(ctx: &RuntimeContext, report: SyncReport) -> Result<()> {
+    if ctx.json {
+        output_json(&report);
+    } else if !ctx.quiet && (report.pulled || report.created > 0) {
+        println!(
+            "Sync complete: {} created, {} updated, {} deleted",
+            report.created, report.updated, report.deleted
+        );
+    }
+    Ok(())
+}
+
+fn progress(ctx: &RuntimeContext, message: &str) {
+    if !ctx.json && !ctx.quiet {
+        println!("{message}");
+    }
+}
+
+/// Prints unresolved merge conflicts.
+fn report_conflicts(ctx: &RuntimeContext, report: &SyncReport) {
+    if ctx.json {
+        output_json(report);
+        return;
+    }
+    eprintln!(
+        "Sync stopped: {} conflict(s) need manual resolution (conflict strategy is 'manual'):",
+        report.conflicts.len()
+    );
+    for conflict in &report.conflicts {
+        if conflict.field.is_empty() {
+            eprintln!(
+                "  {}: deleted on one side, modified on the other",
+                conflict.id
+            );
+        } else {
+            eprintln!(
+                "  {}: field '{}' changed on both sides",
+                conflict.id, conflict.field
+            );
+        }
+    }
+    eprintln!(
+        "Resolve them in the database, or change conflict.strategy, then run 'bd sync' again."
+    );
+}
+
+/// `bd sync --status`.
+fn run_status(
+    ctx: &RuntimeContext,
+    store: &dyn Storage,
+    jsonl: &Path,
+    repo: Option<&GitRepo>,
+) -> Result<()> {
+    let issues = collect_issues(store, &export_filter())?;
+    let mut rendered = Vec::new();
+    write_jsonl(&mut rendered, &issues)?;
+    let on_disk = fs::read(jsonl).unwrap_or_default();
+
+    let pending_export = rendered != on_disk;
+    let pending_import = jsonl_changed_externally(store, jsonl)?;
+    let uncommitted = repo.is_some_and(|r| {
+        r.git(&["status", "--porcelain", "--", &r.jsonl])
+            .is_ok_and(|s| !s.is_empty())
+    });
+    let (ahead, behind) = repo
+        .and_then(|r| {
+            r.git(&["rev-list", "--left-right", "--count", "HEAD...@{u}"])
+                .ok()
+        })
+        .and_then(|counts| {
+            let mut parts = counts.split_whitespace().map(str::parse::<usize>);
+            Some((parts.next()?.ok()?, parts.next()?.ok()?))
+        })
+        .unzip();
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "jsonl": jsonl.display().to_string(),
+            "issues": issues.len(),
+            "pending_export": pending_export,
+            "pending_import": pending_import,
+            "git": repo.is_some(),
+            "uncommitted": uncommitted,
+            "ahead": ahead,
+            "behind": behind,
+        }));
+        return Ok(());
+    }
+
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    println!("JSONL:            {}", jsonl.display());
+    println!("Issues:           {}", issues.len());
+    println!("Pending export:   {}", yes_no(pending_export));
+    println!("Pending import:   {}", yes_no(pending_import));
+    if repo.is_none() {
+        println!("Git:              not available");
+        return Ok(());
+    }
+    println!("Uncommitted:      {}", yes_no(uncommitted));
+    match (ahead, behind) {
+        (Some(ahead), Some(behind)) => {
+            println!("Upstream:         {ahead} ahead, {behind} behind");
+        }
+        _ => println!("Upstream:         none"),
+    }
     Ok(())
 }
+
+/// Locates the git repository containing `jsonl`.
+fn find_repo(jsonl: &Path) -> Option<GitRepo> {
+    let (root, jsonl) = repo_path(jsonl)?;
+    Some(GitRepo { root, jsonl })
+}
+
+/// Returns the root of the git repository containing `path` and `path`
+/// relative to it (with `/` separators), or `None` outside a repository.
+pub fn repo_path(path: &Path) -> Option<(PathBuf, String)> {
+    let dir = path.parent()?;
+    let root = git_command(&["rev-parse", "--show-toplevel"], dir).ok()?;
+    let root = fs::canonicalize(root).ok()?;
+    let dir = fs::canonicalize(dir).ok()?;
+    let rel = dir.strip_prefix(&root).ok()?.join(path.file_name()?);
+    let rel = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some((root, rel))
+}
+
+/// The filter used for the git-tracked export: everything but wisps.
+fn export_filter() -> IssueFilter {
+    IssueFilter {
+        ephemeral: Some(false),
+        ..Default::default()
+    }
+}
+
+/// Exports the database to `jsonl` and records its hash.
+fn export(store: &dyn Storage, jsonl: &Path) -> Result<Vec<Issue>> {
+    let issues = collect_issues(store, &export_filter())?;
+    write_jsonl_file(jsonl, &issues)?;
+    record_jsonl_state(store, jsonl)?;
+    Ok(issues)
+}
+
+/// Stages and commits the JSONL. Returns `false` if there was nothing to commit.
+fn commit_jsonl(repo: &GitRepo, config: &BeadsConfig, message: Option<&str>) -> Result<bool> {
+    repo.git(&["add", "--", &repo.jsonl])?;
+    match git_command(
+        &["diff", "--cached", "--quiet", "--", &repo.jsonl],
+        &repo.root,
+    ) {
+        Ok(_) => return Ok(false),
+        Err(GitError::CommandFailed { code: Some(1), .. }) => {}
+        Err(e) => return Err(e).context("git diff failed"),
+    }
+
+    let message = message.unwrap_or(DEFAULT_COMMIT_MESSAGE);
+    let mut args = vec!["commit", "--quiet", "-m", message];
+    let author = format!("--author={}", config.git.author);
+    if !config.git.author.is_empty() {
+        args.push(&author);
+    }
+    if config.git.no_gpg_sign {
+        args.push("--no-gpg-sign");
+    }
+    args.extend(["--", &repo.jsonl]);
+    repo.git(&args)?;
+    Ok(true)
+}
+
+/// Merges `theirs_rev` into HEAD and commits `merged` as the JSONL.
+///
+/// Textual conflicts in the JSONL are expected and replaced by the merged
+/// records; conflicts in any other file abort the merge.
+fn merge_upstream(
+    repo: &GitRepo,
+    config: &BeadsConfig,
+    theirs_rev: &str,
+    jsonl: &Path,
+    merged: &[Issue],
+) -> Result<()> {
+    let mut args = vec!["merge", "--quiet", "--no-edit"];
+    if config.git.no_gpg_sign {
+        args.push("--no-gpg-sign");
+    }
+    args.push(theirs_rev);
+
+    let in_merge = match git_command(&args, &repo.root) {
+        Ok(_) => false,
+        Err(GitError::CommandFailed { .. }) => {
+            let unmerged = repo.git(&["diff", "--name-only", "--diff-filter=U"])?;
+            let others: Vec<&str> = unmerged.lines().filter(|f| *f != repo.jsonl).collect();
+            if !others.is_empty() {
+                let _ = repo.git(&["merge", "--abort"]);
+                bail!(
+                    "merging upstream conflicts outside the issue export: {}\n\
+                     Hint: merge manually, then run 'bd sync' again",
+                    others.join(", ")
+                );
+            }
+            if unmerged.is_empty() {
+                bail!("git merge failed; see 'git status'");
+            }
+            true
+        }
+        Err(e) => return Err(e).context("git merge failed"),
+    };
+
+    write_jsonl_file(jsonl, merged)?;
+    repo.git(&["add", "--", &repo.jsonl])?;
+    if in_merge {
+        let mut args = vec!["commit", "--quiet", "--no-edit"];
+        if config.git.no_gpg_sign {
+            args.push("--no-gpg-sign");
+        }
+        repo.git(&args)?;
+    } else {
+        commit_jsonl(repo, config, Some("bd sync: merge issues"))?;
+    }
+    Ok(())
+}
+
+/// Imports the merged records and deletes local issues the merge dropped.
+fn apply_snapshot(
+    store: &dyn Storage,
+    local: &[Issue],
+    merged: &[Issue],
+    actor: &str,
+) -> Result<(ImportSummary, usize)> {
+    let keep: BTreeSet<&str> = merged.iter().map(|i| i.id.as_str()).collect();
+    let dropped: Vec<&str> = local
+        .iter()
+        .map(|i| i.id.as_str())
+        .filter(|id| !keep.contains(id))
+        .collect();
+
+    let deleted = RefCell::new(0);
+    store
+        .run_in_transaction(&|tx| {
+            for id in &dropped {
+                tx.delete_issue(id)?;
+                *deleted.borrow_mut() += 1;
+            }
+            Ok(())
+        })
+        .context("failed to delete issues removed upstream")?;
+
+    let summary = import_issues(store, merged, actor, false)?;
+    Ok((summary, deleted.into_inner()))
+}
+
+/// SHA-256 of the file at `path`, or `None` if it cannot be read.
+fn file_hash(path: &Path) -> Option<String> {
+    let bytes = fs::read(path).ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Records the current JSONL hash as in sync with the database.
+pub fn record_jsonl_state(store: &dyn Storage, jsonl: &Path) -> Result<()> {
+    let hash = file_hash(jsonl).unwrap_or_default();
+    store
+        .run_in_transaction(&|tx| tx.set_metadata(JSONL_HASH_KEY, &hash))
+        .context("failed to record JSONL state")
+}
+
+/// Returns true if the JSONL differs from what was last exported or imported.
+fn jsonl_changed_externally(store: &dyn Storage, jsonl: &Path) -> Result<bool> {
+    let Some(current) = file_hash(jsonl) else {
+        return Ok(false);
+    };
+    let recorded = RefCell::new(None);
+    store
+        .run_in_transaction(&|tx| {
+            *recorded.borrow_mut() = match tx.get_metadata(JSONL_HASH_KEY) {
+                Ok(v) => Some(v),
+                Err(e) if e.is_not_found() => None,
+                Err(e) => return Err(e),
+            };
+            Ok(())
+        })
+        .context("failed to read JSONL state")?;
+    // Nothing recorded yet: only a non-empty file counts as a change.
+    Ok(match recorded.into_inner() {
+        Some(recorded) => recorded != current,
+        None => fs::metadata(jsonl).is_ok_and(|m| m.len() > 0),
+    })
+}
+
+/// Returns the JSONL path and the store for an automatic trigger, if it
+/// is set to `change`: `store`, or else the context's own. The trigger is
+/// checked before the store is opened, so commands with the trigger off
+/// never take the database lock here.
+fn auto_context<'a>(
+    ctx: &'a RuntimeContext,
+    store: Option<&'a dyn Storage>,
+    trigger: fn(&SyncConfig) -> &str,
+) -> Option<(&'a dyn Storage, PathBuf)> {
+    if ctx.sandbox || ctx.readonly {
+        return None;
+    }
+    let beads_dir = ctx.resolve_db_path()?;
+    if !beads_dir.join("beads.db").exists() {
+        return None;
+    }
+    if trigger(&ctx.config().ok()?.sync) != "change" {
+        return None;
+    }
+    let store = match store {
+        Some(store) => store,
+        None => ctx.store().ok()?,
+    };
+    Some((store, beads_dir.join("issues.jsonl")))
+}
+
+/// `sync.import_on: change` -- imports the JSONL before a command runs if it
+/// changed since the last export or import (e.g. after `git pull`).
+///
+/// Failures are reported as warnings; they never block the command.
+pub fn auto_import(ctx: &RuntimeContext) {
+    import_changes(ctx, None);
+}
+
+/// [`auto_import`] through `store` rather than the context's store.
+pub fn auto_import_with(ctx: &RuntimeContext, store: &dyn Storage) {
+    import_changes(ctx, Some(store));
+}
+
+fn import_changes(ctx: &RuntimeContext, store: Option<&dyn Storage>) {
+    let Some((store, jsonl)) = auto_context(ctx, store, |sync| &sync.import_on) else {
+        return;
+    };
+    let result = (|| -> Result<()> {
+        let _lock = locks::lock_file(&jsonl, LockMode::Shared)?;
+        if !jsonl_changed_externally(store, &jsonl)? {
+            return Ok(());
+        }
+        let issues = parse_jsonl(&fs::read(&jsonl)?)?;
+        let summary = import_issues(store, &issues, &ctx.actor, false)?;
+        record_jsonl_state(store, &jsonl)?;
+        if ctx.verbose {
+            eprintln!(
+                "Auto-imported {}: {} created, {} updated",
+                jsonl.display(),
+                summary.created,
+                summary.updated
+            );
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        if !ctx.quiet {
+            eprintln!("Warning: auto-import failed: {e:#}");
+        }
+    }
+}
+
+/// `sync.export_on: change` -- rewrites the JSONL after a command if the
+/// database no longer matches it.
+///
+/// A JSONL that changed externally and was not imported yet is never
+/// overwritten; the user is pointed at `bd sync` instead.
+pub fn auto_export(ctx: &RuntimeContext) {
+    export_changes(ctx, None);
+}
+
+/// [`auto_export`] through `store` rather than the context's store.
+pub fn auto_export_with(ctx: &RuntimeContext, store: &dyn Storage) {
+    export_changes(ctx, Some(store));
+}
+
+fn export_changes(ctx: &RuntimeContext, store: Option<&dyn Storage>) {
+    let Some((store, jsonl)) = auto_context(ctx, store, |sync| &sync.export_on) else {
+        return;
+    };
+    let result = (|| -> Result<()> {
+        let _lock = locks::lock_file(&jsonl, LockMode::Exclusive)?;
+        let issues = collect_issues(store, &export_filter())?;
+        let mut rendered = Vec::new();
+        write_jsonl(&mut rendered, &issues)?;
+        if fs::read(&jsonl).ok().as_deref() == Some(rendered.as_slice()) {
+            return Ok(());
+        }
+        if jsonl_changed_externally(store, &jsonl)? {
+            bail!(
+                "{} changed since the last import; run 'bd sync' to merge it",
+                jsonl.display()
+            );
+        }
+        write_jsonl_file(&jsonl, &issues)?;
+        record_jsonl_state(store, &jsonl)
+    })();
+    if let Err(e) = result {
+        if !ctx.quiet {
+            eprintln!("Warning: auto-export skipped: {e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beads_core::issue::IssueBuilder;
+    use beads_storage::SqliteStore;
+
+    #[test]
+    fn jsonl_state_tracks_external_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let jsonl = dir.path().join("issues.jsonl");
+        let store = SqliteStore::open_in_memory().unwrap();
+        let issue = IssueBuilder::new("Tracked").id("bd-1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        assert!(!jsonl_changed_externally(&store, &jsonl).unwrap());
+        export(&store, &jsonl).unwrap();
+        assert!(!jsonl_changed_externally(&store, &jsonl).unwrap());
+
+        fs::write(&jsonl, "").unwrap();
+        assert!(jsonl_changed_externally(&store, &jsonl).unwrap());
+    }
+
+    #[test]
+    fn apply_snapshot_deletes_dropped_issues() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        for id in ["bd-1", "bd-2"] {
+            let issue = IssueBuilder::new(id).id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        store.add_label_impl("bd-2", "doomed", "alice").unwrap();
+        let local = collect_issues(&store, &export_filter()).unwrap();
+        let merged = vec![local[0].clone()];
+
+        let (_, deleted) = apply_snapshot(&store, &local, &merged, "alice").unwrap();
+        assert_eq!(deleted, 1);
+        assert!(store.get_issue_impl("bd-2").unwrap_err().is_not_found());
+    }
+}
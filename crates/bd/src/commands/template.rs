@@ -10,10 +10,14 @@ use std::collections::{HashMap, HashSet};
 use anyhow::{Context, Result, bail};
 use chrono::Utc;
 
-use beads_core::enums::IssueType;
-use beads_core::idgen;
+use beads_core::dependency::Dependency;
+use beads_core::enums::{DependencyType, IssueType, Status};
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
+use beads_storage::Storage;
 
 use crate::cli::{TemplateArgs, TemplateCommands};
+use crate::commands::create::{generate_issue_id, issue_prefix, parse_priority};
 use crate::context::RuntimeContext;
 use crate::output::{output_json, output_table};
 
@@ -33,31 +37,15 @@ pub fn run(ctx: &RuntimeContext, args: &TemplateArgs) -> Result<()> {
 // ---------------------------------------------------------------------------
 
 fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
-    let conn = open_db(ctx, false)?;
-
-    let mut stmt = conn.prepare(
-        "SELECT id, title, description, status, priority, issue_type, assignee, created_at, created_by, updated_at \
-         FROM issues WHERE COALESCE(is_template, 0) = 1 \
-         ORDER BY created_at DESC",
-    )?;
-
-    let templates: Vec<TemplateRow> = stmt
-        .query_map([], |row| {
-            Ok(TemplateRow {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get::<_, String>(2).unwrap_or_default(),
-                status: row.get::<_, String>(3).unwrap_or_default(),
-                priority: row.get(4)?,
-                issue_type: row.get::<_, String>(5).unwrap_or_default(),
-                assignee: row.get::<_, String>(6).unwrap_or_default(),
-                created_at: row.get::<_, String>(7).unwrap_or_default(),
-                created_by: row.get::<_, String>(8).unwrap_or_default(),
-                updated_at: row.get::<_, String>(9).unwrap_or_default(),
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let store = ctx.store()?;
+
+    let filter = IssueFilter {
+        is_template: Some(true),
+        ..Default::default()
+    };
+    let mut issues = store.search_issues("", &filter)?;
+    issues.sort_by_key(|i| std::cmp::Reverse(i.created_at));
+    let templates: Vec<TemplateRow> = issues.iter().map(TemplateRow::from).collect();
 
     if ctx.json {
         output_json(&templates);
@@ -97,14 +85,31 @@ struct TemplateRow {
     updated_at: String,
 }
 
+impl From<&Issue> for TemplateRow {
+    fn from(issue: &Issue) -> Self {
+        Self {
+            id: issue.id.clone(),
+            title: issue.title.clone(),
+            description: issue.description.clone(),
+            status: issue.status.as_str().to_string(),
+            priority: issue.priority,
+            issue_type: issue.issue_type.as_str().to_string(),
+            assignee: issue.assignee.clone(),
+            created_at: issue.created_at.to_rfc3339(),
+            created_by: issue.created_by.clone(),
+            updated_at: issue.updated_at.to_rfc3339(),
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Show
 // ---------------------------------------------------------------------------
 
 fn cmd_show(ctx: &RuntimeContext, id: &str) -> Result<()> {
-    let conn = open_db(ctx, false)?;
+    let store = ctx.store()?;
 
-    let row = load_template_row(&conn, id)?;
+    let row = TemplateRow::from(&load_template(store, id)?);
 
     // Extract variables from title and description
     let variables = extract_variables(&row.title, &row.description);
@@ -151,88 +156,33 @@ fn cmd_create(ctx: &RuntimeContext, args: &crate::cli::TemplateCreateArgs) -> Re
         bail!("cannot create templates in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
+    let store = ctx.store()?;
 
     // Parse priority
     let priority = parse_priority(&args.priority)?;
     let issue_type = IssueType::from(args.issue_type.as_str()).normalize();
 
-    // Get prefix
-    let prefix: String = conn
-        .query_row(
-            "SELECT value FROM config WHERE key = 'issue_prefix'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "bd".to_string());
-
-    // Generate ID
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM issues", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    let hash_length = idgen::compute_adaptive_length(
-        count as usize,
-        idgen::adaptive_defaults::MIN_LENGTH,
-        idgen::adaptive_defaults::MAX_LENGTH,
-        idgen::adaptive_defaults::MAX_COLLISION_PROB,
-    );
-
     let description = args.description.as_deref().unwrap_or("");
     let now = Utc::now();
-
-    let mut issue_id = String::new();
-    for nonce in 0..10 {
-        let candidate = idgen::generate_hash_id(
-            &prefix,
-            &args.title,
-            description,
-            &ctx.actor,
-            now,
-            hash_length,
-            nonce,
-        );
-        let exists: bool = conn
-            .query_row(
-                "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                rusqlite::params![&candidate],
-                |row| row.get(0),
-            )
-            .unwrap_or(false);
-        if !exists {
-            issue_id = candidate;
-            break;
-        }
-    }
-
-    if issue_id.is_empty() {
-        bail!("failed to generate unique ID after 10 attempts");
-    }
-
-    let now_str = now.to_rfc3339();
-
-    conn.execute(
-        "INSERT INTO issues (id, title, description, status, priority, issue_type, is_template, created_at, created_by, updated_at) \
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9)",
-        rusqlite::params![
-            &issue_id,
-            &args.title,
-            description,
-            "open",
-            priority,
-            issue_type.as_str(),
-            &now_str,
-            &ctx.actor,
-            &now_str,
-        ],
-    )
-    .with_context(|| format!("failed to create template {}", issue_id))?;
-
-    // Record event
-    conn.execute(
-        "INSERT INTO events (issue_id, event_type, actor, new_value, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
-        rusqlite::params![&issue_id, "created", &ctx.actor, &args.title, &now_str],
-    )?;
+    let prefix = issue_prefix(ctx, store);
+    let issue_id = generate_issue_id(store, &prefix, &args.title, description, &ctx.actor, now)?;
+
+    let issue = Issue {
+        id: issue_id.clone(),
+        title: args.title.clone(),
+        description: description.to_string(),
+        status: Status::Open,
+        priority,
+        issue_type,
+        is_template: true,
+        created_at: now,
+        created_by: ctx.actor.clone(),
+        updated_at: now,
+        ..Issue::default()
+    };
+    store
+        .create_issue(&issue, &ctx.actor)
+        .with_context(|| format!("failed to create template {}", issue_id))?;
 
     if ctx.json {
         output_json(&serde_json::json!({
@@ -258,39 +208,21 @@ fn cmd_delete(ctx: &RuntimeContext, id: &str) -> Result<()> {
         bail!("cannot delete templates in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
+    let store = ctx.store()?;
 
     // Verify it's actually a template
-    let is_template: bool = conn
-        .query_row(
-            "SELECT COALESCE(is_template, 0) FROM issues WHERE id = ?1",
-            rusqlite::params![id],
-            |row| row.get(0),
-        )
-        .with_context(|| format!("template '{}' not found", id))?;
+    let issue = match store.get_issue(id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("template '{}' not found", id),
+        Err(e) => return Err(e.into()),
+    };
 
-    if !is_template {
+    if !issue.is_template {
         bail!("issue '{}' is not a template", id);
     }
 
-    // Delete dependencies first (cascade should handle this, but be explicit)
-    conn.execute(
-        "DELETE FROM dependencies WHERE issue_id = ?1 OR depends_on_id = ?1",
-        rusqlite::params![id],
-    )?;
-    conn.execute(
-        "DELETE FROM labels WHERE issue_id = ?1",
-        rusqlite::params![id],
-    )?;
-    conn.execute(
-        "DELETE FROM comments WHERE issue_id = ?1",
-        rusqlite::params![id],
-    )?;
-    conn.execute(
-        "DELETE FROM events WHERE issue_id = ?1",
-        rusqlite::params![id],
-    )?;
-    conn.execute("DELETE FROM issues WHERE id = ?1", rusqlite::params![id])?;
+    // Dependencies, labels, comments and events go with it
+    store.delete_issue(id)?;
 
     if ctx.json {
         output_json(&serde_json::json!({ "deleted": id }));
@@ -310,24 +242,23 @@ fn cmd_instantiate(ctx: &RuntimeContext, args: &crate::cli::TemplateInstantiateA
         bail!("cannot instantiate templates in read-only mode");
     }
 
-    let conn = open_db(ctx, true)?;
+    let store = ctx.store()?;
 
     // 1. Load the root template issue
-    let root = load_template_row(&conn, &args.id)?;
+    let root = load_template(store, &args.id)?;
 
     // 2. Recursively load all children via parent-child deps
     let mut all_ids: Vec<String> = Vec::new();
     let mut visited: HashSet<String> = HashSet::new();
-    collect_children_recursive(&conn, &args.id, &mut all_ids, &mut visited)?;
+    collect_children_recursive(store, &args.id, &mut all_ids, &mut visited)?;
     // Prepend root
     all_ids.insert(0, args.id.clone());
 
     // 3. Load all issues
-    let mut templates: HashMap<String, TemplateRow> = HashMap::new();
+    let mut templates: HashMap<String, Issue> = HashMap::new();
     templates.insert(args.id.clone(), root);
     for child_id in &all_ids[1..] {
-        let row = load_issue_row(&conn, child_id)?;
-        templates.insert(child_id.clone(), row);
+        templates.insert(child_id.clone(), store.get_issue(child_id)?);
     }
 
     // 4. Extract all variables from all issues
@@ -364,31 +295,13 @@ fn cmd_instantiate(ctx: &RuntimeContext, args: &crate::cli::TemplateInstantiateA
     }
 
     // 7. Determine prefix for new IDs
-    let prefix: String = if let Some(ref p) = args.prefix {
-        p.clone()
-    } else {
-        conn.query_row(
-            "SELECT value FROM config WHERE key = 'issue_prefix'",
-            [],
-            |row| row.get(0),
-        )
-        .unwrap_or_else(|_| "bd".to_string())
+    let prefix = match args.prefix {
+        Some(ref p) => p.clone(),
+        None => issue_prefix(ctx, store),
     };
 
     // 8. Clone each issue with substituted text and new IDs
-    let count: i64 = conn
-        .query_row("SELECT COUNT(*) FROM issues", [], |row| row.get(0))
-        .unwrap_or(0);
-
-    let hash_length = idgen::compute_adaptive_length(
-        count as usize,
-        idgen::adaptive_defaults::MIN_LENGTH,
-        idgen::adaptive_defaults::MAX_LENGTH,
-        idgen::adaptive_defaults::MAX_COLLISION_PROB,
-    );
-
     let now = Utc::now();
-    let now_str = now.to_rfc3339();
     let mut id_map: HashMap<String, String> = HashMap::new(); // old_id -> new_id
     let mut created_issues: Vec<serde_json::Value> = Vec::new();
 
@@ -397,64 +310,41 @@ fn cmd_instantiate(ctx: &RuntimeContext, args: &crate::cli::TemplateInstantiateA
         let new_title = substitute_variables(&tmpl.title, &var_map);
         let new_description = substitute_variables(&tmpl.description, &var_map);
 
-        // Generate new ID
-        let mut new_id = String::new();
-        for nonce in 0..10 {
-            let candidate = idgen::generate_hash_id(
-                &prefix,
-                &new_title,
-                &new_description,
-                &ctx.actor,
-                now,
-                hash_length,
-                nonce,
-            );
-            let exists: bool = conn
-                .query_row(
-                    "SELECT EXISTS(SELECT 1 FROM issues WHERE id = ?1)",
-                    rusqlite::params![&candidate],
-                    |row| row.get(0),
-                )
-                .unwrap_or(false);
-            if !exists && !id_map.values().any(|v| v == &candidate) {
-                new_id = candidate;
-                break;
-            }
-        }
-
-        if new_id.is_empty() {
-            bail!(
+        // Each clone is stored before the next ID is generated, so the
+        // collision check also covers earlier clones.
+        let new_id = generate_issue_id(
+            store,
+            &prefix,
+            &new_title,
+            &new_description,
+            &ctx.actor,
+            now,
+        )
+        .with_context(|| {
+            format!(
                 "failed to generate unique ID for cloned issue (template: {})",
                 old_id
-            );
-        }
+            )
+        })?;
 
         id_map.insert(old_id.clone(), new_id.clone());
 
-        conn.execute(
-            "INSERT INTO issues (id, title, description, status, priority, issue_type, assignee, \
-             is_template, created_at, created_by, updated_at) \
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0, ?8, ?9, ?10)",
-            rusqlite::params![
-                &new_id,
-                &new_title,
-                &new_description,
-                "open",
-                tmpl.priority,
-                &tmpl.issue_type,
-                &tmpl.assignee,
-                &now_str,
-                &ctx.actor,
-                &now_str,
-            ],
-        )
-        .with_context(|| format!("failed to create cloned issue from template {}", old_id))?;
-
-        // Record event
-        conn.execute(
-            "INSERT INTO events (issue_id, event_type, actor, new_value, comment, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            rusqlite::params![&new_id, "created", &ctx.actor, &new_title, &format!("Instantiated from template {}", old_id), &now_str],
-        )?;
+        let issue = Issue {
+            id: new_id.clone(),
+            title: new_title.clone(),
+            description: new_description,
+            status: Status::Open,
+            priority: tmpl.priority,
+            issue_type: tmpl.issue_type.clone(),
+            assignee: tmpl.assignee.clone(),
+            created_at: now,
+            created_by: ctx.actor.clone(),
+            updated_at: now,
+            ..Issue::default()
+        };
+        store
+            .create_issue(&issue, &ctx.actor)
+            .with_context(|| format!("failed to create cloned issue from template {}", old_id))?;
 
         created_issues.push(serde_json::json!({
             "id": new_id,
@@ -464,44 +354,29 @@ fn cmd_instantiate(ctx: &RuntimeContext, args: &crate::cli::TemplateInstantiateA
     }
 
     // 9. Recreate all dependencies between cloned issues (map old IDs to new IDs)
-    let mut dep_stmt = conn.prepare(
-        "SELECT issue_id, depends_on_id, type FROM dependencies \
-         WHERE issue_id IN (SELECT value FROM json_each(?1)) \
-            OR depends_on_id IN (SELECT value FROM json_each(?1))",
-    )?;
-
-    let all_ids_json = serde_json::to_string(&all_ids)?;
-    let deps: Vec<(String, String, String)> = dep_stmt
-        .query_map(rusqlite::params![&all_ids_json], |row| {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
-
-    for (from_id, to_id, dep_type) in &deps {
-        // Only recreate deps where both sides are in our template set
-        if let (Some(new_from), Some(new_to)) = (id_map.get(from_id), id_map.get(to_id)) {
-            conn.execute(
-                "INSERT OR IGNORE INTO dependencies (issue_id, depends_on_id, type, created_at, created_by) \
-                 VALUES (?1, ?2, ?3, ?4, ?5)",
-                rusqlite::params![new_from, new_to, dep_type, &now_str, &ctx.actor],
-            )?;
+    for old_id in &all_ids {
+        for dep in store.get_dependency_records(old_id)? {
+            // Only recreate deps where both sides are in our template set
+            if let (Some(new_from), Some(new_to)) =
+                (id_map.get(&dep.issue_id), id_map.get(&dep.depends_on_id))
+            {
+                let cloned = Dependency {
+                    issue_id: new_from.clone(),
+                    depends_on_id: new_to.clone(),
+                    created_at: now,
+                    created_by: ctx.actor.clone(),
+                    ..dep
+                };
+                store.add_dependency(&cloned, &ctx.actor)?;
+            }
         }
     }
 
     // 10. Copy labels for each cloned issue
     for old_id in &all_ids {
         if let Some(new_id) = id_map.get(old_id) {
-            let mut label_stmt = conn.prepare("SELECT label FROM labels WHERE issue_id = ?1")?;
-            let labels: Vec<String> = label_stmt
-                .query_map(rusqlite::params![old_id], |row| row.get(0))?
-                .filter_map(|r| r.ok())
-                .collect();
-            for label in labels {
-                conn.execute(
-                    "INSERT OR IGNORE INTO labels (issue_id, label) VALUES (?1, ?2)",
-                    rusqlite::params![new_id, &label],
-                )?;
+            for label in store.get_labels(old_id)? {
+                store.add_label(new_id, &label, &ctx.actor)?;
             }
         }
     }
@@ -618,7 +493,7 @@ fn substitute_variables(text: &str, vars: &HashMap<String, String>) -> String {
 
 /// Recursively collect all children (via parent-child deps) of a given issue ID.
 fn collect_children_recursive(
-    conn: &rusqlite::Connection,
+    store: &dyn Storage,
     parent_id: &str,
     result: &mut Vec<String>,
     visited: &mut HashSet<String>,
@@ -628,110 +503,36 @@ fn collect_children_recursive(
     }
     visited.insert(parent_id.to_string());
 
-    // Children are issues where: dep(child -> parent, type='parent-child')
-    // i.e., child.issue_id has depends_on_id = parent_id with type='parent-child'
-    let mut stmt = conn.prepare(
-        "SELECT issue_id FROM dependencies WHERE depends_on_id = ?1 AND type = 'parent-child'",
-    )?;
-
-    let children: Vec<String> = stmt
-        .query_map(rusqlite::params![parent_id], |row| row.get(0))?
-        .filter_map(|r| r.ok())
+    // Children are issues with a parent-child edge pointing at parent_id
+    let mut children: Vec<String> = store
+        .get_dependents_with_metadata(parent_id)?
+        .into_iter()
+        .filter(|e| e.dependency.dep_type == DependencyType::ParentChild)
+        .map(|e| e.issue.id)
         .collect();
+    children.sort();
 
     for child_id in children {
         if !visited.contains(&child_id) {
             result.push(child_id.clone());
-            collect_children_recursive(conn, &child_id, result, visited)?;
+            collect_children_recursive(store, &child_id, result, visited)?;
         }
     }
 
     Ok(())
 }
 
-/// Load a template issue row (verifies it's a template).
-fn load_template_row(conn: &rusqlite::Connection, id: &str) -> Result<TemplateRow> {
-    let row = load_issue_row(conn, id)?;
-
-    // Verify it's actually a template
-    let is_template: bool = conn
-        .query_row(
-            "SELECT COALESCE(is_template, 0) FROM issues WHERE id = ?1",
-            rusqlite::params![id],
-            |row| row.get(0),
-        )
-        .unwrap_or(false);
-
-    if !is_template {
-        bail!("issue '{}' is not a template (is_template != 1)", id);
-    }
-
-    Ok(row)
-}
-
-/// Load an issue row by ID.
-fn load_issue_row(conn: &rusqlite::Connection, id: &str) -> Result<TemplateRow> {
-    conn.query_row(
-        "SELECT id, title, description, status, priority, issue_type, assignee, created_at, created_by, updated_at \
-         FROM issues WHERE id = ?1",
-        rusqlite::params![id],
-        |row| {
-            Ok(TemplateRow {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get::<_, String>(2).unwrap_or_default(),
-                status: row.get::<_, String>(3).unwrap_or_default(),
-                priority: row.get(4)?,
-                issue_type: row.get::<_, String>(5).unwrap_or_default(),
-                assignee: row.get::<_, String>(6).unwrap_or_default(),
-                created_at: row.get::<_, String>(7).unwrap_or_default(),
-                created_by: row.get::<_, String>(8).unwrap_or_default(),
-                updated_at: row.get::<_, String>(9).unwrap_or_default(),
-            })
-        },
-    )
-    .with_context(|| format!("issue '{}' not found", id))
-}
-
-/// Parse a priority string (bare number or P-prefixed).
-fn parse_priority(s: &str) -> Result<i32> {
-    let s = s.trim();
-    let num_str = if s.starts_with('P') || s.starts_with('p') {
-        &s[1..]
-    } else {
-        s
+/// Load a template issue (verifies it's a template).
+fn load_template(store: &dyn Storage, id: &str) -> Result<Issue> {
+    let issue = match store.get_issue(id) {
+        Ok(issue) => issue,
+        Err(e) if e.is_not_found() => bail!("issue '{}' not found", id),
+        Err(e) => return Err(e.into()),
     };
-    let p: i32 = num_str
-        .parse()
-        .with_context(|| format!("invalid priority '{}': expected 0-4 or P0-P4", s))?;
-    if !(0..=4).contains(&p) {
-        bail!("priority must be between 0 and 4 (got {})", p);
-    }
-    Ok(p)
-}
-
-/// Open the beads database (read-only or read-write).
-fn open_db(ctx: &RuntimeContext, writable: bool) -> Result<rusqlite::Connection> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
 
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
+    if !issue.is_template {
+        bail!("issue '{}' is not a template (is_template != 1)", id);
     }
 
-    if writable {
-        rusqlite::Connection::open(&db_path)
-            .with_context(|| format!("failed to open database: {}", db_path.display()))
-    } else {
-        rusqlite::Connection::open_with_flags(
-            &db_path,
-            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-        )
-        .with_context(|| format!("failed to open database: {}", db_path.display()))
-    }
+    Ok(issue)
 }
@@ -1,89 +1,28 @@
 //! `bd todo` -- show open issues sorted by priority.
 
-use anyhow::{Context, Result, bail};
-use chrono::{DateTime, Utc};
+use anyhow::Result;
 
-use beads_core::enums::{IssueType, Status};
-use beads_core::issue::Issue;
+use beads_core::enums::Status;
+use beads_core::filter::IssueFilter;
 
 use crate::cli::TodoArgs;
+use crate::commands::list::{listing_filter, sort_issues};
 use crate::context::RuntimeContext;
 use crate::output::{format_issue_row, output_json, output_table, populate_labels_bulk};
 
 /// Execute the `bd todo` command.
 pub fn run(ctx: &RuntimeContext, args: &TodoArgs) -> Result<()> {
-    let beads_dir = ctx
-        .resolve_db_path()
-        .context("no beads database found. Run 'bd init' to create one.")?;
-    let db_path = beads_dir.join("beads.db");
-
-    if !db_path.exists() {
-        bail!(
-            "no beads database found at {}\nHint: run 'bd init' to create a database",
-            db_path.display()
-        );
-    }
-
-    let conn = rusqlite::Connection::open_with_flags(
-        &db_path,
-        rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
-    )
-    .with_context(|| format!("failed to open database: {}", db_path.display()))?;
-
-    let sql = "SELECT id, title, description, design, acceptance_criteria, notes, spec_id, \
-               status, priority, issue_type, assignee, owner, estimated_minutes, \
-               created_at, created_by, updated_at, closed_at, close_reason, \
-               due_at, defer_until, external_ref \
-               FROM issues \
-               WHERE status = 'open' \
-                 AND COALESCE(is_template, 0) = 0 \
-                 AND issue_type != 'gate' \
-               ORDER BY priority ASC, created_at DESC \
-               LIMIT ?1";
-
-    let mut stmt = conn.prepare(sql)?;
-
-    let issues: Vec<Issue> = stmt
-        .query_map(rusqlite::params![args.limit], |row| {
-            let status_str: String = row.get(7)?;
-            let type_str: String = row.get(9)?;
-            let created_at_str: String = row.get(13)?;
-            let updated_at_str: String = row.get(15)?;
-            let closed_at_str: Option<String> = row.get(16)?;
-            let due_at_str: Option<String> = row.get(18)?;
-            let defer_until_str: Option<String> = row.get(19)?;
-
-            Ok(Issue {
-                id: row.get(0)?,
-                title: row.get(1)?,
-                description: row.get::<_, String>(2).unwrap_or_default(),
-                design: row.get::<_, String>(3).unwrap_or_default(),
-                acceptance_criteria: row.get::<_, String>(4).unwrap_or_default(),
-                notes: row.get::<_, String>(5).unwrap_or_default(),
-                spec_id: row.get::<_, String>(6).unwrap_or_default(),
-                status: Status::from(status_str.as_str()),
-                priority: row.get(8)?,
-                issue_type: IssueType::from(type_str.as_str()),
-                assignee: row.get::<_, String>(10).unwrap_or_default(),
-                owner: row.get::<_, String>(11).unwrap_or_default(),
-                estimated_minutes: row.get(12)?,
-                created_at: parse_datetime(&created_at_str),
-                created_by: row.get::<_, String>(14).unwrap_or_default(),
-                updated_at: parse_datetime(&updated_at_str),
-                closed_at: closed_at_str.as_deref().map(parse_datetime),
-                close_reason: row.get::<_, String>(17).unwrap_or_default(),
-                due_at: due_at_str.as_deref().map(parse_datetime),
-                defer_until: defer_until_str.as_deref().map(parse_datetime),
-                external_ref: row.get(20)?,
-                ..Issue::default()
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
+    let store = ctx.store()?;
+    let filter = IssueFilter {
+        status: Some(Status::Open),
+        ..listing_filter()
+    };
+    let mut issues = store.search_issues("", &filter)?;
+    sort_issues(&mut issues, None, false);
+    issues.truncate(args.limit.max(0) as usize);
 
     if ctx.json {
-        let mut issues = issues;
-        populate_labels_bulk(&conn, &mut issues);
+        populate_labels_bulk(store, &mut issues);
         output_json(&issues);
     } else {
         let headers = &["ID", "PRI", "TYPE", "STATUS", "TITLE", "ASSIGNEE"];
@@ -93,10 +32,3 @@ pub fn run(ctx: &RuntimeContext, args: &TodoArgs) -> Result<()> {
 
     Ok(())
 }
-
-/// Parse a datetime string (RFC3339) into a `DateTime<Utc>`.
-fn parse_datetime(s: &str) -> DateTime<Utc> {
-    DateTime::parse_from_rfc3339(s)
-        .map(|dt| dt.with_timezone(&Utc))
-        .unwrap_or_else(|_| Utc::now())
-}
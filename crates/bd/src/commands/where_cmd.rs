@@ -1,12 +1,73 @@
-//! `bd where` -- show where an issue lives (stub).
+//! `bd where` -- show which beads database is used and why.
+//!
+//! Reports the `.beads` directory the other commands resolve (see
+//! [`RuntimeContext::resolve_beads_dir`]): how it was chosen (`--db`,
+//! `BEADS_DIR` or found above the current directory) and the redirects
+//! followed to reach it. Given an issue ID, also checks the issue lives there.
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 
 use crate::cli::WhereCmdArgs;
+use crate::commands::create::issue_prefix;
 use crate::context::RuntimeContext;
+use crate::output::output_json;
+
+use beads_config::beads_dir::BeadsDirSource;
 
 /// Execute the `bd where` command.
-pub fn run(_ctx: &RuntimeContext, _args: &WhereCmdArgs) -> Result<()> {
-    println!("bd where: not yet implemented");
+pub fn run(ctx: &RuntimeContext, args: &WhereCmdArgs) -> Result<()> {
+    let Some(resolved) = ctx.resolve_beads_dir()? else {
+        bail!("no .beads directory found (run 'bd init' first)");
+    };
+    let database = resolved.path.join("beads.db");
+    let store = database.exists().then(|| ctx.store()).transpose()?;
+
+    if let Some(id) = &args.id {
+        let Some(store) = store else {
+            bail!("no beads database found at {}", database.display());
+        };
+        match store.get_issue(id) {
+            Ok(_) => {}
+            Err(e) if e.is_not_found() => {
+                bail!("issue '{}' not found in {}", id, database.display())
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if ctx.json {
+        output_json(&serde_json::json!({
+            "beads_dir": resolved.path,
+            "database": database.exists().then_some(&database),
+            "source": resolved.source.as_str(),
+            "redirects": resolved.redirects,
+            "prefix": store.map(|store| issue_prefix(ctx, store)),
+            "issue": args.id,
+        }));
+        return Ok(());
+    }
+
+    if let Some(id) = &args.id {
+        println!("{} is in {}", id, database.display());
+        return Ok(());
+    }
+    println!("{}", resolved.path.display());
+    let source = match resolved.source {
+        BeadsDirSource::Flag => "--db",
+        BeadsDirSource::Env => "BEADS_DIR",
+        BeadsDirSource::Discovered => "found above the current directory",
+    };
+    println!("  Source: {}", source);
+    for from in &resolved.redirects {
+        println!("  Redirected from: {}", from.display());
+    }
+    if database.exists() {
+        println!("  Database: {}", database.display());
+    } else {
+        println!("  Database: none yet (run 'bd init')");
+    }
+    if let Some(store) = store {
+        println!("  Prefix: {}", issue_prefix(ctx, store));
+    }
     Ok(())
 }
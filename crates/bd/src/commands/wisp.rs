@@ -1,12 +1,132 @@
-//! `bd wisp` -- ephemeral formula execution (Phase 4 stub).
+//! `bd wisp` -- ephemeral formula execution.
+//!
+//! Wisps are ephemeral issues: poured molecules that stay out of `bd ready`
+//! and are garbage collected by `bd cleanup --wisps` once they have been
+//! idle for longer than the TTL of their `WispType` (see `wisp:` in
+//! `.beads/config.yaml`).
 
-use anyhow::Result;
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Duration, Utc};
 
-use crate::cli::WispArgs;
+use beads_config::config::WispConfig;
+use beads_core::enums::WispType;
+use beads_core::filter::IssueFilter;
+use beads_core::issue::Issue;
+use beads_storage::Storage;
+use beads_timeparsing::{Unit, parse_duration_in};
+
+use crate::cli::{WispArgs, WispCommands, WispRunArgs};
+use crate::commands::mol;
 use crate::context::RuntimeContext;
+use crate::output::{output_json, output_table};
+
+/// Execute the `bd wisp` command.
+pub fn run(ctx: &RuntimeContext, args: &WispArgs) -> Result<()> {
+    match &args.command {
+        WispCommands::Run(a) => cmd_run(ctx, a),
+        WispCommands::List => cmd_list(ctx),
+    }
+}
+
+fn cmd_run(ctx: &RuntimeContext, args: &WispRunArgs) -> Result<()> {
+    let wisp_type = WispType::from(args.wisp_type.as_deref().unwrap_or_default());
+    let config = wisp_config(ctx)?;
+    ttl_duration(&config, &wisp_type)?;
+
+    mol::cmd_pour(
+        ctx,
+        Some(&args.formula),
+        &args.vars,
+        args.dry_run,
+        Some(&wisp_type),
+    )?;
+    if !ctx.json && !ctx.quiet && !args.dry_run {
+        println!(
+            "  Expires after {} without activity",
+            config.ttl_for(wisp_type.as_str())
+        );
+    }
+    Ok(())
+}
+
+fn cmd_list(ctx: &RuntimeContext) -> Result<()> {
+    let store = ctx.store()?;
+    let config = wisp_config(ctx)?;
+
+    let mut wisps = load_wisps(store)?;
+    wisps.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.id.cmp(&b.id)));
+    let mut rows = Vec::with_capacity(wisps.len());
+    for wisp in &wisps {
+        rows.push((wisp, expires_at(&config, wisp)?));
+    }
 
-/// Execute the `bd wisp` command (stub).
-pub fn run(_ctx: &RuntimeContext, _args: &WispArgs) -> Result<()> {
-    println!("bd wisp: ephemeral formula execution not yet implemented");
+    if ctx.json {
+        let items: Vec<serde_json::Value> = rows
+            .iter()
+            .map(|(w, expires)| {
+                serde_json::json!({
+                    "id": w.id,
+                    "title": w.title,
+                    "status": w.status,
+                    "wisp_type": w.wisp_type,
+                    "expires_at": expires.to_rfc3339(),
+                })
+            })
+            .collect();
+        output_json(&items);
+    } else if rows.is_empty() {
+        println!("No wisps found.");
+    } else {
+        let now = Utc::now();
+        let headers = &["ID", "TYPE", "STATUS", "EXPIRES", "TITLE"];
+        let rows: Vec<Vec<String>> = rows
+            .iter()
+            .map(|(w, expires)| {
+                let expiry = if *expires <= now {
+                    "expired".to_string()
+                } else {
+                    expires.format("%Y-%m-%d %H:%M").to_string()
+                };
+                vec![
+                    w.id.clone(),
+                    w.wisp_type.as_str().to_string(),
+                    w.status.to_string(),
+                    expiry,
+                    w.title.clone(),
+                ]
+            })
+            .collect();
+        output_table(headers, &rows);
+    }
     Ok(())
 }
+
+// ---------------------------------------------------------------------------
+// Expiry
+// ---------------------------------------------------------------------------
+
+/// The `wisp:` section of `.beads/config.yaml`.
+pub(crate) fn wisp_config(ctx: &RuntimeContext) -> Result<WispConfig> {
+    Ok(ctx.config()?.wisp.clone())
+}
+
+/// All ephemeral issues.
+pub(crate) fn load_wisps(store: &dyn Storage) -> Result<Vec<Issue>> {
+    let filter = IssueFilter {
+        ephemeral: Some(true),
+        ..Default::default()
+    };
+    Ok(store.search_issues("", &filter)?)
+}
+
+/// The TTL of wisps of `wisp_type`; a bare number is seconds.
+fn ttl_duration(config: &WispConfig, wisp_type: &WispType) -> Result<Duration> {
+    let ttl = config.ttl_for(wisp_type.as_str());
+    parse_duration_in(ttl, Unit::Second)
+        .map_err(|e| anyhow!("invalid TTL for {} wisps: {}", wisp_type.as_str(), e))
+}
+
+/// When `wisp` expires: its last update plus the TTL of its type.
+pub(crate) fn expires_at(config: &WispConfig, wisp: &Issue) -> Result<DateTime<Utc>> {
+    Ok(wisp.updated_at + ttl_duration(config, &wisp.wisp_type)?)
+}
@@ -8,9 +8,11 @@ use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use anyhow::{Context, Result, bail};
+use beads_lockfile::{Lock, LockMode};
 
 use crate::cli::{WorktreeArgs, WorktreeCommands, WorktreeCreateArgs, WorktreeRemoveArgs};
 use crate::context::RuntimeContext;
+use crate::locks;
 use crate::output::output_json;
 
 /// Execute the `bd worktree` command.
@@ -46,6 +48,8 @@ fn run_create(ctx: &RuntimeContext, args: &WorktreeCreateArgs) -> Result<()> {
         );
     }
 
+    let _lock = lock_worktrees(&main_beads)?;
+
     // Create the worktree
     let output = Command::new("git")
         .args(["worktree", "add", "-b", branch])
@@ -94,6 +98,8 @@ fn run_remove(ctx: &RuntimeContext, args: &WorktreeRemoveArgs) -> Result<()> {
         check_worktree_safety(&wt_path)?;
     }
 
+    let _lock = lock_worktrees(&repo_root.join(".beads"))?;
+
     // Remove the worktree
     let mut cmd_args = vec!["worktree", "remove"];
     if args.force {
@@ -312,6 +318,15 @@ fn parse_worktree_list(output: &str) -> Vec<WorktreeEntry> {
     entries
 }
 
+/// Serializes worktree creation and removal, which rewrite the repository
+/// `.gitignore`. Nothing to lock if the main `.beads` directory is missing.
+fn lock_worktrees(main_beads: &Path) -> Result<Option<Lock>> {
+    if !main_beads.is_dir() {
+        return Ok(None);
+    }
+    locks::acquire(&main_beads.join(locks::WORKTREE_LOCK), LockMode::Exclusive).map(Some)
+}
+
 fn git_repo_root() -> Result<PathBuf> {
     let output = Command::new("git")
         .args(["rev-parse", "--show-toplevel"])
@@ -1,19 +1,29 @@
 //! Runtime context for command execution.
 //!
 //! The [`RuntimeContext`] holds all the state a command handler needs:
-//! resolved database path, actor name, global flags, and (eventually)
-//! the storage handle.
+//! resolved database path, actor name, global flags, and the storage
+//! handle.
 
 use std::env;
+use std::fmt;
+use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+use beads_config::beads_dir::{self, ResolvedBeadsDir};
+use beads_config::config::BeadsConfig;
+use beads_config::layered::LayeredConfig;
+use beads_lockfile::{Lock, LockMode};
+use beads_storage::{SqliteStore, Storage};
 
 use crate::cli::GlobalArgs;
+use crate::locks;
 
 /// Runtime context passed to every command handler.
 ///
 /// Constructed once in `main` after CLI parsing, before command dispatch.
-#[derive(Debug)]
 pub struct RuntimeContext {
     /// Resolved database directory path (e.g., `/repo/.beads`).
     pub db_path: Option<PathBuf>,
@@ -25,7 +35,6 @@ pub struct RuntimeContext {
     pub json: bool,
 
     /// Sandbox mode: disables auto-sync.
-    #[allow(dead_code)]
     pub sandbox: bool,
 
     /// Allow operations on potentially stale data.
@@ -40,54 +49,190 @@ pub struct RuntimeContext {
 
     /// Quiet mode: suppress non-essential output.
     pub quiet: bool,
+
+    /// Storage backend, opened on first use by [`RuntimeContext::store`].
+    store: OnceLock<Box<dyn Storage>>,
+
+    /// Shared lock on `beads.db`, held for as long as the store is open.
+    db_lock: OnceLock<Lock>,
+
+    /// Config keys set by global flags, the top configuration layer.
+    config_flags: Vec<(&'static str, String)>,
+
+    /// Layered configuration, loaded on first use by
+    /// [`RuntimeContext::layered_config`].
+    config: OnceLock<LayeredConfig>,
+}
+
+impl fmt::Debug for RuntimeContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RuntimeContext")
+            .field("db_path", &self.db_path)
+            .field("actor", &self.actor)
+            .field("json", &self.json)
+            .field("sandbox", &self.sandbox)
+            .field("readonly", &self.readonly)
+            .field("verbose", &self.verbose)
+            .field("quiet", &self.quiet)
+            .finish_non_exhaustive()
+    }
 }
 
 impl RuntimeContext {
     /// Build a `RuntimeContext` from parsed global arguments.
     ///
-    /// Resolves the actor name using the same priority chain as the Go version:
-    /// `--actor` flag > `BD_ACTOR` env > `BEADS_ACTOR` env > `git config user.name` > `$USER` > `"unknown"`.
+    /// The database path, actor and JSON mode come from the global flags,
+    /// falling back to the layered configuration (`config.yaml`, `BD_*`).
+    /// The actor then follows the same priority chain as the Go version:
+    /// `--actor` flag > `actor` config (`BD_ACTOR`) > `BEADS_ACTOR` env >
+    /// `git config user.name` > `$USER` > `"unknown"`.
     pub fn from_global_args(global: &GlobalArgs) -> Self {
-        let actor = resolve_actor(global.actor.as_deref());
-
         let db_path = global.db.as_ref().map(PathBuf::from);
 
-        Self {
+        let mut config_flags = Vec::new();
+        if let Some(ref db) = global.db {
+            config_flags.push(("db", db.clone()));
+        }
+        if let Some(ref actor) = global.actor {
+            config_flags.push(("actor", actor.clone()));
+        }
+        if global.json {
+            config_flags.push(("json", "true".to_string()));
+        }
+
+        let mut ctx = Self {
             db_path,
-            actor,
+            actor: String::new(),
             json: global.json,
             sandbox: global.sandbox,
             allow_stale: global.allow_stale,
             readonly: global.readonly,
             verbose: global.verbose,
             quiet: global.quiet,
+            store: OnceLock::new(),
+            db_lock: OnceLock::new(),
+            config_flags,
+            config: OnceLock::new(),
+        };
+
+        // A configured `db` points at another `.beads` directory, unless
+        // `--db` or `BEADS_DIR` already chose one. Configuration errors are
+        // reported later, by the commands that need the configuration.
+        let beads_dir_env = env::var_os(beads_dir::BEADS_DIR_ENV).is_some_and(|v| !v.is_empty());
+        if ctx.db_path.is_none() && !beads_dir_env {
+            let configured = ctx.config().ok().and_then(|c| c.db.clone());
+            if let Some(db) = configured {
+                ctx.db_path = Some(PathBuf::from(db));
+                ctx.config = OnceLock::new();
+            }
         }
+        let (actor, json) = match ctx.config() {
+            Ok(config) => (config.actor.clone(), config.json),
+            Err(_) => (None, false),
+        };
+        ctx.actor = resolve_actor(global.actor.as_deref().or(actor.as_deref()));
+        ctx.json |= json;
+        ctx
     }
 
-    /// Discover the `.beads` directory by walking up from the current directory.
+    /// Discover the `.beads` directory from `BEADS_DIR` or by walking up from
+    /// the current directory, following redirects.
     ///
     /// Returns `None` if no `.beads` directory is found.
     pub fn find_beads_dir() -> Option<PathBuf> {
-        let mut dir = env::current_dir().ok()?;
-        loop {
-            let candidate = dir.join(".beads");
-            if candidate.is_dir() {
-                return Some(candidate);
-            }
-            if !dir.pop() {
-                break;
-            }
-        }
-        None
+        beads_dir::find_beads_dir(&env::current_dir().ok()?)
     }
 
-    /// Returns the resolved database path, auto-discovering if needed.
+    /// Resolves the `.beads` directory: `--db`, then `BEADS_DIR`, then the
+    /// nearest `.beads` above the current directory, following
+    /// `.beads/redirect` files.
+    ///
+    /// Returns `Ok(None)` if no `.beads` directory is found.
+    pub fn resolve_beads_dir(&self) -> Result<Option<ResolvedBeadsDir>> {
+        let cwd = env::current_dir().context("failed to get current directory")?;
+        Ok(beads_dir::resolve_beads_dir(&cwd, self.db_path.as_deref())?)
+    }
+
+    /// Returns the resolved `.beads` directory, or `None` if there is none or
+    /// it cannot be resolved.
     pub fn resolve_db_path(&self) -> Option<PathBuf> {
-        if let Some(ref p) = self.db_path {
-            return Some(p.clone());
+        self.resolve_beads_dir().ok().flatten().map(|r| r.path)
+    }
+
+    /// Returns the layered configuration: defaults, the user config,
+    /// `.beads/config.yaml`, `BD_*` environment variables and global flags.
+    pub fn layered_config(&self) -> Result<&LayeredConfig> {
+        if let Some(config) = self.config.get() {
+            return Ok(config);
+        }
+        let config = LayeredConfig::load(self.resolve_db_path().as_deref(), &self.config_flags)
+            .context("failed to load configuration")?;
+        Ok(self.config.get_or_init(|| config))
+    }
+
+    /// Returns the effective configuration; see
+    /// [`RuntimeContext::layered_config`].
+    pub fn config(&self) -> Result<&BeadsConfig> {
+        Ok(&self.layered_config()?.config)
+    }
+
+    /// Returns the storage backend for the resolved `.beads` directory,
+    /// opening it on first use.
+    ///
+    /// Fails with a `bd init` hint when no database exists yet.
+    pub fn store(&self) -> Result<&dyn Storage> {
+        if let Some(store) = self.store.get() {
+            return Ok(store.as_ref());
+        }
+        let LockedStore { store, lock } = self.open_store()?;
+        let _ = self.db_lock.set(lock);
+        Ok(self.store.get_or_init(|| Box::new(store)).as_ref())
+    }
+
+    /// Opens a new handle on the SQLite store in the resolved `.beads`
+    /// directory, independent of [`RuntimeContext::store`].
+    ///
+    /// The database lock is held shared until the handle is dropped, so
+    /// `bd migrate` in another process waits for it; long-running commands
+    /// open one per pass rather than holding the lock throughout. Pending
+    /// migrations are applied under the exclusive lock, which is then traded
+    /// back for a shared one.
+    pub fn open_store(&self) -> Result<LockedStore> {
+        let db_path = self.db_file()?;
+        let open_err = || format!("failed to open database: {}", db_path.display());
+
+        let shared = locks::lock_file(&db_path, LockMode::Shared)?;
+        let store = SqliteStore::open_without_migrations(&db_path).with_context(open_err)?;
+        if !store.migration_status()?.pending.is_empty() {
+            drop(store);
+            drop(shared);
+            let exclusive = locks::lock_file(&db_path, LockMode::Exclusive)?;
+            SqliteStore::open(&db_path).with_context(open_err)?;
+            drop(exclusive);
+            return self.open_store();
+        }
+        Ok(LockedStore {
+            store,
+            lock: shared,
+        })
+    }
+
+    /// Returns the path of the existing `beads.db` in the resolved `.beads`
+    /// directory.
+    pub fn db_file(&self) -> Result<PathBuf> {
+        let beads_dir = self
+            .resolve_beads_dir()?
+            .context("no beads database found. Run 'bd init' to create one.")?
+            .path;
+        let db_path = beads_dir.join("beads.db");
+
+        if !db_path.exists() {
+            bail!(
+                "no beads database found at {}\nHint: run 'bd init' to create a database",
+                db_path.display()
+            );
         }
-        // Auto-discover .beads directory
-        Self::find_beads_dir()
+        Ok(db_path)
     }
 
     /// Returns `true` if the `.beads` directory exists relative to the given path.
@@ -97,11 +242,27 @@ impl RuntimeContext {
     }
 }
 
+/// A store handle holding the shared database lock; see
+/// [`RuntimeContext::open_store`].
+pub struct LockedStore {
+    store: SqliteStore,
+    lock: Lock,
+}
+
+impl Deref for LockedStore {
+    type Target = SqliteStore;
+
+    fn deref(&self) -> &SqliteStore {
+        &self.store
+    }
+}
+
 /// Resolves the actor name using the priority chain.
 ///
-/// Priority: explicit flag > BD_ACTOR env > BEADS_ACTOR env > git config user.name > USER env > "unknown".
+/// Priority: explicit flag or configured actor > BD_ACTOR env > BEADS_ACTOR
+/// env > git config user.name > USER env > "unknown".
 fn resolve_actor(flag_value: Option<&str>) -> String {
-    // 1. Explicit flag value
+    // 1. Explicit flag value, or the `actor` config key
     if let Some(actor) = flag_value {
         if !actor.is_empty() {
             return actor.to_string();
@@ -0,0 +1,124 @@
+//! `all:` and `any:` gates combining other gate specs.
+
+use super::{GateContext, GateEvaluator, GateResult};
+
+/// `all:<json list>` resolves when every spec has, `any:<json list>` when
+/// one has. Specs are await types with their argument, e.g.
+/// `["file:dist/app", "issue:bd-12"]`.
+pub(super) enum Composite {
+    All,
+    Any,
+}
+
+impl GateEvaluator for Composite {
+    fn name(&self) -> &str {
+        match self {
+            Composite::All => "all",
+            Composite::Any => "any",
+        }
+    }
+
+    fn evaluate(&self, arg: &str, ctx: &GateContext<'_>) -> GateResult {
+        let specs: Vec<String> = match serde_json::from_str(arg) {
+            Ok(specs) => specs,
+            Err(e) => {
+                return GateResult::Error(format!(
+                    "{} gate needs a JSON list of gate specs: {}",
+                    self.name(),
+                    e
+                ));
+            }
+        };
+        if specs.is_empty() {
+            return GateResult::Error(format!("{} gate has no gate specs", self.name()));
+        }
+
+        let results: Vec<GateResult> = specs
+            .iter()
+            .map(|spec| match ctx.registry.resolve(spec, "") {
+                Some((evaluator, arg)) => evaluator.evaluate(&arg, ctx),
+                None => GateResult::Error(format!("unknown gate spec '{}'", spec)),
+            })
+            .collect();
+        let reasons = || {
+            results
+                .iter()
+                .filter_map(|r| match r {
+                    GateResult::Resolved(reason) | GateResult::Error(reason) => {
+                        Some(reason.as_str())
+                    }
+                    GateResult::Pending => None,
+                })
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+
+        match self {
+            Composite::All => {
+                if results.iter().any(|r| matches!(r, GateResult::Error(_))) {
+                    let errors: Vec<&str> = results
+                        .iter()
+                        .filter_map(|r| match r {
+                            GateResult::Error(e) => Some(e.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    GateResult::Error(errors.join("; "))
+                } else if results.contains(&GateResult::Pending) {
+                    GateResult::Pending
+                } else {
+                    GateResult::Resolved(format!("all resolved: {}", reasons()))
+                }
+            }
+            Composite::Any => {
+                if let Some(GateResult::Resolved(reason)) = results
+                    .iter()
+                    .find(|r| matches!(r, GateResult::Resolved(_)))
+                {
+                    GateResult::Resolved(reason.clone())
+                } else if results.iter().all(|r| matches!(r, GateResult::Error(_))) {
+                    GateResult::Error(reasons())
+                } else {
+                    GateResult::Pending
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gates::tests::{context, trusting};
+
+    #[test]
+    fn all_and_any_combine_specs() {
+        let registry = trusting();
+        let ctx = context(&registry, None);
+
+        assert!(matches!(
+            registry.evaluate(r#"all:["cmd:true","timer:1m"]"#, "", &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(
+            registry.evaluate("all", r#"["cmd:true","cmd:false"]"#, &ctx),
+            GateResult::Pending
+        );
+        assert!(matches!(
+            registry.evaluate(r#"any:["cmd:false","cmd:true"]"#, "", &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(
+            registry.evaluate(r#"any:["cmd:false","human"]"#, "", &ctx),
+            GateResult::Pending
+        );
+        assert!(matches!(
+            registry.evaluate(r#"all:["cmd:true","bogus:x"]"#, "", &ctx),
+            GateResult::Error(_)
+        ));
+        assert!(matches!(
+            registry.evaluate("any:cmd:true", "", &ctx),
+            GateResult::Error(_)
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+//! Evaluators backed by external commands.
+//!
+//! `gate.evaluators` in `.beads/config.yaml` maps an await type to a shell
+//! command. To evaluate a gate, bd runs the command with a JSON request on
+//! stdin:
+//!
+//! ```json
+//! {"type": "jenkins", "arg": "deploy#42", "gate": {"id": "bd-x1",
+//!  "created_at": "2026-01-01T00:00:00Z", "timeout_ns": 0},
+//!  "now": "2026-01-01T01:00:00Z"}
+//! ```
+//!
+//! and expects a JSON answer on stdout:
+//!
+//! ```json
+//! {"status": "resolved", "reason": "build 42 passed"}
+//! ```
+//!
+//! where `status` is `resolved`, `pending` or `error`. A non-zero exit is an
+//! error.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::{GateContext, GateEvaluator, GateResult, run_command, shell};
+
+/// How long an external evaluator may run.
+const EXTERNAL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An await type evaluated by an external command.
+pub(super) struct External {
+    name: String,
+    command: String,
+}
+
+impl External {
+    pub(super) fn new(name: &str, command: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            command: command.to_string(),
+        }
+    }
+}
+
+/// The answer of an external evaluator.
+#[derive(Deserialize)]
+struct Answer {
+    status: String,
+    #[serde(default)]
+    reason: String,
+}
+
+impl GateEvaluator for External {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn evaluate(&self, arg: &str, ctx: &GateContext<'_>) -> GateResult {
+        let request = serde_json::json!({
+            "type": self.name,
+            "arg": arg,
+            "gate": {
+                "id": ctx.gate_id,
+                "created_at": ctx.created_at.to_rfc3339(),
+                "timeout_ns": ctx.timeout.and_then(|t| t.num_nanoseconds()).unwrap_or(0),
+            },
+            "now": ctx.now.to_rfc3339(),
+        });
+        let output = match run_command(
+            shell(&self.command),
+            Some(request.to_string().as_bytes()),
+            EXTERNAL_TIMEOUT,
+        ) {
+            Ok(output) => output,
+            Err(e) => return GateResult::Error(e),
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return GateResult::Error(format!("{} evaluator failed: {}", self.name, stderr.trim()));
+        }
+        let answer: Answer = match serde_json::from_slice(&output.stdout) {
+            Ok(answer) => answer,
+            Err(e) => {
+                return GateResult::Error(format!(
+                    "{} evaluator gave an invalid answer: {}",
+                    self.name, e
+                ));
+            }
+        };
+        match answer.status.as_str() {
+            "resolved" if answer.reason.is_empty() => {
+                GateResult::Resolved(format!("{} gate resolved", self.name))
+            }
+            "resolved" => GateResult::Resolved(answer.reason),
+            "pending" => GateResult::Pending,
+            "error" => GateResult::Error(answer.reason),
+            other => GateResult::Error(format!(
+                "{} evaluator answered unknown status '{}'",
+                self.name, other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gates::GateRegistry;
+    use crate::gates::tests::context;
+
+    #[test]
+    fn external_commands_speak_json() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+
+        // Resolves when the request's arg is "ok", echoing the gate ID
+        let script = r#"req=$(cat); case "$req" in
+            *'"arg":"ok"'*) echo '{"status":"resolved","reason":"ok for g-1"}' ;;
+            *) echo '{"status":"pending"}' ;;
+        esac"#;
+        let external = External::new("ci", script);
+        assert_eq!(
+            external.evaluate("ok", &ctx),
+            GateResult::Resolved("ok for g-1".to_string())
+        );
+        assert_eq!(external.evaluate("later", &ctx), GateResult::Pending);
+
+        let failing = External::new("ci", "echo broken >&2; exit 2");
+        assert_eq!(
+            failing.evaluate("", &ctx),
+            GateResult::Error("ci evaluator failed: broken".to_string())
+        );
+        let garbled = External::new("ci", "echo nope");
+        assert!(matches!(garbled.evaluate("", &ctx), GateResult::Error(_)));
+    }
+}
@@ -0,0 +1,260 @@
+//! Evaluators that look at the clock, the file system, local commands and
+//! other issues.
+
+use std::path::Path;
+use std::time::Duration;
+
+use sha2::{Digest, Sha256};
+
+use beads_core::enums::Status;
+use beads_timeparsing::{Unit, format_duration, parse_duration_in};
+
+use super::{GateContext, GateEvaluator, GateResult, run_command, shell};
+
+/// How long a `cmd:` gate may run.
+const CMD_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Stands in for an evaluator that has to be allowed first: every gate of
+/// its await type errors until `setting` is enabled.
+pub(super) struct Disabled {
+    pub(super) name: String,
+    pub(super) setting: &'static str,
+}
+
+impl GateEvaluator for Disabled {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn evaluate(&self, _arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        GateResult::Error(format!(
+            "{} gates are disabled; set {}: true in your user config to allow them",
+            self.name, self.setting
+        ))
+    }
+}
+
+/// `human`: only closing the gate by hand resolves it.
+pub(super) struct Human;
+
+impl GateEvaluator for Human {
+    fn name(&self) -> &str {
+        "human"
+    }
+
+    fn evaluate(&self, _arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        GateResult::Pending
+    }
+}
+
+/// `timer[:<duration>]`: resolves once the duration, or the gate's timeout,
+/// has passed since the gate was created.
+pub(super) struct Timer;
+
+impl GateEvaluator for Timer {
+    fn name(&self) -> &str {
+        "timer"
+    }
+
+    fn evaluate(&self, arg: &str, ctx: &GateContext<'_>) -> GateResult {
+        let timeout = if arg.trim().is_empty() {
+            match ctx.timeout {
+                Some(timeout) if timeout > chrono::Duration::zero() => timeout,
+                _ => return GateResult::Pending,
+            }
+        } else {
+            match parse_duration_in(arg, Unit::Second) {
+                Ok(timeout) => timeout,
+                Err(e) => return GateResult::Error(e.to_string()),
+            }
+        };
+        if ctx.now.signed_duration_since(ctx.created_at) >= timeout {
+            GateResult::Resolved(format!("timer expired ({})", format_duration(timeout)))
+        } else {
+            GateResult::Pending
+        }
+    }
+}
+
+/// `file:<path>[#sha256=<hex>]`: resolves once the file exists, with the
+/// given SHA-256 if one is given.
+pub(super) struct File;
+
+impl GateEvaluator for File {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn evaluate(&self, arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        let (path, hash) = match arg.rsplit_once("#sha256=") {
+            Some((path, hash)) => (path, Some(hash.trim().to_lowercase())),
+            None => (arg, None),
+        };
+        if path.trim().is_empty() {
+            return GateResult::Error("no path set for file gate".to_string());
+        }
+        let path = Path::new(path.trim());
+        if !path.is_file() {
+            return GateResult::Pending;
+        }
+        let Some(hash) = hash else {
+            return GateResult::Resolved(format!("file {} exists", path.display()));
+        };
+        match std::fs::read(path) {
+            Ok(bytes) if format!("{:x}", Sha256::digest(&bytes)) == hash => {
+                GateResult::Resolved(format!("file {} matches sha256 {}", path.display(), hash))
+            }
+            Ok(_) => GateResult::Pending,
+            Err(e) => GateResult::Error(format!("failed to read {}: {}", path.display(), e)),
+        }
+    }
+}
+
+/// `cmd:<shell>`: resolves once the command exits 0.
+pub(super) struct Cmd;
+
+impl GateEvaluator for Cmd {
+    fn name(&self) -> &str {
+        "cmd"
+    }
+
+    fn evaluate(&self, arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        if arg.trim().is_empty() {
+            return GateResult::Error("no command set for cmd gate".to_string());
+        }
+        match run_command(shell(arg), None, CMD_TIMEOUT) {
+            Ok(output) if output.status.success() => {
+                GateResult::Resolved(format!("command succeeded: {}", arg))
+            }
+            Ok(_) => GateResult::Pending,
+            Err(e) => GateResult::Error(e),
+        }
+    }
+}
+
+/// `issue:<id> [reason=<text>]`: resolves once the issue is closed, with
+/// the given close reason if one is given.
+pub(super) struct IssueClosed;
+
+impl GateEvaluator for IssueClosed {
+    fn name(&self) -> &str {
+        "issue"
+    }
+
+    fn evaluate(&self, arg: &str, ctx: &GateContext<'_>) -> GateResult {
+        let (id, reason) = match arg.split_once(" reason=") {
+            Some((id, reason)) => (id.trim(), Some(reason.trim())),
+            None => (arg.trim(), None),
+        };
+        if id.is_empty() {
+            return GateResult::Error("no issue set for issue gate".to_string());
+        }
+        let Some(store) = ctx.store else {
+            return GateResult::Error("issue gates need a database".to_string());
+        };
+        let issue = match store.get_issue(id) {
+            Ok(issue) => issue,
+            Err(e) if e.is_not_found() => {
+                return GateResult::Error(format!("issue {} not found", id));
+            }
+            Err(e) => return GateResult::Error(e.to_string()),
+        };
+        if issue.status != Status::Closed {
+            return GateResult::Pending;
+        }
+        match reason {
+            Some(reason) if issue.close_reason != reason => GateResult::Error(format!(
+                "issue {} was closed with reason '{}', not '{}'",
+                id, issue.close_reason, reason
+            )),
+            _ => GateResult::Resolved(format!("issue {} closed", id)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gates::GateRegistry;
+    use crate::gates::tests::context;
+    use beads_core::issue::Issue;
+    use beads_storage::{SqliteStore, Storage};
+
+    #[test]
+    fn timer_uses_argument_or_gate_timeout() {
+        let registry = GateRegistry::builtin();
+        let mut ctx = context(&registry, None);
+        assert_eq!(Timer.evaluate("", &ctx), GateResult::Pending);
+        assert!(matches!(
+            Timer.evaluate("30m", &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(Timer.evaluate("2h", &ctx), GateResult::Pending);
+        ctx.timeout = Some(chrono::Duration::minutes(5));
+        assert!(matches!(Timer.evaluate("", &ctx), GateResult::Resolved(_)));
+    }
+
+    #[test]
+    fn file_checks_existence_and_hash() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ready");
+        let spec = path.display().to_string();
+        assert_eq!(File.evaluate(&spec, &ctx), GateResult::Pending);
+
+        std::fs::write(&path, "ok").unwrap();
+        assert!(matches!(
+            File.evaluate(&spec, &ctx),
+            GateResult::Resolved(_)
+        ));
+        let hash = format!("{:x}", Sha256::digest(b"ok"));
+        assert!(matches!(
+            File.evaluate(&format!("{spec}#sha256={hash}"), &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(
+            File.evaluate(&format!("{spec}#sha256=00"), &ctx),
+            GateResult::Pending
+        );
+    }
+
+    #[test]
+    fn cmd_checks_exit_code() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+        assert!(matches!(
+            Cmd.evaluate("true", &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(Cmd.evaluate("exit 3", &ctx), GateResult::Pending);
+    }
+
+    #[test]
+    fn issue_checks_status_and_reason() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let issue = Issue {
+            id: "t-1".to_string(),
+            title: "Review".to_string(),
+            ..Issue::default()
+        };
+        store.create_issue(&issue, "tester").unwrap();
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, Some(&store));
+
+        assert_eq!(IssueClosed.evaluate("t-1", &ctx), GateResult::Pending);
+        store.close_issue("t-1", "approved", "tester", "").unwrap();
+        assert!(matches!(
+            IssueClosed.evaluate("t-1 reason=approved", &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert!(matches!(
+            IssueClosed.evaluate("t-1 reason=rejected", &ctx),
+            GateResult::Error(_)
+        ));
+        assert!(matches!(
+            IssueClosed.evaluate("t-404", &ctx),
+            GateResult::Error(_)
+        ));
+    }
+}
@@ -0,0 +1,382 @@
+//! Gate evaluators.
+//!
+//! A gate's `await_type` names the [`GateEvaluator`] that decides whether
+//! it has resolved; the evaluator's argument is the rest of the await type
+//! after `<name>:` (`file:/tmp/ready`), or else the gate's `await_id`.
+//!
+//! Built in:
+//! - `human`: pending until closed by hand
+//! - `timer[:<duration>]`: resolves once the duration (default: the gate's
+//!   timeout) has passed since the gate was created
+//! - `file:<path>[#sha256=<hex>]`: the file exists (with that hash)
+//! - `cmd:<shell>`: the command exits 0
+//! - `http:<url> [status=<code> | <.json.path>=<value>]`: the URL answers
+//!   2xx, the given status, or JSON with that value
+//! - `issue:<id> [reason=<text>]`: the issue is closed (with that reason)
+//! - `gh:run:<id>`, `gh:pr:<number>`: a GitHub Actions run succeeded or a PR
+//!   was merged
+//! - `all:<json list>`, `any:<json list>`: composites of other specs, e.g.
+//!   `all:["file:dist/app","issue:bd-12"]`
+//!
+//! Further evaluators are configured under `gate.evaluators` in
+//! `.beads/config.yaml` as external commands (see [`external`]). They cannot
+//! replace a built-in await type.
+//!
+//! A gate's await type is issue data that arrives from other clones through
+//! import and sync, so `cmd:` and `http:` gates, which would run a command or
+//! make a request on anyone's say-so, error unless `gate.allow-cmd` or
+//! `gate.allow-http` is set on this machine. The project config is shared
+//! the same way, so its external evaluators error unless
+//! `gate.allow-external` is set on this machine.
+
+mod composite;
+mod external;
+mod local;
+mod remote;
+
+use std::io::{Read, Write};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+
+use beads_config::config::GateConfig;
+use beads_storage::Storage;
+
+/// Outcome of evaluating a gate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GateResult {
+    /// The condition holds; the gate can close with this reason.
+    Resolved(String),
+    /// The condition does not hold yet.
+    Pending,
+    /// The condition cannot be checked, or can no longer hold.
+    Error(String),
+}
+
+/// What an evaluator may look at besides its argument.
+pub struct GateContext<'a> {
+    /// Registry for evaluating nested specs.
+    pub registry: &'a GateRegistry,
+    /// Database, for evaluators that look at other issues.
+    pub store: Option<&'a dyn Storage>,
+    /// The gate being evaluated.
+    pub gate_id: &'a str,
+    /// When the gate was created.
+    pub created_at: DateTime<Utc>,
+    /// The gate's timeout, if any.
+    pub timeout: Option<chrono::Duration>,
+    /// Evaluation time.
+    pub now: DateTime<Utc>,
+}
+
+/// Decides whether gates of one await type have resolved.
+pub trait GateEvaluator {
+    /// The await type handled, e.g. `file` or `gh:run`.
+    fn name(&self) -> &str;
+
+    /// Evaluates a gate whose argument is `arg`.
+    fn evaluate(&self, arg: &str, ctx: &GateContext<'_>) -> GateResult;
+}
+
+/// The evaluators known by await type.
+pub struct GateRegistry {
+    evaluators: Vec<Box<dyn GateEvaluator>>,
+}
+
+impl GateRegistry {
+    /// A registry with the built-in evaluators, `cmd` and `http` disabled.
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            evaluators: Vec::new(),
+        };
+        registry.register(Box::new(local::Human));
+        registry.register(Box::new(local::Timer));
+        registry.register(Box::new(local::File));
+        registry.register(Box::new(local::Disabled {
+            name: "cmd".to_string(),
+            setting: "gate.allow-cmd",
+        }));
+        registry.register(Box::new(local::IssueClosed));
+        registry.register(Box::new(local::Disabled {
+            name: "http".to_string(),
+            setting: "gate.allow-http",
+        }));
+        registry.register(Box::new(remote::GhRun::default()));
+        registry.register(Box::new(remote::GhPr::default()));
+        registry.register(Box::new(composite::Composite::All));
+        registry.register(Box::new(composite::Composite::Any));
+        registry
+    }
+
+    /// The built-in evaluators, with `cmd` and `http` as allowed by
+    /// `config`, plus the external ones configured in `gate.evaluators`
+    /// (disabled unless `config.allow_external`). Configured evaluators
+    /// named like a built-in one are ignored.
+    pub fn with_config(config: &GateConfig) -> Self {
+        let mut registry = Self::builtin();
+        if config.allow_cmd {
+            registry.register(Box::new(local::Cmd));
+        }
+        if config.allow_http {
+            registry.register(Box::new(remote::Http::default()));
+        }
+        let mut names: Vec<&String> = config.evaluators.keys().collect();
+        names.sort();
+        for name in names {
+            // Only built-ins are registered so far
+            if registry.get(name).is_some() {
+                continue;
+            }
+            if config.allow_external {
+                registry.register(Box::new(external::External::new(
+                    name,
+                    &config.evaluators[name],
+                )));
+            } else {
+                registry.register(Box::new(local::Disabled {
+                    name: name.clone(),
+                    setting: "gate.allow-external",
+                }));
+            }
+        }
+        registry
+    }
+
+    /// Adds `evaluator`, replacing any evaluator of the same name.
+    pub fn register(&mut self, evaluator: Box<dyn GateEvaluator>) {
+        self.evaluators.retain(|e| e.name() != evaluator.name());
+        self.evaluators.push(evaluator);
+    }
+
+    /// Names of the known await types, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.evaluators.iter().map(|e| e.name()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn get(&self, name: &str) -> Option<&dyn GateEvaluator> {
+        self.evaluators
+            .iter()
+            .find(|e| e.name() == name)
+            .map(|e| e.as_ref())
+    }
+
+    /// The evaluator for `await_type` and its argument: the longest
+    /// registered name that `await_type` equals (argument `await_id`) or
+    /// starts with followed by `:` (argument: the rest).
+    pub fn resolve(
+        &self,
+        await_type: &str,
+        await_id: &str,
+    ) -> Option<(&dyn GateEvaluator, String)> {
+        if let Some(evaluator) = self.get(await_type) {
+            return Some((evaluator, await_id.to_string()));
+        }
+        await_type
+            .rmatch_indices(':')
+            .find_map(|(i, _)| Some((self.get(&await_type[..i])?, i)))
+            .map(|(evaluator, i)| (evaluator, await_type[i + 1..].to_string()))
+    }
+
+    /// Evaluates a gate. Unknown await types stay pending.
+    pub fn evaluate(&self, await_type: &str, await_id: &str, ctx: &GateContext<'_>) -> GateResult {
+        match self.resolve(await_type, await_id) {
+            Some((evaluator, arg)) => evaluator.evaluate(&arg, ctx),
+            None => GateResult::Pending,
+        }
+    }
+}
+
+/// Runs `command` with `input` on stdin, killing it after `timeout`.
+pub(crate) fn run_command(
+    mut command: Command,
+    input: Option<&[u8]>,
+    timeout: Duration,
+) -> Result<Output, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // A command that exits without reading its input is not an error
+        let _ = stdin.write_all(input);
+    }
+    // Drain the pipes while waiting so a chatty command cannot block
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "{} timed out after {}s",
+                    program,
+                    timeout.as_secs()
+                ));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(20)),
+            Err(e) => return Err(format!("failed to wait for {}: {}", program, e)),
+        }
+    };
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/// A `sh -c <script>` command.
+pub(crate) fn shell(script: &str) -> Command {
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(script);
+    command
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The built-in evaluators with `cmd` and `http` allowed.
+    pub(crate) fn trusting() -> GateRegistry {
+        GateRegistry::with_config(&GateConfig {
+            allow_cmd: true,
+            allow_http: true,
+            ..GateConfig::default()
+        })
+    }
+
+    /// A context for gate `g-1`, created an hour ago without a timeout.
+    pub(crate) fn context<'a>(
+        registry: &'a GateRegistry,
+        store: Option<&'a dyn Storage>,
+    ) -> GateContext<'a> {
+        let now = Utc::now();
+        GateContext {
+            registry,
+            store,
+            gate_id: "g-1",
+            created_at: now - chrono::Duration::hours(1),
+            timeout: None,
+            now,
+        }
+    }
+
+    #[test]
+    fn resolve_prefers_longest_name() {
+        let registry = GateRegistry::builtin();
+        let (e, arg) = registry.resolve("gh:run", "42").unwrap();
+        assert_eq!((e.name(), arg.as_str()), ("gh:run", "42"));
+        let (e, arg) = registry.resolve("gh:run:42", "").unwrap();
+        assert_eq!((e.name(), arg.as_str()), ("gh:run", "42"));
+        let (e, arg) = registry.resolve("file:/a:b", "").unwrap();
+        assert_eq!((e.name(), arg.as_str()), ("file", "/a:b"));
+        assert!(registry.resolve("nope:x", "").is_none());
+    }
+
+    #[test]
+    fn configured_evaluators_cannot_replace_builtins() {
+        let mut config = GateConfig {
+            allow_external: true,
+            ..GateConfig::default()
+        };
+        for name in ["file", "cmd", "human"] {
+            config.evaluators.insert(
+                name.to_string(),
+                "echo '{\"status\":\"resolved\"}'".to_string(),
+            );
+        }
+        let registry = GateRegistry::with_config(&config);
+        let ctx = context(&registry, None);
+        let missing = std::env::temp_dir().join("beads-no-such-file");
+        assert_eq!(
+            registry.evaluate(&format!("file:{}", missing.display()), "", &ctx),
+            GateResult::Pending
+        );
+        assert!(matches!(
+            registry.evaluate("cmd:true", "", &ctx),
+            GateResult::Error(e) if e.contains("gate.allow-cmd")
+        ));
+        assert_eq!(registry.evaluate("human", "", &ctx), GateResult::Pending);
+        assert_eq!(registry.names().iter().filter(|n| **n == "file").count(), 1);
+    }
+
+    #[test]
+    fn external_evaluators_need_allowing() {
+        let mut config = GateConfig::default();
+        config.evaluators.insert(
+            "ci".to_string(),
+            "echo '{\"status\":\"resolved\",\"reason\":\"green\"}'".to_string(),
+        );
+        let registry = GateRegistry::with_config(&config);
+        let ctx = context(&registry, None);
+        assert!(matches!(
+            registry.evaluate("ci:main", "", &ctx),
+            GateResult::Error(e) if e.contains("gate.allow-external")
+        ));
+
+        config.allow_external = true;
+        let registry = GateRegistry::with_config(&config);
+        let ctx = context(&registry, None);
+        assert_eq!(
+            registry.evaluate("ci:main", "", &ctx),
+            GateResult::Resolved("green".to_string())
+        );
+    }
+
+    #[test]
+    fn cmd_and_http_need_allowing() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+        let result = registry.evaluate("cmd:true", "", &ctx);
+        assert!(
+            matches!(&result, GateResult::Error(e) if e.contains("gate.allow-cmd")),
+            "{result:?}"
+        );
+        assert!(matches!(
+            registry.evaluate(r#"all:["cmd:true","timer:1m"]"#, "", &ctx),
+            GateResult::Error(_)
+        ));
+        assert!(matches!(
+            registry.evaluate("http:http://127.0.0.1:9", "", &ctx),
+            GateResult::Error(e) if e.contains("gate.allow-http")
+        ));
+
+        let registry = trusting();
+        let ctx = context(&registry, None);
+        assert!(matches!(
+            registry.evaluate("cmd:true", "", &ctx),
+            GateResult::Resolved(_)
+        ));
+    }
+
+    #[test]
+    fn run_command_times_out() {
+        let err = run_command(shell("sleep 5"), None, Duration::from_millis(100)).unwrap_err();
+        assert!(err.contains("timed out"), "{err}");
+    }
+}
@@ -0,0 +1,337 @@
+//! Evaluators that ask remote services, through `curl` and the GitHub CLI.
+
+use std::process::Command;
+use std::time::Duration;
+
+use super::{GateContext, GateEvaluator, GateResult, run_command};
+
+/// How long a remote check may take.
+const REMOTE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// `http:<url> [status=<code> | <.json.path>=<value>]`: resolves when the
+/// URL answers 2xx, answers with the given status, or answers 2xx with JSON
+/// holding the value at the path (`.items.0.state=done`).
+pub(super) struct Http {
+    /// The `curl` binary.
+    pub(super) curl: String,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            curl: "curl".to_string(),
+        }
+    }
+}
+
+/// What an `http:` gate checks in the response.
+#[derive(Debug, PartialEq)]
+enum HttpCheck<'a> {
+    Success,
+    Status(u16),
+    Json(&'a str, &'a str),
+}
+
+fn parse_http_arg(arg: &str) -> Result<(&str, HttpCheck<'_>), String> {
+    let arg = arg.trim();
+    let (url, check) = match arg.split_once(char::is_whitespace) {
+        Some((url, check)) => (url, check.trim()),
+        None => (arg, ""),
+    };
+    if url.is_empty() {
+        return Err("no URL set for http gate".to_string());
+    }
+    let check = if check.is_empty() {
+        HttpCheck::Success
+    } else if let Some(code) = check.strip_prefix("status=") {
+        HttpCheck::Status(
+            code.trim()
+                .parse()
+                .map_err(|_| format!("invalid status '{}'", code))?,
+        )
+    } else if let Some((path, value)) = check.split_once('=').filter(|(p, _)| p.starts_with('.')) {
+        HttpCheck::Json(path.trim(), value.trim())
+    } else {
+        return Err(format!(
+            "invalid http check '{}': use status=<code> or <.json.path>=<value>",
+            check
+        ));
+    };
+    Ok((url, check))
+}
+
+/// The value at a `.a.b.0` path, as text (strings unquoted).
+fn json_at(value: &serde_json::Value, path: &str) -> Option<String> {
+    let mut current = value;
+    for key in path
+        .trim_start_matches('.')
+        .split('.')
+        .filter(|k| !k.is_empty())
+    {
+        current = match current {
+            serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?)?,
+            other => other.get(key)?,
+        };
+    }
+    Some(match current {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    })
+}
+
+impl GateEvaluator for Http {
+    fn name(&self) -> &str {
+        "http"
+    }
+
+    fn evaluate(&self, arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        let (url, check) = match parse_http_arg(arg) {
+            Ok(parsed) => parsed,
+            Err(e) => return GateResult::Error(e),
+        };
+
+        let mut command = Command::new(&self.curl);
+        command
+            .args(["-sS", "-L", "--max-time"])
+            .arg(REMOTE_TIMEOUT.as_secs().to_string())
+            .args(["-o", "-", "-w", "\n%{http_code}"])
+            .arg(url);
+        let output = match run_command(command, None, REMOTE_TIMEOUT + Duration::from_secs(5)) {
+            Ok(output) => output,
+            Err(e) => return GateResult::Error(e),
+        };
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return GateResult::Error(format!("request to {} failed: {}", url, stderr.trim()));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (body, code) = stdout.rsplit_once('\n').unwrap_or(("", &stdout));
+        let Ok(status) = code.trim().parse::<u16>() else {
+            return GateResult::Error(format!("no HTTP status from {}", url));
+        };
+
+        match check {
+            HttpCheck::Success if (200..300).contains(&status) => {
+                GateResult::Resolved(format!("{} answered {}", url, status))
+            }
+            HttpCheck::Status(expected) if status == expected => {
+                GateResult::Resolved(format!("{} answered {}", url, status))
+            }
+            HttpCheck::Json(path, expected) if (200..300).contains(&status) => {
+                match serde_json::from_str::<serde_json::Value>(body) {
+                    Ok(json) if json_at(&json, path).as_deref() == Some(expected) => {
+                        GateResult::Resolved(format!("{}: {} is {}", url, path, expected))
+                    }
+                    Ok(_) => GateResult::Pending,
+                    Err(e) => GateResult::Error(format!("{} did not answer JSON: {}", url, e)),
+                }
+            }
+            _ => GateResult::Pending,
+        }
+    }
+}
+
+/// Runs `gh` with `args` and parses its JSON output.
+fn gh_json(program: &str, args: &[&str]) -> Result<serde_json::Value, String> {
+    let mut command = Command::new(program);
+    command.args(args);
+    let output = run_command(command, None, REMOTE_TIMEOUT)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "gh {} {} failed: {}",
+            args[0],
+            args[1],
+            stderr.trim()
+        ));
+    }
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("failed to parse gh output: {}", e))
+}
+
+/// `gh:run:<id>`: resolves when the GitHub Actions run completed
+/// successfully.
+pub(super) struct GhRun {
+    /// The `gh` binary.
+    pub(super) program: String,
+}
+
+impl Default for GhRun {
+    fn default() -> Self {
+        Self {
+            program: "gh".to_string(),
+        }
+    }
+}
+
+impl GateEvaluator for GhRun {
+    fn name(&self) -> &str {
+        "gh:run"
+    }
+
+    fn evaluate(&self, arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        if arg.is_empty() {
+            return GateResult::Error("no await_id set for gh:run gate".to_string());
+        }
+        let val = match gh_json(
+            &self.program,
+            &["run", "view", arg, "--json", "status,conclusion"],
+        ) {
+            Ok(val) => val,
+            Err(e) => return GateResult::Error(e),
+        };
+        let status = val["status"].as_str().unwrap_or("");
+        let conclusion = val["conclusion"].as_str().unwrap_or("");
+        if status == "completed" && conclusion == "success" {
+            GateResult::Resolved(format!("GitHub Actions run {} completed successfully", arg))
+        } else if status == "completed" {
+            GateResult::Error(format!(
+                "run {} completed with conclusion: {}",
+                arg, conclusion
+            ))
+        } else {
+            GateResult::Pending
+        }
+    }
+}
+
+/// `gh:pr:<number>`: resolves when the GitHub PR was merged.
+pub(super) struct GhPr {
+    /// The `gh` binary.
+    pub(super) program: String,
+}
+
+impl Default for GhPr {
+    fn default() -> Self {
+        Self {
+            program: "gh".to_string(),
+        }
+    }
+}
+
+impl GateEvaluator for GhPr {
+    fn name(&self) -> &str {
+        "gh:pr"
+    }
+
+    fn evaluate(&self, arg: &str, _ctx: &GateContext<'_>) -> GateResult {
+        if arg.is_empty() {
+            return GateResult::Error("no await_id set for gh:pr gate".to_string());
+        }
+        let val = match gh_json(
+            &self.program,
+            &["pr", "view", arg, "--json", "state,merged"],
+        ) {
+            Ok(val) => val,
+            Err(e) => return GateResult::Error(e),
+        };
+        let state = val["state"].as_str().unwrap_or("");
+        let merged = val["merged"].as_bool().unwrap_or(false);
+        if state == "MERGED" || merged {
+            GateResult::Resolved(format!("GitHub PR {} merged", arg))
+        } else if state == "CLOSED" {
+            GateResult::Error(format!("PR {} was closed without merging", arg))
+        } else {
+            GateResult::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gates::GateRegistry;
+    use crate::gates::tests::context;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    /// Serves one canned HTTP response per entry on a local port.
+    fn serve(responses: Vec<(u16, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {status} X\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+            }
+        });
+        url
+    }
+
+    /// An executable script standing in for `gh`.
+    fn fake_gh(dir: &std::path::Path, output: &str) -> String {
+        let path = dir.join("gh");
+        std::fs::write(&path, format!("#!/bin/sh\necho '{output}'\n")).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        path.display().to_string()
+    }
+
+    #[test]
+    fn http_checks_status_and_json() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+        let url = serve(vec![
+            (200, r#"{"build":{"state":"done"}}"#),
+            (503, ""),
+            (202, ""),
+            (200, r#"{"build":{"state":"running"}}"#),
+        ]);
+        let http = Http::default();
+        assert!(matches!(
+            http.evaluate(&format!("{url} .build.state=done"), &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(http.evaluate(&url, &ctx), GateResult::Pending);
+        assert!(matches!(
+            http.evaluate(&format!("{url} status=202"), &ctx),
+            GateResult::Resolved(_)
+        ));
+        assert_eq!(
+            http.evaluate(&format!("{url} .build.state=done"), &ctx),
+            GateResult::Pending
+        );
+        assert!(matches!(
+            http.evaluate(&format!("{url} state"), &ctx),
+            GateResult::Error(_)
+        ));
+    }
+
+    #[test]
+    fn json_paths_index_arrays() {
+        let json = serde_json::json!({"items": [{"n": 1}, {"n": true}]});
+        assert_eq!(json_at(&json, ".items.1.n").as_deref(), Some("true"));
+        assert_eq!(json_at(&json, ".items.2"), None);
+    }
+
+    #[test]
+    fn gh_gates_read_gh_output() {
+        let registry = GateRegistry::builtin();
+        let ctx = context(&registry, None);
+        let dir = tempfile::tempdir().unwrap();
+
+        let run = GhRun {
+            program: fake_gh(
+                dir.path(),
+                r#"{"status":"completed","conclusion":"success"}"#,
+            ),
+        };
+        assert!(matches!(run.evaluate("42", &ctx), GateResult::Resolved(_)));
+        let pr = GhPr {
+            program: fake_gh(dir.path(), r#"{"state":"OPEN","merged":false}"#),
+        };
+        assert_eq!(pr.evaluate("7", &ctx), GateResult::Pending);
+        let pr = GhPr {
+            program: fake_gh(dir.path(), r#"{"state":"CLOSED","merged":false}"#),
+        };
+        assert!(matches!(pr.evaluate("7", &ctx), GateResult::Error(_)));
+    }
+}
@@ -0,0 +1,88 @@
+//! Cross-process locks on `.beads` state.
+//!
+//! Every file that several `bd` processes may write is guarded by an
+//! advisory lock on a sibling `<file>.lock`:
+//!
+//! - `beads.db.lock` -- held shared by every process using the database and
+//!   exclusively while the schema is created or migrated.
+//! - `issues.jsonl.lock` (or the lock of any other JSONL path) -- held
+//!   exclusively while the JSONL is exported or synced, shared while it is
+//!   imported.
+//! - `worktree.lock` -- held exclusively while worktree redirects change.
+//!
+//! Waits are bounded by `BD_LOCK_TIMEOUT` (a duration such as `10s` or `2m`,
+//! default 30s); on timeout the error names the process holding the lock.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use beads_lockfile::{Lock, LockMode, LockOptions};
+use beads_timeparsing::{Unit, parse_duration_in};
+
+/// Lock file for worktree redirects, relative to the `.beads` directory.
+pub const WORKTREE_LOCK: &str = "worktree.lock";
+
+/// Default wait for a conflicting holder.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Returns the lock file guarding `path`: the same path with `.lock`
+/// appended.
+pub fn lock_path_for(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+/// Locks the file guarding `path` in `mode`.
+pub fn lock_file(path: &Path, mode: LockMode) -> Result<Lock> {
+    acquire(&lock_path_for(path), mode)
+}
+
+/// Acquires the lock file `lock_path` itself in `mode`.
+pub fn acquire(lock_path: &Path, mode: LockMode) -> Result<Lock> {
+    let options = LockOptions::default()
+        .timeout(Some(timeout()?))
+        .purpose(purpose());
+    Ok(Lock::acquire(lock_path, mode, &options)?)
+}
+
+/// The configured lock timeout.
+fn timeout() -> Result<Duration> {
+    match std::env::var("BD_LOCK_TIMEOUT") {
+        Ok(value) if !value.trim().is_empty() => {
+            let d = parse_duration_in(&value, Unit::Second).context("BD_LOCK_TIMEOUT")?;
+            Ok(d.to_std().unwrap_or(Duration::ZERO))
+        }
+        _ => Ok(DEFAULT_TIMEOUT),
+    }
+}
+
+/// Describes this invocation for other processes waiting on its locks.
+fn purpose() -> String {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut purpose = format!("bd {}", args.join(" "));
+    if purpose.len() > 80 {
+        let cut = (0..=77)
+            .rev()
+            .find(|&i| purpose.is_char_boundary(i))
+            .unwrap_or(0);
+        purpose.truncate(cut);
+        purpose.push_str("...");
+    }
+    purpose.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_path_is_a_sibling() {
+        assert_eq!(
+            lock_path_for(Path::new("/repo/.beads/issues.jsonl")),
+            PathBuf::from("/repo/.beads/issues.jsonl.lock")
+        );
+    }
+}
@@ -6,13 +6,16 @@
 mod cli;
 mod commands;
 mod context;
+mod gates;
+mod locks;
+mod merge;
 mod output;
 
 use std::sync::atomic::{AtomicBool, Ordering};
 
 use clap::Parser;
 
-use cli::{Cli, Commands};
+use cli::{AgentArgs, AgentCommands, Cli, Commands, GateArgs, GateCommands};
 use context::RuntimeContext;
 
 /// Tracks whether a Ctrl+C has already been received.
@@ -44,6 +47,33 @@ fn main() {
             .init();
     }
 
+    // Commands that manage the JSONL themselves (or never touch the
+    // database) skip the automatic sync.export_on/import_on triggers, as do
+    // agent heartbeats, which are too frequent to rewrite the JSONL for, and
+    // the gate watcher, which syncs on each pass.
+    let auto_sync = !matches!(
+        cli.command,
+        None | Some(
+            Commands::Version
+                | Commands::Init(_)
+                | Commands::Import(_)
+                | Commands::Export(_)
+                | Commands::Sync(_)
+                | Commands::MergeDriver(_)
+                | Commands::Migrate(_)
+                | Commands::Completion(_)
+                | Commands::Agent(AgentArgs {
+                    command: AgentCommands::Heartbeat(_)
+                })
+                | Commands::Gate(GateArgs {
+                    command: GateCommands::Watch(_)
+                })
+        )
+    );
+    if auto_sync {
+        commands::sync_cmd::auto_import(&ctx);
+    }
+
     // Dispatch to command handler
     let result = match cli.command {
         Some(Commands::Version) => commands::version::run(&ctx),
@@ -60,7 +90,7 @@ fn main() {
         Some(Commands::Comment(args)) => commands::comment::run_add(&ctx, &args),
         Some(Commands::Comments(args)) => commands::comment::run_list(&ctx, &args),
         Some(Commands::Update(args)) => commands::update::run(&ctx, &args),
-        Some(Commands::Sync) => commands::sync_cmd::run(&ctx),
+        Some(Commands::Sync(args)) => commands::sync_cmd::run(&ctx, &args),
         // Phase 2: Dependencies & Structure
         Some(Commands::Children(args)) => commands::children_cmd::run(&ctx, &args),
         Some(Commands::Relate(args)) => commands::relate::run_relate(&ctx, &args),
@@ -101,6 +131,7 @@ fn main() {
         // Phase 5: Sync, Import/Export & Integrations
         Some(Commands::Import(args)) => commands::import::run(&ctx, &args),
         Some(Commands::Export(args)) => commands::export::run(&ctx, &args),
+        Some(Commands::MergeDriver(args)) => commands::merge_driver::run(&ctx, &args),
         Some(Commands::Jira(args)) => commands::jira::run(&ctx, &args),
         Some(Commands::Linear(args)) => commands::linear::run(&ctx, &args),
         Some(Commands::Github(args)) => commands::github::run(&ctx, &args),
@@ -109,10 +140,10 @@ fn main() {
         // Phase 6: Database & Maintenance
         Some(Commands::Doctor(args)) => commands::doctor::run(&ctx, &args),
         Some(Commands::Dolt(args)) => commands::dolt::run(&ctx, &args),
-        Some(Commands::Cleanup) => commands::cleanup::run(&ctx),
+        Some(Commands::Cleanup(args)) => commands::cleanup::run(&ctx, &args),
         Some(Commands::Compact) => commands::compact::run(&ctx),
         Some(Commands::Reset) => commands::reset::run(&ctx),
-        Some(Commands::Migrate) => commands::migrate::run(&ctx),
+        Some(Commands::Migrate(args)) => commands::migrate::run(&ctx, &args),
         Some(Commands::Admin(args)) => commands::admin::run(&ctx, &args),
         Some(Commands::DetectPollution) => commands::detect_pollution::run(&ctx),
         Some(Commands::Lint(args)) => commands::lint::run(&ctx, &args),
@@ -160,10 +191,14 @@ fn main() {
         }
     };
 
+    if auto_sync && result.is_ok() {
+        commands::sync_cmd::auto_export(&ctx);
+    }
+
     // Handle errors: print message and exit with code 1
     if let Err(e) = result {
         // For JSON mode, output error as JSON
-        if cli.global.json {
+        if ctx.json {
             let err_json = serde_json::json!({
                 "error": format!("{:#}", e),
             });
@@ -0,0 +1,477 @@
+//! Three-way merge of JSONL issue snapshots.
+//!
+//! Used by `bd sync` and `bd merge-driver` to combine the common ancestor
+//! (base), local (ours) and remote (theirs) versions of `issues.jsonl`.
+//! Records are matched by ID and merged field by field: a field changed on
+//! one side only takes that side's value, while a field changed on both sides
+//! is resolved with its [`FieldStrategy`] if one applies, otherwise with the
+//! global [`ConflictStrategy`].
+//!
+//! Built-in field strategies (overridable via `conflict.fields` in
+//! `config.yaml`): `compaction_level` takes the maximum; `labels`, `waiters`,
+//! `dependencies` and `comments` are merged as sets.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufReader;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use beads_config::config::{ConflictConfig, ConflictStrategy, FieldStrategy};
+use beads_core::content_hash::compute_content_hash;
+use beads_core::issue::Issue;
+use beads_core::jsonl::{canonicalize, read_jsonl};
+
+/// Keys that never go through field-wise resolution.
+const SKIPPED_KEYS: &[&str] = &["id", "updated_at"];
+
+/// What kind of disagreement a conflict records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictKind {
+    /// Both sides changed the same field to different values.
+    Field,
+    /// One side deleted the issue while the other modified it.
+    ModifyDelete,
+}
+
+/// A conflict that the configured strategies could not resolve.
+///
+/// The merged output keeps the local value (or the surviving record for
+/// modify/delete conflicts) so it is still usable as-is.
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeConflict {
+    pub id: String,
+    pub kind: ConflictKind,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub field: String,
+    pub base: Option<Value>,
+    pub ours: Option<Value>,
+    pub theirs: Option<Value>,
+}
+
+/// Result of a three-way merge.
+#[derive(Debug, Default)]
+pub struct MergeResult {
+    /// Merged issues, canonicalized and sorted by ID.
+    pub issues: Vec<Issue>,
+    /// Unresolved conflicts (only produced by `manual` strategies).
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// Parses JSONL bytes into issues. Empty input yields no issues.
+pub fn parse_jsonl(bytes: &[u8]) -> Result<Vec<Issue>> {
+    read_jsonl(BufReader::new(bytes))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .context("failed to parse JSONL")
+}
+
+/// Merges three snapshots of the issue set.
+pub fn merge_issues(
+    base: &[Issue],
+    ours: &[Issue],
+    theirs: &[Issue],
+    config: &ConflictConfig,
+) -> Result<MergeResult> {
+    let base = index(base);
+    let ours = index(ours);
+    let theirs = index(theirs);
+
+    let ids: BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+
+    let mut result = MergeResult::default();
+    for id in ids {
+        let (b, o, t) = (base.get(id), ours.get(id), theirs.get(id));
+        let merged = match (b, o, t) {
+            (_, Some(o), Some(t)) if untouched(o, t) => Some(o.clone()),
+            (Some(b), Some(o), Some(t)) if untouched(b, o) => Some(t.clone()),
+            (Some(b), Some(o), Some(t)) if untouched(b, t) => Some(o.clone()),
+            (b, Some(o), Some(t)) => Some(merge_record(b, o, t, config, &mut result.conflicts)?),
+            (None, Some(x), None) | (None, None, Some(x)) => Some(x.clone()),
+            (Some(b), Some(o), None) => {
+                if untouched(b, o) {
+                    None
+                } else {
+                    modify_delete(b, o, true, config, &mut result.conflicts)?
+                }
+            }
+            (Some(b), None, Some(t)) => {
+                if untouched(b, t) {
+                    None
+                } else {
+                    modify_delete(b, t, false, config, &mut result.conflicts)?
+                }
+            }
+            (_, None, None) => None,
+        };
+        if let Some(issue) = merged {
+            result.issues.push(issue);
+        }
+    }
+    Ok(result)
+}
+
+/// Canonicalizes a snapshot and indexes it by ID.
+fn index(issues: &[Issue]) -> BTreeMap<String, Issue> {
+    issues
+        .iter()
+        .map(|issue| {
+            let mut issue = issue.clone();
+            canonicalize(&mut issue);
+            (issue.id.clone(), issue)
+        })
+        .collect()
+}
+
+/// Returns true if `a` and `b` carry the same data.
+///
+/// The content hash is a cheap first check; records that hash equal are
+/// compared in full since the hash skips timestamps, compaction state and
+/// relations.
+fn untouched(a: &Issue, b: &Issue) -> bool {
+    compute_content_hash(a) == compute_content_hash(b)
+        && serde_json::to_value(a).ok() == serde_json::to_value(b).ok()
+}
+
+/// Resolves a record deleted on one side and modified on the other.
+fn modify_delete(
+    base: &Issue,
+    modified: &Issue,
+    modified_is_ours: bool,
+    config: &ConflictConfig,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Result<Option<Issue>> {
+    let keep = match config.strategy {
+        ConflictStrategy::Ours => modified_is_ours,
+        ConflictStrategy::Theirs => !modified_is_ours,
+        // A modification is always newer than the version that was deleted.
+        ConflictStrategy::Newest => true,
+        ConflictStrategy::Manual => {
+            let modified_value = Some(serde_json::to_value(modified)?);
+            let (ours, theirs) = if modified_is_ours {
+                (modified_value, None)
+            } else {
+                (None, modified_value)
+            };
+            conflicts.push(MergeConflict {
+                id: base.id.clone(),
+                kind: ConflictKind::ModifyDelete,
+                field: String::new(),
+                base: Some(serde_json::to_value(base)?),
+                ours,
+                theirs,
+            });
+            true
+        }
+    };
+    Ok(keep.then(|| modified.clone()))
+}
+
+/// Merges a record present on both sides, field by field.
+fn merge_record(
+    base: Option<&Issue>,
+    ours: &Issue,
+    theirs: &Issue,
+    config: &ConflictConfig,
+    conflicts: &mut Vec<MergeConflict>,
+) -> Result<Issue> {
+    let bv = match base {
+        Some(b) => to_map(b)?,
+        None => Map::new(),
+    };
+    let ov = to_map(ours)?;
+    let tv = to_map(theirs)?;
+    let theirs_newer = theirs.updated_at > ours.updated_at;
+
+    let keys: BTreeSet<&String> = bv.keys().chain(ov.keys()).chain(tv.keys()).collect();
+    let mut merged = Map::new();
+    for key in keys {
+        if SKIPPED_KEYS.contains(&key.as_str()) {
+            continue;
+        }
+        let (b, o, t) = (bv.get(key), ov.get(key), tv.get(key));
+        let value = if o == t || t == b {
+            o.cloned()
+        } else if o == b {
+            t.cloned()
+        } else {
+            match resolve_field(key, b, o, t, theirs_newer, config) {
+                Some(value) => value,
+                None => {
+                    conflicts.push(MergeConflict {
+                        id: ours.id.clone(),
+                        kind: ConflictKind::Field,
+                        field: key.clone(),
+                        base: b.cloned(),
+                        ours: o.cloned(),
+                        theirs: t.cloned(),
+                    });
+                    o.cloned()
+                }
+            }
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    merged.insert("id".into(), Value::String(ours.id.clone()));
+
+    let mut issue: Issue = serde_json::from_value(Value::Object(merged))
+        .with_context(|| format!("failed to rebuild merged issue {}", ours.id))?;
+    issue.updated_at = ours.updated_at.max(theirs.updated_at);
+
+    // Set-merged dependencies may carry the same edge twice with different
+    // creation metadata; keep one per (target, type).
+    let mut seen = BTreeSet::new();
+    issue
+        .dependencies
+        .retain(|d| seen.insert((d.depends_on_id.clone(), d.dep_type.as_str().to_string())));
+    canonicalize(&mut issue);
+    Ok(issue)
+}
+
+/// Resolves a field changed on both sides. Returns `None` for a manual
+/// conflict, `Some(None)` if the resolved value is "absent".
+fn resolve_field(
+    key: &str,
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    theirs_newer: bool,
+    config: &ConflictConfig,
+) -> Option<Option<Value>> {
+    let newest = || {
+        if theirs_newer {
+            theirs.cloned()
+        } else {
+            ours.cloned()
+        }
+    };
+
+    match field_strategy(config, key) {
+        Some(FieldStrategy::Max) => {
+            if let Some(value) = max_value(ours, theirs) {
+                return Some(Some(value));
+            }
+        }
+        Some(FieldStrategy::Union) => {
+            if let Some(value) = set_merge(base, ours, theirs) {
+                return Some(Some(value));
+            }
+        }
+        Some(FieldStrategy::Newest) => return Some(newest()),
+        Some(FieldStrategy::Manual) => return None,
+        None => {}
+    }
+
+    match config.strategy {
+        ConflictStrategy::Newest => Some(newest()),
+        ConflictStrategy::Ours => Some(ours.cloned()),
+        ConflictStrategy::Theirs => Some(theirs.cloned()),
+        ConflictStrategy::Manual => None,
+    }
+}
+
+/// The strategy for `key`: the configured override, else the built-in one.
+fn field_strategy(config: &ConflictConfig, key: &str) -> Option<FieldStrategy> {
+    if let Some(strategy) = config.fields.get(key) {
+        return Some(strategy.clone());
+    }
+    match key {
+        "compaction_level" => Some(FieldStrategy::Max),
+        "labels" | "waiters" | "dependencies" | "comments" => Some(FieldStrategy::Union),
+        _ => None,
+    }
+}
+
+/// The larger of two numeric values; `None` if either side is not a number.
+fn max_value(ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    let o = ours.map_or(Some(0.0), Value::as_f64)?;
+    let t = theirs.map_or(Some(0.0), Value::as_f64)?;
+    if t > o {
+        theirs.cloned()
+    } else {
+        ours.cloned()
+    }
+}
+
+/// Three-way set merge of two arrays: elements added on either side are kept,
+/// elements removed on either side are dropped. `None` if a side is not an
+/// array.
+fn set_merge(base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>) -> Option<Value> {
+    fn items(v: Option<&Value>) -> Option<Vec<Value>> {
+        match v {
+            None | Some(Value::Null) => Some(Vec::new()),
+            Some(Value::Array(items)) => Some(items.clone()),
+            Some(_) => None,
+        }
+    }
+    let base = items(base)?;
+    let ours = items(ours)?;
+    let theirs = items(theirs)?;
+
+    let removed = |v: &Value| base.contains(v) && (!ours.contains(v) || !theirs.contains(v));
+    let mut merged: Vec<Value> = Vec::new();
+    for v in ours.iter().chain(theirs.iter()) {
+        if !removed(v) && !merged.contains(v) {
+            merged.push(v.clone());
+        }
+    }
+    Some(Value::Array(merged))
+}
+
+fn to_map(issue: &Issue) -> Result<Map<String, Value>> {
+    match serde_json::to_value(issue)? {
+        Value::Object(map) => Ok(map),
+        _ => unreachable!("issues serialize to JSON objects"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beads_core::issue::IssueBuilder;
+    use chrono::{Duration, SubsecRound, Utc};
+
+    fn issue(id: &str, title: &str) -> Issue {
+        let mut issue = IssueBuilder::new(title).id(id).build();
+        let t = Utc::now() - Duration::hours(1);
+        issue.created_at = t;
+        issue.updated_at = t;
+        issue
+    }
+
+    fn touched(mut issue: Issue, minutes: i64) -> Issue {
+        issue.updated_at += Duration::minutes(minutes);
+        issue
+    }
+
+    fn merge(base: &[Issue], ours: &[Issue], theirs: &[Issue]) -> MergeResult {
+        merge_issues(base, ours, theirs, &ConflictConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn disjoint_field_edits_combine() {
+        let base = issue("bd-1", "Title");
+        let mut ours = touched(base.clone(), 1);
+        ours.priority = 0;
+        let mut theirs = touched(base.clone(), 2);
+        theirs.assignee = "bob".into();
+
+        let result = merge(&[base], &[ours], &[theirs.clone()]);
+        assert!(result.conflicts.is_empty());
+        let merged = &result.issues[0];
+        assert_eq!(merged.priority, 0);
+        assert_eq!(merged.assignee, "bob");
+        assert_eq!(merged.updated_at, theirs.updated_at.trunc_subsecs(3));
+    }
+
+    #[test]
+    fn same_field_conflict_honors_strategy() {
+        let base = issue("bd-1", "Title");
+        let mut ours = touched(base.clone(), 2);
+        ours.title = "Ours".into();
+        let mut theirs = touched(base.clone(), 1);
+        theirs.title = "Theirs".into();
+        let sides = ([base], [ours], [theirs]);
+
+        let newest = merge(&sides.0, &sides.1, &sides.2);
+        assert_eq!(newest.issues[0].title, "Ours");
+
+        let mut config = ConflictConfig {
+            strategy: ConflictStrategy::Theirs,
+            ..Default::default()
+        };
+        let theirs_wins = merge_issues(&sides.0, &sides.1, &sides.2, &config).unwrap();
+        assert_eq!(theirs_wins.issues[0].title, "Theirs");
+
+        config.strategy = ConflictStrategy::Manual;
+        let manual = merge_issues(&sides.0, &sides.1, &sides.2, &config).unwrap();
+        assert_eq!(manual.issues[0].title, "Ours");
+        assert_eq!(manual.conflicts.len(), 1);
+        assert_eq!(manual.conflicts[0].field, "title");
+        assert_eq!(manual.conflicts[0].kind, ConflictKind::Field);
+    }
+
+    #[test]
+    fn labels_union_and_compaction_max() {
+        let mut base = issue("bd-1", "Title");
+        base.labels = vec!["keep".into(), "drop".into()];
+        let mut ours = touched(base.clone(), 1);
+        ours.labels = vec!["keep".into(), "mine".into()];
+        ours.compaction_level = 2;
+        let mut theirs = touched(base.clone(), 2);
+        theirs.labels = vec!["drop".into(), "keep".into(), "yours".into()];
+        theirs.compaction_level = 1;
+
+        let config = ConflictConfig {
+            strategy: ConflictStrategy::Manual,
+            ..Default::default()
+        };
+        let result = merge_issues(&[base], &[ours], &[theirs], &config).unwrap();
+        assert!(result.conflicts.is_empty(), "{:?}", result.conflicts);
+        assert_eq!(result.issues[0].labels, vec!["keep", "mine", "yours"]);
+        assert_eq!(result.issues[0].compaction_level, 2);
+    }
+
+    #[test]
+    fn field_override_beats_global_strategy() {
+        let base = issue("bd-1", "Title");
+        let mut ours = touched(base.clone(), 2);
+        ours.priority = 0;
+        let mut theirs = touched(base.clone(), 1);
+        theirs.priority = 4;
+
+        let mut config = ConflictConfig::default();
+        config.fields.insert("priority".into(), FieldStrategy::Max);
+        let result = merge_issues(&[base], &[ours], &[theirs], &config).unwrap();
+        assert_eq!(result.issues[0].priority, 4);
+    }
+
+    #[test]
+    fn additions_and_deletions() {
+        let kept = issue("bd-1", "Kept");
+        let gone = issue("bd-2", "Gone");
+        let added_ours = issue("bd-3", "Ours");
+        let added_theirs = issue("bd-4", "Theirs");
+
+        let result = merge(
+            &[kept.clone(), gone.clone()],
+            &[kept.clone(), gone, added_ours],
+            &[kept, added_theirs],
+        );
+        let ids: Vec<_> = result.issues.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["bd-1", "bd-3", "bd-4"]);
+    }
+
+    #[test]
+    fn modify_delete_keeps_modification_unless_manual() {
+        let base = issue("bd-1", "Title");
+        let mut ours = touched(base.clone(), 1);
+        ours.title = "Edited".into();
+
+        let (base, ours) = ([base], [ours]);
+        let result = merge(&base, &ours, &[]);
+        assert_eq!(result.issues[0].title, "Edited");
+
+        let config = ConflictConfig {
+            strategy: ConflictStrategy::Theirs,
+            ..Default::default()
+        };
+        let result = merge_issues(&base, &ours, &[], &config).unwrap();
+        assert!(result.issues.is_empty());
+
+        let config = ConflictConfig {
+            strategy: ConflictStrategy::Manual,
+            ..Default::default()
+        };
+        let result = merge_issues(&base, &ours, &[], &config).unwrap();
+        assert_eq!(result.conflicts[0].kind, ConflictKind::ModifyDelete);
+        assert!(result.conflicts[0].theirs.is_none());
+    }
+}
@@ -5,6 +5,8 @@
 
 use beads_core::enums::Status;
 use beads_core::issue::Issue;
+use beads_storage::{Storage, StorageError};
+use chrono::{DateTime, Local, Utc};
 use serde::Serialize;
 use std::io::{self, Write};
 
@@ -79,28 +81,26 @@ impl BeadView {
     }
 }
 
-/// Load labels for an issue from the database.
+/// Load labels for an issue from the store.
 ///
-/// Returns an empty `Vec` if the query fails.
-pub fn load_labels(conn: &rusqlite::Connection, issue_id: &str) -> Vec<String> {
-    conn.prepare("SELECT label FROM labels WHERE issue_id = ?1 ORDER BY label")
-        .and_then(|mut stmt| {
-            stmt.query_map(rusqlite::params![issue_id], |row| row.get(0))
-                .map(|rows| rows.filter_map(|r| r.ok()).collect())
-        })
-        .unwrap_or_default()
+/// Returns an empty `Vec` if the lookup fails.
+pub fn load_labels(store: &dyn Storage, issue_id: &str) -> Vec<String> {
+    store.get_labels(issue_id).unwrap_or_default()
 }
 
-/// Populate the `labels` field on an issue by loading from the database.
-#[allow(dead_code)]
-pub fn populate_labels(conn: &rusqlite::Connection, issue: &mut Issue) {
-    issue.labels = load_labels(conn, &issue.id);
+/// Load an issue with its labels populated.
+pub fn load_issue(store: &dyn Storage, issue_id: &str) -> Result<Issue, StorageError> {
+    let mut issue = store.get_issue(issue_id)?;
+    issue.labels = load_labels(store, issue_id);
+    Ok(issue)
 }
 
-/// Populate labels on a slice of issues from the database.
-pub fn populate_labels_bulk(conn: &rusqlite::Connection, issues: &mut [Issue]) {
+/// Populate labels on a slice of issues from the store.
+pub fn populate_labels_bulk(store: &dyn Storage, issues: &mut [Issue]) {
+    let ids: Vec<String> = issues.iter().map(|i| i.id.clone()).collect();
+    let mut labels = store.get_labels_for_issues(&ids).unwrap_or_default();
     for issue in issues.iter_mut() {
-        issue.labels = load_labels(conn, &issue.id);
+        issue.labels = labels.remove(&issue.id).unwrap_or_default();
     }
 }
 
@@ -247,14 +247,11 @@ pub fn format_issue_detail(issue: &Issue) -> String {
     }
 
     // Time-based scheduling
-    if let Some(ref due_at) = issue.due_at {
-        lines.push(format!("Due: {}", due_at.format("%Y-%m-%d %H:%M")));
+    if let Some(due_at) = issue.due_at {
+        lines.push(format!("Due: {}", format_time(due_at)));
     }
-    if let Some(ref defer_until) = issue.defer_until {
-        lines.push(format!(
-            "Deferred until: {}",
-            defer_until.format("%Y-%m-%d %H:%M")
-        ));
+    if let Some(defer_until) = issue.defer_until {
+        lines.push(format!("Deferred until: {}", format_time(defer_until)));
     }
 
     // Content sections
@@ -293,6 +290,16 @@ pub fn format_issue_detail(issue: &Issue) -> String {
     lines.join("\n")
 }
 
+/// Formats a scheduling time in local time with its distance from now,
+/// e.g. `2026-06-01 09:00 (in 3d4h)`.
+pub fn format_time(t: DateTime<Utc>) -> String {
+    format!(
+        "{} ({})",
+        t.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        beads_timeparsing::format_relative(t, Utc::now())
+    )
+}
+
 /// Format an issue as a compact row for list output.
 ///
 /// Returns a vector of column values suitable for [`output_table`].
@@ -13,7 +13,7 @@ use tempfile::TempDir;
 
 /// Build a `Command` targeting the cargo-built `bd` binary.
 fn bd() -> Command {
-    Command::cargo_bin("bd").unwrap()
+    assert_cmd::cargo::cargo_bin_cmd!("bd")
 }
 
 /// Initialize a fresh beads project in a temp directory and return the handle.
@@ -78,7 +78,7 @@ fn flow1_full_lifecycle() {
     // so we check that key fields are present and non-null on the bug issue (P0, type=bug).
     let bug_issue = arr
         .iter()
-        .find(|i| i["title"].as_str().map_or(false, |t| t.contains("login")))
+        .find(|i| i["title"].as_str().is_some_and(|t| t.contains("login")))
         .expect("should find the login bug issue");
     assert!(bug_issue["id"].is_string());
     assert!(bug_issue["title"].is_string());
@@ -292,6 +292,99 @@ fn flow3_search_and_filter() {
     assert_eq!(ready.as_array().unwrap().len(), 1, "only 1 P0 issue");
 }
 
+#[test]
+fn search_rank_and_snippet() {
+    let tmp = init_project();
+    let body = create_issue(
+        &tmp,
+        "Session cleanup",
+        &["--description", "Expired tokens break the login page"],
+    );
+    let title = create_issue(&tmp, "Login redirect loop", &[]);
+    create_issue(&tmp, "Unrelated work", &[]);
+
+    let output = bd()
+        .args(["search", "login", "--rank", "--snippet", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let hits: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let ids: Vec<&str> = hits.iter().map(|h| h["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, [title.as_str(), body.as_str()]);
+    assert!(hits[0]["score"].as_f64().unwrap() > hits[1]["score"].as_f64().unwrap());
+    assert!(hits[1]["snippet"].as_str().unwrap().contains("**login**"));
+
+    bd().args(["search", "title:login", "--snippet"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("**Login** redirect loop"))
+        .stdout(predicate::str::contains(&body).not());
+}
+
+#[test]
+fn query_flag_filters_listings() {
+    let tmp = init_project();
+    let backend = create_issue(&tmp, "Backend cache", &["-p", "2", "-l", "backend"]);
+    let urgent = create_issue(&tmp, "Urgent login fix", &["-p", "0", "-a", "alice"]);
+    let other = create_issue(&tmp, "Docs pass", &["-p", "3"]);
+
+    let ids = |args: &[&str]| -> Vec<String> {
+        let output = bd()
+            .args(args)
+            .arg("--json")
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        let issues: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        let mut ids: Vec<String> = issues
+            .iter()
+            .map(|i| i["id"].as_str().unwrap().to_string())
+            .collect();
+        ids.sort();
+        ids
+    };
+    let mut expected = vec![backend.clone(), urgent.clone()];
+    expected.sort();
+
+    let q = "status:open AND (label:backend OR priority<=1)";
+    assert_eq!(ids(&["list", "--query", q]), expected);
+    assert_eq!(ids(&["ready", "--query", q]), expected);
+    assert_eq!(
+        ids(&["list", "--query", "NOT assignee:none"]),
+        [urgent.as_str()]
+    );
+    assert_eq!(
+        ids(&["search", "login", "--query", "p:0"]),
+        [urgent.as_str()]
+    );
+    assert_eq!(
+        ids(&["search", "login", "--query", "p:1"]),
+        Vec::<String>::new()
+    );
+
+    bd().args(["count", "--query", "updated>1h NOT label:backend"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout("2\n");
+
+    bd().args(["close", &other])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    // A status condition replaces the default exclusion of closed issues.
+    assert_eq!(ids(&["list", "--query", "status:closed"]), [other]);
+
+    bd().args(["list", "--query", "status:open AND priorty<=1"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("^^^^^^^ unknown field 'priorty'"));
+}
+
 // ---------------------------------------------------------------------------
 // Flow 4: Labels
 // ---------------------------------------------------------------------------
@@ -350,56 +443,1893 @@ fn flow5_comments_and_history() {
         .assert()
         .success();
 
-    bd().args(["comment", &id, "Second comment"])
+    bd().args(["comment", &id, "Second comment"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    // List comments => both present
+    let output = bd()
+        .args(["comments", &id, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let comments: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let arr = comments.as_array().unwrap();
+    assert_eq!(arr.len(), 2, "should have 2 comments");
+    assert_eq!(arr[0]["text"].as_str().unwrap(), "First comment");
+    assert_eq!(arr[1]["text"].as_str().unwrap(), "Second comment");
+
+    // History => should have events (created, commented)
+    let output = bd()
+        .args(["history", &id, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let history: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let events = history["events"].as_array().unwrap();
+    assert!(
+        events.len() >= 3,
+        "should have at least 3 events (created + 2 commented)"
+    );
+
+    let event_types: Vec<&str> = events
+        .iter()
+        .map(|e| e["event_type"].as_str().unwrap())
+        .collect();
+    assert!(
+        event_types.contains(&"created"),
+        "should have 'created' event"
+    );
+    assert!(
+        event_types.contains(&"commented"),
+        "should have 'commented' event"
+    );
+}
+
+#[test]
+fn defer_and_undefer_record_history() {
+    let tmp = init_project();
+    let id = create_issue(&tmp, "Deferred work", &[]);
+
+    bd().args(["defer", &id, "--until", "2030-01-01"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["undefer", &id])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    let output = bd()
+        .args(["history", &id, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let history: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let transitions: Vec<(&str, &str)> = history["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|e| e["event_type"] == "status_changed")
+        .map(|e| {
+            (
+                e["old_value"].as_str().unwrap(),
+                e["new_value"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(transitions, [("open", "deferred"), ("deferred", "open")]);
+}
+
+#[test]
+fn natural_time_flags() {
+    let tmp = init_project();
+    let id = create_issue(
+        &tmp,
+        "Scheduled work",
+        &["--due", "tomorrow 5pm", "--defer", "+2d"],
+    );
+
+    let show = |id: &str| -> serde_json::Value {
+        let output = bd()
+            .args(["show", id, "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let issues: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        issues[0].clone()
+    };
+    let issue = show(&id);
+    assert!(issue["due_at"].as_str().unwrap().contains("T"));
+    assert!(issue["defer_until"].is_string());
+
+    bd().args(["update", &id, "--due", "none"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("due -> none"));
+    assert!(show(&id)["due_at"].is_null());
+
+    bd().args(["defer", &id, "--until", "next monday 9am"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["defer", &id, "--until", "someday"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--until: invalid time 'someday'"));
+
+    // A bare duration is that long from now; zones may be named.
+    let before = chrono::Utc::now();
+    bd().args(["update", &id, "--due", "2h"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let due: chrono::DateTime<chrono::Utc> = show(&id)["due_at"].as_str().unwrap().parse().unwrap();
+    assert!(due >= before + chrono::Duration::hours(2));
+    assert!(due <= chrono::Utc::now() + chrono::Duration::hours(2));
+    bd().args(["defer", &id, "--until", "3d"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["update", &id, "--due", "2030-06-01 9am Europe/Berlin"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    assert!(
+        show(&id)["due_at"]
+            .as_str()
+            .unwrap()
+            .starts_with("2030-06-01T07:00:00")
+    );
+
+    // Relative ranges on list.
+    bd().args(["list", "--all", "--created-after", "1 hour ago"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&id));
+    bd().args(["list", "--all", "--created-before", "-1d"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&id).not());
+
+    bd().args(["stale", "--days", "2w"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("14 days"));
+
+    // Thresholds under a day are not rounded down to zero.
+    let stale = || -> serde_json::Value {
+        let output = bd()
+            .args(["stale", "--days", "12h", "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+    assert_eq!(stale()["count"], 0);
+    let conn = rusqlite::Connection::open(tmp.path().join(".beads/beads.db")).unwrap();
+    let backdated = (chrono::Utc::now() - chrono::Duration::hours(13))
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string();
+    conn.execute(
+        "UPDATE issues SET updated_at = ?1 WHERE id = ?2",
+        rusqlite::params![backdated, id],
+    )
+    .unwrap();
+    assert_eq!(stale()["issues"][0]["id"], id.as_str());
+    bd().args(["stale", "--days", "12h"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("not updated in 12h"));
+
+    bd().args(["gate", "create", "Wait", "--timeout", "1h30m", "--json"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("5400000000000"));
+}
+
+// ---------------------------------------------------------------------------
+// Flow 6: Stats and views
+// ---------------------------------------------------------------------------
+
+#[test]
+fn locks_report_their_holder() {
+    use beads_lockfile::{Lock, LockMode};
+
+    let tmp = init_project();
+    create_issue(&tmp, "Locked out", &[]);
+    let jsonl_lock = tmp.path().join(".beads/issues.jsonl.lock");
+
+    let held = Lock::try_acquire(&jsonl_lock, LockMode::Exclusive, "test sync").unwrap();
+    bd().args(["export"])
+        .env("BD_LOCK_TIMEOUT", "0")
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("waiting for an exclusive lock"))
+        .stderr(predicate::str::contains(format!(
+            "held by pid {}",
+            std::process::id()
+        )))
+        .stderr(predicate::str::contains("test sync"));
+    bd().args(["doctor"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("issues.jsonl.lock held by"));
+    drop(held);
+
+    bd().args(["export"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    // Migrations wait for readers of the database.
+    let reader = Lock::try_acquire(
+        &tmp.path().join(".beads/beads.db.lock"),
+        LockMode::Shared,
+        "reader",
+    )
+    .unwrap();
+    bd().args(["list"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["migrate"])
+        .env("BD_LOCK_TIMEOUT", "0")
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("beads.db.lock"));
+    drop(reader);
+    bd().args(["migrate"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    // Every command holds the database lock shared; none leaves a record.
+    for _ in 0..3 {
+        bd().args(["list"])
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    }
+    assert!(beads_lockfile::read_holders(&tmp.path().join(".beads/beads.db.lock")).is_empty());
+}
+
+#[test]
+fn formula_crud_and_discovery() {
+    let tmp = init_project();
+    let home = TempDir::new().unwrap();
+    let bd_home = || {
+        let mut cmd = bd();
+        cmd.env("HOME", home.path()).current_dir(tmp.path());
+        cmd
+    };
+
+    bd_home()
+        .args(["formula", "create", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Created formula feature with 3 steps",
+        ));
+    bd_home()
+        .args(["formula", "create", "feature"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    // An epic with two ordered children becomes a formula.
+    let epic = create_issue(&tmp, "Launch", &["--type", "epic"]);
+    let first = create_issue(&tmp, "Write docs", &[]);
+    let second = create_issue(&tmp, "Announce", &[]);
+    for child in [&first, &second] {
+        bd_home()
+            .args(["dep", "add", child, &epic, "--type", "parent-child"])
+            .assert()
+            .success();
+    }
+    bd_home()
+        .args(["dep", "add", &second, &first])
+        .assert()
+        .success();
+    bd_home()
+        .args([
+            "formula",
+            "create",
+            "launch",
+            "--from-epic",
+            &epic,
+            "--global",
+        ])
+        .assert()
+        .success();
+
+    let output = bd_home()
+        .args(["formula", "list", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<&str> = list
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["feature", "launch"]);
+    assert_eq!(list[0]["scope"], "project");
+    assert_eq!(list[0]["steps"], 3);
+    assert_eq!(list[1]["scope"], "user");
+    assert_eq!(list[1]["vars"], serde_json::json!([]));
+
+    bd_home()
+        .args(["formula", "show", "launch"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[1] write-docs: Write docs"))
+        .stdout(predicate::str::contains("[2] announce: Announce"))
+        .stdout(predicate::str::contains("needs: write-docs"));
+    bd_home()
+        .args(["formula", "show", "feature"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("when: {{reviewer}}"))
+        .stdout(predicate::str::contains("gate: human (timeout 2d)"));
+
+    bd_home()
+        .args(["formula", "delete", "feature"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    bd_home()
+        .args(["formula", "delete", "feature", "--force"])
+        .assert()
+        .success();
+    bd_home()
+        .args(["formula", "show", "feature"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn cook_fans_out_for_each_steps() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("review.formula.toml"),
+        r#"
+formula = "review"
+
+[vars.modules]
+required = true
+
+[[steps]]
+id = "review"
+title = "Review {{item}}"
+for_each = "{{modules}}"
+
+[[steps]]
+id = "merge"
+title = "Merge"
+needs = ["review"]
+"#,
+    )
+    .unwrap();
+
+    bd().args(["cook", "review", "--var", "modules=api,web"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(step review.1): Review web"));
+
+    // Only the copies are ready; merge waits for all of them.
+    let output = bd()
+        .args(["ready", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let ready: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let mut titles: Vec<&str> = ready.iter().map(|i| i["title"].as_str().unwrap()).collect();
+    titles.sort();
+    assert_eq!(titles, ["Review api", "Review web"]);
+
+    bd().args(["export"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let jsonl = std::fs::read_to_string(tmp.path().join(".beads/issues.jsonl")).unwrap();
+    let merge: serde_json::Value = jsonl
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .find(|issue| issue["title"] == "Merge")
+        .unwrap();
+    let deps = merge["dependencies"].as_array().unwrap();
+    assert_eq!(deps.len(), 2);
+    for dep in deps {
+        assert_eq!(dep["type"], "waits-for");
+        let meta: serde_json::Value =
+            serde_json::from_str(dep["metadata"].as_str().unwrap()).unwrap();
+        // The for_each step has no issue of its own, so no spawner is
+        // recorded; a step ID would not resolve to anything.
+        assert_eq!(meta, serde_json::json!({"gate": "all-children"}));
+    }
+}
+
+#[test]
+fn cook_composes_expansions_and_aspects() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("release.formula.toml"),
+        r#"
+formula = "release"
+aspects = ["signoff"]
+
+[[steps]]
+id = "test"
+expand = "checks"
+with = { target = "linux" }
+
+[[steps]]
+id = "ship"
+title = "Ship"
+type = "feature"
+needs = ["test"]
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        formulas.join("checks.formula.toml"),
+        r#"
+formula = "checks"
+type = "expansion"
+
+[[steps]]
+id = "unit"
+title = "Unit tests on {{target}}"
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        formulas.join("signoff.formula.toml"),
+        r#"
+formula = "signoff"
+type = "aspect"
+pointcut = { after = { type = "feature" } }
+
+[[steps]]
+id = "signoff"
+title = "Sign off {{target}}"
+"#,
+    )
+    .unwrap();
+
+    let output = bd()
+        .args(["cook", "release", "--dry-run", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let cooked: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let steps: Vec<(&str, &str)> = cooked["steps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| (s["id"].as_str().unwrap(), s["title"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            ("test.unit", "Unit tests on linux"),
+            ("ship", "Ship"),
+            ("ship.signoff", "Sign off ship"),
+        ]
+    );
+    assert_eq!(
+        cooked["steps"][1]["needs"],
+        serde_json::json!(["test.unit"])
+    );
+
+    // An expansion that includes its caller is reported with the chain.
+    std::fs::write(
+        formulas.join("checks.formula.toml"),
+        "formula = \"checks\"\ntype = \"expansion\"\n[[steps]]\nid = \"again\"\nexpand = \"checks\"\n",
+    )
+    .unwrap();
+    bd().args(["cook", "release", "--dry-run"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "formula include cycle: release -> checks -> checks",
+        ));
+}
+
+#[test]
+fn molecule_lifecycle() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("ship.formula.toml"),
+        r#"
+formula = "ship"
+
+[vars.service]
+required = true
+
+[[steps]]
+id = "build"
+title = "Build {{service}}"
+
+[[steps]]
+id = "deploy"
+title = "Deploy {{service}}"
+needs = ["build"]
+"#,
+    )
+    .unwrap();
+
+    let pour = |service: &str| -> (String, Vec<String>) {
+        let output = bd()
+            .args(["mol", "pour", "ship", "--var"])
+            .arg(format!("service={service}"))
+            .arg("--json")
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let out: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let steps = out["created"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap().to_string())
+            .collect();
+        (out["molecule"].as_str().unwrap().to_string(), steps)
+    };
+    let (api, api_steps) = pour("api");
+    let (web, web_steps) = pour("web");
+
+    // Bonding sequentially makes web's build wait on api's deploy.
+    bd().args(["mol", "bond", &api, &web])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("1 edges"));
+    bd().args(["mol", "bond", &api, &web])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already bonded"));
+    bd().args(["mol", "show", &api])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Bonded: {web} (sequential)"
+        )));
+    let output = bd()
+        .args(["ready", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let ready: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let titles: Vec<&str> = ready.iter().map(|i| i["title"].as_str().unwrap()).collect();
+    assert!(titles.contains(&"Build api"));
+    assert!(!titles.contains(&"Build web"));
+
+    // Distilling recovers the variable from the step titles.
+    bd().args(["mol", "distill", &api, "--name", "shipped"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("title = \"Build {{service}}\""))
+        .stdout(predicate::str::contains("default = \"api\""));
+
+    // Squash needs closed steps or --force.
+    bd().args(["mol", "squash", &api])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Use --force"));
+    for step in &api_steps {
+        bd().args(["close", step])
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    }
+    bd().args(["mol", "squash", &api])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("(closed)"));
+    bd().args(["show", &api_steps[0]])
+        .current_dir(tmp.path())
+        .assert()
+        .failure();
+    bd().args(["show", &api])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Squashed 2 steps"));
+
+    // Burning is confirmed with --force and removes root and steps.
+    bd().args(["mol", "burn", &web])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--force"));
+    bd().args(["mol", "burn", &web, "--force"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("deleted 3 issues"));
+    for id in web_steps.iter().chain([&web]) {
+        bd().args(["show", id])
+            .current_dir(tmp.path())
+            .assert()
+            .failure();
+    }
+
+    // A seeded molecule is poured later with its recorded variables.
+    let output = bd()
+        .args(["mol", "seed", "ship", "--var", "service=db", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let seed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let seed = seed["id"].as_str().unwrap();
+    bd().args(["mol", "pour", seed])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Deploy db"));
+    bd().args(["mol", "pour", seed])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already been poured"));
+}
+
+#[test]
+fn failed_pour_leaves_no_partial_molecule() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("ship.formula.toml"),
+        r#"
+formula = "ship"
+
+[[steps]]
+id = "build"
+title = "Build"
+
+[[steps]]
+id = "deploy"
+title = "Deploy"
+labels = ["boom"]
+needs = ["build"]
+"#,
+    )
+    .unwrap();
+    // Make the second step fail halfway through the pour.
+    let conn = rusqlite::Connection::open(tmp.path().join(".beads/beads.db")).unwrap();
+    conn.execute_batch(
+        "CREATE TRIGGER boom BEFORE INSERT ON labels WHEN new.label = 'boom'
+         BEGIN SELECT RAISE(ABORT, 'boom'); END;",
+    )
+    .unwrap();
+
+    bd().args(["mol", "pour", "ship"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure();
+    let issues: i64 = conn
+        .query_row("SELECT COUNT(*) FROM issues", [], |r| r.get(0))
+        .unwrap();
+    assert_eq!(issues, 0);
+
+    // Nor does a failed pour of a seeded molecule touch its root.
+    let output = bd()
+        .args(["mol", "seed", "ship", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let seed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let seed = seed["id"].as_str().unwrap();
+    let before: String = conn
+        .query_row("SELECT updated_at FROM issues WHERE id = ?1", [seed], |r| {
+            r.get(0)
+        })
+        .unwrap();
+    bd().args(["mol", "pour", seed])
+        .current_dir(tmp.path())
+        .assert()
+        .failure();
+    let (issues, after): (i64, String) = conn
+        .query_row(
+            "SELECT (SELECT COUNT(*) FROM issues), updated_at FROM issues WHERE id = ?1",
+            [seed],
+            |r| Ok((r.get(0)?, r.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(issues, 1);
+    assert_eq!(after, before);
+}
+
+#[test]
+fn molecule_tracking_queries() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("ship.formula.toml"),
+        r#"
+formula = "ship"
+
+[[steps]]
+id = "build"
+title = "Build"
+
+[[steps]]
+id = "notes"
+title = "Write notes"
+
+[[steps]]
+id = "deploy"
+title = "Deploy"
+needs = ["build"]
+"#,
+    )
+    .unwrap();
+
+    let output = bd()
+        .args(["mol", "pour", "ship", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let root = out["molecule"].as_str().unwrap().to_string();
+    let step = |name: &str| -> String {
+        out["created"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["step"] == name)
+            .unwrap()["id"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    };
+    let (build, notes, deploy) = (step("build"), step("notes"), step("deploy"));
+
+    let output = bd()
+        .args([
+            "gate",
+            "create",
+            "Approve deploy",
+            "--await-type",
+            "human",
+            "--json",
+        ])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let gate: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let gate = gate["id"].as_str().unwrap().to_string();
+    bd().args(["dep", "add", &deploy, &gate, "--type", "blocks"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    bd().args(["mol", "current", "--actor", "alice"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no current molecule for alice"));
+    bd().args([
+        "update",
+        &build,
+        "--status",
+        "in_progress",
+        "--assignee",
+        "alice",
+    ])
+    .current_dir(tmp.path())
+    .assert()
+    .success();
+    bd().args(["mol", "current", "--actor", "alice"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Molecule: {root}")))
+        .stdout(predicate::str::contains(format!("Working on: {build}")))
+        .stdout(predicate::str::contains(format!("Next: {notes}")));
+
+    // Gated only once the remaining work waits on nothing but the gate.
+    bd().args(["mol", "ready-gated"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No molecules waiting only on gates",
+        ));
+    for id in [&build, &notes] {
+        bd().args(["close", id])
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    }
+    let output = bd()
+        .args(["mol", "ready-gated", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let gated: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(gated[0]["id"], root.as_str());
+    assert_eq!(gated[0]["gates"][0]["id"], gate.as_str());
+    assert_eq!(gated[0]["gates"][0]["await_type"], "human");
+
+    bd().args(["mol", "stale"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No stale molecules"));
+    // A threshold under a day is not rounded down to zero.
+    bd().args(["mol", "stale", "--days", "1h"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "No stale molecules (step activity within last 1h)",
+        ));
+    bd().args(["mol", "stale", "--days", "0"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&root))
+        .stdout(predicate::str::contains("2/3"));
+}
+
+#[test]
+fn wisps_expire_and_are_collected() {
+    let tmp = init_project();
+    let beads = tmp.path().join(".beads");
+    std::fs::create_dir_all(beads.join("formulas")).unwrap();
+    std::fs::write(
+        beads.join("formulas/check.formula.toml"),
+        r#"
+formula = "check"
+
+[[steps]]
+id = "ping"
+title = "Ping"
+
+[[steps]]
+id = "report"
+title = "Report"
+needs = ["ping"]
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        beads.join("config.yaml"),
+        "wisp:\n  ttl:\n    heartbeat: 0s\n",
+    )
+    .unwrap();
+
+    bd().args(["wisp", "run", "check", "--type", "heartbeat"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Expires after 0s"));
+    bd().args(["wisp", "run", "check", "--type", "patrol"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Expires after 24h"));
+
+    // Wisps stay out of ready work.
+    bd().args(["ready"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Ping").not());
+
+    let output = bd()
+        .args(["cleanup", "--wisps", "--dry-run", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let dry: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(dry["would_delete"].as_array().unwrap().len(), 3);
+
+    let output = bd()
+        .args(["cleanup", "--wisps", "--report", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let gc: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(gc["deleted"], dry["would_delete"]);
+    let report = gc["report"].as_str().unwrap();
+
+    let output = bd()
+        .args(["wisp", "list", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let wisps: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let mut types: Vec<&str> = wisps
+        .iter()
+        .map(|w| w["wisp_type"].as_str().unwrap())
+        .collect();
+    types.sort();
+    assert_eq!(types, ["gc_report", "patrol", "patrol", "patrol"]);
+    bd().args(["show", report])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[heartbeat] Ping"));
+    for id in dry["would_delete"].as_array().unwrap() {
+        bd().args(["show", id.as_str().unwrap()])
+            .current_dir(tmp.path())
+            .assert()
+            .failure();
+    }
+}
+
+#[test]
+fn gate_evaluators() {
+    let tmp = init_project();
+    let marker = tmp.path().join("built");
+    std::fs::write(
+        tmp.path().join(".beads").join("config.yaml"),
+        "gate:\n  evaluators:\n    ci: \"grep -q '\\\"arg\\\":\\\"green\\\"' && echo '{\\\"status\\\":\\\"resolved\\\",\\\"reason\\\":\\\"ci green\\\"}' || echo '{\\\"status\\\":\\\"pending\\\"}'\"\n",
+    )
+    .unwrap();
+
+    bd().args(["gate", "create", "Bogus", "--await-type", "jenkins:42"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown await type 'jenkins:42'"));
+
+    let create = |title: &str, await_type: &str| {
+        let output = bd()
+            .args([
+                "gate",
+                "create",
+                title,
+                "--await-type",
+                await_type,
+                "--json",
+            ])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "gate create failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        let created: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        created["id"].as_str().unwrap().to_string()
+    };
+    let file_gate = create("Built", &format!("file:{}", marker.display()));
+    let ci_gate = create("CI", "ci:green");
+    let both = create(
+        "Both",
+        &format!(r#"all:["file:{}","issue:{}"]"#, marker.display(), file_gate),
+    );
+    create("CI red", "ci:red");
+
+    // Evaluators from the shared project config only run once this
+    // machine allows them.
+    bd().args(["gate", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("ci gates are disabled"));
+
+    let check = || {
+        let output = bd()
+            .args(["gate", "check", "--json"])
+            .env("BD_GATE_ALLOW_EXTERNAL", "true")
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let checked: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        checked["closed"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["id"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(check(), std::slice::from_ref(&ci_gate));
+
+    // Gates are checked oldest first, so the composite sees the file gate
+    // closed in the same pass.
+    std::fs::write(&marker, "ok").unwrap();
+    assert_eq!(check(), [file_gate, both]);
+    assert!(check().is_empty());
+    bd().args(["show", &ci_gate])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ci green"));
+}
+
+#[test]
+fn gate_watch_notifies_and_escalates() {
+    let tmp = init_project();
+    let marker = tmp.path().join("built");
+    let mail_log = tmp.path().join("mail.log");
+    let delegate = tmp.path().join("mail.sh");
+    std::fs::write(
+        &delegate,
+        format!("echo \"$@\" >> '{}'\n", mail_log.display()),
+    )
+    .unwrap();
+    let delegate = format!("sh {}", delegate.display());
+    let waiter = create_issue(&tmp, "Deploy", &[]);
+
+    let create = |title: &str, args: &[&str]| {
+        let output = bd()
+            .args(["gate", "create", title, "--json"])
+            .args(args)
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let created: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        created["id"].as_str().unwrap().to_string()
+    };
+    let built = create(
+        "Built",
+        &[
+            "--await-type",
+            &format!("file:{}", marker.display()),
+            "--waiter",
+            &waiter,
+            "--waiter",
+            "ops",
+        ],
+    );
+    let review = create("Review", &["--await-type", "cmd:false", "--timeout", "1s"]);
+
+    // Overdue gates escalate once.
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+    let output = bd()
+        .args(["gate", "check", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let checked: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(checked["closed"].as_array().unwrap().len(), 0);
+    assert_eq!(checked["escalated"][0]["id"], review.as_str());
+    let escalation = checked["escalated"][0]["escalation"].as_str().unwrap();
+    bd().args(["show", escalation])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Gate {review} timed out")));
+    let output = bd()
+        .args(["show", &review, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown[0]["priority"], 0);
+    let output = bd()
+        .args(["gate", "check", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let checked: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(checked["escalated"].as_array().unwrap().len(), 0);
+    bd().args(["gate", "close", &review])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    // Watch resolves the file gate and tells both waiters.
+    std::fs::write(&marker, "ok").unwrap();
+    bd().args([
+        "gate",
+        "watch",
+        "--interval",
+        "1s",
+        "--mail",
+        "--exit-when-idle",
+    ])
+    .env("BEADS_MAIL_DELEGATE", &delegate)
+    .current_dir(tmp.path())
+    .timeout(std::time::Duration::from_secs(30))
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(format!("Closed gate {built}")))
+    .stdout(predicate::str::contains(format!("Notified: {waiter}, ops")));
+    bd().args(["comments", &waiter])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "Gate {built} (Built) resolved"
+        )));
+    let log = std::fs::read_to_string(&mail_log).unwrap();
+    assert!(log.contains(&format!("send ops -s Gate {built} resolved -m")));
+    assert!(log.contains(&format!("send {waiter} -s")));
+}
+
+#[test]
+fn gate_watch_releases_the_database_between_passes() {
+    let tmp = init_project();
+    bd().args(["gate", "create", "Sign-off"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    let mut watch = std::process::Command::new(assert_cmd::cargo::cargo_bin!("bd"))
+        .args(["gate", "watch", "--interval", "1s", "--quiet"])
+        .current_dir(tmp.path())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    let migrated = bd()
+        .args(["migrate"])
+        .env("BD_LOCK_TIMEOUT", "5s")
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    watch.kill().unwrap();
+    watch.wait().unwrap();
+    assert!(migrated.status.success(), "{migrated:?}");
+}
+
+#[test]
+fn gate_watch_outlasts_a_busy_database() {
+    use beads_lockfile::{Lock, LockMode};
+
+    let tmp = init_project();
+    let marker = tmp.path().join("built");
+    bd().args([
+        "gate",
+        "create",
+        "Built",
+        "--await-type",
+        &format!("file:{}", marker.display()),
+    ])
+    .current_dir(tmp.path())
+    .assert()
+    .success();
+
+    let held = Lock::try_acquire(
+        &tmp.path().join(".beads/beads.db.lock"),
+        LockMode::Exclusive,
+        "migrate",
+    )
+    .unwrap();
+    let mut watch = std::process::Command::new(assert_cmd::cargo::cargo_bin!("bd"))
+        .args([
+            "gate",
+            "watch",
+            "--interval",
+            "1s",
+            "--exit-when-idle",
+            "--quiet",
+        ])
+        .env("BD_LOCK_TIMEOUT", "0")
+        .current_dir(tmp.path())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(1500));
+    drop(held);
+    std::fs::write(&marker, "ok").unwrap();
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+    while watch.try_wait().unwrap().is_none() && std::time::Instant::now() < deadline {
+        std::thread::sleep(std::time::Duration::from_millis(50));
+    }
+    let _ = watch.kill();
+    let output = watch.wait_with_output().unwrap();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stderr}");
+    assert!(stderr.contains("gate watch pass failed"), "{stderr}");
+}
+
+#[test]
+fn imported_cmd_gates_are_refused() {
+    let tmp = init_project();
+    let marker = tmp.path().join("pwned");
+    std::fs::write(
+        tmp.path().join("in.jsonl"),
+        format!(
+            r#"{{"id":"t-gate","title":"Sneaky","issue_type":"gate","await_type":"cmd:touch {}","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}}"#,
+            marker.display()
+        ) + "\n",
+    )
+    .unwrap();
+    import_json(&tmp, &[]);
+    // The shared project config cannot allow commands
+    let config = tmp.path().join(".beads/config.yaml");
+    let mut yaml = std::fs::read_to_string(&config).unwrap_or_default();
+    yaml.push_str("\ngate:\n  allow-cmd: true\n");
+    std::fs::write(&config, yaml).unwrap();
+
+    bd().args(["gate", "check"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("cmd gates are disabled"));
+    assert!(!marker.exists());
+
+    // This machine can
+    bd().args(["gate", "check"])
+        .env("BD_GATE_ALLOW_CMD", "true")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("auto-closed"));
+    assert!(marker.exists());
+}
+
+#[test]
+fn redirects_and_beads_dir_resolve_the_shared_database() {
+    let tmp = init_project();
+    let worktree = TempDir::new().unwrap();
+    let wt_beads = worktree.path().join(".beads");
+    std::fs::create_dir_all(&wt_beads).unwrap();
+    std::fs::write(
+        wt_beads.join("redirect"),
+        tmp.path().join(".beads").display().to_string(),
+    )
+    .unwrap();
+    let nested = worktree.path().join("src");
+    std::fs::create_dir_all(&nested).unwrap();
+
+    // Writes from the worktree land in the shared database.
+    bd().args(["create", "From worktree"])
+        .current_dir(&nested)
+        .assert()
+        .success();
+    bd().args(["list"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("From worktree"));
+    assert!(!wt_beads.join("beads.db").exists());
+
+    let output = bd()
+        .args(["where", "--json"])
+        .current_dir(&nested)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let found: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(found["source"], "discovered");
+    assert_eq!(found["prefix"], "t");
+    assert_eq!(found["redirects"].as_array().unwrap().len(), 1);
+
+    let elsewhere = TempDir::new().unwrap();
+    bd().args(["where"])
+        .env("BEADS_DIR", &wt_beads)
+        .current_dir(elsewhere.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Source: BEADS_DIR"))
+        .stdout(predicate::str::contains(format!(
+            "Redirected from: {}",
+            wt_beads.display()
+        )));
+
+    // A redirect back to the worktree is a loop.
+    std::fs::write(
+        tmp.path().join(".beads").join("redirect"),
+        wt_beads.display().to_string(),
+    )
+    .unwrap();
+    bd().args(["list"])
+        .current_dir(&nested)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("redirect loop"));
+}
+
+#[test]
+fn layered_config_is_enforced() {
+    let tmp = init_project();
+    let home = TempDir::new().unwrap();
+    std::fs::create_dir_all(home.path().join("beads")).unwrap();
+    std::fs::write(
+        home.path().join("beads/config.yaml"),
+        "types:\n  custom: \"spike\"\nhierarchy:\n  max-depth: 5\n",
+    )
+    .unwrap();
+    std::fs::write(
+        tmp.path().join(".beads/config.yaml"),
+        "create:\n  require-description: true\nhierarchy:\n  max-depth: 4\n",
+    )
+    .unwrap();
+    let bd = || {
+        let mut cmd = bd();
+        cmd.env("XDG_CONFIG_HOME", home.path())
+            .env("BD_HIERARCHY_MAX_DEPTH", "2")
+            .current_dir(tmp.path());
+        cmd
+    };
+
+    bd().args(["create", "No description"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("a description is required"));
+    bd().args(["create", "Weird", "-t", "weird", "-d", "x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown issue type 'weird'"));
+    bd().args(["create", "Spike", "-t", "spike", "-d", "x"])
+        .assert()
+        .success();
+
+    // Children get hierarchical IDs, up to the env's max depth of 2
+    let epic = String::from_utf8(
+        bd().args(["create", "Epic", "-d", "x", "--silent"])
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .unwrap()
+    .trim()
+    .to_string();
+    let child = format!("{epic}.1");
+    let grandchild = format!("{child}.1");
+    bd().args(["create", "Child", "-d", "x", "--parent", &epic])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&child));
+    bd().args(["create", "Grandchild", "-d", "x", "--parent", &child])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&grandchild));
+    bd().args(["create", "Too deep", "-d", "x", "--parent", &grandchild])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("beyond hierarchy.max-depth 2"));
+    let other = String::from_utf8(
+        bd().args(["create", "Other", "-d", "x", "--silent"])
+            .output()
+            .unwrap()
+            .stdout,
+    )
+    .unwrap()
+    .trim()
+    .to_string();
+    bd().args(["dep", "add", &epic, &other, "--type", "parent-child"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("beyond hierarchy.max-depth 2"));
+    bd().args(["update", &other, "--status", "parked"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown status 'parked'"));
+    bd().args(["status", &other, "parked"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown status 'parked'"));
+
+    let output = bd()
+        .args(["config", "show", "--origin", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown["types.custom"]["layer"], "user");
+    assert_eq!(shown["create.require-description"]["layer"], "project");
+    assert_eq!(shown["hierarchy.max-depth"]["value"], 2);
+    assert_eq!(
+        shown["hierarchy.max-depth"]["origin"],
+        "env BD_HIERARCHY_MAX_DEPTH"
+    );
+    assert_eq!(shown["conflict.strategy"]["layer"], "default");
+    bd().args(["--json", "config", "show", "--origin"])
+        .env_remove("BD_HIERARCHY_MAX_DEPTH")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"value\": 4"));
+}
+
+#[test]
+fn config_sets_actor_json_and_db() {
+    let tmp = init_project();
+    std::fs::write(
+        tmp.path().join(".beads/config.yaml"),
+        "actor: alice\njson: true\n",
+    )
+    .unwrap();
+    let bd = || {
+        let mut cmd = bd();
+        cmd.env_remove("BD_ACTOR")
+            .env_remove("BEADS_ACTOR")
+            .current_dir(tmp.path());
+        cmd
+    };
+
+    // json: true makes plain commands print JSON.
+    let created_by = |args: &[&str], env: &[(&str, &str)]| {
+        let output = bd().args(args).envs(env.iter().copied()).output().unwrap();
+        assert!(output.status.success(), "{output:?}");
+        let created: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let id = created["id"].as_str().unwrap().to_string();
+        let output = bd().args(["show", &id]).output().unwrap();
+        let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let shown = shown.as_array().map_or(shown.clone(), |a| a[0].clone());
+        shown["created_by"].as_str().unwrap().to_string()
+    };
+    assert_eq!(created_by(&["create", "Configured"], &[]), "alice");
+
+    // Flags and BD_* variables outrank config.yaml.
+    assert_eq!(
+        created_by(&["--actor", "bob", "create", "From flag"], &[]),
+        "bob"
+    );
+    assert_eq!(
+        created_by(&["create", "From env"], &[("BD_ACTOR", "carol")]),
+        "carol"
+    );
+
+    // BD_DB points at the project from anywhere.
+    let elsewhere = TempDir::new().unwrap();
+    let output = bd()
+        .args(["list"])
+        .env("BD_DB", tmp.path().join(".beads"))
+        .current_dir(elsewhere.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let listed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(listed.as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn close_skipped_stays_within_the_filter() {
+    let tmp = init_project();
+    let epic = create_issue(&tmp, "Release", &["-t", "epic"]);
+    let deploy = create_issue(&tmp, "Deploy", &[]);
+    let inside = create_issue(&tmp, "Rollback release", &["-l", "ops"]);
+    let outside = create_issue(&tmp, "Rollback elsewhere", &[]);
+    let labelled = create_issue(&tmp, "Rollback labelled", &["-l", "ops"]);
+    for (from, to, dep_type) in [
+        (&inside, &epic, "parent-child"),
+        (&inside, &deploy, "conditional-blocks"),
+        (&outside, &deploy, "conditional-blocks"),
+        (&labelled, &deploy, "conditional-blocks"),
+    ] {
+        bd().args(["dep", "add", from, to, "--type", dep_type])
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    }
+    bd().args(["close", &deploy, "--reason", "Deployed"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    let status = |id: &str| -> String {
+        let output = bd()
+            .args(["show", id, "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        shown[0]["status"].as_str().unwrap_or("open").to_string()
+    };
+
+    bd().args(["ready", "--epic", &epic, "--close-skipped"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Closed 1 skipped issue:"));
+    assert_eq!(status(&inside), "closed");
+    assert_eq!(status(&outside), "open");
+    assert_eq!(status(&labelled), "open");
+
+    bd().args(["ready", "-l", "ops", "--close-skipped"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    assert_eq!(status(&labelled), "closed");
+    assert_eq!(status(&outside), "open");
+}
+
+#[test]
+fn conditional_blocks_run_only_on_failure() {
+    let tmp = init_project();
+    let deploy = create_issue(&tmp, "Deploy", &[]);
+    let rollback = create_issue(&tmp, "Rollback", &[]);
+    let postmortem = create_issue(&tmp, "Postmortem", &[]);
+    let announce = create_issue(&tmp, "Announce", &[]);
+    let dep = |from: &str, to: &str, dep_type: &str| {
+        bd().args(["dep", "add", from, to, "--type", dep_type])
+            .current_dir(tmp.path())
+            .assert()
+            .success();
+    };
+    // Rollback runs only if the deploy fails, the postmortem only after a
+    // rollback; the announcement follows the deploy either way.
+    dep(&rollback, &deploy, "conditional-blocks");
+    dep(&postmortem, &rollback, "conditional-blocks");
+    dep(&announce, &deploy, "blocks");
+    let ready_ids = || -> Vec<String> {
+        let output = bd()
+            .args(["ready", "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        let issues: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        issues
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert!(!ready_ids().contains(&rollback));
+    bd().args(["list", "--query", "blocked:true"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&rollback));
+
+    bd().args(["close", &deploy, "--reason", "Deployed"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    let ready = ready_ids();
+    assert!(ready.contains(&announce));
+    assert!(!ready.contains(&rollback));
+    bd().args(["ready"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipped (2 issues"))
+        .stdout(predicate::str::contains(format!(
+            "{rollback}: Rollback ({deploy} closed: Deployed)"
+        )))
+        .stdout(predicate::str::contains(format!(
+            "{postmortem}: Postmortem ({deploy} closed: Deployed)"
+        )));
+
+    let epic = create_issue(&tmp, "Release", &["-t", "epic"]);
+    for child in [&deploy, &rollback, &postmortem, &announce] {
+        dep(child, &epic, "parent-child");
+    }
+    let output = bd()
+        .args(["swarm", "status", &epic, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let mut skipped: Vec<&str> = status["skipped"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].as_str().unwrap())
+        .collect();
+    skipped.sort_unstable();
+    let mut expected = vec![postmortem.as_str(), rollback.as_str()];
+    expected.sort_unstable();
+    assert_eq!(skipped, expected);
+
+    let output = bd()
+        .args(["graph", &deploy, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let graph: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let node = |id: &str| {
+        graph["nodes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|n| n["id"] == id)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(node(&rollback)["skipped"], true);
+    assert_eq!(node(&postmortem)["skipped"], true);
+    assert_eq!(node(&announce)["skipped"], false);
+
+    // Closing skipped issues cascades through the conditional chain
+    bd().args(["ready", "--close-skipped"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Closed 2 skipped issues"));
+    let output = bd()
+        .args(["show", &postmortem, "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown[0]["status"], "closed");
+    assert_eq!(
+        shown[0]["close_reason"],
+        format!("skipped: {deploy} closed without failing")
+    );
+}
+
+#[test]
+fn conditional_dependents_run_after_failure() {
+    let tmp = init_project();
+    let deploy = create_issue(&tmp, "Deploy", &[]);
+    let rollback = create_issue(&tmp, "Rollback", &[]);
+    bd().args([
+        "dep",
+        "add",
+        &rollback,
+        &deploy,
+        "--type",
+        "conditional-blocks",
+    ])
+    .current_dir(tmp.path())
+    .assert()
+    .success();
+    bd().args(["close", &deploy, "--reason", "Health checks failed"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["ready"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&rollback))
+        .stdout(predicate::str::contains("Skipped").not());
+}
+
+#[test]
+fn ready_scopes_to_an_epic() {
+    let tmp = init_project();
+    let epic = create_issue(&tmp, "Swarm", &["-t", "epic"]);
+    let child = create_issue(&tmp, "Child", &["--parent", &epic, "-p", "2"]);
+    let grandchild = create_issue(&tmp, "Grandchild", &["--parent", &child, "-p", "1"]);
+    create_issue(&tmp, "Elsewhere", &["-p", "0"]);
+
+    let output = bd()
+        .args(["ready", "--epic", &epic, "--sort", "hybrid", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let ready: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let ids: Vec<&str> = ready.iter().map(|i| i["id"].as_str().unwrap()).collect();
+    assert_eq!(ids, [grandchild.as_str(), child.as_str()]);
+
+    bd().args(["ready", "--epic", "t-nope"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("issue 't-nope' not found"));
+}
+
+const ROUTE_JSONL: &str = r#"{"id":"t-lead","title":"Tech lead","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","status":"closed","closed_at":"2024-01-01T00:00:00Z","dependencies":[{"issue_id":"t-lead","depends_on_id":"t-rusty","type":"attests","created_at":"2024-01-01T00:00:00Z","metadata":"{\"skill\":\"rust\",\"level\":\"expert\",\"date\":\"2024-01-01T00:00:00Z\"}"},{"issue_id":"t-lead","depends_on_id":"t-newbie","type":"attests","created_at":"2024-01-01T00:00:00Z","metadata":"{\"skill\":\"rust\",\"level\":\"beginner\",\"date\":\"2024-01-01T00:00:00Z\"}"}]}
+{"id":"t-rusty","title":"Agent: rusty","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"idle","labels":["gt:agent"]}
+{"id":"t-newbie","title":"Agent: newbie","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"idle","labels":["gt:agent"]}
+{"id":"t-busy","title":"Agent: busy","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"working","labels":["gt:agent"]}
+{"id":"t-parser","title":"Rewrite parser","priority":1,"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","labels":["skill:rust:advanced"]}
+{"id":"t-docs","title":"Write docs","priority":2,"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","metadata":{"skills":["python"]}}
+{"id":"t-bench","title":"Speed up builds","priority":3,"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","work_type":"open_competition","labels":["skill:rust"]}
+"#;
+
+#[test]
+fn agent_route_matches_skills() {
+    let tmp = init_project();
+    std::fs::write(tmp.path().join("in.jsonl"), ROUTE_JSONL).unwrap();
+    import_json(&tmp, &[]);
+
+    let route = |args: &[&str]| -> serde_json::Value {
+        let output = bd()
+            .args(["agent", "route", "--json"])
+            .args(args)
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "route failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+
+    // The plan: the expert takes the parser, the beginner competes on the
+    // benchmark work, nobody speaks python and the busy agent is skipped.
+    let plan = route(&["--dry-run"]);
+    let assignments = plan["assignments"].as_array().unwrap();
+    assert_eq!(assignments.len(), 2);
+    assert_eq!(assignments[0]["agent"], "t-rusty");
+    assert_eq!(assignments[0]["issue"], "t-parser");
+    assert_eq!(assignments[0]["agent_skills"]["rust"], "expert");
+    assert_eq!(assignments[1]["agent"], "t-newbie");
+    assert_eq!(assignments[1]["issue"], "t-bench");
+    assert_eq!(assignments[1]["work_type"], "open_competition");
+    assert_eq!(plan["unrouted"][0]["issue"], "t-docs");
+    assert_eq!(plan["skipped_agents"][0]["agent"], "t-busy");
+
+    // A dry run changes nothing
+    let show = |id: &str| -> serde_json::Value {
+        let output = bd()
+            .args(["show", id, "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()[0].clone()
+    };
+    assert!(show("t-rusty").get("hook_bead").is_none());
+
+    route(&[]);
+    let rusty = show("t-rusty");
+    assert_eq!(rusty["hook_bead"], "t-parser");
+    assert_eq!(rusty["agent_state"], "running");
+    let parser = show("t-parser");
+    assert_eq!(parser["assignee"], "t-rusty");
+    assert_eq!(parser["status"], "in_progress");
+    // Open competition leaves the issue unassigned and open
+    assert_eq!(show("t-newbie")["hook_bead"], "t-bench");
+    let bench = show("t-bench");
+    assert!(bench.get("assignee").is_none());
+    assert_ne!(bench["status"], "in_progress");
+
+    // Routed agents are busy now
+    bd().args(["agent", "route", "t-rusty"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot take work"));
+}
+
+#[test]
+fn agent_route_assigns_poured_steps() {
+    let tmp = init_project();
+    let formulas = tmp.path().join(".beads/formulas");
+    std::fs::create_dir_all(&formulas).unwrap();
+    std::fs::write(
+        formulas.join("ship.formula.toml"),
+        "formula = \"ship\"\n[[steps]]\nid = \"build\"\ntitle = \"Build\"\n",
+    )
+    .unwrap();
+    let output = bd()
+        .args(["mol", "pour", "ship", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let out: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let build = out["created"][0]["id"].as_str().unwrap().to_string();
+    std::fs::write(
+        tmp.path().join("in.jsonl"),
+        r#"{"id":"t-idle","title":"Agent: idle","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"idle","labels":["gt:agent"]}
+"#,
+    )
+    .unwrap();
+    import_json(&tmp, &[]);
+
+    // The step is routed; the molecule root is not work in its own right.
+    let output = bd()
+        .args(["agent", "route", "--dry-run", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let plan: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let assignments = plan["assignments"].as_array().unwrap();
+    assert_eq!(assignments.len(), 1);
+    assert_eq!(assignments[0]["agent"], "t-idle");
+    assert_eq!(assignments[0]["issue"], build.as_str());
+    assert_eq!(plan["unrouted"], serde_json::json!([]));
+}
+
+const REAP_JSONL: &str = r#"{"id":"t-crashed","title":"Agent: crashed","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"working","role_type":"polecat","hook_bead":"t-task","last_activity":"2024-01-01T00:00:00Z","labels":["gt:agent"]}
+{"id":"t-crew","title":"Agent: crew","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"working","role_type":"crew","last_activity":"2024-01-01T00:00:00Z","labels":["gt:agent"]}
+{"id":"t-alive","title":"Agent: alive","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","agent_state":"working","role_type":"polecat","last_activity":"2024-01-01T00:00:00Z","labels":["gt:agent"]}
+{"id":"t-task","title":"Long task","status":"in_progress","assignee":"t-crashed","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}
+"#;
+
+#[test]
+fn agent_reap_reclaims_work_from_silent_agents() {
+    let tmp = init_project();
+    std::fs::write(tmp.path().join("in.jsonl"), REAP_JSONL).unwrap();
+    import_json(&tmp, &[]);
+    let config = tmp.path().join(".beads/config.yaml");
+    let mut yaml = std::fs::read_to_string(&config).unwrap_or_default();
+    yaml.push_str("\nagent:\n  liveness:\n    crew: 36500d\n");
+    std::fs::write(&config, yaml).unwrap();
+
+    bd().args(["agent", "heartbeat", "t-alive"])
         .current_dir(tmp.path())
         .assert()
         .success();
 
-    // List comments => both present
+    let reap = |args: &[&str]| -> serde_json::Value {
+        let output = bd()
+            .args(["agent", "reap", "--json"])
+            .args(args)
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "reap failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        serde_json::from_slice(&output.stdout).unwrap()
+    };
+
+    // Only the polecat that stopped talking is silent for too long
+    let plan = reap(&["--dry-run"]);
+    let reaped = plan["reaped"].as_array().unwrap();
+    assert_eq!(reaped.len(), 1);
+    assert_eq!(reaped[0]["agent"], "t-crashed");
+    assert_eq!(reaped[0]["reclaimed"], "t-task");
+    assert_eq!(reaped[0]["timeout"], "15m");
+
+    let result = reap(&[]);
+    let wisp = result["reaped"][0]["wisp"].as_str().unwrap().to_string();
+
+    let show = |id: &str| -> serde_json::Value {
+        let output = bd()
+            .args(["show", id, "--json"])
+            .current_dir(tmp.path())
+            .output()
+            .unwrap();
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()[0].clone()
+    };
+    let crashed = show("t-crashed");
+    assert_eq!(crashed["agent_state"], "dead");
+    assert!(crashed.get("hook_bead").is_none());
+    let task = show("t-task");
+    assert_ne!(task["status"], "in_progress");
+    assert!(task.get("assignee").is_none());
     let output = bd()
-        .args(["comments", &id, "--json"])
+        .args(["comments", "t-task", "--json"])
         .current_dir(tmp.path())
         .output()
         .unwrap();
-    assert!(output.status.success());
-    let comments: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
-    let arr = comments.as_array().unwrap();
-    assert_eq!(arr.len(), 2, "should have 2 comments");
-    assert_eq!(arr[0]["text"].as_str().unwrap(), "First comment");
-    assert_eq!(arr[1]["text"].as_str().unwrap(), "Second comment");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Reclaimed from agent t-crashed"));
+    assert_eq!(show(&wisp)["wisp_type"], "recovery");
 
-    // History => should have events (created, commented)
+    // The task is ready again, and a second reap finds nothing
     let output = bd()
-        .args(["history", &id, "--json"])
+        .args(["ready", "--json"])
         .current_dir(tmp.path())
         .output()
         .unwrap();
-    assert!(output.status.success());
-    let history: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
-    let events = history["events"].as_array().unwrap();
-    assert!(
-        events.len() >= 3,
-        "should have at least 3 events (created + 2 commented)"
-    );
-
-    let event_types: Vec<&str> = events
-        .iter()
-        .map(|e| e["event_type"].as_str().unwrap())
-        .collect();
-    assert!(
-        event_types.contains(&"created"),
-        "should have 'created' event"
-    );
-    assert!(
-        event_types.contains(&"commented"),
-        "should have 'commented' event"
-    );
+    assert!(String::from_utf8_lossy(&output.stdout).contains("t-task"));
+    assert!(reap(&[])["reaped"].as_array().unwrap().is_empty());
 }
 
-// ---------------------------------------------------------------------------
-// Flow 6: Stats and views
-// ---------------------------------------------------------------------------
-
 #[test]
 fn flow6_stats_and_views() {
     let tmp = init_project();
@@ -894,6 +2824,12 @@ fn init_creates_beads_dir() {
     assert!(tmp.path().join(".beads").is_dir());
     assert!(tmp.path().join(".beads").join("beads.db").is_file());
     assert!(tmp.path().join(".beads").join("metadata.json").is_file());
+
+    // Backups from bd migrate and temporary export files stay out of git
+    let gitignore = std::fs::read_to_string(tmp.path().join(".beads/.gitignore")).unwrap();
+    assert!(gitignore.lines().any(|l| l == "*.bak"));
+    assert!(gitignore.lines().any(|l| l == "*.tmp"));
+    assert!(gitignore.lines().any(|l| l == "*.lock.d/"));
 }
 
 #[test]
@@ -1011,23 +2947,55 @@ fn dep_cycles_detection() {
     let a = create_issue(&tmp, "Issue A", &[]);
     let b = create_issue(&tmp, "Issue B", &[]);
 
-    // Create a cycle: a -> b -> a
     bd().args(["dep", "add", &a, &b, "--type", "blocks"])
         .current_dir(tmp.path())
         .assert()
         .success();
 
+    // Closing the loop b -> a is rejected by storage validation
     bd().args(["dep", "add", &b, &a, "--type", "blocks"])
         .current_dir(tmp.path())
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("cycle"));
+
+    bd().args(["dep", "cycles"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No dependency cycles"));
 
-    // Detect cycles
+    // An import cannot close the loop either
+    std::fs::write(
+        tmp.path().join("in.jsonl"),
+        format!(
+            r#"{{"id":"{b}","title":"Issue B","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-02T00:00:00Z","dependencies":[{{"issue_id":"{b}","depends_on_id":"{a}","type":"blocks","created_at":"2024-01-02T00:00:00Z"}}]}}"#
+        ) + "\n",
+    )
+    .unwrap();
+    bd().args(["import", "in.jsonl"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cycle"));
+
+    // A database written before cycles were rejected may still hold one,
+    // and dep cycles reports it
+    let conn = rusqlite::Connection::open(tmp.path().join(".beads").join("beads.db")).unwrap();
+    conn.execute(
+        "INSERT INTO dependencies (issue_id, depends_on_id, type, created_at, created_by)
+         VALUES (?1, ?2, 'blocks', '2024-01-01T00:00:00Z', 'legacy')",
+        [&b, &a],
+    )
+    .unwrap();
+    drop(conn);
     bd().args(["dep", "cycles"])
         .current_dir(tmp.path())
         .assert()
         .success()
-        .stdout(predicate::str::contains("cycle"));
+        .stdout(predicate::str::contains(&a))
+        .stdout(predicate::str::contains(&b))
+        .stdout(predicate::str::contains("No dependency cycles").not());
 }
 
 #[test]
@@ -1329,3 +3297,494 @@ fn worktree_list_in_git_repo() {
         .success()
         .stdout(predicate::str::contains("NAME"));
 }
+
+// ---------------------------------------------------------------------------
+// Import
+// ---------------------------------------------------------------------------
+
+const IMPORT_JSONL: &str = r#"{"id":"t-imp1","title":"Imported one","priority":1,"created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","labels":["backend"],"comments":[{"id":1,"issue_id":"t-imp1","author":"alice","text":"first","created_at":"2024-01-01T00:00:00Z"}]}
+{"id":"t-imp2","title":"Imported two","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z","dependencies":[{"issue_id":"t-imp2","depends_on_id":"t-imp1","type":"blocks","created_at":"2024-01-01T00:00:00Z"}]}
+"#;
+
+fn import_json(tmp: &TempDir, extra_args: &[&str]) -> serde_json::Value {
+    let mut args = vec!["import", "in.jsonl", "--json"];
+    args.extend_from_slice(extra_args);
+    let output = bd().args(&args).current_dir(tmp.path()).output().unwrap();
+    assert!(
+        output.status.success(),
+        "import failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn import_jsonl_upserts_by_content_hash() {
+    let tmp = init_project();
+    std::fs::write(tmp.path().join("in.jsonl"), IMPORT_JSONL).unwrap();
+
+    // Dry run reports creations but writes nothing.
+    let summary = import_json(&tmp, &["--dry-run"]);
+    assert_eq!(summary["created"], 2);
+    bd().args(["show", "t-imp1"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure();
+
+    let summary = import_json(&tmp, &[]);
+    assert_eq!(summary["created"], 2);
+    assert_eq!(summary["failed"], 0);
+
+    let output = bd()
+        .args(["show", "t-imp1", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let show: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(show[0]["title"], "Imported one");
+
+    let output = bd()
+        .args(["label", "t-imp1", "list", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("backend"));
+
+    // Only t-imp1 is ready: t-imp2 is blocked by it.
+    let output = bd()
+        .args(["ready", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    let ready = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(ready.contains("t-imp1"));
+    assert!(!ready.contains("t-imp2"));
+
+    // Re-import is a no-op.
+    let summary = import_json(&tmp, &[]);
+    assert_eq!(summary["unchanged"], 2);
+
+    // Changing one record updates only that record.
+    let changed = IMPORT_JSONL.replace("Imported two", "Imported two (edited)");
+    std::fs::write(tmp.path().join("in.jsonl"), changed).unwrap();
+    let summary = import_json(&tmp, &[]);
+    assert_eq!(summary["updated"], 1);
+    assert_eq!(summary["unchanged"], 1);
+}
+
+#[test]
+fn import_reports_malformed_lines() {
+    let tmp = init_project();
+    std::fs::write(
+        tmp.path().join("in.jsonl"),
+        "{\"id\":\"t-ok\",\"title\":\"Fine\",\"created_at\":\"2024-01-01T00:00:00Z\",\"updated_at\":\"2024-01-01T00:00:00Z\"}\nnot json\n",
+    )
+    .unwrap();
+
+    bd().args(["import", "in.jsonl"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("1 created"))
+        .stdout(predicate::str::contains("1 failed"));
+}
+
+// ---------------------------------------------------------------------------
+// Export
+// ---------------------------------------------------------------------------
+
+#[test]
+fn export_jsonl_roundtrips_through_import() {
+    let tmp = init_project();
+    let a = create_issue(&tmp, "Export A", &["-l", "backend"]);
+    let b = create_issue(&tmp, "Export B", &[]);
+    bd().args(["dep", "add", &b, &a])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    bd().args(["comment", &a, "exported comment"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+
+    bd().args(["export"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 2 issues"));
+
+    let path = tmp.path().join(".beads").join("issues.jsonl");
+    let first = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = first.lines().collect();
+    assert_eq!(lines.len(), 2);
+    let mut sorted = [a.clone(), b.clone()];
+    sorted.sort();
+    assert!(lines[0].contains(&format!("\"id\":\"{}\"", sorted[0])));
+    assert!(first.contains("backend"));
+    assert!(first.contains("exported comment"));
+    assert!(first.contains("depends_on_id"));
+
+    // Exporting unchanged data is byte-identical.
+    bd().args(["export"])
+        .current_dir(tmp.path())
+        .assert()
+        .success();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), first);
+
+    // Filtered exports go to stdout.
+    bd().args(["export", "--label", "backend"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(a.as_str()))
+        .stdout(predicate::str::contains(b.as_str()).not());
+
+    // A fresh project rebuilt from the export re-exports identically.
+    let fresh = init_project();
+    std::fs::write(fresh.path().join("in.jsonl"), &first).unwrap();
+    let summary = import_json(&fresh, &[]);
+    assert_eq!(summary["created"], 2);
+    bd().args(["export"])
+        .current_dir(fresh.path())
+        .assert()
+        .success();
+    let rebuilt =
+        std::fs::read_to_string(fresh.path().join(".beads").join("issues.jsonl")).unwrap();
+    assert_eq!(rebuilt, first);
+}
+
+// ---------------------------------------------------------------------------
+// Sync: git-backed JSONL exchange between two clones
+// ---------------------------------------------------------------------------
+
+/// Run git in `dir` with a fixed identity, asserting success.
+fn git(dir: &std::path::Path, args: &[&str]) {
+    let output = std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "tester")
+        .env("GIT_AUTHOR_EMAIL", "tester@example.com")
+        .env("GIT_COMMITTER_NAME", "tester")
+        .env("GIT_COMMITTER_EMAIL", "tester@example.com")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "git {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// `bd` with a fixed git identity so sync can commit.
+fn bd_git(dir: &std::path::Path) -> Command {
+    let mut cmd = bd();
+    cmd.current_dir(dir)
+        .env("GIT_AUTHOR_NAME", "tester")
+        .env("GIT_AUTHOR_EMAIL", "tester@example.com")
+        .env("GIT_COMMITTER_NAME", "tester")
+        .env("GIT_COMMITTER_EMAIL", "tester@example.com");
+    cmd
+}
+
+fn show_json(dir: &std::path::Path, id: &str) -> serde_json::Value {
+    let output = bd_git(dir).args(["show", id, "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let show: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    show[0].clone()
+}
+
+#[test]
+fn sync_merges_concurrent_edits_between_clones() {
+    let root = TempDir::new().unwrap();
+    let remote = root.path().join("remote.git");
+    let a = root.path().join("a");
+    let b = root.path().join("b");
+    git(root.path(), &["init", "--bare", "-b", "main", "remote.git"]);
+
+    // Clone A: create an issue and publish it.
+    git(root.path(), &["clone", "remote.git", "a"]);
+    git(&a, &["checkout", "-b", "main"]);
+    bd_git(&a)
+        .args(["init", "--prefix", "t", "--quiet"])
+        .assert()
+        .success();
+    let output = bd_git(&a)
+        .args(["create", "Shared issue", "--json"])
+        .output()
+        .unwrap();
+    let id = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    git(&a, &["add", ".beads"]);
+    git(&a, &["commit", "-q", "-m", "init beads"]);
+    git(&a, &["push", "-q", "-u", "origin", "main"]);
+    bd_git(&a).args(["sync"]).assert().success();
+    assert!(remote.exists());
+
+    // Clone B picks the issue up from the JSONL.
+    git(root.path(), &["clone", "-q", "remote.git", "b"]);
+    bd_git(&b)
+        .args(["init", "--prefix", "t", "--quiet"])
+        .assert()
+        .success();
+    bd_git(&b).args(["sync"]).assert().success();
+    assert_eq!(show_json(&b, &id)["title"], "Shared issue");
+
+    // Concurrent edits to different fields of the same issue.
+    bd_git(&a)
+        .args(["update", &id, "--title", "Renamed in A"])
+        .assert()
+        .success();
+    bd_git(&b)
+        .args(["update", &id, "--add-label", "from-b", "-p", "0"])
+        .assert()
+        .success();
+    let output = bd_git(&b)
+        .args(["create", "Only in B", "--json"])
+        .output()
+        .unwrap();
+    let id_b = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+
+    bd_git(&a).args(["sync"]).assert().success();
+    let output = bd_git(&b).args(["sync", "--json"]).output().unwrap();
+    assert!(
+        output.status.success(),
+        "sync failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["pulled"], true);
+    assert_eq!(report["pushed"], true);
+
+    let merged = show_json(&b, &id);
+    assert_eq!(merged["title"], "Renamed in A");
+    assert_eq!(merged["priority"], 0);
+
+    // A receives B's side of the merge.
+    bd_git(&a).args(["sync"]).assert().success();
+    let merged = show_json(&a, &id);
+    assert_eq!(merged["title"], "Renamed in A");
+    assert_eq!(merged["priority"], 0);
+    assert_eq!(show_json(&a, &id_b)["title"], "Only in B");
+    let labels = bd_git(&a)
+        .args(["label", &id, "list", "--json"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8_lossy(&labels.stdout).contains("from-b"));
+
+    // Nothing left to do on either side.
+    let output = bd_git(&a)
+        .args(["sync", "--status", "--json"])
+        .output()
+        .unwrap();
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["pending_export"], false);
+    assert_eq!(status["uncommitted"], false);
+    assert_eq!(status["ahead"], 0);
+}
+
+#[test]
+fn sync_export_on_change_keeps_jsonl_current() {
+    let tmp = init_project();
+    std::fs::write(
+        tmp.path().join(".beads").join("config.yaml"),
+        "sync:\n  export_on: change\n",
+    )
+    .unwrap();
+
+    let id = create_issue(&tmp, "Auto exported", &[]);
+    let jsonl = std::fs::read_to_string(tmp.path().join(".beads").join("issues.jsonl")).unwrap();
+    assert!(jsonl.contains(&id));
+
+    // Sandbox mode disables the trigger.
+    let output = bd()
+        .args(["--sandbox", "create", "Not exported", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let jsonl = std::fs::read_to_string(tmp.path().join(".beads").join("issues.jsonl")).unwrap();
+    assert!(!jsonl.contains("Not exported"));
+}
+
+// ---------------------------------------------------------------------------
+// Merge driver
+// ---------------------------------------------------------------------------
+
+/// Run git with the built `bd` first on PATH so the merge driver resolves.
+fn git_with_bd(dir: &std::path::Path, args: &[&str]) -> std::process::Output {
+    let bin_dir = std::path::Path::new(env!("CARGO_BIN_EXE_bd"))
+        .parent()
+        .unwrap()
+        .to_path_buf();
+    let path = std::env::join_paths(
+        std::iter::once(bin_dir).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    std::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("PATH", path)
+        .env("GIT_AUTHOR_NAME", "tester")
+        .env("GIT_AUTHOR_EMAIL", "tester@example.com")
+        .env("GIT_COMMITTER_NAME", "tester")
+        .env("GIT_COMMITTER_EMAIL", "tester@example.com")
+        .output()
+        .unwrap()
+}
+
+/// A repo with one exported issue committed on `main`; returns its ID.
+fn merge_driver_repo(dir: &std::path::Path) -> String {
+    git(dir, &["init", "-q", "-b", "main"]);
+    bd_git(dir)
+        .args(["init", "--prefix", "t", "--quiet"])
+        .assert()
+        .success();
+    let attributes = std::fs::read_to_string(dir.join(".gitattributes")).unwrap();
+    assert!(attributes.contains(".beads/issues.jsonl merge=beads"));
+
+    let output = bd_git(dir)
+        .args(["create", "Driver issue", "--json"])
+        .output()
+        .unwrap();
+    let id = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()["id"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    bd_git(dir).args(["export"]).assert().success();
+    git(dir, &["add", "."]);
+    git(dir, &["commit", "-q", "-m", "base"]);
+    id
+}
+
+/// Edits the issue on a side branch and on main, exporting each time.
+fn diverge(dir: &std::path::Path, id: &str, feature: &[&str], main: &[&str]) {
+    git(dir, &["checkout", "-q", "-b", "feature"]);
+    let mut args = vec!["update", id];
+    args.extend_from_slice(feature);
+    bd_git(dir).args(&args).assert().success();
+    bd_git(dir).args(["export"]).assert().success();
+    git(dir, &["commit", "-q", "-am", "feature edit"]);
+
+    git(dir, &["checkout", "-q", "main"]);
+    let mut args = vec!["update", id];
+    args.extend_from_slice(main);
+    bd_git(dir).args(&args).assert().success();
+    bd_git(dir).args(["export"]).assert().success();
+    git(dir, &["commit", "-q", "-am", "main edit"]);
+}
+
+#[test]
+fn merge_driver_resolves_field_edits() {
+    let tmp = TempDir::new().unwrap();
+    let id = merge_driver_repo(tmp.path());
+    diverge(
+        tmp.path(),
+        &id,
+        &["--title", "Feature title"],
+        &["-p", "0", "--add-label", "main"],
+    );
+
+    let output = git_with_bd(tmp.path(), &["merge", "--no-edit", "feature"]);
+    assert!(
+        output.status.success(),
+        "merge failed: {}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let jsonl = std::fs::read_to_string(tmp.path().join(".beads").join("issues.jsonl")).unwrap();
+    assert_eq!(jsonl.lines().count(), 1);
+    let merged: serde_json::Value = serde_json::from_str(jsonl.trim()).unwrap();
+    assert_eq!(merged["title"], "Feature title");
+    assert_eq!(merged["priority"], 0);
+    assert_eq!(merged["labels"], serde_json::json!(["main"]));
+}
+
+#[test]
+fn merge_driver_reports_manual_conflicts() {
+    let tmp = TempDir::new().unwrap();
+    let id = merge_driver_repo(tmp.path());
+    std::fs::write(
+        tmp.path().join(".beads").join("config.yaml"),
+        "conflict:\n  strategy: manual\n",
+    )
+    .unwrap();
+    git(tmp.path(), &["add", "."]);
+    git(tmp.path(), &["commit", "-q", "-m", "manual conflicts"]);
+    diverge(
+        tmp.path(),
+        &id,
+        &["--title", "Feature title"],
+        &["--title", "Main title"],
+    );
+
+    let output = git_with_bd(tmp.path(), &["merge", "--no-edit", "feature"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("\"conflicts\""), "{stderr}");
+    assert!(stderr.contains("\"field\": \"title\""), "{stderr}");
+}
+
+// ---------------------------------------------------------------------------
+// Migrate
+// ---------------------------------------------------------------------------
+
+#[test]
+fn migrate_status_and_noop() {
+    let tmp = init_project();
+
+    let output = bd()
+        .args(["migrate", "--status", "--json"])
+        .current_dir(tmp.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let status: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(status["current_version"], status["latest_version"]);
+    assert_eq!(status["pending"], serde_json::json!([]));
+
+    bd().args(["migrate", "--dry-run"])
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No pending migrations"));
+    bd().arg("migrate")
+        .current_dir(tmp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("up to date"));
+    bd().args(["migrate", "--to", "0"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot migrate down"));
+}
+
+#[test]
+fn newer_schema_is_refused() {
+    let tmp = init_project();
+    let conn = rusqlite::Connection::open(tmp.path().join(".beads").join("beads.db")).unwrap();
+    conn.execute(
+        "UPDATE config SET value = '999' WHERE key = 'schema_version'",
+        [],
+    )
+    .unwrap();
+    drop(conn);
+
+    bd().arg("list")
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("newer than this bd supports"));
+    bd().args(["migrate", "--status"])
+        .current_dir(tmp.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("newer than this bd supports"));
+}
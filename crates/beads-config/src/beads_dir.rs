@@ -1,8 +1,10 @@
 //! Discovery and management of the `.beads/` directory.
 //!
 //! The `.beads/` directory is the root of a beads project's metadata. This
-//! module provides functions to find it by walking up the directory tree,
-//! and to create it when initializing a new project.
+//! module provides functions to find it (from `BEADS_DIR` or by walking up
+//! the directory tree, following `.beads/redirect` files such as the ones
+//! `bd worktree create` writes), and to create it when initializing a new
+//! project.
 //!
 //! Ported from Go `internal/beads/beads.go` (`FindBeadsDir`, `findLocalBeadsDir`).
 
@@ -13,13 +15,94 @@ use std::path::{Path, PathBuf};
 const BEADS_DIR_NAME: &str = ".beads";
 
 /// The name of the environment variable that can override the beads directory.
-const BEADS_DIR_ENV: &str = "BEADS_DIR";
+pub const BEADS_DIR_ENV: &str = "BEADS_DIR";
 
-/// Walk up the directory tree from `start` looking for a `.beads/` directory.
+/// The name of the file that points a `.beads/` directory at another one.
+const REDIRECT_FILE: &str = "redirect";
+
+/// How a `.beads/` directory was chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeadsDirSource {
+    /// Given explicitly (`bd --db`).
+    Flag,
+    /// Set in the `BEADS_DIR` environment variable.
+    Env,
+    /// Found by walking up from the start directory.
+    Discovered,
+}
+
+impl BeadsDirSource {
+    /// Short name: `flag`, `env` or `discovered`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BeadsDirSource::Flag => "flag",
+            BeadsDirSource::Env => "env",
+            BeadsDirSource::Discovered => "discovered",
+        }
+    }
+}
+
+/// A `.beads/` directory and how it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedBeadsDir {
+    /// The directory holding the database, after following redirects.
+    pub path: PathBuf,
+    /// How the first directory of the chain was chosen.
+    pub source: BeadsDirSource,
+    /// The directories redirected away from, in order.
+    pub redirects: Vec<PathBuf>,
+}
+
+/// Resolve the `.beads/` directory to use.
+///
+/// In order of priority: `explicit` (`bd --db`), the `BEADS_DIR`
+/// environment variable, then the nearest `.beads/` walking up from
+/// `start`. The chosen directory's `redirect` file, if any, is followed
+/// (repeatedly) to the directory it names; relative targets are relative to
+/// the directory containing the `.beads/` holding the redirect.
+///
+/// Returns `Ok(None)` if no `.beads/` directory is found.
+///
+/// # Errors
+///
+/// Returns [`ConfigError::BeadsDirEnvInvalid`] if `BEADS_DIR` is set to
+/// something other than a directory, [`ConfigError::RedirectTargetMissing`]
+/// if a redirect points nowhere, and [`ConfigError::RedirectLoop`] if
+/// redirects form a cycle.
+pub fn resolve_beads_dir(
+    start: &Path,
+    explicit: Option<&Path>,
+) -> Result<Option<ResolvedBeadsDir>, ConfigError> {
+    let env_dir = std::env::var_os(BEADS_DIR_ENV).filter(|v| !v.is_empty());
+    let (dir, source) = if let Some(dir) = explicit {
+        (dir.to_path_buf(), BeadsDirSource::Flag)
+    } else if let Some(dir) = env_dir {
+        let dir = PathBuf::from(dir);
+        if !dir.is_dir() {
+            return Err(ConfigError::BeadsDirEnvInvalid(dir));
+        }
+        (dir, BeadsDirSource::Env)
+    } else {
+        match walk_up(start) {
+            Some(dir) => (dir, BeadsDirSource::Discovered),
+            None => return Ok(None),
+        }
+    };
+    let (path, redirects) = follow_redirects(dir)?;
+    Ok(Some(ResolvedBeadsDir {
+        path,
+        source,
+        redirects,
+    }))
+}
+
+/// Walk up the directory tree from `start` looking for a `.beads/` directory,
+/// following redirects.
 ///
 /// Returns the path to the `.beads/` directory if found, or `None` if the
-/// filesystem root is reached without finding one. The `BEADS_DIR`
-/// environment variable is checked first (highest priority).
+/// filesystem root is reached without finding one or resolution fails. The
+/// `BEADS_DIR` environment variable is checked first (highest priority); see
+/// [`resolve_beads_dir`].
 ///
 /// # Examples
 ///
@@ -32,20 +115,16 @@ const BEADS_DIR_ENV: &str = "BEADS_DIR";
 /// }
 /// ```
 pub fn find_beads_dir(start: &Path) -> Option<PathBuf> {
-    // 1. Check BEADS_DIR environment variable (highest priority).
-    if let Ok(env_dir) = std::env::var(BEADS_DIR_ENV) {
-        let env_path = PathBuf::from(&env_dir);
-        if env_path.is_dir() {
-            return Some(env_path);
-        }
-    }
+    resolve_beads_dir(start, None)
+        .ok()
+        .flatten()
+        .map(|r| r.path)
+}
 
-    // 2. Walk up from `start` looking for .beads/.
+/// The nearest `.beads/` directory at or above `start`.
+fn walk_up(start: &Path) -> Option<PathBuf> {
     // Canonicalize the start path so we get absolute paths.
-    let start = match start.canonicalize() {
-        Ok(p) => p,
-        Err(_) => return None,
-    };
+    let start = start.canonicalize().ok()?;
 
     let mut current = start.as_path();
     loop {
@@ -58,11 +137,58 @@ pub fn find_beads_dir(start: &Path) -> Option<PathBuf> {
             Some(parent) if parent != current => {
                 current = parent;
             }
-            _ => break, // Reached filesystem root.
+            _ => return None, // Reached filesystem root.
         }
     }
+}
 
-    None
+/// Follows `redirect` files from `dir`, returning the final directory and
+/// the ones redirected away from. Redirect targets are canonicalized, so a
+/// relative `../main/.beads` resolves to a clean absolute path.
+fn follow_redirects(dir: PathBuf) -> Result<(PathBuf, Vec<PathBuf>), ConfigError> {
+    let mut current = dir;
+    let mut redirects: Vec<PathBuf> = Vec::new();
+    let mut seen = vec![canonical(&current)];
+    while let Some(target) = read_redirect(&current)? {
+        let target = match current.parent() {
+            Some(base) if target.is_relative() => base.join(target),
+            _ => target,
+        };
+        if !target.is_dir() {
+            return Err(ConfigError::RedirectTargetMissing {
+                from: current,
+                target,
+            });
+        }
+        let key = canonical(&target);
+        redirects.push(current);
+        if seen.contains(&key) {
+            redirects.push(target);
+            return Err(ConfigError::RedirectLoop(redirects));
+        }
+        seen.push(key.clone());
+        current = key;
+    }
+    Ok((current, redirects))
+}
+
+/// The target of `dir/redirect`: its first line that is neither blank nor
+/// a `#` comment.
+fn read_redirect(dir: &Path) -> Result<Option<PathBuf>, ConfigError> {
+    let content = match std::fs::read_to_string(dir.join(REDIRECT_FILE)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(PathBuf::from))
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_path_buf())
 }
 
 /// Walk up the directory tree looking for `.beads/`, returning an error if
@@ -159,6 +285,54 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_resolve_follows_redirect_chain() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main").join(".beads");
+        let hop = dir.path().join("hop").join(".beads");
+        let wt = dir.path().join("wt").join(".beads");
+        for d in [&main, &hop, &wt] {
+            std::fs::create_dir_all(d).unwrap();
+        }
+        // Relative targets are relative to the worktree root.
+        std::fs::write(wt.join("redirect"), "# shared\n../hop/.beads\n").unwrap();
+        std::fs::write(hop.join("redirect"), "../main/.beads").unwrap();
+
+        // The result is canonical, without the `..` of the redirects.
+        let main = main.canonicalize().unwrap();
+        let resolved = resolve_beads_dir(&dir.path().join("wt"), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resolved.source, BeadsDirSource::Discovered);
+        assert_eq!(resolved.path, main);
+        assert_eq!(resolved.redirects.len(), 2);
+
+        let resolved = resolve_beads_dir(dir.path(), Some(&hop)).unwrap().unwrap();
+        assert_eq!(resolved.source, BeadsDirSource::Flag);
+        assert_eq!(resolved.path, main);
+    }
+
+    #[test]
+    fn test_resolve_detects_redirect_loops() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a");
+        let b = dir.path().join("b");
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+        std::fs::write(a.join("redirect"), b.display().to_string()).unwrap();
+        std::fs::write(b.join("redirect"), a.display().to_string()).unwrap();
+        assert!(matches!(
+            resolve_beads_dir(dir.path(), Some(&a)),
+            Err(ConfigError::RedirectLoop(chain)) if chain.len() == 3
+        ));
+
+        std::fs::write(b.join("redirect"), "missing").unwrap();
+        assert!(matches!(
+            resolve_beads_dir(dir.path(), Some(&a)),
+            Err(ConfigError::RedirectTargetMissing { .. })
+        ));
+    }
+
     #[test]
     fn test_ensure_beads_dir_creates() {
         let dir = tempfile::tempdir().unwrap();
@@ -8,7 +8,7 @@
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 // ---------------------------------------------------------------------------
@@ -30,6 +30,24 @@ pub enum ConfigError {
     #[error("no .beads directory found (run 'bd init' first)")]
     BeadsDirNotFound,
 
+    /// `BEADS_DIR` is set but does not name a directory.
+    #[error("BEADS_DIR is set to {}, which is not a directory", .0.display())]
+    BeadsDirEnvInvalid(PathBuf),
+
+    /// A `.beads/redirect` file points at something that is not a directory.
+    #[error("{} redirects to {}, which is not a directory", from.display(), target.display())]
+    RedirectTargetMissing {
+        /// The `.beads/` directory holding the redirect.
+        from: PathBuf,
+        /// Where the redirect points.
+        target: PathBuf,
+    },
+
+    /// A chain of `.beads/redirect` files leads back to a directory it
+    /// already passed.
+    #[error("redirect loop: {}", .0.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(" -> "))]
+    RedirectLoop(Vec<PathBuf>),
+
     /// A configuration value was invalid.
     #[error("invalid configuration value for key '{key}': {reason}")]
     InvalidValue {
@@ -352,6 +370,125 @@ pub struct StatusConfig {
     pub custom: String,
 }
 
+/// Wisp garbage collection configuration section.
+///
+/// TTLs are durations such as `30m`, `6h` or `7d`; a wisp is collected once
+/// it has been idle for longer than the TTL of its type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WispConfig {
+    /// TTL for wisp types without their own entry in `ttl`.
+    #[serde(default = "default_wisp_ttl", rename = "default-ttl")]
+    pub default_ttl: String,
+
+    /// TTL overrides keyed by wisp type (e.g. `heartbeat: 30m`).
+    #[serde(default)]
+    pub ttl: HashMap<String, String>,
+}
+
+impl Default for WispConfig {
+    fn default() -> Self {
+        Self {
+            default_ttl: default_wisp_ttl(),
+            ttl: HashMap::new(),
+        }
+    }
+}
+
+fn default_wisp_ttl() -> String {
+    "24h".to_string()
+}
+
+/// Built-in TTLs: liveness signals go quickly, reports and incidents stay
+/// around for a week.
+const BUILTIN_WISP_TTLS: &[(&str, &str)] = &[
+    ("heartbeat", "1h"),
+    ("ping", "1h"),
+    ("patrol", "24h"),
+    ("gc_report", "7d"),
+    ("recovery", "7d"),
+    ("error", "7d"),
+    ("escalation", "7d"),
+];
+
+impl WispConfig {
+    /// Returns the TTL for `wisp_type`: the configured override, else the
+    /// built-in TTL of a known type, else `default-ttl`.
+    pub fn ttl_for(&self, wisp_type: &str) -> &str {
+        if let Some(ttl) = self.ttl.get(wisp_type) {
+            return ttl;
+        }
+        BUILTIN_WISP_TTLS
+            .iter()
+            .find(|(name, _)| *name == wisp_type)
+            .map_or(self.default_ttl.as_str(), |(_, ttl)| ttl)
+    }
+}
+
+/// Gate configuration section.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GateConfig {
+    /// External evaluators keyed by await type: shell commands that read a
+    /// JSON request on stdin and answer with JSON on stdout.
+    #[serde(default)]
+    pub evaluators: HashMap<String, String>,
+
+    /// Allow `cmd:` gates, which run their await type as a shell command.
+    /// Only honored from the user config, the environment or a flag, never
+    /// from the shared project config.
+    #[serde(default, rename = "allow-cmd")]
+    pub allow_cmd: bool,
+
+    /// Allow `http:` gates, which fetch their await type's URL. Honored
+    /// like `allow-cmd`.
+    #[serde(default, rename = "allow-http")]
+    pub allow_http: bool,
+
+    /// Allow the external evaluators in `evaluators` to run. Honored like
+    /// `allow-cmd`.
+    #[serde(default, rename = "allow-external")]
+    pub allow_external: bool,
+}
+
+/// Agent liveness configuration section.
+///
+/// Timeouts are durations such as `15m` or `2h`; an agent that has not
+/// reported activity for longer than the timeout of its role type is
+/// considered dead by `bd agent reap`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentConfig {
+    /// Liveness timeout for role types without their own entry in
+    /// `liveness`.
+    #[serde(default = "default_liveness_timeout", rename = "liveness-timeout")]
+    pub liveness_timeout: String,
+
+    /// Liveness timeouts keyed by role type (e.g. `crew: 2h`).
+    #[serde(default)]
+    pub liveness: HashMap<String, String>,
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            liveness_timeout: default_liveness_timeout(),
+            liveness: HashMap::new(),
+        }
+    }
+}
+
+fn default_liveness_timeout() -> String {
+    "15m".to_string()
+}
+
+impl AgentConfig {
+    /// Returns the liveness timeout for `role_type`: the configured
+    /// override, else `liveness-timeout`.
+    pub fn timeout_for(&self, role_type: &str) -> &str {
+        self.liveness
+            .get(role_type)
+            .map_or(self.liveness_timeout.as_str(), String::as_str)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Main config struct
 // ---------------------------------------------------------------------------
@@ -453,6 +590,18 @@ pub struct BeadsConfig {
     /// External projects for cross-project dependency resolution.
     #[serde(default)]
     pub external_projects: HashMap<String, String>,
+
+    /// Wisp garbage collection configuration.
+    #[serde(default)]
+    pub wisp: WispConfig,
+
+    /// Gate configuration.
+    #[serde(default)]
+    pub gate: GateConfig,
+
+    /// Agent liveness configuration.
+    #[serde(default)]
+    pub agent: AgentConfig,
 }
 
 /// Directory label configuration section.
@@ -580,8 +729,10 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let beads_dir = dir.path().join(".beads");
 
-        let mut cfg = BeadsConfig::default();
-        cfg.prefix = Some("test-".to_string());
+        let mut cfg = BeadsConfig {
+            prefix: Some("test-".to_string()),
+            ..Default::default()
+        };
         cfg.types.custom = "epic, spike".to_string();
 
         save_config(&beads_dir, &cfg).unwrap();
@@ -609,6 +760,25 @@ mod tests {
         assert!(cfg.conflict.fields.is_empty());
     }
 
+    #[test]
+    fn test_wisp_ttls() {
+        let yaml = "wisp:\n  default-ttl: 2d\n  ttl:\n    heartbeat: 10m\n";
+        let cfg: BeadsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(cfg.wisp.ttl_for("heartbeat"), "10m");
+        assert_eq!(cfg.wisp.ttl_for("ping"), "1h");
+        assert_eq!(cfg.wisp.ttl_for(""), "2d");
+        assert_eq!(BeadsConfig::default().wisp.ttl_for("custom"), "24h");
+    }
+
+    #[test]
+    fn test_agent_liveness_timeouts() {
+        let yaml = "agent:\n  liveness-timeout: 5m\n  liveness:\n    crew: 2h\n";
+        let cfg: BeadsConfig = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(cfg.agent.timeout_for("crew"), "2h");
+        assert_eq!(cfg.agent.timeout_for("polecat"), "5m");
+        assert_eq!(BeadsConfig::default().agent.timeout_for(""), "15m");
+    }
+
     #[test]
     fn test_sync_config_defaults() {
         let cfg = BeadsConfig::default();
@@ -0,0 +1,415 @@
+//! Layered configuration resolution.
+//!
+//! A [`LayeredConfig`] merges, from lowest to highest precedence:
+//!
+//! 1. built-in defaults ([`BeadsConfig::default`])
+//! 2. the user config, `~/.config/beads/config.yaml` (or
+//!    `$XDG_CONFIG_HOME/beads/config.yaml`)
+//! 3. the project config, `.beads/config.yaml`
+//! 4. `BD_*` environment variables, named after the dotted key with dots and
+//!    dashes turned into underscores (`BD_HIERARCHY_MAX_DEPTH` sets
+//!    `hierarchy.max-depth`)
+//! 5. command-line flags
+//!
+//! YAML files are merged key by key, so a project file only needs the keys it
+//! changes. Every value remembers the layer it came from ([`ConfigOrigin`]).
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::config::{BeadsConfig, ConfigError, Result};
+
+/// Prefix of the environment variables that override config keys.
+const ENV_PREFIX: &str = "BD_";
+
+/// Where a configuration value came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigOrigin {
+    /// Built-in default.
+    Default,
+    /// The user config file at this path.
+    User(PathBuf),
+    /// The project config file at this path.
+    Project(PathBuf),
+    /// This environment variable.
+    Env(String),
+    /// This command-line flag.
+    Flag(String),
+}
+
+impl ConfigOrigin {
+    /// Short name of the layer: `default`, `user`, `project`, `env` or
+    /// `flag`.
+    pub fn layer(&self) -> &'static str {
+        match self {
+            ConfigOrigin::Default => "default",
+            ConfigOrigin::User(_) => "user",
+            ConfigOrigin::Project(_) => "project",
+            ConfigOrigin::Env(_) => "env",
+            ConfigOrigin::Flag(_) => "flag",
+        }
+    }
+}
+
+impl fmt::Display for ConfigOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::User(path) | ConfigOrigin::Project(path) => {
+                write!(f, "{}", path.display())
+            }
+            ConfigOrigin::Env(name) => write!(f, "env {}", name),
+            ConfigOrigin::Flag(name) => write!(f, "flag --{}", name),
+        }
+    }
+}
+
+/// The merged configuration and the origin of each value.
+#[derive(Debug, Clone)]
+pub struct LayeredConfig {
+    /// The effective configuration.
+    pub config: BeadsConfig,
+
+    /// Every leaf value by dotted key, with its origin.
+    values: BTreeMap<String, (Value, ConfigOrigin)>,
+}
+
+impl LayeredConfig {
+    /// Load all layers: defaults, the user config, `config.yaml` in
+    /// `beads_dir` (if any), `BD_*` environment variables and `flags`
+    /// (dotted key and value, e.g. `("actor", "alice")`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ReadError`] or [`ConfigError::ParseError`] if a
+    /// config file cannot be read or parsed, or if the merged values do not
+    /// fit [`BeadsConfig`], and [`ConfigError::InvalidValue`] if an
+    /// environment variable or flag does not fit its key.
+    pub fn load(beads_dir: Option<&Path>, flags: &[(&str, String)]) -> Result<Self> {
+        let project = beads_dir.map(|d| d.join("config.yaml"));
+        Self::from_sources(
+            user_config_path().as_deref(),
+            project.as_deref(),
+            std::env::vars(),
+            flags,
+        )
+    }
+
+    /// Merge the given layers; see [`LayeredConfig::load`].
+    fn from_sources(
+        user: Option<&Path>,
+        project: Option<&Path>,
+        env: impl IntoIterator<Item = (String, String)>,
+        flags: &[(&str, String)],
+    ) -> Result<Self> {
+        let defaults = serde_yaml::to_value(BeadsConfig::default())?;
+        let mut merged = Value::Mapping(Mapping::new());
+        let mut origins = BTreeMap::new();
+        overlay(
+            &mut merged,
+            &defaults,
+            "",
+            &ConfigOrigin::Default,
+            &mut origins,
+        );
+
+        let files = [
+            (user, ConfigOrigin::User as fn(PathBuf) -> ConfigOrigin),
+            (project, ConfigOrigin::Project),
+        ];
+        for (path, origin) in files {
+            let Some(path) = path else { continue };
+            if let Some(value) = read_yaml(path)? {
+                overlay(
+                    &mut merged,
+                    &value,
+                    "",
+                    &origin(path.to_path_buf()),
+                    &mut origins,
+                );
+            }
+        }
+
+        // Only keys the defaults know can be set from the environment, which
+        // also keeps unrelated BD_* variables out.
+        let mut known = Vec::new();
+        leaves(&defaults, "", &mut known);
+        let by_env: BTreeMap<String, (&str, &Value)> = known
+            .iter()
+            .map(|(key, value)| (env_name(key), (key.as_str(), *value)))
+            .collect();
+        let mut env: Vec<(String, String)> = env
+            .into_iter()
+            .filter(|(name, _)| by_env.contains_key(name))
+            .collect();
+        env.sort();
+        for (name, raw) in env {
+            let (key, default) = by_env[&name];
+            set(&mut merged, key, typed(key, &raw, default)?);
+            origins.insert(key.to_string(), ConfigOrigin::Env(name));
+        }
+
+        for (key, raw) in flags {
+            let default = known
+                .iter()
+                .find(|(k, _)| k == key)
+                .map_or(&Value::Null, |(_, v)| *v);
+            set(&mut merged, key, typed(key, raw, default)?);
+            origins.insert(key.to_string(), ConfigOrigin::Flag(key.to_string()));
+        }
+
+        let config: BeadsConfig = serde_yaml::from_value(merged.clone())?;
+        let mut values = Vec::new();
+        leaves(&merged, "", &mut values);
+        let values = values
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = origins.get(&key).cloned().unwrap_or(ConfigOrigin::Default);
+                (key, (value.clone(), origin))
+            })
+            .collect();
+        Ok(Self { config, values })
+    }
+
+    /// Where the value of the dotted `key` came from, if it is known.
+    pub fn origin(&self, key: &str) -> Option<&ConfigOrigin> {
+        self.values.get(key).map(|(_, origin)| origin)
+    }
+
+    /// Whether `key` was set on this machine -- by the user config, the
+    /// environment or a flag -- rather than by the default or the project
+    /// config, which is shared with everyone who can push to the repository.
+    pub fn is_local(&self, key: &str) -> bool {
+        matches!(
+            self.origin(key),
+            Some(ConfigOrigin::User(_) | ConfigOrigin::Env(_) | ConfigOrigin::Flag(_))
+        )
+    }
+
+    /// Every leaf value by dotted key, sorted, with its origin.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &Value, &ConfigOrigin)> {
+        self.values
+            .iter()
+            .map(|(key, (value, origin))| (key.as_str(), value, origin))
+    }
+}
+
+/// The user config file: `$XDG_CONFIG_HOME/beads/config.yaml`, else
+/// `~/.config/beads/config.yaml`.
+pub fn user_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .filter(|v| !v.is_empty())
+                .map(|home| PathBuf::from(home).join(".config"))
+        })?;
+    Some(base.join("beads").join("config.yaml"))
+}
+
+/// The environment variable overriding `key`: `hierarchy.max-depth` is
+/// `BD_HIERARCHY_MAX_DEPTH`.
+fn env_name(key: &str) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        key.to_uppercase().replace(['.', '-'], "_")
+    )
+}
+
+/// Reads a YAML file; `None` if it does not exist or is empty.
+fn read_yaml(path: &Path) -> Result<Option<Value>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_yaml::from_str(&content)?))
+}
+
+/// Merges `layer` into `base` key by key, recording `origin` for every leaf
+/// it sets.
+fn overlay(
+    base: &mut Value,
+    layer: &Value,
+    prefix: &str,
+    origin: &ConfigOrigin,
+    origins: &mut BTreeMap<String, ConfigOrigin>,
+) {
+    match (base, layer) {
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                let Some(name) = key.as_str() else { continue };
+                let path = join(prefix, name);
+                let slot = base.entry(key.clone()).or_insert(Value::Null);
+                if value.is_mapping() {
+                    if !slot.is_mapping() {
+                        *slot = Value::Mapping(Mapping::new());
+                    }
+                    overlay(slot, value, &path, origin, origins);
+                } else {
+                    *slot = value.clone();
+                    origins.insert(path, origin.clone());
+                }
+            }
+        }
+        (base, layer) => {
+            *base = layer.clone();
+            origins.insert(prefix.to_string(), origin.clone());
+        }
+    }
+}
+
+/// Collects the leaves of `value` as dotted keys. Empty mappings have none.
+fn leaves<'a>(value: &'a Value, prefix: &str, out: &mut Vec<(String, &'a Value)>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, value) in map {
+                if let Some(name) = key.as_str() {
+                    leaves(value, &join(prefix, name), out);
+                }
+            }
+        }
+        leaf => out.push((prefix.to_string(), leaf)),
+    }
+}
+
+/// Sets the dotted `key` in `root`, creating mappings on the way.
+fn set(root: &mut Value, key: &str, value: Value) {
+    let mut current = root;
+    for part in key.split('.') {
+        if !current.is_mapping() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(map) = current else {
+            unreachable!()
+        };
+        current = map
+            .entry(Value::String(part.to_string()))
+            .or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+/// Parses `raw` as the type of `default`: booleans and numbers stay typed,
+/// anything else is a string.
+fn typed(key: &str, raw: &str, default: &Value) -> Result<Value> {
+    let invalid = |expected: &str| ConfigError::InvalidValue {
+        key: key.to_string(),
+        reason: format!("expected {}, got '{}'", expected, raw),
+    };
+    match default {
+        Value::Bool(_) => match raw.trim().to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" | "" => Ok(Value::Bool(false)),
+            _ => Err(invalid("a boolean")),
+        },
+        Value::Number(_) => raw
+            .trim()
+            .parse::<u64>()
+            .map(|n| Value::Number(n.into()))
+            .map_err(|_| invalid("a number")),
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+fn join(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}.{}", prefix, name)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn origin_of<'a>(layered: &'a LayeredConfig, key: &str) -> &'a ConfigOrigin {
+        layered.origin(key).unwrap()
+    }
+
+    #[test]
+    fn test_layers_override_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        std::fs::write(
+            &user,
+            "create:\n  require-description: true\nhierarchy:\n  max-depth: 5\nactor: user\n",
+        )
+        .unwrap();
+        std::fs::write(&project, "hierarchy:\n  max-depth: 4\nactor: project\n").unwrap();
+        let env = vec![
+            ("BD_HIERARCHY_MAX_DEPTH".to_string(), "2".to_string()),
+            ("BD_ACTOR".to_string(), "env".to_string()),
+            ("BD_UNRELATED".to_string(), "x".to_string()),
+        ];
+        let flags = [("actor", "flag".to_string())];
+
+        let layered =
+            LayeredConfig::from_sources(Some(&user), Some(&project), env, &flags).unwrap();
+        assert!(layered.config.create.require_description);
+        assert_eq!(layered.config.hierarchy.max_depth, 2);
+        assert_eq!(layered.config.actor.as_deref(), Some("flag"));
+        // Keys only the defaults set keep them.
+        assert_eq!(layered.config.validation.on_create, "none");
+
+        assert_eq!(
+            origin_of(&layered, "create.require-description"),
+            &ConfigOrigin::User(user)
+        );
+        assert_eq!(
+            origin_of(&layered, "hierarchy.max-depth"),
+            &ConfigOrigin::Env("BD_HIERARCHY_MAX_DEPTH".to_string())
+        );
+        assert_eq!(
+            origin_of(&layered, "actor"),
+            &ConfigOrigin::Flag("actor".to_string())
+        );
+        assert_eq!(
+            origin_of(&layered, "validation.on-create"),
+            &ConfigOrigin::Default
+        );
+    }
+
+    #[test]
+    fn test_project_config_is_not_local() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user.yaml");
+        let project = dir.path().join("project.yaml");
+        std::fs::write(&user, "gate:\n  allow-http: true\n").unwrap();
+        std::fs::write(&project, "gate:\n  allow-cmd: true\n").unwrap();
+
+        let layered = LayeredConfig::from_sources(Some(&user), Some(&project), [], &[]).unwrap();
+        assert!(layered.config.gate.allow_cmd);
+        assert!(!layered.is_local("gate.allow-cmd"));
+        assert!(layered.is_local("gate.allow-http"));
+        assert!(!layered.is_local("hierarchy.max-depth"));
+
+        let env = vec![("BD_GATE_ALLOW_CMD".to_string(), "1".to_string())];
+        let layered = LayeredConfig::from_sources(None, Some(&project), env, &[]).unwrap();
+        assert!(layered.is_local("gate.allow-cmd"));
+    }
+
+    #[test]
+    fn test_env_values_must_fit_their_key() {
+        let env = vec![("BD_HIERARCHY_MAX_DEPTH".to_string(), "deep".to_string())];
+        let err = LayeredConfig::from_sources(None, None, env, &[]).unwrap_err();
+        assert!(
+            matches!(err, ConfigError::InvalidValue { ref key, .. } if key == "hierarchy.max-depth")
+        );
+        assert_eq!(env_name("sync.export_on"), "BD_SYNC_EXPORT_ON");
+    }
+}
@@ -6,10 +6,15 @@ use serde::{Deserialize, Serialize};
 use crate::enums::EventType;
 
 /// A comment on an issue.
+///
+/// `id` is a local row ID; it is omitted when zero so that JSONL exports do
+/// not differ between clones.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Comment {
+    #[serde(default, skip_serializing_if = "is_zero_id")]
     pub id: i64,
 
+    #[serde(default)]
     pub issue_id: String,
 
     pub author: String,
@@ -19,6 +24,10 @@ pub struct Comment {
     pub created_at: DateTime<Utc>,
 }
 
+fn is_zero_id(id: &i64) -> bool {
+    *id == 0
+}
+
 /// An audit trail entry.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Event {
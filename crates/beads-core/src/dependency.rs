@@ -3,7 +3,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
-use crate::enums::DependencyType;
+use crate::enums::{DependencyType, Status};
 
 /// Represents a relationship between issues.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -112,6 +112,12 @@ pub fn is_failure_close(close_reason: &str) -> bool {
     FAILURE_CLOSE_KEYWORDS.iter().any(|kw| lower.contains(kw))
 }
 
+/// Returns `true` if a conditional-blocks dependent will never run: its
+/// blocker closed, and not with a failure.
+pub fn is_condition_skipped(blocker_status: &Status, blocker_close_reason: &str) -> bool {
+    *blocker_status == Status::Closed && !is_failure_close(blocker_close_reason)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -146,6 +152,13 @@ mod tests {
         assert!(!is_failure_close("Completed successfully"));
     }
 
+    #[test]
+    fn conditions_skip_on_success() {
+        assert!(is_condition_skipped(&Status::Closed, "Done"));
+        assert!(!is_condition_skipped(&Status::Closed, "Build failed"));
+        assert!(!is_condition_skipped(&Status::Open, ""));
+    }
+
     #[test]
     fn waits_for_meta_serde() {
         let m = WaitsForMeta {
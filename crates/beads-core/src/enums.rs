@@ -372,7 +372,9 @@ define_enum! {
 // ===========================================================================
 
 define_enum! {
-    /// Determines how ready work is ordered.
+    /// Determines how ready work is ordered: `hybrid` (priority tiers that
+    /// aged issues climb, up to two, issues unblocking the most work first),
+    /// `priority`, or `oldest`.
     SortPolicy, default = Hybrid, custom_variant = Custom,
     variants: [
         (Hybrid, "hybrid"),
@@ -138,6 +138,8 @@ pub struct WorkFilter {
 pub struct StaleFilter {
     /// Issues not updated in this many days.
     pub days: i32,
+    /// Issues not updated since this time; takes precedence over `days`.
+    pub updated_before: Option<DateTime<Utc>>,
     /// Filter by status (open|in_progress|blocked), empty = all non-closed.
     pub status: Option<String>,
     /// Maximum issues to return.
@@ -148,6 +150,7 @@ impl Default for StaleFilter {
     fn default() -> Self {
         Self {
             days: 30,
+            updated_before: None,
             status: None,
             limit: None,
         }
@@ -4,6 +4,8 @@
 
 use std::io::{self, BufRead, Write};
 
+use chrono::{DateTime, SubsecRound, Utc};
+
 use crate::issue::Issue;
 
 /// Error type for JSONL operations.
@@ -35,6 +37,66 @@ pub fn write_jsonl<W: Write>(writer: &mut W, issues: &[Issue]) -> Result<()> {
     Ok(())
 }
 
+/// Puts an issue into canonical export form so that identical data always
+/// serializes to identical bytes.
+///
+/// - Timestamps are truncated to millisecond precision (what SQLite stores).
+/// - Labels are sorted and deduplicated; dependencies are sorted by target
+///   and type; comments by creation time.
+/// - Comment row IDs are cleared (they are local to each database).
+/// - Metadata is re-serialized with sorted keys.
+pub fn canonicalize(issue: &mut Issue) {
+    fn ms(t: &mut DateTime<Utc>) {
+        *t = t.trunc_subsecs(3);
+    }
+    fn ms_opt(t: &mut Option<DateTime<Utc>>) {
+        if let Some(t) = t {
+            ms(t);
+        }
+    }
+
+    ms(&mut issue.created_at);
+    ms(&mut issue.updated_at);
+    ms_opt(&mut issue.closed_at);
+    ms_opt(&mut issue.compacted_at);
+    ms_opt(&mut issue.last_activity);
+    ms_opt(&mut issue.due_at);
+    ms_opt(&mut issue.defer_until);
+
+    issue.labels.sort();
+    issue.labels.dedup();
+
+    for dep in &mut issue.dependencies {
+        ms(&mut dep.created_at);
+        if dep.issue_id.is_empty() {
+            dep.issue_id = issue.id.clone();
+        }
+    }
+    issue.dependencies.sort_by(|a, b| {
+        (&a.depends_on_id, a.dep_type.as_str()).cmp(&(&b.depends_on_id, b.dep_type.as_str()))
+    });
+
+    for comment in &mut issue.comments {
+        ms(&mut comment.created_at);
+        comment.id = 0;
+        if comment.issue_id.is_empty() {
+            comment.issue_id = issue.id.clone();
+        }
+    }
+    issue.comments.sort_by(|a, b| {
+        (a.created_at, &a.author, &a.text).cmp(&(b.created_at, &b.author, &b.text))
+    });
+
+    if let Some(ref meta) = issue.metadata {
+        // serde_json::Value maps are ordered, so a round-trip sorts the keys.
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(meta.get()) {
+            if let Ok(raw) = serde_json::value::to_raw_value(&value) {
+                issue.metadata = Some(raw);
+            }
+        }
+    }
+}
+
 /// Returns an iterator that reads issues from a JSONL reader.
 ///
 /// Each line is parsed as a JSON object. Empty lines are skipped.
@@ -124,6 +186,45 @@ mod tests {
         assert_eq!(results.len(), 2);
     }
 
+    #[test]
+    fn canonicalize_is_stable() {
+        use crate::comment::Comment;
+
+        let t = "2024-05-06T07:08:09.123456789Z"
+            .parse::<DateTime<Utc>>()
+            .unwrap();
+        let comment = |text: &str, id: i64| Comment {
+            id,
+            issue_id: String::new(),
+            author: "alice".into(),
+            text: text.into(),
+            created_at: t,
+        };
+        let mut issue = IssueBuilder::new("A")
+            .id("bd-1")
+            .created_at(t)
+            .updated_at(t)
+            .labels(vec!["b".into(), "a".into(), "b".into()])
+            .build();
+        issue.comments = vec![comment("second", 7), comment("first", 3)];
+        issue.metadata =
+            Some(serde_json::value::RawValue::from_string(r#"{"z":1, "a":2}"#.into()).unwrap());
+
+        canonicalize(&mut issue);
+
+        assert_eq!(issue.labels, vec!["a", "b"]);
+        assert_eq!(issue.created_at.timestamp_subsec_nanos(), 123_000_000);
+        assert_eq!(issue.comments[0].text, "first");
+        assert_eq!(issue.comments[0].issue_id, "bd-1");
+        assert_eq!(issue.metadata.as_ref().unwrap().get(), r#"{"a":2,"z":1}"#);
+
+        let line = serde_json::to_string(&issue).unwrap();
+        assert!(!line.contains("\"id\":7"));
+        let mut again = issue.clone();
+        canonicalize(&mut again);
+        assert_eq!(serde_json::to_string(&again).unwrap(), line);
+    }
+
     #[test]
     fn read_reports_line_number_on_error() {
         let data = b"{\"title\":\"A\"}\nnot-json\n";
@@ -0,0 +1,520 @@
+//! Step condition expressions.
+//!
+//! ```text
+//! expr     := and ("||" and)*
+//! and      := unary ("&&" unary)*
+//! unary    := "!" unary | "(" expr ")" | test
+//! test     := operand [cmp operand | ["not"] "in" list | ("=~" | "!~") operand]
+//! cmp      := "==" | "!=" | "<" | "<=" | ">" | ">="
+//! list     := "[" [operand ("," operand)*] "]"
+//! operand  := "{{var}}" | number | "quoted" | 'quoted' | bare-word
+//! ```
+//!
+//! A lone operand is truthy unless it is empty, `false` or `0`. `<`, `<=`,
+//! `>` and `>=` compare numbers; `==` and `!=` compare numerically when both
+//! sides are numbers and as text otherwise. `=~` matches a regular
+//! expression anywhere in the left side. Bare words run until whitespace or
+//! an operator, so `{{type}} == major` needs no quotes.
+
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use crate::types::FormulaError;
+
+/// A parsed condition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Or(Box<Condition>, Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+    /// Truthiness of a single operand.
+    Truthy(Operand),
+    Compare(Operand, CmpOp, Operand),
+    /// `lhs in [..]`, or `lhs not in [..]` when `negated`.
+    In {
+        lhs: Operand,
+        list: Vec<Operand>,
+        negated: bool,
+    },
+    /// `lhs =~ pattern`, or `lhs !~ pattern` when `negated`.
+    Matches {
+        lhs: Operand,
+        pattern: Operand,
+        negated: bool,
+    },
+}
+
+/// Comparison operators.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A value in a condition.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// `{{name}}`.
+    Var(String),
+    /// A quoted string, bare word or number.
+    Literal(String),
+}
+
+impl Condition {
+    /// Parses a condition.
+    pub fn parse(input: &str) -> Result<Condition, FormulaError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            input,
+            tokens,
+            pos: 0,
+        };
+        let cond = parser.or()?;
+        if let Some(tok) = parser.tokens.get(parser.pos) {
+            return Err(parser.error(&format!("unexpected '{}'", tok.text())));
+        }
+        Ok(cond)
+    }
+
+    /// Evaluates the condition. Referencing a variable missing from `vars`
+    /// is an error.
+    pub fn evaluate(&self, vars: &HashMap<String, String>) -> Result<bool, FormulaError> {
+        Ok(match self {
+            Condition::Or(a, b) => a.evaluate(vars)? || b.evaluate(vars)?,
+            Condition::And(a, b) => a.evaluate(vars)? && b.evaluate(vars)?,
+            Condition::Not(c) => !c.evaluate(vars)?,
+            Condition::Truthy(op) => truthy(op.value(vars)?),
+            Condition::Compare(lhs, op, rhs) => compare(lhs.value(vars)?, *op, rhs.value(vars)?)?,
+            Condition::In { lhs, list, negated } => {
+                let value = lhs.value(vars)?;
+                let mut found = false;
+                for item in list {
+                    if equal(value, item.value(vars)?) {
+                        found = true;
+                        break;
+                    }
+                }
+                found != *negated
+            }
+            Condition::Matches {
+                lhs,
+                pattern,
+                negated,
+            } => {
+                let pattern = pattern.value(vars)?;
+                let re = Regex::new(pattern).map_err(|e| {
+                    FormulaError::InvalidCondition(format!("bad pattern '{}': {}", pattern, e))
+                })?;
+                re.is_match(lhs.value(vars)?) != *negated
+            }
+        })
+    }
+}
+
+impl Operand {
+    fn value<'a>(&'a self, vars: &'a HashMap<String, String>) -> Result<&'a str, FormulaError> {
+        match self {
+            Operand::Literal(s) => Ok(s),
+            Operand::Var(name) => vars
+                .get(name)
+                .map(String::as_str)
+                .ok_or_else(|| FormulaError::UnknownVariable(name.clone())),
+        }
+    }
+}
+
+/// Truthiness of a value.
+pub(crate) fn truthy(value: &str) -> bool {
+    !value.is_empty() && value != "false" && value != "0"
+}
+
+fn equal(a: &str, b: &str) -> bool {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(x), Ok(y)) => x == y,
+        _ => a == b,
+    }
+}
+
+fn compare(lhs: &str, op: CmpOp, rhs: &str) -> Result<bool, FormulaError> {
+    match op {
+        CmpOp::Eq => return Ok(equal(lhs, rhs)),
+        CmpOp::Ne => return Ok(!equal(lhs, rhs)),
+        _ => {}
+    }
+    let number = |s: &str| {
+        s.trim()
+            .parse::<f64>()
+            .map_err(|_| FormulaError::InvalidCondition(format!("'{}' is not a number", s)))
+    };
+    let (x, y) = (number(lhs)?, number(rhs)?);
+    Ok(match op {
+        CmpOp::Lt => x < y,
+        CmpOp::Le => x <= y,
+        CmpOp::Gt => x > y,
+        _ => x >= y,
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Or,
+    And,
+    Not,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+    Cmp(CmpOp),
+    Match(bool),
+    /// `in`; `not in` is `Word("not")` followed by `In`.
+    In,
+    Var(String),
+    /// A quoted string (never a keyword).
+    Str(String),
+    Word(String),
+}
+
+impl Tok {
+    fn text(&self) -> String {
+        match self {
+            Tok::Or => "||".into(),
+            Tok::And => "&&".into(),
+            Tok::Not => "!".into(),
+            Tok::LParen => "(".into(),
+            Tok::RParen => ")".into(),
+            Tok::LBracket => "[".into(),
+            Tok::RBracket => "]".into(),
+            Tok::Comma => ",".into(),
+            Tok::Cmp(op) => match op {
+                CmpOp::Eq => "==",
+                CmpOp::Ne => "!=",
+                CmpOp::Lt => "<",
+                CmpOp::Le => "<=",
+                CmpOp::Gt => ">",
+                CmpOp::Ge => ">=",
+            }
+            .into(),
+            Tok::Match(negated) => if *negated { "!~" } else { "=~" }.into(),
+            Tok::In => "in".into(),
+            Tok::Var(name) => format!("{{{{{name}}}}}"),
+            Tok::Str(s) => format!("\"{s}\""),
+            Tok::Word(w) => w.clone(),
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Tok>, FormulaError> {
+    let err = |msg: String| FormulaError::InvalidCondition(format!("{} in '{}'", msg, input));
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let two = |tok: Tok, tokens: &mut Vec<Tok>| {
+            tokens.push(tok);
+            2
+        };
+        let step = match (c, next) {
+            (c, _) if c.is_whitespace() => 1,
+            ('|', Some('|')) => two(Tok::Or, &mut tokens),
+            ('&', Some('&')) => two(Tok::And, &mut tokens),
+            ('=', Some('=')) => two(Tok::Cmp(CmpOp::Eq), &mut tokens),
+            ('!', Some('=')) => two(Tok::Cmp(CmpOp::Ne), &mut tokens),
+            ('<', Some('=')) => two(Tok::Cmp(CmpOp::Le), &mut tokens),
+            ('>', Some('=')) => two(Tok::Cmp(CmpOp::Ge), &mut tokens),
+            ('=', Some('~')) => two(Tok::Match(false), &mut tokens),
+            ('!', Some('~')) => two(Tok::Match(true), &mut tokens),
+            ('{', Some('{')) => {
+                let close = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '}' && chars[j + 1] == '}')
+                    .ok_or_else(|| err("unclosed '{{'".into()))?;
+                let name: String = chars[i + 2..close].iter().collect();
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err(err("empty variable reference".into()));
+                }
+                tokens.push(Tok::Var(name.to_string()));
+                close + 2 - i
+            }
+            ('<', _) => {
+                tokens.push(Tok::Cmp(CmpOp::Lt));
+                1
+            }
+            ('>', _) => {
+                tokens.push(Tok::Cmp(CmpOp::Gt));
+                1
+            }
+            ('!', _) => {
+                tokens.push(Tok::Not);
+                1
+            }
+            ('(', _) | (')', _) | ('[', _) | (']', _) | (',', _) => {
+                tokens.push(match c {
+                    '(' => Tok::LParen,
+                    ')' => Tok::RParen,
+                    '[' => Tok::LBracket,
+                    ']' => Tok::RBracket,
+                    _ => Tok::Comma,
+                });
+                1
+            }
+            ('"', _) | ('\'', _) => {
+                let mut s = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => return Err(err("unterminated string".into())),
+                        Some(&ch) if ch == c => break,
+                        Some('\\') if chars.get(j + 1).is_some() => {
+                            s.push(chars[j + 1]);
+                            j += 2;
+                        }
+                        Some(&ch) => {
+                            s.push(ch);
+                            j += 1;
+                        }
+                    }
+                }
+                tokens.push(Tok::Str(s));
+                j + 1 - i
+            }
+            _ => {
+                let mut j = i;
+                while j < chars.len() && !ends_word(&chars, j) {
+                    j += 1;
+                }
+                if j == i {
+                    return Err(err(format!("unexpected '{}'", c)));
+                }
+                let word: String = chars[i..j].iter().collect();
+                tokens.push(if word == "in" {
+                    Tok::In
+                } else {
+                    Tok::Word(word)
+                });
+                j - i
+            }
+        };
+        i += step;
+    }
+    Ok(tokens)
+}
+
+/// Whether a bare word stops at `chars[j]`.
+fn ends_word(chars: &[char], j: usize) -> bool {
+    let c = chars[j];
+    let next = chars.get(j + 1).copied();
+    c.is_whitespace()
+        || matches!(c, '(' | ')' | '[' | ']' | ',' | '<' | '>' | '"' | '\'')
+        || matches!(
+            (c, next),
+            ('|', Some('|'))
+                | ('&', Some('&'))
+                | ('=', Some('=' | '~'))
+                | ('!', Some('=' | '~'))
+                | ('{', Some('{'))
+        )
+}
+
+// ---------------------------------------------------------------------------
+// Parser
+// ---------------------------------------------------------------------------
+
+struct Parser<'a> {
+    input: &'a str,
+    tokens: Vec<Tok>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: &str) -> FormulaError {
+        FormulaError::InvalidCondition(format!("{} in '{}'", msg, self.input))
+    }
+
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, tok: &Tok) -> bool {
+        if self.peek() == Some(tok) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Condition, FormulaError> {
+        let mut lhs = self.and()?;
+        while self.eat(&Tok::Or) {
+            lhs = Condition::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Condition, FormulaError> {
+        let mut lhs = self.unary()?;
+        while self.eat(&Tok::And) {
+            lhs = Condition::And(Box::new(lhs), Box::new(self.unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Condition, FormulaError> {
+        if self.eat(&Tok::Not) {
+            return Ok(Condition::Not(Box::new(self.unary()?)));
+        }
+        if self.eat(&Tok::LParen) {
+            let inner = self.or()?;
+            if !self.eat(&Tok::RParen) {
+                return Err(self.error("expected ')'"));
+            }
+            return Ok(inner);
+        }
+        self.test()
+    }
+
+    fn test(&mut self) -> Result<Condition, FormulaError> {
+        let lhs = self.operand()?;
+        match self.peek().cloned() {
+            Some(Tok::Cmp(op)) => {
+                self.pos += 1;
+                Ok(Condition::Compare(lhs, op, self.operand()?))
+            }
+            Some(Tok::Match(negated)) => {
+                self.pos += 1;
+                Ok(Condition::Matches {
+                    lhs,
+                    pattern: self.operand()?,
+                    negated,
+                })
+            }
+            Some(Tok::In) => {
+                self.pos += 1;
+                Ok(Condition::In {
+                    lhs,
+                    list: self.list()?,
+                    negated: false,
+                })
+            }
+            Some(Tok::Word(ref w))
+                if w == "not" && self.tokens.get(self.pos + 1) == Some(&Tok::In) =>
+            {
+                self.pos += 2;
+                Ok(Condition::In {
+                    lhs,
+                    list: self.list()?,
+                    negated: true,
+                })
+            }
+            _ => Ok(Condition::Truthy(lhs)),
+        }
+    }
+
+    fn list(&mut self) -> Result<Vec<Operand>, FormulaError> {
+        if !self.eat(&Tok::LBracket) {
+            return Err(self.error("expected '[' after 'in'"));
+        }
+        let mut items = Vec::new();
+        if self.eat(&Tok::RBracket) {
+            return Ok(items);
+        }
+        loop {
+            items.push(self.operand()?);
+            if self.eat(&Tok::RBracket) {
+                return Ok(items);
+            }
+            if !self.eat(&Tok::Comma) {
+                return Err(self.error("expected ',' or ']' in list"));
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, FormulaError> {
+        let tok = self.peek().cloned();
+        let operand = match tok {
+            Some(Tok::Var(name)) => Operand::Var(name),
+            Some(Tok::Str(s)) | Some(Tok::Word(s)) => Operand::Literal(s),
+            Some(other) => return Err(self.error(&format!("unexpected '{}'", other.text()))),
+            None => return Err(self.error("unexpected end of condition")),
+        };
+        self.pos += 1;
+        Ok(operand)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn eval(cond: &str, pairs: &[(&str, &str)]) -> Result<bool, FormulaError> {
+        Condition::parse(cond)?.evaluate(&vars(pairs))
+    }
+
+    #[test]
+    fn boolean_logic_and_lists() {
+        let v = [("type", "feature"), ("risk", "high")];
+        let cond = "{{type}} == feature && {{risk}} in [high, critical]";
+        assert!(eval(cond, &v).unwrap());
+        assert!(!eval(cond, &[("type", "feature"), ("risk", "low")]).unwrap());
+        assert!(eval("{{risk}} not in ['low', \"medium\"]", &v).unwrap());
+        assert!(
+            eval(
+                "{{type}} == bug || ({{risk}} == high && !{{skip}})",
+                &[("type", "feature"), ("risk", "high"), ("skip", "false"),]
+            )
+            .unwrap()
+        );
+        // && binds tighter than ||.
+        assert!(eval("1 || 0 && 0", &[]).unwrap());
+    }
+
+    #[test]
+    fn numbers_and_patterns() {
+        let v = [("replicas", "3"), ("branch", "release/2.1")];
+        assert!(eval("{{replicas}} >= 3", &v).unwrap());
+        assert!(!eval("{{replicas}} > 3", &v).unwrap());
+        assert!(eval("{{replicas}} == 3.0", &v).unwrap());
+        assert!(eval("{{branch}} =~ '^release/'", &v).unwrap());
+        assert!(eval("{{branch}} !~ hotfix", &v).unwrap());
+    }
+
+    #[test]
+    fn errors_are_reported_not_false() {
+        assert!(matches!(
+            eval("{{branch}} > 3", &[("branch", "main")]),
+            Err(FormulaError::InvalidCondition(ref m)) if m.contains("not a number")
+        ));
+        assert!(matches!(
+            eval("{{missing}}", &[]),
+            Err(FormulaError::UnknownVariable(ref v)) if v == "missing"
+        ));
+        for bad in [
+            "(a == b", "a ==", "a in b", "a == b)", "'open", "{{x", "a =~ '('",
+        ] {
+            let result = eval(bad, &[]);
+            assert!(
+                matches!(result, Err(FormulaError::InvalidCondition(_))),
+                "{bad}: {result:?}"
+            );
+        }
+    }
+}
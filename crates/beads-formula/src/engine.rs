@@ -2,7 +2,8 @@
 
 use std::collections::{HashMap, HashSet};
 
-use crate::types::{CookedStep, Formula, FormulaError};
+use crate::condition::Condition;
+use crate::types::{CookedStep, Formula, FormulaError, Step};
 
 /// Substitute `{{variable}}` patterns in a string with provided values.
 /// Unresolved variables are left as-is.
@@ -38,7 +39,8 @@ pub fn substitute_vars(text: &str, vars: &HashMap<String, String>) -> String {
 }
 
 /// Extract all `{{variable}}` names referenced in a formula's steps
-/// (titles, descriptions, assignees, and conditions).
+/// (titles, descriptions, assignees, conditions, and `for_each` lists).
+/// The loop variables `item` and `index` are left out when a step loops.
 pub fn extract_variables(formula: &Formula) -> Vec<String> {
     let mut vars: HashSet<String> = HashSet::new();
     for step in &formula.steps {
@@ -50,13 +52,22 @@ pub fn extract_variables(formula: &Formula) -> Vec<String> {
         if let Some(ref c) = step.condition {
             scan_vars(c, &mut vars);
         }
+        if let Some(ref list) = step.for_each {
+            scan_vars(list, &mut vars);
+        }
+    }
+    // Loop variables are bound while cooking, not provided
+    if formula.steps.iter().any(|s| s.for_each.is_some()) {
+        vars.remove("item");
+        vars.remove("index");
     }
     let mut result: Vec<String> = vars.into_iter().collect();
     result.sort();
     result
 }
 
-/// Validate that all required variables are provided.
+/// Validate that all required variables are provided and that provided
+/// values and defaults fit their declared types.
 /// Missing required vars with no default trigger an error.
 pub fn validate_vars(
     formula: &Formula,
@@ -66,6 +77,16 @@ pub fn validate_vars(
         if def.required && !provided.contains_key(name) {
             return Err(FormulaError::MissingVariable(name.clone()));
         }
+        for value in [provided.get(name), def.default.as_ref()]
+            .into_iter()
+            .flatten()
+        {
+            def.check(value)
+                .map_err(|reason| FormulaError::InvalidVariable {
+                    name: name.clone(),
+                    reason,
+                })?;
+        }
     }
     Ok(())
 }
@@ -88,51 +109,69 @@ pub fn resolve_vars(
     vars
 }
 
-/// Evaluate a simple condition string against variables.
+/// Evaluate a condition string against variables.
 ///
-/// Supported formats:
-/// - `"{{var}}"` -- truthy (non-empty and not "false"/"0")
-/// - `"!{{var}}"` -- negated truthy
-/// - `"{{var}} == value"` -- equality
-/// - `"{{var}} != value"` -- inequality
+/// See [`crate::condition`] for the grammar: `&&`, `||`, `!`, parentheses,
+/// comparisons, `in` lists and regex matches over `{{var}}` references and
+/// literals. An empty condition passes. Malformed conditions fail with
+/// [`FormulaError::InvalidCondition`] and references to variables missing
+/// from `vars` with [`FormulaError::UnknownVariable`].
 ///
 /// Returns `true` if the condition passes (step should be included).
-pub fn evaluate_condition(condition: &str, vars: &HashMap<String, String>) -> bool {
-    let cond = condition.trim();
-    if cond.is_empty() {
-        return true;
-    }
-
-    // Check for == or !=
-    if let Some(pos) = cond.find("!=") {
-        let lhs = substitute_vars(cond[..pos].trim(), vars);
-        let rhs = cond[pos + 2..].trim();
-        return lhs != rhs;
-    }
-    if let Some(pos) = cond.find("==") {
-        let lhs = substitute_vars(cond[..pos].trim(), vars);
-        let rhs = cond[pos + 2..].trim();
-        return lhs == rhs;
+pub fn evaluate_condition(
+    condition: &str,
+    vars: &HashMap<String, String>,
+) -> Result<bool, FormulaError> {
+    if condition.trim().is_empty() {
+        return Ok(true);
     }
-
-    // Truthy / negated truthy
-    let (negated, expr) = if let Some(stripped) = cond.strip_prefix('!') {
-        (true, stripped.trim())
-    } else {
-        (false, cond)
-    };
-
-    let resolved = substitute_vars(expr, vars);
-    let truthy = !resolved.is_empty() && resolved != "false" && resolved != "0";
-    if negated { !truthy } else { truthy }
+    Condition::parse(condition)?.evaluate(vars)
 }
 
 /// Cook a formula: validate variables, evaluate conditions, substitute, and filter.
 ///
-/// Returns the list of steps that should be created as issues.
+/// Returns the list of steps that should be created as issues. A
+/// `for_each` step becomes one step per item, `<step id>.<index>`, each
+/// marked `spawned_by` the step; steps needing it need every copy. Formulas
+/// that expand other formulas or weave aspects need [`cook_with`].
 pub fn cook(
     formula: &Formula,
     provided: &HashMap<String, String>,
+) -> Result<Vec<CookedStep>, FormulaError> {
+    cook_with(formula, provided, &|name: &str| {
+        Err(FormulaError::Composition(format!(
+            "cannot load formula '{}' without a loader",
+            name
+        )))
+    })
+}
+
+/// Cook a formula, loading the formulas it expands and the aspects it
+/// weaves by name with `load`.
+///
+/// An `expand` step is replaced by the cooked steps of its expansion
+/// formula, with IDs prefixed by `<step id>.`: the expansion's first steps
+/// inherit the step's `needs`, and steps needing it wait on the expansion's
+/// last steps. Each aspect listed in `aspects` is then woven before or after
+/// every step its pointcut selects. Fails with
+/// [`FormulaError::IncludeCycle`] if a formula includes itself.
+pub fn cook_with(
+    formula: &Formula,
+    provided: &HashMap<String, String>,
+    load: &dyn Fn(&str) -> Result<Formula, FormulaError>,
+) -> Result<Vec<CookedStep>, FormulaError> {
+    let mut chain = vec![formula.formula.clone()];
+    cook_scoped(formula, provided, load, &mut chain, "")
+}
+
+/// Cooks `formula` with step IDs prefixed by `prefix`; `chain` holds the
+/// names of the formulas currently being cooked, outermost first.
+fn cook_scoped(
+    formula: &Formula,
+    provided: &HashMap<String, String>,
+    load: &dyn Fn(&str) -> Result<Formula, FormulaError>,
+    chain: &mut Vec<String>,
+    prefix: &str,
 ) -> Result<Vec<CookedStep>, FormulaError> {
     // 1. Validate required variables
     validate_vars(formula, provided)?;
@@ -140,18 +179,81 @@ pub fn cook(
     // 2. Build full variable map (defaults + provided)
     let vars = resolve_vars(formula, provided);
 
-    // 3. Evaluate conditions and collect surviving step IDs
+    // 3. Evaluate conditions and collect surviving step IDs; declared
+    //    variables without a value compare as empty. `for_each` steps keep
+    //    the items whose condition passes, in their original positions
+    let mut cond_vars = vars.clone();
+    for name in formula.vars.keys() {
+        cond_vars.entry(name.clone()).or_default();
+    }
     let mut included_ids: HashSet<String> = HashSet::new();
+    let mut fanned: HashMap<&str, Vec<(usize, String)>> = HashMap::new();
     for step in &formula.steps {
-        if let Some(ref cond) = step.condition {
-            if !evaluate_condition(cond, &vars) {
+        if let Some(ref list) = step.for_each {
+            if step.expand.is_some() {
+                return Err(FormulaError::Composition(format!(
+                    "step '{}' cannot use both expand and for_each",
+                    step.id
+                )));
+            }
+            let mut kept = Vec::new();
+            for (index, item) in loop_items(list, &cond_vars)?.into_iter().enumerate() {
+                if step_passes(step, &bind_item(&cond_vars, &item, index))? {
+                    kept.push((index, item));
+                }
+            }
+            if kept.is_empty() {
                 continue;
             }
+            fanned.insert(step.id.as_str(), kept);
+        } else if !step_passes(step, &cond_vars)? {
+            continue;
         }
         included_ids.insert(step.id.clone());
     }
 
-    // 4. Build cooked steps, filtering out deps that reference removed steps
+    // 4. Cook expansions; remember where each one starts and ends so edges
+    //    to the expanding step can be redirected
+    let mut expanded: HashMap<&str, Vec<CookedStep>> = HashMap::new();
+    for step in &formula.steps {
+        let Some(ref name) = step.expand else {
+            continue;
+        };
+        if !included_ids.contains(&step.id) {
+            continue;
+        }
+        let child = enter(name, "expansion", load, chain)?;
+        let bindings: HashMap<String, String> = step
+            .with
+            .iter()
+            .map(|(k, v)| (k.clone(), substitute_vars(v, &vars)))
+            .collect();
+        let steps = cook_scoped(
+            &child,
+            &bindings,
+            load,
+            chain,
+            &format!("{prefix}{}.", step.id),
+        )?;
+        chain.pop();
+        expanded.insert(step.id.as_str(), steps);
+    }
+
+    // Cooked IDs a dependent of `id` has to wait for.
+    let exits = |id: &str| -> Vec<String> {
+        if let Some(steps) = expanded.get(id) {
+            return leaves(steps);
+        }
+        if let Some(items) = fanned.get(id) {
+            return items
+                .iter()
+                .map(|(index, _)| format!("{prefix}{id}.{index}"))
+                .collect();
+        }
+        vec![format!("{prefix}{id}")]
+    };
+
+    // 5. Build cooked steps, filtering out deps that reference removed steps
     let mut cooked = Vec::new();
     for step in &formula.steps {
         if !included_ids.contains(&step.id) {
@@ -162,25 +264,274 @@ pub fn cook(
             .needs
             .iter()
             .filter(|dep| included_ids.contains(dep.as_str()))
-            .cloned()
+            .flat_map(|dep| exits(dep))
             .collect();
 
-        cooked.push(CookedStep {
-            id: step.id.clone(),
-            title: substitute_vars(&step.title, &vars),
-            description: substitute_vars(&step.description, &vars),
-            issue_type: step.r#type.clone(),
-            priority: step.priority,
+        if let Some(steps) = expanded.get(step.id.as_str()) {
+            let roots = roots(steps);
+            for mut inner in steps.iter().cloned() {
+                if roots.contains(&inner.id) {
+                    inner.needs.extend(needs.iter().cloned());
+                }
+                cooked.push(inner);
+            }
+            continue;
+        }
+
+        if let Some(items) = fanned.get(step.id.as_str()) {
+            let spawner = format!("{prefix}{}", step.id);
+            for (index, item) in items {
+                let mut copy = cook_step(
+                    step,
+                    format!("{spawner}.{index}"),
+                    &bind_item(&vars, item, *index),
+                    needs.clone(),
+                );
+                copy.spawned_by = Some(spawner.clone());
+                cooked.push(copy);
+            }
+            continue;
+        }
+
+        cooked.push(cook_step(
+            step,
+            format!("{prefix}{}", step.id),
+            &vars,
             needs,
-            gate: step.gate.clone(),
-            assignee: step.assignee.as_ref().map(|a| substitute_vars(a, &vars)),
-            labels: step.labels.clone(),
-        });
+        ));
+    }
+
+    // 6. Weave aspects into this formula's steps
+    let mut woven = HashSet::new();
+    for name in &formula.aspects {
+        let aspect = enter(name, "aspect", load, chain)?;
+        cooked = weave(&aspect, cooked, &mut woven, &vars, load, chain)?;
+        chain.pop();
     }
 
     Ok(cooked)
 }
 
+/// Substitutes `vars` into `step`'s fields.
+fn cook_step(
+    step: &Step,
+    id: String,
+    vars: &HashMap<String, String>,
+    needs: Vec<String>,
+) -> CookedStep {
+    CookedStep {
+        id,
+        title: substitute_vars(&step.title, vars),
+        description: substitute_vars(&step.description, vars),
+        issue_type: step.r#type.clone(),
+        priority: step.priority,
+        needs,
+        gate: step.gate.clone(),
+        assignee: step.assignee.as_ref().map(|a| substitute_vars(a, vars)),
+        labels: step.labels.clone(),
+        spawned_by: None,
+    }
+}
+
+/// Evaluates `step`'s condition, naming the step in syntax errors.
+fn step_passes(step: &Step, vars: &HashMap<String, String>) -> Result<bool, FormulaError> {
+    let Some(ref cond) = step.condition else {
+        return Ok(true);
+    };
+    evaluate_condition(cond, vars).map_err(|e| match e {
+        FormulaError::InvalidCondition(msg) => {
+            FormulaError::InvalidCondition(format!("step '{}': {}", step.id, msg))
+        }
+        other => other,
+    })
+}
+
+/// Splits a `for_each` list into its items after substitution. Items are
+/// separated by commas or newlines; blanks are dropped.
+fn loop_items(list: &str, vars: &HashMap<String, String>) -> Result<Vec<String>, FormulaError> {
+    let mut referenced = HashSet::new();
+    scan_vars(list, &mut referenced);
+    if let Some(name) = referenced.into_iter().find(|v| !vars.contains_key(v)) {
+        return Err(FormulaError::UnknownVariable(name));
+    }
+    Ok(substitute_vars(list, vars)
+        .split([',', '\n'])
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// `vars` with the loop variables `item` and `index` bound.
+fn bind_item(vars: &HashMap<String, String>, item: &str, index: usize) -> HashMap<String, String> {
+    let mut bound = vars.clone();
+    bound.insert("item".to_string(), item.to_string());
+    bound.insert("index".to_string(), index.to_string());
+    bound
+}
+
+/// Loads formula `name` for inclusion, checking its type and pushing it on
+/// the include chain. Fails if it is already being cooked.
+fn enter(
+    name: &str,
+    expected_type: &str,
+    load: &dyn Fn(&str) -> Result<Formula, FormulaError>,
+    chain: &mut Vec<String>,
+) -> Result<Formula, FormulaError> {
+    let formula = load(name)?;
+    if chain.contains(&formula.formula) {
+        let mut cycle = chain.clone();
+        cycle.push(formula.formula);
+        return Err(FormulaError::IncludeCycle(cycle));
+    }
+    if formula.r#type != expected_type {
+        return Err(FormulaError::Composition(format!(
+            "'{}' is a {} formula, expected an {}",
+            name, formula.r#type, expected_type
+        )));
+    }
+    chain.push(formula.formula.clone());
+    Ok(formula)
+}
+
+/// Weaves `aspect` around every step of `steps` its pointcut selects. Woven
+/// steps get IDs prefixed by `<target id>.` and the variables `target` and
+/// `target_title`; their IDs are added to `woven` and never selected.
+fn weave(
+    aspect: &Formula,
+    mut steps: Vec<CookedStep>,
+    woven: &mut HashSet<String>,
+    vars: &HashMap<String, String>,
+    load: &dyn Fn(&str) -> Result<Formula, FormulaError>,
+    chain: &mut Vec<String>,
+) -> Result<Vec<CookedStep>, FormulaError> {
+    let pointcut = aspect.pointcut.as_ref();
+    let (selector, after) = match pointcut.map(|p| (&p.before, &p.after)) {
+        Some((None, Some(sel))) => (sel, true),
+        Some((Some(sel), None)) => (sel, false),
+        _ => {
+            return Err(FormulaError::Composition(format!(
+                "aspect '{}' needs a pointcut with exactly one of before/after",
+                aspect.formula
+            )));
+        }
+    };
+
+    let targets: Vec<usize> = (0..steps.len())
+        .filter(|&i| !woven.contains(&steps[i].id) && selector.matches(&steps[i]))
+        .collect();
+    let mut added = Vec::new();
+    for i in targets {
+        let target = steps[i].clone();
+        let mut bindings = vars.clone();
+        bindings.insert("target".into(), target.id.clone());
+        bindings.insert("target_title".into(), target.title.clone());
+        let mut advice = cook_scoped(aspect, &bindings, load, chain, &format!("{}.", target.id))?;
+        if advice.is_empty() {
+            continue;
+        }
+        let (first, last) = (roots(&advice), leaves(&advice));
+        if after {
+            // target -> advice -> former dependents of target
+            for step in &mut advice {
+                if first.contains(&step.id) {
+                    step.needs.push(target.id.clone());
+                }
+            }
+            for step in &mut steps {
+                if step.needs.contains(&target.id) {
+                    step.needs.retain(|n| *n != target.id);
+                    step.needs.extend(last.iter().cloned());
+                }
+            }
+        } else {
+            // target's needs -> advice -> target
+            for step in &mut advice {
+                if first.contains(&step.id) {
+                    step.needs.extend(target.needs.iter().cloned());
+                }
+            }
+            steps[i].needs = last;
+        }
+        woven.extend(advice.iter().map(|s| s.id.clone()));
+        added.extend(advice);
+    }
+    steps.extend(added);
+    Ok(steps)
+}
+
+/// IDs of the steps that need no other step in `steps`.
+fn roots(steps: &[CookedStep]) -> Vec<String> {
+    let ids: HashSet<&str> = steps.iter().map(|s| s.id.as_str()).collect();
+    steps
+        .iter()
+        .filter(|s| !s.needs.iter().any(|n| ids.contains(n.as_str())))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// IDs of the steps in `steps` that no other step needs.
+fn leaves(steps: &[CookedStep]) -> Vec<String> {
+    let needed: HashSet<&str> = steps
+        .iter()
+        .flat_map(|s| s.needs.iter().map(String::as_str))
+        .collect();
+    steps
+        .iter()
+        .filter(|s| !needed.contains(s.id.as_str()))
+        .map(|s| s.id.clone())
+        .collect()
+}
+
+/// Arrange a formula's steps into dependency layers.
+///
+/// Layer 0 holds the steps that need nothing; every other step sits one
+/// layer below the deepest step it needs. Steps keep their file order
+/// within a layer. Fails if a step needs an unknown step or the needs form
+/// a cycle.
+pub fn step_layers(formula: &Formula) -> Result<Vec<Vec<&Step>>, FormulaError> {
+    let index: HashMap<&str, usize> = formula
+        .steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| (step.id.as_str(), i))
+        .collect();
+    for step in &formula.steps {
+        if let Some(missing) = step.needs.iter().find(|n| !index.contains_key(n.as_str())) {
+            return Err(FormulaError::StepNotFound(format!(
+                "{} (needed by {})",
+                missing, step.id
+            )));
+        }
+    }
+
+    // Relax depths until stable; more rounds than steps means a cycle.
+    let mut depth = vec![0usize; formula.steps.len()];
+    for _ in 0..=formula.steps.len() {
+        let mut changed = false;
+        for (i, step) in formula.steps.iter().enumerate() {
+            for need in &step.needs {
+                let d = depth[index[need.as_str()]] + 1;
+                if d > depth[i] {
+                    depth[i] = d;
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            let mut layers: Vec<Vec<&Step>> = Vec::new();
+            for (step, &d) in formula.steps.iter().zip(&depth) {
+                if layers.len() <= d {
+                    layers.resize_with(d + 1, Vec::new);
+                }
+                layers[d].push(step);
+            }
+            return Ok(layers);
+        }
+    }
+    Err(FormulaError::CycleDetected)
+}
+
 // ---------------------------------------------------------------------------
 // Internal helpers
 // ---------------------------------------------------------------------------
@@ -221,7 +572,10 @@ fn scan_vars(text: &str, vars: &mut HashSet<String>) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{Formula, Step, StepGate, VarDef};
+    use std::collections::BTreeMap;
+
+    use crate::parser::parse_toml;
+    use crate::types::{Formula, Step, StepGate, VarDef, VarType};
 
     fn make_vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
         pairs
@@ -261,39 +615,39 @@ mod tests {
     #[test]
     fn condition_equality() {
         let vars = make_vars(&[("type", "feature")]);
-        assert!(evaluate_condition("{{type}} == feature", &vars));
-        assert!(!evaluate_condition("{{type}} == bug", &vars));
+        assert!(evaluate_condition("{{type}} == feature", &vars).unwrap());
+        assert!(!evaluate_condition("{{type}} == bug", &vars).unwrap());
     }
 
     #[test]
     fn condition_inequality() {
         let vars = make_vars(&[("type", "feature")]);
-        assert!(evaluate_condition("{{type}} != bug", &vars));
-        assert!(!evaluate_condition("{{type}} != feature", &vars));
+        assert!(evaluate_condition("{{type}} != bug", &vars).unwrap());
+        assert!(!evaluate_condition("{{type}} != feature", &vars).unwrap());
     }
 
     #[test]
     fn condition_truthy() {
         let vars = make_vars(&[("docs", "yes")]);
-        assert!(evaluate_condition("{{docs}}", &vars));
+        assert!(evaluate_condition("{{docs}}", &vars).unwrap());
     }
 
     #[test]
     fn condition_falsy_zero() {
         let vars = make_vars(&[("docs", "0")]);
-        assert!(!evaluate_condition("{{docs}}", &vars));
+        assert!(!evaluate_condition("{{docs}}", &vars).unwrap());
     }
 
     #[test]
     fn condition_negated() {
         let vars = make_vars(&[("skip", "false")]);
-        assert!(evaluate_condition("!{{skip}}", &vars));
+        assert!(evaluate_condition("!{{skip}}", &vars).unwrap());
     }
 
     #[test]
     fn condition_empty_passes() {
         let vars = HashMap::new();
-        assert!(evaluate_condition("", &vars));
+        assert!(evaluate_condition("", &vars).unwrap());
     }
 
     // -- extract_variables -------------------------------------------------
@@ -305,7 +659,7 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::new(),
+            vars: BTreeMap::new(),
             steps: vec![Step {
                 id: "a".into(),
                 title: "Design {{component}}".into(),
@@ -317,8 +671,13 @@ mod tests {
                 gate: None,
                 assignee: Some("{{owner}}".into()),
                 labels: vec![],
+                expand: None,
+                with: BTreeMap::new(),
+                for_each: None,
             }],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
         let vars = extract_variables(&f);
         assert_eq!(vars, vec!["component", "owner"]);
@@ -333,12 +692,14 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::from([(
+            vars: BTreeMap::from([(
                 "name".into(),
                 VarDef {
                     description: String::new(),
                     required: true,
                     default: None,
+                    r#type: VarType::String,
+                    values: vec![],
                 },
             )]),
             steps: vec![
@@ -353,6 +714,9 @@ mod tests {
                     gate: None,
                     assignee: None,
                     labels: vec![],
+                    expand: None,
+                    with: BTreeMap::new(),
+                    for_each: None,
                 },
                 Step {
                     id: "impl".into(),
@@ -365,9 +729,14 @@ mod tests {
                     gate: None,
                     assignee: None,
                     labels: vec![],
+                    expand: None,
+                    with: BTreeMap::new(),
+                    for_each: None,
                 },
             ],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
 
         let vars = make_vars(&[("name", "auth")]);
@@ -385,12 +754,14 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::from([(
+            vars: BTreeMap::from([(
                 "type".into(),
                 VarDef {
                     description: String::new(),
                     required: true,
                     default: None,
+                    r#type: VarType::String,
+                    values: vec![],
                 },
             )]),
             steps: vec![
@@ -405,6 +776,9 @@ mod tests {
                     gate: None,
                     assignee: None,
                     labels: vec![],
+                    expand: None,
+                    with: BTreeMap::new(),
+                    for_each: None,
                 },
                 Step {
                     id: "docs".into(),
@@ -417,6 +791,9 @@ mod tests {
                     gate: None,
                     assignee: None,
                     labels: vec![],
+                    expand: None,
+                    with: BTreeMap::new(),
+                    for_each: None,
                 },
                 Step {
                     id: "release".into(),
@@ -429,9 +806,14 @@ mod tests {
                     gate: None,
                     assignee: None,
                     labels: vec![],
+                    expand: None,
+                    with: BTreeMap::new(),
+                    for_each: None,
                 },
             ],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
 
         // With type=patch, "docs" is filtered out
@@ -457,16 +839,20 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::from([(
+            vars: BTreeMap::from([(
                 "name".into(),
                 VarDef {
                     description: String::new(),
                     required: true,
                     default: None,
+                    r#type: VarType::String,
+                    values: vec![],
                 },
             )]),
             steps: vec![],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
         let result = cook(&f, &HashMap::new());
         assert!(result.is_err());
@@ -480,12 +866,14 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::from([(
+            vars: BTreeMap::from([(
                 "env".into(),
                 VarDef {
                     description: String::new(),
                     required: false,
                     default: Some("staging".into()),
+                    r#type: VarType::String,
+                    values: vec![],
                 },
             )]),
             steps: vec![Step {
@@ -499,8 +887,13 @@ mod tests {
                 gate: None,
                 assignee: None,
                 labels: vec![],
+                expand: None,
+                with: BTreeMap::new(),
+                for_each: None,
             }],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
 
         // No vars provided -- should use default
@@ -520,7 +913,7 @@ mod tests {
             description: String::new(),
             r#type: "workflow".into(),
             version: 1,
-            vars: HashMap::new(),
+            vars: BTreeMap::new(),
             steps: vec![Step {
                 id: "ci".into(),
                 title: "Wait for CI".into(),
@@ -536,10 +929,420 @@ mod tests {
                 }),
                 assignee: None,
                 labels: vec![],
+                expand: None,
+                with: BTreeMap::new(),
+                for_each: None,
             }],
             source: String::new(),
+            aspects: vec![],
+            pointcut: None,
         };
         let cooked = cook(&f, &HashMap::new()).unwrap();
         assert_eq!(cooked[0].gate.as_ref().unwrap().r#type, "gh:run");
     }
+
+    // -- step_layers -------------------------------------------------------
+
+    fn steps_toml(body: &str) -> Formula {
+        parse_toml(&format!("formula = \"dag\"\n{body}")).unwrap()
+    }
+
+    #[test]
+    fn layers_follow_longest_path() {
+        let f = steps_toml(
+            r#"
+[[steps]]
+id = "docs"
+title = "Docs"
+needs = ["build"]
+
+[[steps]]
+id = "design"
+title = "Design"
+
+[[steps]]
+id = "build"
+title = "Build"
+needs = ["design"]
+
+[[steps]]
+id = "release"
+title = "Release"
+needs = ["design", "docs"]
+"#,
+        );
+        let layers = step_layers(&f).unwrap();
+        let ids: Vec<Vec<&str>> = layers
+            .iter()
+            .map(|layer| layer.iter().map(|s| s.id.as_str()).collect())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![vec!["design"], vec!["build"], vec!["docs"], vec!["release"]]
+        );
+    }
+
+    #[test]
+    fn layers_reject_unknown_needs_and_cycles() {
+        let unknown = steps_toml(
+            r#"
+[[steps]]
+id = "a"
+title = "A"
+needs = ["ghost"]
+"#,
+        );
+        assert!(matches!(
+            step_layers(&unknown),
+            Err(FormulaError::StepNotFound(ref m)) if m == "ghost (needed by a)"
+        ));
+
+        let cyclic = steps_toml(
+            r#"
+[[steps]]
+id = "a"
+title = "A"
+needs = ["b"]
+
+[[steps]]
+id = "b"
+title = "B"
+needs = ["a"]
+"#,
+        );
+        assert!(matches!(
+            step_layers(&cyclic),
+            Err(FormulaError::CycleDetected)
+        ));
+    }
+
+    // -- composition -------------------------------------------------------
+
+    fn library(sources: &[&str]) -> impl Fn(&str) -> Result<Formula, FormulaError> + use<> {
+        let formulas: HashMap<String, Formula> = sources
+            .iter()
+            .map(|src| {
+                let f = parse_toml(src).unwrap();
+                (f.formula.clone(), f)
+            })
+            .collect();
+        move |name: &str| {
+            formulas
+                .get(name)
+                .cloned()
+                .ok_or_else(|| FormulaError::Parse(format!("formula '{}' not found", name)))
+        }
+    }
+
+    fn edges(steps: &[CookedStep]) -> Vec<(String, Vec<String>)> {
+        steps
+            .iter()
+            .map(|s| (s.id.clone(), s.needs.clone()))
+            .collect()
+    }
+
+    fn pair(id: &str, needs: &[&str]) -> (String, Vec<String>) {
+        (id.into(), needs.iter().map(|n| n.to_string()).collect())
+    }
+
+    const TEST_SUITE: &str = r#"
+formula = "test-suite"
+type = "expansion"
+
+[vars.target]
+required = true
+
+[[steps]]
+id = "unit"
+title = "Unit tests for {{target}}"
+
+[[steps]]
+id = "e2e"
+title = "E2E tests for {{target}}"
+needs = ["unit"]
+"#;
+
+    #[test]
+    fn expansion_is_namespaced_and_rewired() {
+        let root = parse_toml(
+            r#"
+formula = "release"
+
+[vars.component]
+default = "api"
+
+[[steps]]
+id = "build"
+title = "Build"
+
+[[steps]]
+id = "test"
+expand = "test-suite"
+with = { target = "{{component}}" }
+needs = ["build"]
+
+[[steps]]
+id = "ship"
+title = "Ship"
+needs = ["test"]
+"#,
+        )
+        .unwrap();
+        let load = library(&[TEST_SUITE]);
+        let cooked = cook_with(&root, &HashMap::new(), &load).unwrap();
+        assert_eq!(
+            edges(&cooked),
+            vec![
+                pair("build", &[]),
+                pair("test.unit", &["build"]),
+                pair("test.e2e", &["test.unit"]),
+                pair("ship", &["test.e2e"]),
+            ]
+        );
+        assert_eq!(cooked[1].title, "Unit tests for api");
+
+        // Plain cook has no way to load the expansion.
+        assert!(matches!(
+            cook(&root, &HashMap::new()),
+            Err(FormulaError::Composition(_))
+        ));
+    }
+
+    #[test]
+    fn aspects_weave_before_and_after_selected_steps() {
+        let root = parse_toml(
+            r#"
+formula = "feature-flow"
+aspects = ["security", "kickoff"]
+
+[[steps]]
+id = "design"
+title = "Design"
+
+[[steps]]
+id = "impl-api"
+title = "Build API"
+type = "feature"
+needs = ["design"]
+
+[[steps]]
+id = "impl-ui"
+title = "Build UI"
+needs = ["design"]
+
+[[steps]]
+id = "ship"
+title = "Ship"
+needs = ["impl-api", "impl-ui"]
+"#,
+        )
+        .unwrap();
+        let security = r#"
+formula = "security"
+type = "aspect"
+pointcut = { after = { type = "feature" } }
+
+[[steps]]
+id = "review"
+title = "Security review of {{target_title}}"
+labels = ["security"]
+"#;
+        let kickoff = r#"
+formula = "kickoff"
+type = "aspect"
+pointcut = { before = { id = "impl-*" } }
+
+[[steps]]
+id = "kickoff"
+title = "Kick off {{target}}"
+"#;
+        let load = library(&[security, kickoff]);
+        let cooked = cook_with(&root, &HashMap::new(), &load).unwrap();
+        assert_eq!(
+            edges(&cooked),
+            vec![
+                pair("design", &[]),
+                pair("impl-api", &["impl-api.kickoff"]),
+                pair("impl-ui", &["impl-ui.kickoff"]),
+                pair("ship", &["impl-ui", "impl-api.review"]),
+                pair("impl-api.review", &["impl-api"]),
+                pair("impl-api.kickoff", &["design"]),
+                pair("impl-ui.kickoff", &["design"]),
+            ]
+        );
+        assert_eq!(cooked[4].title, "Security review of Build API");
+    }
+
+    #[test]
+    fn include_cycles_report_the_chain() {
+        let a = r#"
+formula = "a"
+type = "expansion"
+
+[[steps]]
+id = "inner"
+expand = "b"
+"#;
+        let b = r#"
+formula = "b"
+type = "expansion"
+
+[[steps]]
+id = "back"
+expand = "a"
+"#;
+        let root = parse_toml(
+            r#"
+formula = "root"
+
+[[steps]]
+id = "start"
+expand = "a"
+"#,
+        )
+        .unwrap();
+        let err = cook_with(&root, &HashMap::new(), &library(&[a, b])).unwrap_err();
+        assert!(matches!(err, FormulaError::IncludeCycle(ref chain)
+            if chain.as_slice() == ["root", "a", "b", "a"]));
+        assert_eq!(
+            err.to_string(),
+            "formula include cycle: root -> a -> b -> a"
+        );
+
+        // Expanding something that is not an expansion is rejected.
+        let root = parse_toml(
+            r#"
+formula = "root"
+
+[[steps]]
+id = "start"
+expand = "plain"
+"#,
+        )
+        .unwrap();
+        let plain = "formula = \"plain\"";
+        let err = cook_with(&root, &HashMap::new(), &library(&[plain])).unwrap_err();
+        assert!(matches!(err, FormulaError::Composition(_)));
+    }
+
+    // -- typed variables and conditions ------------------------------------
+
+    const RELEASE: &str = r#"
+formula = "release"
+
+[vars.replicas]
+type = "int"
+default = "1"
+
+[vars.env]
+type = "enum"
+values = ["staging", "production"]
+default = "staging"
+
+[vars.notes]
+
+[[steps]]
+id = "deploy"
+title = "Deploy to {{env}}"
+
+[[steps]]
+id = "scale"
+title = "Scale out"
+condition = "{{replicas}} > 1 && {{env}} in [production]"
+
+[[steps]]
+id = "announce"
+title = "Announce"
+condition = "{{notes}} || {{env}} == production"
+"#;
+
+    fn cooked_ids(steps: &[CookedStep]) -> Vec<&str> {
+        steps.iter().map(|s| s.id.as_str()).collect()
+    }
+
+    #[test]
+    fn cook_checks_typed_vars() {
+        let f = parse_toml(RELEASE).unwrap();
+        assert_eq!(f.vars["replicas"].r#type, VarType::Int);
+
+        let cooked = cook(&f, &HashMap::new()).unwrap();
+        assert_eq!(cooked_ids(&cooked), ["deploy"]);
+
+        let vars = make_vars(&[("replicas", "3"), ("env", "production")]);
+        let cooked = cook(&f, &vars).unwrap();
+        assert_eq!(cooked_ids(&cooked), ["deploy", "scale", "announce"]);
+
+        let err = cook(&f, &make_vars(&[("replicas", "many")])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value for variable replicas: 'many' is not an integer"
+        );
+        let err = cook(&f, &make_vars(&[("env", "prod")])).unwrap_err();
+        assert!(matches!(err, FormulaError::InvalidVariable { ref name, .. } if name == "env"));
+    }
+
+    #[test]
+    fn cook_reports_bad_conditions() {
+        let mut f = parse_toml(RELEASE).unwrap();
+        f.steps[1].condition = Some("{{replicas}} >".into());
+        let err = cook(&f, &HashMap::new()).unwrap_err();
+        assert!(
+            matches!(err, FormulaError::InvalidCondition(ref m) if m.starts_with("step 'scale'")),
+            "{err}"
+        );
+
+        f.steps[1].condition = Some("{{region}} == eu".into());
+        let err = cook(&f, &HashMap::new()).unwrap_err();
+        assert!(matches!(err, FormulaError::UnknownVariable(ref v) if v == "region"));
+    }
+
+    // -- for_each ----------------------------------------------------------
+
+    const REVIEW: &str = r#"
+formula = "review"
+
+[vars.modules]
+default = "api, web,cli"
+
+[[steps]]
+id = "plan"
+title = "Plan"
+
+[[steps]]
+id = "review"
+title = "Review {{item}} (#{{index}})"
+for_each = "{{modules}}"
+condition = "{{item}} != web"
+needs = ["plan"]
+
+[[steps]]
+id = "merge"
+title = "Merge"
+needs = ["review"]
+"#;
+
+    #[test]
+    fn for_each_fans_out_and_fans_in() {
+        let f = parse_toml(REVIEW).unwrap();
+        assert_eq!(extract_variables(&f), ["modules"]);
+
+        let cooked = cook(&f, &HashMap::new()).unwrap();
+        assert_eq!(
+            edges(&cooked),
+            [
+                pair("plan", &[]),
+                pair("review.0", &["plan"]),
+                pair("review.2", &["plan"]),
+                pair("merge", &["review.0", "review.2"]),
+            ]
+        );
+        assert_eq!(cooked[1].title, "Review api (#0)");
+        assert_eq!(cooked[2].title, "Review cli (#2)");
+        assert_eq!(cooked[2].spawned_by.as_deref(), Some("review"));
+        assert_eq!(cooked[3].spawned_by, None);
+
+        // An empty list drops the step like a failed condition.
+        let cooked = cook(&f, &make_vars(&[("modules", " ")])).unwrap();
+        assert_eq!(edges(&cooked), [pair("plan", &[]), pair("merge", &[])]);
+    }
 }
@@ -3,7 +3,12 @@
 //! Formulas are high-level workflow templates that compile down to proto beads.
 //! They support variable definitions with defaults, step definitions that become
 //! issue hierarchies, conditions for optional steps, and dependencies between steps.
+//! Variables can be typed (`string`, `int`, `bool`, `enum`) and conditions
+//! combine comparisons with `&&`, `||` and parentheses.
+//! `expansion` formulas can be cooked in place of a step and `aspect` formulas
+//! woven around the steps they select.
 
+pub mod condition;
 pub mod engine;
 pub mod parser;
 pub mod types;
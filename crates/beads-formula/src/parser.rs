@@ -2,6 +2,8 @@
 
 use std::path::{Path, PathBuf};
 
+use serde::Serialize;
+
 use crate::types::{Formula, FormulaError};
 
 /// Parse a formula from a TOML string.
@@ -29,6 +31,121 @@ pub fn load_formula(path: &Path) -> Result<Formula, FormulaError> {
     Ok(formula)
 }
 
+/// File suffixes a formula name may be given without, in lookup order.
+pub const FORMULA_SUFFIXES: [&str; 4] = [".formula.toml", ".formula.json", ".toml", ".json"];
+
+/// Where a formula was found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormulaScope {
+    /// The current directory.
+    Cwd,
+    /// The project's `.beads/formulas/`.
+    Project,
+    /// The user's `~/.beads/formulas/`.
+    User,
+}
+
+impl FormulaScope {
+    /// Short name for listings.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FormulaScope::Cwd => "cwd",
+            FormulaScope::Project => "project",
+            FormulaScope::User => "user",
+        }
+    }
+}
+
+/// A formula file found by [`discover_formulas`].
+#[derive(Debug, Clone, Serialize)]
+pub struct FormulaFile {
+    /// Name to look the formula up by (the file name without its suffix).
+    pub name: String,
+    /// Path to the file.
+    pub path: PathBuf,
+    /// Which search root it was found in.
+    pub scope: FormulaScope,
+}
+
+/// The directories searched for formulas, in priority order, with their
+/// scopes. Directories that do not exist are included.
+pub fn search_roots(cwd: &Path) -> Vec<(FormulaScope, PathBuf)> {
+    let mut roots = vec![
+        (FormulaScope::Cwd, cwd.to_path_buf()),
+        (FormulaScope::Project, project_formula_dir(cwd)),
+    ];
+    if let Some(dir) = user_formula_dir() {
+        roots.push((FormulaScope::User, dir));
+    }
+    roots
+}
+
+/// `.beads/formulas/` under `cwd`.
+pub fn project_formula_dir(cwd: &Path) -> PathBuf {
+    cwd.join(".beads").join("formulas")
+}
+
+/// `~/.beads/formulas/`, if the home directory is known.
+pub fn user_formula_dir() -> Option<PathBuf> {
+    home_dir().map(|home| home.join(".beads").join("formulas"))
+}
+
+/// Returns the lookup name of a formula file, or `None` if the file name
+/// does not end in a formula suffix.
+///
+/// In the current directory only `.formula.toml` / `.formula.json` count,
+/// so unrelated TOML and JSON files are not mistaken for formulas.
+pub fn formula_name(path: &Path, scope: FormulaScope) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let suffixes = match scope {
+        FormulaScope::Cwd => &FORMULA_SUFFIXES[..2],
+        _ => &FORMULA_SUFFIXES[..],
+    };
+    suffixes
+        .iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+}
+
+/// Lists the formula files in every search root, sorted by name.
+///
+/// A name found in several roots is listed once, from the root that
+/// [`find_formula`] would pick.
+pub fn discover_formulas(cwd: &Path) -> Vec<FormulaFile> {
+    let mut found: Vec<FormulaFile> = Vec::new();
+    for (scope, root) in search_roots(cwd) {
+        let Ok(entries) = std::fs::read_dir(&root) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.is_file())
+            .collect();
+        // Within a root, suffix order decides between `x.formula.toml`
+        // and `x.toml`, as it does for lookups.
+        paths.sort_by_key(|p| {
+            let name = p.file_name().map(|n| n.to_string_lossy().into_owned());
+            let rank = FORMULA_SUFFIXES
+                .iter()
+                .position(|s| name.as_deref().is_some_and(|n| n.ends_with(s)));
+            (rank, name)
+        });
+        for path in paths {
+            let Some(name) = formula_name(&path, scope) else {
+                continue;
+            };
+            if found.iter().any(|f| f.name == name) {
+                continue;
+            }
+            found.push(FormulaFile { name, path, scope });
+        }
+    }
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    found
+}
+
 /// Search for a formula by name in standard locations.
 ///
 /// Search order:
@@ -47,47 +164,35 @@ pub fn find_formula(name: &str, cwd: &Path) -> Result<PathBuf, FormulaError> {
         return Ok(relative);
     }
 
-    // Standard suffixes to try
-    let suffixes = [".formula.toml", ".formula.json", ".toml", ".json"];
-
-    // 2. Current directory
-    for suffix in &suffixes {
-        let candidate = cwd.join(format!("{}{}", name, suffix));
-        if candidate.exists() {
-            return Ok(candidate);
+    // 2-4. Search roots with the standard suffixes
+    for (_, root) in search_roots(cwd) {
+        if !root.is_dir() {
+            continue;
         }
-    }
-
-    // 3. .beads/formulas/ under cwd
-    let beads_formulas = cwd.join(".beads").join("formulas");
-    if beads_formulas.is_dir() {
-        for suffix in &suffixes {
-            let candidate = beads_formulas.join(format!("{}{}", name, suffix));
+        for suffix in &FORMULA_SUFFIXES {
+            let candidate = root.join(format!("{}{}", name, suffix));
             if candidate.exists() {
                 return Ok(candidate);
             }
         }
     }
 
-    // 4. ~/.beads/formulas/
-    if let Some(home) = home_dir() {
-        let home_formulas = home.join(".beads").join("formulas");
-        if home_formulas.is_dir() {
-            for suffix in &suffixes {
-                let candidate = home_formulas.join(format!("{}{}", name, suffix));
-                if candidate.exists() {
-                    return Ok(candidate);
-                }
-            }
-        }
-    }
-
     Err(FormulaError::Parse(format!(
         "formula '{}' not found (searched cwd, .beads/formulas/, ~/.beads/formulas/)",
         name
     )))
 }
 
+/// Find a formula by name (see [`find_formula`]) and load it.
+pub fn load_named(name: &str, cwd: &Path) -> Result<Formula, FormulaError> {
+    load_formula(&find_formula(name, cwd)?)
+}
+
+/// Serialize a formula as TOML, in the layout `load_formula` reads back.
+pub fn to_toml(formula: &Formula) -> Result<String, FormulaError> {
+    toml::to_string_pretty(formula).map_err(|e| FormulaError::Parse(e.to_string()))
+}
+
 /// Get the user's home directory.
 fn home_dir() -> Option<PathBuf> {
     #[cfg(target_os = "windows")]
@@ -175,4 +280,80 @@ needs = ["design"]
         assert_eq!(f.steps[0].gate.as_ref().unwrap().r#type, "gh:run");
         assert_eq!(f.steps[1].condition.as_deref(), Some("{{type}} == major"));
     }
+
+    #[test]
+    fn toml_round_trip_omits_empty_fields() {
+        let toml_str = r#"
+formula = "mol-feature"
+version = 1
+
+[vars.component]
+required = true
+
+[[steps]]
+id = "design"
+title = "Design {{component}}"
+
+[[steps]]
+id = "ship"
+title = "Ship it"
+needs = ["design"]
+gate = { type = "human" }
+"#;
+        let f = parse_toml(toml_str).unwrap();
+        let rendered = to_toml(&f).unwrap();
+        assert!(!rendered.contains("description"));
+        assert!(!rendered.contains("labels"));
+        let back = parse_toml(&rendered).unwrap();
+        assert_eq!(back.steps.len(), 2);
+        assert_eq!(back.steps[1].needs, vec!["design"]);
+        assert_eq!(back.steps[1].gate.as_ref().unwrap().r#type, "human");
+        assert!(back.vars["component"].required);
+    }
+
+    #[test]
+    fn discovery_prefers_earlier_roots_and_formula_suffixes() {
+        let tmp = tempfile::tempdir().unwrap();
+        let cwd = tmp.path();
+        let project = project_formula_dir(cwd);
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::write(cwd.join("release.formula.toml"), "formula = \"release\"").unwrap();
+        std::fs::write(cwd.join("Cargo.toml"), "[package]").unwrap();
+        std::fs::write(project.join("release.toml"), "formula = \"shadowed\"").unwrap();
+        std::fs::write(project.join("triage.toml"), "formula = \"triage\"").unwrap();
+        std::fs::write(
+            project.join("triage.formula.json"),
+            r#"{"formula": "triage"}"#,
+        )
+        .unwrap();
+        std::fs::write(project.join("notes.md"), "").unwrap();
+
+        let found: Vec<(String, FormulaScope, String)> = discover_formulas(cwd)
+            .into_iter()
+            .filter(|f| f.scope != FormulaScope::User)
+            .map(|f| {
+                let file = f.path.file_name().unwrap().to_string_lossy().into_owned();
+                (f.name, f.scope, file)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "release".into(),
+                    FormulaScope::Cwd,
+                    "release.formula.toml".into()
+                ),
+                (
+                    "triage".into(),
+                    FormulaScope::Project,
+                    "triage.formula.json".into()
+                ),
+            ]
+        );
+        assert_eq!(
+            find_formula("triage", cwd).unwrap(),
+            project.join("triage.formula.json")
+        );
+    }
 }
@@ -1,9 +1,10 @@
 //! Formula data model -- a minimal subset of the Go formula type system.
 //!
-//! Covers: steps, variables (with defaults & required), conditions,
-//! dependencies between steps, and gate definitions.
+//! Covers: steps, typed variables (with defaults & required), conditions,
+//! dependencies between steps, gate definitions, and composition through
+//! expansion steps and aspect pointcuts.
 
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
@@ -22,6 +23,10 @@ fn default_priority() -> i32 {
     2
 }
 
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
 /// Root structure for `.formula.json` / `.formula.toml` files.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Formula {
@@ -29,7 +34,7 @@ pub struct Formula {
     pub formula: String,
 
     /// Human-readable description.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
 
     /// Formula type: "workflow", "expansion", "aspect".
@@ -41,13 +46,22 @@ pub struct Formula {
     pub version: i32,
 
     /// Template variables with optional defaults and validation.
-    #[serde(default)]
-    pub vars: HashMap<String, VarDef>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, VarDef>,
 
     /// Steps that become issues when the formula is cooked.
     #[serde(default)]
     pub steps: Vec<Step>,
 
+    /// Aspect formulas woven into this formula's steps when it is cooked.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aspects: Vec<String>,
+
+    /// For `aspect` formulas: which steps the aspect's steps are woven
+    /// around.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pointcut: Option<Pointcut>,
+
     /// Where this formula was loaded from (set by the parser).
     #[serde(skip)]
     pub source: String,
@@ -57,16 +71,87 @@ pub struct Formula {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VarDef {
     /// What this variable is for.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
 
     /// Whether the variable must be provided (no default).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "is_false")]
     pub required: bool,
 
     /// Default value (None = no default).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub default: Option<String>,
+
+    /// Value type; values are checked against it before cooking.
+    #[serde(default, skip_serializing_if = "VarType::is_string")]
+    pub r#type: VarType,
+
+    /// Allowed values of an `enum` variable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+/// Type of a formula variable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VarType {
+    /// Any text.
+    #[default]
+    String,
+    /// A signed integer.
+    Int,
+    /// `true` or `false`.
+    Bool,
+    /// One of the variable's `values`.
+    Enum,
+}
+
+impl VarType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VarType::String => "string",
+            VarType::Int => "int",
+            VarType::Bool => "bool",
+            VarType::Enum => "enum",
+        }
+    }
+
+    fn is_string(&self) -> bool {
+        *self == VarType::String
+    }
+}
+
+impl VarDef {
+    /// Checks `value` against the variable's type, returning why it does
+    /// not fit.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match self.r#type {
+            VarType::String => Ok(()),
+            VarType::Int => value
+                .trim()
+                .parse::<i64>()
+                .map(|_| ())
+                .map_err(|_| format!("'{}' is not an integer", value)),
+            VarType::Bool => match value {
+                "true" | "false" => Ok(()),
+                _ => Err(format!("'{}' is not true or false", value)),
+            },
+            VarType::Enum if self.values.is_empty() => {
+                Err("enum variable declares no values".to_string())
+            }
+            VarType::Enum => {
+                if self.values.iter().any(|v| v == value) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "'{}' is not one of: {}",
+                        value,
+                        self.values.join(", ")
+                    ))
+                }
+            }
+        }
+    }
 }
 
 /// A work-item step that becomes an issue when cooked.
@@ -75,11 +160,13 @@ pub struct Step {
     /// Unique identifier within this formula.
     pub id: String,
 
-    /// Issue title (supports `{{variable}}` substitution).
+    /// Issue title (supports `{{variable}}` substitution). Unused by
+    /// `expand` steps.
+    #[serde(default)]
     pub title: String,
 
     /// Issue description (supports substitution).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub description: String,
 
     /// Issue type: "task", "bug", "feature", "epic", "chore".
@@ -91,24 +178,105 @@ pub struct Step {
     pub priority: i32,
 
     /// Step IDs this step depends on.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub needs: Vec<String>,
 
     /// Condition for including this step, e.g. `"{{type}} == feature"`.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub condition: Option<String>,
 
     /// Gate configuration (async wait condition).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gate: Option<StepGate>,
 
     /// Default assignee (supports substitution).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub assignee: Option<String>,
 
     /// Labels applied to the created issue.
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub labels: Vec<String>,
+
+    /// Name of an `expansion` formula cooked in place of this step. Its
+    /// step IDs are prefixed with `<this id>.`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expand: Option<String>,
+
+    /// Variables passed to the `expand` formula (values support
+    /// substitution from this formula's variables).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub with: BTreeMap<String, String>,
+
+    /// Comma-separated list, usually a variable such as `"{{modules}}"`.
+    /// The step is cooked once per item as `<id>.<index>`, with `{{item}}`
+    /// and `{{index}}` (0-based) bound, and steps needing it wait on every
+    /// copy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub for_each: Option<String>,
+}
+
+/// Where an aspect formula's steps are woven in: before or after every
+/// step matching a selector.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Pointcut {
+    /// Weave the aspect's steps in front of matching steps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub before: Option<Selector>,
+
+    /// Weave the aspect's steps behind matching steps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub after: Option<Selector>,
+}
+
+/// Matches cooked steps. Every field that is set must match.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Selector {
+    /// Issue type, e.g. `"feature"`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub r#type: Option<String>,
+
+    /// A label the step carries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Glob over the (namespaced) step ID; `*` matches any run of
+    /// characters and `?` a single one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+}
+
+impl Selector {
+    /// Whether `step` matches.
+    pub fn matches(&self, step: &CookedStep) -> bool {
+        self.r#type.as_ref().is_none_or(|t| *t == step.issue_type)
+            && self.label.as_ref().is_none_or(|l| step.labels.contains(l))
+            && self.id.as_ref().is_none_or(|g| glob_match(g, &step.id))
+    }
+}
+
+/// Matches `text` against a glob with `*` and `?` wildcards.
+fn glob_match(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    // Position of the last `*` and the text position it was tried at.
+    let (mut g, mut t) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == text[t]) {
+            g += 1;
+            t += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            star = Some((g, t));
+            g += 1;
+        } else if let Some((sg, st)) = star {
+            g = sg + 1;
+            t = st + 1;
+            star = Some((sg, st + 1));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
 }
 
 /// Gate defines an async wait condition for a step.
@@ -118,11 +286,11 @@ pub struct StepGate {
     pub r#type: String,
 
     /// Condition identifier (e.g. workflow name for gh:run).
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub id: String,
 
     /// How long to wait before escalation (e.g. "30m", "1h").
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub timeout: String,
 }
 
@@ -138,6 +306,9 @@ pub struct CookedStep {
     pub gate: Option<StepGate>,
     pub assignee: Option<String>,
     pub labels: Vec<String>,
+    /// The `for_each` step this step is a copy of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub spawned_by: Option<String>,
 }
 
 /// Errors that can occur during formula parsing and cooking.
@@ -155,12 +326,38 @@ pub enum FormulaError {
     #[error("invalid condition: {0}")]
     InvalidCondition(String),
 
+    /// A variable value (or default) that does not fit the declared type.
+    #[error("invalid value for variable {name}: {reason}")]
+    InvalidVariable { name: String, reason: String },
+
     #[error("step not found: {0}")]
     StepNotFound(String),
 
     #[error("cycle detected in step dependencies")]
     CycleDetected,
 
+    /// A formula expands or weaves itself, directly or through others.
+    #[error("formula include cycle: {}", .0.join(" -> "))]
+    IncludeCycle(Vec<String>),
+
+    #[error("invalid composition: {0}")]
+    Composition(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_matches_wildcards() {
+        assert!(glob_match("impl-*", "impl-api"));
+        assert!(glob_match("*.review", "test.unit.review"));
+        assert!(glob_match("step-?", "step-1"));
+        assert!(!glob_match("step-?", "step-10"));
+        assert!(!glob_match("impl-*", "design"));
+        assert!(glob_match("*", ""));
+    }
+}
@@ -0,0 +1,53 @@
+//! Lock errors.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::holder::LockHolder;
+use crate::lock::LockMode;
+
+/// Errors from acquiring or releasing a lock.
+#[derive(Debug, thiserror::Error)]
+pub enum LockError {
+    /// The lock file could not be opened, locked or written.
+    #[error("lock file {}: {source}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+
+    /// Another process held a conflicting lock for the whole timeout.
+    #[error(
+        "timed out after {}s waiting for {} lock on {}; {}",
+        waited.as_secs(),
+        mode.article(),
+        path.display(),
+        describe_holders(holders)
+    )]
+    Timeout {
+        path: PathBuf,
+        mode: LockMode,
+        waited: Duration,
+        /// Live holders recorded in the lock file.
+        holders: Vec<LockHolder>,
+    },
+}
+
+impl LockError {
+    pub(crate) fn io(path: &std::path::Path, source: io::Error) -> Self {
+        LockError::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+}
+
+fn describe_holders(holders: &[LockHolder]) -> String {
+    if holders.is_empty() {
+        return "the holder is unknown (recorded holders have exited)".to_string();
+    }
+    let list: Vec<String> = holders.iter().map(ToString::to_string).collect();
+    format!("held by {}", list.join(", "))
+}
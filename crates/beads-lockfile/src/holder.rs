@@ -0,0 +1,244 @@
+//! Lock holder records.
+//!
+//! Each holder writes one line describing itself, so a process that cannot
+//! get the lock can say who has it:
+//!
+//! ```text
+//! pid=4242 host=build-1 mode=exclusive since=1760000000 purpose=bd export
+//! ```
+//!
+//! An exclusive holder writes its line into the lock file itself. Shared
+//! holders cannot rewrite that file under each other, so each writes its
+//! own record file in the `<lock>.d` directory next to it and deletes it on
+//! release.
+//!
+//! The kernel releases a lock when its holder exits, but a crashed holder's
+//! record stays behind. A record whose process no longer runs on this host
+//! is stale and ignored.
+
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::lock::LockMode;
+
+/// A process holding (or having held) a lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockHolder {
+    /// Process ID.
+    pub pid: u32,
+    /// Host the process runs on.
+    pub hostname: String,
+    /// Mode the lock was taken in.
+    pub mode: LockMode,
+    /// When the lock was taken, in seconds since the Unix epoch.
+    pub since: u64,
+    /// What the holder is doing, e.g. `bd export`.
+    pub purpose: String,
+}
+
+impl LockHolder {
+    /// Describes the current process.
+    pub fn current(mode: LockMode, purpose: &str) -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: hostname(),
+            mode,
+            since: unix_now(),
+            purpose: purpose.replace(['\n', '\r'], " "),
+        }
+    }
+
+    /// Returns `true` if the record is left over from a process that has
+    /// exited. Holders on other hosts cannot be checked and are never stale.
+    pub fn is_stale(&self) -> bool {
+        self.hostname == hostname() && process_alive(self.pid) == Some(false)
+    }
+
+    /// Formats the record as a lock file line (without newline).
+    pub(crate) fn to_line(&self) -> String {
+        format!(
+            "pid={} host={} mode={} since={} purpose={}",
+            self.pid, self.hostname, self.mode, self.since, self.purpose
+        )
+    }
+
+    /// Parses a lock file line written by [`LockHolder::to_line`].
+    pub(crate) fn from_line(line: &str) -> Option<Self> {
+        let (fields, purpose) = line.split_once(" purpose=")?;
+        let mut pid = None;
+        let mut hostname = None;
+        let mut mode = None;
+        let mut since = None;
+        for field in fields.split_whitespace() {
+            match field.split_once('=')? {
+                ("pid", v) => pid = v.parse().ok(),
+                ("host", v) => hostname = Some(v.to_string()),
+                ("mode", v) => mode = v.parse().ok(),
+                ("since", v) => since = v.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(Self {
+            pid: pid?,
+            hostname: hostname?,
+            mode: mode?,
+            since: since?,
+            purpose: purpose.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pid {} on {} ({}, for {}s",
+            self.pid,
+            self.hostname,
+            self.mode,
+            unix_now().saturating_sub(self.since)
+        )?;
+        if !self.purpose.is_empty() {
+            write!(f, ": {}", self.purpose)?;
+        }
+        f.write_str(")")
+    }
+}
+
+/// Reads all holder records of a lock file, including stale ones: those
+/// in the file itself, then the shared holders' record files.
+///
+/// A missing or unreadable file has no holders.
+pub fn read_holders(path: &Path) -> Vec<LockHolder> {
+    let mut holders = read_records(path);
+    for record in record_files(path) {
+        holders.extend(read_records(&record));
+    }
+    holders
+}
+
+/// The directory holding the shared holders' record files of a lock file.
+pub(crate) fn records_dir(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".d");
+    PathBuf::from(name)
+}
+
+/// The shared holders' record files of a lock file, by name.
+pub(crate) fn record_files(path: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(records_dir(path)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries.filter_map(|e| e.ok().map(|e| e.path())).collect();
+    files.sort();
+    files
+}
+
+fn read_records(path: &Path) -> Vec<LockHolder> {
+    std::fs::read_to_string(path)
+        .map(|content| content.lines().filter_map(LockHolder::from_line).collect())
+        .unwrap_or_default()
+}
+
+/// Returns the holders of a lock file whose processes are still running.
+pub fn holders(path: &Path) -> Vec<LockHolder> {
+    read_holders(path)
+        .into_iter()
+        .filter(|h| !h.is_stale())
+        .collect()
+}
+
+/// The name of this host, or `unknown`.
+pub fn hostname() -> String {
+    let from_file = ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|p| std::fs::read_to_string(p).ok());
+    let name = from_file
+        .or_else(|| {
+            Command::new("hostname")
+                .output()
+                .ok()
+                .filter(|o| o.status.success())
+                .map(|o| String::from_utf8_lossy(&o.stdout).into_owned())
+        })
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .or_else(|| std::env::var("HOSTNAME").ok())
+        .unwrap_or_default();
+    let name = name.trim();
+    if name.is_empty() {
+        "unknown".to_string()
+    } else {
+        name.split_whitespace().collect::<Vec<_>>().join("-")
+    }
+}
+
+/// Whether a local process is running, or `None` if that cannot be told.
+fn process_alive(pid: u32) -> Option<bool> {
+    if pid == std::process::id() {
+        return Some(true);
+    }
+    if Path::new("/proc/self").exists() {
+        return Some(Path::new("/proc").join(pid.to_string()).exists());
+    }
+    if cfg!(unix) {
+        return Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .ok()
+            .map(|o| o.status.success());
+    }
+    None
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_round_trip() {
+        let holder = LockHolder {
+            pid: 42,
+            hostname: "build-1".into(),
+            mode: LockMode::Shared,
+            since: 1_760_000_000,
+            purpose: "bd export --all".into(),
+        };
+        let line = holder.to_line();
+        assert_eq!(
+            line,
+            "pid=42 host=build-1 mode=shared since=1760000000 purpose=bd export --all"
+        );
+        assert_eq!(LockHolder::from_line(&line), Some(holder));
+        assert_eq!(LockHolder::from_line("garbage"), None);
+    }
+
+    #[test]
+    fn staleness_is_judged_by_pid_and_host() {
+        let me = LockHolder::current(LockMode::Exclusive, "test");
+        assert!(!me.is_stale());
+
+        // PIDs are bounded well below u32::MAX on every supported platform.
+        let dead = LockHolder {
+            pid: u32::MAX - 1,
+            ..me.clone()
+        };
+        if Path::new("/proc/self").exists() {
+            assert!(dead.is_stale());
+        }
+
+        let remote = LockHolder {
+            hostname: format!("{}-elsewhere", me.hostname),
+            ..dead
+        };
+        assert!(!remote.is_stale());
+    }
+}
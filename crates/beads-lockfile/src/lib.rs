@@ -1 +1,15 @@
 //! File locking for the beads system.
+//!
+//! Advisory locks (via `fs2`) that let several `bd` processes share a
+//! `.beads` directory safely: shared and exclusive modes, acquisition
+//! timeouts, and holder records so a process that has to wait can say who
+//! it is waiting for. Records left behind by exited processes are detected
+//! by PID and hostname and ignored.
+
+pub mod error;
+pub mod holder;
+pub mod lock;
+
+pub use error::LockError;
+pub use holder::{LockHolder, holders, hostname, read_holders};
+pub use lock::{Lock, LockMode, LockOptions};
@@ -0,0 +1,355 @@
+//! Advisory file locks with timeouts.
+
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use fs2::FileExt;
+
+use crate::error::LockError;
+use crate::holder::{LockHolder, holders, record_files, records_dir};
+
+/// Distinguishes the record files of shared locks held by one process.
+static NEXT_RECORD: AtomicU64 = AtomicU64::new(0);
+
+/// How a lock is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockMode {
+    /// Any number of shared holders, but no exclusive one.
+    Shared,
+    /// A single holder.
+    Exclusive,
+}
+
+impl LockMode {
+    /// `a shared` / `an exclusive`, for messages.
+    pub(crate) fn article(self) -> &'static str {
+        match self {
+            LockMode::Shared => "a shared",
+            LockMode::Exclusive => "an exclusive",
+        }
+    }
+}
+
+impl fmt::Display for LockMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LockMode::Shared => "shared",
+            LockMode::Exclusive => "exclusive",
+        })
+    }
+}
+
+impl FromStr for LockMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "shared" => Ok(LockMode::Shared),
+            "exclusive" => Ok(LockMode::Exclusive),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Options for [`Lock::acquire`].
+#[derive(Debug, Clone)]
+pub struct LockOptions {
+    /// How long to wait for a conflicting holder; `None` waits forever and
+    /// zero tries once.
+    pub timeout: Option<Duration>,
+    /// How often to retry while waiting.
+    pub poll_interval: Duration,
+    /// What the lock is taken for, shown to processes waiting on it.
+    pub purpose: String,
+}
+
+impl Default for LockOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            poll_interval: Duration::from_millis(50),
+            purpose: String::new(),
+        }
+    }
+}
+
+impl LockOptions {
+    /// Sets the timeout.
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the purpose shown to waiters.
+    pub fn purpose(mut self, purpose: impl Into<String>) -> Self {
+        self.purpose = purpose.into();
+        self
+    }
+}
+
+/// A held advisory lock, released on drop.
+///
+/// Locks are taken on a dedicated lock file (created if missing) rather
+/// than on the protected file itself, so the protected file can be replaced
+/// by rename while locked. They are advisory: they only exclude other
+/// processes that lock the same file.
+#[derive(Debug)]
+pub struct Lock {
+    file: File,
+    path: PathBuf,
+    mode: LockMode,
+    /// A shared holder's record file.
+    record: Option<PathBuf>,
+}
+
+impl Lock {
+    /// Acquires `path` in `mode`, waiting up to `options.timeout` for
+    /// conflicting holders to release it.
+    pub fn acquire(path: &Path, mode: LockMode, options: &LockOptions) -> Result<Lock, LockError> {
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| LockError::io(path, e))?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| LockError::io(path, e))?;
+
+        let start = Instant::now();
+        loop {
+            match try_lock(&file, mode) {
+                Ok(()) => break,
+                Err(e) if is_contended(&e) => {}
+                Err(e) => return Err(LockError::io(path, e)),
+            }
+            let waited = start.elapsed();
+            if options.timeout.is_some_and(|t| waited >= t) {
+                return Err(LockError::Timeout {
+                    path: path.to_path_buf(),
+                    mode,
+                    waited,
+                    holders: holders(path),
+                });
+            }
+            thread::sleep(options.poll_interval);
+        }
+
+        let mut lock = Lock {
+            file,
+            path: path.to_path_buf(),
+            mode,
+            record: None,
+        };
+        lock.record(&options.purpose)
+            .map_err(|e| LockError::io(path, e))?;
+        Ok(lock)
+    }
+
+    /// Acquires `path` in `mode` without waiting.
+    pub fn try_acquire(path: &Path, mode: LockMode, purpose: &str) -> Result<Lock, LockError> {
+        let options = LockOptions::default()
+            .timeout(Some(Duration::ZERO))
+            .purpose(purpose);
+        Lock::acquire(path, mode, &options)
+    }
+
+    /// The lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The mode the lock is held in.
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+
+    /// Releases the lock, reporting errors that dropping would ignore.
+    pub fn release(self) -> Result<(), LockError> {
+        let result = self.clear().and_then(|()| FileExt::unlock(&self.file));
+        result.map_err(|e| LockError::io(&self.path, e))
+    }
+
+    /// Records this process as a holder.
+    ///
+    /// An exclusive holder knows every earlier record is stale, starts the
+    /// lock file over and removes the shared records. A shared holder gets
+    /// a record file of its own, after removing those of exited processes.
+    fn record(&mut self, purpose: &str) -> io::Result<()> {
+        let line = format!("{}\n", LockHolder::current(self.mode, purpose).to_line());
+        match self.mode {
+            LockMode::Exclusive => {
+                self.file.set_len(0)?;
+                (&self.file).write_all(line.as_bytes())?;
+                for record in record_files(&self.path) {
+                    let _ = std::fs::remove_file(record);
+                }
+                Ok(())
+            }
+            LockMode::Shared => {
+                prune_stale_records(&self.path);
+                let dir = records_dir(&self.path);
+                std::fs::create_dir_all(&dir)?;
+                let seq = NEXT_RECORD.fetch_add(1, Ordering::Relaxed);
+                let record = dir.join(format!("{}-{seq:06}", std::process::id()));
+                std::fs::write(&record, line)?;
+                self.record = Some(record);
+                Ok(())
+            }
+        }
+    }
+
+    /// Removes this holder's record.
+    fn clear(&self) -> io::Result<()> {
+        match &self.record {
+            Some(record) => std::fs::remove_file(record),
+            None => self.file.set_len(0),
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = self.clear();
+        let _ = FileExt::unlock(&self.file);
+    }
+}
+
+// Called through `FileExt` explicitly: `File` has inherent lock methods of
+// the same names on newer toolchains.
+fn try_lock(file: &File, mode: LockMode) -> io::Result<()> {
+    match mode {
+        LockMode::Shared => FileExt::try_lock_shared(file),
+        LockMode::Exclusive => FileExt::try_lock_exclusive(file),
+    }
+}
+
+/// Removes the shared record files left behind by exited processes.
+fn prune_stale_records(path: &Path) {
+    for record in record_files(path) {
+        // A record still being written does not parse yet and is kept.
+        let stale = std::fs::read_to_string(&record)
+            .ok()
+            .and_then(|content| content.lines().next().and_then(LockHolder::from_line))
+            .is_some_and(|h| h.is_stale());
+        if stale {
+            let _ = std::fs::remove_file(record);
+        }
+    }
+}
+
+fn is_contended(e: &io::Error) -> bool {
+    e.kind() == io::ErrorKind::WouldBlock
+        || e.raw_os_error() == fs2::lock_contended_error().raw_os_error()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holder::read_holders;
+
+    fn quick(purpose: &str) -> LockOptions {
+        LockOptions::default()
+            .timeout(Some(Duration::from_millis(100)))
+            .purpose(purpose)
+    }
+
+    #[test]
+    fn shared_locks_coexist_and_exclude_writers() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.lock");
+
+        let a = Lock::acquire(&path, LockMode::Shared, &quick("reader a")).unwrap();
+        let b = Lock::acquire(&path, LockMode::Shared, &quick("reader b")).unwrap();
+        assert_eq!(a.mode(), LockMode::Shared);
+
+        let err = Lock::acquire(&path, LockMode::Exclusive, &quick("writer")).unwrap_err();
+        let LockError::Timeout { ref holders, .. } = err else {
+            panic!("expected timeout, got {err:?}");
+        };
+        let purposes: Vec<&str> = holders.iter().map(|h| h.purpose.as_str()).collect();
+        assert_eq!(purposes, ["reader a", "reader b"]);
+        assert!(err.to_string().contains("waiting for an exclusive lock"));
+        assert!(err.to_string().contains("held by pid"));
+
+        drop(a);
+        b.release().unwrap();
+        assert!(read_holders(&path).is_empty());
+        let w = Lock::acquire(&path, LockMode::Exclusive, &quick("writer")).unwrap();
+        let recorded: Vec<String> = read_holders(&path).into_iter().map(|h| h.purpose).collect();
+        assert_eq!(recorded, ["writer"]);
+        drop(w);
+        assert!(read_holders(&path).is_empty());
+    }
+
+    #[test]
+    fn shared_records_do_not_pile_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.lock");
+
+        for _ in 0..5 {
+            let held = Lock::try_acquire(&path, LockMode::Shared, "reader").unwrap();
+            assert_eq!(read_holders(&path).len(), 1);
+            drop(held);
+        }
+        Lock::try_acquire(&path, LockMode::Shared, "reader")
+            .unwrap()
+            .release()
+            .unwrap();
+        assert!(read_holders(&path).is_empty());
+        assert_eq!(std::fs::read(&path).unwrap(), b"");
+
+        // A record left by a crashed reader is dropped by the next one.
+        let crashed = LockHolder {
+            pid: u32::MAX - 1,
+            ..LockHolder::current(LockMode::Shared, "crashed")
+        };
+        std::fs::write(
+            records_dir(&path).join("crashed"),
+            format!("{}\n", crashed.to_line()),
+        )
+        .unwrap();
+        if std::path::Path::new("/proc/self").exists() {
+            let held = Lock::try_acquire(&path, LockMode::Shared, "reader").unwrap();
+            let recorded: Vec<String> =
+                read_holders(&path).into_iter().map(|h| h.purpose).collect();
+            assert_eq!(recorded, ["reader"]);
+            drop(held);
+        }
+    }
+
+    #[test]
+    fn exclusive_lock_excludes_everyone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("db.lock");
+
+        let w = Lock::try_acquire(&path, LockMode::Exclusive, "migrate").unwrap();
+        let err = Lock::try_acquire(&path, LockMode::Shared, "reader").unwrap_err();
+        assert!(matches!(err, LockError::Timeout { ref holders, .. }
+            if holders.len() == 1 && holders[0].purpose == "migrate"));
+        drop(w);
+        Lock::try_acquire(&path, LockMode::Shared, "reader").unwrap();
+    }
+
+    #[test]
+    fn waiter_gets_the_lock_once_released() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.lock");
+
+        let held = Lock::try_acquire(&path, LockMode::Exclusive, "first").unwrap();
+        let waiter_path = path.clone();
+        let waiter = thread::spawn(move || {
+            let options = LockOptions::default().timeout(Some(Duration::from_secs(10)));
+            Lock::acquire(&waiter_path, LockMode::Exclusive, &options).map(|_| ())
+        });
+        thread::sleep(Duration::from_millis(150));
+        drop(held);
+        waiter.join().unwrap().unwrap();
+    }
+}
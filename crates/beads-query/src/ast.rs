@@ -0,0 +1,267 @@
+//! Query syntax tree.
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::error::Span;
+
+/// A boolean query expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    /// All sub-expressions must match.
+    And(Vec<Expr>),
+    /// At least one sub-expression must match.
+    Or(Vec<Expr>),
+    /// The sub-expression must not match.
+    Not(Box<Expr>),
+    /// A single `field <op> value` comparison.
+    Pred(Predicate),
+}
+
+impl Expr {
+    /// Returns the top-level conjuncts: the children of an `And`, or the
+    /// expression itself.
+    pub fn conjuncts(&self) -> &[Expr] {
+        match self {
+            Expr::And(children) => children,
+            other => std::slice::from_ref(other),
+        }
+    }
+
+    /// Calls `f` for every predicate in the expression.
+    pub fn visit(&self, f: &mut impl FnMut(&Predicate)) {
+        match self {
+            Expr::And(children) | Expr::Or(children) => {
+                for child in children {
+                    child.visit(f);
+                }
+            }
+            Expr::Not(inner) => inner.visit(f),
+            Expr::Pred(pred) => f(pred),
+        }
+    }
+}
+
+/// A `field <op> value` comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    /// Field being compared.
+    pub field: Field,
+    /// Comparison operator.
+    pub op: CmpOp,
+    /// Typed comparison value.
+    pub value: Value,
+    /// Source span of the whole predicate.
+    pub span: Span,
+}
+
+/// Comparison operators. `:` and `=` are equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CmpOp {
+    /// `:` or `=`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+}
+
+impl CmpOp {
+    /// The SQL spelling of the operator.
+    pub fn sql(self) -> &'static str {
+        match self {
+            CmpOp::Eq => "=",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        }
+    }
+
+    /// `true` for `<`, `<=`, `>` and `>=`.
+    pub fn is_ordering(self) -> bool {
+        matches!(self, CmpOp::Lt | CmpOp::Le | CmpOp::Gt | CmpOp::Ge)
+    }
+}
+
+/// How a field's values are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Free text; `:` matches a substring (case-insensitive).
+    Text,
+    /// Exact value; `none` matches an empty value where allowed.
+    Keyword,
+    /// Integer, all comparisons allowed.
+    Int,
+    /// Timestamp, compared with `<`, `<=`, `>`, `>=`.
+    Time,
+    /// `true`/`false`.
+    Bool,
+    /// Issue label; `none` matches unlabeled issues.
+    Label,
+    /// Issue ID of a dependency edge.
+    Relation,
+}
+
+/// Queryable issue fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// Issue ID; a trailing `*` matches a prefix.
+    Id,
+    Title,
+    Description,
+    Design,
+    Notes,
+    Status,
+    /// Issue type.
+    Type,
+    Priority,
+    Assignee,
+    Owner,
+    Label,
+    Created,
+    Updated,
+    Closed,
+    Due,
+    Defer,
+    /// Parent issue (via a `parent-child` dependency).
+    Parent,
+    /// Issue this one depends on (any dependency type).
+    DependsOn,
+    /// Issue this one blocks.
+    Blocks,
+    /// Has an open blocking dependency.
+    Blocked,
+    Pinned,
+    Template,
+    Ephemeral,
+}
+
+/// Field names and aliases accepted in queries.
+const FIELD_NAMES: &[(&str, Field)] = &[
+    ("id", Field::Id),
+    ("title", Field::Title),
+    ("description", Field::Description),
+    ("desc", Field::Description),
+    ("design", Field::Design),
+    ("notes", Field::Notes),
+    ("status", Field::Status),
+    ("type", Field::Type),
+    ("priority", Field::Priority),
+    ("p", Field::Priority),
+    ("assignee", Field::Assignee),
+    ("owner", Field::Owner),
+    ("label", Field::Label),
+    ("labels", Field::Label),
+    ("created", Field::Created),
+    ("updated", Field::Updated),
+    ("closed", Field::Closed),
+    ("due", Field::Due),
+    ("defer", Field::Defer),
+    ("deferred", Field::Defer),
+    ("parent", Field::Parent),
+    ("depends_on", Field::DependsOn),
+    ("dep", Field::DependsOn),
+    ("blocks", Field::Blocks),
+    ("blocked", Field::Blocked),
+    ("pinned", Field::Pinned),
+    ("template", Field::Template),
+    ("ephemeral", Field::Ephemeral),
+];
+
+impl Field {
+    /// Looks up a field by (case-insensitive) name or alias.
+    pub fn from_name(name: &str) -> Option<Field> {
+        let name = name.to_lowercase();
+        FIELD_NAMES
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, f)| *f)
+    }
+
+    /// How values of this field are interpreted.
+    pub fn kind(self) -> FieldKind {
+        match self {
+            Field::Title | Field::Description | Field::Design | Field::Notes => FieldKind::Text,
+            Field::Id | Field::Status | Field::Type | Field::Assignee | Field::Owner => {
+                FieldKind::Keyword
+            }
+            Field::Priority => FieldKind::Int,
+            Field::Created | Field::Updated | Field::Closed | Field::Due | Field::Defer => {
+                FieldKind::Time
+            }
+            Field::Blocked | Field::Pinned | Field::Template | Field::Ephemeral => FieldKind::Bool,
+            Field::Label => FieldKind::Label,
+            Field::Parent | Field::DependsOn | Field::Blocks => FieldKind::Relation,
+        }
+    }
+
+    /// The `issues` column backing this field, if it is a plain column.
+    pub fn column(self) -> Option<&'static str> {
+        Some(match self {
+            Field::Id => "id",
+            Field::Title => "title",
+            Field::Description => "description",
+            Field::Design => "design",
+            Field::Notes => "notes",
+            Field::Status => "status",
+            Field::Type => "issue_type",
+            Field::Priority => "priority",
+            Field::Assignee => "assignee",
+            Field::Owner => "owner",
+            Field::Created => "created_at",
+            Field::Updated => "updated_at",
+            Field::Closed => "closed_at",
+            Field::Due => "due_at",
+            Field::Defer => "defer_until",
+            Field::Pinned => "pinned",
+            Field::Template => "is_template",
+            Field::Ephemeral => "ephemeral",
+            Field::Label | Field::Parent | Field::DependsOn | Field::Blocks | Field::Blocked => {
+                return None;
+            }
+        })
+    }
+}
+
+/// A typed comparison value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Text or keyword value.
+    Text(String),
+    /// ID prefix (`id:bd-*`).
+    Prefix(String),
+    /// `none`: an empty assignee/owner or no labels.
+    None,
+    /// Integer value.
+    Int(i64),
+    /// Point in time.
+    Time(TimeValue),
+    /// Boolean value.
+    Bool(bool),
+}
+
+/// A time value: absolute, or relative to the evaluation time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeValue {
+    /// A fixed instant (`2026-01-31`, RFC 3339).
+    At(DateTime<Utc>),
+    /// That long before now (`7d`, `12h`).
+    Ago(Duration),
+}
+
+impl TimeValue {
+    /// Resolves the value to an instant relative to `now`.
+    pub fn resolve(self, now: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            TimeValue::At(at) => at,
+            TimeValue::Ago(d) => now - d,
+        }
+    }
+}
@@ -0,0 +1,276 @@
+//! Compiling queries into [`IssueFilter`] and [`WorkFilter`].
+//!
+//! Only conjunctions of conditions the filters can express exactly compile;
+//! anything else (`OR` other than between labels, most `NOT`s, dependency
+//! fields, strict time bounds) returns `None` and should be evaluated with
+//! [`to_sql`](crate::sql::to_sql) instead. A condition on a field the base
+//! filter already constrains also returns `None`, so the result always
+//! matches exactly the issues matching both.
+
+use chrono::{DateTime, Utc};
+
+use beads_core::enums::{IssueType, Status};
+use beads_core::filter::{IssueFilter, WorkFilter};
+
+use crate::ast::{CmpOp, Expr, Field, Predicate, Value};
+
+/// Narrows `base` by the expression, or returns `None` if the expression
+/// cannot be expressed as an [`IssueFilter`]. Relative times resolve
+/// against `now`.
+pub fn to_issue_filter(expr: &Expr, base: &IssueFilter, now: DateTime<Utc>) -> Option<IssueFilter> {
+    let mut filter = base.clone();
+    for conjunct in expr.conjuncts() {
+        match conjunct {
+            Expr::Pred(pred) => apply_issue_pred(&mut filter, pred, false, now)?,
+            Expr::Not(inner) => match inner.as_ref() {
+                Expr::Pred(pred) => apply_issue_pred(&mut filter, pred, true, now)?,
+                _ => return None,
+            },
+            Expr::Or(children) => {
+                let labels = label_alternatives(children)?;
+                if !filter.labels_any.is_empty() {
+                    return None;
+                }
+                filter.labels_any = labels;
+            }
+            Expr::And(_) => return None,
+        }
+    }
+    Some(filter)
+}
+
+/// Narrows `base` by the expression, or returns `None` if the expression
+/// cannot be expressed as a [`WorkFilter`].
+pub fn to_work_filter(expr: &Expr, base: &WorkFilter) -> Option<WorkFilter> {
+    let mut filter = base.clone();
+    for conjunct in expr.conjuncts() {
+        match conjunct {
+            Expr::Pred(pred) if pred.op == CmpOp::Eq => match (pred.field, &pred.value) {
+                (Field::Type, Value::Text(t)) if filter.issue_type.is_none() => {
+                    filter.issue_type = Some(t.clone());
+                }
+                (Field::Priority, Value::Int(p)) if filter.priority.is_none() => {
+                    filter.priority = Some(i32::try_from(*p).ok()?);
+                }
+                (Field::Assignee, Value::None) if filter.assignee.is_none() => {
+                    filter.unassigned = true;
+                }
+                (Field::Assignee, Value::Text(a))
+                    if filter.assignee.is_none() && !filter.unassigned =>
+                {
+                    filter.assignee = Some(a.clone());
+                }
+                (Field::Label, Value::Text(l)) => filter.labels.push(l.clone()),
+                _ => return None,
+            },
+            Expr::Or(children) if filter.labels_any.is_empty() => {
+                filter.labels_any = label_alternatives(children)?;
+            }
+            _ => return None,
+        }
+    }
+    Some(filter)
+}
+
+/// Returns the labels of an `OR` made only of `label:x` conditions.
+fn label_alternatives(children: &[Expr]) -> Option<Vec<String>> {
+    children
+        .iter()
+        .map(|child| match child {
+            Expr::Pred(Predicate {
+                field: Field::Label,
+                op: CmpOp::Eq,
+                value: Value::Text(label),
+                ..
+            }) => Some(label.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Applies one (possibly negated) condition to the filter.
+fn apply_issue_pred(
+    filter: &mut IssueFilter,
+    pred: &Predicate,
+    negated: bool,
+    now: DateTime<Utc>,
+) -> Option<()> {
+    // `a != b` is `NOT a = b`.
+    let (op, negated) = match pred.op {
+        CmpOp::Ne => (CmpOp::Eq, !negated),
+        op => (op, negated),
+    };
+
+    match (pred.field, op, &pred.value, negated) {
+        (Field::Status, CmpOp::Eq, Value::Text(s), false) if filter.status.is_none() => {
+            filter.status = Some(Status::from(s.as_str()));
+        }
+        (Field::Status, CmpOp::Eq, Value::Text(s), true) => {
+            filter.exclude_status.push(Status::from(s.as_str()));
+        }
+        (Field::Type, CmpOp::Eq, Value::Text(t), false) if filter.issue_type.is_none() => {
+            filter.issue_type = Some(IssueType::from(t.as_str()));
+        }
+        (Field::Type, CmpOp::Eq, Value::Text(t), true) => {
+            filter.exclude_types.push(IssueType::from(t.as_str()));
+        }
+        (Field::Priority, op, Value::Int(p), false) => {
+            let p = i32::try_from(*p).ok()?;
+            match op {
+                CmpOp::Eq if filter.priority.is_none() => filter.priority = Some(p),
+                CmpOp::Le => {
+                    filter.priority_max = Some(filter.priority_max.map_or(p, |m| m.min(p)))
+                }
+                CmpOp::Lt => {
+                    let p = p - 1;
+                    filter.priority_max = Some(filter.priority_max.map_or(p, |m| m.min(p)));
+                }
+                CmpOp::Ge => {
+                    filter.priority_min = Some(filter.priority_min.map_or(p, |m| m.max(p)))
+                }
+                CmpOp::Gt => {
+                    let p = p + 1;
+                    filter.priority_min = Some(filter.priority_min.map_or(p, |m| m.max(p)));
+                }
+                _ => return None,
+            }
+        }
+        (Field::Assignee, CmpOp::Eq, Value::None, false) if filter.assignee.is_none() => {
+            filter.no_assignee = true;
+        }
+        (Field::Assignee, CmpOp::Eq, Value::Text(a), false)
+            if filter.assignee.is_none() && !filter.no_assignee =>
+        {
+            filter.assignee = Some(a.clone());
+        }
+        (Field::Label, CmpOp::Eq, Value::Text(l), false) if !filter.no_labels => {
+            filter.labels.push(l.clone());
+        }
+        (Field::Label, CmpOp::Eq, Value::None, false)
+            if filter.labels.is_empty() && filter.labels_any.is_empty() =>
+        {
+            filter.no_labels = true;
+        }
+        (Field::Id, CmpOp::Eq, Value::Text(id), false)
+            if filter.ids.is_empty() && filter.id_prefix.is_none() =>
+        {
+            filter.ids = vec![id.clone()];
+        }
+        (Field::Id, CmpOp::Eq, Value::Prefix(prefix), false)
+            if filter.ids.is_empty() && filter.id_prefix.is_none() =>
+        {
+            filter.id_prefix = Some(prefix.clone());
+        }
+        (Field::Title, CmpOp::Eq, Value::Text(t), false) if filter.title_contains.is_none() => {
+            filter.title_contains = Some(t.clone());
+        }
+        (Field::Description, CmpOp::Eq, Value::Text(t), false)
+            if filter.description_contains.is_none() =>
+        {
+            filter.description_contains = Some(t.clone());
+        }
+        (Field::Notes, CmpOp::Eq, Value::Text(t), false) if filter.notes_contains.is_none() => {
+            filter.notes_contains = Some(t.clone());
+        }
+        (Field::Pinned, CmpOp::Eq, Value::Bool(b), negated) if filter.pinned.is_none() => {
+            filter.pinned = Some(*b != negated);
+        }
+        (Field::Template, CmpOp::Eq, Value::Bool(b), negated) if filter.is_template.is_none() => {
+            filter.is_template = Some(*b != negated);
+        }
+        (Field::Ephemeral, CmpOp::Eq, Value::Bool(b), negated) if filter.ephemeral.is_none() => {
+            filter.ephemeral = Some(*b != negated);
+        }
+        (Field::Created | Field::Updated | Field::Closed, op, Value::Time(t), false) => {
+            // Filter bounds are inclusive, so only `>=` and `<=` map exactly.
+            let at = t.resolve(now);
+            let (after, before) = match pred.field {
+                Field::Created => (&mut filter.created_after, &mut filter.created_before),
+                Field::Updated => (&mut filter.updated_after, &mut filter.updated_before),
+                _ => (&mut filter.closed_after, &mut filter.closed_before),
+            };
+            match op {
+                CmpOp::Ge => *after = Some(after.map_or(at, |a| a.max(at))),
+                CmpOp::Le => *before = Some(before.map_or(at, |b| b.min(at))),
+                _ => return None,
+            }
+        }
+        _ => return None,
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_expr;
+    use chrono::Duration;
+
+    fn issue_filter(q: &str) -> Option<IssueFilter> {
+        to_issue_filter(&parse_expr(q).unwrap(), &IssueFilter::default(), Utc::now())
+    }
+
+    #[test]
+    fn conjunctions_compile_to_issue_filter() {
+        let f = issue_filter(
+            "status:open type!=epic p<=1 p>0 label:a (label:b OR label:c) assignee:none",
+        )
+        .unwrap();
+        assert_eq!(f.status, Some(Status::Open));
+        assert_eq!(f.exclude_types, vec![IssueType::Epic]);
+        assert_eq!(f.priority_max, Some(1));
+        assert_eq!(f.priority_min, Some(1));
+        assert_eq!(f.labels, vec!["a"]);
+        assert_eq!(f.labels_any, vec!["b", "c"]);
+        assert!(f.no_assignee);
+    }
+
+    #[test]
+    fn time_bounds_resolve_against_now() {
+        let now = Utc::now();
+        let f = to_issue_filter(
+            &parse_expr("updated>=7d created<=2026-01-31").unwrap(),
+            &IssueFilter::default(),
+            now,
+        )
+        .unwrap();
+        assert_eq!(f.updated_after, Some(now - Duration::days(7)));
+        assert!(f.created_before.is_some());
+    }
+
+    #[test]
+    fn inexpressible_queries_do_not_compile() {
+        assert!(issue_filter("status:open OR p:0").is_none());
+        assert!(issue_filter("NOT assignee:none").is_none());
+        assert!(issue_filter("updated>7d").is_none());
+        assert!(issue_filter("blocked:true").is_none());
+        assert!(issue_filter("NOT (label:a OR label:b)").is_none());
+        assert!(issue_filter("status:open status:closed").is_none());
+    }
+
+    #[test]
+    fn base_filter_conflicts_fall_back() {
+        let base = IssueFilter {
+            is_template: Some(false),
+            ..IssueFilter::default()
+        };
+        let expr = parse_expr("template:true").unwrap();
+        assert!(to_issue_filter(&expr, &base, Utc::now()).is_none());
+        let expr = parse_expr("status:open").unwrap();
+        let f = to_issue_filter(&expr, &base, Utc::now()).unwrap();
+        assert_eq!(f.is_template, Some(false));
+    }
+
+    #[test]
+    fn work_filter_compilation() {
+        let expr = parse_expr("type:bug p:1 assignee:none label:x").unwrap();
+        let f = to_work_filter(&expr, &WorkFilter::default()).unwrap();
+        assert_eq!(f.issue_type.as_deref(), Some("bug"));
+        assert_eq!(f.priority, Some(1));
+        assert!(f.unassigned);
+        assert_eq!(f.labels, vec!["x"]);
+
+        let expr = parse_expr("status:open").unwrap();
+        assert!(to_work_filter(&expr, &WorkFilter::default()).is_none());
+    }
+}
@@ -0,0 +1,91 @@
+//! Query parse errors with source spans.
+
+use std::fmt;
+
+/// A byte range in the query source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Offset of the first byte.
+    pub start: usize,
+    /// Offset one past the last byte.
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a span covering `start..end`.
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// An error in a query expression, pointing at the offending text.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message} (at {span})")]
+pub struct QueryError {
+    /// What went wrong.
+    pub message: String,
+    /// Where in the source it went wrong.
+    pub span: Span,
+}
+
+impl QueryError {
+    pub(crate) fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// Renders the error under the source with a caret marker, e.g.
+    ///
+    /// ```text
+    /// status:open AND priorty<=1
+    ///                 ^^^^^^^ unknown field 'priorty'
+    /// ```
+    pub fn render(&self, source: &str) -> String {
+        let start = source
+            .get(..self.span.start)
+            .map_or(0, |s| s.chars().count());
+        let width = source
+            .get(self.span.start..self.span.end)
+            .map_or(1, |s| s.chars().count())
+            .max(1);
+        format!(
+            "{source}\n{}{} {}",
+            " ".repeat(start),
+            "^".repeat(width),
+            self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_points_at_span() {
+        let err = QueryError::new("unknown field 'priorty'", Span::new(16, 23));
+        assert_eq!(
+            err.render("status:open AND priorty<=1"),
+            "status:open AND priorty<=1\n                ^^^^^^^ unknown field 'priorty'"
+        );
+    }
+
+    #[test]
+    fn render_marks_end_of_input() {
+        let err = QueryError::new("expected a value", Span::new(7, 7));
+        assert_eq!(err.render("status:"), "status:\n       ^ expected a value");
+    }
+}
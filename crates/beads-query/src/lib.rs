@@ -1 +1,92 @@
 //! Query engine for the beads system.
+//!
+//! A small filter language over issues:
+//!
+//! ```text
+//! status:open AND (label:backend OR priority<=1) AND updated>7d AND NOT assignee:none
+//! ```
+//!
+//! Conditions are `field <op> value` with `:`/`=`, `!=`, `<`, `<=`, `>`, `>=`;
+//! juxtaposed conditions are ANDed. Queries compile into an
+//! [`IssueFilter`]/[`WorkFilter`] where the filter can express them exactly,
+//! and into parameterised SQL otherwise.
+
+pub mod ast;
+pub mod compile;
+pub mod error;
+pub mod parser;
+pub mod sql;
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use beads_core::filter::{IssueFilter, WorkFilter};
+
+pub use ast::{CmpOp, Expr, Field, Predicate, Value};
+pub use error::{QueryError, Span};
+pub use sql::{SqlParam, SqlWhere};
+
+/// A parsed query together with its source text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+    source: String,
+}
+
+impl Query {
+    /// Parses a query expression.
+    pub fn parse(source: &str) -> Result<Self, QueryError> {
+        Ok(Self {
+            expr: parser::parse_expr(source)?,
+            source: source.to_string(),
+        })
+    }
+
+    /// The parsed expression.
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// The query as written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns `true` if any condition in the query is on `field`.
+    pub fn mentions(&self, field: Field) -> bool {
+        let mut found = false;
+        self.expr.visit(&mut |pred| found |= pred.field == field);
+        found
+    }
+
+    /// See [`compile::to_issue_filter`].
+    pub fn to_issue_filter(&self, base: &IssueFilter, now: DateTime<Utc>) -> Option<IssueFilter> {
+        compile::to_issue_filter(&self.expr, base, now)
+    }
+
+    /// See [`compile::to_work_filter`].
+    pub fn to_work_filter(&self, base: &WorkFilter) -> Option<WorkFilter> {
+        compile::to_work_filter(&self.expr, base)
+    }
+
+    /// See [`sql::to_sql`].
+    pub fn to_sql(&self, alias: &str, first_param: usize, now: DateTime<Utc>) -> SqlWhere {
+        sql::to_sql(&self.expr, alias, first_param, now)
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
@@ -0,0 +1,544 @@
+//! Recursive-descent parser for query expressions.
+//!
+//! Grammar (keywords are case-insensitive; adjacent conditions are ANDed):
+//!
+//! ```text
+//! query     := or
+//! or        := and ("OR" and)*
+//! and       := unary (["AND"] unary)*
+//! unary     := "NOT" unary | "(" or ")" | predicate
+//! predicate := field op value
+//! op        := ":" | "=" | "!=" | "<" | "<=" | ">" | ">="
+//! value     := bare-word | "\"" quoted "\""
+//! ```
+
+use chrono::Local;
+
+use beads_core::enums::{IssueType, Status};
+
+use crate::ast::{CmpOp, Expr, Field, FieldKind, Predicate, TimeValue, Value};
+use crate::error::{QueryError, Span};
+
+/// Parses a query expression.
+pub fn parse_expr(input: &str) -> Result<Expr, QueryError> {
+    let mut parser = Parser {
+        src: input,
+        pos: 0,
+        peeked: None,
+    };
+    if parser.peek()?.kind == TokKind::End {
+        return Err(QueryError::new("empty query", Span::new(0, input.len())));
+    }
+    let expr = parser.parse_or()?;
+    let tok = parser.next()?;
+    match tok.kind {
+        TokKind::End => Ok(expr),
+        TokKind::RParen => Err(QueryError::new("unmatched ')'", tok.span)),
+        _ => Err(QueryError::new(
+            "expected AND, OR or end of query",
+            tok.span,
+        )),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokKind {
+    LParen,
+    RParen,
+    And,
+    Or,
+    Not,
+    Pred(Predicate),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Tok {
+    kind: TokKind,
+    span: Span,
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+    peeked: Option<Tok>,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Expr, QueryError> {
+        let mut children = vec![self.parse_and()?];
+        while self.peek()?.kind == TokKind::Or {
+            let op = self.next()?;
+            children.push(self.parse_operand(&op, "OR", Self::parse_and)?);
+        }
+        Ok(flatten(children, Expr::Or))
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, QueryError> {
+        let mut children = vec![self.parse_unary()?];
+        loop {
+            match self.peek()?.kind {
+                TokKind::And => {
+                    let op = self.next()?;
+                    children.push(self.parse_operand(&op, "AND", Self::parse_unary)?);
+                }
+                // Implicit AND between adjacent conditions.
+                TokKind::Not | TokKind::LParen | TokKind::Pred(_) => {
+                    children.push(self.parse_unary()?);
+                }
+                _ => break,
+            }
+        }
+        Ok(flatten(children, Expr::And))
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, QueryError> {
+        let tok = self.next()?;
+        match tok.kind {
+            TokKind::Not => {
+                let inner = self.parse_operand(&tok, "NOT", Self::parse_unary)?;
+                Ok(Expr::Not(Box::new(inner)))
+            }
+            TokKind::LParen => {
+                if self.peek()?.kind == TokKind::RParen {
+                    let close = self.next()?;
+                    return Err(QueryError::new(
+                        "empty parentheses",
+                        tok.span.to(close.span),
+                    ));
+                }
+                let inner = self.parse_or()?;
+                let close = self.next()?;
+                match close.kind {
+                    TokKind::RParen => Ok(inner),
+                    TokKind::End => Err(QueryError::new("unclosed '('", tok.span)),
+                    _ => Err(QueryError::new("expected ')'", close.span)),
+                }
+            }
+            TokKind::Pred(pred) => Ok(Expr::Pred(pred)),
+            TokKind::RParen => Err(QueryError::new("unexpected ')'", tok.span)),
+            TokKind::End => Err(QueryError::new("expected a condition", tok.span)),
+            TokKind::And | TokKind::Or => Err(QueryError::new(
+                "expected a condition before this operator",
+                tok.span,
+            )),
+        }
+    }
+
+    /// Parses the operand following a keyword, reporting a missing operand
+    /// at the keyword.
+    fn parse_operand(
+        &mut self,
+        keyword: &Tok,
+        name: &str,
+        parse: fn(&mut Self) -> Result<Expr, QueryError>,
+    ) -> Result<Expr, QueryError> {
+        match self.peek()?.kind {
+            TokKind::End | TokKind::RParen | TokKind::And | TokKind::Or => Err(QueryError::new(
+                format!("expected a condition after {name}"),
+                keyword.span,
+            )),
+            _ => parse(self),
+        }
+    }
+
+    fn peek(&mut self) -> Result<&Tok, QueryError> {
+        if self.peeked.is_none() {
+            let tok = self.lex()?;
+            self.peeked = Some(tok);
+        }
+        Ok(self.peeked.as_ref().expect("peeked token"))
+    }
+
+    fn next(&mut self) -> Result<Tok, QueryError> {
+        match self.peeked.take() {
+            Some(tok) => Ok(tok),
+            None => self.lex(),
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn lex(&mut self) -> Result<Tok, QueryError> {
+        while self.peek_char().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+        let start = self.pos;
+        let Some(c) = self.peek_char() else {
+            return Ok(Tok {
+                kind: TokKind::End,
+                span: Span::new(start, start),
+            });
+        };
+        match c {
+            '(' | ')' => {
+                self.bump();
+                let kind = if c == '(' {
+                    TokKind::LParen
+                } else {
+                    TokKind::RParen
+                };
+                return Ok(Tok {
+                    kind,
+                    span: Span::new(start, self.pos),
+                });
+            }
+            '"' => {
+                self.bump();
+                return Err(QueryError::new(
+                    "expected a field name (quote only values, e.g. title:\"two words\")",
+                    Span::new(start, self.pos),
+                ));
+            }
+            _ => {}
+        }
+
+        while self
+            .peek_char()
+            .is_some_and(|c| !c.is_whitespace() && !"()\":=!<>".contains(c))
+        {
+            self.bump();
+        }
+        let word = &self.src[start..self.pos];
+        let word_span = Span::new(start, self.pos);
+
+        if !self.peek_char().is_some_and(|c| ":=!<>".contains(c)) {
+            let kind = match word.to_ascii_uppercase().as_str() {
+                "AND" => TokKind::And,
+                "OR" => TokKind::Or,
+                "NOT" => TokKind::Not,
+                "" => {
+                    self.bump();
+                    return Err(QueryError::new(
+                        "expected a field name",
+                        Span::new(start, self.pos),
+                    ));
+                }
+                _ => {
+                    return Err(QueryError::new(
+                        format!("expected an operator after '{word}' (e.g. title:{word})"),
+                        word_span,
+                    ));
+                }
+            };
+            return Ok(Tok {
+                kind,
+                span: word_span,
+            });
+        }
+        if word.is_empty() {
+            let op_start = self.pos;
+            self.bump();
+            return Err(QueryError::new(
+                "expected a field name",
+                Span::new(op_start, self.pos),
+            ));
+        }
+
+        let field = Field::from_name(word)
+            .ok_or_else(|| QueryError::new(format!("unknown field '{word}'"), word_span))?;
+        let (op, op_span) = self.lex_op()?;
+        let (raw, quoted, value_span) = self.lex_value(word, op_span)?;
+        let value = typed_value(field, word, op, op_span, &raw, quoted, value_span)?;
+
+        Ok(Tok {
+            kind: TokKind::Pred(Predicate {
+                field,
+                op,
+                value,
+                span: word_span.to(value_span),
+            }),
+            span: word_span.to(value_span),
+        })
+    }
+
+    fn lex_op(&mut self) -> Result<(CmpOp, Span), QueryError> {
+        let start = self.pos;
+        let rest = self.rest();
+        let (op, len) = if rest.starts_with("!=") {
+            (CmpOp::Ne, 2)
+        } else if rest.starts_with("<=") {
+            (CmpOp::Le, 2)
+        } else if rest.starts_with(">=") {
+            (CmpOp::Ge, 2)
+        } else if rest.starts_with("==") {
+            (CmpOp::Eq, 2)
+        } else if rest.starts_with(':') || rest.starts_with('=') {
+            (CmpOp::Eq, 1)
+        } else if rest.starts_with('<') {
+            (CmpOp::Lt, 1)
+        } else if rest.starts_with('>') {
+            (CmpOp::Gt, 1)
+        } else {
+            return Err(QueryError::new(
+                "expected an operator (:, =, !=, <, <=, >, >=)",
+                Span::new(start, start + 1),
+            ));
+        };
+        self.pos += len;
+        Ok((op, Span::new(start, self.pos)))
+    }
+
+    fn lex_value(
+        &mut self,
+        field: &str,
+        op_span: Span,
+    ) -> Result<(String, bool, Span), QueryError> {
+        let start = self.pos;
+        if self.peek_char() == Some('"') {
+            self.bump();
+            let mut value = String::new();
+            loop {
+                match self.bump() {
+                    Some('"') => return Ok((value, true, Span::new(start, self.pos))),
+                    Some('\\') => match self.bump() {
+                        Some(c) => value.push(c),
+                        None => break,
+                    },
+                    Some(c) => value.push(c),
+                    None => break,
+                }
+            }
+            return Err(QueryError::new(
+                "unterminated string",
+                Span::new(start, self.pos),
+            ));
+        }
+
+        while self
+            .peek_char()
+            .is_some_and(|c| !c.is_whitespace() && c != '(' && c != ')')
+        {
+            self.bump();
+        }
+        if self.pos == start {
+            return Err(QueryError::new(
+                format!("expected a value after '{field}'"),
+                Span::new(op_span.end, op_span.end),
+            ));
+        }
+        Ok((
+            self.src[start..self.pos].to_string(),
+            false,
+            Span::new(start, self.pos),
+        ))
+    }
+}
+
+/// Collapses a single-element list to its element.
+fn flatten(mut children: Vec<Expr>, wrap: fn(Vec<Expr>) -> Expr) -> Expr {
+    if children.len() == 1 {
+        return children.pop().expect("one child");
+    }
+    wrap(children)
+}
+
+/// Checks the operator against the field and converts the raw value.
+fn typed_value(
+    field: Field,
+    name: &str,
+    op: CmpOp,
+    op_span: Span,
+    raw: &str,
+    quoted: bool,
+    span: Span,
+) -> Result<Value, QueryError> {
+    let kind = field.kind();
+    if op.is_ordering() && !matches!(kind, FieldKind::Int | FieldKind::Time) {
+        return Err(QueryError::new(
+            format!("'{name}' only supports :, = and !="),
+            op_span,
+        ));
+    }
+    let none = !quoted && raw.eq_ignore_ascii_case("none");
+
+    match kind {
+        FieldKind::Text | FieldKind::Relation => Ok(Value::Text(raw.to_string())),
+        FieldKind::Keyword => match field {
+            Field::Assignee | Field::Owner if none => Ok(Value::None),
+            Field::Id if !quoted && raw.len() > 1 && raw.ends_with('*') => {
+                Ok(Value::Prefix(raw[..raw.len() - 1].to_string()))
+            }
+            Field::Type => Ok(Value::Text(
+                IssueType::from(raw).normalize().as_str().to_string(),
+            )),
+            Field::Status => Ok(Value::Text(Status::from(raw).as_str().to_string())),
+            _ => Ok(Value::Text(raw.to_string())),
+        },
+        FieldKind::Label if none => Ok(Value::None),
+        FieldKind::Label => Ok(Value::Text(raw.to_string())),
+        FieldKind::Int => {
+            let digits = raw
+                .strip_prefix(['P', 'p'])
+                .filter(|_| field == Field::Priority)
+                .unwrap_or(raw);
+            digits
+                .parse::<i64>()
+                .map(Value::Int)
+                .map_err(|_| QueryError::new(format!("invalid {name} '{raw}'"), span))
+        }
+        FieldKind::Time => {
+            if !op.is_ordering() {
+                return Err(QueryError::new(
+                    format!("'{name}' needs <, <=, > or >= (e.g. {name}>7d)"),
+                    op_span,
+                ));
+            }
+            parse_time(raw).map(Value::Time).ok_or_else(|| {
+                QueryError::new(
+                    format!("invalid time '{raw}' (use e.g. 7d, 1h30m, 2026-01-31, yesterday)"),
+                    span,
+                )
+            })
+        }
+        FieldKind::Bool => match raw.to_ascii_lowercase().as_str() {
+            "true" | "yes" | "1" => Ok(Value::Bool(true)),
+            "false" | "no" | "0" => Ok(Value::Bool(false)),
+            _ => Err(QueryError::new(
+                format!("invalid {name} '{raw}' (use true or false)"),
+                span,
+            )),
+        },
+    }
+}
+
+/// Parses a duration (`7d`, `1h30m`: that long ago) or any time accepted
+/// by [`beads_timeparsing::parse_time`] in the local timezone.
+fn parse_time(raw: &str) -> Option<TimeValue> {
+    if let Ok(d) = beads_timeparsing::parse_duration(raw) {
+        return Some(TimeValue::Ago(d));
+    }
+    beads_timeparsing::parse_time(raw, &Local::now())
+        .ok()
+        .map(TimeValue::At)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn pred(field: Field, op: CmpOp, value: Value) -> (Field, CmpOp, Value) {
+        (field, op, value)
+    }
+
+    fn preds(expr: &Expr) -> Vec<(Field, CmpOp, Value)> {
+        let mut out = Vec::new();
+        expr.visit(&mut |p| out.push((p.field, p.op, p.value.clone())));
+        out
+    }
+
+    #[test]
+    fn parses_full_example() {
+        let expr = parse_expr(
+            "status:open AND (label:backend OR priority<=1) AND updated>7d AND NOT assignee:none",
+        )
+        .unwrap();
+        let Expr::And(children) = &expr else {
+            panic!("expected AND, got {expr:?}");
+        };
+        assert_eq!(children.len(), 4);
+        assert!(matches!(children[1], Expr::Or(ref c) if c.len() == 2));
+        assert!(matches!(children[3], Expr::Not(_)));
+        assert_eq!(
+            preds(&expr),
+            vec![
+                pred(Field::Status, CmpOp::Eq, Value::Text("open".into())),
+                pred(Field::Label, CmpOp::Eq, Value::Text("backend".into())),
+                pred(Field::Priority, CmpOp::Le, Value::Int(1)),
+                pred(
+                    Field::Updated,
+                    CmpOp::Gt,
+                    Value::Time(TimeValue::Ago(Duration::days(7)))
+                ),
+                pred(Field::Assignee, CmpOp::Eq, Value::None),
+            ]
+        );
+    }
+
+    #[test]
+    fn implicit_and_and_precedence() {
+        // AND binds tighter than OR.
+        let expr = parse_expr("type:bug p:0 or label:urgent").unwrap();
+        let Expr::Or(children) = &expr else {
+            panic!("expected OR, got {expr:?}");
+        };
+        assert!(matches!(children[0], Expr::And(ref c) if c.len() == 2));
+        assert!(matches!(children[1], Expr::Pred(_)));
+    }
+
+    #[test]
+    fn values_are_typed() {
+        let expr = parse_expr(
+            r#"id:bd-a* title:"two words" p:P2 pinned:yes type:feat closed<2026-01-31 label:gt:agent"#,
+        )
+        .unwrap();
+        let values: Vec<Value> = preds(&expr).into_iter().map(|p| p.2).collect();
+        assert_eq!(values[0], Value::Prefix("bd-a".into()));
+        assert_eq!(values[1], Value::Text("two words".into()));
+        assert_eq!(values[2], Value::Int(2));
+        assert_eq!(values[3], Value::Bool(true));
+        assert_eq!(values[4], Value::Text("feature".into()));
+        assert!(matches!(values[5], Value::Time(TimeValue::At(_))));
+        assert_eq!(values[6], Value::Text("gt:agent".into()));
+    }
+
+    #[test]
+    fn quoted_none_is_literal() {
+        let expr = parse_expr(r#"assignee:"none""#).unwrap();
+        assert_eq!(preds(&expr)[0].2, Value::Text("none".into()));
+    }
+
+    fn err(input: &str) -> (String, Span) {
+        let e = parse_expr(input).unwrap_err();
+        (e.message, e.span)
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(
+            err("status:open AND priorty<=1"),
+            ("unknown field 'priorty'".into(), Span::new(16, 23))
+        );
+        assert_eq!(
+            err("priority<=high"),
+            ("invalid priority 'high'".into(), Span::new(10, 14))
+        );
+        assert_eq!(
+            err("label<3"),
+            ("'label' only supports :, = and !=".into(), Span::new(5, 6))
+        );
+        assert_eq!(err("updated:7d").1, Span::new(7, 8));
+        assert_eq!(
+            err("status:"),
+            ("expected a value after 'status'".into(), Span::new(7, 7))
+        );
+        assert_eq!(
+            err("(status:open"),
+            ("unclosed '('".into(), Span::new(0, 1))
+        );
+        assert_eq!(
+            err("status:open)"),
+            ("unmatched ')'".into(), Span::new(11, 12))
+        );
+        assert_eq!(
+            err("status:open AND"),
+            ("expected a condition after AND".into(), Span::new(12, 15))
+        );
+        assert_eq!(err("login").1, Span::new(0, 5));
+        assert_eq!(err(r#"title:"oops"#).1, Span::new(6, 11));
+        assert_eq!(err("   ").0, "empty query");
+    }
+}
@@ -0,0 +1,232 @@
+//! Compiling queries into parameterised SQL.
+//!
+//! The generated condition refers to the `issues` table through a caller
+//! supplied alias and uses correlated subqueries over `labels` and
+//! `dependencies` for label and dependency fields. Parameters are numbered
+//! (`?N`) starting at a caller supplied index so the condition can be
+//! spliced into a larger statement.
+
+use chrono::{DateTime, Utc};
+
+use crate::ast::{CmpOp, Expr, Field, FieldKind, Predicate, Value};
+
+/// Dependency types that keep an issue from being ready.
+const BLOCKING_TYPES: &str = "'blocks', 'parent-child', 'conditional-blocks', 'waits-for'";
+
+/// Statuses of a dependency target that still block.
+const OPEN_STATUSES: &str = "'open', 'in_progress', 'blocked', 'deferred', 'hooked'";
+
+/// A bound SQL parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlParam {
+    /// Text value.
+    Text(String),
+    /// Integer value.
+    Int(i64),
+    /// Timestamp; the storage layer formats it the way it stores times.
+    Time(DateTime<Utc>),
+}
+
+/// A SQL boolean condition with its parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SqlWhere {
+    /// The condition, with `?N` placeholders.
+    pub sql: String,
+    /// Parameter values, in placeholder order.
+    pub params: Vec<SqlParam>,
+}
+
+/// Compiles the expression into a condition over `alias` (the `issues`
+/// table). Placeholders start at `?first_param`; relative times resolve
+/// against `now`.
+pub fn to_sql(expr: &Expr, alias: &str, first_param: usize, now: DateTime<Utc>) -> SqlWhere {
+    let mut builder = Builder {
+        alias,
+        now,
+        next: first_param,
+        params: Vec::new(),
+    };
+    let sql = builder.expr(expr);
+    SqlWhere {
+        sql,
+        params: builder.params,
+    }
+}
+
+struct Builder<'a> {
+    alias: &'a str,
+    now: DateTime<Utc>,
+    next: usize,
+    params: Vec<SqlParam>,
+}
+
+impl Builder<'_> {
+    fn bind(&mut self, param: SqlParam) -> String {
+        let placeholder = format!("?{}", self.next);
+        self.next += 1;
+        self.params.push(param);
+        placeholder
+    }
+
+    fn expr(&mut self, expr: &Expr) -> String {
+        match expr {
+            Expr::And(children) => self.join(children, " AND "),
+            Expr::Or(children) => self.join(children, " OR "),
+            Expr::Not(inner) => format!("NOT {}", self.expr(inner)),
+            Expr::Pred(pred) => format!("({})", self.pred(pred)),
+        }
+    }
+
+    fn join(&mut self, children: &[Expr], sep: &str) -> String {
+        let parts: Vec<String> = children.iter().map(|c| self.expr(c)).collect();
+        format!("({})", parts.join(sep))
+    }
+
+    fn pred(&mut self, pred: &Predicate) -> String {
+        let a = self.alias;
+        let negate = pred.op == CmpOp::Ne;
+        let not = if negate { "NOT " } else { "" };
+
+        match (pred.field.kind(), &pred.value) {
+            (FieldKind::Text, Value::Text(text)) => {
+                let column = pred.field.column().expect("text column");
+                let p = self.bind(SqlParam::Text(format!("%{}%", escape_like(text))));
+                format!("COALESCE({a}.{column}, '') {not}LIKE {p} ESCAPE '\\'")
+            }
+            (_, Value::Prefix(prefix)) => {
+                let column = pred.field.column().expect("prefix column");
+                let p = self.bind(SqlParam::Text(format!("{}%", escape_like(prefix))));
+                format!("{a}.{column} {not}LIKE {p} ESCAPE '\\'")
+            }
+            (FieldKind::Keyword, Value::None) => {
+                let column = pred.field.column().expect("keyword column");
+                format!("COALESCE({a}.{column}, '') {} ''", pred.op.sql())
+            }
+            (FieldKind::Keyword, Value::Text(text)) => {
+                let column = pred.field.column().expect("keyword column");
+                let p = self.bind(SqlParam::Text(text.clone()));
+                format!("COALESCE({a}.{column}, '') {} {p}", pred.op.sql())
+            }
+            (FieldKind::Int, Value::Int(n)) => {
+                let column = pred.field.column().expect("int column");
+                let p = self.bind(SqlParam::Int(*n));
+                format!("{a}.{column} {} {p}", pred.op.sql())
+            }
+            (FieldKind::Time, Value::Time(t)) => {
+                let column = pred.field.column().expect("time column");
+                let p = self.bind(SqlParam::Time(t.resolve(self.now)));
+                // An unset time compares false, so `NOT` matches it.
+                format!("COALESCE({a}.{column} {} {p}, 0)", pred.op.sql())
+            }
+            (FieldKind::Bool, Value::Bool(b)) if pred.field == Field::Blocked => {
+                let exists = if *b != negate { "EXISTS" } else { "NOT EXISTS" };
+                format!(
+                    "{exists} (SELECT 1 FROM dependencies qd \
+                     JOIN issues qb ON qb.id = qd.depends_on_id \
+                     WHERE qd.issue_id = {a}.id AND qd.type IN ({BLOCKING_TYPES}) \
+                     AND qb.status IN ({OPEN_STATUSES}))"
+                )
+            }
+            (FieldKind::Bool, Value::Bool(b)) => {
+                let column = pred.field.column().expect("bool column");
+                let p = self.bind(SqlParam::Int(i64::from(*b)));
+                format!("COALESCE({a}.{column}, 0) {} {p}", pred.op.sql())
+            }
+            (FieldKind::Label, Value::None) => {
+                let exists = if negate { "EXISTS" } else { "NOT EXISTS" };
+                format!("{exists} (SELECT 1 FROM labels ql WHERE ql.issue_id = {a}.id)")
+            }
+            (FieldKind::Label, Value::Text(label)) => {
+                let p = self.bind(SqlParam::Text(label.clone()));
+                format!(
+                    "{not}EXISTS (SELECT 1 FROM labels ql \
+                     WHERE ql.issue_id = {a}.id AND ql.label = {p})"
+                )
+            }
+            (FieldKind::Relation, Value::Text(id)) => {
+                let p = self.bind(SqlParam::Text(id.clone()));
+                let condition = match pred.field {
+                    Field::Parent => format!(
+                        "qd.issue_id = {a}.id AND qd.depends_on_id = {p} AND qd.type = 'parent-child'"
+                    ),
+                    Field::DependsOn => format!("qd.issue_id = {a}.id AND qd.depends_on_id = {p}"),
+                    _ => format!(
+                        "qd.issue_id = {p} AND qd.depends_on_id = {a}.id \
+                         AND qd.type IN ({BLOCKING_TYPES})"
+                    ),
+                };
+                format!("{not}EXISTS (SELECT 1 FROM dependencies qd WHERE {condition})")
+            }
+            // The parser only produces values matching the field kind.
+            _ => "0".to_string(),
+        }
+    }
+}
+
+/// Escapes `%`, `_` and `\` for a `LIKE ... ESCAPE '\'` pattern.
+fn escape_like(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_expr;
+    use pretty_assertions::assert_eq;
+
+    fn sql(q: &str) -> SqlWhere {
+        to_sql(&parse_expr(q).unwrap(), "i", 3, Utc::now())
+    }
+
+    #[test]
+    fn placeholders_start_at_first_param() {
+        let w = sql("status:open AND (label:backend OR priority<=1)");
+        assert_eq!(
+            w.sql,
+            "((COALESCE(i.status, '') = ?3) AND ((EXISTS (SELECT 1 FROM labels ql \
+             WHERE ql.issue_id = i.id AND ql.label = ?4)) OR (i.priority <= ?5)))"
+        );
+        assert_eq!(
+            w.params,
+            vec![
+                SqlParam::Text("open".into()),
+                SqlParam::Text("backend".into()),
+                SqlParam::Int(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn negation_and_none() {
+        let w = sql("NOT assignee:none label!=x");
+        assert_eq!(
+            w.sql,
+            "(NOT (COALESCE(i.assignee, '') = '') AND (NOT EXISTS (SELECT 1 FROM labels ql \
+             WHERE ql.issue_id = i.id AND ql.label = ?3)))"
+        );
+    }
+
+    #[test]
+    fn text_patterns_are_escaped() {
+        let w = sql("title:100%_done");
+        assert_eq!(w.params, vec![SqlParam::Text("%100\\%\\_done%".into())]);
+    }
+
+    #[test]
+    fn relative_times_resolve_against_now() {
+        let now = Utc::now();
+        let w = to_sql(&parse_expr("updated>7d").unwrap(), "issues", 1, now);
+        assert_eq!(w.sql, "(COALESCE(issues.updated_at > ?1, 0))");
+        assert_eq!(
+            w.params,
+            vec![SqlParam::Time(now - chrono::Duration::days(7))]
+        );
+    }
+}
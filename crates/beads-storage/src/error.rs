@@ -67,19 +67,45 @@ pub enum StorageError {
         reason: String,
     },
 
+    /// The database was written by a newer binary than this one.
+    #[error(
+        "database schema version {found} is newer than this bd supports ({supported}); upgrade bd"
+    )]
+    SchemaTooNew {
+        /// Schema version recorded in the database.
+        found: i32,
+        /// Newest schema version this binary knows.
+        supported: i32,
+    },
+
     /// A raw SQLite query error.
+    ///
+    /// The wrapped error is part of the message rather than a `source()`,
+    /// so error chains don't print it twice.
     #[error("query error: {0}")]
-    Query(#[from] rusqlite::Error),
+    Query(rusqlite::Error),
 
     /// JSON serialization/deserialization failed.
     #[error("serialization error: {0}")]
-    Serialization(#[from] serde_json::Error),
+    Serialization(serde_json::Error),
 
     /// Catch-all for unexpected internal errors.
     #[error("internal error: {0}")]
     Internal(String),
 }
 
+impl From<rusqlite::Error> for StorageError {
+    fn from(err: rusqlite::Error) -> Self {
+        Self::Query(err)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Serialization(err)
+    }
+}
+
 /// Convenience alias used throughout the storage crate.
 pub type Result<T> = std::result::Result<T, StorageError>;
 
@@ -10,8 +10,8 @@ pub mod traits;
 pub use error::StorageError;
 pub use sqlite::SqliteStore;
 pub use traits::{
-    BlockedIssue, EpicStatus, IssueUpdates, IssueWithDependencyMetadata, Statistics, Storage,
-    Transaction, TreeNode,
+    BlockedIssue, EpicStatus, HealthReport, IssueUpdates, IssueWithDependencyMetadata, SearchHit,
+    SkippedIssue, Statistics, Storage, Transaction, TreeNode,
 };
 
 // ---------------------------------------------------------------------------
@@ -20,10 +20,14 @@ pub use traits::{
 
 use std::collections::HashMap;
 
+use chrono::{DateTime, Utc};
+
 use beads_core::comment::{Comment, Event};
 use beads_core::dependency::Dependency;
-use beads_core::filter::{IssueFilter, WorkFilter};
+use beads_core::enums::DependencyType;
+use beads_core::filter::{IssueFilter, StaleFilter, WorkFilter};
 use beads_core::issue::Issue;
+use beads_query::Query;
 
 use crate::error::Result;
 
@@ -52,6 +56,10 @@ impl Storage for SqliteStore {
         self.update_issue_impl(id, updates, actor)
     }
 
+    fn record_heartbeat(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        self.record_heartbeat_impl(id, at)
+    }
+
     fn close_issue(&self, id: &str, reason: &str, actor: &str, session: &str) -> Result<()> {
         self.close_issue_impl(id, reason, actor, session)
     }
@@ -64,6 +72,14 @@ impl Storage for SqliteStore {
         self.search_issues_impl(query, filter)
     }
 
+    fn search_issues_ranked(&self, query: &str, filter: &IssueFilter) -> Result<Vec<SearchHit>> {
+        self.search_issues_ranked_impl(query, filter)
+    }
+
+    fn query_issues(&self, query: &Query, filter: &IssueFilter) -> Result<Vec<Issue>> {
+        self.query_issues_impl(query, filter)
+    }
+
     fn add_dependency(&self, dep: &Dependency, actor: &str) -> Result<()> {
         self.add_dependency_impl(dep, actor)
     }
@@ -72,6 +88,24 @@ impl Storage for SqliteStore {
         self.remove_dependency_impl(issue_id, depends_on_id, actor)
     }
 
+    fn remove_dependency_of_type(
+        &self,
+        issue_id: &str,
+        depends_on_id: &str,
+        dep_type: &DependencyType,
+        actor: &str,
+    ) -> Result<()> {
+        self.remove_dependency_of_type_impl(issue_id, depends_on_id, dep_type, actor)
+    }
+
+    fn get_dependency_records(&self, issue_id: &str) -> Result<Vec<Dependency>> {
+        self.get_dependency_records_impl(issue_id)
+    }
+
+    fn get_all_dependency_records(&self) -> Result<HashMap<String, Vec<Dependency>>> {
+        self.get_all_dependency_records_impl()
+    }
+
     fn get_dependencies(&self, issue_id: &str) -> Result<Vec<Issue>> {
         self.get_dependencies_impl(issue_id)
     }
@@ -116,6 +150,10 @@ impl Storage for SqliteStore {
         self.get_labels_impl(issue_id)
     }
 
+    fn get_labels_for_issues(&self, issue_ids: &[String]) -> Result<HashMap<String, Vec<String>>> {
+        self.get_labels_for_issues_impl(issue_ids)
+    }
+
     fn get_issues_by_label(&self, label: &str) -> Result<Vec<Issue>> {
         self.get_issues_by_label_impl(label)
     }
@@ -128,10 +166,18 @@ impl Storage for SqliteStore {
         self.get_blocked_issues_impl(filter)
     }
 
+    fn get_skipped_issues(&self, filter: &WorkFilter) -> Result<Vec<SkippedIssue>> {
+        self.get_skipped_issues_impl(filter)
+    }
+
     fn get_epics_eligible_for_closure(&self) -> Result<Vec<EpicStatus>> {
         self.get_epics_eligible_for_closure_impl()
     }
 
+    fn get_stale_issues(&self, filter: &StaleFilter) -> Result<Vec<Issue>> {
+        self.get_stale_issues_impl(filter)
+    }
+
     fn add_comment(&self, issue_id: &str, author: &str, text: &str) -> Result<Comment> {
         self.add_comment_impl(issue_id, author, text)
     }
@@ -152,6 +198,10 @@ impl Storage for SqliteStore {
         self.get_statistics_impl()
     }
 
+    fn check_health(&self) -> Result<HealthReport> {
+        self.check_health_impl()
+    }
+
     fn set_config(&self, key: &str, value: &str) -> Result<()> {
         self.set_config_impl(key, value)
     }
@@ -164,6 +214,26 @@ impl Storage for SqliteStore {
         self.get_all_config_impl()
     }
 
+    fn delete_config(&self, key: &str) -> Result<()> {
+        self.delete_config_impl(key)
+    }
+
+    fn set_metadata(&self, key: &str, value: &str) -> Result<()> {
+        self.set_metadata_impl(key, value)
+    }
+
+    fn get_metadata(&self, key: &str) -> Result<String> {
+        self.get_metadata_impl(key)
+    }
+
+    fn delete_metadata(&self, key: &str) -> Result<()> {
+        self.delete_metadata_impl(key)
+    }
+
+    fn get_all_metadata(&self) -> Result<HashMap<String, String>> {
+        self.get_all_metadata_impl()
+    }
+
     fn run_in_transaction(&self, f: &dyn Fn(&dyn Transaction) -> Result<()>) -> Result<()> {
         self.run_in_transaction_impl(f)
     }
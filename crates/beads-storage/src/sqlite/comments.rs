@@ -32,6 +32,11 @@ pub(crate) fn add_comment_on_conn(
 
     let id = conn.last_insert_rowid();
 
+    conn.execute(
+        "UPDATE issues SET updated_at = ?1 WHERE id = ?2",
+        params![now_str, issue_id],
+    )?;
+
     // Emit a "commented" event.
     emit_event(
         conn,
@@ -144,7 +149,7 @@ impl SqliteStore {
         let mut stmt = conn.prepare(
             "SELECT id, issue_id, event_type, actor, old_value, new_value, comment, created_at
              FROM events WHERE issue_id = ?1
-             ORDER BY created_at DESC LIMIT ?2",
+             ORDER BY created_at DESC, id DESC LIMIT ?2",
         )?;
         let rows = stmt.query_map(params![issue_id, limit], scan_event)?;
         let mut events = Vec::new();
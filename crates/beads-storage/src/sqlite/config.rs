@@ -30,6 +30,27 @@ pub(crate) fn get_config_on_conn(conn: &Connection, key: &str) -> Result<String>
     })
 }
 
+fn delete_key_on_conn(conn: &Connection, table: &str, entity: &str, key: &str) -> Result<()> {
+    let deleted = conn.execute(&format!("DELETE FROM {table} WHERE key = ?1"), params![key])?;
+    if deleted == 0 {
+        return Err(StorageError::not_found(entity, key));
+    }
+    Ok(())
+}
+
+fn get_all_on_conn(conn: &Connection, table: &str) -> Result<HashMap<String, String>> {
+    let mut stmt = conn.prepare(&format!("SELECT key, value FROM {table} ORDER BY key"))?;
+    let rows = stmt.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    let mut map = HashMap::new();
+    for row in rows {
+        let (k, v) = row?;
+        map.insert(k, v);
+    }
+    Ok(map)
+}
+
 pub(crate) fn set_metadata_on_conn(conn: &Connection, key: &str, value: &str) -> Result<()> {
     conn.execute(
         "INSERT OR REPLACE INTO metadata (key, value) VALUES (?1, ?2)",
@@ -70,16 +91,37 @@ impl SqliteStore {
     /// Returns all configuration key-value pairs.
     pub fn get_all_config_impl(&self) -> Result<HashMap<String, String>> {
         let conn = self.lock_conn()?;
-        let mut stmt = conn.prepare("SELECT key, value FROM config ORDER BY key")?;
-        let rows = stmt.query_map([], |row| {
-            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
-        })?;
-        let mut map = HashMap::new();
-        for row in rows {
-            let (k, v) = row?;
-            map.insert(k, v);
-        }
-        Ok(map)
+        get_all_on_conn(&conn, "config")
+    }
+
+    /// Deletes a configuration key.
+    pub fn delete_config_impl(&self, key: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        delete_key_on_conn(&conn, "config", "config", key)
+    }
+
+    /// Sets an internal metadata key-value pair.
+    pub fn set_metadata_impl(&self, key: &str, value: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        set_metadata_on_conn(&conn, key, value)
+    }
+
+    /// Gets a metadata value by key.
+    pub fn get_metadata_impl(&self, key: &str) -> Result<String> {
+        let conn = self.lock_conn()?;
+        get_metadata_on_conn(&conn, key)
+    }
+
+    /// Deletes a metadata key.
+    pub fn delete_metadata_impl(&self, key: &str) -> Result<()> {
+        let conn = self.lock_conn()?;
+        delete_key_on_conn(&conn, "metadata", "metadata", key)
+    }
+
+    /// Returns all metadata key-value pairs.
+    pub fn get_all_metadata_impl(&self) -> Result<HashMap<String, String>> {
+        let conn = self.lock_conn()?;
+        get_all_on_conn(&conn, "metadata")
     }
 }
 
@@ -122,4 +164,31 @@ mod tests {
         // Default config should be present.
         assert!(config.contains_key("compaction_enabled"));
     }
+
+    #[test]
+    fn delete_config() {
+        let store = test_store();
+        store.set_config_impl("gone", "soon").unwrap();
+        store.delete_config_impl("gone").unwrap();
+        assert!(store.get_config_impl("gone").unwrap_err().is_not_found());
+        assert!(store.delete_config_impl("gone").unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn metadata_roundtrip() {
+        let store = test_store();
+        store.set_metadata_impl("bd_version", "0.1.0").unwrap();
+        assert_eq!(store.get_metadata_impl("bd_version").unwrap(), "0.1.0");
+        assert_eq!(
+            store.get_all_metadata_impl().unwrap().get("bd_version"),
+            Some(&"0.1.0".to_string())
+        );
+        store.delete_metadata_impl("bd_version").unwrap();
+        assert!(
+            store
+                .get_metadata_impl("bd_version")
+                .unwrap_err()
+                .is_not_found()
+        );
+    }
 }
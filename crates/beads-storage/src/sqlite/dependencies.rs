@@ -1,6 +1,6 @@
 //! Dependency CRUD operations and cycle detection for [`SqliteStore`].
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 use chrono::Utc;
 use rusqlite::{Connection, params};
@@ -101,26 +101,75 @@ pub(crate) fn remove_dependency_on_conn(
     Ok(())
 }
 
+/// Removes the edge of one dependency type on the given connection.
+pub(crate) fn remove_dependency_of_type_on_conn(
+    conn: &Connection,
+    issue_id: &str,
+    depends_on_id: &str,
+    dep_type: &DependencyType,
+    actor: &str,
+) -> Result<()> {
+    let now = Utc::now();
+    let now_str = format_datetime(&now);
+
+    let affected = conn.execute(
+        "DELETE FROM dependencies WHERE issue_id = ?1 AND depends_on_id = ?2 AND type = ?3",
+        params![issue_id, depends_on_id, dep_type.as_str()],
+    )?;
+
+    if affected == 0 {
+        return Err(StorageError::not_found(
+            "dependency",
+            format!("{issue_id} -({})-> {depends_on_id}", dep_type.as_str()),
+        ));
+    }
+
+    emit_event(
+        conn,
+        issue_id,
+        EventType::DependencyRemoved,
+        actor,
+        Some(depends_on_id),
+        None,
+        Some(dep_type.as_str()),
+        &now_str,
+    )?;
+
+    Ok(())
+}
+
+const DEPENDENCY_COLUMNS: &str =
+    "issue_id, depends_on_id, type, created_at, created_by, metadata, thread_id";
+
+/// Maps a row selected with [`DEPENDENCY_COLUMNS`] to a [`Dependency`].
+fn scan_dependency(row: &rusqlite::Row<'_>) -> rusqlite::Result<Dependency> {
+    Ok(Dependency {
+        issue_id: row.get("issue_id")?,
+        depends_on_id: row.get("depends_on_id")?,
+        dep_type: DependencyType::from(row.get::<_, String>("type")?.as_str()),
+        created_at: crate::sqlite::issues::parse_datetime(&row.get::<_, String>("created_at")?),
+        created_by: row
+            .get::<_, Option<String>>("created_by")?
+            .unwrap_or_default(),
+        metadata: row
+            .get::<_, Option<String>>("metadata")?
+            .unwrap_or_default(),
+        thread_id: row
+            .get::<_, Option<String>>("thread_id")?
+            .unwrap_or_default(),
+    })
+}
+
 /// Returns raw dependency records for an issue on the given connection.
 pub(crate) fn get_dependency_records_on_conn(
     conn: &Connection,
     issue_id: &str,
 ) -> Result<Vec<Dependency>> {
-    let mut stmt = conn.prepare(
-        "SELECT issue_id, depends_on_id, type, created_at, created_by, metadata, thread_id
-         FROM dependencies WHERE issue_id = ?1",
-    )?;
-    let rows = stmt.query_map(params![issue_id], |row| {
-        Ok(Dependency {
-            issue_id: row.get("issue_id")?,
-            depends_on_id: row.get("depends_on_id")?,
-            dep_type: DependencyType::from(row.get::<_, String>("type")?.as_str()),
-            created_at: crate::sqlite::issues::parse_datetime(&row.get::<_, String>("created_at")?),
-            created_by: row.get("created_by")?,
-            metadata: row.get("metadata")?,
-            thread_id: row.get("thread_id")?,
-        })
-    })?;
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {DEPENDENCY_COLUMNS} FROM dependencies WHERE issue_id = ?1
+         ORDER BY depends_on_id"
+    ))?;
+    let rows = stmt.query_map(params![issue_id], scan_dependency)?;
 
     let mut deps = Vec::new();
     for row in rows {
@@ -189,6 +238,40 @@ impl SqliteStore {
         remove_dependency_on_conn(&conn, issue_id, depends_on_id, actor)
     }
 
+    /// Removes the edge of one dependency type.
+    pub fn remove_dependency_of_type_impl(
+        &self,
+        issue_id: &str,
+        depends_on_id: &str,
+        dep_type: &DependencyType,
+        actor: &str,
+    ) -> Result<()> {
+        let conn = self.lock_conn()?;
+        remove_dependency_of_type_on_conn(&conn, issue_id, depends_on_id, dep_type, actor)
+    }
+
+    /// Returns the raw outgoing dependency edges of an issue.
+    pub fn get_dependency_records_impl(&self, issue_id: &str) -> Result<Vec<Dependency>> {
+        let conn = self.lock_conn()?;
+        get_dependency_records_on_conn(&conn, issue_id)
+    }
+
+    /// Returns every dependency edge, keyed by the dependent issue's ID.
+    pub fn get_all_dependency_records_impl(&self) -> Result<HashMap<String, Vec<Dependency>>> {
+        let conn = self.lock_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {DEPENDENCY_COLUMNS} FROM dependencies ORDER BY issue_id, depends_on_id"
+        ))?;
+        let rows = stmt.query_map([], scan_dependency)?;
+
+        let mut map: HashMap<String, Vec<Dependency>> = HashMap::new();
+        for row in rows {
+            let dep = row?;
+            map.entry(dep.issue_id.clone()).or_default().push(dep);
+        }
+        Ok(map)
+    }
+
     /// Returns issues that the given issue depends on.
     pub fn get_dependencies_impl(&self, issue_id: &str) -> Result<Vec<Issue>> {
         let conn = self.lock_conn()?;
@@ -458,4 +541,35 @@ mod tests {
             .unwrap_err();
         assert!(matches!(err, StorageError::CycleDetected));
     }
+
+    #[test]
+    fn remove_dependency_of_type_keeps_other_pairs() {
+        let store = test_store();
+        for id in ["bd-rt1", "bd-rt2", "bd-rt3"] {
+            let issue = IssueBuilder::new("Issue").id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        let mut related = make_dep("bd-rt1", "bd-rt2");
+        related.dep_type = DependencyType::Related;
+        store.add_dependency_impl(&related, "alice").unwrap();
+        store
+            .add_dependency_impl(&make_dep("bd-rt1", "bd-rt3"), "alice")
+            .unwrap();
+
+        let err = store
+            .remove_dependency_of_type_impl("bd-rt1", "bd-rt2", &DependencyType::Blocks, "alice")
+            .unwrap_err();
+        assert!(err.is_not_found());
+        store
+            .remove_dependency_of_type_impl("bd-rt1", "bd-rt2", &DependencyType::Related, "alice")
+            .unwrap();
+
+        let all = store.get_all_dependency_records_impl().unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all["bd-rt1"][0].depends_on_id, "bd-rt3");
+        assert_eq!(
+            store.get_dependency_records_impl("bd-rt1").unwrap().len(),
+            1
+        );
+    }
 }
@@ -0,0 +1,353 @@
+//! Full-text search support: translating user queries into FTS5 syntax.
+//!
+//! The `issues_fts` table (see the `issues_fts` migration in
+//! [`schema`](crate::sqlite::schema)) indexes each issue's ID, title,
+//! description, design, acceptance criteria, notes and the concatenated text
+//! of its comments. User queries are translated rather than passed through so
+//! that arbitrary input (`bd-12`, `fix: crash`) never produces an FTS5 syntax
+//! error:
+//!
+//! - bare words are matched as quoted tokens; `log*` is a prefix query
+//! - `"exact phrase"` is a phrase query
+//! - `field:term` restricts a term or phrase to one column (`title:login`)
+//! - `AND`, `OR`, `NOT` and parentheses are passed through when well-formed
+//!
+//! Adjacent terms are implicitly ANDed.
+
+/// Columns that may be used as `field:` prefixes, with accepted aliases.
+const FIELDS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("title", "title"),
+    ("description", "description"),
+    ("desc", "description"),
+    ("design", "design"),
+    ("acceptance_criteria", "acceptance_criteria"),
+    ("acceptance", "acceptance_criteria"),
+    ("notes", "notes"),
+    ("comments", "comments"),
+    ("comment", "comments"),
+];
+
+/// Per-column BM25 weights, in `issues_fts` column order (the leading
+/// unindexed `issue_id` gets 0). Matches in IDs and titles rank highest.
+pub(crate) const BM25_WEIGHTS: &str = "0.0, 10.0, 10.0, 4.0, 2.0, 2.0, 1.0, 1.0";
+
+/// Marker placed before each match in snippets.
+pub const SNIPPET_OPEN: &str = "**";
+
+/// Marker placed after each match in snippets.
+pub const SNIPPET_CLOSE: &str = "**";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Term(String),
+    Op(&'static str),
+    Open,
+    Close,
+}
+
+/// Translates a user search query into an FTS5 `MATCH` expression.
+///
+/// Returns `None` if the query contains nothing searchable.
+pub fn fts_query(input: &str) -> Option<String> {
+    let tokens = tokenize(input);
+
+    // Operators are only kept between two operands; anywhere else they are
+    // searched for as plain words.
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Op(op) => {
+                let after_operand = matches!(out.last(), Some(Token::Term(_) | Token::Close));
+                let before_operand =
+                    matches!(tokens.get(i + 1), Some(Token::Term(_) | Token::Open));
+                if after_operand && before_operand {
+                    out.push(token.clone());
+                } else if let Some(term) = quote_term(&op.to_lowercase(), false) {
+                    out.push(Token::Term(term));
+                }
+            }
+            _ => out.push(token.clone()),
+        }
+    }
+
+    // Drop parentheses entirely unless they balance and enclose something.
+    let mut depth = 0i32;
+    let mut balanced = true;
+    for (i, token) in out.iter().enumerate() {
+        match token {
+            Token::Open => {
+                depth += 1;
+                if matches!(out.get(i + 1), Some(Token::Close | Token::Op(_)) | None) {
+                    balanced = false;
+                }
+            }
+            Token::Close => {
+                depth -= 1;
+                if depth < 0 || matches!(out.get(i.wrapping_sub(1)), Some(Token::Op(_))) {
+                    balanced = false;
+                }
+            }
+            _ => {}
+        }
+    }
+    if !balanced || depth != 0 {
+        out.retain(|t| !matches!(t, Token::Open | Token::Close));
+        // Without their parentheses some operators may now dangle.
+        return fts_query_from(strip_dangling_ops(out));
+    }
+    fts_query_from(out)
+}
+
+fn fts_query_from(tokens: Vec<Token>) -> Option<String> {
+    if !tokens.iter().any(|t| matches!(t, Token::Term(_))) {
+        return None;
+    }
+    let mut query = String::new();
+    let mut prev_open = true;
+    let mut prev: Option<Token> = None;
+    for token in tokens {
+        // FTS5 only allows implicit AND between plain terms; a group next to
+        // another operand needs an explicit operator.
+        let grouped = matches!(
+            (&prev, &token),
+            (Some(Token::Close), Token::Term(_) | Token::Open)
+                | (Some(Token::Term(_)), Token::Open)
+        );
+        if grouped {
+            query.push_str(" AND");
+        }
+        prev = Some(token.clone());
+        let (text, is_open, is_close) = match token {
+            Token::Term(t) => (t, false, false),
+            Token::Op(op) => (op.to_string(), false, false),
+            Token::Open => ("(".to_string(), true, false),
+            Token::Close => (")".to_string(), false, true),
+        };
+        if !prev_open && !is_close {
+            query.push(' ');
+        }
+        query.push_str(&text);
+        prev_open = is_open;
+    }
+    Some(query)
+}
+
+fn strip_dangling_ops(tokens: Vec<Token>) -> Vec<Token> {
+    let mut out: Vec<Token> = Vec::with_capacity(tokens.len());
+    for token in tokens {
+        if matches!(token, Token::Op(_)) && !matches!(out.last(), Some(Token::Term(_))) {
+            continue;
+        }
+        out.push(token);
+    }
+    while matches!(out.last(), Some(Token::Op(_))) {
+        out.pop();
+    }
+    out
+}
+
+/// Splits the input into terms, operators and parentheses.
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' {
+            chars.next();
+            tokens.push(Token::Open);
+            continue;
+        }
+        if c == ')' {
+            chars.next();
+            tokens.push(Token::Close);
+            continue;
+        }
+
+        // A word, possibly `field:` prefixed, possibly followed by a phrase.
+        let mut word = String::new();
+        let mut phrase: Option<String> = None;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            chars.next();
+            if c == '"' {
+                let mut text = String::new();
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    text.push(c);
+                }
+                phrase = Some(text);
+                break;
+            }
+            word.push(c);
+        }
+        let prefix = phrase.is_some() && chars.next_if_eq(&'*').is_some();
+
+        let (column, text) = match phrase {
+            Some(text) => match word.strip_suffix(':').and_then(column_for) {
+                Some(column) => (Some(column), text),
+                // Text glued to the quote (`foo"bar"`) is searched as well.
+                None => (None, format!("{word} {text}")),
+            },
+            None => {
+                if let Some(op) = operator(&word) {
+                    tokens.push(Token::Op(op));
+                    continue;
+                }
+                match word.split_once(':') {
+                    Some((field, rest)) if !rest.is_empty() => match column_for(field) {
+                        Some(column) => (Some(column), rest.to_string()),
+                        None => (None, word),
+                    },
+                    _ => (None, word),
+                }
+            }
+        };
+
+        let (text, prefix) = match text.strip_suffix('*') {
+            Some(stripped) => (stripped.to_string(), true),
+            None => (text, prefix),
+        };
+        if let Some(term) = quote_term(&text, prefix) {
+            tokens.push(Token::Term(match column {
+                Some(column) => format!("{column}:{term}"),
+                None => term,
+            }));
+        }
+    }
+    tokens
+}
+
+fn operator(word: &str) -> Option<&'static str> {
+    match word {
+        "AND" => Some("AND"),
+        "OR" => Some("OR"),
+        "NOT" => Some("NOT"),
+        _ => None,
+    }
+}
+
+fn column_for(field: &str) -> Option<&'static str> {
+    let field = field.to_lowercase();
+    FIELDS
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, column)| *column)
+}
+
+/// Quotes text as an FTS5 string, or returns `None` if it contains no
+/// indexable characters.
+fn quote_term(text: &str, prefix: bool) -> Option<String> {
+    if !text.chars().any(char::is_alphanumeric) {
+        return None;
+    }
+    let quoted = format!("\"{}\"", text.trim().replace('"', "\"\""));
+    Some(if prefix { format!("{quoted}*") } else { quoted })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_words_are_quoted() {
+        assert_eq!(
+            fts_query("login crash").as_deref(),
+            Some(r#""login" "crash""#)
+        );
+        assert_eq!(fts_query("bd-12").as_deref(), Some(r#""bd-12""#));
+    }
+
+    #[test]
+    fn prefix_and_phrase_queries() {
+        assert_eq!(fts_query("log*").as_deref(), Some(r#""log"*"#));
+        assert_eq!(
+            fts_query(r#""null pointer" deref"#).as_deref(),
+            Some(r#""null pointer" "deref""#)
+        );
+        assert_eq!(
+            fts_query(r#""null poi"*"#).as_deref(),
+            Some(r#""null poi"*"#)
+        );
+    }
+
+    #[test]
+    fn field_scoped_terms() {
+        assert_eq!(
+            fts_query("title:login").as_deref(),
+            Some(r#"title:"login""#)
+        );
+        assert_eq!(
+            fts_query(r#"desc:"stack trace""#).as_deref(),
+            Some(r#"description:"stack trace""#)
+        );
+        assert_eq!(
+            fts_query("Notes:auth*").as_deref(),
+            Some(r#"notes:"auth"*"#)
+        );
+        // Unknown fields are searched literally.
+        assert_eq!(fts_query("fix:crash").as_deref(), Some(r#""fix:crash""#));
+        assert_eq!(
+            fts_query("fix: crash").as_deref(),
+            Some(r#""fix:" "crash""#)
+        );
+    }
+
+    #[test]
+    fn operators_pass_through_when_well_formed() {
+        assert_eq!(
+            fts_query("login OR signup").as_deref(),
+            Some(r#""login" OR "signup""#)
+        );
+        assert_eq!(
+            fts_query("(login OR signup) NOT oauth").as_deref(),
+            Some(r#"("login" OR "signup") NOT "oauth""#)
+        );
+        // Dangling operators become words.
+        assert_eq!(fts_query("OR login").as_deref(), Some(r#""or" "login""#));
+        assert_eq!(fts_query("login AND").as_deref(), Some(r#""login" "and""#));
+    }
+
+    #[test]
+    fn groups_next_to_operands_are_anded() {
+        assert_eq!(fts_query("(a) (b)").as_deref(), Some(r#"("a") AND ("b")"#));
+        assert_eq!(
+            fts_query("login (crash)").as_deref(),
+            Some(r#""login" AND ("crash")"#)
+        );
+        assert_eq!(
+            fts_query("(login) crash").as_deref(),
+            Some(r#"("login") AND "crash""#)
+        );
+    }
+
+    #[test]
+    fn unbalanced_parentheses_are_dropped() {
+        assert_eq!(
+            fts_query("(login OR signup").as_deref(),
+            Some(r#""login" OR "signup""#)
+        );
+        assert_eq!(fts_query("login)").as_deref(), Some(r#""login""#));
+        assert_eq!(fts_query("()").as_deref(), None);
+    }
+
+    #[test]
+    fn nothing_searchable() {
+        assert_eq!(fts_query(""), None);
+        assert_eq!(fts_query("  - * "), None);
+        assert_eq!(fts_query(r#""""#), None);
+    }
+
+    #[test]
+    fn embedded_quotes_are_escaped() {
+        assert_eq!(fts_query(r#"say"hi""#).as_deref(), Some(r#""say hi""#));
+    }
+}
@@ -0,0 +1,93 @@
+//! Integrity and consistency checks for [`SqliteStore`].
+
+use rusqlite::Connection;
+
+use crate::error::Result;
+use crate::sqlite::store::SqliteStore;
+use crate::traits::HealthReport;
+
+/// Tables whose row counts are reported.
+const COUNTED_TABLES: &[&str] = &["issues", "dependencies", "labels", "comments", "events"];
+
+fn count(conn: &Connection, sql: &str) -> Result<i64> {
+    Ok(conn.query_row(sql, [], |row| row.get(0))?)
+}
+
+impl SqliteStore {
+    /// Runs `PRAGMA integrity_check` and counts rows that reference missing
+    /// issues.
+    pub fn check_health_impl(&self) -> Result<HealthReport> {
+        let conn = self.lock_conn()?;
+
+        let integrity = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+
+        let mut stmt = conn.prepare(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'
+             ORDER BY name",
+        )?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut tables = Vec::new();
+        for row in rows {
+            tables.push(row?);
+        }
+
+        let mut record_counts = Vec::new();
+        for table in COUNTED_TABLES {
+            if tables.iter().any(|t| t == table) {
+                record_counts.push((
+                    table.to_string(),
+                    count(&conn, &format!("SELECT COUNT(*) FROM {table}"))?,
+                ));
+            }
+        }
+
+        let orphaned_dependencies = count(
+            &conn,
+            "SELECT COUNT(*) FROM dependencies
+             WHERE issue_id NOT IN (SELECT id FROM issues)
+                OR depends_on_id NOT IN (SELECT id FROM issues)",
+        )?;
+        let orphaned_labels = count(
+            &conn,
+            "SELECT COUNT(*) FROM labels WHERE issue_id NOT IN (SELECT id FROM issues)",
+        )?;
+        let orphaned_comments = count(
+            &conn,
+            "SELECT COUNT(*) FROM comments WHERE issue_id NOT IN (SELECT id FROM issues)",
+        )?;
+
+        Ok(HealthReport {
+            integrity,
+            tables,
+            record_counts,
+            orphaned_dependencies,
+            orphaned_labels,
+            orphaned_comments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use beads_core::issue::IssueBuilder;
+
+    fn test_store() -> SqliteStore {
+        SqliteStore::open_in_memory().unwrap()
+    }
+
+    #[test]
+    fn healthy_store_reports_ok() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Healthy").id("bd-h1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        let report = store.check_health_impl().unwrap();
+        assert_eq!(report.integrity, "ok");
+        assert!(report.tables.iter().any(|t| t == "issues"));
+        assert!(report.record_counts.contains(&("issues".to_string(), 1)));
+        assert_eq!(report.orphaned_dependencies, 0);
+        assert_eq!(report.orphaned_labels, 0);
+        assert_eq!(report.orphaned_comments, 0);
+    }
+}
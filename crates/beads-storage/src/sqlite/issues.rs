@@ -8,10 +8,12 @@ use beads_core::entity::{BondRef, Validation};
 use beads_core::enums::{AgentState, EventType, IssueType, MolType, Status, WispType, WorkType};
 use beads_core::filter::IssueFilter;
 use beads_core::issue::Issue;
+use beads_query::{Query, SqlParam};
 
 use crate::error::{Result, StorageError};
+use crate::sqlite::fts;
 use crate::sqlite::store::SqliteStore;
-use crate::traits::IssueUpdates;
+use crate::traits::{IssueUpdates, SearchHit};
 
 // ---------------------------------------------------------------------------
 // Column list (shared between INSERT and SELECT)
@@ -104,7 +106,9 @@ pub(crate) fn scan_issue(row: &Row<'_>) -> rusqlite::Result<Issue> {
     let quality_score: Option<f64> = row.get("quality_score")?;
 
     let source_system: String = row.get("source_system")?;
-    let metadata_str: String = row.get("metadata")?;
+    let metadata_str: String = row
+        .get::<_, Option<String>>("metadata")?
+        .unwrap_or_default();
     let source_repo: String = row.get("source_repo")?;
     let close_reason: String = row.get("close_reason")?;
 
@@ -255,6 +259,74 @@ pub(crate) fn parse_datetime(s: &str) -> DateTime<Utc> {
 pub(crate) fn insert_issue(conn: &Connection, issue: &Issue, actor: &str) -> Result<()> {
     let now = Utc::now();
     let now_str = format_datetime(&now);
+
+    write_issue_row(conn, issue, false)?;
+
+    // Emit "created" event.
+    emit_event(
+        conn,
+        &issue.id,
+        EventType::Created,
+        actor,
+        None,
+        Some(&issue.title),
+        None,
+        &now_str,
+    )?;
+
+    Ok(())
+}
+
+/// Inserts or overwrites an issue row verbatim (for import/sync).
+///
+/// Unlike [`insert_issue`] and [`update_issue_on_conn`], the issue's own
+/// timestamps are preserved so that re-importing an export is lossless. Emits
+/// a "created" event for new issues and an "updated" event otherwise.
+pub(crate) fn import_issue_on_conn(conn: &Connection, issue: &Issue, actor: &str) -> Result<()> {
+    let now_str = format_datetime(&Utc::now());
+
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) FROM issues WHERE id = ?1",
+        params![issue.id],
+        |row| row.get::<_, i64>(0),
+    )? > 0;
+
+    write_issue_row(conn, issue, exists)?;
+
+    let event_type = if exists {
+        EventType::Updated
+    } else {
+        EventType::Created
+    };
+    emit_event(
+        conn,
+        &issue.id,
+        event_type,
+        actor,
+        None,
+        None,
+        Some("imported"),
+        &now_str,
+    )?;
+
+    Ok(())
+}
+
+/// Builds the `SET` list for an upsert: every column except `id` is taken
+/// from the `excluded` row.
+fn upsert_assignments() -> String {
+    ISSUE_COLUMNS
+        .split(',')
+        .map(str::trim)
+        .filter(|col| !col.is_empty() && *col != "id")
+        .map(|col| format!("{col} = excluded.{col}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Writes all issue columns. With `upsert`, an existing row with the same ID
+/// is overwritten in place (keeping its labels, dependencies and comments).
+fn write_issue_row(conn: &Connection, issue: &Issue, upsert: bool) -> Result<()> {
     let content_hash = compute_content_hash(issue);
 
     let metadata_str = issue
@@ -277,6 +349,12 @@ pub(crate) fn insert_issue(conn: &Connection, issue: &Issue, actor: &str) -> Res
     let due_at_str = issue.due_at.as_ref().map(format_datetime);
     let defer_until_str = issue.defer_until.as_ref().map(format_datetime);
 
+    let on_conflict = if upsert {
+        format!(" ON CONFLICT(id) DO UPDATE SET {}", upsert_assignments())
+    } else {
+        String::new()
+    };
+
     conn.execute(
         &format!(
             "INSERT INTO issues ({ISSUE_COLUMNS}) VALUES (
@@ -294,7 +372,7 @@ pub(crate) fn insert_issue(conn: &Connection, issue: &Issue, actor: &str) -> Res
                 ?46, ?47, ?48, ?49, ?50, ?51,
                 ?52, ?53,
                 ?54, ?55
-            )"
+            ){on_conflict}"
         ),
         params![
             issue.id,                              // 1
@@ -355,18 +433,6 @@ pub(crate) fn insert_issue(conn: &Connection, issue: &Issue, actor: &str) -> Res
         ],
     )?;
 
-    // Emit "created" event.
-    emit_event(
-        conn,
-        &issue.id,
-        EventType::Created,
-        actor,
-        None,
-        None,
-        None,
-        &now_str,
-    )?;
-
     Ok(())
 }
 
@@ -466,6 +532,19 @@ impl SqliteStore {
         update_issue_on_conn(&conn, id, updates, actor)
     }
 
+    /// Sets an agent's last_activity without bumping updated_at.
+    pub fn record_heartbeat_impl(&self, id: &str, at: DateTime<Utc>) -> Result<()> {
+        let conn = self.lock_conn()?;
+        let affected = conn.execute(
+            "UPDATE issues SET last_activity = ?1 WHERE id = ?2",
+            params![format_datetime(&at), id],
+        )?;
+        if affected == 0 {
+            return Err(StorageError::not_found("issue", id));
+        }
+        Ok(())
+    }
+
     /// Closes an issue.
     pub fn close_issue_impl(
         &self,
@@ -489,6 +568,22 @@ impl SqliteStore {
         let conn = self.lock_conn()?;
         search_issues_on_conn(&conn, query, filter)
     }
+
+    /// Full-text searches issues, most relevant first, with snippets.
+    pub fn search_issues_ranked_impl(
+        &self,
+        query: &str,
+        filter: &IssueFilter,
+    ) -> Result<Vec<SearchHit>> {
+        let conn = self.lock_conn()?;
+        search_hits_on_conn(&conn, query, filter)
+    }
+
+    /// Returns issues matching a query expression and filter.
+    pub fn query_issues_impl(&self, query: &Query, filter: &IssueFilter) -> Result<Vec<Issue>> {
+        let conn = self.lock_conn()?;
+        query_issues_on_conn(&conn, query, filter)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -515,6 +610,17 @@ pub(crate) fn update_issue_on_conn(
     let now = Utc::now();
     let now_str = format_datetime(&now);
 
+    let old_status: String = conn
+        .query_row(
+            "SELECT status FROM issues WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+        .map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => StorageError::not_found("issue", id),
+            other => StorageError::Query(other),
+        })?;
+
     // Build SET clause dynamically from non-None fields.
     let mut set_clauses: Vec<String> = Vec::new();
     let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
@@ -564,10 +670,6 @@ pub(crate) fn update_issue_on_conn(
     add_field!(target, "target");
     add_field!(payload, "payload");
 
-    if let Some(ref status) = updates.status {
-        set_clauses.push("status = ?".to_string());
-        param_values.push(Box::new(status.as_str().to_string()));
-    }
     if let Some(ref issue_type) = updates.issue_type {
         set_clauses.push("issue_type = ?".to_string());
         param_values.push(Box::new(issue_type.as_str().to_string()));
@@ -605,6 +707,12 @@ pub(crate) fn update_issue_on_conn(
             serde_json::to_string(waiters).unwrap_or_else(|_| "[]".to_string()),
         ));
     }
+    if let Some(ref bonded_from) = updates.bonded_from {
+        set_clauses.push("bonded_from = ?".to_string());
+        param_values.push(Box::new(
+            serde_json::to_string(bonded_from).unwrap_or_else(|_| "[]".to_string()),
+        ));
+    }
 
     // DateTime Option<Option<DateTime>> fields.
     if let Some(ref due) = updates.due_at {
@@ -625,6 +733,37 @@ pub(crate) fn update_issue_on_conn(
     add_bool_field!(ephemeral, "ephemeral");
     add_bool_field!(crystallizes, "crystallizes");
 
+    // Columns changed besides the status, for the "updated" event.
+    let changed: Vec<&str> = set_clauses
+        .iter()
+        .filter_map(|clause| clause.split(" = ").next())
+        .collect();
+    let changed = changed.join(",");
+
+    // Status transitions keep closed_at/close_reason consistent and get
+    // their own event type.
+    let closed = Status::Closed.as_str();
+    let status_event = match updates.status {
+        Some(ref status) if status.as_str() != old_status => {
+            set_clauses.push("status = ?".to_string());
+            param_values.push(Box::new(status.as_str().to_string()));
+            if status.as_str() == closed {
+                set_clauses.push("closed_at = ?".to_string());
+                param_values.push(Box::new(now_str.clone()));
+                Some(EventType::Closed)
+            } else if old_status == closed {
+                set_clauses.push("closed_at = NULL".to_string());
+                if updates.close_reason.is_none() {
+                    set_clauses.push("close_reason = ''".to_string());
+                }
+                Some(EventType::Reopened)
+            } else {
+                Some(EventType::StatusChanged)
+            }
+        }
+        _ => None,
+    };
+
     if set_clauses.is_empty() {
         return Ok(()); // Nothing to update.
     }
@@ -644,17 +783,44 @@ pub(crate) fn update_issue_on_conn(
         return Err(StorageError::not_found("issue", id));
     }
 
-    // Emit "updated" event.
-    emit_event(
-        conn,
-        id,
-        EventType::Updated,
-        actor,
-        None,
-        None,
-        None,
-        &now_str,
-    )?;
+    let new_status = updates.status.as_ref().map(|s| s.as_str());
+    let status_changed = status_event.is_some();
+    match status_event {
+        Some(EventType::Closed) => emit_event(
+            conn,
+            id,
+            EventType::Closed,
+            actor,
+            Some(&old_status),
+            updates.close_reason.as_deref(),
+            None,
+            &now_str,
+        )?,
+        Some(event_type) => emit_event(
+            conn,
+            id,
+            event_type,
+            actor,
+            Some(&old_status),
+            new_status,
+            None,
+            &now_str,
+        )?,
+        None => {}
+    }
+
+    if !status_changed || !changed.is_empty() {
+        emit_event(
+            conn,
+            id,
+            EventType::Updated,
+            actor,
+            None,
+            (!changed.is_empty()).then_some(changed.as_str()),
+            None,
+            &now_str,
+        )?;
+    }
 
     Ok(())
 }
@@ -694,7 +860,18 @@ pub(crate) fn close_issue_on_conn(
 }
 
 /// Deletes an issue on the given connection.
+///
+/// Related rows are removed explicitly rather than relying on `ON DELETE
+/// CASCADE`, which databases created by `bd init` do not declare. Edges from
+/// other issues pointing at the deleted one are removed as well.
 pub(crate) fn delete_issue_on_conn(conn: &Connection, id: &str) -> Result<()> {
+    conn.execute("DELETE FROM labels WHERE issue_id = ?1", params![id])?;
+    conn.execute("DELETE FROM comments WHERE issue_id = ?1", params![id])?;
+    conn.execute("DELETE FROM events WHERE issue_id = ?1", params![id])?;
+    conn.execute(
+        "DELETE FROM dependencies WHERE issue_id = ?1 OR depends_on_id = ?1",
+        params![id],
+    )?;
     let affected = conn.execute("DELETE FROM issues WHERE id = ?1", params![id])?;
     if affected == 0 {
         return Err(StorageError::not_found("issue", id));
@@ -708,19 +885,78 @@ pub(crate) fn search_issues_on_conn(
     query: &str,
     filter: &IssueFilter,
 ) -> Result<Vec<Issue>> {
+    Ok(search_hits_on_conn(conn, query, filter)?
+        .into_iter()
+        .map(|hit| hit.issue)
+        .collect())
+}
+
+/// Searches issues on the given connection, returning relevance scores and
+/// snippets.
+///
+/// A non-empty `query` is matched against the `issues_fts` index (see
+/// [`fts::fts_query`] for the syntax) and results are ordered by BM25
+/// relevance. An empty query matches every issue, newest first, with a zero
+/// score and no snippet.
+pub(crate) fn search_hits_on_conn(
+    conn: &Connection,
+    query: &str,
+    filter: &IssueFilter,
+) -> Result<Vec<SearchHit>> {
+    filtered_hits_on_conn(conn, query, filter, None)
+}
+
+/// Returns issues matching a query expression and filter on the given
+/// connection.
+///
+/// Queries the filter can express exactly run as a plain filtered search;
+/// anything else is compiled to SQL and added to the search conditions.
+pub(crate) fn query_issues_on_conn(
+    conn: &Connection,
+    query: &Query,
+    filter: &IssueFilter,
+) -> Result<Vec<Issue>> {
+    let hits = match query.to_issue_filter(filter, Utc::now()) {
+        Some(compiled) => filtered_hits_on_conn(conn, "", &compiled, None)?,
+        None => filtered_hits_on_conn(conn, "", filter, Some(query))?,
+    };
+    Ok(hits.into_iter().map(|hit| hit.issue).collect())
+}
+
+/// Shared implementation of [`search_hits_on_conn`] with an optional extra
+/// query expression.
+fn filtered_hits_on_conn(
+    conn: &Connection,
+    query: &str,
+    filter: &IssueFilter,
+    extra: Option<&Query>,
+) -> Result<Vec<SearchHit>> {
     let mut where_clauses: Vec<String> = Vec::new();
     let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
     let mut param_idx = 1;
 
-    // Full-text search across title, description, notes.
-    if !query.is_empty() {
-        where_clauses.push(format!(
-            "(title LIKE ?{pi} OR description LIKE ?{pi} OR notes LIKE ?{pi})",
-            pi = param_idx
-        ));
-        param_values.push(Box::new(format!("%{query}%")));
+    // Full-text search across ID, title, description, design, acceptance
+    // criteria, notes and comments.
+    let fts_join = if query.trim().is_empty() {
+        None
+    } else {
+        let Some(match_expr) = fts::fts_query(query) else {
+            return Ok(Vec::new());
+        };
+        param_values.push(Box::new(match_expr));
         param_idx += 1;
-    }
+        Some(format!(
+            "JOIN (
+                SELECT issue_id,
+                       -bm25(issues_fts, {weights}) AS fts_score,
+                       snippet(issues_fts, -1, '{open}', '{close}', '...', 16) AS fts_snippet
+                FROM issues_fts WHERE issues_fts MATCH ?1
+            ) AS fts ON fts.issue_id = issues.id",
+            weights = fts::BM25_WEIGHTS,
+            open = fts::SNIPPET_OPEN,
+            close = fts::SNIPPET_CLOSE,
+        ))
+    };
 
     // Filter fields.
     if let Some(ref status) = filter.status {
@@ -733,6 +969,16 @@ pub(crate) fn search_issues_on_conn(
         param_values.push(Box::new(priority));
         param_idx += 1;
     }
+    if let Some(priority_min) = filter.priority_min {
+        where_clauses.push(format!("priority >= ?{param_idx}"));
+        param_values.push(Box::new(priority_min));
+        param_idx += 1;
+    }
+    if let Some(priority_max) = filter.priority_max {
+        where_clauses.push(format!("priority <= ?{param_idx}"));
+        param_values.push(Box::new(priority_max));
+        param_idx += 1;
+    }
     if let Some(ref issue_type) = filter.issue_type {
         where_clauses.push(format!("issue_type = ?{param_idx}"));
         param_values.push(Box::new(issue_type.as_str().to_string()));
@@ -778,6 +1024,16 @@ pub(crate) fn search_issues_on_conn(
         param_values.push(Box::new(format_datetime(updated_before)));
         param_idx += 1;
     }
+    if let Some(ref closed_after) = filter.closed_after {
+        where_clauses.push(format!("closed_at >= ?{param_idx}"));
+        param_values.push(Box::new(format_datetime(closed_after)));
+        param_idx += 1;
+    }
+    if let Some(ref closed_before) = filter.closed_before {
+        where_clauses.push(format!("closed_at <= ?{param_idx}"));
+        param_values.push(Box::new(format_datetime(closed_before)));
+        param_idx += 1;
+    }
     if filter.no_assignee {
         where_clauses.push("(assignee IS NULL OR assignee = '')".to_string());
     }
@@ -887,6 +1143,20 @@ pub(crate) fn search_issues_on_conn(
         );
     }
 
+    // Query expression.
+    if let Some(query) = extra {
+        let compiled = query.to_sql("issues", param_idx, Utc::now());
+        where_clauses.push(compiled.sql);
+        for param in compiled.params {
+            match param {
+                SqlParam::Text(text) => param_values.push(Box::new(text)),
+                SqlParam::Int(n) => param_values.push(Box::new(n)),
+                SqlParam::Time(at) => param_values.push(Box::new(format_datetime(&at))),
+            }
+            param_idx += 1;
+        }
+    }
+
     // Build final SQL.
     let where_sql = if where_clauses.is_empty() {
         String::new()
@@ -899,25 +1169,43 @@ pub(crate) fn search_issues_on_conn(
         .map(|l| format!(" LIMIT {l}"))
         .unwrap_or_default();
 
+    let (rank_columns, join_sql, order_sql) = match fts_join {
+        Some(join) => (
+            "fts_score, fts_snippet",
+            join,
+            "fts_score DESC, created_at DESC",
+        ),
+        None => (
+            "0.0 AS fts_score, '' AS fts_snippet",
+            String::new(),
+            "created_at DESC",
+        ),
+    };
     let sql = format!(
-        "SELECT {ISSUE_COLUMNS} FROM issues {where_sql} ORDER BY created_at DESC{limit_sql}"
+        "SELECT {ISSUE_COLUMNS}, {rank_columns} FROM issues {join_sql} {where_sql} ORDER BY {order_sql}{limit_sql}"
     );
 
     let param_refs: Vec<&dyn rusqlite::types::ToSql> =
         param_values.iter().map(|p| p.as_ref()).collect();
 
     let mut stmt = conn.prepare(&sql)?;
-    let rows = stmt.query_map(param_refs.as_slice(), scan_issue)?;
+    let rows = stmt.query_map(param_refs.as_slice(), |row| {
+        Ok(SearchHit {
+            issue: scan_issue(row)?,
+            score: row.get("fts_score")?,
+            snippet: row.get("fts_snippet")?,
+        })
+    })?;
 
-    let mut issues = Vec::new();
+    let mut hits = Vec::new();
     for row in rows {
-        issues.push(row?);
+        hits.push(row?);
     }
 
     // Suppress the "unused" warning for param_idx.
     let _ = param_idx;
 
-    Ok(issues)
+    Ok(hits)
 }
 
 #[cfg(test)]
@@ -973,6 +1261,29 @@ mod tests {
         assert_eq!(got.priority, 3);
     }
 
+    #[test]
+    fn update_bonded_from() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Compound").id("bd-bond1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        let bond = BondRef {
+            source_id: "bd-other".into(),
+            bond_type: "sequential".into(),
+            bond_point: "bd-step".into(),
+        };
+        let updates = IssueUpdates {
+            bonded_from: Some(vec![bond.clone()]),
+            ..Default::default()
+        };
+        store
+            .update_issue_impl("bd-bond1", &updates, "bob")
+            .unwrap();
+
+        let got = store.get_issue_impl("bd-bond1").unwrap();
+        assert_eq!(got.bonded_from, vec![bond]);
+    }
+
     #[test]
     fn close_issue() {
         let store = test_store();
@@ -989,6 +1300,121 @@ mod tests {
         assert_eq!(got.close_reason, "completed");
     }
 
+    #[test]
+    fn update_status_tracks_close_and_reopen() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Cycle").id("bd-cyc1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        let close = IssueUpdates {
+            status: Some(Status::Closed),
+            close_reason: Some("done".into()),
+            ..Default::default()
+        };
+        store.update_issue_impl("bd-cyc1", &close, "bob").unwrap();
+        let got = store.get_issue_impl("bd-cyc1").unwrap();
+        assert!(got.closed_at.is_some());
+
+        let reopen = IssueUpdates {
+            status: Some(Status::Open),
+            ..Default::default()
+        };
+        store.update_issue_impl("bd-cyc1", &reopen, "bob").unwrap();
+        let got = store.get_issue_impl("bd-cyc1").unwrap();
+        assert!(got.closed_at.is_none());
+        assert_eq!(got.close_reason, "");
+
+        let kinds: Vec<String> = store
+            .get_events_impl("bd-cyc1", 10)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.event_type.as_str().to_string())
+            .collect();
+        assert_eq!(kinds, vec!["reopened", "updated", "closed", "created"]);
+    }
+
+    #[test]
+    fn update_missing_issue_is_not_found() {
+        let store = test_store();
+        let updates = IssueUpdates {
+            title: Some("x".into()),
+            ..Default::default()
+        };
+        let err = store
+            .update_issue_impl("bd-nope", &updates, "bob")
+            .unwrap_err();
+        assert!(err.is_not_found());
+    }
+
+    #[test]
+    fn heartbeat_sets_only_last_activity() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Agent").id("bd-agent1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+        let before = store.get_issue_impl("bd-agent1").unwrap();
+
+        let at = Utc::now() + chrono::Duration::minutes(5);
+        store.record_heartbeat_impl("bd-agent1", at).unwrap();
+        let got = store.get_issue_impl("bd-agent1").unwrap();
+        assert_eq!(
+            got.last_activity.map(|t| t.timestamp()),
+            Some(at.timestamp())
+        );
+        assert_eq!(got.updated_at, before.updated_at);
+        assert_eq!(store.get_events_impl("bd-agent1", 10).unwrap().len(), 1);
+
+        let err = store.record_heartbeat_impl("bd-nope", at).unwrap_err();
+        assert!(err.is_not_found());
+    }
+
+    #[test]
+    fn import_issue_upserts_and_preserves_timestamps() {
+        let store = test_store();
+        let created = "2024-01-02T03:04:05Z".parse::<DateTime<Utc>>().unwrap();
+        let issue = IssueBuilder::new("Imported")
+            .id("bd-imp1")
+            .created_at(created)
+            .updated_at(created)
+            .build();
+        {
+            let conn = store.lock_conn().unwrap();
+            import_issue_on_conn(&conn, &issue, "alice").unwrap();
+        }
+        store.add_label_impl("bd-imp1", "keep", "alice").unwrap();
+
+        let mut changed = issue.clone();
+        changed.title = "Imported again".into();
+        {
+            let conn = store.lock_conn().unwrap();
+            import_issue_on_conn(&conn, &changed, "alice").unwrap();
+        }
+
+        let got = store.get_issue_impl("bd-imp1").unwrap();
+        assert_eq!(got.title, "Imported again");
+        assert_eq!(got.created_at, created);
+        assert_eq!(got.updated_at, created);
+        assert_eq!(got.content_hash, compute_content_hash(&changed));
+        assert_eq!(store.get_labels_impl("bd-imp1").unwrap(), vec!["keep"]);
+    }
+
+    #[test]
+    fn scan_tolerates_null_metadata() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Null meta").id("bd-null1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+        {
+            let conn = store.lock_conn().unwrap();
+            conn.execute(
+                "UPDATE issues SET metadata = NULL WHERE id = 'bd-null1'",
+                [],
+            )
+            .unwrap();
+        }
+
+        let got = store.get_issue_impl("bd-null1").unwrap();
+        assert!(got.metadata.is_none());
+    }
+
     #[test]
     fn delete_issue() {
         let store = test_store();
@@ -1022,6 +1448,22 @@ mod tests {
         assert_eq!(results[0].id, "bd-s1");
     }
 
+    #[test]
+    fn search_accepts_groups_next_to_terms() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Login crash on startup")
+            .id("bd-g1")
+            .build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        for query in ["(login) (crash)", "login (crash)", "(login) crash"] {
+            let results = store
+                .search_issues_impl(query, &IssueFilter::default())
+                .unwrap();
+            assert_eq!(results.len(), 1, "{query}");
+        }
+    }
+
     #[test]
     fn search_issues_by_status_filter() {
         let store = test_store();
@@ -1045,4 +1487,178 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].id, "bd-sf1");
     }
+
+    #[test]
+    fn search_ranks_title_matches_first() {
+        let store = test_store();
+        let body_only = IssueBuilder::new("Session cleanup")
+            .id("bd-r1")
+            .description("Expired tokens break the login page")
+            .build();
+        let title = IssueBuilder::new("Login redirect loop").id("bd-r2").build();
+        store.create_issue_impl(&body_only, "alice").unwrap();
+        store.create_issue_impl(&title, "alice").unwrap();
+
+        let hits = store
+            .search_issues_ranked_impl("login", &IssueFilter::default())
+            .unwrap();
+        let ids: Vec<&str> = hits.iter().map(|h| h.issue.id.as_str()).collect();
+        assert_eq!(ids, ["bd-r2", "bd-r1"]);
+        assert!(hits[0].score > hits[1].score);
+        assert!(hits[1].snippet.contains("**login**"), "{}", hits[1].snippet);
+    }
+
+    #[test]
+    fn search_covers_design_and_comments() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Cache layer")
+            .id("bd-c1")
+            .design("Use an LRU eviction policy")
+            .build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+        store
+            .add_comment_impl("bd-c1", "bob", "Watch out for thundering herds")
+            .unwrap();
+
+        let filter = IssueFilter::default();
+        assert_eq!(
+            store.search_issues_impl("eviction", &filter).unwrap().len(),
+            1
+        );
+        assert_eq!(
+            store
+                .search_issues_impl("thundering", &filter)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            store
+                .search_issues_impl("comments:herds", &filter)
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(
+            store
+                .search_issues_impl("title:herds", &filter)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn search_index_follows_updates_and_deletes() {
+        let store = test_store();
+        let issue = IssueBuilder::new("Old title").id("bd-u1").build();
+        store.create_issue_impl(&issue, "alice").unwrap();
+
+        let updates = IssueUpdates {
+            title: Some("Renamed widget".into()),
+            ..Default::default()
+        };
+        store.update_issue_impl("bd-u1", &updates, "alice").unwrap();
+
+        let filter = IssueFilter::default();
+        assert!(store.search_issues_impl("old", &filter).unwrap().is_empty());
+        assert_eq!(
+            store.search_issues_impl("widget", &filter).unwrap().len(),
+            1
+        );
+
+        store.delete_issue_impl("bd-u1").unwrap();
+        assert!(
+            store
+                .search_issues_impl("widget", &filter)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn search_supports_prefix_phrase_and_boolean_queries() {
+        let store = test_store();
+        for (id, title) in [
+            ("bd-q1", "Null pointer in parser"),
+            ("bd-q2", "Pointer arithmetic overflow"),
+            ("bd-q3", "Parser null check"),
+        ] {
+            let issue = IssueBuilder::new(title).id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+
+        let ids = |q: &str| -> Vec<String> {
+            let mut ids: Vec<String> = store
+                .search_issues_impl(q, &IssueFilter::default())
+                .unwrap()
+                .into_iter()
+                .map(|i| i.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(r#""null pointer""#), ["bd-q1"]);
+        assert_eq!(ids("pars*"), ["bd-q1", "bd-q3"]);
+        assert_eq!(ids("overflow OR check"), ["bd-q2", "bd-q3"]);
+        assert_eq!(ids("pointer NOT parser"), ["bd-q2"]);
+        assert_eq!(ids("bd-q2"), ["bd-q2"]);
+        // Operators and punctuation alone never reach FTS5 as syntax.
+        assert_eq!(ids("AND"), Vec::<String>::new());
+        assert_eq!(ids("(("), Vec::<String>::new());
+    }
+
+    #[test]
+    fn query_issues_uses_filter_or_sql() {
+        use beads_core::dependency::Dependency;
+        use beads_core::enums::DependencyType;
+
+        let store = test_store();
+        for (id, priority, assignee) in
+            [("bd-x1", 0, ""), ("bd-x2", 1, "alice"), ("bd-x3", 3, "bob")]
+        {
+            let issue = IssueBuilder::new(format!("Issue {id}"))
+                .id(id)
+                .priority(priority)
+                .assignee(assignee)
+                .build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        store.add_label_impl("bd-x1", "backend", "alice").unwrap();
+        store
+            .add_dependency_impl(
+                &Dependency {
+                    issue_id: "bd-x3".into(),
+                    depends_on_id: "bd-x1".into(),
+                    dep_type: DependencyType::Blocks,
+                    created_at: Utc::now(),
+                    created_by: "alice".into(),
+                    metadata: String::new(),
+                    thread_id: String::new(),
+                },
+                "alice",
+            )
+            .unwrap();
+
+        let ids = |q: &str| -> Vec<String> {
+            let query = Query::parse(q).unwrap();
+            let mut ids: Vec<String> = store
+                .query_issues_impl(&query, &IssueFilter::default())
+                .unwrap()
+                .into_iter()
+                .map(|i| i.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        // Compiled to an IssueFilter.
+        assert_eq!(ids("p<=1 label:backend"), ["bd-x1"]);
+        assert_eq!(ids("p>=1"), ["bd-x2", "bd-x3"]);
+        // Compiled to SQL.
+        assert_eq!(ids("label:backend OR priority>2"), ["bd-x1", "bd-x3"]);
+        assert_eq!(ids("NOT assignee:none"), ["bd-x2", "bd-x3"]);
+        assert_eq!(ids("blocked:true"), ["bd-x3"]);
+        assert_eq!(ids("blocks:bd-x3 OR dep:bd-x1"), ["bd-x1", "bd-x3"]);
+        assert_eq!(ids("updated>1h"), ["bd-x1", "bd-x2", "bd-x3"]);
+        assert_eq!(ids("NOT closed>1h"), ["bd-x1", "bd-x2", "bd-x3"]);
+    }
 }
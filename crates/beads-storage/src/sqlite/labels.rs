@@ -1,5 +1,7 @@
 //! Label CRUD operations for [`SqliteStore`].
 
+use std::collections::HashMap;
+
 use chrono::Utc;
 use rusqlite::{Connection, params};
 
@@ -110,6 +112,33 @@ impl SqliteStore {
         get_labels_on_conn(&conn, issue_id)
     }
 
+    /// Returns the labels of several issues, keyed by issue ID.
+    pub fn get_labels_for_issues_impl(
+        &self,
+        issue_ids: &[String],
+    ) -> Result<HashMap<String, Vec<String>>> {
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        if issue_ids.is_empty() {
+            return Ok(map);
+        }
+        let conn = self.lock_conn()?;
+        let placeholders = issue_ids.iter().map(|_| "?").collect::<Vec<_>>().join(",");
+        let sql = format!(
+            "SELECT issue_id, label FROM labels WHERE issue_id IN ({placeholders})
+             ORDER BY issue_id, label"
+        );
+        let mut stmt = conn.prepare(&sql)?;
+        let params = rusqlite::params_from_iter(issue_ids.iter());
+        let rows = stmt.query_map(params, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        for row in rows {
+            let (id, label) = row?;
+            map.entry(id).or_default().push(label);
+        }
+        Ok(map)
+    }
+
     /// Returns all issues with the given label.
     pub fn get_issues_by_label_impl(&self, label: &str) -> Result<Vec<Issue>> {
         let conn = self.lock_conn()?;
@@ -184,4 +213,21 @@ mod tests {
         let issues = store.get_issues_by_label_impl("p0").unwrap();
         assert_eq!(issues.len(), 2);
     }
+
+    #[test]
+    fn get_labels_for_issues() {
+        let store = test_store();
+        for id in ["bd-lbl5", "bd-lbl6", "bd-lbl7"] {
+            let issue = IssueBuilder::new("Issue").id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        store.add_label_impl("bd-lbl5", "ui", "alice").unwrap();
+        store.add_label_impl("bd-lbl5", "bug", "alice").unwrap();
+        store.add_label_impl("bd-lbl6", "ui", "alice").unwrap();
+
+        let ids = vec!["bd-lbl5".to_string(), "bd-lbl7".to_string()];
+        let map = store.get_labels_for_issues_impl(&ids).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map["bd-lbl5"], vec!["bug", "ui"]);
+    }
 }
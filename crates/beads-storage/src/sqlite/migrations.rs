@@ -0,0 +1,608 @@
+//! Versioned, checksummed schema migrations.
+//!
+//! The DDL in [`schema::SCHEMA_STATEMENTS`] is schema version
+//! [`schema::BASE_SCHEMA_VERSION`]. Every later schema change is a
+//! [`Migration`] in [`schema::MIGRATIONS`] carrying the next version number.
+//! Each migration runs in its own transaction, is recorded in the
+//! `schema_migrations` table together with a checksum, and bumps the
+//! `schema_version` config key. A recorded checksum that no longer matches the
+//! binary's migration means the migration was edited after it shipped, and
+//! further migrations are refused.
+
+use std::path::Path;
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+
+use crate::error::{Result, StorageError};
+use crate::sqlite::schema;
+
+/// DDL for the table recording applied migrations.
+const HISTORY_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version    INTEGER PRIMARY KEY,
+        name       TEXT NOT NULL,
+        checksum   TEXT NOT NULL,
+        applied_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+    )
+"#;
+
+/// A Rust migration step. Runs inside the migration's transaction.
+pub type MigrationFn = fn(&Connection) -> Result<()>;
+
+/// What a migration does.
+#[derive(Debug, Clone, Copy)]
+pub enum MigrationStep {
+    /// A batch of SQL statements.
+    Sql(&'static str),
+    /// Arbitrary Rust code, for data rewrites SQL cannot express.
+    Rust(MigrationFn),
+}
+
+/// A single schema migration.
+#[derive(Debug, Clone, Copy)]
+pub struct Migration {
+    /// Schema version this migration upgrades to. Versions must be
+    /// consecutive, starting after [`schema::BASE_SCHEMA_VERSION`].
+    pub version: i32,
+    /// Short descriptive name, e.g. `add_foo_column`.
+    pub name: &'static str,
+    /// The work to perform.
+    pub step: MigrationStep,
+}
+
+impl Migration {
+    /// Creates a SQL migration.
+    pub const fn sql(version: i32, name: &'static str, sql: &'static str) -> Self {
+        Self {
+            version,
+            name,
+            step: MigrationStep::Sql(sql),
+        }
+    }
+
+    /// Creates a Rust migration.
+    pub const fn rust(version: i32, name: &'static str, apply: MigrationFn) -> Self {
+        Self {
+            version,
+            name,
+            step: MigrationStep::Rust(apply),
+        }
+    }
+
+    /// SHA-256 hex digest identifying this migration.
+    ///
+    /// SQL migrations hash their statements; Rust code cannot be hashed, so
+    /// Rust migrations hash only their version and name.
+    pub fn checksum(&self) -> String {
+        let mut h = Sha256::new();
+        h.update(self.version.to_string());
+        h.update([0]);
+        h.update(self.name);
+        h.update([0]);
+        match self.step {
+            MigrationStep::Sql(sql) => h.update(sql.trim()),
+            MigrationStep::Rust(_) => h.update("rust"),
+        }
+        format!("{:x}", h.finalize())
+    }
+
+    fn info(&self) -> MigrationInfo {
+        MigrationInfo {
+            version: self.version,
+            name: self.name.to_string(),
+            checksum: self.checksum(),
+        }
+    }
+}
+
+/// A migration known to the binary.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationInfo {
+    /// Schema version the migration upgrades to.
+    pub version: i32,
+    /// Migration name.
+    pub name: String,
+    /// Checksum of the migration.
+    pub checksum: String,
+}
+
+/// A migration recorded in the database's `schema_migrations` table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppliedMigration {
+    /// Schema version the migration upgraded to.
+    pub version: i32,
+    /// Migration name.
+    pub name: String,
+    /// Checksum recorded when the migration ran.
+    pub checksum: String,
+    /// When the migration ran (ISO 8601).
+    pub applied_at: String,
+    /// `true` if the binary's copy of the migration no longer matches the
+    /// recorded checksum (or the binary no longer knows the migration).
+    pub modified: bool,
+}
+
+/// Migration state of a database.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStatus {
+    /// Schema version recorded in the database.
+    pub current_version: i32,
+    /// Newest schema version this binary can migrate to.
+    pub latest_version: i32,
+    /// Migrations recorded as applied, oldest first.
+    pub applied: Vec<AppliedMigration>,
+    /// Migrations that have not run yet, in the order they would run.
+    pub pending: Vec<MigrationInfo>,
+}
+
+/// Outcome of a migration run.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    /// Schema version before the run.
+    pub from_version: i32,
+    /// Schema version after the run.
+    pub to_version: i32,
+    /// Migrations that ran, in order.
+    pub applied: Vec<MigrationInfo>,
+    /// Where the pre-migration backup was written, if one was taken.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<String>,
+}
+
+/// Returns the newest version reachable with `migrations`.
+pub const fn latest_version(migrations: &[Migration]) -> i32 {
+    let mut latest = schema::BASE_SCHEMA_VERSION;
+    let mut i = 0;
+    while i < migrations.len() {
+        if migrations[i].version > latest {
+            latest = migrations[i].version;
+        }
+        i += 1;
+    }
+    latest
+}
+
+/// Reads the `schema_version` config key. Returns `None` for a database that
+/// has no schema yet.
+pub(crate) fn schema_version(conn: &Connection) -> Result<Option<i32>> {
+    if !table_exists(conn, "config")? {
+        return Ok(None);
+    }
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM config WHERE key = 'schema_version'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(value.map(|v| v.parse::<i32>().unwrap_or(0)))
+}
+
+/// Writes the `schema_version` config key.
+pub(crate) fn set_schema_version(conn: &Connection, version: i32) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO config (key, value) VALUES ('schema_version', ?1)",
+        rusqlite::params![version.to_string()],
+    )
+    .map_err(|e| StorageError::Migration {
+        name: "schema_version".into(),
+        reason: e.to_string(),
+    })?;
+    Ok(())
+}
+
+/// Fails with [`StorageError::SchemaTooNew`] if `version` is newer than the
+/// newest version in `migrations`.
+pub(crate) fn check_supported(version: i32, migrations: &[Migration]) -> Result<()> {
+    let supported = latest_version(migrations);
+    if version > supported {
+        return Err(StorageError::SchemaTooNew {
+            found: version,
+            supported,
+        });
+    }
+    Ok(())
+}
+
+/// Creates the `schema_migrations` table if needed.
+pub(crate) fn ensure_history_table(conn: &Connection) -> Result<()> {
+    conn.execute_batch(HISTORY_TABLE)
+        .map_err(|e| StorageError::Migration {
+            name: "schema_migrations".into(),
+            reason: e.to_string(),
+        })
+}
+
+/// Reports applied and pending migrations without changing the database.
+pub(crate) fn status(conn: &Connection, migrations: &[Migration]) -> Result<MigrationStatus> {
+    let current_version = schema_version(conn)?.unwrap_or(schema::BASE_SCHEMA_VERSION);
+    check_supported(current_version, migrations)?;
+
+    let mut applied = Vec::new();
+    if table_exists(conn, "schema_migrations")? {
+        let mut stmt = conn.prepare(
+            "SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(AppliedMigration {
+                version: row.get(0)?,
+                name: row.get(1)?,
+                checksum: row.get(2)?,
+                applied_at: row.get(3)?,
+                modified: false,
+            })
+        })?;
+        for row in rows {
+            let mut record = row?;
+            record.modified = migrations
+                .iter()
+                .find(|m| m.version == record.version)
+                .is_none_or(|m| m.checksum() != record.checksum);
+            applied.push(record);
+        }
+    }
+
+    let pending = migrations
+        .iter()
+        .filter(|m| m.version > current_version)
+        .map(Migration::info)
+        .collect();
+
+    Ok(MigrationStatus {
+        current_version,
+        latest_version: latest_version(migrations),
+        applied,
+        pending,
+    })
+}
+
+/// Applies every migration up to and including `target`.
+///
+/// If anything is pending and `backup` is given, the database is first
+/// copied there with `VACUUM INTO`. Each migration runs in its own
+/// transaction, so a failure leaves the database at the last successful
+/// version.
+pub(crate) fn migrate(
+    conn: &mut Connection,
+    migrations: &[Migration],
+    target: i32,
+    backup: Option<&Path>,
+) -> Result<MigrationReport> {
+    let status = status(conn, migrations)?;
+    let from_version = status.current_version;
+
+    if target > status.latest_version {
+        return Err(StorageError::validation(format!(
+            "unknown schema version {target} (latest is {})",
+            status.latest_version
+        )));
+    }
+    if target < from_version {
+        return Err(StorageError::validation(format!(
+            "cannot migrate down from version {from_version} to {target}"
+        )));
+    }
+    if let Some(record) = status.applied.iter().find(|r| r.modified) {
+        return Err(StorageError::Migration {
+            name: record.name.clone(),
+            reason: format!(
+                "checksum mismatch for applied migration {} (recorded {})",
+                record.version, record.checksum
+            ),
+        });
+    }
+
+    let mut pending: Vec<&Migration> = migrations
+        .iter()
+        .filter(|m| m.version > from_version && m.version <= target)
+        .collect();
+    pending.sort_by_key(|m| m.version);
+
+    let mut report = MigrationReport {
+        from_version,
+        to_version: from_version,
+        applied: Vec::new(),
+        backup: None,
+    };
+    if pending.is_empty() {
+        return Ok(report);
+    }
+
+    if let Some(path) = backup {
+        info!(?path, "backing up database before migrating");
+        conn.execute("VACUUM INTO ?1", rusqlite::params![path.to_string_lossy()])
+            .map_err(|e| StorageError::Migration {
+                name: "backup".into(),
+                reason: format!("failed to write {}: {e}", path.display()),
+            })?;
+        report.backup = Some(path.display().to_string());
+    }
+
+    ensure_history_table(conn)?;
+
+    for migration in pending {
+        debug!(
+            version = migration.version,
+            name = migration.name,
+            "applying migration"
+        );
+        let fail = |reason: String| StorageError::Migration {
+            name: migration.name.to_string(),
+            reason,
+        };
+
+        let tx = conn.transaction().map_err(|e| fail(e.to_string()))?;
+        match migration.step {
+            MigrationStep::Sql(sql) => tx.execute_batch(sql).map_err(|e| fail(e.to_string()))?,
+            MigrationStep::Rust(apply) => apply(&tx).map_err(|e| fail(e.to_string()))?,
+        }
+        tx.execute(
+            "INSERT INTO schema_migrations (version, name, checksum) VALUES (?1, ?2, ?3)",
+            rusqlite::params![migration.version, migration.name, migration.checksum()],
+        )
+        .map_err(|e| fail(format!("failed to record migration: {e}")))?;
+        set_schema_version(&tx, migration.version)?;
+        tx.commit().map_err(|e| fail(e.to_string()))?;
+
+        report.to_version = migration.version;
+        report.applied.push(migration.info());
+    }
+
+    info!(
+        from = report.from_version,
+        to = report.to_version,
+        "schema migrated"
+    );
+    Ok(report)
+}
+
+fn table_exists(conn: &Connection, name: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        rusqlite::params![name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_points(conn: &Connection) -> Result<()> {
+        conn.execute("UPDATE issues SET points = length(title)", [])?;
+        Ok(())
+    }
+
+    fn fail_step(_conn: &Connection) -> Result<()> {
+        Err(StorageError::Internal("boom".into()))
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration::sql(
+            2,
+            "add_points_column",
+            "ALTER TABLE issues ADD COLUMN points INTEGER DEFAULT 0",
+        ),
+        Migration::rust(3, "backfill_points", add_points),
+    ];
+
+    /// A version-1 database with a single issue.
+    fn base_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        for stmt in schema::SCHEMA_STATEMENTS {
+            conn.execute_batch(stmt).unwrap();
+        }
+        set_schema_version(&conn, schema::BASE_SCHEMA_VERSION).unwrap();
+        conn.execute(
+            "INSERT INTO issues (id, title) VALUES ('bd-1', 'hello')",
+            [],
+        )
+        .unwrap();
+        conn
+    }
+
+    #[test]
+    fn latest_version_of_empty_list_is_base() {
+        assert_eq!(latest_version(&[]), schema::BASE_SCHEMA_VERSION);
+        assert_eq!(latest_version(TEST_MIGRATIONS), 3);
+    }
+
+    #[test]
+    fn migrate_applies_sql_and_rust_steps() {
+        let mut conn = base_conn();
+        let report = migrate(&mut conn, TEST_MIGRATIONS, 3, None).unwrap();
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.to_version, 3);
+        assert_eq!(report.applied.len(), 2);
+
+        let points: i64 = conn
+            .query_row("SELECT points FROM issues WHERE id = 'bd-1'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(points, 5);
+        assert_eq!(schema_version(&conn).unwrap(), Some(3));
+
+        let status = status(&conn, TEST_MIGRATIONS).unwrap();
+        assert!(status.pending.is_empty());
+        assert_eq!(status.applied.len(), 2);
+        assert!(status.applied.iter().all(|r| !r.modified));
+    }
+
+    #[test]
+    fn migrate_stops_at_target() {
+        let mut conn = base_conn();
+        let report = migrate(&mut conn, TEST_MIGRATIONS, 2, None).unwrap();
+        assert_eq!(report.to_version, 2);
+
+        let status = status(&conn, TEST_MIGRATIONS).unwrap();
+        assert_eq!(status.current_version, 2);
+        assert_eq!(status.pending.len(), 1);
+        assert_eq!(status.pending[0].name, "backfill_points");
+    }
+
+    #[test]
+    fn migrate_refuses_downgrade_and_unknown_versions() {
+        let mut conn = base_conn();
+        migrate(&mut conn, TEST_MIGRATIONS, 3, None).unwrap();
+        let err = migrate(&mut conn, TEST_MIGRATIONS, 2, None).unwrap_err();
+        assert!(err.to_string().contains("cannot migrate down"));
+        let err = migrate(&mut conn, TEST_MIGRATIONS, 9, None).unwrap_err();
+        assert!(err.to_string().contains("unknown schema version"));
+    }
+
+    #[test]
+    fn failed_migration_rolls_back() {
+        let migrations = &[
+            TEST_MIGRATIONS[0],
+            Migration::sql(3, "bad", "ALTER TABLE issues ADD COLUMN x TEXT; SELEKT 1"),
+        ];
+        let mut conn = base_conn();
+        let err = migrate(&mut conn, migrations, 3, None).unwrap_err();
+        assert!(matches!(err, StorageError::Migration { ref name, .. } if name == "bad"));
+
+        // The first migration stuck; the failed one left no trace.
+        assert_eq!(schema_version(&conn).unwrap(), Some(2));
+        assert!(conn.prepare("SELECT x FROM issues").is_err());
+
+        let rust_fail = &[Migration::rust(2, "fails", fail_step)];
+        let mut conn = base_conn();
+        assert!(migrate(&mut conn, rust_fail, 2, None).is_err());
+        assert_eq!(schema_version(&conn).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn edited_migration_is_detected() {
+        let mut conn = base_conn();
+        migrate(&mut conn, &TEST_MIGRATIONS[..1], 2, None).unwrap();
+
+        let edited = &[
+            Migration::sql(
+                2,
+                "add_points_column",
+                "ALTER TABLE issues ADD COLUMN points INTEGER DEFAULT 1",
+            ),
+            TEST_MIGRATIONS[1],
+        ];
+        let status = status(&conn, edited).unwrap();
+        assert!(status.applied[0].modified);
+        let err = migrate(&mut conn, edited, 3, None).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"));
+    }
+
+    #[test]
+    fn newer_schema_is_rejected() {
+        let conn = base_conn();
+        set_schema_version(&conn, 4).unwrap();
+        let err = status(&conn, TEST_MIGRATIONS).unwrap_err();
+        assert!(matches!(
+            err,
+            StorageError::SchemaTooNew {
+                found: 4,
+                supported: 3
+            }
+        ));
+    }
+
+    #[test]
+    fn backup_is_written_before_migrating() {
+        let dir = tempfile::tempdir().unwrap();
+        let backup = dir.path().join("beads.db.bak");
+        let mut conn = base_conn();
+
+        let report = migrate(&mut conn, TEST_MIGRATIONS, 3, Some(&backup)).unwrap();
+        assert_eq!(
+            report.backup.as_deref(),
+            Some(backup.display().to_string().as_str())
+        );
+
+        let copy = Connection::open(&backup).unwrap();
+        assert_eq!(schema_version(&copy).unwrap(), Some(1));
+        assert!(copy.prepare("SELECT points FROM issues").is_err());
+
+        // Nothing pending: no backup.
+        let again = dir.path().join("again.bak");
+        let report = migrate(&mut conn, TEST_MIGRATIONS, 3, Some(&again)).unwrap();
+        assert!(report.backup.is_none());
+        assert!(!again.exists());
+    }
+
+    #[test]
+    fn schema_migrations_backfill_search_index() {
+        let mut conn = base_conn();
+        migrate(
+            &mut conn,
+            schema::MIGRATIONS,
+            schema::CURRENT_SCHEMA_VERSION,
+            None,
+        )
+        .unwrap();
+
+        let id: String = conn
+            .query_row(
+                "SELECT issue_id FROM issues_fts WHERE issues_fts MATCH 'hello'",
+                [],
+                |r| r.get(0),
+            )
+            .unwrap();
+        assert_eq!(id, "bd-1");
+    }
+
+    #[test]
+    fn search_index_is_keyed_by_rowid() {
+        let mut conn = base_conn();
+        migrate(&mut conn, schema::MIGRATIONS, 2, None).unwrap();
+        conn.execute(
+            "INSERT INTO comments (issue_id, author, text) VALUES ('bd-1', 'a', 'lgtm')",
+            [],
+        )
+        .unwrap();
+        migrate(
+            &mut conn,
+            schema::MIGRATIONS,
+            schema::CURRENT_SCHEMA_VERSION,
+            None,
+        )
+        .unwrap();
+
+        let matches = |conn: &Connection, query: &str| -> Vec<String> {
+            let mut stmt = conn
+                .prepare("SELECT issue_id FROM issues_fts WHERE issues_fts MATCH ?1")
+                .unwrap();
+            stmt.query_map([query], |r| r.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect()
+        };
+        assert_eq!(matches(&conn, "lgtm"), ["bd-1"]);
+
+        // Writes after a VACUUM still find their rows.
+        conn.execute_batch(
+            "INSERT INTO issues (id, title) VALUES ('bd-2', 'second');
+             VACUUM;
+             UPDATE issues SET title = 'goodbye' WHERE id = 'bd-1';
+             UPDATE issues SET id = 'bd-3' WHERE id = 'bd-2';
+             INSERT INTO comments (issue_id, author, text) VALUES ('bd-3', 'a', 'shipit');",
+        )
+        .unwrap();
+        assert!(matches(&conn, "hello").is_empty());
+        assert_eq!(matches(&conn, "goodbye"), ["bd-1"]);
+        assert_eq!(matches(&conn, "shipit"), ["bd-3"]);
+        assert_eq!(matches(&conn, "second"), ["bd-3"]);
+
+        conn.execute("DELETE FROM issues WHERE id = 'bd-1'", [])
+            .unwrap();
+        let rows: i64 = conn
+            .query_row("SELECT COUNT(*) FROM issues_fts", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 1);
+        let keys: i64 = conn
+            .query_row("SELECT COUNT(*) FROM issues_fts_keys", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(keys, 1);
+    }
+}
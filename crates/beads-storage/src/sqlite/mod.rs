@@ -3,8 +3,11 @@
 mod comments;
 mod config;
 mod dependencies;
+pub mod fts;
+mod health;
 mod issues;
 mod labels;
+pub mod migrations;
 mod queries;
 pub mod schema;
 mod store;
@@ -1,51 +1,108 @@
-//! Complex queries: ready work, blocked issues, epic status.
+//! Complex queries: ready work, blocked issues, epic status, stale issues.
 
-use chrono::Utc;
+use chrono::{Duration, Utc};
+use rusqlite::params;
 
-use beads_core::filter::WorkFilter;
+use beads_core::dependency::FAILURE_CLOSE_KEYWORDS;
+use beads_core::enums::SortPolicy;
+use beads_core::filter::{StaleFilter, WorkFilter};
 use beads_core::issue::Issue;
 
-use crate::error::Result;
+use crate::error::{Result, StorageError};
 use crate::sqlite::issues::{ISSUE_COLUMNS, format_datetime, scan_issue};
 use crate::sqlite::store::SqliteStore;
-use crate::traits::{BlockedIssue, EpicStatus, Statistics};
+use crate::traits::{BlockedIssue, EpicStatus, SkippedIssue, Statistics};
+
+/// Hybrid sort: an issue moves up one priority tier per this many days of
+/// age, up to P0.
+const HYBRID_AGING_DAYS: i32 = 7;
+
+/// Hybrid sort: the most tiers aging can move an issue up, so that old
+/// backlog never overtakes fresh urgent work.
+const HYBRID_MAX_AGING_TIERS: i32 = 2;
+
+/// `blocked_ids`: issues with an open blocks, conditional-blocks or
+/// waits-for blocker, plus children of blocked or deferred issues, down the
+/// parent-child tree.
+const BLOCKED_CTE: &str = "blocked_ids(id) AS (
+        SELECT d.issue_id FROM dependencies d
+        INNER JOIN issues blocker ON blocker.id = d.depends_on_id
+        WHERE d.type IN ('blocks', 'conditional-blocks', 'waits-for')
+          AND blocker.status IN ('open', 'in_progress', 'blocked', 'deferred', 'hooked')
+        UNION
+        SELECT d.issue_id FROM dependencies d
+        INNER JOIN issues parent ON parent.id = d.depends_on_id
+        WHERE d.type = 'parent-child' AND parent.status IN ('blocked', 'deferred')
+        UNION
+        SELECT d.issue_id FROM dependencies d
+        INNER JOIN blocked_ids ON d.depends_on_id = blocked_ids.id
+        WHERE d.type = 'parent-child'
+    )";
+
+/// `downstream(root, id)`: every issue waiting on `root` through blocking
+/// edges, directly or transitively.
+const DOWNSTREAM_CTE: &str = ",
+    downstream(root, id) AS (
+        SELECT depends_on_id, issue_id FROM dependencies
+        WHERE type IN ('blocks', 'conditional-blocks', 'waits-for')
+        UNION
+        SELECT downstream.root, d.issue_id FROM dependencies d
+        INNER JOIN downstream ON d.depends_on_id = downstream.id
+        WHERE d.type IN ('blocks', 'conditional-blocks', 'waits-for')
+    )";
 
 impl SqliteStore {
     /// Returns issues that are ready to work on.
     ///
     /// An issue is ready if:
-    /// - status is "open"
-    /// - it has no open blocking dependencies (type="blocks")
+    /// - its status is `filter.status` ("open" by default)
+    /// - it is not blocked: no open blocks, conditional-blocks or waits-for
+    ///   blocker, and no blocked or deferred ancestor via parent-child
+    /// - it is not skipped: no conditional-blocks blocker closed without
+    ///   failing
     /// - it is not ephemeral (unless `include_ephemeral` is set)
     /// - it is not deferred past now (unless `include_deferred` is set)
-    /// - it is not a template
+    /// - it is not a molecule step (unless `include_mol_steps` is set)
+    /// - it is not a template or a gate
+    ///
+    /// `parent_id` restricts the result to the (recursive) descendants of an
+    /// issue. See [`SortPolicy`] for the orderings.
     pub fn get_ready_work_impl(&self, filter: &WorkFilter) -> Result<Vec<Issue>> {
+        check_label_regex(filter)?;
+
         let conn = self.lock_conn()?;
         let now = Utc::now();
         let now_str = format_datetime(&now);
 
         let mut where_clauses: Vec<String> = vec![
-            "i.status = 'open'".to_string(),
             "i.is_template = 0".to_string(),
+            "i.issue_type != 'gate'".to_string(),
+            "i.id NOT IN (SELECT id FROM blocked_ids)".to_string(),
         ];
         let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
         let mut param_idx = 1;
 
-        // Exclude issues with open blocking dependencies.
-        where_clauses.push(
+        match filter.status {
+            Some(ref status) => {
+                where_clauses.push(format!("i.status = ?{param_idx}"));
+                param_values.push(Box::new(status.as_str().to_string()));
+                param_idx += 1;
+            }
+            None => where_clauses.push("i.status = 'open'".to_string()),
+        }
+
+        // Exclude conditional dependents whose blocker succeeded.
+        where_clauses.push(format!(
             "NOT EXISTS (
                 SELECT 1 FROM dependencies d
                 INNER JOIN issues blocker ON blocker.id = d.depends_on_id
                 WHERE d.issue_id = i.id
-                  AND d.type IN ('blocks', 'parent-child', 'conditional-blocks', 'waits-for')
-                  AND blocker.status IN ('open', 'in_progress', 'blocked', 'deferred', 'hooked')
-            )"
-            .to_string(),
-        );
-
-        if !filter.include_ephemeral {
-            where_clauses.push("(i.ephemeral = 0 OR i.ephemeral IS NULL)".to_string());
-        }
+                  AND d.type = 'conditional-blocks'
+                  AND blocker.status = 'closed'
+                  AND NOT {}
+            )",
+            failure_close_sql("blocker.close_reason")
+        ));
 
         if !filter.include_deferred {
             where_clauses.push(format!(
@@ -55,70 +112,31 @@ impl SqliteStore {
             param_idx += 1;
         }
 
-        // Optional filters.
-        if let Some(ref issue_type) = filter.issue_type {
-            where_clauses.push(format!("i.issue_type = ?{param_idx}"));
-            param_values.push(Box::new(issue_type.clone()));
-            param_idx += 1;
-        }
-        if let Some(priority) = filter.priority {
-            where_clauses.push(format!("i.priority = ?{param_idx}"));
-            param_values.push(Box::new(priority));
-            param_idx += 1;
-        }
-        if let Some(ref assignee) = filter.assignee {
-            where_clauses.push(format!("i.assignee = ?{param_idx}"));
-            param_values.push(Box::new(assignee.clone()));
-            param_idx += 1;
-        }
-        if filter.unassigned {
-            where_clauses.push("(i.assignee IS NULL OR i.assignee = '')".to_string());
-        }
-        if let Some(ref mol_type) = filter.mol_type {
-            where_clauses.push(format!("i.mol_type = ?{param_idx}"));
-            param_values.push(Box::new(mol_type.as_str().to_string()));
-            param_idx += 1;
-        }
-        if let Some(ref wisp_type) = filter.wisp_type {
-            where_clauses.push(format!("i.wisp_type = ?{param_idx}"));
-            param_values.push(Box::new(wisp_type.as_str().to_string()));
-            param_idx += 1;
-        }
-
-        // Label filters (AND).
-        for label in &filter.labels {
-            where_clauses.push(format!(
-                "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label = ?{param_idx})"
-            ));
-            param_values.push(Box::new(label.clone()));
-            param_idx += 1;
-        }
-
-        // Label filters (OR).
-        if !filter.labels_any.is_empty() {
-            let placeholders: Vec<String> = filter
-                .labels_any
-                .iter()
-                .enumerate()
-                .map(|(j, _)| format!("?{}", param_idx + j))
-                .collect();
-            where_clauses.push(format!(
-                "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label IN ({}))",
-                placeholders.join(",")
-            ));
-            for label in &filter.labels_any {
-                param_values.push(Box::new(label.clone()));
-            }
-            param_idx += filter.labels_any.len();
-        }
+        push_scope_clauses(
+            filter,
+            &mut where_clauses,
+            &mut param_values,
+            &mut param_idx,
+        );
 
         let where_sql = where_clauses.join(" AND ");
 
         // Sort order.
-        let order_sql = match filter.sort_policy {
-            beads_core::enums::SortPolicy::Priority => "i.priority ASC, i.created_at ASC",
-            beads_core::enums::SortPolicy::Oldest => "i.created_at ASC",
-            _ => "i.priority ASC, i.created_at ASC", // Hybrid default
+        let (order_sql, downstream_cte) = match filter.sort_policy {
+            SortPolicy::Oldest => ("i.created_at ASC, i.id ASC".to_string(), ""),
+            SortPolicy::Hybrid => {
+                param_values.push(Box::new(now_str.clone()));
+                let order = format!(
+                    "MAX(i.priority - MIN(CAST((julianday(?{param_idx}) - julianday(i.created_at)) / {HYBRID_AGING_DAYS} AS INTEGER), {HYBRID_MAX_AGING_TIERS}), 0) ASC,
+                     (SELECT COUNT(*) FROM downstream
+                      INNER JOIN issues waiting ON waiting.id = downstream.id
+                      WHERE downstream.root = i.id AND waiting.status != 'closed') DESC,
+                     i.priority ASC, i.created_at ASC, i.id ASC"
+                );
+                param_idx += 1;
+                (order, DOWNSTREAM_CTE)
+            }
+            _ => ("i.priority ASC, i.created_at ASC, i.id ASC".to_string(), ""),
         };
 
         let limit_sql = filter
@@ -126,10 +144,11 @@ impl SqliteStore {
             .map(|l| format!(" LIMIT {l}"))
             .unwrap_or_default();
 
-        // We need to alias the issue table as `i` but select with the ISSUE_COLUMNS
-        // which reference bare column names. We'll use a subquery approach.
+        // ISSUE_COLUMNS are bare column names; they resolve against `i` as
+        // the only table in the outer FROM.
         let sql = format!(
-            "SELECT {ISSUE_COLUMNS} FROM issues i WHERE {where_sql} ORDER BY {order_sql}{limit_sql}"
+            "WITH RECURSIVE {BLOCKED_CTE}{downstream_cte}
+             SELECT {ISSUE_COLUMNS} FROM issues i WHERE {where_sql} ORDER BY {order_sql}{limit_sql}"
         );
 
         let param_refs: Vec<&dyn rusqlite::types::ToSql> =
@@ -147,7 +166,8 @@ impl SqliteStore {
         Ok(issues)
     }
 
-    /// Returns issues that have at least one open blocking dependency.
+    /// Returns issues that have at least one open blocks or
+    /// conditional-blocks dependency.
     pub fn get_blocked_issues_impl(&self, filter: &WorkFilter) -> Result<Vec<BlockedIssue>> {
         let conn = self.lock_conn()?;
 
@@ -163,7 +183,7 @@ impl SqliteStore {
                 SELECT 1 FROM dependencies d
                 INNER JOIN issues blocker ON blocker.id = d.depends_on_id
                 WHERE d.issue_id = i.id
-                  AND d.type = 'blocks'
+                  AND d.type IN ('blocks', 'conditional-blocks')
                   AND blocker.status IN ('open', 'in_progress', 'blocked', 'deferred', 'hooked')
             )"
             .to_string(),
@@ -192,7 +212,7 @@ impl SqliteStore {
                      FROM dependencies d
                      INNER JOIN issues blocker ON blocker.id = d.depends_on_id
                      WHERE d.issue_id = i.id
-                       AND d.type = 'blocks'
+                       AND d.type IN ('blocks', 'conditional-blocks')
                        AND blocker.status IN ('open', 'in_progress', 'blocked', 'deferred', 'hooked')
                     ) AS blocked_by_count
              FROM issues i
@@ -222,6 +242,77 @@ impl SqliteStore {
         Ok(result)
     }
 
+    /// Returns open issues with a conditional-blocks blocker that closed
+    /// without failing, or that was itself skipped. Skipping cascades down
+    /// conditional chains, each issue naming the blocker that succeeded.
+    pub fn get_skipped_issues_impl(&self, filter: &WorkFilter) -> Result<Vec<SkippedIssue>> {
+        check_label_regex(filter)?;
+        let conn = self.lock_conn()?;
+
+        let mut where_clauses: Vec<String> = vec!["i.status = 'open'".to_string()];
+        let mut param_values: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
+        let mut param_idx = 1;
+        push_scope_clauses(
+            filter,
+            &mut where_clauses,
+            &mut param_values,
+            &mut param_idx,
+        );
+
+        let where_sql = where_clauses.join(" AND ");
+        let failed = failure_close_sql("blocker.close_reason");
+        let sql = format!(
+            "WITH RECURSIVE skipped(id, blocker_id, blocker_close_reason) AS (
+                SELECT d.issue_id, blocker.id, COALESCE(blocker.close_reason, '')
+                FROM dependencies d
+                INNER JOIN issues blocker ON blocker.id = d.depends_on_id
+                WHERE d.type = 'conditional-blocks'
+                  AND blocker.status = 'closed' AND NOT {failed}
+                UNION
+                SELECT d.issue_id, skipped.blocker_id, skipped.blocker_close_reason
+                FROM skipped
+                INNER JOIN issues mid ON mid.id = skipped.id AND mid.status != 'closed'
+                INNER JOIN dependencies d
+                    ON d.depends_on_id = skipped.id AND d.type = 'conditional-blocks'
+             )
+             SELECT {ISSUE_COLUMNS}, blocker_id, blocker_close_reason
+             FROM (
+                SELECT i.*, skipped.blocker_id, skipped.blocker_close_reason
+                FROM issues i
+                INNER JOIN skipped ON skipped.id = i.id
+                WHERE {where_sql}
+             )
+             ORDER BY priority ASC, created_at ASC, blocker_id ASC"
+        );
+
+        let param_refs: Vec<&dyn rusqlite::types::ToSql> =
+            param_values.iter().map(|p| p.as_ref()).collect();
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(param_refs.as_slice(), |row| {
+            Ok(SkippedIssue {
+                issue: scan_issue(row)?,
+                blocker_id: row.get("blocker_id")?,
+                blocker_close_reason: row.get("blocker_close_reason")?,
+            })
+        })?;
+
+        // One entry per issue, naming its first successful blocker.
+        let mut result: Vec<SkippedIssue> = Vec::new();
+        for row in rows {
+            let skipped = row?;
+            if !result.iter().any(|s| s.issue.id == skipped.issue.id) {
+                result.push(skipped);
+            }
+        }
+        if let Some(limit) = filter.limit {
+            result.truncate(limit.max(0) as usize);
+        }
+
+        let _ = param_idx;
+        Ok(result)
+    }
+
     /// Returns epics where all children are closed.
     pub fn get_epics_eligible_for_closure_impl(&self) -> Result<Vec<EpicStatus>> {
         let conn = self.lock_conn()?;
@@ -281,6 +372,38 @@ impl SqliteStore {
         Ok(result)
     }
 
+    /// Returns non-closed issues that have not been updated since
+    /// `filter.updated_before` (else in `filter.days` days), least recently
+    /// updated first.
+    pub fn get_stale_issues_impl(&self, filter: &StaleFilter) -> Result<Vec<Issue>> {
+        let conn = self.lock_conn()?;
+        let cutoff = filter
+            .updated_before
+            .unwrap_or_else(|| Utc::now() - Duration::days(i64::from(filter.days)));
+        let cutoff = format_datetime(&cutoff);
+
+        let mut sql = format!(
+            "SELECT {ISSUE_COLUMNS} FROM issues
+             WHERE status != 'closed'
+               AND is_template = 0
+               AND issue_type != 'gate'
+               AND updated_at < ?1
+               AND (?2 IS NULL OR status = ?2)
+             ORDER BY updated_at ASC"
+        );
+        if let Some(limit) = filter.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![cutoff, filter.status], scan_issue)?;
+        let mut issues = Vec::new();
+        for row in rows {
+            issues.push(row?);
+        }
+        Ok(issues)
+    }
+
     /// Returns aggregate statistics.
     pub fn get_statistics_impl(&self) -> Result<Statistics> {
         let conn = self.lock_conn()?;
@@ -369,6 +492,147 @@ impl SqliteStore {
         })
     }
 }
+/// Rejects an invalid `label_regex` up front, rather than as an SQLite
+/// function error mid-query.
+fn check_label_regex(filter: &WorkFilter) -> Result<()> {
+    if let Some(ref pattern) = filter.label_regex {
+        regex::Regex::new(pattern).map_err(|e| StorageError::Validation {
+            message: format!("invalid label regex '{pattern}': {e}"),
+        })?;
+    }
+    Ok(())
+}
+
+/// Appends the clauses that scope a work query to what `filter` selects:
+/// ephemeral and molecule-step exclusion, type, priority, assignee,
+/// molecule and wisp type, `parent_id` descendants and the label filters.
+/// Ready work and skipped issues share these, so acting on either stays
+/// within the same scope.
+fn push_scope_clauses(
+    filter: &WorkFilter,
+    where_clauses: &mut Vec<String>,
+    param_values: &mut Vec<Box<dyn rusqlite::types::ToSql>>,
+    param_idx: &mut usize,
+) {
+    if !filter.include_ephemeral {
+        where_clauses.push("(i.ephemeral = 0 OR i.ephemeral IS NULL)".to_string());
+    }
+
+    // Poured molecule steps are labelled `mol:<root>`.
+    if !filter.include_mol_steps {
+        where_clauses.push(
+            "NOT EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label LIKE 'mol:%')"
+                .to_string(),
+        );
+    }
+
+    // Optional filters.
+    if let Some(ref issue_type) = filter.issue_type {
+        where_clauses.push(format!("i.issue_type = ?{param_idx}"));
+        param_values.push(Box::new(issue_type.clone()));
+        *param_idx += 1;
+    }
+    if let Some(priority) = filter.priority {
+        where_clauses.push(format!("i.priority = ?{param_idx}"));
+        param_values.push(Box::new(priority));
+        *param_idx += 1;
+    }
+    if let Some(ref assignee) = filter.assignee {
+        where_clauses.push(format!("i.assignee = ?{param_idx}"));
+        param_values.push(Box::new(assignee.clone()));
+        *param_idx += 1;
+    }
+    if filter.unassigned {
+        where_clauses.push("(i.assignee IS NULL OR i.assignee = '')".to_string());
+    }
+    if let Some(ref mol_type) = filter.mol_type {
+        where_clauses.push(format!("i.mol_type = ?{param_idx}"));
+        param_values.push(Box::new(mol_type.as_str().to_string()));
+        *param_idx += 1;
+    }
+    if let Some(ref wisp_type) = filter.wisp_type {
+        where_clauses.push(format!("i.wisp_type = ?{param_idx}"));
+        param_values.push(Box::new(wisp_type.as_str().to_string()));
+        *param_idx += 1;
+    }
+
+    // Descendants of parent_id via parent-child edges, at any depth.
+    if let Some(ref parent_id) = filter.parent_id {
+        where_clauses.push(format!(
+            "i.id IN (
+                WITH RECURSIVE descendants(id) AS (
+                    SELECT issue_id FROM dependencies
+                    WHERE depends_on_id = ?{param_idx} AND type = 'parent-child'
+                    UNION
+                    SELECT d.issue_id FROM dependencies d
+                    INNER JOIN descendants ON d.depends_on_id = descendants.id
+                    WHERE d.type = 'parent-child'
+                )
+                SELECT id FROM descendants
+            )"
+        ));
+        param_values.push(Box::new(parent_id.clone()));
+        *param_idx += 1;
+    }
+
+    // Label filters (AND).
+    for label in &filter.labels {
+        where_clauses.push(format!(
+            "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label = ?{param_idx})"
+        ));
+        param_values.push(Box::new(label.clone()));
+        *param_idx += 1;
+    }
+
+    // Label filters (OR).
+    if !filter.labels_any.is_empty() {
+        let placeholders: Vec<String> = filter
+            .labels_any
+            .iter()
+            .enumerate()
+            .map(|(j, _)| format!("?{}", *param_idx + j))
+            .collect();
+        where_clauses.push(format!(
+            "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label IN ({}))",
+            placeholders.join(",")
+        ));
+        for label in &filter.labels_any {
+            param_values.push(Box::new(label.clone()));
+        }
+        *param_idx += filter.labels_any.len();
+    }
+
+    // Label pattern (glob) and regex filters.
+    if let Some(ref pattern) = filter.label_pattern {
+        where_clauses.push(format!(
+            "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label GLOB ?{param_idx})"
+        ));
+        param_values.push(Box::new(pattern.clone()));
+        *param_idx += 1;
+    }
+    if let Some(ref pattern) = filter.label_regex {
+        where_clauses.push(format!(
+            "EXISTS (SELECT 1 FROM labels WHERE labels.issue_id = i.id AND labels.label REGEXP ?{param_idx})"
+        ));
+        param_values.push(Box::new(pattern.clone()));
+        *param_idx += 1;
+    }
+}
+
+/// SQL that is true when `column` holds a failure close reason; mirrors
+/// [`beads_core::dependency::is_failure_close`].
+fn failure_close_sql(column: &str) -> String {
+    let keywords: Vec<String> = FAILURE_CLOSE_KEYWORDS
+        .iter()
+        .map(|kw| {
+            format!(
+                "INSTR(LOWER(COALESCE({column}, '')), '{}') > 0",
+                kw.replace('\'', "''")
+            )
+        })
+        .collect();
+    format!("({})", keywords.join(" OR "))
+}
 
 #[cfg(test)]
 mod tests {
@@ -420,6 +684,244 @@ mod tests {
         assert!(!ids.contains(&"bd-blk2"));
     }
 
+    #[test]
+    fn conditional_blocks_run_only_on_failure() {
+        let store = test_store();
+        for id in ["bd-a", "bd-b", "bd-c", "bd-d"] {
+            let issue = IssueBuilder::new(id).id(id).status(Status::Open).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        // bd-b runs if bd-a fails, bd-d runs if bd-c fails
+        for (issue_id, depends_on_id) in [("bd-b", "bd-a"), ("bd-d", "bd-c")] {
+            let dep = Dependency {
+                issue_id: issue_id.into(),
+                depends_on_id: depends_on_id.into(),
+                dep_type: DependencyType::ConditionalBlocks,
+                created_at: Utc::now(),
+                created_by: "alice".into(),
+                metadata: String::new(),
+                thread_id: String::new(),
+            };
+            store.add_dependency_impl(&dep, "alice").unwrap();
+        }
+        let ready = |store: &SqliteStore| -> Vec<String> {
+            let work = store.get_ready_work_impl(&WorkFilter::default()).unwrap();
+            work.into_iter().map(|i| i.id).collect()
+        };
+        assert_eq!(ready(&store), vec!["bd-a", "bd-c"]);
+        let blocked = store
+            .get_blocked_issues_impl(&WorkFilter::default())
+            .unwrap();
+        assert_eq!(blocked.len(), 2);
+
+        store
+            .close_issue_impl("bd-a", "Build failed", "alice", "")
+            .unwrap();
+        store.close_issue_impl("bd-c", "Done", "alice", "").unwrap();
+        assert_eq!(ready(&store), vec!["bd-b"]);
+        let skipped = store
+            .get_skipped_issues_impl(&WorkFilter::default())
+            .unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].issue.id, "bd-d");
+        assert_eq!(skipped[0].blocker_id, "bd-c");
+        assert!(
+            store
+                .get_blocked_issues_impl(&WorkFilter::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn skipping_cascades_through_conditional_chains() {
+        let store = test_store();
+        for id in ["bd-a", "bd-b", "bd-c"] {
+            let issue = IssueBuilder::new(id).id(id).status(Status::Open).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        // bd-b runs if bd-a fails, bd-c if bd-b fails
+        add_dep(&store, "bd-b", "bd-a", DependencyType::ConditionalBlocks);
+        add_dep(&store, "bd-c", "bd-b", DependencyType::ConditionalBlocks);
+        store.close_issue_impl("bd-a", "Done", "alice", "").unwrap();
+
+        let skipped = store
+            .get_skipped_issues_impl(&WorkFilter::default())
+            .unwrap();
+        let skipped: Vec<(&str, &str)> = skipped
+            .iter()
+            .map(|s| (s.issue.id.as_str(), s.blocker_id.as_str()))
+            .collect();
+        assert_eq!(skipped, [("bd-b", "bd-a"), ("bd-c", "bd-a")]);
+    }
+
+    fn add_dep(store: &SqliteStore, issue_id: &str, depends_on_id: &str, dep_type: DependencyType) {
+        let dep = Dependency {
+            issue_id: issue_id.into(),
+            depends_on_id: depends_on_id.into(),
+            dep_type,
+            created_at: Utc::now(),
+            created_by: "alice".into(),
+            metadata: String::new(),
+            thread_id: String::new(),
+        };
+        store.add_dependency_impl(&dep, "alice").unwrap();
+    }
+
+    fn ready_ids(store: &SqliteStore, filter: WorkFilter) -> Vec<String> {
+        let work = store.get_ready_work_impl(&filter).unwrap();
+        work.into_iter().map(|i| i.id).collect()
+    }
+
+    #[test]
+    fn ready_work_honors_filters() {
+        let store = test_store();
+        for id in ["bd-epic", "bd-child", "bd-grand", "bd-step", "bd-other"] {
+            let issue = IssueBuilder::new(id).id(id).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        add_dep(&store, "bd-child", "bd-epic", DependencyType::ParentChild);
+        add_dep(&store, "bd-grand", "bd-child", DependencyType::ParentChild);
+        add_dep(&store, "bd-step", "bd-child", DependencyType::ParentChild);
+        store
+            .add_label_impl("bd-child", "tech-debt", "alice")
+            .unwrap();
+        store
+            .add_label_impl("bd-other", "tech-legacy", "alice")
+            .unwrap();
+        store
+            .add_label_impl("bd-step", "mol:bd-epic", "alice")
+            .unwrap();
+
+        // Open parents do not block; descendants are found at any depth.
+        let under_epic = WorkFilter {
+            parent_id: Some("bd-epic".into()),
+            ..WorkFilter::default()
+        };
+        assert_eq!(
+            ready_ids(&store, under_epic.clone()),
+            ["bd-child", "bd-grand"]
+        );
+        let with_steps = WorkFilter {
+            include_mol_steps: true,
+            ..under_epic.clone()
+        };
+        assert_eq!(
+            ready_ids(&store, with_steps),
+            ["bd-child", "bd-grand", "bd-step"]
+        );
+
+        let pattern = WorkFilter {
+            label_pattern: Some("tech-*".into()),
+            ..WorkFilter::default()
+        };
+        assert_eq!(ready_ids(&store, pattern), ["bd-child", "bd-other"]);
+        let regex = WorkFilter {
+            label_regex: Some("^tech-(legacy|old)$".into()),
+            ..WorkFilter::default()
+        };
+        assert_eq!(ready_ids(&store, regex), ["bd-other"]);
+        let invalid = WorkFilter {
+            label_regex: Some("tech-(".into()),
+            ..WorkFilter::default()
+        };
+        assert!(matches!(
+            store.get_ready_work_impl(&invalid),
+            Err(StorageError::Validation { .. })
+        ));
+
+        // A blocked parent blocks its whole subtree.
+        store
+            .update_issue_impl(
+                "bd-child",
+                &crate::traits::IssueUpdates {
+                    status: Some(Status::Blocked),
+                    ..Default::default()
+                },
+                "alice",
+            )
+            .unwrap();
+        assert!(ready_ids(&store, under_epic).is_empty());
+        let blocked = WorkFilter {
+            status: Some(Status::Blocked),
+            ..WorkFilter::default()
+        };
+        assert_eq!(ready_ids(&store, blocked), ["bd-child"]);
+    }
+
+    #[test]
+    fn hybrid_sort_ages_and_unblocks() {
+        let store = test_store();
+        for (id, priority) in [
+            ("bd-new2", 2),
+            ("bd-old2", 2),
+            ("bd-lone1", 1),
+            ("bd-zhub1", 1),
+            ("bd-x", 3),
+            ("bd-y", 3),
+        ] {
+            let issue = IssueBuilder::new(id).id(id).priority(priority).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        {
+            let conn = store.lock_conn().unwrap();
+            conn.execute(
+                "UPDATE issues SET created_at = '2020-01-01T00:00:00.000Z' WHERE id = 'bd-old2'",
+                [],
+            )
+            .unwrap();
+        }
+        add_dep(&store, "bd-x", "bd-zhub1", DependencyType::Blocks);
+        add_dep(&store, "bd-y", "bd-x", DependencyType::Blocks);
+
+        // The aged P2 reaches P0; the P1 that unblocks two issues comes
+        // before the P1 that unblocks none.
+        let hybrid = WorkFilter {
+            sort_policy: SortPolicy::Hybrid,
+            ..WorkFilter::default()
+        };
+        assert_eq!(
+            ready_ids(&store, hybrid),
+            ["bd-old2", "bd-zhub1", "bd-lone1", "bd-new2"]
+        );
+        let priority = WorkFilter {
+            sort_policy: SortPolicy::Priority,
+            ..WorkFilter::default()
+        };
+        assert_eq!(
+            ready_ids(&store, priority),
+            ["bd-lone1", "bd-zhub1", "bd-old2", "bd-new2"]
+        );
+    }
+
+    #[test]
+    fn hybrid_aging_is_capped() {
+        let store = test_store();
+        for (id, priority) in [("bd-fresh0", 0), ("bd-aged4", 4), ("bd-new2", 2)] {
+            let issue = IssueBuilder::new(id).id(id).priority(priority).build();
+            store.create_issue_impl(&issue, "alice").unwrap();
+        }
+        {
+            let conn = store.lock_conn().unwrap();
+            conn.execute(
+                "UPDATE issues SET created_at = '2020-01-01T00:00:00.000Z' WHERE id = 'bd-aged4'",
+                [],
+            )
+            .unwrap();
+        }
+
+        // Years of age lift the P4 two tiers, level with a fresh P2 but
+        // still behind a fresh P0.
+        let hybrid = WorkFilter {
+            sort_policy: SortPolicy::Hybrid,
+            ..WorkFilter::default()
+        };
+        assert_eq!(
+            ready_ids(&store, hybrid),
+            ["bd-fresh0", "bd-new2", "bd-aged4"]
+        );
+    }
+
     #[test]
     fn get_statistics() {
         let store = test_store();
@@ -440,4 +942,39 @@ mod tests {
         assert_eq!(stats.open_issues, 1);
         assert_eq!(stats.closed_issues, 1);
     }
+
+    #[test]
+    fn get_stale_issues_uses_cutoff() {
+        let store = test_store();
+        let old = IssueBuilder::new("Old").id("bd-old1").build();
+        let fresh = IssueBuilder::new("Fresh").id("bd-new1").build();
+        store.create_issue_impl(&old, "alice").unwrap();
+        store.create_issue_impl(&fresh, "alice").unwrap();
+        {
+            let conn = store.lock_conn().unwrap();
+            conn.execute(
+                "UPDATE issues SET updated_at = '2020-01-01T00:00:00.000Z' WHERE id = 'bd-old1'",
+                [],
+            )
+            .unwrap();
+        }
+
+        let filter = StaleFilter {
+            days: 30,
+            updated_before: None,
+            status: None,
+            limit: None,
+        };
+        let stale = store.get_stale_issues_impl(&filter).unwrap();
+        let ids: Vec<&str> = stale.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["bd-old1"]);
+
+        // An explicit cutoff is not rounded to days.
+        let filter = StaleFilter {
+            updated_before: Some(Utc::now() + Duration::seconds(1)),
+            ..filter
+        };
+        let stale = store.get_stale_issues_impl(&filter).unwrap();
+        assert_eq!(stale.len(), 2);
+    }
 }
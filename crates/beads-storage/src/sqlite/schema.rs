@@ -4,8 +4,16 @@
 //! Timestamps are stored as TEXT in ISO 8601 format (SQLite has no native
 //! datetime type). Booleans are stored as INTEGER (0/1). JSON blobs are TEXT.
 
-/// Current schema version. Bumped whenever DDL or migrations change.
-pub const CURRENT_SCHEMA_VERSION: i32 = 1;
+use crate::sqlite::migrations::{self, Migration};
+
+/// Schema version produced by [`SCHEMA_STATEMENTS`] alone.
+///
+/// The base DDL is frozen at this version: schema changes go into
+/// [`MIGRATIONS`] instead, so existing databases can be upgraded in place.
+pub const BASE_SCHEMA_VERSION: i32 = 1;
+
+/// Current schema version: the version of the newest migration.
+pub const CURRENT_SCHEMA_VERSION: i32 = migrations::latest_version(MIGRATIONS);
 
 /// Core DDL statements executed during `init_schema`.
 pub const SCHEMA_STATEMENTS: &[&str] = &[
@@ -184,11 +192,164 @@ pub const DEFAULT_CONFIG: &[(&str, &str)] = &[
     ),
 ];
 
-/// Schema migrations applied after initial DDL.
+/// Schema migrations applied after the base DDL, in version order.
 ///
-/// Each migration is a `(name, sql)` pair. Migrations are tracked in the
-/// `metadata` table under the key `migration:<name>` so they run at most once.
-pub const MIGRATIONS: &[(&str, &str)] = &[
-    // Future migrations go here, e.g.:
-    // ("001_add_foo_column", "ALTER TABLE issues ADD COLUMN foo TEXT DEFAULT ''"),
+/// Each entry upgrades the schema to its `version`, which must be one more
+/// than the previous entry's (the first is `BASE_SCHEMA_VERSION + 1`).
+/// Applied migrations are recorded with a checksum in `schema_migrations`;
+/// never edit one that has shipped -- add a new migration instead.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration::sql(2, "issues_fts", ISSUES_FTS),
+    Migration::sql(3, "issues_fts_keys", ISSUES_FTS_KEYS),
 ];
+
+/// FTS5 index over issue text and comments, kept in sync by triggers.
+///
+/// Rows are keyed by the unindexed `issue_id` column rather than the rowid,
+/// which `VACUUM` may renumber for `issues`; [`ISSUES_FTS_KEYS`] replaces the
+/// triggers with ones keyed by a stable rowid. Column order matters for
+/// [`fts::BM25_WEIGHTS`](crate::sqlite::fts::BM25_WEIGHTS).
+const ISSUES_FTS: &str = r#"
+CREATE VIRTUAL TABLE issues_fts USING fts5(
+    issue_id UNINDEXED,
+    id,
+    title,
+    description,
+    design,
+    acceptance_criteria,
+    notes,
+    comments,
+    tokenize = 'porter unicode61 remove_diacritics 2',
+    prefix = '2 3'
+);
+
+INSERT INTO issues_fts
+    (issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+SELECT id, id, title, description, design, acceptance_criteria, notes,
+       (SELECT group_concat(text, ' ') FROM comments WHERE comments.issue_id = issues.id)
+FROM issues;
+
+CREATE TRIGGER issues_fts_insert AFTER INSERT ON issues BEGIN
+    INSERT INTO issues_fts
+        (issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+    VALUES (new.id, new.id, new.title, new.description, new.design,
+            new.acceptance_criteria, new.notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.id));
+END;
+
+CREATE TRIGGER issues_fts_update
+AFTER UPDATE OF id, title, description, design, acceptance_criteria, notes ON issues BEGIN
+    DELETE FROM issues_fts WHERE issue_id = old.id;
+    INSERT INTO issues_fts
+        (issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+    VALUES (new.id, new.id, new.title, new.description, new.design,
+            new.acceptance_criteria, new.notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.id));
+END;
+
+CREATE TRIGGER issues_fts_delete AFTER DELETE ON issues BEGIN
+    DELETE FROM issues_fts WHERE issue_id = old.id;
+END;
+
+CREATE TRIGGER comments_fts_insert AFTER INSERT ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.issue_id)
+    WHERE issue_id = new.issue_id;
+END;
+
+CREATE TRIGGER comments_fts_update AFTER UPDATE OF text, issue_id ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = old.issue_id)
+    WHERE issue_id = old.issue_id;
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.issue_id)
+    WHERE issue_id = new.issue_id;
+END;
+
+CREATE TRIGGER comments_fts_delete AFTER DELETE ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = old.issue_id)
+    WHERE issue_id = old.issue_id;
+END;
+"#;
+
+/// Keys the `issues_fts` rows by rowid.
+///
+/// The triggers of [`ISSUES_FTS`] find an issue's row by its unindexed
+/// `issue_id` column, which scans the whole index on every write. Each issue
+/// now gets a stable FTS rowid in `issues_fts_keys` (an `INTEGER PRIMARY
+/// KEY`, so `VACUUM` keeps it) and the triggers look rows up by that rowid.
+/// The index is rebuilt so every row carries its key.
+const ISSUES_FTS_KEYS: &str = r#"
+CREATE TABLE issues_fts_keys (
+    fts_rowid INTEGER PRIMARY KEY,
+    issue_id  TEXT NOT NULL UNIQUE
+);
+
+INSERT INTO issues_fts_keys (issue_id) SELECT id FROM issues;
+
+DELETE FROM issues_fts;
+INSERT INTO issues_fts
+    (rowid, issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+SELECT k.fts_rowid, i.id, i.id, i.title, i.description, i.design, i.acceptance_criteria,
+       i.notes,
+       (SELECT group_concat(text, ' ') FROM comments WHERE comments.issue_id = i.id)
+FROM issues i JOIN issues_fts_keys k ON k.issue_id = i.id;
+
+DROP TRIGGER issues_fts_insert;
+DROP TRIGGER issues_fts_update;
+DROP TRIGGER issues_fts_delete;
+DROP TRIGGER comments_fts_insert;
+DROP TRIGGER comments_fts_update;
+DROP TRIGGER comments_fts_delete;
+
+CREATE TRIGGER issues_fts_insert AFTER INSERT ON issues BEGIN
+    INSERT INTO issues_fts_keys (issue_id) VALUES (new.id);
+    INSERT INTO issues_fts
+        (rowid, issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+    VALUES ((SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = new.id),
+            new.id, new.id, new.title, new.description, new.design,
+            new.acceptance_criteria, new.notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.id));
+END;
+
+CREATE TRIGGER issues_fts_update
+AFTER UPDATE OF id, title, description, design, acceptance_criteria, notes ON issues BEGIN
+    DELETE FROM issues_fts
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = old.id);
+    UPDATE issues_fts_keys SET issue_id = new.id WHERE issue_id = old.id;
+    INSERT INTO issues_fts
+        (rowid, issue_id, id, title, description, design, acceptance_criteria, notes, comments)
+    VALUES ((SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = new.id),
+            new.id, new.id, new.title, new.description, new.design,
+            new.acceptance_criteria, new.notes,
+            (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.id));
+END;
+
+CREATE TRIGGER issues_fts_delete AFTER DELETE ON issues BEGIN
+    DELETE FROM issues_fts
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = old.id);
+    DELETE FROM issues_fts_keys WHERE issue_id = old.id;
+END;
+
+CREATE TRIGGER comments_fts_insert AFTER INSERT ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.issue_id)
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = new.issue_id);
+END;
+
+CREATE TRIGGER comments_fts_update AFTER UPDATE OF text, issue_id ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = old.issue_id)
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = old.issue_id);
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = new.issue_id)
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = new.issue_id);
+END;
+
+CREATE TRIGGER comments_fts_delete AFTER DELETE ON comments BEGIN
+    UPDATE issues_fts
+    SET comments = (SELECT group_concat(text, ' ') FROM comments WHERE issue_id = old.issue_id)
+    WHERE rowid = (SELECT fts_rowid FROM issues_fts_keys WHERE issue_id = old.issue_id);
+END;
+"#;
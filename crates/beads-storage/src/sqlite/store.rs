@@ -1,12 +1,16 @@
 //! [`SqliteStore`] -- SQLite-backed storage implementation.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
 
+use chrono::Utc;
+use regex::Regex;
 use rusqlite::Connection;
+use rusqlite::functions::FunctionFlags;
 use tracing::{debug, info};
 
 use crate::error::{Result, StorageError};
+use crate::sqlite::migrations::{self, MigrationReport, MigrationStatus};
 use crate::sqlite::schema;
 
 /// SQLite-backed implementation of the [`Storage`](crate::traits::Storage) trait.
@@ -16,14 +20,29 @@ use crate::sqlite::schema;
 pub struct SqliteStore {
     /// The mutex-protected SQLite connection.
     pub(crate) conn: Mutex<Connection>,
+    /// Database file, used to place pre-migration backups. `None` in memory.
+    path: Option<PathBuf>,
 }
 
 impl SqliteStore {
     /// Opens (or creates) a SQLite database at the given path.
     ///
-    /// Enables WAL mode and foreign keys, then initialises the schema.
+    /// Enables WAL mode and foreign keys, initialises the schema and applies
+    /// pending migrations, backing up an existing database first. Fails with
+    /// [`StorageError::SchemaTooNew`] if the database was written by a newer
+    /// binary.
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        let path = path.as_ref();
+        Self::open_path(path.as_ref(), true)
+    }
+
+    /// Like [`SqliteStore::open`], but leaves pending migrations of an
+    /// existing database unapplied (a new database still gets the full
+    /// schema). Used by `bd migrate` to inspect and migrate explicitly.
+    pub fn open_without_migrations(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open_path(path.as_ref(), false)
+    }
+
+    fn open_path(path: &Path, apply_migrations: bool) -> Result<Self> {
         info!(?path, "opening SQLite database");
 
         let conn = Connection::open(path).map_err(|e| {
@@ -32,9 +51,10 @@ impl SqliteStore {
 
         let store = Self {
             conn: Mutex::new(conn),
+            path: Some(path.to_path_buf()),
         };
         store.configure_connection()?;
-        store.init_schema()?;
+        store.init_schema(apply_migrations)?;
 
         Ok(store)
     }
@@ -47,14 +67,31 @@ impl SqliteStore {
 
         let store = Self {
             conn: Mutex::new(conn),
+            path: None,
         };
         store.configure_connection()?;
-        store.init_schema()?;
+        store.init_schema(true)?;
 
         Ok(store)
     }
 
-    /// Sets connection pragmas (WAL mode, foreign keys, busy timeout).
+    /// Reports the schema version and applied/pending migrations.
+    pub fn migration_status(&self) -> Result<MigrationStatus> {
+        let conn = self.lock_conn()?;
+        migrations::status(&conn, schema::MIGRATIONS)
+    }
+
+    /// Applies pending migrations up to and including `target`, backing up
+    /// the database file first.
+    pub fn migrate_to(&self, target: i32) -> Result<MigrationReport> {
+        let mut conn = self.lock_conn()?;
+        let version = migrations::schema_version(&conn)?.unwrap_or(schema::BASE_SCHEMA_VERSION);
+        let backup = self.backup_path(version);
+        migrations::migrate(&mut conn, schema::MIGRATIONS, target, backup.as_deref())
+    }
+
+    /// Sets connection pragmas (WAL mode, foreign keys, busy timeout) and
+    /// registers the SQL functions bd queries use.
     fn configure_connection(&self) -> Result<()> {
         let conn = self
             .conn
@@ -67,28 +104,21 @@ impl SqliteStore {
              PRAGMA busy_timeout = 5000;",
         )
         .map_err(|e| StorageError::Connection(format!("failed to set pragmas: {e}")))?;
+        register_regexp(&conn)
+            .map_err(|e| StorageError::Connection(format!("failed to register REGEXP: {e}")))?;
 
         Ok(())
     }
 
-    /// Creates all tables and indexes if they do not exist, then runs
-    /// migrations.
-    fn init_schema(&self) -> Result<()> {
-        let conn = self
-            .conn
-            .lock()
-            .map_err(|e| StorageError::Connection(format!("mutex poisoned: {e}")))?;
+    /// Creates all tables and indexes for a new database, then brings the
+    /// schema up to date if `apply_migrations` is set (always for a new
+    /// database).
+    fn init_schema(&self, apply_migrations: bool) -> Result<()> {
+        let mut conn = self.lock_conn()?;
 
-        // Check if schema is already at current version.
-        let version: std::result::Result<i32, _> = conn.query_row(
-            "SELECT value FROM config WHERE key = 'schema_version'",
-            [],
-            |row| {
-                let v: String = row.get(0)?;
-                Ok(v.parse::<i32>().unwrap_or(0))
-            },
-        );
-        if let Ok(v) = version {
+        let version = migrations::schema_version(&conn)?;
+        if let Some(v) = version {
+            migrations::check_supported(v, schema::MIGRATIONS)?;
             if v >= schema::CURRENT_SCHEMA_VERSION {
                 debug!(
                     version = v,
@@ -96,41 +126,42 @@ impl SqliteStore {
                 );
                 return Ok(());
             }
-        }
+        } else {
+            // Execute DDL statements.
+            for stmt in schema::SCHEMA_STATEMENTS {
+                conn.execute_batch(stmt)
+                    .map_err(|e| StorageError::Migration {
+                        name: "init_schema".into(),
+                        reason: format!("{e}\nStatement: {}", truncate(stmt, 120)),
+                    })?;
+            }
 
-        // Execute DDL statements.
-        for stmt in schema::SCHEMA_STATEMENTS {
-            conn.execute_batch(stmt)
+            // Insert default config (INSERT OR IGNORE to be idempotent).
+            for &(key, value) in schema::DEFAULT_CONFIG {
+                conn.execute(
+                    "INSERT OR IGNORE INTO config (key, value) VALUES (?1, ?2)",
+                    rusqlite::params![key, value],
+                )
                 .map_err(|e| StorageError::Migration {
-                    name: "init_schema".into(),
-                    reason: format!("{e}\nStatement: {}", truncate(stmt, 120)),
+                    name: "default_config".into(),
+                    reason: format!("failed to insert {key}: {e}"),
                 })?;
-        }
+            }
 
-        // Insert default config (INSERT OR IGNORE to be idempotent).
-        for &(key, value) in schema::DEFAULT_CONFIG {
-            conn.execute(
-                "INSERT OR IGNORE INTO config (key, value) VALUES (?1, ?2)",
-                rusqlite::params![key, value],
-            )
-            .map_err(|e| StorageError::Migration {
-                name: "default_config".into(),
-                reason: format!("failed to insert {key}: {e}"),
-            })?;
+            migrations::set_schema_version(&conn, schema::BASE_SCHEMA_VERSION)?;
+            migrations::ensure_history_table(&conn)?;
         }
 
-        // Run migrations.
-        Self::run_migrations_on_conn(&conn)?;
-
-        // Mark schema version.
-        conn.execute(
-            "INSERT OR REPLACE INTO config (key, value) VALUES ('schema_version', ?1)",
-            rusqlite::params![schema::CURRENT_SCHEMA_VERSION.to_string()],
-        )
-        .map_err(|e| StorageError::Migration {
-            name: "schema_version".into(),
-            reason: e.to_string(),
-        })?;
+        if version.is_none() || apply_migrations {
+            // A database created just now has nothing worth backing up.
+            let backup = version.and_then(|v| self.backup_path(v));
+            migrations::migrate(
+                &mut conn,
+                schema::MIGRATIONS,
+                schema::CURRENT_SCHEMA_VERSION,
+                backup.as_deref(),
+            )?;
+        }
 
         info!(
             "schema initialized (version {})",
@@ -139,41 +170,14 @@ impl SqliteStore {
         Ok(())
     }
 
-    /// Applies pending migrations tracked via the `metadata` table.
-    fn run_migrations_on_conn(conn: &Connection) -> Result<()> {
-        for &(name, sql) in schema::MIGRATIONS {
-            let key = format!("migration:{name}");
-            let already_applied: bool = conn
-                .query_row(
-                    "SELECT COUNT(*) FROM metadata WHERE key = ?1",
-                    rusqlite::params![key],
-                    |row| row.get::<_, i32>(0),
-                )
-                .unwrap_or(0)
-                > 0;
-
-            if already_applied {
-                debug!(name, "migration already applied, skipping");
-                continue;
-            }
-
-            debug!(name, "applying migration");
-            conn.execute_batch(sql)
-                .map_err(|e| StorageError::Migration {
-                    name: name.to_string(),
-                    reason: e.to_string(),
-                })?;
-
-            conn.execute(
-                "INSERT INTO metadata (key, value) VALUES (?1, ?2)",
-                rusqlite::params![key, "applied"],
-            )
-            .map_err(|e| StorageError::Migration {
-                name: name.to_string(),
-                reason: format!("failed to mark migration: {e}"),
-            })?;
-        }
-        Ok(())
+    /// Path for a pre-migration backup next to the database file, e.g.
+    /// `beads.db.v1-20260301T120000Z.bak`. `None` for in-memory stores.
+    fn backup_path(&self, version: i32) -> Option<PathBuf> {
+        let path = self.path.as_ref()?;
+        let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+        let mut name = path.file_name()?.to_os_string();
+        name.push(format!(".v{version}-{stamp}.bak"));
+        Some(path.with_file_name(name))
     }
 
     /// Acquires the connection lock. Helper used by all operation modules.
@@ -199,6 +203,29 @@ fn truncate(s: &str, max: usize) -> String {
     }
 }
 
+/// Registers `REGEXP`, so `x REGEXP 'pattern'` matches with the `regex`
+/// crate. Each statement compiles its pattern once.
+fn register_regexp(conn: &Connection) -> rusqlite::Result<()> {
+    conn.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            let regex: std::sync::Arc<Regex> = ctx.get_or_create_aux(0, |pattern| {
+                Regex::new(pattern.as_str()?)
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+            })?;
+            let text = match ctx.get_raw(1) {
+                rusqlite::types::ValueRef::Null => return Ok(false),
+                value => value
+                    .as_str()
+                    .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?,
+            };
+            Ok(regex.is_match(text))
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -232,6 +259,55 @@ mod tests {
     fn idempotent_init() {
         let store = SqliteStore::open_in_memory().unwrap();
         // Re-init should succeed without error.
-        store.init_schema().unwrap();
+        store.init_schema(true).unwrap();
+    }
+
+    #[test]
+    fn open_rejects_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("beads.db");
+        {
+            let store = SqliteStore::open(&path).unwrap();
+            let conn = store.lock_conn().unwrap();
+            migrations::set_schema_version(&conn, schema::CURRENT_SCHEMA_VERSION + 1).unwrap();
+        }
+
+        let err = SqliteStore::open(&path).unwrap_err();
+        assert!(matches!(err, StorageError::SchemaTooNew { .. }));
+        assert!(err.to_string().contains("newer than this bd supports"));
+        assert!(SqliteStore::open_without_migrations(&path).is_err());
+    }
+
+    #[test]
+    fn reopen_reports_no_pending_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("beads.db");
+        drop(SqliteStore::open(&path).unwrap());
+
+        let store = SqliteStore::open_without_migrations(&path).unwrap();
+        let status = store.migration_status().unwrap();
+        assert_eq!(status.current_version, schema::CURRENT_SCHEMA_VERSION);
+        assert!(status.pending.is_empty());
+
+        let report = store.migrate_to(schema::CURRENT_SCHEMA_VERSION).unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.backup.is_none());
+    }
+
+    #[test]
+    fn backup_path_sits_next_to_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SqliteStore::open(dir.path().join("beads.db")).unwrap();
+        let backup = store.backup_path(1).unwrap();
+        assert_eq!(backup.parent(), Some(dir.path()));
+        let name = backup.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("beads.db.v1-") && name.ends_with(".bak"));
+
+        assert!(
+            SqliteStore::open_in_memory()
+                .unwrap()
+                .backup_path(1)
+                .is_none()
+        );
     }
 }
@@ -38,6 +38,10 @@ impl Transaction for SqliteTx<'_> {
         Ok(())
     }
 
+    fn import_issue(&self, issue: &Issue, actor: &str) -> Result<()> {
+        issues::import_issue_on_conn(self.conn, issue, actor)
+    }
+
     fn update_issue(&self, id: &str, updates: &IssueUpdates, actor: &str) -> Result<()> {
         issues::update_issue_on_conn(self.conn, id, updates, actor)
     }
@@ -3,13 +3,17 @@
 //! Consumers depend on these traits rather than on concrete implementations so
 //! that alternative backends (mocks, proxies, etc.) can be substituted.
 
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 
 use beads_core::comment::{Comment, Event};
 use beads_core::dependency::Dependency;
+use beads_core::entity::BondRef;
 use beads_core::enums::{DependencyType, IssueType, Status};
-use beads_core::filter::{IssueFilter, WorkFilter};
+use beads_core::filter::{IssueFilter, StaleFilter, WorkFilter};
 use beads_core::issue::Issue;
+use beads_query::Query;
 
 use crate::error::Result;
 
@@ -55,6 +59,7 @@ pub struct IssueUpdates {
     pub await_id: Option<String>,
     pub timeout: Option<Option<std::time::Duration>>,
     pub waiters: Option<Vec<String>>,
+    pub bonded_from: Option<Vec<BondRef>>,
     pub hook_bead: Option<String>,
     pub role_bead: Option<String>,
     pub agent_state: Option<String>,
@@ -98,6 +103,18 @@ pub struct BlockedIssue {
     pub blocked_by_count: i32,
 }
 
+/// An open issue whose conditional-blocks blocker closed without failing,
+/// so it will never run.
+#[derive(Debug, Clone)]
+pub struct SkippedIssue {
+    /// The skipped issue.
+    pub issue: Issue,
+    /// The blocker whose success skipped it.
+    pub blocker_id: String,
+    /// The blocker's close reason.
+    pub blocker_close_reason: String,
+}
+
 /// Status of an epic with respect to its children.
 #[derive(Debug, Clone)]
 pub struct EpicStatus {
@@ -127,6 +144,36 @@ pub struct Statistics {
     pub by_assignee: Vec<(String, i64)>,
 }
 
+/// An issue matched by a full-text search.
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    /// The matching issue.
+    pub issue: Issue,
+    /// BM25 relevance; higher is more relevant. Zero for an empty query.
+    pub score: f64,
+    /// Excerpt of the best-matching field with matches wrapped in
+    /// [`SNIPPET_OPEN`](crate::sqlite::fts::SNIPPET_OPEN)/[`SNIPPET_CLOSE`](crate::sqlite::fts::SNIPPET_CLOSE).
+    /// Empty for an empty query.
+    pub snippet: String,
+}
+
+/// Result of a structural health check of the database.
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    /// Backend integrity check result; `"ok"` when healthy.
+    pub integrity: String,
+    /// Names of the tables present in the database.
+    pub tables: Vec<String>,
+    /// Row counts per table: `(table, count)`.
+    pub record_counts: Vec<(String, i64)>,
+    /// Dependency rows whose source or target issue does not exist.
+    pub orphaned_dependencies: i64,
+    /// Label rows attached to a missing issue.
+    pub orphaned_labels: i64,
+    /// Comment rows attached to a missing issue.
+    pub orphaned_comments: i64,
+}
+
 // ---------------------------------------------------------------------------
 // Storage trait
 // ---------------------------------------------------------------------------
@@ -153,9 +200,20 @@ pub trait Storage: Send + Sync {
     /// Retrieves multiple issues by their IDs.
     fn get_issues_by_ids(&self, ids: &[String]) -> Result<Vec<Issue>>;
 
-    /// Applies partial updates to an issue and emits an "updated" event.
+    /// Applies partial updates to an issue.
+    ///
+    /// Status changes maintain `closed_at`/`close_reason` and are recorded
+    /// as "closed", "reopened" or "status_changed" events; changes to other
+    /// fields are recorded as an "updated" event listing the columns.
     fn update_issue(&self, id: &str, updates: &IssueUpdates, actor: &str) -> Result<()>;
 
+    /// Sets an agent's `last_activity` to `at`. Unlike [`update_issue`],
+    /// this leaves `updated_at` alone and records no event, so agents can
+    /// heartbeat often.
+    ///
+    /// [`update_issue`]: Storage::update_issue
+    fn record_heartbeat(&self, id: &str, at: DateTime<Utc>) -> Result<()>;
+
     /// Closes an issue (sets status=closed, closed_at=now) and emits a
     /// "closed" event.
     fn close_issue(&self, id: &str, reason: &str, actor: &str, session: &str) -> Result<()>;
@@ -164,8 +222,20 @@ pub trait Storage: Send + Sync {
     fn delete_issue(&self, id: &str) -> Result<()>;
 
     /// Searches issues by text query and optional filter.
+    ///
+    /// The query uses the full-text syntax described in
+    /// [`fts`](crate::sqlite::fts): words, `prefix*`, `"phrases"`,
+    /// `field:term` and `AND`/`OR`/`NOT`.
     fn search_issues(&self, query: &str, filter: &IssueFilter) -> Result<Vec<Issue>>;
 
+    /// Like [`Storage::search_issues`], but returns matches most relevant
+    /// first, with their score and a highlighted snippet.
+    fn search_issues_ranked(&self, query: &str, filter: &IssueFilter) -> Result<Vec<SearchHit>>;
+
+    /// Returns the issues matching both the filter and a
+    /// [`beads_query`] expression, newest first.
+    fn query_issues(&self, query: &Query, filter: &IssueFilter) -> Result<Vec<Issue>>;
+
     // -- Dependencies --------------------------------------------------------
 
     /// Adds a dependency edge between two issues.
@@ -174,6 +244,21 @@ pub trait Storage: Send + Sync {
     /// Removes a dependency edge.
     fn remove_dependency(&self, issue_id: &str, depends_on_id: &str, actor: &str) -> Result<()>;
 
+    /// Removes only the edge of the given type between two issues.
+    fn remove_dependency_of_type(
+        &self,
+        issue_id: &str,
+        depends_on_id: &str,
+        dep_type: &DependencyType,
+        actor: &str,
+    ) -> Result<()>;
+
+    /// Returns the raw outgoing dependency edges of an issue.
+    fn get_dependency_records(&self, issue_id: &str) -> Result<Vec<Dependency>>;
+
+    /// Returns every dependency edge, keyed by the dependent issue's ID.
+    fn get_all_dependency_records(&self) -> Result<HashMap<String, Vec<Dependency>>>;
+
     /// Returns the issues that the given issue depends on.
     fn get_dependencies(&self, issue_id: &str) -> Result<Vec<Issue>>;
 
@@ -212,6 +297,10 @@ pub trait Storage: Send + Sync {
     /// Returns all labels for an issue.
     fn get_labels(&self, issue_id: &str) -> Result<Vec<String>>;
 
+    /// Returns the labels of several issues at once. Issues without labels
+    /// are absent from the map.
+    fn get_labels_for_issues(&self, issue_ids: &[String]) -> Result<HashMap<String, Vec<String>>>;
+
     /// Returns all issues with the given label.
     fn get_issues_by_label(&self, label: &str) -> Result<Vec<Issue>>;
 
@@ -224,9 +313,17 @@ pub trait Storage: Send + Sync {
     /// Returns issues that have at least one open blocking dependency.
     fn get_blocked_issues(&self, filter: &WorkFilter) -> Result<Vec<BlockedIssue>>;
 
+    /// Returns open issues skipped by a conditional-blocks blocker that
+    /// closed without failing, one entry per issue.
+    fn get_skipped_issues(&self, filter: &WorkFilter) -> Result<Vec<SkippedIssue>>;
+
     /// Returns epics where all children are closed.
     fn get_epics_eligible_for_closure(&self) -> Result<Vec<EpicStatus>>;
 
+    /// Returns non-closed issues not updated within `filter.days`, least
+    /// recently updated first. Templates and gates are excluded.
+    fn get_stale_issues(&self, filter: &StaleFilter) -> Result<Vec<Issue>>;
+
     // -- Comments and events -------------------------------------------------
 
     /// Adds a comment to an issue and returns the created comment.
@@ -246,6 +343,9 @@ pub trait Storage: Send + Sync {
     /// Returns aggregate statistics about the issue database.
     fn get_statistics(&self) -> Result<Statistics>;
 
+    /// Runs integrity and consistency checks over the stored data.
+    fn check_health(&self) -> Result<HealthReport>;
+
     // -- Configuration -------------------------------------------------------
 
     /// Sets a configuration key-value pair.
@@ -255,7 +355,24 @@ pub trait Storage: Send + Sync {
     fn get_config(&self, key: &str) -> Result<String>;
 
     /// Returns all configuration key-value pairs.
-    fn get_all_config(&self) -> Result<std::collections::HashMap<String, String>>;
+    fn get_all_config(&self) -> Result<HashMap<String, String>>;
+
+    /// Deletes a configuration key.
+    fn delete_config(&self, key: &str) -> Result<()>;
+
+    // -- Metadata ------------------------------------------------------------
+
+    /// Sets an internal metadata key-value pair.
+    fn set_metadata(&self, key: &str, value: &str) -> Result<()>;
+
+    /// Gets a metadata value by key.
+    fn get_metadata(&self, key: &str) -> Result<String>;
+
+    /// Deletes a metadata key.
+    fn delete_metadata(&self, key: &str) -> Result<()>;
+
+    /// Returns all metadata key-value pairs.
+    fn get_all_metadata(&self) -> Result<HashMap<String, String>>;
 
     // -- Transactions --------------------------------------------------------
 
@@ -284,6 +401,9 @@ pub trait Transaction {
 
     fn create_issue(&self, issue: &Issue, actor: &str) -> Result<()>;
     fn create_issues(&self, issues: &[Issue], actor: &str) -> Result<()>;
+    /// Creates or overwrites an issue, preserving its timestamps (for
+    /// JSONL import). Labels, dependencies and comments are not touched.
+    fn import_issue(&self, issue: &Issue, actor: &str) -> Result<()>;
     fn update_issue(&self, id: &str, updates: &IssueUpdates, actor: &str) -> Result<()>;
     fn close_issue(&self, id: &str, reason: &str, actor: &str, session: &str) -> Result<()>;
     fn delete_issue(&self, id: &str) -> Result<()>;
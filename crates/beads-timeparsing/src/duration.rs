@@ -0,0 +1,272 @@
+//! Durations: `30m`, `1h30m`, `2w`, `1 hour 30 minutes`.
+
+use chrono::Duration;
+
+use crate::error::ParseError;
+
+/// Duration units, smallest first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+}
+
+/// Unit names and abbreviations accepted after a number.
+const UNIT_NAMES: &[(&str, Unit)] = &[
+    ("s", Unit::Second),
+    ("sec", Unit::Second),
+    ("secs", Unit::Second),
+    ("second", Unit::Second),
+    ("seconds", Unit::Second),
+    ("m", Unit::Minute),
+    ("min", Unit::Minute),
+    ("mins", Unit::Minute),
+    ("minute", Unit::Minute),
+    ("minutes", Unit::Minute),
+    ("h", Unit::Hour),
+    ("hr", Unit::Hour),
+    ("hrs", Unit::Hour),
+    ("hour", Unit::Hour),
+    ("hours", Unit::Hour),
+    ("d", Unit::Day),
+    ("day", Unit::Day),
+    ("days", Unit::Day),
+    ("w", Unit::Week),
+    ("wk", Unit::Week),
+    ("wks", Unit::Week),
+    ("week", Unit::Week),
+    ("weeks", Unit::Week),
+];
+
+impl Unit {
+    /// Length of one unit in seconds.
+    pub fn seconds(self) -> i64 {
+        match self {
+            Unit::Second => 1,
+            Unit::Minute => 60,
+            Unit::Hour => 60 * 60,
+            Unit::Day => 24 * 60 * 60,
+            Unit::Week => 7 * 24 * 60 * 60,
+        }
+    }
+
+    /// Short suffix used by [`format_duration`].
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Unit::Second => "s",
+            Unit::Minute => "m",
+            Unit::Hour => "h",
+            Unit::Day => "d",
+            Unit::Week => "w",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Unit> {
+        UNIT_NAMES.iter().find(|(n, _)| *n == name).map(|(_, u)| *u)
+    }
+}
+
+/// Parses a duration made of `<number><unit>` parts, e.g. `30m`, `1h30m`,
+/// `2w` or `1 hour 30 minutes`. Units are `s`, `m`, `h`, `d` and `w` (or
+/// their spelled-out names); every number needs one.
+pub fn parse_duration(input: &str) -> Result<Duration, ParseError> {
+    parse(input, None)
+}
+
+/// Like [`parse_duration`], but a lone number is read in `unit`
+/// (`30` with [`Unit::Day`] is thirty days).
+pub fn parse_duration_in(input: &str, unit: Unit) -> Result<Duration, ParseError> {
+    parse(input, Some(unit))
+}
+
+fn parse(input: &str, bare_unit: Option<Unit>) -> Result<Duration, ParseError> {
+    let text = input.trim().to_lowercase();
+    if text.is_empty() {
+        return Err(ParseError::duration(input, "empty duration"));
+    }
+
+    if let Some(unit) = bare_unit {
+        if let Ok(n) = text.parse::<i64>() {
+            return to_duration(input, n, unit);
+        }
+    }
+
+    let mut total = Duration::zero();
+    let mut rest = text.as_str();
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() {
+            break;
+        }
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if digits == 0 {
+            return Err(ParseError::duration(
+                input,
+                format!("expected a number at '{rest}'"),
+            ));
+        }
+        let n: i64 = rest[..digits]
+            .parse()
+            .map_err(|_| ParseError::duration(input, "number too large"))?;
+        rest = rest[digits..].trim_start();
+
+        let letters = rest.len()
+            - rest
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .len();
+        let name = &rest[..letters];
+        if name.is_empty() {
+            return Err(ParseError::duration(
+                input,
+                format!("missing unit after {n} (valid: s, m, h, d, w)"),
+            ));
+        }
+        let unit = Unit::from_name(name).ok_or_else(|| {
+            ParseError::duration(
+                input,
+                format!("unknown unit '{name}' (valid: s, m, h, d, w)"),
+            )
+        })?;
+        rest = &rest[letters..];
+
+        total = total
+            .checked_add(&to_duration(input, n, unit)?)
+            .ok_or_else(|| ParseError::duration(input, "duration too large"))?;
+    }
+    Ok(total)
+}
+
+fn to_duration(input: &str, n: i64, unit: Unit) -> Result<Duration, ParseError> {
+    n.checked_mul(unit.seconds())
+        .and_then(Duration::try_seconds)
+        .ok_or_else(|| ParseError::duration(input, "duration too large"))
+}
+
+/// Formats a duration compactly (`1w3d`, `1h30m`, `0s`), whole seconds only.
+///
+/// The output parses back to the same duration with [`parse_duration`];
+/// negative durations get a leading `-`.
+pub fn format_duration(d: Duration) -> String {
+    let secs = d.num_seconds();
+    if secs == 0 {
+        return "0s".to_string();
+    }
+    let mut out = String::new();
+    if secs < 0 {
+        out.push('-');
+    }
+    let mut rest = secs.unsigned_abs();
+    for unit in [
+        Unit::Week,
+        Unit::Day,
+        Unit::Hour,
+        Unit::Minute,
+        Unit::Second,
+    ] {
+        let size = unit.seconds() as u64;
+        if rest >= size {
+            out.push_str(&format!("{}{}", rest / size, unit.suffix()));
+            rest %= size;
+        }
+    }
+    out
+}
+
+/// Like [`format_duration`], but keeps only the `parts` most significant
+/// units, rounding the rest down (`3d4h12m5s` with 2 parts is `3d4h`).
+pub fn format_duration_approx(d: Duration, parts: usize) -> String {
+    let secs = d.num_seconds();
+    let mut rest = secs.unsigned_abs();
+    let mut kept = 0;
+    let mut rounded: u64 = 0;
+    for unit in [
+        Unit::Week,
+        Unit::Day,
+        Unit::Hour,
+        Unit::Minute,
+        Unit::Second,
+    ] {
+        let size = unit.seconds() as u64;
+        if kept < parts && rest >= size {
+            rounded += rest / size * size;
+            rest %= size;
+            kept += 1;
+        } else if kept > 0 {
+            kept = parts;
+        }
+    }
+    let rounded = rounded as i64;
+    format_duration(Duration::seconds(if secs < 0 { -rounded } else { rounded }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn parses_compact_and_spelled_out_forms() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::minutes(90));
+        assert_eq!(parse_duration("2w").unwrap(), Duration::days(14));
+        assert_eq!(
+            parse_duration("1 hour, 30 minutes").unwrap(),
+            Duration::minutes(90)
+        );
+        assert_eq!(parse_duration(" 1D 2H ").unwrap(), Duration::hours(26));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        let err = parse_duration("30").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid duration '30': missing unit after 30 (valid: s, m, h, d, w)"
+        );
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("3mo").is_err());
+        assert!(parse_duration("99999999999999999999s").is_err());
+    }
+
+    #[test]
+    fn bare_numbers_use_the_default_unit() {
+        assert_eq!(
+            parse_duration_in("30", Unit::Day).unwrap(),
+            Duration::days(30)
+        );
+        assert_eq!(
+            parse_duration_in("2w", Unit::Day).unwrap(),
+            Duration::days(14)
+        );
+    }
+
+    #[test]
+    fn format_round_trips() {
+        for d in [
+            Duration::zero(),
+            Duration::seconds(59),
+            Duration::minutes(90),
+            Duration::days(10) + Duration::seconds(5),
+            Duration::weeks(3),
+        ] {
+            let text = format_duration(d);
+            assert_eq!(parse_duration(&text).unwrap(), d, "{text}");
+        }
+        assert_eq!(format_duration(Duration::days(10)), "1w3d");
+        assert_eq!(format_duration(Duration::minutes(-90)), "-1h30m");
+    }
+
+    #[test]
+    fn approx_keeps_leading_units() {
+        let d = Duration::days(3) + Duration::hours(4) + Duration::minutes(12);
+        assert_eq!(format_duration_approx(d, 2), "3d4h");
+        assert_eq!(format_duration_approx(d, 1), "3d");
+        // Skipped units still count towards the precision.
+        let d = Duration::days(1) + Duration::minutes(5);
+        assert_eq!(format_duration_approx(d, 2), "1d");
+    }
+}
@@ -0,0 +1,31 @@
+//! Time parsing errors.
+
+/// An input that could not be parsed as a duration or time.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("invalid {what} '{input}': {reason}")]
+pub struct ParseError {
+    /// What was being parsed ("duration" or "time").
+    pub what: &'static str,
+    /// The input as given.
+    pub input: String,
+    /// Why it was rejected.
+    pub reason: String,
+}
+
+impl ParseError {
+    pub(crate) fn duration(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            what: "duration",
+            input: input.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn time(input: &str, reason: impl Into<String>) -> Self {
+        Self {
+            what: "time",
+            input: input.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
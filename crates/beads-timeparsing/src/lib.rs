@@ -1 +1,16 @@
 //! Time expression parsing for the beads system.
+//!
+//! One parser for every time-valued flag: durations (`30m`, `1h30m`, `2w`),
+//! points in time (`2026-06-01`, `+3d`, `tomorrow 9am`, `next monday`,
+//! `end of month`, with an optional offset or zone such as `Europe/Berlin`),
+//! and formatters whose output parses back.
+
+pub mod duration;
+pub mod error;
+pub mod time;
+
+pub use duration::{
+    Unit, format_duration, format_duration_approx, parse_duration, parse_duration_in,
+};
+pub use error::ParseError;
+pub use time::{format_relative, parse_future_time, parse_time};
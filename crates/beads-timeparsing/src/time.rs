@@ -0,0 +1,529 @@
+//! Points in time: ISO timestamps, relative offsets and natural phrases.
+
+use chrono::{
+    DateTime, Datelike, Days, Duration, FixedOffset, Months, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Utc, Weekday,
+};
+use chrono_tz::{TZ_VARIANTS, Tz as NamedZone};
+
+use crate::duration::{format_duration_approx, parse_duration};
+use crate::error::ParseError;
+
+/// Parses a point in time relative to `now`, in `now`'s timezone unless the
+/// input names one.
+///
+/// Accepted forms (case-insensitive):
+///
+/// - ISO dates and timestamps: `2026-06-01`, `2026-06-01 14:30`,
+///   `2026-06-01T14:30:00Z`, `2026-06-01T14:30:00+02:00`
+/// - `now`, offsets from now: `+3d`, `-2h`, `in 1h30m`, `3 days ago`
+/// - days with an optional time: `today`, `tomorrow 9am`, `yesterday noon`,
+///   `friday`, `next monday at 14:30`, `last tuesday`, `next week`,
+///   `next month`, `2026-06-01 5pm`
+/// - period boundaries: `end of day`, `start of week`, `end of month`,
+///   `beginning of year`
+///
+/// A trailing `UTC`, `Z`, `+HH:MM`, IANA zone name (`Europe/Berlin`) or
+/// common abbreviation (`PST`, `CEST`) sets the timezone. Days without a
+/// time start at midnight; weeks start on Monday; `end of ...` is the last
+/// second of the period.
+pub fn parse_time<Tz: TimeZone>(
+    input: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<Utc>, ParseError> {
+    let text = input.trim();
+    if text.is_empty() {
+        return Err(ParseError::time(input, "empty time"));
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(text) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let lower = text.to_lowercase();
+    let mut words: Vec<&str> = lower.split_whitespace().collect();
+    let now_utc = now.with_timezone(&Utc);
+    let Some(zone) = words.last().and_then(|w| parse_zone(w)) else {
+        return eval(input, &words, now_utc, &now.timezone());
+    };
+    words.pop();
+    if words.is_empty() {
+        return Err(ParseError::time(
+            input,
+            "expected a time before the timezone",
+        ));
+    }
+    match zone {
+        Zone::Fixed(offset) => eval(input, &words, now_utc, &offset),
+        Zone::Named(tz) => eval(input, &words, now_utc, &tz),
+    }
+}
+
+/// Like [`parse_time`], but a bare duration (`2h`, `3d`, `1w2d`) is that
+/// long from now, as for deadlines and deferrals.
+pub fn parse_future_time<Tz: TimeZone>(
+    input: &str,
+    now: &DateTime<Tz>,
+) -> Result<DateTime<Utc>, ParseError> {
+    parse_time(input, now).or_else(|err| match parse_duration(input) {
+        Ok(d) => shift(input, now.with_timezone(&Utc), d),
+        Err(_) => Err(err),
+    })
+}
+
+/// Formats `t` relative to `now` as `in 3d4h`, `2h30m ago` or `now`,
+/// keeping the two most significant units.
+///
+/// The output is accepted by [`parse_time`], which maps it back to `t`
+/// within that precision.
+pub fn format_relative(t: DateTime<Utc>, now: DateTime<Utc>) -> String {
+    let diff = t - now;
+    let text = format_duration_approx(diff.abs(), 2);
+    if text == "0s" {
+        "now".to_string()
+    } else if diff < Duration::zero() {
+        format!("{text} ago")
+    } else {
+        format!("in {text}")
+    }
+}
+
+/// Common timezone abbreviations and their UTC offsets in hours. IANA
+/// names are preferred, as an abbreviation is fixed to one side of a DST
+/// change.
+const ZONE_ABBREVIATIONS: &[(&str, i32)] = &[
+    ("hst", -10),
+    ("akst", -9),
+    ("akdt", -8),
+    ("pst", -8),
+    ("pdt", -7),
+    ("mst", -7),
+    ("mdt", -6),
+    ("cst", -6),
+    ("cdt", -5),
+    ("est", -5),
+    ("edt", -4),
+    ("wet", 0),
+    ("west", 1),
+    ("bst", 1),
+    ("cet", 1),
+    ("cest", 2),
+    ("eet", 2),
+    ("eest", 3),
+    ("jst", 9),
+    ("aest", 10),
+    ("aedt", 11),
+];
+
+/// A timezone named in the input.
+enum Zone {
+    Fixed(FixedOffset),
+    Named(NamedZone),
+}
+
+/// Parses a timezone suffix: `utc`, `gmt`, `z`, `+hh:mm`, `+hhmm`, an
+/// abbreviation from [`ZONE_ABBREVIATIONS`] or an IANA name (any case).
+fn parse_zone(word: &str) -> Option<Zone> {
+    if matches!(word, "utc" | "gmt" | "z") {
+        return FixedOffset::east_opt(0).map(Zone::Fixed);
+    }
+    if let Some((_, hours)) = ZONE_ABBREVIATIONS.iter().find(|(abbr, _)| *abbr == word) {
+        return FixedOffset::east_opt(hours * 3600).map(Zone::Fixed);
+    }
+    if let Some(tz) = TZ_VARIANTS
+        .iter()
+        .find(|tz| tz.name().eq_ignore_ascii_case(word))
+    {
+        return Some(Zone::Named(*tz));
+    }
+    parse_offset(word).map(Zone::Fixed)
+}
+
+/// Parses a UTC offset: `+hh:mm` or `+hhmm`.
+fn parse_offset(word: &str) -> Option<FixedOffset> {
+    let sign = match word.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits: String = word[1..].chars().filter(|c| *c != ':').collect();
+    let colon_ok = !word.contains(':') || word.len() == 6 && &word[3..4] == ":";
+    if digits.len() != 4 || !colon_ok || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+fn eval<Tz: TimeZone>(
+    input: &str,
+    words: &[&str],
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> Result<DateTime<Utc>, ParseError> {
+    let err = |reason: String| ParseError::time(input, reason);
+
+    // Offsets from now.
+    match words {
+        ["now"] => return Ok(now),
+        [w] if w.starts_with('+') || w.starts_with('-') => {
+            let d = parse_duration(&w[1..]).map_err(|e| err(e.reason))?;
+            return shift(input, now, if w.starts_with('-') { -d } else { d });
+        }
+        ["in", rest @ ..] if !rest.is_empty() => {
+            let d = parse_duration(&rest.join(" ")).map_err(|e| err(e.reason))?;
+            return shift(input, now, d);
+        }
+        [rest @ .., "ago"] if !rest.is_empty() => {
+            let d = parse_duration(&rest.join(" ")).map_err(|e| err(e.reason))?;
+            return shift(input, now, -d);
+        }
+        _ => {}
+    }
+
+    let today = now.with_timezone(tz).date_naive();
+
+    // Period boundaries.
+    if let [edge @ ("end" | "start" | "beginning"), "of", period] = words {
+        let (first, last) = period_bounds(today, period).ok_or_else(|| {
+            err(format!(
+                "unknown period '{period}' (day, week, month, year)"
+            ))
+        })?;
+        let at = if *edge == "end" {
+            last.and_hms_opt(23, 59, 59).expect("valid time")
+        } else {
+            first.and_time(NaiveTime::MIN)
+        };
+        return localize(input, tz, at);
+    }
+
+    // A day, optionally followed by a time of day.
+    let (day, rest) = parse_day(today, words).ok_or_else(|| {
+        err(format!(
+            "unrecognized time '{}' (try 2026-06-01, tomorrow 9am, +3d, next monday, end of month)",
+            words.join(" ")
+        ))
+    })?;
+    let at = match rest {
+        DayRest::DateTime(at) => at,
+        DayRest::Words(rest) => {
+            let rest = match rest {
+                ["at", rest @ ..] => rest,
+                rest => rest,
+            };
+            let time = match rest {
+                [] => NaiveTime::MIN,
+                rest => parse_time_of_day(&rest.join(""))
+                    .ok_or_else(|| err(format!("invalid time of day '{}'", rest.join(" "))))?,
+            };
+            day.and_time(time)
+        }
+    };
+    localize(input, tz, at)
+}
+
+/// What follows the day part of a phrase.
+enum DayRest<'a> {
+    /// The day word was a full naive timestamp.
+    DateTime(NaiveDateTime),
+    /// Remaining words (a time of day, if any).
+    Words(&'a [&'a str]),
+}
+
+/// Parses the leading day words of a phrase.
+fn parse_day<'a>(today: NaiveDate, words: &'a [&'a str]) -> Option<(NaiveDate, DayRest<'a>)> {
+    let (first, rest) = words.split_first()?;
+    let day = match *first {
+        "today" => today,
+        "tomorrow" => today.succ_opt()?,
+        "yesterday" => today.pred_opt()?,
+        "next" | "last" => {
+            let (what, rest) = rest.split_first()?;
+            let day = if *first == "next" {
+                match *what {
+                    "week" => week_start(today).checked_add_days(Days::new(7))?,
+                    "month" => month_start(today).checked_add_months(Months::new(1))?,
+                    "year" => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1)?,
+                    w => weekday_after(today, parse_weekday(w)?, 1),
+                }
+            } else {
+                match *what {
+                    "week" => week_start(today).checked_sub_days(Days::new(7))?,
+                    "month" => month_start(today).checked_sub_months(Months::new(1))?,
+                    "year" => NaiveDate::from_ymd_opt(today.year() - 1, 1, 1)?,
+                    w => weekday_before(today, parse_weekday(w)?),
+                }
+            };
+            return Some((day, DayRest::Words(rest)));
+        }
+        w => {
+            if let Some(weekday) = parse_weekday(w) {
+                weekday_after(today, weekday, 0)
+            } else if let Ok(date) = NaiveDate::parse_from_str(w, "%Y-%m-%d") {
+                date
+            } else {
+                let at = ["%Y-%m-%dt%H:%M:%S%.f", "%Y-%m-%dt%H:%M"]
+                    .iter()
+                    .find_map(|f| NaiveDateTime::parse_from_str(w, f).ok())?;
+                if !rest.is_empty() {
+                    return None;
+                }
+                return Some((at.date(), DayRest::DateTime(at)));
+            }
+        }
+    };
+    Some((day, DayRest::Words(rest)))
+}
+
+/// Parses `9am`, `9:30pm`, `14:30`, `14:30:15`, `noon` or `midnight`.
+fn parse_time_of_day(s: &str) -> Option<NaiveTime> {
+    match s {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return Some(NaiveTime::MIN),
+        _ => {}
+    }
+    let (clock, meridiem) = if let Some(c) = s.strip_suffix("am") {
+        (c, Some(false))
+    } else if let Some(c) = s.strip_suffix("pm") {
+        (c, Some(true))
+    } else {
+        (s, None)
+    };
+    let mut parts = clock.split(':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next().map_or(Some(0), |m| m.parse().ok())?;
+    let second: u32 = parts.next().map_or(Some(0), |s| s.parse().ok())?;
+    if parts.next().is_some() {
+        return None;
+    }
+    let hour = match meridiem {
+        Some(pm) => {
+            if !(1..=12).contains(&hour) {
+                return None;
+            }
+            hour % 12 + if pm { 12 } else { 0 }
+        }
+        // A bare number is only a time with am/pm (`9` alone is ambiguous).
+        None if !clock.contains(':') => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, second)
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    Some(match s {
+        "monday" | "mon" => Weekday::Mon,
+        "tuesday" | "tue" | "tues" => Weekday::Tue,
+        "wednesday" | "wed" => Weekday::Wed,
+        "thursday" | "thu" | "thurs" => Weekday::Thu,
+        "friday" | "fri" => Weekday::Fri,
+        "saturday" | "sat" => Weekday::Sat,
+        "sunday" | "sun" => Weekday::Sun,
+        _ => return None,
+    })
+}
+
+/// The first `weekday` at least `min_days` after `day`.
+fn weekday_after(day: NaiveDate, weekday: Weekday, min_days: u32) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - day.weekday().num_days_from_monday()) % 7;
+    let ahead = if ahead < min_days { ahead + 7 } else { ahead };
+    day + Duration::days(i64::from(ahead))
+}
+
+/// The last `weekday` strictly before `day`.
+fn weekday_before(day: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let back = (day.weekday().num_days_from_monday() + 7 - weekday.num_days_from_monday()) % 7;
+    day - Duration::days(i64::from(if back == 0 { 7 } else { back }))
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(i64::from(day.weekday().num_days_from_monday()))
+}
+
+fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).expect("day 1 exists")
+}
+
+/// First and last day of the period containing `day`.
+fn period_bounds(day: NaiveDate, period: &str) -> Option<(NaiveDate, NaiveDate)> {
+    Some(match period {
+        "day" => (day, day),
+        "week" => {
+            let start = week_start(day);
+            (start, start + Duration::days(6))
+        }
+        "month" => {
+            let start = month_start(day);
+            (start, start.checked_add_months(Months::new(1))?.pred_opt()?)
+        }
+        "year" => (
+            NaiveDate::from_ymd_opt(day.year(), 1, 1)?,
+            NaiveDate::from_ymd_opt(day.year(), 12, 31)?,
+        ),
+        _ => return None,
+    })
+}
+
+fn shift(input: &str, now: DateTime<Utc>, d: Duration) -> Result<DateTime<Utc>, ParseError> {
+    now.checked_add_signed(d)
+        .ok_or_else(|| ParseError::time(input, "out of range"))
+}
+
+/// Interprets a wall-clock time in `tz`. Ambiguous times (DST fold) take the
+/// earlier instant.
+fn localize<Tz: TimeZone>(
+    input: &str,
+    tz: &Tz,
+    at: NaiveDateTime,
+) -> Result<DateTime<Utc>, ParseError> {
+    tz.from_local_datetime(&at)
+        .earliest()
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok_or_else(|| ParseError::time(input, format!("{at} does not exist in this timezone")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Wednesday 2026-10-14 10:30 UTC.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 14, 10, 30, 0).unwrap()
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        parse_time(s, &now()).unwrap()
+    }
+
+    fn utc(y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(y, mo, d, h, mi, 0).unwrap()
+    }
+
+    #[test]
+    fn iso_dates_and_timestamps() {
+        assert_eq!(at("2026-06-01"), utc(2026, 6, 1, 0, 0));
+        assert_eq!(at("2026-06-01 14:30"), utc(2026, 6, 1, 14, 30));
+        assert_eq!(at("2026-06-01T14:30"), utc(2026, 6, 1, 14, 30));
+        assert_eq!(at("2026-06-01T14:30:00Z"), utc(2026, 6, 1, 14, 30));
+        assert_eq!(at("2026-06-01T14:30:00+02:00"), utc(2026, 6, 1, 12, 30));
+    }
+
+    #[test]
+    fn relative_offsets() {
+        assert_eq!(at("now"), now());
+        assert_eq!(at("+3d"), now() + Duration::days(3));
+        assert_eq!(at("-2h"), now() - Duration::hours(2));
+        assert_eq!(at("in 1h30m"), now() + Duration::minutes(90));
+        assert_eq!(at("3 days ago"), now() - Duration::days(3));
+    }
+
+    #[test]
+    fn natural_days_and_times() {
+        assert_eq!(at("today"), utc(2026, 10, 14, 0, 0));
+        assert_eq!(at("tomorrow 9am"), utc(2026, 10, 15, 9, 0));
+        assert_eq!(at("Tomorrow at 9:30 PM"), utc(2026, 10, 15, 21, 30));
+        assert_eq!(at("yesterday noon"), utc(2026, 10, 13, 12, 0));
+        // Today is a Wednesday.
+        assert_eq!(at("wednesday"), utc(2026, 10, 14, 0, 0));
+        assert_eq!(at("next wednesday"), utc(2026, 10, 21, 0, 0));
+        assert_eq!(at("next monday 14:00"), utc(2026, 10, 19, 14, 0));
+        assert_eq!(at("last friday"), utc(2026, 10, 9, 0, 0));
+        assert_eq!(at("next week"), utc(2026, 10, 19, 0, 0));
+        assert_eq!(at("next month"), utc(2026, 11, 1, 0, 0));
+        assert_eq!(at("2026-06-01 5pm"), utc(2026, 6, 1, 17, 0));
+    }
+
+    #[test]
+    fn period_boundaries() {
+        assert_eq!(
+            at("end of month"),
+            Utc.with_ymd_and_hms(2026, 10, 31, 23, 59, 59).unwrap()
+        );
+        assert_eq!(at("start of week"), utc(2026, 10, 12, 0, 0));
+        assert_eq!(at("beginning of year"), utc(2026, 1, 1, 0, 0));
+        assert_eq!(
+            at("end of day"),
+            Utc.with_ymd_and_hms(2026, 10, 14, 23, 59, 59).unwrap()
+        );
+    }
+
+    #[test]
+    fn timezones() {
+        let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+        let local_now = now().with_timezone(&berlin);
+        // Naive times are in the reference timezone...
+        assert_eq!(
+            parse_time("tomorrow 9am", &local_now).unwrap(),
+            utc(2026, 10, 15, 7, 0)
+        );
+        // ...unless the input names one.
+        assert_eq!(
+            parse_time("tomorrow 9am UTC", &local_now).unwrap(),
+            utc(2026, 10, 15, 9, 0)
+        );
+        assert_eq!(at("2026-06-01 12:00 -05:00"), utc(2026, 6, 1, 17, 0));
+        assert_eq!(at("2026-06-01 12:00 +0530"), utc(2026, 6, 1, 6, 30));
+    }
+
+    #[test]
+    fn named_timezones() {
+        // IANA names follow daylight saving time.
+        assert_eq!(at("2026-06-01 9am Europe/Berlin"), utc(2026, 6, 1, 7, 0));
+        assert_eq!(at("2026-12-01 9am europe/berlin"), utc(2026, 12, 1, 8, 0));
+        assert_eq!(
+            at("tomorrow 5pm America/Los_Angeles"),
+            utc(2026, 10, 16, 0, 0)
+        );
+        // Abbreviations are fixed offsets.
+        assert_eq!(at("tomorrow 9am PST"), utc(2026, 10, 15, 17, 0));
+        assert_eq!(at("tomorrow 9am PDT"), utc(2026, 10, 15, 16, 0));
+        assert_eq!(at("2026-06-01 12:00 CEST"), utc(2026, 6, 1, 10, 0));
+        assert!(parse_time("tomorrow 9am Mars/Olympus", &now()).is_err());
+    }
+
+    #[test]
+    fn bare_durations_are_from_now_in_the_future() {
+        let ahead = |s: &str| parse_future_time(s, &now()).unwrap();
+        assert_eq!(ahead("2h"), now() + Duration::hours(2));
+        assert_eq!(ahead("1w2d"), now() + Duration::days(9));
+        assert_eq!(ahead("3 days"), now() + Duration::days(3));
+        // Everything parse_time accepts means the same.
+        assert_eq!(ahead("-2h"), now() - Duration::hours(2));
+        assert_eq!(ahead("tomorrow 9am"), utc(2026, 10, 15, 9, 0));
+        assert_eq!(ahead("2026-06-01"), utc(2026, 6, 1, 0, 0));
+        assert!(parse_time("2h", &now()).is_err());
+        let err = parse_future_time("someday", &now()).unwrap_err();
+        assert!(err.to_string().contains("unrecognized time"));
+    }
+
+    #[test]
+    fn rejects_nonsense() {
+        let err = parse_time("someday", &now()).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("invalid time 'someday': unrecognized time")
+        );
+        assert!(parse_time("", &now()).is_err());
+        assert!(parse_time("tomorrow 25:00", &now()).is_err());
+        assert!(parse_time("tomorrow 9", &now()).is_err());
+        assert!(parse_time("end of decade", &now()).is_err());
+        assert!(parse_time("in 3 fortnights", &now()).is_err());
+        assert!(parse_time("UTC", &now()).is_err());
+    }
+
+    #[test]
+    fn relative_format_round_trips() {
+        let t = now() + Duration::days(3) + Duration::hours(4);
+        assert_eq!(format_relative(t, now()), "in 3d4h");
+        assert_eq!(at(&format_relative(t, now())), t);
+        let t = now() - Duration::minutes(150);
+        assert_eq!(format_relative(t, now()), "2h30m ago");
+        assert_eq!(at(&format_relative(t, now())), t);
+        assert_eq!(format_relative(now(), now()), "now");
+    }
+}